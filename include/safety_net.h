@@ -0,0 +1,200 @@
+/* Generated from src/capi.rs by tests/capi.rs, do not edit. */
+
+#ifndef SAFETY_NET_H
+#define SAFETY_NET_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/**
+ * The status of a call that succeeded
+ */
+#define SN_OK 0
+
+/**
+ * The status of a call that failed, see sn_last_error
+ */
+#define SN_ERROR (-1)
+
+/**
+ * A netlist of Gates, owned by the caller until sn_netlist_free
+ */
+typedef struct SnNetlist SnNetlist;
+
+/**
+ * The stable identifier of a circuit node, see ObjectId
+ */
+typedef uint64_t SnObject;
+
+/**
+ * A net, as the stable identifier of its driver and its output position on the driver, see NetId
+ */
+typedef struct SnNet {
+    /* The circuit node driving the net */
+    SnObject object;
+    /* The output position of the net on its driver */
+    uint64_t output;
+} SnNet;
+
+/**
+ * Returns the description of the last failure on the calling thread, which is empty if there was none.
+ * The string is owned by the library and is valid until the next failure on the thread.
+ */
+const char *sn_last_error(void);
+
+/**
+ * Frees a string returned by the library.
+ *
+ * # Safety
+ *
+ * `s` must be null or a string returned by the library that was not freed
+ */
+void sn_string_free(char *s);
+
+/**
+ * Creates an empty netlist named `name`, or returns null if `name` is not valid UTF-8.
+ *
+ * # Safety
+ *
+ * `name` must be a valid nul-terminated string
+ */
+SnNetlist *sn_netlist_new(const char *name);
+
+/**
+ * Frees a netlist.
+ *
+ * # Safety
+ *
+ * `netlist` must be null or a handle from sn_netlist_new that was not freed
+ */
+void sn_netlist_free(SnNetlist *netlist);
+
+/**
+ * Inserts an input net named `name`, writing it to `out`.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle, `name` a valid nul-terminated string and `out` valid for writes
+ */
+int32_t sn_insert_input(SnNetlist *netlist, const char *name, SnNet *out);
+
+/**
+ * Inserts an instance `inst_name` of the gate `cell`, with the `n_ports` input ports named in `ports` and output `Y`.
+ * The inputs are left unconnected for sn_connect, and the circuit node is written to `out`. Fails if `cell` is a bit slice like `X1`.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle, `cell`, `inst_name` and the `n_ports` entries of `ports` valid nul-terminated strings,
+ * and `out` valid for writes
+ */
+int32_t sn_insert_gate(SnNetlist *netlist, const char *cell, const char *inst_name, const char *const *ports, size_t n_ports, SnObject *out);
+
+/**
+ * Connects input `input` of the circuit node `object` to the net `driver`, replacing its previous driver.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle
+ */
+int32_t sn_connect(SnNetlist *netlist, SnNet driver, SnObject object, size_t input);
+
+/**
+ * Drives the top-level output `name` with `net`.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle and `name` a valid nul-terminated string
+ */
+int32_t sn_expose(SnNetlist *netlist, SnNet net, const char *name);
+
+/**
+ * Writes the identifiers of up to `capacity` circuit nodes to `out`, in netlist order, and returns the number of circuit nodes.
+ * Pass a null `out` to only count them.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle, and `out` null or valid for `capacity` writes
+ */
+size_t sn_objects(const SnNetlist *netlist, SnObject *out, size_t capacity);
+
+/**
+ * Returns the instance name of the circuit node `object`, or the name of its net for an input, or null on failure.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle
+ */
+char *sn_object_name(const SnNetlist *netlist, SnObject object);
+
+/**
+ * Returns the name of the gate of the circuit node `object`, or null for an input or on failure.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle
+ */
+char *sn_cell_name(const SnNetlist *netlist, SnObject object);
+
+/**
+ * Returns the name of `net`, or null on failure.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle
+ */
+char *sn_net_name(const SnNetlist *netlist, SnNet net);
+
+/**
+ * Writes the number of input ports of the circuit node `object` to `out`.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle and `out` valid for writes
+ */
+int32_t sn_num_inputs(const SnNetlist *netlist, SnObject object, size_t *out);
+
+/**
+ * Writes the net driving input `input` of the circuit node `object` to `out`. Fails if the input is unconnected.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle and `out` valid for writes
+ */
+int32_t sn_get_driver(const SnNetlist *netlist, SnObject object, size_t input, SnNet *out);
+
+/**
+ * Removes the circuit nodes that do not reach a top-level output, see Netlist::clean.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle
+ */
+int32_t sn_clean(SnNetlist *netlist);
+
+/**
+ * Checks that the netlist has outputs, unique names and no unconnected inputs, see Netlist::verify_with.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle
+ */
+int32_t sn_verify(const SnNetlist *netlist);
+
+/**
+ * Returns the netlist as Verilog with the default VerilogOptions, or null on failure.
+ *
+ * # Safety
+ *
+ * `netlist` must be a live handle
+ */
+char *sn_to_verilog(const SnNetlist *netlist);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SAFETY_NET_H */
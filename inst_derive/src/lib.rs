@@ -1,7 +1,7 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{Data, DeriveInput, Fields};
+use syn::{Data, DeriveInput, Fields, spanned::Spanned};
 
 /// Derive macro for the Instantiable trait.
 ///
@@ -9,7 +9,10 @@ use syn::{Data, DeriveInput, Fields};
 /// It generates an implementation that delegates all trait methods to the wrapped type.
 ///
 /// Use the `#[instantiable(constant)]` attribute on a variant to specify which variant
-/// should be used for `from_constant()`.
+/// should be used for `from_constant()` with `Logic::True` and `Logic::False`.
+/// Other values are delegated with a list of the `Logic` values a variant handles,
+/// like `#[instantiable(constant(Z))]` for a library with a tri-state constant cell.
+/// Each value can be handled by at most one variant.
 ///
 /// # Example
 ///
@@ -22,61 +25,115 @@ use syn::{Data, DeriveInput, Fields};
 ///     Gate(Gate),
 /// }
 ///
+/// Use the `#[instantiable(accessors)]` attribute on the enum to also generate `From` and `AsCell` impls for each variant,
+/// and `as_lut`, `as_lut_mut`, and `into_lut` accessors named after the variants in snake case.
+/// `into_lut` returns the enum back when it holds another variant.
+///
+/// It also works with structs whose fields are marked with attributes, see [impl_struct].
 fn impl_instantiable_trait(ast: DeriveInput) -> TokenStream2 {
     let ident = ast.ident;
+    let generics = ast.generics;
 
-    // Only support enums
+    // Only support enums and structs
     let variants = match ast.data {
         Data::Enum(data_enum) => data_enum.variants,
+        Data::Struct(data_struct) => {
+            return impl_struct(ident, &ast.vis, &generics, &ast.attrs, data_struct.fields);
+        }
         _ => {
-            return syn::Error::new_spanned(ident, "Instantiable can only be derived for enums")
-                .to_compile_error();
+            return syn::Error::new_spanned(
+                ident,
+                "Instantiable can only be derived for enums and structs",
+            )
+            .to_compile_error();
         }
     };
 
-    // Extract variant names and find the constant variant
+    if variants.is_empty() {
+        return syn::Error::new_spanned(
+            ident,
+            "Instantiable cannot be derived for an enum without variants",
+        )
+        .to_compile_error();
+    }
+
+    // All the errors are reported at once
+    let mut errors = None;
+
+    // Check for #[instantiable(accessors)] attribute
+    let mut accessors = false;
+    for attr in &ast.attrs {
+        if attr.path().is_ident("instantiable") {
+            let result = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("accessors") {
+                    accessors = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected 'accessors'"))
+                }
+            });
+            if let Err(err) = result {
+                combine(&mut errors, err);
+            }
+        }
+    }
+
+    // Extract variant names and types, and find the constant variant
     let mut variant_names = Vec::new();
-    let mut constant_variant: Option<syn::Ident> = None;
+    let mut variant_types = Vec::new();
+    // The variants that build constants, with the values they handle
+    let mut constant_variants: Vec<(syn::Ident, syn::Type, Vec<syn::Ident>)> = Vec::new();
 
     for variant in variants {
         let variant_name = &variant.ident;
 
         // Validate that the variant has exactly one unnamed field
-        match variant.fields {
-            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {}
-            _ => {
-                return syn::Error::new_spanned(
-                    variant,
-                    "Each enum variant must have exactly one unnamed field",
-                )
-                .to_compile_error();
+        let ty = match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => fields.unnamed[0].ty.clone(),
+            Fields::Unit => {
+                let msg = "Each enum variant must have exactly one unnamed field";
+                combine(&mut errors, syn::Error::new_spanned(variant_name, msg));
+                continue;
             }
-        }
+            fields => {
+                let msg = "Each enum variant must have exactly one unnamed field";
+                combine(&mut errors, syn::Error::new_spanned(fields, msg));
+                continue;
+            }
+        };
 
         // Check for #[instantiable(constant)] attribute
         for attr in &variant.attrs {
             if attr.path().is_ident("instantiable") {
                 let result = attr.parse_nested_meta(|meta| {
                     if meta.path.is_ident("constant") {
-                        if constant_variant.is_some() {
-                            return Err(syn::Error::new_spanned(
-                                attr,
-                                "Only one variant can be marked with #[instantiable(constant)]",
-                            ));
+                        let values = constant_values(&meta)?;
+                        for v in &values {
+                            if constant_variants.iter().any(|(_, _, vs)| vs.contains(v)) {
+                                let msg =
+                                    "Only one variant can be marked with #[instantiable(constant)]";
+                                let msg = format!("{msg} for Logic::{v}");
+                                return Err(syn::Error::new_spanned(attr, msg));
+                            }
                         }
-                        constant_variant = Some(variant_name.clone());
+                        constant_variants.push((variant_name.clone(), ty.clone(), values));
                         Ok(())
                     } else {
-                        Err(meta.error("expected 'constant'"))
+                        Err(meta.error("expected 'constant' or 'constant(...)'"))
                     }
                 });
 
                 if let Err(err) = result {
-                    return err.to_compile_error();
+                    combine(&mut errors, err);
                 }
             }
         }
         variant_names.push(variant_name.clone());
+        variant_types.push(ty);
+    }
+
+    if let Some(err) = errors {
+        return err.to_compile_error();
     }
 
     // Generate match arms for each method
@@ -84,13 +141,17 @@ fn impl_instantiable_trait(ast: DeriveInput) -> TokenStream2 {
         quote! { #ident::#v(inner) => inner.get_name() }
     });
 
-    let get_input_ports_arms = variant_names.iter().map(|v| {
-        quote! { #ident::#v(inner) => inner.get_input_ports().into_iter().collect::<Vec<_>>() }
-    });
+    let get_input_ports_body = dispatch_iter(
+        &ident,
+        &variant_names,
+        quote! { inner.get_input_ports().into_iter() },
+    );
 
-    let get_output_ports_arms = variant_names.iter().map(|v| {
-        quote! { #ident::#v(inner) => inner.get_output_ports().into_iter().collect::<Vec<_>>() }
-    });
+    let get_output_ports_body = dispatch_iter(
+        &ident,
+        &variant_names,
+        quote! { inner.get_output_ports().into_iter() },
+    );
 
     let has_parameter_arms = variant_names.iter().map(|v| {
         quote! { #ident::#v(inner) => inner.has_parameter(id) }
@@ -104,26 +165,54 @@ fn impl_instantiable_trait(ast: DeriveInput) -> TokenStream2 {
         quote! { #ident::#v(inner) => inner.set_parameter(id, val) }
     });
 
-    let parameters_arms = variant_names.iter().map(|v| {
-        quote! { #ident::#v(inner) => inner.parameters().collect::<Vec<_>>().into_iter() }
+    let parameters_body = dispatch_iter(&ident, &variant_names, quote! { inner.parameters() });
+
+    let get_default_parameter_arms = variant_names.iter().map(|v| {
+        quote! { #ident::#v(inner) => inner.get_default_parameter(id) }
     });
 
     let get_constant_arms = variant_names.iter().map(|v| {
         quote! { #ident::#v(inner) => inner.get_constant() }
     });
 
+    let fold_arms = variant_names.iter().map(|v| {
+        quote! { #ident::#v(inner) => inner.fold(inputs) }
+    });
+
     let is_seq_arms = variant_names.iter().map(|v| {
         quote! { #ident::#v(inner) => inner.is_seq() }
     });
 
-    // Generate from_constant implementation based on the marked variant
-    let from_constant_impl = if let Some(const_var) = constant_variant {
+    let is_blackbox_arms = variant_names.iter().map(|v| {
+        quote! { #ident::#v(inner) => inner.is_blackbox() }
+    });
+
+    let is_latch_arms = variant_names.iter().map(|v| {
+        quote! { #ident::#v(inner) => inner.is_latch() }
+    });
+
+    // Generate from_constant implementation based on the marked variants
+    let from_constant_impl = if !constant_variants.is_empty() {
+        let arms = constant_variants
+            .iter()
+            .map(|(const_var, const_ty, values)| {
+                // Types with generic arguments need to be qualified to call associated functions
+                let const_ty = match const_ty {
+                    syn::Type::Path(path) if path.qself.is_none() && !has_arguments(&path.path) => {
+                        quote! { #const_ty }
+                    }
+                    _ => quote! { <#const_ty> },
+                };
+                quote! {
+                    #(Logic::#values)|* => #const_ty::from_constant(val).map(#ident::#const_var)
+                }
+            });
         quote! {
             fn from_constant(val: Logic) -> Option<Self> {
-                if (val == Logic::True) || (val == Logic::False) {
-                    return #const_var::from_constant(val).map(#ident::#const_var);
-                } else {
-                    return None;
+                match val {
+                    #(#arms,)*
+                    #[allow(unreachable_patterns)]
+                    _ => None,
                 }
             }
         }
@@ -136,8 +225,16 @@ fn impl_instantiable_trait(ast: DeriveInput) -> TokenStream2 {
     };
 
     // Generate the implementation
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let accessors_impl = if accessors {
+        impl_accessors(&ident, &generics, &variant_names, &variant_types)
+    } else {
+        quote! {}
+    };
     quote! {
-        impl Instantiable for #ident {
+        #accessors_impl
+
+        impl #impl_generics Instantiable for #ident #ty_generics #where_clause {
             fn get_name(&self) -> &Identifier {
                 match self {
                     #(#get_name_arms),*
@@ -145,15 +242,11 @@ fn impl_instantiable_trait(ast: DeriveInput) -> TokenStream2 {
             }
 
             fn get_input_ports(&self) -> impl IntoIterator<Item = &Net> {
-                match self {
-                    #(#get_input_ports_arms),*
-                }
+                #get_input_ports_body
             }
 
             fn get_output_ports(&self) -> impl IntoIterator<Item = &Net> {
-                match self {
-                    #(#get_output_ports_arms),*
-                }
+                #get_output_ports_body
             }
 
             fn has_parameter(&self, id: &Identifier) -> bool {
@@ -175,8 +268,12 @@ fn impl_instantiable_trait(ast: DeriveInput) -> TokenStream2 {
             }
 
             fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)> {
+                #parameters_body
+            }
+
+            fn get_default_parameter(&self, id: &Identifier) -> Option<Parameter> {
                 match self {
-                    #(#parameters_arms),*
+                    #(#get_default_parameter_arms),*
                 }
             }
 
@@ -188,21 +285,651 @@ fn impl_instantiable_trait(ast: DeriveInput) -> TokenStream2 {
                 }
             }
 
+            fn fold(&self, inputs: &[Option<Logic>]) -> Option<Logic> {
+                match self {
+                    #(#fold_arms),*
+                }
+            }
+
             fn is_seq(&self) -> bool {
                 match self {
                     #(#is_seq_arms),*
                 }
             }
+
+            fn is_blackbox(&self) -> bool {
+                match self {
+                    #(#is_blackbox_arms),*
+                }
+            }
+
+            fn is_latch(&self) -> bool {
+                match self {
+                    #(#is_latch_arms),*
+                }
+            }
+        }
+    }
+}
+
+/// Adds `err` to the errors reported by the derive
+fn combine(errors: &mut Option<syn::Error>, err: syn::Error) {
+    match errors {
+        Some(errors) => errors.combine(err),
+        None => *errors = Some(err),
+    }
+}
+
+/// Parses the `Logic` values of a `constant(X, Z)` attribute, which are `True` and `False` when none are listed
+fn constant_values(meta: &syn::meta::ParseNestedMeta) -> syn::Result<Vec<syn::Ident>> {
+    if !meta.input.peek(syn::token::Paren) {
+        let span = meta.path.span();
+        return Ok(vec![
+            syn::Ident::new("True", span),
+            syn::Ident::new("False", span),
+        ]);
+    }
+    let mut values = Vec::new();
+    meta.parse_nested_meta(|value| match value.path.get_ident() {
+        Some(v) if ["True", "False", "X", "Z"].contains(&v.to_string().as_str()) => {
+            values.push(v.clone());
+            Ok(())
+        }
+        _ => Err(value.error("expected one of 'True', 'False', 'X' or 'Z'")),
+    })?;
+    Ok(values)
+}
+
+/// Generates a block that calls the iterator expression `call` on the `inner` cell of each variant.
+/// The iterators of the variants have different types, so they are wrapped in a local enum that dispatches
+/// to the active one instead of being collected into a `Vec`.
+fn dispatch_iter(
+    ident: &syn::Ident,
+    variant_names: &[syn::Ident],
+    call: TokenStream2,
+) -> TokenStream2 {
+    let iters: Vec<syn::Ident> = (0..variant_names.len())
+        .map(|i| quote::format_ident!("__I{}", i))
+        .collect();
+    quote! {
+        enum __Iter<#(#iters),*> {
+            #(#iters(#iters)),*
+        }
+
+        impl<__T, #(#iters: Iterator<Item = __T>),*> Iterator for __Iter<#(#iters),*> {
+            type Item = __T;
+
+            fn next(&mut self) -> Option<__T> {
+                match self {
+                    #(__Iter::#iters(iter) => iter.next()),*
+                }
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                match self {
+                    #(__Iter::#iters(iter) => iter.size_hint()),*
+                }
+            }
+        }
+
+        match self {
+            #(#ident::#variant_names(inner) => __Iter::#iters(#call)),*
+        }
+    }
+}
+
+/// Converts a variant name like `FlipFlop` to snake case like `flip_flop`
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// Generates the `From` impls and the accessors of the variants of an enum
+fn impl_accessors(
+    ident: &syn::Ident,
+    generics: &syn::Generics,
+    variant_names: &[syn::Ident],
+    variant_types: &[syn::Type],
+) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut methods = Vec::new();
+    for (v, ty) in variant_names.iter().zip(variant_types) {
+        let snake = snake_case(&v.to_string());
+        let as_ref = quote::format_ident!("as_{}", snake);
+        let as_mut = quote::format_ident!("as_{}_mut", snake);
+        let into = quote::format_ident!("into_{}", snake);
+        let doc = format!("Returns the wrapped cell if this is a [{ident}::{v}]");
+        let into_doc =
+            format!("Unwraps the cell if this is a [{ident}::{v}], and returns `self` otherwise");
+        methods.push(quote! {
+            #[doc = #doc]
+            pub fn #as_ref(&self) -> Option<&#ty> {
+                match self {
+                    #ident::#v(inner) => Some(inner),
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+
+            #[doc = #doc]
+            pub fn #as_mut(&mut self) -> Option<&mut #ty> {
+                match self {
+                    #ident::#v(inner) => Some(inner),
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+
+            #[doc = #into_doc]
+            pub fn #into(self) -> Result<#ty, Self> {
+                match self {
+                    #ident::#v(inner) => Ok(inner),
+                    #[allow(unreachable_patterns)]
+                    other => Err(other),
+                }
+            }
+        });
+    }
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#methods)*
+        }
+
+        #(
+            impl #impl_generics From<#variant_types> for #ident #ty_generics #where_clause {
+                fn from(inner: #variant_types) -> Self {
+                    #ident::#variant_names(inner)
+                }
+            }
+
+            impl #impl_generics ::safety_net::circuit::AsCell<#variant_types> for #ident #ty_generics #where_clause {
+                fn as_cell(&self) -> Option<&#variant_types> {
+                    match self {
+                        #ident::#variant_names(inner) => Some(inner),
+                        #[allow(unreachable_patterns)]
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    }
+}
+
+/// Returns `true` if any segment of `path` has generic arguments, like `Lut<T>`
+fn has_arguments(path: &syn::Path) -> bool {
+    path.segments
+        .iter()
+        .any(|s| !matches!(s.arguments, syn::PathArguments::None))
+}
+
+/// A parameter field of a struct
+struct ParameterField {
+    /// The name of the parameter
+    name: String,
+    /// The field that holds the value
+    field: syn::Ident,
+}
+
+/// Returns `true` if `ty` is a single [Net], rather than a collection of them
+fn is_single_net(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) => path.path.segments.last().is_some_and(|s| s.ident == "Net"),
+        _ => false,
+    }
+}
+
+/// Returns the named arguments of a format string like `"LUT{k}"`, which refer to fields
+fn format_fields(template: &syn::LitStr) -> syn::Result<Vec<syn::Ident>> {
+    let value = template.value();
+    let mut fields = Vec::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+            }
+            '{' => {
+                let arg: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                let name = arg.split(':').next().unwrap_or_default();
+                let field = syn::parse_str::<syn::Ident>(name).map_err(|_| {
+                    syn::Error::new_spanned(template, "cell_name arguments must be field names")
+                })?;
+                if !fields.contains(&field) {
+                    fields.push(field);
+                }
+            }
+            _ => (),
         }
     }
+    Ok(fields)
 }
 
-#[proc_macro_derive(Instantiable, attributes(instantiable))]
+/// Implements [Instantiable] for a struct with named fields:
+///
+/// - `#[input_port]` and `#[output_port]` mark fields of type `Net`, or collections of them with an `iter` method like `Vec<Net>`.
+///   The ports of the cell are the marked fields in declaration order.
+/// - `#[parameter]` or `#[parameter(name = "INIT")]` marks a field whose type converts to and from `Parameter`.
+///   The parameter is named after the field by default. Setting a value of the wrong type panics.
+/// - `#[cell_name]` marks an `Identifier` field with the name of the cell. Instead, the struct can be marked
+///   with a constant name like `#[cell_name = "FDRE"]`.
+/// - A struct marked with a format string like `#[cell_name = "LUT{k}"]`, whose arguments are fields, also needs
+///   a `#[cell_name]` field to hold the formatted name. The derive adds an associated function `cell_name` that
+///   formats the name from the argument fields, and `set_parameter` keeps the field up to date.
+/// - `#[instantiable(seq)]` or `#[instantiable(blackbox)]` on the struct mark sequential cells and black boxes.
+///
+/// # Example
+///
+///
+/// #[derive(Debug, Clone, Instantiable)]
+/// #[cell_name = "LUT{k}"]
+/// struct Lut {
+///     #[cell_name]
+///     name: Identifier,
+///     k: usize,
+///     #[parameter(name = "INIT")]
+///     init: BitVec,
+///     #[input_port]
+///     inputs: Vec<Net>,
+///     #[output_port]
+///     output: Net,
+/// }
+///
+/// let lut = Lut { name: Lut::cell_name(&2), k: 2, ... };
+///
+fn impl_struct(
+    ident: syn::Ident,
+    vis: &syn::Visibility,
+    generics: &syn::Generics,
+    attrs: &[syn::Attribute],
+    fields: Fields,
+) -> TokenStream2 {
+    let Fields::Named(fields) = fields else {
+        return syn::Error::new_spanned(
+            ident,
+            "Instantiable can only be derived for structs with named fields",
+        )
+        .to_compile_error();
+    };
+
+    // All the errors are reported at once
+    let mut errors = None;
+
+    let mut is_seq = false;
+    let mut is_blackbox = false;
+    let mut is_latch = false;
+    let mut template: Option<syn::LitStr> = None;
+    for attr in attrs {
+        let result = if attr.path().is_ident("instantiable") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("seq") {
+                    is_seq = true;
+                    Ok(())
+                } else if meta.path.is_ident("blackbox") {
+                    is_blackbox = true;
+                    Ok(())
+                } else if meta.path.is_ident("latch") {
+                    is_latch = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected 'seq', 'blackbox' or 'latch'"))
+                }
+            })
+        } else if attr.path().is_ident("cell_name") {
+            attr.meta
+                .require_name_value()
+                .and_then(|nv| match &nv.value {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    }) => {
+                        template = Some(lit.clone());
+                        Ok(())
+                    }
+                    value => Err(syn::Error::new_spanned(value, "expected a format string")),
+                })
+        } else {
+            Ok(())
+        };
+        if let Err(err) = result {
+            combine(&mut errors, err);
+        }
+    }
+
+    let args = match template.as_ref().map(format_fields).transpose() {
+        Ok(args) => args.unwrap_or_default(),
+        Err(err) => {
+            combine(&mut errors, err);
+            Vec::new()
+        }
+    };
+
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut parameters: Vec<ParameterField> = Vec::new();
+    let mut name_field: Option<syn::Ident> = None;
+    let mut arg_types = vec![None; args.len()];
+    for field in fields.named {
+        let field_name = field.ident.clone().unwrap();
+        if let Some(i) = args.iter().position(|arg| *arg == field_name) {
+            arg_types[i] = Some(field.ty.clone());
+        }
+        for attr in &field.attrs {
+            let result =
+                if attr.path().is_ident("input_port") || attr.path().is_ident("output_port") {
+                    let port = if is_single_net(&field.ty) {
+                        quote! { std::iter::once(&self.#field_name) }
+                    } else {
+                        quote! { self.#field_name.iter() }
+                    };
+                    if attr.path().is_ident("input_port") {
+                        inputs.push(port);
+                    } else {
+                        outputs.push(port);
+                    }
+                    attr.meta.require_path_only().map(|_| ())
+                } else if attr.path().is_ident("parameter") {
+                    let mut name = field_name.to_string();
+                    let result = match &attr.meta {
+                        syn::Meta::Path(_) => Ok(()),
+                        _ => attr.parse_nested_meta(|meta| {
+                            if meta.path.is_ident("name") {
+                                name = meta.value()?.parse::<syn::LitStr>()?.value();
+                                Ok(())
+                            } else {
+                                Err(meta.error("expected 'name'"))
+                            }
+                        }),
+                    };
+                    parameters.push(ParameterField {
+                        name,
+                        field: field_name.clone(),
+                    });
+                    result
+                } else if attr.path().is_ident("cell_name") {
+                    if name_field.is_some() || (template.is_some() && args.is_empty()) {
+                        Err(syn::Error::new_spanned(
+                            attr,
+                            "The cell name can only be given once",
+                        ))
+                    } else {
+                        name_field = Some(field_name.clone());
+                        attr.meta.require_path_only().map(|_| ())
+                    }
+                } else {
+                    Ok(())
+                };
+            if let Err(err) = result {
+                combine(&mut errors, err);
+            }
+        }
+    }
+
+    if let Some(template) = &template {
+        if !args.is_empty() && name_field.is_none() {
+            let msg = "A #[cell_name = \"...\"] with arguments needs a #[cell_name] field to hold the name";
+            combine(&mut errors, syn::Error::new_spanned(template, msg));
+        }
+        for (arg, ty) in args.iter().zip(&arg_types) {
+            if ty.is_none() {
+                let msg = format!("cell_name argument '{arg}' is not a field");
+                combine(&mut errors, syn::Error::new_spanned(template, msg));
+            }
+        }
+    }
+
+    if let Some(err) = errors {
+        return err.to_compile_error();
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // Formatted names are stored in a field, which is refreshed whenever a parameter changes
+    let mut format_name = None;
+    let mut refresh_name = None;
+    let get_name = match (name_field, template) {
+        (Some(field), Some(template)) => {
+            let types = arg_types.iter().flatten();
+            format_name = Some(quote! {
+                impl #impl_generics #ident #ty_generics #where_clause {
+                    /// Formats the name of the cell from the values of its fields
+                    #vis fn cell_name(#(#args: &#types),*) -> Identifier {
+                        Identifier::from(format!(#template, #(#args = #args),*))
+                    }
+                }
+            });
+            refresh_name = Some(quote! {
+                self.#field = Self::cell_name(#(&self.#args),*);
+            });
+            quote! { &self.#field }
+        }
+        (Some(field), None) => quote! { &self.#field },
+        (None, Some(template)) => {
+            quote! {
+                static NAME: std::sync::OnceLock<Identifier> = std::sync::OnceLock::new();
+                NAME.get_or_init(|| Identifier::from(format!(#template)))
+            }
+        }
+        (None, None) => {
+            return syn::Error::new_spanned(
+                ident,
+                "Expected a #[cell_name] field or a #[cell_name = \"...\"] attribute",
+            )
+            .to_compile_error();
+        }
+    };
+
+    let names: Vec<&String> = parameters.iter().map(|p| &p.name).collect();
+    let fields: Vec<&syn::Ident> = parameters.iter().map(|p| &p.field).collect();
+
+    quote! {
+        #format_name
+
+        impl #impl_generics Instantiable for #ident #ty_generics #where_clause {
+            fn get_name(&self) -> &Identifier {
+                #get_name
+            }
+
+            fn get_input_ports(&self) -> impl IntoIterator<Item = &Net> {
+                std::iter::empty::<&Net>()#(.chain(#inputs))*
+            }
+
+            fn get_output_ports(&self) -> impl IntoIterator<Item = &Net> {
+                std::iter::empty::<&Net>()#(.chain(#outputs))*
+            }
+
+            fn has_parameter(&self, id: &Identifier) -> bool {
+                [#(#names),*].into_iter().any(|name: &str| *id == Identifier::from(name))
+            }
+
+            fn get_parameter(&self, id: &Identifier) -> Option<Parameter> {
+                self.parameters().find(|(name, _)| name == id).map(|(_, val)| val)
+            }
+
+            fn set_parameter(&mut self, id: &Identifier, val: Parameter) -> Option<Parameter> {
+                #(
+                    if *id == Identifier::from(#names) {
+                        let new = match TryFrom::try_from(val) {
+                            Ok(new) => new,
+                            Err(_) => panic!("Invalid parameter type for {}", #names),
+                        };
+                        let old = std::mem::replace(&mut self.#fields, new);
+                        #refresh_name
+                        return Some(Into::<Parameter>::into(old));
+                    }
+                )*
+                let _ = (id, val);
+                None
+            }
+
+            fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)> {
+                [#((Identifier::from(#names), Into::<Parameter>::into(self.#fields.clone()))),*].into_iter()
+            }
+
+            fn from_constant(_val: Logic) -> Option<Self> {
+                None
+            }
+
+            fn get_constant(&self) -> Option<Logic> {
+                None
+            }
+
+            fn is_seq(&self) -> bool {
+                #is_seq
+            }
+
+            fn is_blackbox(&self) -> bool {
+                #is_blackbox
+            }
+
+            fn is_latch(&self) -> bool {
+                #is_latch
+            }
+        }
+    }
+}
+
+/// Derive macro for the Evaluate trait.
+///
+/// On enums, it delegates `eval` and `eval_words` to the wrapped types, which must all implement Evaluate.
+/// On structs, the `#[evaluate(table = "INIT")]` attribute evaluates the cell as a lookup table,
+/// with the truth table given by its bit vector parameter `INIT` and input `i` as bit `i` of the index.
+///
+/// # Example
+///
+///
+/// #[derive(Debug, Clone, Instantiable, Evaluate)]
+/// #[cell_name = "LUT{k}"]
+/// #[evaluate(table = "INIT")]
+/// struct Lut {
+///     #[cell_name]
+///     name: Identifier,
+///     k: usize,
+///     #[parameter(name = "INIT")]
+///     init: BitVec,
+///     #[input_port]
+///     inputs: Vec<Net>,
+///     #[output_port]
+///     output: Net,
+/// }
+///
+fn impl_evaluate_trait(ast: DeriveInput) -> TokenStream2 {
+    let ident = ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let variants = match ast.data {
+        Data::Enum(data_enum) => data_enum.variants,
+        Data::Struct(_) => {
+            let mut table: Option<syn::LitStr> = None;
+            for attr in &ast.attrs {
+                if attr.path().is_ident("evaluate") {
+                    let result = attr.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("table") {
+                            table = Some(meta.value()?.parse()?);
+                            Ok(())
+                        } else {
+                            Err(meta.error("expected 'table'"))
+                        }
+                    });
+                    if let Err(err) = result {
+                        return err.to_compile_error();
+                    }
+                }
+            }
+            let Some(table) = table else {
+                return syn::Error::new_spanned(
+                    ident,
+                    "Expected a #[evaluate(table = \"...\")] attribute naming the truth table parameter",
+                )
+                .to_compile_error();
+            };
+            let lookup = quote! {
+                match self.get_parameter(&Identifier::from(#table)) {
+                    Some(Parameter::BitVec(table)) => table,
+                    _ => panic!("Expected a bit vector parameter {}", #table),
+                }
+            };
+            return quote! {
+                impl #impl_generics Evaluate for #ident #ty_generics #where_clause {
+                    fn eval(&self, inputs: &[Logic]) -> Vec<Logic> {
+                        let table = #lookup;
+                        vec![::safety_net::circuit::eval_table(&table, inputs)]
+                    }
+
+                    fn eval_words(&self, inputs: &[u64]) -> Vec<u64> {
+                        let table = #lookup;
+                        vec![::safety_net::circuit::eval_table_words(&table, inputs)]
+                    }
+                }
+            };
+        }
+        _ => {
+            return syn::Error::new_spanned(
+                ident,
+                "Evaluate can only be derived for enums and structs",
+            )
+            .to_compile_error();
+        }
+    };
+
+    let mut errors = None;
+    let mut variant_names = Vec::new();
+    for variant in variants {
+        match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                variant_names.push(variant.ident);
+            }
+            _ => {
+                let msg = "Each enum variant must have exactly one unnamed field";
+                combine(&mut errors, syn::Error::new_spanned(&variant.ident, msg));
+            }
+        }
+    }
+    if let Some(err) = errors {
+        return err.to_compile_error();
+    }
+
+    quote! {
+        impl #impl_generics Evaluate for #ident #ty_generics #where_clause {
+            fn eval(&self, inputs: &[Logic]) -> Vec<Logic> {
+                match self {
+                    #(#ident::#variant_names(inner) => inner.eval(inputs)),*
+                }
+            }
+
+            fn eval_words(&self, inputs: &[u64]) -> Vec<u64> {
+                match self {
+                    #(#ident::#variant_names(inner) => inner.eval_words(inputs)),*
+                }
+            }
+        }
+    }
+}
+
+#[proc_macro_derive(
+    Instantiable,
+    attributes(instantiable, input_port, output_port, parameter, cell_name)
+)]
 pub fn inst_derive_macro(item: TokenStream) -> TokenStream {
-    let ast = syn::parse(item).unwrap();
+    let ast = syn::parse_macro_input!(item as DeriveInput);
     TokenStream::from(impl_instantiable_trait(ast))
 }
 
+#[proc_macro_derive(Evaluate, attributes(evaluate))]
+pub fn evaluate_derive_macro(item: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(item as DeriveInput);
+    TokenStream::from(impl_evaluate_trait(ast))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -273,16 +1000,62 @@ mod tests {
                 }
 
                 fn get_input_ports(&self) -> impl IntoIterator<Item = &Net> {
+                    enum __Iter<__I0, __I1> {
+                        __I0(__I0),
+                        __I1(__I1)
+                    }
+
+                    impl<__T, __I0: Iterator<Item = __T>, __I1: Iterator<Item = __T>> Iterator for __Iter<__I0, __I1> {
+                        type Item = __T;
+
+                        fn next(&mut self) -> Option<__T> {
+                            match self {
+                                __Iter::__I0(iter) => iter.next(),
+                                __Iter::__I1(iter) => iter.next()
+                            }
+                        }
+
+                        fn size_hint(&self) -> (usize, Option<usize>) {
+                            match self {
+                                __Iter::__I0(iter) => iter.size_hint(),
+                                __Iter::__I1(iter) => iter.size_hint()
+                            }
+                        }
+                    }
+
                     match self {
-                        SimpleCell::Lut(inner) => inner.get_input_ports().into_iter().collect::<Vec<_>>(),
-                        SimpleCell::Gate(inner) => inner.get_input_ports().into_iter().collect::<Vec<_>>()
+                        SimpleCell::Lut(inner) => __Iter::__I0(inner.get_input_ports().into_iter()),
+                        SimpleCell::Gate(inner) => __Iter::__I1(inner.get_input_ports().into_iter())
                     }
                 }
 
                 fn get_output_ports(&self) -> impl IntoIterator<Item = &Net> {
+                    enum __Iter<__I0, __I1> {
+                        __I0(__I0),
+                        __I1(__I1)
+                    }
+
+                    impl<__T, __I0: Iterator<Item = __T>, __I1: Iterator<Item = __T>> Iterator for __Iter<__I0, __I1> {
+                        type Item = __T;
+
+                        fn next(&mut self) -> Option<__T> {
+                            match self {
+                                __Iter::__I0(iter) => iter.next(),
+                                __Iter::__I1(iter) => iter.next()
+                            }
+                        }
+
+                        fn size_hint(&self) -> (usize, Option<usize>) {
+                            match self {
+                                __Iter::__I0(iter) => iter.size_hint(),
+                                __Iter::__I1(iter) => iter.size_hint()
+                            }
+                        }
+                    }
+
                     match self {
-                        SimpleCell::Lut(inner) => inner.get_output_ports().into_iter().collect::<Vec<_>>(),
-                        SimpleCell::Gate(inner) => inner.get_output_ports().into_iter().collect::<Vec<_>>()
+                        SimpleCell::Lut(inner) => __Iter::__I0(inner.get_output_ports().into_iter()),
+                        SimpleCell::Gate(inner) => __Iter::__I1(inner.get_output_ports().into_iter())
                     }
                 }
 
@@ -308,17 +1081,47 @@ mod tests {
                 }
 
                 fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)> {
+                    enum __Iter<__I0, __I1> {
+                        __I0(__I0),
+                        __I1(__I1)
+                    }
+
+                    impl<__T, __I0: Iterator<Item = __T>, __I1: Iterator<Item = __T>> Iterator for __Iter<__I0, __I1> {
+                        type Item = __T;
+
+                        fn next(&mut self) -> Option<__T> {
+                            match self {
+                                __Iter::__I0(iter) => iter.next(),
+                                __Iter::__I1(iter) => iter.next()
+                            }
+                        }
+
+                        fn size_hint(&self) -> (usize, Option<usize>) {
+                            match self {
+                                __Iter::__I0(iter) => iter.size_hint(),
+                                __Iter::__I1(iter) => iter.size_hint()
+                            }
+                        }
+                    }
+
+                    match self {
+                        SimpleCell::Lut(inner) => __Iter::__I0(inner.parameters()),
+                        SimpleCell::Gate(inner) => __Iter::__I1(inner.parameters())
+                    }
+                }
+
+                fn get_default_parameter(&self, id: &Identifier) -> Option<Parameter> {
                     match self {
-                        SimpleCell::Lut(inner) => inner.parameters().collect::<Vec<_>>().into_iter(),
-                        SimpleCell::Gate(inner) => inner.parameters().collect::<Vec<_>>().into_iter()
+                        SimpleCell::Lut(inner) => inner.get_default_parameter(id),
+                        SimpleCell::Gate(inner) => inner.get_default_parameter(id)
                     }
                 }
 
                 fn from_constant(val: Logic) -> Option<Self> {
-                    if (val == Logic::True) || (val == Logic::False) {
-                        return Gate::from_constant(val).map(SimpleCell::Gate);
-                    } else {
-                        return None;
+                    match val {
+                        Logic::True | Logic::False => Gate::from_constant(val).map(SimpleCell::Gate),
+                        #[allow(unreachable_patterns)]
+                        _ => None,
                     }
                 }
 
@@ -329,12 +1132,33 @@ mod tests {
                     }
                 }
 
+                fn fold(&self, inputs: &[Option<Logic>]) -> Option<Logic> {
+                    match self {
+                        SimpleCell::Lut(inner) => inner.fold(inputs),
+                        SimpleCell::Gate(inner) => inner.fold(inputs)
+                    }
+                }
+
                 fn is_seq(&self) -> bool {
                     match self {
                         SimpleCell::Lut(inner) => inner.is_seq(),
                         SimpleCell::Gate(inner) => inner.is_seq()
                     }
                 }
+
+                fn is_blackbox(&self) -> bool {
+                    match self {
+                        SimpleCell::Lut(inner) => inner.is_blackbox(),
+                        SimpleCell::Gate(inner) => inner.is_blackbox()
+                    }
+                }
+
+                fn is_latch(&self) -> bool {
+                    match self {
+                        SimpleCell::Lut(inner) => inner.is_latch(),
+                        SimpleCell::Gate(inner) => inner.is_latch()
+                    }
+                }
             }
         };
 
@@ -363,16 +1187,62 @@ mod tests {
                 }
 
                 fn get_input_ports(&self) -> impl IntoIterator<Item = &Net> {
+                    enum __Iter<__I0, __I1> {
+                        __I0(__I0),
+                        __I1(__I1)
+                    }
+
+                    impl<__T, __I0: Iterator<Item = __T>, __I1: Iterator<Item = __T>> Iterator for __Iter<__I0, __I1> {
+                        type Item = __T;
+
+                        fn next(&mut self) -> Option<__T> {
+                            match self {
+                                __Iter::__I0(iter) => iter.next(),
+                                __Iter::__I1(iter) => iter.next()
+                            }
+                        }
+
+                        fn size_hint(&self) -> (usize, Option<usize>) {
+                            match self {
+                                __Iter::__I0(iter) => iter.size_hint(),
+                                __Iter::__I1(iter) => iter.size_hint()
+                            }
+                        }
+                    }
+
                     match self {
-                        SimpleCell::Lut(inner) => inner.get_input_ports().into_iter().collect::<Vec<_>>(),
-                        SimpleCell::Gate(inner) => inner.get_input_ports().into_iter().collect::<Vec<_>>()
+                        SimpleCell::Lut(inner) => __Iter::__I0(inner.get_input_ports().into_iter()),
+                        SimpleCell::Gate(inner) => __Iter::__I1(inner.get_input_ports().into_iter())
                     }
                 }
 
                 fn get_output_ports(&self) -> impl IntoIterator<Item = &Net> {
+                    enum __Iter<__I0, __I1> {
+                        __I0(__I0),
+                        __I1(__I1)
+                    }
+
+                    impl<__T, __I0: Iterator<Item = __T>, __I1: Iterator<Item = __T>> Iterator for __Iter<__I0, __I1> {
+                        type Item = __T;
+
+                        fn next(&mut self) -> Option<__T> {
+                            match self {
+                                __Iter::__I0(iter) => iter.next(),
+                                __Iter::__I1(iter) => iter.next()
+                            }
+                        }
+
+                        fn size_hint(&self) -> (usize, Option<usize>) {
+                            match self {
+                                __Iter::__I0(iter) => iter.size_hint(),
+                                __Iter::__I1(iter) => iter.size_hint()
+                            }
+                        }
+                    }
+
                     match self {
-                        SimpleCell::Lut(inner) => inner.get_output_ports().into_iter().collect::<Vec<_>>(),
-                        SimpleCell::Gate(inner) => inner.get_output_ports().into_iter().collect::<Vec<_>>()
+                        SimpleCell::Lut(inner) => __Iter::__I0(inner.get_output_ports().into_iter()),
+                        SimpleCell::Gate(inner) => __Iter::__I1(inner.get_output_ports().into_iter())
                     }
                 }
 
@@ -398,9 +1268,39 @@ mod tests {
                 }
 
                 fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)> {
+                    enum __Iter<__I0, __I1> {
+                        __I0(__I0),
+                        __I1(__I1)
+                    }
+
+                    impl<__T, __I0: Iterator<Item = __T>, __I1: Iterator<Item = __T>> Iterator for __Iter<__I0, __I1> {
+                        type Item = __T;
+
+                        fn next(&mut self) -> Option<__T> {
+                            match self {
+                                __Iter::__I0(iter) => iter.next(),
+                                __Iter::__I1(iter) => iter.next()
+                            }
+                        }
+
+                        fn size_hint(&self) -> (usize, Option<usize>) {
+                            match self {
+                                __Iter::__I0(iter) => iter.size_hint(),
+                                __Iter::__I1(iter) => iter.size_hint()
+                            }
+                        }
+                    }
+
+                    match self {
+                        SimpleCell::Lut(inner) => __Iter::__I0(inner.parameters()),
+                        SimpleCell::Gate(inner) => __Iter::__I1(inner.parameters())
+                    }
+                }
+
+                fn get_default_parameter(&self, id: &Identifier) -> Option<Parameter> {
                     match self {
-                        SimpleCell::Lut(inner) => inner.parameters().collect::<Vec<_>>().into_iter(),
-                        SimpleCell::Gate(inner) => inner.parameters().collect::<Vec<_>>().into_iter()
+                        SimpleCell::Lut(inner) => inner.get_default_parameter(id),
+                        SimpleCell::Gate(inner) => inner.get_default_parameter(id)
                     }
                 }
 
@@ -415,12 +1315,33 @@ mod tests {
                     }
                 }
 
+                fn fold(&self, inputs: &[Option<Logic>]) -> Option<Logic> {
+                    match self {
+                        SimpleCell::Lut(inner) => inner.fold(inputs),
+                        SimpleCell::Gate(inner) => inner.fold(inputs)
+                    }
+                }
+
                 fn is_seq(&self) -> bool {
                     match self {
                         SimpleCell::Lut(inner) => inner.is_seq(),
                         SimpleCell::Gate(inner) => inner.is_seq()
                     }
                 }
+
+                fn is_blackbox(&self) -> bool {
+                    match self {
+                        SimpleCell::Lut(inner) => inner.is_blackbox(),
+                        SimpleCell::Gate(inner) => inner.is_blackbox()
+                    }
+                }
+
+                fn is_latch(&self) -> bool {
+                    match self {
+                        SimpleCell::Lut(inner) => inner.is_latch(),
+                        SimpleCell::Gate(inner) => inner.is_latch()
+                    }
+                }
             }
         };
 
@@ -450,4 +1371,224 @@ mod tests {
             output_str
         );
     }
+
+    #[test]
+    fn test_struct_errors() {
+        let input: DeriveInput = parse_quote! {
+            struct Lut(Net);
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        assert!(output.contains("structs with named fields"));
+
+        let input: DeriveInput = parse_quote! {
+            struct Lut {
+                #[input_port]
+                inputs: Vec<Net>,
+            }
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        assert!(output.contains("Expected a #[cell_name] field"));
+
+        let input: DeriveInput = parse_quote! {
+            #[cell_name = "LUT{k-1}"]
+            struct Lut {
+                k: usize,
+            }
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        assert!(output.contains("cell_name arguments must be field names"));
+
+        let input: DeriveInput = parse_quote! {
+            #[cell_name = "LUT{k}"]
+            struct Lut {
+                k: usize,
+            }
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        assert!(output.contains("needs a #[cell_name] field"));
+
+        let input: DeriveInput = parse_quote! {
+            #[cell_name = "LUT{size}"]
+            struct Lut {
+                #[cell_name]
+                name: Identifier,
+            }
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        assert!(output.contains("'size' is not a field"));
+    }
+
+    #[test]
+    fn test_struct_ports() {
+        let input: DeriveInput = parse_quote! {
+            #[cell_name = "LUT{k}"]
+            #[instantiable(seq)]
+            struct Lut {
+                #[cell_name]
+                name: Identifier,
+                k: usize,
+                #[input_port]
+                inputs: Vec<Net>,
+                #[output_port]
+                output: Net,
+            }
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        assert!(output.contains(&quote! { .chain(self.inputs.iter()) }.to_string()));
+        assert!(output.contains(&quote! { .chain(std::iter::once(&self.output)) }.to_string()));
+        assert!(output.contains(&quote! { fn cell_name(k: &usize) -> Identifier }.to_string()));
+        assert!(output.contains(&quote! { format!("LUT{k}", k = k) }.to_string()));
+        assert!(
+            output
+                .contains(&quote! { fn get_name(&self) -> &Identifier { &self.name } }.to_string())
+        );
+        assert!(output.contains(&quote! { fn is_seq(&self) -> bool { true } }.to_string()));
+    }
+
+    #[test]
+    fn test_generic_enum() {
+        let input: DeriveInput = parse_quote! {
+            enum Cell<'a, T: Instantiable> where T: Clone {
+                #[instantiable(constant)]
+                Wrapped(Wrapper<'a, T>),
+                Inner(T),
+            }
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        let header = quote! {
+            impl<'a, T: Instantiable> Instantiable for Cell<'a, T> where T: Clone
+        };
+        assert!(output.starts_with(&header.to_string()));
+        let from_constant = quote! {
+            <Wrapper<'a, T> >::from_constant(val).map(Cell::Wrapped)
+        };
+        assert!(output.contains(&from_constant.to_string()));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let input: DeriveInput = parse_quote! {
+            #[instantiable(accessors)]
+            enum Cell {
+                Lut(Lut),
+                FlipFlop(FlipFlop),
+            }
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        let from = quote! {
+            impl From<FlipFlop> for Cell {
+                fn from(inner: FlipFlop) -> Self {
+                    Cell::FlipFlop(inner)
+                }
+            }
+        };
+        assert!(output.contains(&from.to_string()));
+        assert!(
+            output
+                .contains(&quote! { pub fn as_flip_flop(&self) -> Option<&FlipFlop> }.to_string())
+        );
+        assert!(
+            output.contains(&quote! { pub fn into_lut(self) -> Result<Lut, Self> }.to_string())
+        );
+
+        let input: DeriveInput = parse_quote! {
+            #[instantiable(constant)]
+            enum Cell {
+                Lut(Lut),
+            }
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        assert!(output.contains("expected 'accessors'"));
+    }
+
+    #[test]
+    fn test_constant_values() {
+        let input: DeriveInput = parse_quote! {
+            enum Cell {
+                #[instantiable(constant)]
+                Gate(Gate),
+                #[instantiable(constant(X, Z))]
+                Pull(Pull),
+            }
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        let from_constant = quote! {
+            fn from_constant(val: Logic) -> Option<Self> {
+                match val {
+                    Logic::True | Logic::False => Gate::from_constant(val).map(Cell::Gate),
+                    Logic::X | Logic::Z => Pull::from_constant(val).map(Cell::Pull),
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+        };
+        assert!(output.contains(&from_constant.to_string()));
+
+        let input: DeriveInput = parse_quote! {
+            enum Cell {
+                #[instantiable(constant(Z))]
+                Gate(Gate),
+                #[instantiable(constant(Z))]
+                Pull(Pull),
+            }
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        assert!(output.contains("for Logic::Z"));
+
+        let input: DeriveInput = parse_quote! {
+            enum Cell {
+                #[instantiable(constant(High))]
+                Gate(Gate),
+            }
+        };
+        let output = impl_instantiable_trait(input).to_string();
+        assert!(output.contains("expected one of 'True', 'False', 'X' or 'Z'"));
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let input: DeriveInput = parse_quote! {
+            enum Cell {
+                Lut(Lut),
+                Gate(Gate),
+            }
+        };
+        let expected = quote! {
+            impl Evaluate for Cell {
+                fn eval(&self, inputs: &[Logic]) -> Vec<Logic> {
+                    match self {
+                        Cell::Lut(inner) => inner.eval(inputs),
+                        Cell::Gate(inner) => inner.eval(inputs)
+                    }
+                }
+
+                fn eval_words(&self, inputs: &[u64]) -> Vec<u64> {
+                    match self {
+                        Cell::Lut(inner) => inner.eval_words(inputs),
+                        Cell::Gate(inner) => inner.eval_words(inputs)
+                    }
+                }
+            }
+        };
+        assert_tokens_eq(impl_evaluate_trait(input), expected);
+
+        let input: DeriveInput = parse_quote! {
+            struct Lut {
+                #[parameter(name = "INIT")]
+                init: BitVec,
+            }
+        };
+        let output = impl_evaluate_trait(input).to_string();
+        assert!(output.contains("evaluate(table"));
+
+        let input: DeriveInput = parse_quote! {
+            #[evaluate(init = "INIT")]
+            struct Lut {
+                #[parameter(name = "INIT")]
+                init: BitVec,
+            }
+        };
+        let output = impl_evaluate_trait(input).to_string();
+        assert!(output.contains("expected 'table'"));
+    }
 }
@@ -6,7 +6,8 @@
 
 use crate::{
     circuit::Instantiable,
-    logic::Logic,
+    error::Error,
+    logic::{Logic, LogicVec},
     netlist::{NetRef, Netlist},
 };
 use bitvec::{bitvec, field::BitField, order::Lsb0, vec::BitVec};
@@ -14,12 +15,18 @@ use std::collections::{HashMap, HashSet};
 
 /// A Verilog attribute assigned to a net or gate in the netlist: (* dont_touch *)
 pub type AttributeKey = String;
-/// A Verilog attribute can be assigned a string value: bitvec = (* dont_touch = true *)
-pub type AttributeValue = Option<String>;
+/// A Verilog attribute can be assigned a typed value: (* keep_hierarchy = "yes" *)
+pub type AttributeValue = Option<Parameter>;
+
+/// The attribute that protects nodes and nets from being modified by edits and passes
+pub const DONT_TOUCH: &str = "dont_touch";
+
+/// The attribute that marks instances as spare cells, which are protected like [DONT_TOUCH] until an engineering change uses them
+pub const SPARE: &str = "spare";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-/// An attribute can add information to instances and wires in string form, like 'dont_touch'
+/// An attribute can add information to instances, wires, and modules, like 'dont_touch'
 pub struct Attribute {
     k: AttributeKey,
     v: AttributeValue,
@@ -63,48 +70,235 @@ impl std::fmt::Display for Attribute {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A dedicated type to parameters for instantiables
 pub enum Parameter {
-    /// An unsigned integer parameter
-    Integer(u64),
+    /// A signed integer parameter
+    Integer(i64),
     /// A floating-point parameter
-    Real(f32),
+    Real(f64),
     /// A bit vector parameter, like for a truth table
     BitVec(BitVec),
     /// A four-state logic parameter
     Logic(Logic),
+    /// A four-state logic vector parameter, for values with unknown bits
+    LogicVec(LogicVec),
+    /// A string parameter, like a mode selection
+    String(String),
 }
 
 impl Eq for Parameter {}
 
+impl From<i64> for Parameter {
+    fn from(i: i64) -> Self {
+        Parameter::Integer(i)
+    }
+}
+
+impl From<f64> for Parameter {
+    fn from(r: f64) -> Self {
+        Parameter::Real(r)
+    }
+}
+
+/// Booleans are represented as the integers 0 and 1, as in Verilog
+impl From<bool> for Parameter {
+    fn from(b: bool) -> Self {
+        Parameter::Integer(i64::from(b))
+    }
+}
+
+impl From<BitVec> for Parameter {
+    fn from(bv: BitVec) -> Self {
+        Parameter::BitVec(bv)
+    }
+}
+
+impl From<Logic> for Parameter {
+    fn from(l: Logic) -> Self {
+        Parameter::Logic(l)
+    }
+}
+
+impl From<LogicVec> for Parameter {
+    fn from(lv: LogicVec) -> Self {
+        Parameter::LogicVec(lv)
+    }
+}
+
+impl From<String> for Parameter {
+    fn from(s: String) -> Self {
+        Parameter::String(s)
+    }
+}
+
+impl From<&str> for Parameter {
+    fn from(s: &str) -> Self {
+        Parameter::String(s.to_string())
+    }
+}
+
+/// Implements the conversion from a [Parameter] of `variant` to `ty`
+macro_rules! parameter_try_from {
+    ($ty:ty, $variant:ident, $what:literal) => {
+        impl TryFrom<Parameter> for $ty {
+            type Error = Error;
+
+            fn try_from(p: Parameter) -> Result<Self, Self::Error> {
+                match p {
+                    Parameter::$variant(v) => Ok(v),
+                    _ => Err(Error::InstantiableError(format!(
+                        "Expected {} parameter, got {p}",
+                        $what
+                    ))),
+                }
+            }
+        }
+    };
+}
+
+parameter_try_from!(i64, Integer, "an integer");
+parameter_try_from!(f64, Real, "a real");
+parameter_try_from!(BitVec, BitVec, "a bit vector");
+parameter_try_from!(Logic, Logic, "a logic");
+parameter_try_from!(LogicVec, LogicVec, "a logic vector");
+parameter_try_from!(String, String, "a string");
+
+/// Only the integers 0 and 1 are booleans
+impl TryFrom<Parameter> for bool {
+    type Error = Error;
+
+    fn try_from(p: Parameter) -> Result<Self, Self::Error> {
+        match p {
+            Parameter::Integer(0) => Ok(false),
+            Parameter::Integer(1) => Ok(true),
+            _ => Err(Error::InstantiableError(format!(
+                "Expected a boolean parameter, got {p}"
+            ))),
+        }
+    }
+}
+
+/// Parameters print as Verilog literals, such that they can be parsed back with [std::str::FromStr]
 impl std::fmt::Display for Parameter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Parameter::Integer(i) => write!(f, "{i}"),
-            Parameter::Real(_r) => todo!(),
-            Parameter::BitVec(bv) => write!(
-                f,
-                "{}'b{}",
-                bv.len(),
-                bv.iter()
-                    .rev()
-                    .map(|b| if *b { '1' } else { '0' })
-                    .collect::<String>()
-            ),
+            // Debug formatting always includes a decimal point or exponent
+            Parameter::Real(r) => write!(f, "{r:?}"),
+            Parameter::BitVec(bv) => {
+                write!(f, "{}'h", bv.len())?;
+                for nibble in bv.chunks(4).rev() {
+                    let d = nibble.iter().rev().fold(0, |d, b| (d << 1) | u32::from(*b));
+                    write!(
+                        f,
+                        "{}",
+                        char::from_digit(d, 16).unwrap().to_ascii_uppercase()
+                    )?;
+                }
+                Ok(())
+            }
             Parameter::Logic(l) => write!(f, "{l}"),
+            Parameter::LogicVec(lv) => write!(f, "{lv}"),
+            Parameter::String(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        '\t' => write!(f, "\\t")?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                write!(f, "\"")
+            }
         }
     }
 }
 
+/// Parses a string literal, with its quotes
+fn parse_string(s: &str) -> Option<String> {
+    let inner = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut res = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => res.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                c @ ('"' | '\\') => c,
+                _ => return None,
+            }),
+            '"' => return None,
+            c => res.push(c),
+        }
+    }
+    Some(res)
+}
+
+/// Parses a based literal, like `8'hFF` or `'d10`, as a [Parameter::BitVec].
+/// Single-bit literals parse to [Parameter::Logic] instead, and literals with unknown bits to [Parameter::LogicVec].
+fn parse_based(s: &str) -> Option<Parameter> {
+    let lv = s.parse::<LogicVec>().ok()?;
+    if lv.len() == 1 {
+        return lv.get(0).map(Parameter::Logic);
+    }
+    Some(match lv.to_bitvec() {
+        Some(bv) => Parameter::BitVec(bv),
+        None => Parameter::LogicVec(lv),
+    })
+}
+
+impl std::str::FromStr for Parameter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let err = || Error::ParseError(s.to_string());
+        if s.starts_with('"') {
+            return parse_string(s).map(Parameter::String).ok_or_else(err);
+        }
+        if s.contains('\'') {
+            return parse_based(s).ok_or_else(err);
+        }
+
+        let num = s.replace('_', "");
+        if let Ok(i) = num.parse::<i64>() {
+            return Ok(Parameter::Integer(i));
+        }
+        if num.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+')
+            && let Ok(r) = num.parse::<f64>()
+            && r.is_finite()
+        {
+            return Ok(Parameter::Real(r));
+        }
+        Err(err())
+    }
+}
+
 impl Parameter {
     /// Create a new integer parameter
-    pub fn integer(i: u64) -> Self {
+    pub fn integer(i: i64) -> Self {
         Self::Integer(i)
     }
 
     /// Create a new real parameter
-    pub fn real(r: f32) -> Self {
+    pub fn real(r: f64) -> Self {
         Self::Real(r)
     }
 
+    /// Create a new string parameter
+    pub fn string(s: impl Into<String>) -> Self {
+        Self::String(s.into())
+    }
+
+    /// Returns the parameter as a boolean, if it is a zero or one integer or logic value
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Parameter::Integer(0) | Parameter::Logic(Logic::False) => Some(false),
+            Parameter::Integer(1) | Parameter::Logic(Logic::True) => Some(true),
+            _ => None,
+        }
+    }
+
     /// Create a new bitvec parameter
     pub fn bitvec(size: usize, val: u64) -> Self {
         if size > 64 {
@@ -117,7 +311,8 @@ impl Parameter {
     }
 }
 
-/// Filter nodes/nets in the netlist by some attribute, like "dont_touch"
+/// Filter nodes/nets in the netlist by some attribute, like "dont_touch".
+/// A node is selected when it, or one of the nets it drives, has one of the keys.
 pub struct AttributeFilter<'a, I: Instantiable> {
     // A reference to the underlying netlist
     _netlist: &'a Netlist<I>,
@@ -135,16 +330,12 @@ where
 {
     /// Create a new filter for the netlist
     fn new(netlist: &'a Netlist<I>, keys: Vec<AttributeKey>) -> Self {
-        let mut map = HashMap::new();
+        let mut map: HashMap<AttributeKey, HashSet<NetRef<I>>> = HashMap::new();
         let mut full_set = HashSet::new();
-        for nr in netlist.objects() {
-            for attr in nr.attributes() {
-                if keys.contains(attr.key()) {
-                    map.entry(attr.key().clone())
-                        .or_insert_with(HashSet::new)
-                        .insert(nr.clone());
-                    full_set.insert(nr.clone());
-                }
+        for key in keys.iter() {
+            for nr in netlist.objects_with_attribute(key) {
+                map.entry(key.clone()).or_default().insert(nr.clone());
+                full_set.insert(nr);
             }
         }
         Self {
@@ -164,6 +355,26 @@ where
     pub fn keys(&self) -> &[AttributeKey] {
         &self.keys
     }
+
+    /// Check if a node has the attribute `k`, which must be one of the filter keys
+    pub fn has_key(&self, n: &NetRef<I>, k: &AttributeKey) -> bool {
+        self.map.get(k).is_some_and(|s| s.contains(n))
+    }
+
+    /// Returns an iterator to the nodes that match any of the filter keys
+    pub fn iter(&self) -> impl Iterator<Item = &NetRef<I>> {
+        self.full_set.iter()
+    }
+
+    /// Returns the number of nodes that match any of the filter keys
+    pub fn len(&self) -> usize {
+        self.full_set.len()
+    }
+
+    /// Returns `true` if no node matches the filter keys
+    pub fn is_empty(&self) -> bool {
+        self.full_set.is_empty()
+    }
 }
 
 impl<'a, I> IntoIterator for AttributeFilter<'a, I>
@@ -179,12 +390,23 @@ where
     }
 }
 
+/// Returns a filtering of nodes and nets that have any of the attribute `keys`
+pub fn attribute_filter<'a, I>(
+    netlist: &'a Netlist<I>,
+    keys: impl IntoIterator<Item = AttributeKey>,
+) -> AttributeFilter<'a, I>
+where
+    I: Instantiable,
+{
+    AttributeFilter::new(netlist, keys.into_iter().collect())
+}
+
 /// Returns a filtering of nodes and nets that are marked as 'dont_touch'
 pub fn dont_touch_filter<'a, I>(netlist: &'a Netlist<I>) -> AttributeFilter<'a, I>
 where
     I: Instantiable,
 {
-    AttributeFilter::new(netlist, vec!["dont_touch".to_string()])
+    AttributeFilter::new(netlist, vec![DONT_TOUCH.to_string()])
 }
 
 #[cfg(test)]
@@ -193,16 +415,30 @@ mod tests {
 
     #[test]
     fn attribute_iter() {
-        let attributes: [(AttributeKey, AttributeValue); 2] = [
-            ("dont_touch".to_string(), Some("true".to_string())),
+        let attributes: [(AttributeKey, AttributeValue); 4] = [
+            ("dont_touch".to_string(), Some(true.into())),
+            ("keep_hierarchy".to_string(), Some("yes".into())),
+            ("init".to_string(), Some(Parameter::bitvec(4, 5))),
             ("synthesizable".to_string(), None),
         ];
         let real_attrs: Vec<Attribute> = Attribute::from_pairs(attributes.into_iter()).collect();
-        assert_eq!(real_attrs.len(), 2);
+        assert_eq!(real_attrs.len(), 4);
         assert_eq!(
             real_attrs.first().unwrap().to_string(),
-            "(* dont_touch = true *)"
+            "(* dont_touch = 1 *)"
         );
+        assert_eq!(
+            real_attrs
+                .first()
+                .unwrap()
+                .value()
+                .as_ref()
+                .unwrap()
+                .as_bool(),
+            Some(true)
+        );
+        assert_eq!(real_attrs[1].to_string(), "(* keep_hierarchy = \"yes\" *)");
+        assert_eq!(real_attrs[2].to_string(), "(* init = 4'h5 *)");
         assert_eq!(real_attrs.first().unwrap().key(), "dont_touch");
         assert_eq!(
             real_attrs.last().unwrap().to_string(),
@@ -217,6 +453,54 @@ mod tests {
         // Lsb first
         let p2 = Parameter::BitVec(bitvec![0, 0, 0, 0, 0, 0, 0, 1]);
         assert_eq!(p1.to_string(), "42");
-        assert_eq!(p2.to_string(), "8'b10000000");
+        assert_eq!(p2.to_string(), "8'h80");
+        assert_eq!(Parameter::bitvec(16, 0xAAAA).to_string(), "16'hAAAA");
+        assert_eq!(Parameter::bitvec(6, 0b101101).to_string(), "6'h2D");
+        assert_eq!(Parameter::Integer(-3).to_string(), "-3");
+        assert_eq!(Parameter::Real(1.0).to_string(), "1.0");
+        assert_eq!(Parameter::Real(2.5e-9).to_string(), "2.5e-9");
+        assert_eq!(Parameter::string("SYNC").to_string(), "\"SYNC\"");
+        assert_eq!(Parameter::string("a\"b").to_string(), "\"a\\\"b\"");
+    }
+
+    #[test]
+    fn test_parameter_parse() {
+        let parse = |s: &str| s.parse::<Parameter>().unwrap();
+        assert_eq!(parse("32'hDEADBEEF"), Parameter::bitvec(32, 0xDEADBEEF));
+        assert_eq!(parse("8'b1010_0101"), Parameter::bitvec(8, 0xA5));
+        assert_eq!(parse("6'o17"), Parameter::bitvec(6, 0o17));
+        assert_eq!(parse("16'd300"), Parameter::bitvec(16, 300));
+        assert_eq!(parse("4'hFF"), Parameter::bitvec(4, 0xF));
+        assert_eq!(parse("'h10"), Parameter::bitvec(32, 16));
+        assert_eq!(parse("1'b1"), Parameter::Logic(Logic::True));
+        assert_eq!(parse("1'bx"), Parameter::Logic(Logic::X));
+        assert_eq!(parse("-42"), Parameter::Integer(-42));
+        assert_eq!(parse("1_000"), Parameter::Integer(1000));
+        assert_eq!(parse("0.5"), Parameter::Real(0.5));
+        assert_eq!(parse("1e3"), Parameter::Real(1e3));
+        assert_eq!(parse("\"SYNC\""), Parameter::string("SYNC"));
+        assert_eq!(parse("\"a\\\"b\\n\""), Parameter::string("a\"b\n"));
+        assert_eq!(parse("128'h1").to_string(), format!("128'h{:0>32}", 1));
+        assert_eq!(
+            parse("4'bx01z"),
+            Parameter::LogicVec("4'bx01z".parse().unwrap())
+        );
+
+        for bad in [
+            "SYNC", "\"SYNC", "8'q12", "0'b1", "8'hG", "4'b2", "inf", "NaN",
+        ] {
+            assert!(bad.parse::<Parameter>().is_err(), "{bad}");
+        }
+
+        for p in [
+            Parameter::Integer(-7),
+            Parameter::Real(0.1),
+            Parameter::bitvec(12, 0xABC),
+            Parameter::Logic(Logic::Z),
+            Parameter::LogicVec("6'b1xz0_10".parse().unwrap()),
+            Parameter::string("tab\tquote\""),
+        ] {
+            assert_eq!(p.to_string().parse::<Parameter>().unwrap(), p);
+        }
     }
 }
@@ -0,0 +1,22 @@
+//! An interactive shell for netlists, see [safety_net::shell]
+
+use safety_net::shell::Shell;
+use std::io::{BufReader, IsTerminal};
+
+fn main() {
+    let mut shell = Shell::new();
+    let result = match std::env::args().nth(1) {
+        Some(script) => std::fs::File::open(&script)
+            .map_err(Into::into)
+            .and_then(|f| shell.run(BufReader::new(f), std::io::stdout(), None)),
+        None => {
+            let stdin = std::io::stdin();
+            let prompt = stdin.is_terminal().then_some("> ");
+            shell.run(stdin.lock(), std::io::stdout(), prompt)
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,157 @@
+//! A command-line tool for common operations on netlist files, see `snet help`
+
+use safety_net::{
+    error::Error,
+    graph::MultiDiGraph,
+    netlist::{
+        GateNetlist, VerifyOptions,
+        opt::{propagate_constants, push_inverters},
+        rewrite::rewrite,
+        select::Stats,
+        testing::check_equivalent,
+        yosys::run_yosys_step,
+    },
+    shell::{load_netlist, save_netlist},
+};
+use std::{collections::BTreeSet, process::ExitCode, rc::Rc};
+
+const USAGE: &str = "\
+usage: snet <command> <args>
+
+Netlist files are .json, .snet or .aag (ASCII AIGER), and outputs can also be .v (Verilog).
+
+commands:
+  convert <in> <out>   convert a netlist to the format of <out>
+  stats <file>         summarize the inputs, instances and cells
+  lint <file>          report connectivity, naming and cycle problems, and unused logic
+  clean <in> <out>     remove the circuit nodes that do not reach an output
+  diff <a> <b>         compare the cells and the functions of the outputs
+  dot <file>           print the netlist as a Graphviz graph
+  yosys <passes> <in> <out>
+                       run passes separated by commas on a Yosys JSON file, as a step of a Yosys flow:
+                       write_json top.json; !snet yosys clean top.json top.json; design -reset; read_json top.json
+                       passes: clean, constants, inverters, rewrite
+  help                 print this message";
+
+/// The number of random patterns simulated by `diff`
+const DIFF_PATTERNS: usize = 1024;
+
+/// Reports the problems with `netlist`, returning whether there were none
+fn lint(netlist: &Rc<GateNetlist>) -> bool {
+    let diagnostics = netlist.diagnose(&VerifyOptions::signoff());
+    print!("{diagnostics}");
+    // The netlist is not saved, so cleaning it only counts the unused logic
+    let before = netlist.objects().count();
+    if netlist.clean().is_ok() {
+        let unused = before - netlist.objects().count();
+        if unused > 0 {
+            println!("warning: {unused} circuit nodes do not reach an output");
+        }
+    }
+    diagnostics.is_empty()
+}
+
+/// Compares the cells and the output functions of `a` and `b`, returning whether they match
+fn diff(a: &GateNetlist, b: &GateNetlist) -> Result<bool, Error> {
+    let (a_stats, b_stats) = (a.stats(), b.stats());
+    let mut same = true;
+    let cells: BTreeSet<&String> = a_stats.cells.keys().chain(b_stats.cells.keys()).collect();
+    for cell in cells {
+        let count = |s: &Stats| s.cells.get(cell).copied().unwrap_or(0);
+        if count(&a_stats) != count(&b_stats) {
+            println!("{cell}: {} -> {}", count(&a_stats), count(&b_stats));
+            same = false;
+        }
+    }
+    match check_equivalent(a, b, DIFF_PATTERNS, 0) {
+        Ok(()) => println!("outputs agree on {DIFF_PATTERNS} random patterns"),
+        Err(Error::NonequivalentOutputs(nets)) => {
+            for net in nets {
+                println!("output {net} differs");
+            }
+            same = false;
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(same)
+}
+
+/// Runs the passes named in `passes`, separated by commas, on `netlist`
+fn run_passes(netlist: &Rc<GateNetlist>, passes: &str) -> Result<(), Error> {
+    for pass in passes.split(',') {
+        match pass {
+            "clean" => {
+                netlist.clean()?;
+            }
+            "constants" => {
+                propagate_constants(netlist)?;
+            }
+            "inverters" => {
+                push_inverters(netlist)?;
+            }
+            "rewrite" => {
+                rewrite(netlist)?;
+            }
+            _ => return Err(Error::Unsupported(format!("Unknown pass `{pass}`"))),
+        }
+    }
+    Ok(())
+}
+
+/// Runs the command in `args`, returning its exit code: `1` if it found problems or differences, and `2` for bad usage
+fn run(args: &[String]) -> Result<ExitCode, Error> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args[..] {
+        ["convert", input, output] => {
+            let netlist = load_netlist(input)?;
+            save_netlist(&netlist, output)?;
+        }
+        ["stats", file] => print!("{}", load_netlist(file)?.stats()),
+        ["lint", file] => return Ok(exit_code(lint(&load_netlist(file)?))),
+        ["clean", input, output] => {
+            let netlist = load_netlist(input)?;
+            netlist.clean()?;
+            save_netlist(&netlist, output)?;
+        }
+        ["diff", a, b] => {
+            let (a, b) = (load_netlist(a)?, load_netlist(b)?);
+            return Ok(exit_code(diff(&a, &b)?));
+        }
+        ["dot", file] => {
+            let netlist = load_netlist(file)?;
+            let analysis = netlist.get_analysis::<MultiDiGraph<_>>()?;
+            println!("{}", petgraph::dot::Dot::new(analysis.get_graph()));
+        }
+        ["yosys", passes, input, output] => {
+            run_yosys_step(input.as_ref(), output.as_ref(), |netlist| {
+                run_passes(netlist, passes)
+            })?;
+        }
+        ["help"] => println!("{USAGE}"),
+        _ => {
+            eprintln!("{USAGE}");
+            return Ok(ExitCode::from(2));
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Returns the exit code of a command that found no problems when `ok`
+fn exit_code(ok: bool) -> ExitCode {
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,480 @@
+/*!
+
+  A C interface for embedding netlists of [Gate]s in C and C++ tools, behind the `capi` feature.
+
+  Netlists are opaque handles that the caller owns, while circuit nodes and nets are referred to by their stable
+  identifiers (see [ObjectId] and [NetId]), so that they need no freeing and stay valid across edits to the rest of the netlist.
+  The prototypes are in `include/safety_net.h`, which is generated from this module by `tests/capi.rs`
+  (set `SAFETY_NET_HEADER=overwrite` to regenerate it). Build a library to link against with
+  `cargo rustc --release --features capi --crate-type cdylib`, or `staticlib`.
+
+  Functions that can fail return [SN_OK] or [SN_ERROR], and [sn_last_error] describes the last failure on the calling thread.
+  The strings returned by the library are freed with [sn_string_free]. Handles are not thread-safe. Panics never unwind into
+  the caller: they are caught and reported as failures, and the netlist they happened in may be left half-edited.
+
+*/
+
+use crate::{
+    circuit::Identifier,
+    netlist::{
+        DrivenNet, Gate, GateNetlist, NetRef, Netlist, VerifyOptions, VerilogOptions,
+        annotation::{NetId, ObjectId},
+    },
+};
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString, c_char},
+    panic::{AssertUnwindSafe, catch_unwind},
+    ptr::null_mut,
+    rc::Rc,
+};
+
+/// The status of a call that succeeded
+pub const SN_OK: i32 = 0;
+/// The status of a call that failed, see [sn_last_error]
+pub const SN_ERROR: i32 = -1;
+
+/// A netlist of [Gate]s, owned by the caller until [sn_netlist_free]
+pub struct SnNetlist(Rc<GateNetlist>);
+
+/// The stable identifier of a circuit node, see [ObjectId]
+pub type SnObject = u64;
+
+/// A net, as the stable identifier of its driver and its output position on the driver, see [NetId]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnNet {
+    /// The circuit node driving the net
+    pub object: SnObject,
+    /// The output position of the net on its driver
+    pub output: u64,
+}
+
+impl From<NetId> for SnNet {
+    fn from(id: NetId) -> Self {
+        Self {
+            object: id.object.0 as u64,
+            output: id.output as u64,
+        }
+    }
+}
+
+impl From<SnNet> for NetId {
+    fn from(net: SnNet) -> Self {
+        Self {
+            object: ObjectId(net.object as usize),
+            output: net.output as usize,
+        }
+    }
+}
+
+thread_local! {
+    /// The description of the last failure on this thread
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Converts `s` to a C string, dropping interior nul bytes
+fn c_string(s: impl ToString) -> CString {
+    CString::new(s.to_string().replace('\0', "")).unwrap_or_default()
+}
+
+/// Records `e` as the last failure on this thread, for [sn_last_error]
+fn record(e: impl ToString) {
+    LAST_ERROR.with(|last| *last.borrow_mut() = c_string(e));
+}
+
+/// Runs `f`, or returns `default` after recording the failure if it panics, since a panic must not unwind into C
+fn guard<T>(default: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|panic| {
+        let message = match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+            (Some(s), _) => s.to_string(),
+            (_, Some(s)) => s.clone(),
+            _ => "unknown cause".to_string(),
+        };
+        record(format!("Panic: {message}"));
+        default
+    })
+}
+
+/// Runs `f` and returns its status, recording the error for [sn_last_error]
+fn status(f: impl FnOnce() -> Result<(), String>) -> i32 {
+    guard(SN_ERROR, || match f() {
+        Ok(()) => SN_OK,
+        Err(e) => {
+            record(e);
+            SN_ERROR
+        }
+    })
+}
+
+/// Runs `f` and returns its result as a string owned by the caller, or null after recording the error for [sn_last_error]
+fn string(f: impl FnOnce() -> Result<String, String>) -> *mut c_char {
+    guard(null_mut(), || match f() {
+        Ok(s) => c_string(s).into_raw(),
+        Err(e) => {
+            record(e);
+            null_mut()
+        }
+    })
+}
+
+/// Reads the C string `s`
+///
+/// # Safety
+///
+/// `s` must be null or a valid nul-terminated string that outlives the result
+unsafe fn read_str<'a>(s: *const c_char) -> Result<&'a str, String> {
+    if s.is_null() {
+        return Err("Unexpected null string".to_string());
+    }
+    unsafe { CStr::from_ptr(s) }
+        .to_str()
+        .map_err(|e| e.to_string())
+}
+
+/// Returns the netlist of the handle `netlist`
+///
+/// # Safety
+///
+/// `netlist` must be null or a handle from [sn_netlist_new] that was not freed
+unsafe fn read_netlist<'a>(netlist: *const SnNetlist) -> Result<&'a Rc<GateNetlist>, String> {
+    unsafe { netlist.as_ref() }
+        .map(|n| &n.0)
+        .ok_or_else(|| "Unexpected null netlist".to_string())
+}
+
+/// Writes `value` to the output parameter `out`
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), String> {
+    if out.is_null() {
+        return Err("Unexpected null output".to_string());
+    }
+    unsafe { out.write(value) };
+    Ok(())
+}
+
+/// Returns the circuit node of `netlist` with identifier `object`
+fn find_object(netlist: &GateNetlist, object: SnObject) -> Result<NetRef<Gate>, String> {
+    netlist
+        .find_object(ObjectId(object as usize))
+        .ok_or_else(|| format!("No circuit node #{object}"))
+}
+
+/// Returns the net of `netlist` with identifier `net`
+fn find_net(netlist: &GateNetlist, net: SnNet) -> Result<DrivenNet<Gate>, String> {
+    netlist
+        .find_net_by_id(net.into())
+        .ok_or_else(|| format!("No net {}", NetId::from(net)))
+}
+
+/// Returns the description of the last failure on the calling thread, which is empty if there was none.
+/// The string is owned by the library and is valid until the next failure on the thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn sn_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+/// Frees a string returned by the library.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by the library that was not freed
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_string_free(s: *mut c_char) {
+    guard((), || {
+        if !s.is_null() {
+            drop(unsafe { CString::from_raw(s) });
+        }
+    })
+}
+
+/// Creates an empty netlist named `name`, or returns null if `name` is not valid UTF-8.
+///
+/// # Safety
+///
+/// `name` must be a valid nul-terminated string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_netlist_new(name: *const c_char) -> *mut SnNetlist {
+    guard(null_mut(), || match unsafe { read_str(name) } {
+        Ok(name) => Box::into_raw(Box::new(SnNetlist(Netlist::new(name.to_string())))),
+        Err(e) => {
+            record(e);
+            null_mut()
+        }
+    })
+}
+
+/// Frees a netlist.
+///
+/// # Safety
+///
+/// `netlist` must be null or a handle from [sn_netlist_new] that was not freed
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_netlist_free(netlist: *mut SnNetlist) {
+    guard((), || {
+        if !netlist.is_null() {
+            drop(unsafe { Box::from_raw(netlist) });
+        }
+    })
+}
+
+/// Inserts an input net named `name`, writing it to `out`.
+///
+/// # Safety
+///
+/// `netlist` must be a live handle, `name` a valid nul-terminated string and `out` valid for writes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_insert_input(
+    netlist: *mut SnNetlist,
+    name: *const c_char,
+    out: *mut SnNet,
+) -> i32 {
+    status(|| {
+        let netlist = unsafe { read_netlist(netlist) }?;
+        let name = unsafe { read_str(name) }?;
+        let net = netlist.insert_input(name.into());
+        unsafe { write(out, net.get_id().into()) }
+    })
+}
+
+/// Inserts an instance `inst_name` of the gate `cell`, with the `n_ports` input ports named in `ports` and output `Y`.
+/// The inputs are left unconnected for [sn_connect], and the circuit node is written to `out`. Fails if `cell` is a bit slice like `X[1]`.
+///
+/// # Safety
+///
+/// `netlist` must be a live handle, `cell`, `inst_name` and the `n_ports` entries of `ports` valid nul-terminated strings,
+/// and `out` valid for writes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_insert_gate(
+    netlist: *mut SnNetlist,
+    cell: *const c_char,
+    inst_name: *const c_char,
+    ports: *const *const c_char,
+    n_ports: usize,
+    out: *mut SnObject,
+) -> i32 {
+    status(|| {
+        let netlist = unsafe { read_netlist(netlist) }?;
+        let cell = unsafe { read_str(cell) }?;
+        let inst_name = unsafe { read_str(inst_name) }?;
+        if ports.is_null() && n_ports > 0 {
+            return Err("Unexpected null port list".to_string());
+        }
+        let ports = (0..n_ports)
+            .map(|i| unsafe { read_str(*ports.add(i)) }.map(Identifier::from))
+            .collect::<Result<Vec<_>, _>>()?;
+        let cell = Identifier::from(cell);
+        if cell.is_sliced() {
+            return Err(format!("The cell name {cell} cannot be a bit slice"));
+        }
+        let gate = Gate::new_logical(cell, ports, "Y".into());
+        let node = netlist.insert_gate_disconnected(gate, inst_name.into());
+        unsafe { write(out, node.get_id().0 as u64) }
+    })
+}
+
+/// Connects input `input` of the circuit node `object` to the net `driver`, replacing its previous driver.
+///
+/// # Safety
+///
+/// `netlist` must be a live handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_connect(
+    netlist: *mut SnNetlist,
+    driver: SnNet,
+    object: SnObject,
+    input: usize,
+) -> i32 {
+    status(|| {
+        let netlist = unsafe { read_netlist(netlist) }?;
+        let driver = find_net(netlist, driver)?;
+        let node = find_object(netlist, object)?;
+        if input >= node.get_num_input_ports() {
+            return Err(format!("Circuit node #{object} has no input {input}"));
+        }
+        node.get_input(input).reconnect(driver);
+        Ok(())
+    })
+}
+
+/// Drives the top-level output `name` with `net`.
+///
+/// # Safety
+///
+/// `netlist` must be a live handle and `name` a valid nul-terminated string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_expose(
+    netlist: *mut SnNetlist,
+    net: SnNet,
+    name: *const c_char,
+) -> i32 {
+    status(|| {
+        let netlist = unsafe { read_netlist(netlist) }?;
+        let net = find_net(netlist, net)?;
+        let name = unsafe { read_str(name) }?;
+        netlist.expose_net_with_name(net, name.into());
+        Ok(())
+    })
+}
+
+/// Writes the identifiers of up to `capacity` circuit nodes to `out`, in netlist order, and returns the number of circuit nodes.
+/// Pass a null `out` to only count them.
+///
+/// # Safety
+///
+/// `netlist` must be a live handle, and `out` null or valid for `capacity` writes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_objects(
+    netlist: *const SnNetlist,
+    out: *mut SnObject,
+    capacity: usize,
+) -> usize {
+    guard(0, || {
+        let Ok(netlist) = (unsafe { read_netlist(netlist) }) else {
+            return 0;
+        };
+        let mut count = 0;
+        for node in netlist.objects() {
+            if !out.is_null() && count < capacity {
+                unsafe { out.add(count).write(node.get_id().0 as u64) };
+            }
+            count += 1;
+        }
+        count
+    })
+}
+
+/// Returns the instance name of the circuit node `object`, or the name of its net for an input, or null on failure.
+///
+/// # Safety
+///
+/// `netlist` must be a live handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_object_name(
+    netlist: *const SnNetlist,
+    object: SnObject,
+) -> *mut c_char {
+    string(|| {
+        let node = find_object(unsafe { read_netlist(netlist) }?, object)?;
+        Ok(match node.get_instance_name() {
+            Some(name) => name.to_string(),
+            None => node.get_identifier().to_string(),
+        })
+    })
+}
+
+/// Returns the name of the gate of the circuit node `object`, or null for an input or on failure.
+///
+/// # Safety
+///
+/// `netlist` must be a live handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_cell_name(netlist: *const SnNetlist, object: SnObject) -> *mut c_char {
+    string(|| {
+        let node = find_object(unsafe { read_netlist(netlist) }?, object)?;
+        node.get_instance_type()
+            .map(|g| g.get_gate_name().to_string())
+            .ok_or_else(|| format!("Circuit node #{object} is an input"))
+    })
+}
+
+/// Returns the name of `net`, or null on failure.
+///
+/// # Safety
+///
+/// `netlist` must be a live handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_net_name(netlist: *const SnNetlist, net: SnNet) -> *mut c_char {
+    string(|| {
+        let net = find_net(unsafe { read_netlist(netlist) }?, net)?;
+        Ok(net.get_identifier().to_string())
+    })
+}
+
+/// Writes the number of input ports of the circuit node `object` to `out`.
+///
+/// # Safety
+///
+/// `netlist` must be a live handle and `out` valid for writes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_num_inputs(
+    netlist: *const SnNetlist,
+    object: SnObject,
+    out: *mut usize,
+) -> i32 {
+    status(|| {
+        let node = find_object(unsafe { read_netlist(netlist) }?, object)?;
+        unsafe { write(out, node.get_num_input_ports()) }
+    })
+}
+
+/// Writes the net driving input `input` of the circuit node `object` to `out`. Fails if the input is unconnected.
+///
+/// # Safety
+///
+/// `netlist` must be a live handle and `out` valid for writes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_get_driver(
+    netlist: *const SnNetlist,
+    object: SnObject,
+    input: usize,
+    out: *mut SnNet,
+) -> i32 {
+    status(|| {
+        let node = find_object(unsafe { read_netlist(netlist) }?, object)?;
+        if input >= node.get_num_input_ports() {
+            return Err(format!("Circuit node #{object} has no input {input}"));
+        }
+        let driver = node
+            .get_input(input)
+            .get_driver()
+            .ok_or_else(|| format!("Input {input} of circuit node #{object} is unconnected"))?;
+        unsafe { write(out, driver.get_id().into()) }
+    })
+}
+
+/// Removes the circuit nodes that do not reach a top-level output, see [Netlist::clean].
+///
+/// # Safety
+///
+/// `netlist` must be a live handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_clean(netlist: *mut SnNetlist) -> i32 {
+    status(|| {
+        let netlist = unsafe { read_netlist(netlist) }?;
+        netlist.clean().map(|_| ()).map_err(|e| e.to_string())
+    })
+}
+
+/// Checks that the netlist has outputs, unique names and no unconnected inputs, see [Netlist::verify_with].
+///
+/// # Safety
+///
+/// `netlist` must be a live handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_verify(netlist: *const SnNetlist) -> i32 {
+    status(|| {
+        let netlist = unsafe { read_netlist(netlist) }?;
+        let options = VerifyOptions {
+            deny_unconnected_inputs: true,
+            ..VerifyOptions::default()
+        };
+        netlist.verify_with(&options).map_err(|e| e.to_string())
+    })
+}
+
+/// Returns the netlist as Verilog with the default [VerilogOptions], or null on failure.
+///
+/// # Safety
+///
+/// `netlist` must be a live handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sn_to_verilog(netlist: *const SnNetlist) -> *mut c_char {
+    string(|| {
+        let netlist = unsafe { read_netlist(netlist) }?;
+        Ok(netlist.to_verilog(&VerilogOptions::default()))
+    })
+}
@@ -4,7 +4,8 @@
 
 */
 
-use crate::{attribute::Parameter, logic::Logic};
+use crate::{attribute::Parameter, error::Error, logic::Logic};
+use bitvec::vec::BitVec;
 
 /// Signals in a circuit can be binary, tri-state, or four-state.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
@@ -62,56 +63,225 @@ pub struct Identifier {
     id_type: IdentifierType,
 }
 
+/// The reserved keywords of Verilog-2005, which can only be used as identifiers when escaped
+const VERILOG_KEYWORDS: &[&str] = &[
+    "always",
+    "and",
+    "assign",
+    "automatic",
+    "begin",
+    "buf",
+    "bufif0",
+    "bufif1",
+    "case",
+    "casex",
+    "casez",
+    "cell",
+    "cmos",
+    "config",
+    "deassign",
+    "default",
+    "defparam",
+    "design",
+    "disable",
+    "edge",
+    "else",
+    "end",
+    "endcase",
+    "endconfig",
+    "endfunction",
+    "endgenerate",
+    "endmodule",
+    "endprimitive",
+    "endspecify",
+    "endtable",
+    "endtask",
+    "event",
+    "for",
+    "force",
+    "forever",
+    "fork",
+    "function",
+    "generate",
+    "genvar",
+    "highz0",
+    "highz1",
+    "if",
+    "ifnone",
+    "incdir",
+    "include",
+    "initial",
+    "inout",
+    "input",
+    "instance",
+    "integer",
+    "join",
+    "large",
+    "liblist",
+    "library",
+    "localparam",
+    "macromodule",
+    "medium",
+    "module",
+    "nand",
+    "negedge",
+    "nmos",
+    "nor",
+    "noshowcancelled",
+    "not",
+    "notif0",
+    "notif1",
+    "or",
+    "output",
+    "parameter",
+    "pmos",
+    "posedge",
+    "primitive",
+    "pull0",
+    "pull1",
+    "pulldown",
+    "pullup",
+    "pulsestyle_ondetect",
+    "pulsestyle_onevent",
+    "rcmos",
+    "real",
+    "realtime",
+    "reg",
+    "release",
+    "repeat",
+    "rnmos",
+    "rpmos",
+    "rtran",
+    "rtranif0",
+    "rtranif1",
+    "scalared",
+    "showcancelled",
+    "signed",
+    "small",
+    "specify",
+    "specparam",
+    "strong0",
+    "strong1",
+    "supply0",
+    "supply1",
+    "table",
+    "task",
+    "time",
+    "tran",
+    "tranif0",
+    "tranif1",
+    "tri",
+    "tri0",
+    "tri1",
+    "triand",
+    "trior",
+    "trireg",
+    "unsigned",
+    "use",
+    "uwire",
+    "vectored",
+    "wait",
+    "wand",
+    "weak0",
+    "weak1",
+    "while",
+    "wire",
+    "wor",
+    "xnor",
+    "xor",
+];
+
+/// Returns `true` if `name` is a simple Verilog identifier: a letter or underscore,
+/// followed by letters, digits, underscores or dollar signs, that is not a keyword.
+fn is_simple_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !VERILOG_KEYWORDS.contains(&name)
+}
+
+/// Splits a bit-select `name[index]` into the name and the index.
+/// Only canonical decimal indices are accepted, so that the split is lossless.
+fn split_bit_select(name: &str) -> Option<(&str, usize)> {
+    let (base, index) = name.strip_suffix(']')?.split_once('[')?;
+    let parsed: usize = index.parse().ok()?;
+    (parsed.to_string() == index).then_some((base, parsed))
+}
+
 impl Identifier {
-    /// Creates a new identifier with the given name
+    /// Creates a new identifier with the given name.
+    /// A leading `\` marks an escaped identifier, whose name ends at the first whitespace (as in `\foo[3] `).
+    /// A name `x[i]` where `x` is a simple identifier is a bit-slice of the bus `x`.
+    /// Any other name that is not a simple identifier is escaped.
     pub fn new(name: String) -> Self {
         if name.is_empty() {
             panic!("Identifier name cannot be empty");
         }
 
         if let Some(root) = name.strip_prefix('\\') {
+            let root = root.split_whitespace().next().unwrap_or_default();
+            if root.is_empty() {
+                panic!("Escaped identifier name cannot be empty");
+            }
             return Identifier {
                 name: root.to_string(),
                 id_type: IdentifierType::Escaped,
             };
         }
 
-        // Check if first char is a digit
-        let esc_chars = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
-        if esc_chars.contains(&name.chars().next().unwrap()) {
+        if let Some((base, index)) = split_bit_select(&name)
+            && is_simple_identifier(base)
+        {
             return Identifier {
-                name,
-                id_type: IdentifierType::Escaped,
+                name: base.to_string(),
+                id_type: IdentifierType::BitSlice(index),
             };
         }
 
-        // Certainly not an exhaustive list.
-        // TODO(matth2k): Implement a true isEscaped()
-        let esc_chars = [' ', '\\', '(', ')', ',', '+', '-', '$', '\'', '~'];
-        if name.chars().any(|c| esc_chars.contains(&c)) {
-            return Identifier {
-                name,
+        // '$' is legal in simple identifiers, but is escaped to keep names apart from system tasks
+        let id_type = if is_simple_identifier(&name) && !name.contains('$') {
+            IdentifierType::Normal
+        } else {
+            IdentifierType::Escaped
+        };
+        Identifier { name, id_type }
+    }
+
+    /// Parses an identifier as it appears in Verilog source: a simple identifier, a bit-select `x[i]` of one,
+    /// or an escaped identifier `\name` terminated by whitespace or the end of the input.
+    /// Surrounding whitespace is ignored. Unlike [Identifier::new], malformed names are an error rather than escaped.
+    pub fn from_verilog(s: &str) -> Result<Self, Error> {
+        let err = || Error::ParseError(format!("Invalid Verilog identifier `{s}`"));
+        let trimmed = s.trim();
+        if let Some(root) = trimmed.strip_prefix('\\') {
+            if root.is_empty() || root.contains(char::is_whitespace) {
+                return Err(err());
+            }
+            return Ok(Identifier {
+                name: root.to_string(),
                 id_type: IdentifierType::Escaped,
-            };
+            });
         }
 
-        if name.contains('[') && name.ends_with(']') {
-            let name_ind = name.find('[').unwrap();
-            let rname = &name[..name_ind];
-            let index_start = name_ind + 1;
-            let slice = name[index_start..name.len() - 1].parse::<usize>();
-            if let Ok(s) = slice {
-                return Identifier {
-                    name: rname.to_string(),
-                    id_type: IdentifierType::BitSlice(s),
-                };
+        // Whitespace is allowed between the name and the bit-select
+        if let Some((base, index)) = trimmed.strip_suffix(']').and_then(|t| t.split_once('[')) {
+            let index = index.trim().parse().map_err(|_| err())?;
+            let base = base.trim_end();
+            if !is_simple_identifier(base) {
+                return Err(err());
             }
+            return Ok(Identifier {
+                name: base.to_string(),
+                id_type: IdentifierType::BitSlice(index),
+            });
         }
 
-        Identifier {
-            name,
-            id_type: IdentifierType::Normal,
+        if !is_simple_identifier(trimmed) {
+            return Err(err());
         }
+        Ok(Identifier::new(trimmed.to_string()))
     }
 
     /// Returns the name of the identifier
@@ -137,14 +307,23 @@ impl Identifier {
         matches!(self.id_type, IdentifierType::Escaped)
     }
 
-    /// Emit the name as suitable for an HDL like Verilog. This takes into account bit-slicing and escaped identifiers
-    pub fn emit_name(&self) -> String {
+    /// Returns the identifier as it is written in Verilog, including the space that terminates escaped identifiers.
+    /// Escaped identifiers cannot contain whitespace, so any whitespace in the name is emitted as `_`.
+    /// For every identifier, `Identifier::new(id.as_verilog())` is equal to `id`, unless the name contains whitespace.
+    pub fn as_verilog(&self) -> String {
         match &self.id_type {
             IdentifierType::Normal => self.name.clone(),
             IdentifierType::BitSlice(index) => format!("{}[{}]", self.name, index),
-            IdentifierType::Escaped => format!("\\{} ", self.name),
+            IdentifierType::Escaped => {
+                format!("\\{} ", self.name.replace(char::is_whitespace, "_"))
+            }
         }
     }
+
+    /// Emit the name as suitable for an HDL like Verilog. This takes into account bit-slicing and escaped identifiers
+    pub fn emit_name(&self) -> String {
+        self.as_verilog()
+    }
 }
 
 impl std::ops::Add for &Identifier {
@@ -162,12 +341,13 @@ impl std::ops::Add for &Identifier {
             (IdentifierType::Normal, IdentifierType::Normal) => IdentifierType::Normal,
         };
 
+        // Bit-selects are kept as part of the escaped name, so that the original names remain recognizable
         let new_name = match (self.id_type, rhs.id_type) {
             (IdentifierType::BitSlice(l), IdentifierType::BitSlice(r)) => {
-                format!("{}_{}_{}_{}", lname, l, rname, r)
+                format!("{}[{}]_{}[{}]", lname, l, rname, r)
             }
-            (IdentifierType::BitSlice(l), _) => format!("{}_{}_{}", lname, l, rname),
-            (_, IdentifierType::BitSlice(r)) => format!("{}_{}_{}", lname, rname, r),
+            (IdentifierType::BitSlice(l), _) => format!("{}[{}]_{}", lname, l, rname),
+            (_, IdentifierType::BitSlice(r)) => format!("{}_{}[{}]", lname, rname, r),
             _ => format!("{}_{}", lname, rname),
         };
 
@@ -200,12 +380,141 @@ impl From<String> for Identifier {
 
 impl std::fmt::Display for Identifier {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.id_type {
-            IdentifierType::Normal => write!(f, "{}", self.name),
-            IdentifierType::BitSlice(index) => write!(f, "{}[{}]", self.name, index),
-            IdentifierType::Escaped => write!(f, "\\{} ", self.name),
+        write!(f, "{}", self.as_verilog())
+    }
+}
+
+/// A hierarchical path to a net or instance, outermost component first, as in `top.u_core.g3`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HierPath {
+    components: Vec<Identifier>,
+}
+
+impl HierPath {
+    /// Creates a path from its components, outermost first
+    pub fn new(components: impl IntoIterator<Item = Identifier>) -> Self {
+        Self {
+            components: components.into_iter().collect(),
         }
     }
+
+    /// Splits a flattened name like `u_core/u_alu/g3` on `sep`.
+    /// A bit-select stays on the last component. Returns an error if a component is empty.
+    pub fn split(id: &Identifier, sep: char) -> Result<Self, Error> {
+        let name = match id.get_bit_index() {
+            Some(index) => format!("{}[{}]", id.get_name(), index),
+            None => id.get_name().to_string(),
+        };
+        name.split(sep)
+            .map(|c| {
+                if c.is_empty() {
+                    Err(Error::ParseError(format!(
+                        "Empty component in hierarchical name `{name}`"
+                    )))
+                } else {
+                    Ok(Identifier::new(c.to_string()))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self::new)
+    }
+
+    /// Joins the components with `sep` into a single flattened name, or `None` if the path is empty.
+    /// This is the inverse of [HierPath::split].
+    pub fn join(&self, sep: char) -> Option<Identifier> {
+        let names: Vec<String> = self
+            .components
+            .iter()
+            .map(|c| match c.get_bit_index() {
+                Some(index) => format!("{}[{}]", c.get_name(), index),
+                None => c.get_name().to_string(),
+            })
+            .collect();
+        (!names.is_empty()).then(|| Identifier::new(names.join(&sep.to_string())))
+    }
+
+    /// Returns the components of the path, outermost first
+    pub fn components(&self) -> &[Identifier] {
+        &self.components
+    }
+
+    /// Returns the number of components in the path
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Returns `true` if the path has no components
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Returns the innermost component of the path
+    pub fn leaf(&self) -> Option<&Identifier> {
+        self.components.last()
+    }
+
+    /// Returns the path without its innermost component, or `None` if the path is empty
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.components.split_last()?;
+        Some(Self::new(parent.iter().cloned()))
+    }
+
+    /// Appends a component to the path
+    pub fn push(&mut self, component: Identifier) {
+        self.components.push(component);
+    }
+
+    /// Returns `true` if `prefix` is a leading part of the path
+    pub fn starts_with(&self, prefix: &HierPath) -> bool {
+        self.components.starts_with(&prefix.components)
+    }
+
+    /// Returns the path relative to `prefix`, or `None` if the path does not start with `prefix`
+    pub fn strip_prefix(&self, prefix: &HierPath) -> Option<Self> {
+        let rest = self.components.strip_prefix(prefix.components.as_slice())?;
+        Some(Self::new(rest.iter().cloned()))
+    }
+}
+
+impl std::fmt::Display for HierPath {
+    /// Formats the path the way simulators name hierarchical references, as in `top.u_core.\a/b .q`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let components: Vec<String> = self.components.iter().map(|c| c.as_verilog()).collect();
+        write!(f, "{}", components.join("."))
+    }
+}
+
+impl std::str::FromStr for HierPath {
+    type Err = Error;
+
+    /// Parses a `.`-separated hierarchical reference, where escaped components end at whitespace
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || Error::ParseError(format!("Invalid hierarchical path `{s}`"));
+        let mut components = Vec::new();
+        let mut rest = s.trim();
+        while !rest.is_empty() {
+            let end = if rest.starts_with('\\') {
+                rest.find(char::is_whitespace).unwrap_or(rest.len())
+            } else {
+                rest.find('.').unwrap_or(rest.len())
+            };
+            components.push(Identifier::from_verilog(&rest[..end])?);
+            rest = rest[end..].trim_start();
+            if let Some(next) = rest.strip_prefix('.') {
+                rest = next.trim_start();
+                if rest.is_empty() {
+                    return Err(err());
+                }
+            } else if !rest.is_empty() {
+                return Err(err());
+            }
+        }
+        if components.is_empty() {
+            return Err(err());
+        }
+        Ok(Self { components })
+    }
 }
 
 /// A net in a circuit, which is identified with a name and data type.
@@ -314,6 +623,12 @@ pub trait Instantiable: Clone {
     /// Returns an iterator over the parameters of the primitive.
     fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)>;
 
+    /// Returns the value the parameter takes when it is not overridden, if it is known.
+    /// Parameters set to their default value may be omitted from the emitted Verilog.
+    fn get_default_parameter(&self, _id: &Identifier) -> Option<Parameter> {
+        None
+    }
+
     /// Creates the primitive used to represent a constant value, like VDD or GND.
     /// If the implementer does not support the specific constant, `None` is returned.
     fn from_constant(val: Logic) -> Option<Self>;
@@ -321,9 +636,29 @@ pub trait Instantiable: Clone {
     /// Returns the constant value represented by this primitive, if it is constant.
     fn get_constant(&self) -> Option<Logic>;
 
+    /// Returns the value of the single output of the primitive when it is `0` or `1` whatever the unknown inputs,
+    /// which are [None], like `0` for an AND gate with a `0` input. Constant propagation uses it to fold any cell type.
+    /// The default never folds.
+    fn fold(&self, _inputs: &[Option<Logic>]) -> Option<Logic> {
+        None
+    }
+
     /// Returns 'true' if the primitive is sequential.
     fn is_seq(&self) -> bool;
 
+    /// Returns `true` if the function of the primitive is unknown, like an IP block.
+    /// The outputs of black boxes are the free variables of simulation, like those of sequential primitives.
+    fn is_blackbox(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if the primitive is a level-sensitive latch.
+    /// Latches may close loops of otherwise combinational logic, so cycle checks, simulation and timing
+    /// treat their outputs as state, like those of sequential primitives.
+    fn is_latch(&self) -> bool {
+        false
+    }
+
     /// Returns `true` if the primitive is parameterized (has at least one parameter).
     fn is_parameterized(&self) -> bool {
         self.parameters().next().is_some()
@@ -377,6 +712,12 @@ pub trait Instantiable: Clone {
             .position(|n| n.get_identifier() == id)
     }
 
+    /// Returns `true` if the output port at `index` can be driven to high-impedance, so that it may share a net with other tri-state drivers.
+    /// By default, this is the case for output ports of type [DataType::ThreeState].
+    fn is_tristate_output(&self, index: usize) -> bool {
+        *self.get_output_port(index).get_type() == DataType::ThreeState
+    }
+
     /// Returns `true` if the primitive has no input ports. In most cases, this means the cell represents a constant.
     /// **This method should be overriden if the implemenation of `get_input_ports()` is expensive.**
     fn is_driverless(&self) -> bool {
@@ -384,6 +725,82 @@ pub trait Instantiable: Clone {
     }
 }
 
+/// A view of the cells of type `T` within an [Instantiable], like a variant of an enum of cell types.
+/// Every type is a view of itself, and `#[derive(Instantiable)]` with `#[instantiable(accessors)]` implements it for each variant.
+pub trait AsCell<T> {
+    /// Returns the cell as a `T`, if it is one
+    fn as_cell(&self) -> Option<&T>;
+}
+
+impl<T> AsCell<T> for T {
+    fn as_cell(&self) -> Option<&T> {
+        Some(self)
+    }
+}
+
+/// A trait for primitives with a known function, so that they can be simulated.
+pub trait Evaluate: Instantiable {
+    /// Returns the values of the output ports given the values of the input ports.
+    /// Sequential primitives return the values their outputs take after the next clock edge.
+    fn eval(&self, inputs: &[Logic]) -> Vec<Logic>;
+
+    /// Evaluates 64 two-state input patterns at once, where bit `i` of each word belongs to pattern `i`.
+    /// Output values that are not [Logic::True] are read as zero.
+    /// **This method should be overriden if the implemenation is capable of bitwise evaluation.**
+    fn eval_words(&self, inputs: &[u64]) -> Vec<u64> {
+        let mut outputs = vec![0; self.get_output_ports().into_iter().count()];
+        let mut bits = vec![Logic::False; inputs.len()];
+        for lane in 0..64 {
+            for (bit, word) in bits.iter_mut().zip(inputs) {
+                *bit = Logic::from_bool((word >> lane) & 1 == 1);
+            }
+            for (word, val) in outputs.iter_mut().zip(self.eval(&bits)) {
+                if val == Logic::True {
+                    *word |= 1 << lane;
+                }
+            }
+        }
+        outputs
+    }
+}
+
+/// Looks up the value of a truth table, where input `i` is bit `i` of the index into `table`, like the `INIT` parameter of a LUT.
+/// Inputs that are not `0` or `1` may take either value, so the result is [Logic::X] unless all the entries they select agree.
+/// Entries past the end of `table` are zero.
+pub fn eval_table(table: &BitVec, inputs: &[Logic]) -> Logic {
+    let mut value = None;
+    for index in 0..1usize << inputs.len() {
+        let selected = inputs.iter().enumerate().all(|(i, v)| match v {
+            Logic::True => (index >> i) & 1 == 1,
+            Logic::False => (index >> i) & 1 == 0,
+            _ => true,
+        });
+        if !selected {
+            continue;
+        }
+        let bit = table.get(index).is_some_and(|b| *b);
+        match value {
+            None => value = Some(bit),
+            Some(v) if v != bit => return Logic::X,
+            Some(_) => (),
+        }
+    }
+    Logic::from_bool(value.unwrap_or(false))
+}
+
+/// Looks up the values of a truth table for 64 patterns at once, with the input words of [Evaluate::eval_words]
+pub fn eval_table_words(table: &BitVec, inputs: &[u64]) -> u64 {
+    table
+        .iter_ones()
+        .filter(|index| index >> inputs.len() == 0)
+        .fold(0, |acc, index| {
+            let minterm = inputs.iter().enumerate().fold(u64::MAX, |m, (i, w)| {
+                if (index >> i) & 1 == 1 { m & w } else { m & !w }
+            });
+            acc | minterm
+        })
+}
+
 /// A tagged union for objects in a digital circuit, which can be either an input net or an instance of a module or primitive.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -481,17 +898,20 @@ mod tests {
 
     #[test]
     fn identifier_parsing() {
-        let id = Identifier::new("wire".to_string());
+        let id = Identifier::new("data".to_string());
         assert!(!id.is_escaped());
         assert!(!id.is_sliced());
         assert!(id.get_bit_index().is_none());
-        let id = Identifier::new("\\wire".to_string());
+        let id = Identifier::new("\\data".to_string());
         assert!(id.is_escaped());
         assert!(!id.is_sliced());
-        let id = Identifier::new("wire[3]".to_string());
+        let id = Identifier::new("data[3]".to_string());
         assert!(!id.is_escaped());
         assert!(id.is_sliced());
         assert_eq!(id.get_bit_index(), Some(3));
+        // Keywords can only be used escaped
+        assert!(Identifier::new("wire".to_string()).is_escaped());
+        assert!(Identifier::new("wire[3]".to_string()).is_escaped());
     }
 
     #[test]
@@ -502,15 +922,15 @@ mod tests {
 
     #[test]
     fn identifier_emission() {
-        let id = Identifier::new("wire".to_string());
-        assert_eq!(id.emit_name(), "wire");
-        let id = Identifier::new("\\wire".to_string());
+        let id = Identifier::new("data".to_string());
+        assert_eq!(id.emit_name(), "data");
+        let id = Identifier::new("\\data".to_string());
         assert!(id.is_escaped());
-        assert_eq!(id.emit_name(), "\\wire ");
-        assert_eq!(format!("{id}"), "\\wire ");
-        let id = Identifier::new("wire[3]".to_string());
+        assert_eq!(id.emit_name(), "\\data ");
+        assert_eq!(format!("{id}"), "\\data ");
+        let id = Identifier::new("data[3]".to_string());
         assert!(id.is_sliced());
-        assert_eq!(id.emit_name(), "wire[3]");
+        assert_eq!(id.emit_name(), "data[3]");
     }
 
     #[test]
@@ -0,0 +1,485 @@
+/*!
+
+  A subset of SDC/XDC timing constraints.
+
+*/
+
+use crate::{error::Error, util::glob_match};
+use std::str::FromStr;
+
+/// A design object named by a constraint. Names may contain `*` and `?` wildcards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// A top-level port, from `[get_ports ...]`
+    Port(String),
+    /// A cell input or output, as `instance/pin`, from `[get_pins ...]`
+    Pin(String),
+    /// An instance, from `[get_cells ...]`
+    Cell(String),
+    /// A net, from `[get_nets ...]`
+    Net(String),
+    /// A clock, from `[get_clocks ...]`
+    Clock(String),
+    /// A bare name, which may refer to any kind of object
+    Any(String),
+}
+
+impl Target {
+    /// Returns the name pattern of the target
+    pub fn pattern(&self) -> &str {
+        match self {
+            Target::Port(p)
+            | Target::Pin(p)
+            | Target::Cell(p)
+            | Target::Net(p)
+            | Target::Clock(p)
+            | Target::Any(p) => p,
+        }
+    }
+
+    /// Returns `true` if the target refers to the top-level port `name`
+    pub fn matches_port(&self, name: &str) -> bool {
+        matches!(self, Target::Port(_) | Target::Any(_)) && glob_match(self.pattern(), name)
+    }
+
+    /// Returns `true` if the target refers to the instance `name`
+    pub fn matches_cell(&self, name: &str) -> bool {
+        matches!(self, Target::Cell(_) | Target::Any(_)) && glob_match(self.pattern(), name)
+    }
+
+    /// Returns `true` if the target refers to the `pin` of instance `cell`
+    pub fn matches_pin(&self, cell: &str, pin: &str) -> bool {
+        matches!(self, Target::Pin(_) | Target::Any(_))
+            && glob_match(self.pattern(), &format!("{cell}/{pin}"))
+    }
+
+    /// Returns `true` if the target refers to the net `name`
+    pub fn matches_net(&self, name: &str) -> bool {
+        matches!(self, Target::Net(_) | Target::Any(_)) && glob_match(self.pattern(), name)
+    }
+
+    /// Returns `true` if the target refers to the clock `name`
+    pub fn matches_clock(&self, name: &str) -> bool {
+        matches!(self, Target::Clock(_) | Target::Any(_)) && glob_match(self.pattern(), name)
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Target::Port(p) => write!(f, "[get_ports {{{p}}}]"),
+            Target::Pin(p) => write!(f, "[get_pins {{{p}}}]"),
+            Target::Cell(p) => write!(f, "[get_cells {{{p}}}]"),
+            Target::Net(p) => write!(f, "[get_nets {{{p}}}]"),
+            Target::Clock(p) => write!(f, "[get_clocks {{{p}}}]"),
+            Target::Any(p) => write!(f, "{{{p}}}"),
+        }
+    }
+}
+
+/// A clock from `create_clock`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clock {
+    /// The name of the clock, which defaults to the name of its first source
+    pub name: String,
+    /// The clock period
+    pub period: f64,
+    /// The times of the rising and falling edges within the period
+    pub waveform: (f64, f64),
+    /// The ports that carry the clock
+    pub sources: Vec<Target>,
+}
+
+/// An external delay from `set_input_delay` or `set_output_delay`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortDelay {
+    /// The clock the delay is relative to
+    pub clock: Option<String>,
+    /// The delay outside of the module
+    pub delay: f64,
+    /// The ports the delay applies to
+    pub ports: Vec<Target>,
+}
+
+/// A timing exception from `set_false_path`. Empty lists match any object.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FalsePath {
+    /// The startpoints of the paths
+    pub from: Vec<Target>,
+    /// The objects the paths go through
+    pub through: Vec<Target>,
+    /// The endpoints of the paths
+    pub to: Vec<Target>,
+}
+
+/// A timing exception from `set_multicycle_path`, which gives the paths `multiplier` clock periods to arrive
+/// instead of one. Empty lists match any object.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MulticyclePath {
+    /// The number of clock periods of the paths
+    pub multiplier: usize,
+    /// The startpoints of the paths
+    pub from: Vec<Target>,
+    /// The objects the paths go through
+    pub through: Vec<Target>,
+    /// The endpoints of the paths
+    pub to: Vec<Target>,
+}
+
+/// The timing constraints of a module, as parsed from the `create_clock`, `set_input_delay`, `set_output_delay`,
+/// `set_false_path` and `set_multicycle_path` commands of an SDC or XDC file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Constraints {
+    /// The clocks
+    pub clocks: Vec<Clock>,
+    /// The delays before the inputs
+    pub input_delays: Vec<PortDelay>,
+    /// The delays after the outputs
+    pub output_delays: Vec<PortDelay>,
+    /// The false paths
+    pub false_paths: Vec<FalsePath>,
+    /// The multicycle paths, for setup analysis
+    pub multicycle_paths: Vec<MulticyclePath>,
+    /// The names of the unsupported commands that were skipped
+    pub ignored: Vec<String>,
+}
+
+impl Constraints {
+    /// Creates constraints with a single clock of `period` and no sources
+    pub fn with_period(period: f64) -> Self {
+        Self {
+            clocks: vec![Clock {
+                name: "clk".to_string(),
+                period,
+                waveform: (0.0, period / 2.0),
+                sources: Vec::new(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// Returns the shortest clock period, which is infinite if there are no clocks
+    pub fn get_period(&self) -> f64 {
+        self.clocks
+            .iter()
+            .map(|c| c.period)
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Returns `true` if the top-level port `name` carries a clock
+    pub fn is_clock_port(&self, name: &str) -> bool {
+        self.clocks
+            .iter()
+            .any(|c| c.sources.iter().any(|t| t.matches_port(name)))
+    }
+
+    /// Returns the largest input delay of the port `name`, if it has any
+    pub fn input_delay(&self, name: &str) -> Option<f64> {
+        Self::port_delay(&self.input_delays, name)
+    }
+
+    /// Returns the largest output delay of the port `name`, if it has any
+    pub fn output_delay(&self, name: &str) -> Option<f64> {
+        Self::port_delay(&self.output_delays, name)
+    }
+
+    fn port_delay(delays: &[PortDelay], name: &str) -> Option<f64> {
+        delays
+            .iter()
+            .filter(|d| d.ports.iter().any(|t| t.matches_port(name)))
+            .map(|d| d.delay)
+            .reduce(f64::max)
+    }
+}
+
+/// A word of a Tcl command
+enum Word {
+    /// A bare or quoted word
+    Text(String),
+    /// A list in braces
+    List(String),
+    /// A command substitution in brackets
+    Command(String),
+}
+
+/// Splits a constraint file into commands, dropping comments and joining continued lines
+fn commands(s: &str) -> Result<Vec<Vec<Word>>, Error> {
+    let s = s.replace("\\\r\n", " ").replace("\\\n", " ");
+    let mut commands = Vec::new();
+    for line in s.lines() {
+        let mut words = Vec::new();
+        let mut chars = line.trim().chars().peekable();
+        while let Some(&c) = chars.peek() {
+            match c {
+                ' ' | '\t' => {
+                    chars.next();
+                }
+                ';' => {
+                    chars.next();
+                    if !words.is_empty() {
+                        commands.push(std::mem::take(&mut words));
+                    }
+                }
+                '#' if words.is_empty() => break,
+                '{' | '[' => {
+                    let close = if c == '{' { '}' } else { ']' };
+                    chars.next();
+                    let mut depth = 1;
+                    let mut text = String::new();
+                    for ch in chars.by_ref() {
+                        if ch == c {
+                            depth += 1;
+                        } else if ch == close {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        text.push(ch);
+                    }
+                    if depth != 0 {
+                        return Err(Error::ParseError(line.to_string()));
+                    }
+                    words.push(if c == '{' {
+                        Word::List(text)
+                    } else {
+                        Word::Command(text)
+                    });
+                }
+                '"' => {
+                    chars.next();
+                    let text: String = chars.by_ref().take_while(|c| *c != '"').collect();
+                    words.push(Word::Text(text));
+                }
+                _ => {
+                    let mut text = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || c == ';' {
+                            break;
+                        }
+                        text.push(c);
+                        chars.next();
+                    }
+                    words.push(Word::Text(text));
+                }
+            }
+        }
+        if !words.is_empty() {
+            commands.push(words);
+        }
+    }
+    Ok(commands)
+}
+
+impl Word {
+    /// Returns the word as a single string, if it is not a list of objects
+    fn text(&self) -> Result<&str, Error> {
+        match self {
+            Word::Text(t) | Word::List(t) => Ok(t),
+            Word::Command(c) => Err(Error::ParseError(format!("[{c}]"))),
+        }
+    }
+
+    /// Returns the design objects the word refers to
+    fn targets(&self) -> Result<Vec<Target>, Error> {
+        match self {
+            Word::Text(t) => Ok(vec![Target::Any(t.clone())]),
+            Word::List(l) => Ok(l
+                .split_whitespace()
+                .map(|t| Target::Any(t.to_string()))
+                .collect()),
+            Word::Command(c) => {
+                let words = commands(c)?.into_iter().next().unwrap_or_default();
+                let Some((query, args)) = words.split_first() else {
+                    return Err(Error::ParseError("[]".to_string()));
+                };
+                let kind: fn(String) -> Target = match query.text()? {
+                    "get_ports" => Target::Port,
+                    "get_pins" => Target::Pin,
+                    "get_cells" => Target::Cell,
+                    "get_nets" => Target::Net,
+                    "get_clocks" => Target::Clock,
+                    "all_inputs" | "all_outputs" => return Ok(vec![Target::Port("*".to_string())]),
+                    "all_clocks" => return Ok(vec![Target::Clock("*".to_string())]),
+                    _ => return Err(Error::ParseError(format!("[{c}]"))),
+                };
+                let mut targets = Vec::new();
+                for arg in args {
+                    let text = arg.text()?;
+                    if !text.starts_with('-') {
+                        targets.extend(text.split_whitespace().map(|t| kind(t.to_string())));
+                    }
+                }
+                Ok(targets)
+            }
+        }
+    }
+}
+
+/// Parses a number
+fn number(word: Option<&Word>) -> Result<f64, Error> {
+    let text = word
+        .ok_or_else(|| Error::ParseError("missing value".to_string()))?
+        .text()?;
+    text.parse::<f64>()
+        .map_err(|_| Error::ParseError(text.to_string()))
+}
+
+/// The options of a command, with their values
+type Options<'a> = Vec<(&'a str, Option<&'a Word>)>;
+
+/// Parses the options and positional arguments of a command, where `valued` lists the options that take a value
+fn options<'a>(args: &'a [Word], valued: &[&str]) -> Result<(Options<'a>, Vec<&'a Word>), Error> {
+    let mut options = Vec::new();
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg {
+            Word::Text(t) if t.starts_with('-') && t.parse::<f64>().is_err() => {
+                let value = if valued.contains(&t.as_str()) {
+                    Some(
+                        args.next()
+                            .ok_or_else(|| Error::ParseError(format!("{t} needs a value")))?,
+                    )
+                } else {
+                    None
+                };
+                options.push((t.as_str(), value));
+            }
+            _ => positional.push(arg),
+        }
+    }
+    Ok((options, positional))
+}
+
+/// Collects the targets of the positional arguments
+fn targets(positional: &[&Word]) -> Result<Vec<Target>, Error> {
+    let mut targets = Vec::new();
+    for word in positional {
+        targets.extend(word.targets()?);
+    }
+    Ok(targets)
+}
+
+impl FromStr for Constraints {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut constraints = Constraints::default();
+        for command in commands(s)? {
+            let (name, args) = command.split_first().unwrap();
+            let name = name.text()?;
+            match name {
+                "create_clock" => {
+                    let (options, positional) = options(args, &["-period", "-name", "-waveform"])?;
+                    let mut clock_name = None;
+                    let mut period = None;
+                    let mut waveform = None;
+                    for (option, value) in options {
+                        match option {
+                            "-period" => period = Some(number(value)?),
+                            "-name" => clock_name = Some(value.unwrap().text()?.to_string()),
+                            "-waveform" => {
+                                let edges: Vec<f64> = value
+                                    .unwrap()
+                                    .text()?
+                                    .split_whitespace()
+                                    .map(|e| e.parse::<f64>())
+                                    .collect::<Result<_, _>>()
+                                    .map_err(|_| Error::ParseError("-waveform".to_string()))?;
+                                match edges[..] {
+                                    [rise, fall] => waveform = Some((rise, fall)),
+                                    _ => return Err(Error::ParseError("-waveform".to_string())),
+                                }
+                            }
+                            _ => (),
+                        }
+                    }
+                    let period = period
+                        .ok_or_else(|| Error::ParseError("create_clock needs -period".into()))?;
+                    let sources = targets(&positional)?;
+                    let name = clock_name
+                        .or_else(|| sources.first().map(|s| s.pattern().to_string()))
+                        .ok_or_else(|| Error::ParseError("create_clock needs -name".into()))?;
+                    constraints.clocks.push(Clock {
+                        name,
+                        period,
+                        waveform: waveform.unwrap_or((0.0, period / 2.0)),
+                        sources,
+                    });
+                }
+                "set_input_delay" | "set_output_delay" => {
+                    let (options, positional) = options(args, &["-clock", "-reference_pin"])?;
+                    // Minimum delays only matter for hold analysis
+                    if options.iter().any(|(o, _)| *o == "-min")
+                        && !options.iter().any(|(o, _)| *o == "-max")
+                    {
+                        continue;
+                    }
+                    let clock = match options.iter().find(|(o, _)| *o == "-clock") {
+                        Some((_, value)) => Some(match value.unwrap().targets()?.first() {
+                            Some(t) => t.pattern().to_string(),
+                            None => return Err(Error::ParseError("-clock".to_string())),
+                        }),
+                        None => None,
+                    };
+                    let (delay, ports) = positional
+                        .split_first()
+                        .ok_or_else(|| Error::ParseError(format!("{name} needs a delay")))?;
+                    let delay = PortDelay {
+                        clock,
+                        delay: number(Some(delay))?,
+                        ports: targets(ports)?,
+                    };
+                    if name == "set_input_delay" {
+                        constraints.input_delays.push(delay);
+                    } else {
+                        constraints.output_delays.push(delay);
+                    }
+                }
+                "set_false_path" => {
+                    let (options, _) = options(args, &["-from", "-to", "-through"])?;
+                    let mut path = FalsePath::default();
+                    for (option, value) in options {
+                        match option {
+                            "-from" => path.from.extend(value.unwrap().targets()?),
+                            "-through" => path.through.extend(value.unwrap().targets()?),
+                            "-to" => path.to.extend(value.unwrap().targets()?),
+                            _ => (),
+                        }
+                    }
+                    constraints.false_paths.push(path);
+                }
+                "set_multicycle_path" => {
+                    let (options, positional) = options(args, &["-from", "-to", "-through"])?;
+                    // Hold multipliers only matter for hold analysis
+                    if options.iter().any(|(o, _)| *o == "-hold") {
+                        continue;
+                    }
+                    let multiplier = match positional[..] {
+                        [Word::Text(n)] => n.parse::<usize>().ok().filter(|n| *n > 0),
+                        _ => None,
+                    }
+                    .ok_or_else(|| {
+                        Error::ParseError("set_multicycle_path needs a multiplier".into())
+                    })?;
+                    let mut path = MulticyclePath {
+                        multiplier,
+                        ..Default::default()
+                    };
+                    for (option, value) in options {
+                        match option {
+                            "-from" => path.from.extend(value.unwrap().targets()?),
+                            "-through" => path.through.extend(value.unwrap().targets()?),
+                            "-to" => path.to.extend(value.unwrap().targets()?),
+                            _ => (),
+                        }
+                    }
+                    constraints.multicycle_paths.push(path);
+                }
+                _ => constraints.ignored.push(name.to_string()),
+            }
+        }
+        Ok(constraints)
+    }
+}
@@ -6,7 +6,10 @@
 
 use thiserror::Error;
 
-use crate::circuit::{Identifier, Net};
+use crate::{
+    circuit::{Identifier, Net},
+    netlist::annotation::ObjectId,
+};
 
 /// Errors for the `safety-net` library.
 #[derive(Error, Debug)]
@@ -41,4 +44,243 @@ pub enum Error {
     /// A net that was expected but not found
     #[error("Expected to find net {0} in netlist")]
     NetNotFound(Net),
+    /// Input ports of instances that are not connected to a driver
+    #[error("Unconnected input ports {0:?}")]
+    UnconnectedInputs(Vec<(Identifier, Net)>),
+    /// Output ports of a module that are not driven
+    #[error("Undriven output ports {0:?}")]
+    UndrivenOutputs(Vec<Net>),
+    /// The nets of instances whose outputs are neither read nor module outputs
+    #[error("Dangling nets {0:?}")]
+    DanglingNets(Vec<Net>),
+    /// Outputs that are not functionally equivalent between two netlists
+    #[error("Outputs {0:?} are not equivalent")]
+    NonequivalentOutputs(Vec<Net>),
+    /// Nets whose drivers contend with opposing values
+    #[error("Conflicting drivers on nets {0:?}")]
+    BusConflict(Vec<Net>),
+    /// An edit would modify nodes protected by a `dont_touch` attribute
+    #[error("Attempted to modify dont_touch nets {0:?}")]
+    DontTouch(Vec<Net>),
+    /// A name that does not refer to a port of the module
+    #[error("Expected to find port {0} in module")]
+    PortNotFound(Identifier),
+    /// A port that is connected more than once
+    #[error("Port {0} is connected more than once")]
+    DuplicateConnection(Identifier),
+    /// A name that does not refer to a module of the design
+    #[error("Expected to find module {0} in design")]
+    ModuleNotFound(Identifier),
+    /// Two modules of a design with the same name
+    #[error("Module {0} is defined more than once")]
+    DuplicateModule(Identifier),
+    /// Modules that instantiate themselves through their submodules
+    #[error("Modules {0:?} instantiate themselves")]
+    RecursiveModules(Vec<Identifier>),
+    /// A design without a top module
+    #[error("The design has no top module")]
+    NoTopModule,
+    /// An error reading or writing data
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+    /// Downloaded or cached data whose SHA-256 checksum is not the expected one
+    #[error("Checksum mismatch for {name}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        /// What the data is, like the name of a file
+        name: String,
+        /// The expected checksum, in hexadecimal
+        expected: String,
+        /// The checksum of the data, in hexadecimal
+        actual: String,
+    },
+    /// A feature that is not enabled in this build
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+    /// An error with an explanation of where it happened and the circuit nodes it is about
+    #[error("{context}")]
+    Context {
+        /// What was being done or checked when the error happened
+        context: String,
+        /// The circuit nodes the error is about
+        objects: Vec<ObjectId>,
+        /// The underlying error
+        #[source]
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Returns a stable, machine-readable code for the kind of error.
+    /// The code of an [Error::Context] is the code of the underlying error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::CycleDetected(_) => "cycle-detected",
+            Error::ParseError(_) => "parse-error",
+            Error::NonuniqueNets(_) => "nonunique-nets",
+            Error::NonuniqueInsts(_) => "nonunique-insts",
+            Error::NoOutputs => "no-outputs",
+            Error::InstantiableError(_) => "instantiable",
+            Error::DanglingReference(_) => "dangling-reference",
+            Error::ArgumentMismatch(_, _) => "argument-mismatch",
+            Error::InputNeedsAlias(_) => "input-needs-alias",
+            Error::NetNotFound(_) => "net-not-found",
+            Error::UnconnectedInputs(_) => "unconnected-inputs",
+            Error::UndrivenOutputs(_) => "undriven-outputs",
+            Error::DanglingNets(_) => "dangling-nets",
+            Error::NonequivalentOutputs(_) => "nonequivalent-outputs",
+            Error::BusConflict(_) => "bus-conflict",
+            Error::DontTouch(_) => "dont-touch",
+            Error::PortNotFound(_) => "port-not-found",
+            Error::DuplicateConnection(_) => "duplicate-connection",
+            Error::ModuleNotFound(_) => "module-not-found",
+            Error::DuplicateModule(_) => "duplicate-module",
+            Error::RecursiveModules(_) => "recursive-modules",
+            Error::NoTopModule => "no-top-module",
+            Error::IoError(_) => "io",
+            Error::ChecksumMismatch { .. } => "checksum-mismatch",
+            Error::Unsupported(_) => "unsupported",
+            Error::Context { source, .. } => source.code(),
+        }
+    }
+
+    /// Wraps the error in an [Error::Context] explaining where it happened and which circuit nodes it is about
+    pub fn context(
+        self,
+        context: impl Into<String>,
+        objects: impl IntoIterator<Item = ObjectId>,
+    ) -> Self {
+        Error::Context {
+            context: context.into(),
+            objects: objects.into_iter().collect(),
+            source: Box::new(self),
+        }
+    }
+
+    /// Returns the circuit nodes the error is about, from every [Error::Context] around it
+    pub fn objects(&self) -> Vec<ObjectId> {
+        let mut objects = Vec::new();
+        let mut error = self;
+        while let Error::Context {
+            objects: these,
+            source,
+            ..
+        } = error
+        {
+            objects.extend(these);
+            error = source;
+        }
+        objects
+    }
+
+    /// Returns the underlying error, without any [Error::Context] around it
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source.root(),
+            e => e,
+        }
+    }
+
+    /// Consumes the error and returns the underlying error, without any [Error::Context] around it
+    pub fn into_root(self) -> Error {
+        match self {
+            Error::Context { source, .. } => source.into_root(),
+            e => e,
+        }
+    }
+}
+
+/// A collection of errors, so that checks can report every problem they find instead of stopping at the first
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    errors: Vec<Error>,
+}
+
+impl Diagnostics {
+    /// Creates an empty collection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `error` to the collection
+    pub fn push(&mut self, error: Error) {
+        self.errors.push(error);
+    }
+
+    /// Adds the error of `result`, if it is one
+    pub fn check(&mut self, result: Result<(), Error>) {
+        if let Err(e) = result {
+            self.push(e);
+        }
+    }
+
+    /// Returns `true` if there are no errors
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the number of errors
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns an iterator over the errors, in the order they were found
+    pub fn iter(&self) -> std::slice::Iter<'_, Error> {
+        self.errors.iter()
+    }
+
+    /// Returns the first error found, if there is one, for callers that fail on the first problem
+    pub fn into_result(self) -> Result<(), Error> {
+        match self.errors.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Error;
+    type IntoIter = std::vec::IntoIter<Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
+}
+
+impl FromIterator<Error> for Diagnostics {
+    fn from_iter<T: IntoIterator<Item = Error>>(iter: T) -> Self {
+        Self {
+            errors: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<Error> for Diagnostics {
+    fn extend<T: IntoIterator<Item = Error>>(&mut self, iter: T) {
+        self.errors.extend(iter);
+    }
+}
+
+/// Writes each error on its own line as `error[code]: `, followed by the messages of its source chain
+impl std::fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for error in &self.errors {
+            write!(f, "error[{}]: {error}", error.code())?;
+            let mut source = std::error::Error::source(error);
+            while let Some(e) = source {
+                write!(f, ": {e}")?;
+                source = e.source();
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
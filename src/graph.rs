@@ -4,7 +4,7 @@
 
 */
 
-use crate::circuit::{Instantiable, Net};
+use crate::circuit::{Identifier, Instantiable, Net};
 use crate::error::Error;
 #[cfg(feature = "graph")]
 use crate::netlist::Connection;
@@ -182,6 +182,518 @@ where
     }
 }
 
+/// Orders the circuit nodes so that every combinational node comes after all of its drivers.
+/// The outputs of sequential nodes and black boxes are treated as sources, so only combinational loops are reported as cycles.
+pub struct TopoOrder<'a, I: Instantiable> {
+    /// A reference to the underlying netlist
+    _netlist: &'a Netlist<I>,
+    /// The circuit nodes in topological order
+    order: Vec<NetRef<I>>,
+}
+
+impl<I> TopoOrder<'_, I>
+where
+    I: Instantiable,
+{
+    /// Returns the circuit nodes in topological order.
+    pub fn get_order(&self) -> &[NetRef<I>] {
+        &self.order
+    }
+
+    /// Returns an iterator to the circuit nodes in topological order.
+    pub fn iter(&self) -> impl Iterator<Item = &NetRef<I>> {
+        self.order.iter()
+    }
+}
+
+impl<'a, I> Analysis<'a, I> for TopoOrder<'a, I>
+where
+    I: Instantiable,
+{
+    fn build(netlist: &'a Netlist<I>) -> Result<Self, Error> {
+        Ok(TopoOrder {
+            _netlist: netlist,
+            order: topo_sort(netlist, |_| false)?,
+        })
+    }
+}
+
+/// The dominators of the circuit nodes in the combinational graph, towards the outputs.
+/// A node `d` dominates a node `n` if every path from `n` to a top-level output or to the input of a sequential node,
+/// latch or black box goes through `d`, so that `n` only matters to the rest of the design through `d`.
+pub struct Dominators<'a, I: Instantiable> {
+    /// A reference to the underlying netlist
+    _netlist: &'a Netlist<I>,
+    /// Maps a node to its immediate dominator, if it has one
+    idom: HashMap<NetRef<I>, Option<NetRef<I>>>,
+    /// Maps a node to its depth in the dominator tree, where the nodes without a dominator are at depth one
+    depth: HashMap<NetRef<I>, usize>,
+}
+
+impl<I> Dominators<'_, I>
+where
+    I: Instantiable,
+{
+    /// Returns the immediate dominator of a node, or [None] if it reaches the outputs by separate paths.
+    pub fn get_idom(&self, node: &NetRef<I>) -> Option<NetRef<I>> {
+        self.idom.get(node).cloned().flatten()
+    }
+
+    /// Returns `true` if `a` dominates `b`. Every node dominates itself.
+    pub fn dominates(&self, a: &NetRef<I>, b: &NetRef<I>) -> bool {
+        let mut node = Some(b.clone());
+        while let Some(n) = node {
+            if n == *a {
+                return true;
+            }
+            node = self.get_idom(&n);
+        }
+        false
+    }
+
+    /// Returns the nearest node that dominates both `a` and `b`, if any
+    fn common_dominator(&self, a: &NetRef<I>, b: &NetRef<I>) -> Option<NetRef<I>> {
+        let (mut a, mut b) = (a.clone(), b.clone());
+        while a != b {
+            if self.depth[&a] < self.depth[&b] {
+                std::mem::swap(&mut a, &mut b);
+            }
+            a = self.get_idom(&a)?;
+        }
+        Some(a)
+    }
+}
+
+impl<'a, I> Analysis<'a, I> for Dominators<'a, I>
+where
+    I: Instantiable,
+{
+    fn build(netlist: &'a Netlist<I>) -> Result<Self, Error> {
+        let mut dominators = Dominators {
+            _netlist: netlist,
+            idom: HashMap::new(),
+            depth: HashMap::new(),
+        };
+        let comb = PathOptions {
+            combinational_only: true,
+        };
+        // The loads of a node come before it in reverse topological order
+        for node in topo_sort(netlist, |_| false)?.into_iter().rev() {
+            let mut idom = None;
+            let loads = neighbors(netlist, &node, true);
+            let to_sink = loads.is_empty()
+                || node.outputs().any(|o| o.is_top_level_output())
+                || loads.iter().any(|l| !comb.passes_through(l));
+            if !to_sink {
+                idom = loads
+                    .iter()
+                    .skip(1)
+                    .try_fold(loads[0].clone(), |d, l| dominators.common_dominator(&d, l));
+            }
+            let depth = idom.as_ref().map_or(0, |d| dominators.depth[d]) + 1;
+            dominators.depth.insert(node.clone(), depth);
+            dominators.idom.insert(node, idom);
+        }
+        Ok(dominators)
+    }
+}
+
+/// Sorts the circuit nodes so that every combinational node comes after all of its drivers,
+/// treating the outputs of sequential nodes, black boxes and the instances for which `breaks_loops` holds as sources.
+/// Returns an error with the nets of the nodes left on combinational loops.
+pub(crate) fn topo_sort<I>(
+    netlist: &Netlist<I>,
+    breaks_loops: impl Fn(&I) -> bool,
+) -> Result<Vec<NetRef<I>>, Error>
+where
+    I: Instantiable,
+{
+    let nodes: Vec<NetRef<I>> = netlist.objects().collect();
+    let mut in_degree: HashMap<NetRef<I>, usize> = HashMap::new();
+    let mut users: HashMap<NetRef<I>, Vec<NetRef<I>>> = HashMap::new();
+
+    // A tri-state bus can only be read once all of its drivers are evaluated
+    let mut drivers: HashMap<Identifier, Vec<NetRef<I>>> = HashMap::new();
+    for node in nodes.iter() {
+        for net in node.nets() {
+            drivers
+                .entry(net.take_identifier())
+                .or_default()
+                .push(node.clone());
+        }
+    }
+
+    for node in nodes.iter() {
+        let is_comb = node
+            .get_instance_type()
+            .is_some_and(|i| !i.is_seq() && !i.is_blackbox() && !i.is_latch() && !breaks_loops(&i));
+        let mut degree = 0;
+        if is_comb {
+            for net in node.driver_nets().flatten() {
+                for driver in drivers[net.get_identifier()].iter() {
+                    users.entry(driver.clone()).or_default().push(node.clone());
+                    degree += 1;
+                }
+            }
+        }
+        in_degree.insert(node.clone(), degree);
+    }
+
+    let mut ready: std::collections::VecDeque<NetRef<I>> = nodes
+        .iter()
+        .filter(|n| in_degree[*n] == 0)
+        .cloned()
+        .collect();
+    let mut order = Vec::with_capacity(nodes.len());
+    while let Some(node) = ready.pop_front() {
+        for user in users.get(&node).into_iter().flatten() {
+            let degree = in_degree.get_mut(user).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                ready.push_back(user.clone());
+            }
+        }
+        order.push(node);
+    }
+
+    if order.len() < nodes.len() {
+        let nets = nodes
+            .iter()
+            .filter(|n| in_degree[*n] > 0)
+            .flat_map(|n| n.nets())
+            .collect();
+        return Err(Error::CycleDetected(nets));
+    }
+
+    Ok(order)
+}
+
+/// Options for the path queries [reachable], [between], [shortest_path] and [longest_path], and for [sccs]
+#[derive(Debug, Clone, Default)]
+pub struct PathOptions {
+    /// Only follow paths through combinational nodes, so that paths end at the first register, latch or black box.
+    /// The nodes a query starts or ends at may be sequential.
+    pub combinational_only: bool,
+}
+
+impl PathOptions {
+    /// Returns `true` if paths may continue through `node`
+    fn passes_through<I: Instantiable>(&self, node: &NetRef<I>) -> bool {
+        !self.combinational_only
+            || node
+                .get_instance_type()
+                .is_some_and(|i| !i.is_seq() && !i.is_blackbox() && !i.is_latch())
+    }
+}
+
+/// Returns the circuit nodes read by `node`, or that read it if `forward`
+fn neighbors<I>(netlist: &Netlist<I>, node: &NetRef<I>, forward: bool) -> Vec<NetRef<I>>
+where
+    I: Instantiable,
+{
+    if forward {
+        node.outputs()
+            .flat_map(|o| netlist.get_uses(&o))
+            .map(|i| i.unwrap())
+            .collect()
+    } else {
+        node.drivers().flatten().collect()
+    }
+}
+
+/// Returns the circuit nodes reached from `from`, with the node each one was first reached from, in breadth-first order
+fn search<I>(
+    netlist: &Netlist<I>,
+    from: &NetRef<I>,
+    forward: bool,
+    options: &PathOptions,
+) -> Vec<(NetRef<I>, NetRef<I>)>
+where
+    I: Instantiable,
+{
+    let mut visited = HashSet::from([from.clone()]);
+    let mut found: Vec<(NetRef<I>, NetRef<I>)> = Vec::new();
+    let mut next = 0;
+    let mut expand = Some(from.clone());
+    while let Some(node) = expand {
+        for neighbor in neighbors(netlist, &node, forward) {
+            if visited.insert(neighbor.clone()) {
+                found.push((neighbor, node.clone()));
+            }
+        }
+        expand = None;
+        while next < found.len() && expand.is_none() {
+            let node = &found[next].0;
+            if options.passes_through(node) {
+                expand = Some(node.clone());
+            }
+            next += 1;
+        }
+    }
+    found
+}
+
+/// Returns the circuit nodes that `from` drives through one or more connections, in breadth-first order.
+/// `from` is only included if it is on a loop that [PathOptions] lets through.
+pub fn reachable<I>(netlist: &Netlist<I>, from: &NetRef<I>, options: &PathOptions) -> Vec<NetRef<I>>
+where
+    I: Instantiable,
+{
+    let mut nodes: Vec<NetRef<I>> = search(netlist, from, true, options)
+        .into_iter()
+        .map(|(node, _)| node)
+        .collect();
+    if nodes
+        .iter()
+        .filter(|n| options.passes_through(*n))
+        .any(|n| neighbors(netlist, n, true).contains(from))
+    {
+        nodes.push(from.clone());
+    }
+    nodes
+}
+
+/// Returns the circuit nodes on the paths from `a` to `b`, both included, in netlist order.
+/// Returns no nodes if `b` is not reachable from `a`.
+pub fn between<I>(
+    netlist: &Netlist<I>,
+    a: &NetRef<I>,
+    b: &NetRef<I>,
+    options: &PathOptions,
+) -> Vec<NetRef<I>>
+where
+    I: Instantiable,
+{
+    let forward: HashSet<NetRef<I>> = search(netlist, a, true, options)
+        .into_iter()
+        .map(|(node, _)| node)
+        .chain(std::iter::once(a.clone()))
+        .collect();
+    if !forward.contains(b) {
+        return Vec::new();
+    }
+    let backward: HashSet<NetRef<I>> = search(netlist, b, false, options)
+        .into_iter()
+        .map(|(node, _)| node)
+        .chain(std::iter::once(b.clone()))
+        .collect();
+    netlist
+        .objects()
+        .filter(|n| forward.contains(n) && backward.contains(n))
+        .filter(|n| n == a || n == b || options.passes_through(n))
+        .collect()
+}
+
+/// Returns a path from `a` to `b` with the fewest connections, as the sequence of circuit nodes from `a` to `b`,
+/// or [None] if `b` is not reachable from `a`.
+pub fn shortest_path<I>(
+    netlist: &Netlist<I>,
+    a: &NetRef<I>,
+    b: &NetRef<I>,
+    options: &PathOptions,
+) -> Option<Vec<NetRef<I>>>
+where
+    I: Instantiable,
+{
+    if a == b {
+        return Some(vec![a.clone()]);
+    }
+    let parents: HashMap<NetRef<I>, NetRef<I>> =
+        search(netlist, a, true, options).into_iter().collect();
+    let mut path = vec![b.clone()];
+    while path.last() != Some(a) {
+        path.push(parents.get(path.last().unwrap())?.clone());
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Returns a path from `a` to `b` with the most connections, as the sequence of circuit nodes from `a` to `b`,
+/// or [None] if `b` is not reachable from `a`.
+/// Returns [Error::CycleDetected] with the nets of the nodes left on loops between `a` and `b`, as the longest path is
+/// then unbounded. Setting [PathOptions::combinational_only] breaks the loops through registers.
+pub fn longest_path<I>(
+    netlist: &Netlist<I>,
+    a: &NetRef<I>,
+    b: &NetRef<I>,
+    options: &PathOptions,
+) -> Result<Option<Vec<NetRef<I>>>, Error>
+where
+    I: Instantiable,
+{
+    let nodes = between(netlist, a, b, options);
+    if nodes.is_empty() {
+        return Ok(None);
+    }
+    if a == b {
+        return Ok(Some(vec![a.clone()]));
+    }
+
+    // The connections within the paths, ending at `b` and starting from `a`
+    let on_path: HashSet<&NetRef<I>> = nodes.iter().collect();
+    let mut users: HashMap<NetRef<I>, Vec<NetRef<I>>> = HashMap::new();
+    let mut in_degree: HashMap<NetRef<I>, usize> = nodes.iter().map(|n| (n.clone(), 0)).collect();
+    for node in nodes.iter().filter(|n| *n != b) {
+        for user in neighbors(netlist, node, true) {
+            if on_path.contains(&user) && user != *a {
+                *in_degree.get_mut(&user).unwrap() += 1;
+                users.entry(node.clone()).or_default().push(user);
+            }
+        }
+    }
+
+    // The longest distance from `a` and the previous node of each node, in topological order
+    let mut ready = vec![a.clone()];
+    let mut longest: HashMap<NetRef<I>, (usize, Option<NetRef<I>>)> =
+        HashMap::from([(a.clone(), (0, None))]);
+    let mut sorted = 0;
+    while let Some(node) = ready.pop() {
+        sorted += 1;
+        let distance = longest[&node].0 + 1;
+        for user in users.get(&node).into_iter().flatten() {
+            if longest.get(user).is_none_or(|(d, _)| *d < distance) {
+                longest.insert(user.clone(), (distance, Some(node.clone())));
+            }
+            let degree = in_degree.get_mut(user).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                ready.push(user.clone());
+            }
+        }
+    }
+    if sorted < nodes.len() {
+        let nets = nodes
+            .iter()
+            .filter(|n| in_degree[*n] > 0)
+            .flat_map(|n| n.nets())
+            .collect();
+        return Err(Error::CycleDetected(nets));
+    }
+
+    let mut path = vec![b.clone()];
+    while let Some(previous) = &longest[path.last().unwrap()].1 {
+        path.push(previous.clone());
+    }
+    path.reverse();
+    Ok(Some(path))
+}
+
+/// Returns the strongly connected components of the graph of circuit nodes, in topological order, so that every component
+/// comes after the components that drive it. Each feedback loop is within a component, which is either a single node or a
+/// group of nodes that all reach each other; a single node is only on a loop if it reads itself.
+/// With [PathOptions::combinational_only], the connections out of sequential nodes, latches and black boxes are ignored,
+/// so that only the loops that no register breaks are grouped.
+pub fn sccs<I>(netlist: &Netlist<I>, options: &PathOptions) -> Vec<Vec<NetRef<I>>>
+where
+    I: Instantiable,
+{
+    // Tarjan's algorithm, with an explicit stack of nodes and their next load to visit
+    let nodes: Vec<NetRef<I>> = netlist.objects().collect();
+    let position: HashMap<NetRef<I>, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.clone(), i))
+        .collect();
+    let loads: Vec<Vec<usize>> = nodes
+        .iter()
+        .map(|n| {
+            if options.passes_through(n) || n.is_an_input() {
+                neighbors(netlist, n, true)
+                    .iter()
+                    .map(|l| position[l])
+                    .collect()
+            } else {
+                Vec::new()
+            }
+        })
+        .collect();
+
+    let mut index = vec![usize::MAX; nodes.len()];
+    let mut lowlink = vec![0; nodes.len()];
+    let mut on_stack = vec![false; nodes.len()];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0;
+    for start in 0..nodes.len() {
+        if index[start] != usize::MAX {
+            continue;
+        }
+        let mut calls = vec![(start, 0)];
+        while let Some((node, next)) = calls.pop() {
+            if next == 0 {
+                index[node] = next_index;
+                lowlink[node] = next_index;
+                next_index += 1;
+                stack.push(node);
+                on_stack[node] = true;
+            }
+            if let Some(&load) = loads[node].get(next) {
+                calls.push((node, next + 1));
+                if index[load] == usize::MAX {
+                    calls.push((load, 0));
+                } else if on_stack[load] {
+                    lowlink[node] = lowlink[node].min(index[load]);
+                }
+                continue;
+            }
+            if lowlink[node] == index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                components.push(component.into_iter().map(|i| nodes[i].clone()).collect());
+            }
+            if let Some((caller, _)) = calls.last() {
+                lowlink[*caller] = lowlink[*caller].min(lowlink[node]);
+            }
+        }
+    }
+    components.reverse();
+    components
+}
+
+/// Returns the maximum fanout-free cone of `root`: `root` and the circuit nodes that only it reads, directly or through
+/// other such nodes, from `root` towards the inputs. These are the nodes that are left without loads when `root` is removed,
+/// so that [Netlist::clean] would remove them too. The cone stops at principal inputs, sequential nodes, latches and black
+/// boxes, and never includes the nodes that drive top-level outputs or are protected by a [crate::attribute::DONT_TOUCH]
+/// attribute.
+pub fn mffc<I>(netlist: &Netlist<I>, root: &NetRef<I>) -> Vec<NetRef<I>>
+where
+    I: Instantiable,
+{
+    let comb = PathOptions {
+        combinational_only: true,
+    };
+    let mut cone = vec![root.clone()];
+    // The loads left on each driver, as input ports
+    let mut loads: HashMap<NetRef<I>, usize> = HashMap::new();
+    let mut next = 0;
+    while next < cone.len() {
+        let node = cone[next].clone();
+        next += 1;
+        for driver in node.drivers().flatten() {
+            let left = loads
+                .entry(driver.clone())
+                .or_insert_with(|| driver.outputs().map(|o| netlist.get_uses(&o).len()).sum());
+            *left -= 1;
+            if *left == 0
+                && comb.passes_through(&driver)
+                && !driver.drives_a_top_output()
+                && !netlist.is_node_protected(&driver)
+                && driver != *root
+            {
+                cone.push(driver);
+            }
+        }
+    }
+    cone
+}
+
 /// An enum to provide pseudo-nodes for any misc user-programmable behavior.
 #[cfg(feature = "graph")]
 #[derive(Debug, Clone)]
@@ -308,7 +820,7 @@ mod tests {
         let b = netlist.insert_input_escaped_logic_bus("b".to_string(), bitwidth);
         let mut carry: DrivenNet<Gate> = netlist.insert_input("cin".into());
 
-        for (i, (a, b)) in a.into_iter().zip(b.into_iter()).enumerate() {
+        for (i, (a, b)) in a.into_iter().zip(b).enumerate() {
             // Instantiate a full adder for each bit
             let fa = netlist
                 .insert_gate(full_adder(), format_id!("fa_{i}"), &[carry, a, b])
@@ -363,4 +875,84 @@ mod tests {
             );
         }
     }
+
+    /// `y = !(a & b) & ((a & b) | c)` and `z = !c`
+    fn shared_and() -> std::rc::Rc<GateNetlist> {
+        let netlist = Netlist::new("shared_and".to_string());
+        let a = netlist.insert_input("a".into());
+        let b = netlist.insert_input("b".into());
+        let c = netlist.insert_input("c".into());
+        let gate = |name: &str, n: usize| {
+            let inputs = ["A", "B"][..n].iter().map(|p| (*p).into()).collect();
+            Gate::new_logical(name.into(), inputs, "Y".into())
+        };
+        let n1 = netlist
+            .insert_gate(gate("AND", 2), "n1".into(), &[a, b])
+            .unwrap();
+        let n2 = netlist
+            .insert_gate(gate("INV", 1), "n2".into(), &[n1.get_output(0)])
+            .unwrap();
+        let n3 = netlist
+            .insert_gate(gate("OR", 2), "n3".into(), &[n1.get_output(0), c.clone()])
+            .unwrap();
+        let n4 = netlist
+            .insert_gate(
+                gate("AND", 2),
+                "n4".into(),
+                &[n2.get_output(0), n3.get_output(0)],
+            )
+            .unwrap();
+        n4.expose_with_name("y".into());
+        let n5 = netlist
+            .insert_gate(gate("INV", 1), "n5".into(), &[c])
+            .unwrap();
+        n5.expose_with_name("z".into());
+        netlist
+    }
+
+    fn node(netlist: &GateNetlist, name: &str) -> NetRef<Gate> {
+        netlist
+            .objects()
+            .find(|o| {
+                o.get_instance_name()
+                    .unwrap_or_else(|| o.get_identifier())
+                    .to_string()
+                    == name
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn dominators() {
+        let netlist = shared_and();
+        let dominators = Dominators::build(&netlist).unwrap();
+        let idom = |name: &str| {
+            dominators
+                .get_idom(&node(&netlist, name))
+                .map(|d| d.get_instance_name().unwrap().to_string())
+        };
+        assert_eq!(idom("a").as_deref(), Some("n1"));
+        assert_eq!(idom("n1").as_deref(), Some("n4"));
+        assert_eq!(idom("n2").as_deref(), Some("n4"));
+        assert_eq!(idom("n4"), None);
+        // `c` reaches both outputs
+        assert_eq!(idom("c"), None);
+        assert!(dominators.dominates(&node(&netlist, "n4"), &node(&netlist, "a")));
+        assert!(!dominators.dominates(&node(&netlist, "n2"), &node(&netlist, "n1")));
+    }
+
+    #[test]
+    fn fanout_free_cones() {
+        let netlist = shared_and();
+        let names = |root: &str| -> Vec<String> {
+            mffc(&netlist, &node(&netlist, root))
+                .iter()
+                .map(|n| n.get_instance_name().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(names("n4"), ["n4", "n2", "n3", "n1"]);
+        // `n1` is also read by `n2`
+        assert_eq!(names("n3"), ["n3"]);
+        assert_eq!(names("n5"), ["n5"]);
+    }
 }
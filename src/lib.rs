@@ -15,15 +15,22 @@ The most important API is the [Netlist](https://matth2k.github.io/safety-net/saf
 #![doc = "\n```"]
 
 pub mod attribute;
+#[cfg(feature = "capi")]
+pub mod capi;
 pub mod circuit;
+pub mod constraints;
 pub mod error;
 pub mod graph;
 pub mod logic;
 pub mod netlist;
+#[cfg(feature = "shell")]
+pub mod shell;
 #[cfg(feature = "derive")]
-/// Re-export of the `Instantiable` derive macro.
+/// Re-export of the `Instantiable` and `Evaluate` derive macros.
 /// To disable this feature, opt out with "safety-net = { version = "0.2.10", default-features = false }" in your Cargo.toml
 pub mod derive {
-    pub use inst_derive::Instantiable;
+    pub use inst_derive::{Evaluate, Instantiable};
 }
 mod util;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -4,6 +4,7 @@
 
 */
 
+use bitvec::vec::BitVec;
 use std::{fmt, str::FromStr};
 
 use crate::error::Error;
@@ -60,6 +61,54 @@ impl Logic {
     pub fn from_bool(b: bool) -> Logic {
         if b { Logic::True } else { Logic::False }
     }
+
+    /// Resolves two drivers of the same net: high-impedance yields to the other driver,
+    /// and disagreeing drivers produce an unknown value.
+    pub fn resolve(self, other: Logic) -> Logic {
+        match (self, other) {
+            (Logic::Z, v) | (v, Logic::Z) => v,
+            (a, b) if a == b => a,
+            _ => Logic::X,
+        }
+    }
+}
+
+/// How a net with multiple tri-state drivers resolves, named after the equivalent Verilog net types
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Resolution {
+    /// Drivers are resolved with [Logic::resolve], and the net floats when undriven
+    #[default]
+    Tri,
+    /// The net keeps its last value when undriven, like a bus keeper
+    TriReg,
+    /// The net is pulled up when undriven
+    Tri1,
+    /// The net is pulled down when undriven
+    Tri0,
+}
+
+impl Resolution {
+    /// Resolves the values of all `drivers` of a net, given its `previous` value.
+    pub fn resolve(&self, drivers: impl IntoIterator<Item = Logic>, previous: Logic) -> Logic {
+        let val = drivers.into_iter().fold(Logic::Z, Logic::resolve);
+        match (self, val) {
+            (Resolution::TriReg, Logic::Z) => previous,
+            (Resolution::Tri1, Logic::Z) => Logic::True,
+            (Resolution::Tri0, Logic::Z) => Logic::False,
+            _ => val,
+        }
+    }
+
+    /// Returns the Verilog net type of the resolution
+    pub fn as_str(&self) -> &str {
+        match self {
+            Resolution::Tri => "tri",
+            Resolution::TriReg => "trireg",
+            Resolution::Tri1 => "tri1",
+            Resolution::Tri0 => "tri0",
+        }
+    }
 }
 
 impl std::ops::BitAnd for Logic {
@@ -87,6 +136,18 @@ impl std::ops::BitOr for Logic {
     }
 }
 
+impl std::ops::BitXor for Logic {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Logic::True, Logic::False) | (Logic::False, Logic::True) => Logic::True,
+            (Logic::True, Logic::True) | (Logic::False, Logic::False) => Logic::False,
+            _ => Logic::X,
+        }
+    }
+}
+
 impl std::ops::Not for Logic {
     type Output = Self;
 
@@ -116,6 +177,13 @@ impl From<bool> for Logic {
     }
 }
 
+/// Waveforms are written with the integers 0 and 1, like `[0, 1, 1, 0]`. Any nonzero value is true.
+impl From<u8> for Logic {
+    fn from(value: u8) -> Self {
+        Logic::from(value != 0)
+    }
+}
+
 impl FromStr for Logic {
     type Err = Error;
 
@@ -149,3 +217,405 @@ pub fn dont_care() -> Logic {
 pub fn high_z() -> Logic {
     Logic::Z
 }
+
+/// A four-state logic vector of arbitrary width, where bit 0 is the least significant bit.
+/// Each bit is stored in two bit vectors, like the `aval`/`bval` encoding of Verilog:
+/// `0` is (0, 0), `1` is (1, 0), `z` is (0, 1) and `x` is (1, 1).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct LogicVec {
+    /// The value of each bit, which is set for [Logic::True] and [Logic::X]
+    value: BitVec,
+    /// Whether each bit is unknown, which is set for [Logic::X] and [Logic::Z]
+    unknown: BitVec,
+}
+
+impl LogicVec {
+    /// Creates a vector of `width` bits, all set to `val`
+    pub fn filled(width: usize, val: Logic) -> Self {
+        let (v, u) = Self::encode(val);
+        Self {
+            value: BitVec::repeat(v, width),
+            unknown: BitVec::repeat(u, width),
+        }
+    }
+
+    /// Creates a vector of `width` zeros
+    pub fn zeros(width: usize) -> Self {
+        Self::filled(width, Logic::False)
+    }
+
+    /// Creates a vector of `width` bits holding the low bits of `val`
+    pub fn from_u64(val: u64, width: usize) -> Self {
+        (0..width)
+            .map(|i| Logic::from_bool(i < 64 && (val >> i) & 1 == 1))
+            .collect()
+    }
+
+    /// Returns the (value, unknown) encoding of a bit
+    fn encode(val: Logic) -> (bool, bool) {
+        match val {
+            Logic::False => (false, false),
+            Logic::True => (true, false),
+            Logic::Z => (false, true),
+            Logic::X => (true, true),
+        }
+    }
+
+    /// Returns the width of the vector
+    pub fn len(&self) -> usize {
+        self.value.len()
+    }
+
+    /// Returns `true` if the vector has no bits
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// Returns the bit at `index`, if it is in range
+    pub fn get(&self, index: usize) -> Option<Logic> {
+        let v = *self.value.get(index)?;
+        Some(match (v, self.unknown[index]) {
+            (false, false) => Logic::False,
+            (true, false) => Logic::True,
+            (false, true) => Logic::Z,
+            (true, true) => Logic::X,
+        })
+    }
+
+    /// Sets the bit at `index` to `val`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn set(&mut self, index: usize, val: Logic) {
+        let (v, u) = Self::encode(val);
+        self.value.set(index, v);
+        self.unknown.set(index, u);
+    }
+
+    /// Appends `val` as the new most significant bit
+    pub fn push(&mut self, val: Logic) {
+        let (v, u) = Self::encode(val);
+        self.value.push(v);
+        self.unknown.push(u);
+    }
+
+    /// Returns an iterator over the bits, starting from the least significant bit
+    pub fn iter(&self) -> impl Iterator<Item = Logic> + '_ {
+        (0..self.len()).map(|i| self.get(i).unwrap())
+    }
+
+    /// Returns the bits in `range` as a new vector
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn slice(&self, range: impl std::ops::RangeBounds<usize>) -> Self {
+        let start = match range.start_bound() {
+            std::ops::Bound::Included(s) => *s,
+            std::ops::Bound::Excluded(s) => s + 1,
+            std::ops::Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            std::ops::Bound::Included(e) => e + 1,
+            std::ops::Bound::Excluded(e) => *e,
+            std::ops::Bound::Unbounded => self.len(),
+        };
+        Self {
+            value: self.value[start..end].to_bitvec(),
+            unknown: self.unknown[start..end].to_bitvec(),
+        }
+    }
+
+    /// Returns the concatenation `{high, self}`, where the bits of `high` become the most significant bits
+    pub fn concat(&self, high: &LogicVec) -> Self {
+        let mut res = self.clone();
+        res.value.extend_from_bitslice(&high.value);
+        res.unknown.extend_from_bitslice(&high.unknown);
+        res
+    }
+
+    /// Resizes the vector to `width` bits, truncating the most significant bits or extending with `fill`
+    pub fn resize(&mut self, width: usize, fill: Logic) {
+        let (v, u) = Self::encode(fill);
+        self.value.resize(width, v);
+        self.unknown.resize(width, u);
+    }
+
+    /// Returns `true` if no bit is [Logic::X] or [Logic::Z]
+    pub fn is_known(&self) -> bool {
+        self.unknown.not_any()
+    }
+
+    /// Returns the vector as a [BitVec], if every bit is known
+    pub fn to_bitvec(&self) -> Option<BitVec> {
+        self.is_known().then(|| self.value.clone())
+    }
+
+    /// Returns the vector as an unsigned number, if every bit is known and it fits in 64 bits
+    pub fn to_u64(&self) -> Option<u64> {
+        if !self.is_known() || self.value.iter_ones().any(|i| i >= 64) {
+            return None;
+        }
+        Some(self.value.iter_ones().fold(0, |acc, i| acc | (1 << i)))
+    }
+
+    /// Applies `op` to each pair of bits, zero-extending the narrower vector
+    fn zip_with(&self, rhs: &LogicVec, op: impl Fn(Logic, Logic) -> Logic) -> Self {
+        let width = self.len().max(rhs.len());
+        (0..width)
+            .map(|i| {
+                op(
+                    self.get(i).unwrap_or(Logic::False),
+                    rhs.get(i).unwrap_or(Logic::False),
+                )
+            })
+            .collect()
+    }
+
+    /// Applies the arithmetic `op` to the known values of the vectors, zero-extended to the wider width.
+    /// As in Verilog, the result is all [Logic::X] if any operand bit is unknown.
+    fn arith(&self, rhs: &LogicVec, op: impl Fn(&BitVec, &BitVec) -> BitVec) -> Self {
+        let width = self.len().max(rhs.len());
+        match (self.to_bitvec(), rhs.to_bitvec()) {
+            (Some(mut a), Some(mut b)) => {
+                a.resize(width, false);
+                b.resize(width, false);
+                BitVec::from_bitslice(&op(&a, &b)[..width]).into()
+            }
+            _ => Self::filled(width, Logic::X),
+        }
+    }
+}
+
+/// Returns `a + b + carry`, truncated to the width of `a`
+fn add_bits(a: &BitVec, b: &BitVec, mut carry: bool) -> BitVec {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| {
+            let s = *a ^ *b ^ carry;
+            carry = (*a & *b) | (carry & (*a ^ *b));
+            s
+        })
+        .collect()
+}
+
+impl From<BitVec> for LogicVec {
+    fn from(value: BitVec) -> Self {
+        let unknown = BitVec::repeat(false, value.len());
+        Self { value, unknown }
+    }
+}
+
+impl From<Logic> for LogicVec {
+    fn from(val: Logic) -> Self {
+        Self::filled(1, val)
+    }
+}
+
+impl FromIterator<Logic> for LogicVec {
+    fn from_iter<T: IntoIterator<Item = Logic>>(iter: T) -> Self {
+        let mut res = Self::default();
+        for val in iter {
+            res.push(val);
+        }
+        res
+    }
+}
+
+/// Implements a binary operator for owned and borrowed vectors
+macro_rules! logic_vec_op {
+    ($trait:ident, $method:ident, $body:expr) => {
+        impl std::ops::$trait<&LogicVec> for &LogicVec {
+            type Output = LogicVec;
+
+            fn $method(self, rhs: &LogicVec) -> LogicVec {
+                let f: fn(&LogicVec, &LogicVec) -> LogicVec = $body;
+                f(self, rhs)
+            }
+        }
+
+        impl std::ops::$trait for LogicVec {
+            type Output = LogicVec;
+
+            fn $method(self, rhs: LogicVec) -> LogicVec {
+                std::ops::$trait::$method(&self, &rhs)
+            }
+        }
+    };
+}
+
+logic_vec_op!(BitAnd, bitand, |a, b| a.zip_with(b, |a, b| a & b));
+logic_vec_op!(BitOr, bitor, |a, b| a.zip_with(b, |a, b| a | b));
+logic_vec_op!(BitXor, bitxor, |a, b| a.zip_with(b, |a, b| a ^ b));
+logic_vec_op!(Add, add, |a, b| a.arith(b, |a, b| add_bits(a, b, false)));
+logic_vec_op!(Sub, sub, |a, b| a.arith(b, |a, b| add_bits(
+    a,
+    &!b.clone(),
+    true
+)));
+logic_vec_op!(Mul, mul, |a, b| a.arith(b, |a, b| {
+    let mut acc = BitVec::repeat(false, a.len());
+    for i in b.iter_ones() {
+        let mut shifted = BitVec::repeat(false, i);
+        shifted.extend_from_bitslice(&a[..a.len() - i]);
+        acc = add_bits(&acc, &shifted, false);
+    }
+    acc
+}));
+
+impl std::ops::Not for &LogicVec {
+    type Output = LogicVec;
+
+    fn not(self) -> LogicVec {
+        self.iter().map(|b| !b).collect()
+    }
+}
+
+impl std::ops::Not for LogicVec {
+    type Output = LogicVec;
+
+    fn not(self) -> LogicVec {
+        !&self
+    }
+}
+
+/// Vectors print as sized binary literals, like `4'b10xz`
+impl fmt::Display for LogicVec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}'b", self.len())?;
+        for b in self.iter().collect::<Vec<_>>().into_iter().rev() {
+            let c = match b {
+                Logic::False => '0',
+                Logic::True => '1',
+                Logic::X => 'x',
+                Logic::Z => 'z',
+            };
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Vectors print as sized hexadecimal literals with `{:X}`, like `8'hA5` or `8'hx0`.
+/// Vectors with a hexadecimal digit that is partly unknown print as binary literals instead.
+impl fmt::UpperHex for LogicVec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bits: Vec<Logic> = self.iter().collect();
+        let mut digits = Vec::with_capacity(bits.len().div_ceil(4));
+        for nibble in bits.chunks(4) {
+            let digit = match nibble[0] {
+                Logic::X | Logic::Z if nibble.iter().all(|b| *b == nibble[0]) => {
+                    if nibble[0] == Logic::X { 'x' } else { 'z' }
+                }
+                Logic::X | Logic::Z => return write!(f, "{self}"),
+                _ => {
+                    let mut d = 0;
+                    for (i, b) in nibble.iter().enumerate() {
+                        match b {
+                            Logic::True => d |= 1 << i,
+                            Logic::False => (),
+                            _ => return write!(f, "{self}"),
+                        }
+                    }
+                    char::from_digit(d, 16).unwrap().to_ascii_uppercase()
+                }
+            };
+            digits.push(digit);
+        }
+        write!(f, "{}'h", self.len())?;
+        for d in digits.into_iter().rev() {
+            write!(f, "{d}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the digits of a based literal, starting from the least significant digit
+fn parse_digits(digits: &str, radix: u32) -> Option<LogicVec> {
+    let mut res = LogicVec::default();
+    if radix == 10 {
+        return match digits.to_ascii_lowercase().as_str() {
+            "x" => Some(LogicVec::from(Logic::X)),
+            "z" | "?" => Some(LogicVec::from(Logic::Z)),
+            d => {
+                let val = d.parse::<u128>().ok()?;
+                let width = (128 - val.leading_zeros() as usize).max(1);
+                Some(
+                    (0..width)
+                        .map(|i| Logic::from_bool((val >> i) & 1 == 1))
+                        .collect(),
+                )
+            }
+        };
+    }
+    let width = radix.trailing_zeros() as usize;
+    for c in digits.chars().rev() {
+        match c.to_ascii_lowercase() {
+            'x' => (0..width).for_each(|_| res.push(Logic::X)),
+            'z' | '?' => (0..width).for_each(|_| res.push(Logic::Z)),
+            c => {
+                let d = c.to_digit(radix)?;
+                (0..width).for_each(|i| res.push(Logic::from_bool((d >> i) & 1 == 1)));
+            }
+        }
+    }
+    Some(res)
+}
+
+/// Parses a Verilog literal, like `8'b1x0z_0101`, `'hFF` or `42`.
+/// Unsized literals are 32 bits wide. Literals are truncated to their size, or extended with zeros,
+/// unless the most significant digit is `x` or `z` in which case they are extended with it.
+impl FromStr for LogicVec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || Error::ParseError(s.to_string());
+        let trimmed = s.trim();
+        let (size, rest) = match trimmed.split_once('\'') {
+            Some((size, rest)) => (size.trim(), rest),
+            None => ("", trimmed),
+        };
+        let size = match size {
+            "" => None,
+            size => Some(
+                size.replace('_', "")
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(err)?,
+            ),
+        };
+        let (radix, digits) = if trimmed.contains('\'') {
+            let rest = rest.strip_prefix(['s', 'S']).unwrap_or(rest);
+            let mut chars = rest.chars();
+            let radix = match chars.next().ok_or_else(err)?.to_ascii_lowercase() {
+                'b' => 2,
+                'o' => 8,
+                'd' => 10,
+                'h' => 16,
+                _ => return Err(err()),
+            };
+            (radix, chars.as_str())
+        } else if rest.chars().all(|c| c.is_ascii_digit() || c == '_') {
+            (10, rest)
+        } else {
+            return Err(err());
+        };
+        let digits: String = digits.chars().filter(|c| *c != '_').collect();
+        let digits = digits.trim();
+        if digits.is_empty() {
+            return Err(err());
+        }
+
+        let mut res = parse_digits(digits, radix).ok_or_else(err)?;
+        let fill = match res.get(res.len() - 1) {
+            Some(l @ (Logic::X | Logic::Z)) => l,
+            _ => Logic::False,
+        };
+        let width = size.unwrap_or(res.len().max(32));
+        res.resize(width, fill);
+        Ok(res)
+    }
+}
@@ -4,19 +4,74 @@
 
 */
 use crate::{
-    attribute::{Attribute, AttributeKey, AttributeValue, Parameter},
-    circuit::{Identifier, Instantiable, Net, Object},
-    error::Error,
-    graph::{Analysis, FanOutTable},
-    logic::Logic,
+    attribute::{Attribute, AttributeKey, AttributeValue, DONT_TOUCH, Parameter, SPARE},
+    circuit::{AsCell, DataType, Evaluate, HierPath, Identifier, Instantiable, Net, Object},
+    error::{Diagnostics, Error},
+    format_id,
+    graph::{Analysis, FanOutTable, topo_sort},
+    logic::{Logic, LogicVec, Resolution},
+    util::{debug_span, glob_match, trace_event},
 };
+use smallvec::SmallVec;
 use std::{
-    cell::{Ref, RefCell, RefMut},
-    collections::{HashMap, HashSet},
+    cell::{Cell, Ref, RefCell, RefMut},
+    collections::{HashMap, HashSet, hash_map::Entry},
     num::ParseIntError,
     rc::{Rc, Weak},
 };
 
+#[cfg(feature = "abc")]
+pub mod abc;
+pub mod alias;
+pub mod annotation;
+#[cfg(feature = "benchmarks")]
+pub mod benchmark;
+pub mod blackbox;
+pub mod btor;
+pub mod canonical;
+pub mod cost;
+pub mod delay;
+pub mod design;
+pub mod dft;
+pub mod eco;
+pub mod expr;
+pub mod fingerprint;
+pub mod gates;
+pub mod levels;
+pub mod mark;
+pub mod memory;
+pub mod name_map;
+pub mod network;
+pub mod observer;
+pub mod opt;
+pub mod payload;
+pub mod placement;
+pub mod power;
+pub mod provenance;
+pub mod region;
+pub mod report;
+pub mod rewrite;
+pub mod sat;
+pub mod select;
+pub mod seq;
+pub mod sim;
+#[cfg(feature = "serde")]
+pub mod snet;
+pub mod stimulus;
+pub mod strash;
+pub mod synth;
+pub mod testing;
+pub mod timing;
+pub mod verify;
+#[cfg(feature = "word")]
+pub mod word;
+#[cfg(feature = "yosys")]
+pub mod yosys;
+
+use annotation::{NetId, ObjectId};
+use provenance::{Provenance, ProvenanceStyle};
+use report::PassReport;
+
 /// A trait for indexing into a collection of objects weakly.
 trait WeakIndex<Idx: ?Sized> {
     /// The output data type which will be referred to weakly
@@ -83,11 +138,87 @@ impl Instantiable for Gate {
         }
     }
 
+    /// Gates are folded by evaluating them with the unknown inputs as [Logic::X]
+    fn fold(&self, inputs: &[Option<Logic>]) -> Option<Logic> {
+        if self.outputs.len() != 1 {
+            return None;
+        }
+        let inputs: Vec<Logic> = inputs.iter().map(|i| i.unwrap_or(Logic::X)).collect();
+        match self.eval(&inputs)[0] {
+            out @ (Logic::True | Logic::False) => Some(out),
+            _ => None,
+        }
+    }
+
     fn is_seq(&self) -> bool {
         false
     }
 }
 
+/// Gates are evaluated by name: `AND`, `NAND`, `OR`, `NOR`, `XOR` and `XNOR` take any number of inputs,
+/// `INV`/`NOT` and `BUF` take one, and `MUX` selects its third input when the first is high (`S`, `A`, `B`).
+/// `TBUF` drives its first input when the second is high, and is high-impedance otherwise (`A`, `EN`).
+/// `VDD` and `GND` are constants. The outputs of any other gate are unknown.
+/// Two-state evaluation reads high-impedance as zero.
+impl Evaluate for Gate {
+    fn eval(&self, inputs: &[Logic]) -> Vec<Logic> {
+        let out = match (self.name.get_name(), inputs) {
+            ("AND", _) => inputs.iter().fold(Logic::True, |a, b| a & *b),
+            ("NAND", _) => !inputs.iter().fold(Logic::True, |a, b| a & *b),
+            ("OR", _) => inputs.iter().fold(Logic::False, |a, b| a | *b),
+            ("NOR", _) => !inputs.iter().fold(Logic::False, |a, b| a | *b),
+            ("XOR", _) => inputs.iter().fold(Logic::False, |a, b| a ^ *b),
+            ("XNOR", _) => !inputs.iter().fold(Logic::False, |a, b| a ^ *b),
+            ("INV" | "NOT", [a]) => !*a,
+            ("BUF", [a]) => match a {
+                Logic::True | Logic::False => *a,
+                _ => Logic::X,
+            },
+            ("MUX", [s, a, b]) => match s {
+                Logic::False => *a,
+                Logic::True => *b,
+                _ if a == b => *a,
+                _ => Logic::X,
+            },
+            ("TBUF", [a, en]) => match en {
+                Logic::True if matches!(a, Logic::True | Logic::False) => *a,
+                Logic::False => Logic::Z,
+                _ => Logic::X,
+            },
+            ("VDD", []) => Logic::True,
+            ("GND", []) => Logic::False,
+            _ => Logic::X,
+        };
+        let mut outputs = vec![Logic::X; self.outputs.len()];
+        if let Some(o) = outputs.first_mut() {
+            *o = out;
+        }
+        outputs
+    }
+
+    fn eval_words(&self, inputs: &[u64]) -> Vec<u64> {
+        let out = match (self.name.get_name(), inputs) {
+            ("AND", _) => inputs.iter().fold(!0, |a, b| a & b),
+            ("NAND", _) => !inputs.iter().fold(!0, |a, b| a & b),
+            ("OR", _) => inputs.iter().fold(0, |a, b| a | b),
+            ("NOR", _) => !inputs.iter().fold(0, |a, b| a | b),
+            ("XOR", _) => inputs.iter().fold(0, |a, b| a ^ b),
+            ("XNOR", _) => !inputs.iter().fold(0, |a, b| a ^ b),
+            ("INV" | "NOT", [a]) => !a,
+            ("BUF", [a]) => *a,
+            ("MUX", [s, a, b]) => (s & b) | (!s & a),
+            ("TBUF", [a, en]) => a & en,
+            ("VDD", []) => !0,
+            _ => 0,
+        };
+        let mut outputs = vec![0; self.outputs.len()];
+        if let Some(o) = outputs.first_mut() {
+            *o = out;
+        }
+        outputs
+    }
+}
+
 impl Gate {
     /// Creates a new gate primitive with four-state logic types
     pub fn new_logical(name: Identifier, inputs: Vec<Identifier>, output: Identifier) -> Self {
@@ -123,6 +254,16 @@ impl Gate {
         }
     }
 
+    /// Creates a new gate primitive with four-state inputs and a tri-state output, such as a tri-state buffer
+    pub fn new_tristate(name: Identifier, inputs: Vec<Identifier>, output: Identifier) -> Self {
+        let mut gate = Self::new_logical(name, inputs, output);
+        gate.outputs[0] = Net::new(
+            gate.outputs[0].get_identifier().clone(),
+            DataType::tristate(),
+        );
+        gate
+    }
+
     /// Returns the single output port of the gate
     pub fn get_single_output_port(&self) -> &Net {
         if self.outputs.len() > 1 {
@@ -154,6 +295,12 @@ enum Operand {
     CellIndex(usize, usize),
 }
 
+/// The number of input pins that are stored inline with a circuit node, which covers most cells
+pub const INLINE_PINS: usize = 6;
+
+/// The operands of the input pins of a circuit node, which only allocate for cells with more than [INLINE_PINS] inputs
+type Operands = SmallVec<[Option<Operand>; INLINE_PINS]>;
+
 impl Operand {
     /// Remap the node index of the operand to `x`.
     fn remap(self, x: usize) -> Self {
@@ -219,11 +366,19 @@ where
     /// The weak reference to the owner netlist/module
     owner: Weak<O>,
     /// The list of operands for the object
-    operands: Vec<Option<Operand>>,
+    operands: Operands,
     /// A collection of attributes for the object
     attributes: HashMap<AttributeKey, AttributeValue>,
+    /// Where the object came from
+    provenance: Option<Provenance>,
+    /// The user data of the object and its nets
+    payloads: payload::Payloads,
+    /// The traversal that last marked the object, see [Netlist::new_traversal]
+    mark: u64,
     /// The index of the object within the netlist/module
     index: usize,
+    /// The stable identifier of the object
+    id: ObjectId,
 }
 
 impl<I, O> OwnedObject<I, O>
@@ -422,7 +577,7 @@ where
         self.attributes.insert(k, None);
     }
 
-    fn insert_attribute(&mut self, k: AttributeKey, v: String) -> Option<AttributeValue> {
+    fn insert_attribute(&mut self, k: AttributeKey, v: Parameter) -> Option<AttributeValue> {
         self.attributes.insert(k, Some(v))
     }
 
@@ -512,6 +667,7 @@ where
     ///
     /// Panics if the circuit node has multiple outputs.
     pub fn as_net_mut(&self) -> RefMut<'_, Net> {
+        self.invalidate_names();
         RefMut::map(self.netref.borrow_mut(), |f| f.as_net_mut())
     }
 
@@ -522,6 +678,7 @@ where
 
     /// Returns a mutable borrow to the output [Net] as position `idx`
     pub fn get_net_mut(&self, idx: usize) -> RefMut<'_, Net> {
+        self.invalidate_names();
         RefMut::map(self.netref.borrow_mut(), |f| f.get_net_mut(idx))
     }
 
@@ -550,6 +707,19 @@ where
         Some(self.get_input(ind))
     }
 
+    /// Returns the stable identifier of the circuit node
+    pub fn get_id(&self) -> ObjectId {
+        self.netref.borrow().id
+    }
+
+    /// Marks the names of this circuit node as stale in the name index, before they may change
+    fn invalidate_names(&self) {
+        let owner = self.netref.borrow().owner.upgrade();
+        if let Some(netlist) = owner {
+            netlist.stale_names(self.get_id());
+        }
+    }
+
     /// Returns the name of the net at this circuit node.
     ///
     /// # Panics
@@ -605,6 +775,7 @@ where
     ///
     /// Panics if the circuit node is a principal input.
     pub fn set_instance_name(&self, name: Identifier) {
+        self.invalidate_names();
         match self.netref.borrow_mut().get_mut() {
             Object::Instance(_, inst_name, _) => *inst_name = name,
             _ => panic!("Attempted to set instance name on a non-instance object"),
@@ -741,6 +912,11 @@ where
         (0..len).map(move |i| InputPort::new(i, self.clone()))
     }
 
+    /// Returns an iterator to the input ports of this circuit node that are not connected to a driver.
+    pub fn unconnected_inputs(&self) -> impl Iterator<Item = InputPort<I>> {
+        self.inputs().filter(|i| i.get_driver().is_none())
+    }
+
     /// Returns an iterator to the output nets of this circuit node, along with port information.
     pub fn outputs(&self) -> impl Iterator<Item = DrivenNet<I>> {
         let len = self.netref.borrow().get().get_nets().len();
@@ -774,6 +950,7 @@ where
 
     /// Attempts to find a mutable reference to `net` within this circuit node.
     pub fn find_net_mut(&self, net: &Net) -> Option<RefMut<'_, Net>> {
+        self.invalidate_names();
         RefMut::filter_map(self.netref.borrow_mut(), |f| f.find_net_mut(net)).ok()
     }
 
@@ -824,8 +1001,23 @@ where
     }
 
     /// Insert an attribute on this node with a value
-    pub fn insert_attribute(&self, k: AttributeKey, v: String) -> Option<AttributeValue> {
-        self.netref.borrow_mut().insert_attribute(k, v)
+    pub fn insert_attribute(
+        &self,
+        k: AttributeKey,
+        v: impl Into<Parameter>,
+    ) -> Option<AttributeValue> {
+        self.netref.borrow_mut().insert_attribute(k, v.into())
+    }
+
+    /// Returns the value of the attribute with the given key on this circuit node.
+    /// Attributes without a value are returned as `Some(None)`.
+    pub fn get_attribute(&self, k: &AttributeKey) -> Option<AttributeValue> {
+        self.netref.borrow().attributes.get(k).cloned()
+    }
+
+    /// Returns `true` if this circuit node has an attribute with the given key
+    pub fn has_attribute(&self, k: &AttributeKey) -> bool {
+        self.netref.borrow().attributes.contains_key(k)
     }
 
     /// Returns an iterator to the attributes at this circuit node
@@ -903,6 +1095,25 @@ where
     }
 }
 
+/// The direction of a module port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PortDirection {
+    /// A principal input
+    Input,
+    /// A top-level output
+    Output,
+}
+
+impl PortDirection {
+    /// Returns the Verilog keyword for the direction
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PortDirection::Input => "input",
+            PortDirection::Output => "output",
+        }
+    }
+}
+
 /// A netlist data structure
 #[derive(Debug)]
 pub struct Netlist<I>
@@ -913,10 +1124,45 @@ where
     name: RefCell<String>,
     /// The list of objects in the netlist, such as inputs, modules, and primitives
     objects: RefCell<Vec<NetRefT<I>>>,
-    /// The list of operands that point to objects which are outputs
-    outputs: RefCell<HashMap<Operand, Net>>,
+    /// The output ports, in order of insertion, and the operands that drive them
+    outputs: RefCell<Vec<(Option<Operand>, Net)>>,
+    /// The aliases of nets, as in `assign y = a;`, in order of insertion, and the operands they name
+    aliases: RefCell<Vec<(Operand, Net)>>,
+    /// The names of the ports to emit first, in order
+    port_order: RefCell<Vec<Identifier>>,
+    /// How nets with multiple tri-state drivers are resolved, when not [Resolution::Tri]
+    resolutions: RefCell<HashMap<Identifier, Resolution>>,
+    /// A collection of attributes for the module
+    attributes: RefCell<HashMap<AttributeKey, AttributeValue>>,
+    /// A collection of attributes for each net
+    net_attributes: RefCell<HashMap<Identifier, HashMap<AttributeKey, AttributeValue>>>,
+    /// Where each net came from
+    net_provenance: RefCell<HashMap<Identifier, Provenance>>,
+    /// The pass that is editing the netlist, recorded in the provenance of new nodes
+    current_pass: RefCell<Option<String>>,
+    /// Whether edits to nodes marked [DONT_TOUCH] are rejected
+    enforce_dont_touch: Cell<bool>,
+    /// The index of the names of instances and nets, built on the first query after they change
+    name_index: RefCell<Option<select::NameIndex>>,
+    /// The identifier of the next object to be inserted
+    next_id: Cell<usize>,
+    /// The index of each object by identifier, when the objects are not sorted by identifier, as after [Netlist::canonicalize]
+    id_index: RefCell<Option<HashMap<ObjectId, usize>>>,
+    /// The current traversal, by which objects are marked
+    epoch: Cell<u64>,
+    /// The original names of the instances and nets, when they are recorded
+    name_origins: RefCell<Option<name_map::NameOrigins>>,
+    /// The structural hashing table of [Netlist::insert_gate], when it is on
+    strash: RefCell<Option<strash::StrashTable>>,
+    /// The input ports that read each driver, kept up to date by every edit
+    uses: RefCell<UseLists>,
+    /// The callbacks on mutation
+    observers: RefCell<observer::Observers<I>>,
 }
 
+/// The input ports that read each driver, as object and input indices
+type UseLists = HashMap<Operand, Vec<(usize, usize)>>;
+
 /// Represent the input port of a primitive
 #[derive(Debug, Clone)]
 pub struct InputPort<I: Instantiable> {
@@ -966,7 +1212,19 @@ where
     /// Disconnects an input port and returns the previous [DrivenNet] if it was connected.
     pub fn disconnect(&self) -> Option<DrivenNet<I>> {
         let val = self.get_driver();
-        self.netref.clone().unwrap().borrow_mut().operands[self.pos] = None;
+        if val.is_some() {
+            let netlist = self
+                .netref
+                .clone()
+                .unwrap()
+                .borrow()
+                .owner
+                .upgrade()
+                .expect("Input port is unlinked from netlist");
+            let index = self.netref.clone().unwrap().borrow().get_index();
+            netlist.set_operand(index, self.pos, None);
+            netlist.notify_reconnect(self);
+        }
         val
     }
 
@@ -987,6 +1245,14 @@ where
         output.connect(self);
     }
 
+    /// Connects this input port to `output` in place of its current driver, and returns the previous [DrivenNet] if it was connected.
+    /// Unlike [Netlist::replace_net_uses], the other input ports driven by the previous driver are left as they are.
+    pub fn reconnect(&self, output: DrivenNet<I>) -> Option<DrivenNet<I>> {
+        let val = self.get_driver();
+        output.connect(self.clone());
+        val
+    }
+
     /// Return the underlying circuit node
     pub fn unwrap(self) -> NetRef<I> {
         self.netref
@@ -1063,7 +1329,7 @@ where
     /// Connects the net driven by this output port to the given input port.
     pub fn connect(&self, input: InputPort<I>) {
         let operand = self.get_operand();
-        let index = input.netref.unwrap().borrow().get_index();
+        let index = input.netref.clone().unwrap().borrow().get_index();
         let netlist = self
             .netref
             .clone()
@@ -1072,8 +1338,8 @@ where
             .owner
             .upgrade()
             .expect("Output port is unlinked from netlist");
-        let obj = netlist.index_weak(&index);
-        obj.borrow_mut().operands[input.pos] = Some(operand.clone());
+        netlist.set_operand(index, input.pos, Some(operand));
+        netlist.notify_reconnect(&input);
     }
 
     /// Returns `true` if this net is a top-level output in the netlist.
@@ -1086,8 +1352,12 @@ where
             .owner
             .upgrade()
             .expect("DrivenNet is unlinked from netlist");
-        let outputs = netlist.outputs.borrow();
-        outputs.contains_key(&self.get_operand())
+        let operand = self.get_operand();
+        netlist
+            .outputs
+            .borrow()
+            .iter()
+            .any(|(o, _)| o.as_ref() == Some(&operand))
     }
 
     /// Return the underlying circuit node
@@ -1095,6 +1365,14 @@ where
         self.netref
     }
 
+    /// Returns the stable identifier of the net
+    pub fn get_id(&self) -> NetId {
+        NetId {
+            object: self.netref.get_id(),
+            output: self.pos,
+        }
+    }
+
     /// Returns a copy of the identifier of the net being driven.
     pub fn get_identifier(&self) -> Identifier {
         self.as_net().get_identifier().clone()
@@ -1183,7 +1461,23 @@ where
         Rc::new(Self {
             name: RefCell::new(name),
             objects: RefCell::new(Vec::new()),
-            outputs: RefCell::new(HashMap::new()),
+            outputs: RefCell::new(Vec::new()),
+            aliases: RefCell::new(Vec::new()),
+            port_order: RefCell::new(Vec::new()),
+            resolutions: RefCell::new(HashMap::new()),
+            attributes: RefCell::new(HashMap::new()),
+            net_attributes: RefCell::new(HashMap::new()),
+            net_provenance: RefCell::new(HashMap::new()),
+            current_pass: RefCell::new(None),
+            enforce_dont_touch: Cell::new(true),
+            name_index: RefCell::new(None),
+            next_id: Cell::new(0),
+            id_index: RefCell::new(None),
+            epoch: Cell::new(1),
+            name_origins: RefCell::new(None),
+            strash: RefCell::new(None),
+            uses: RefCell::new(HashMap::new()),
+            observers: RefCell::new(observer::Observers::default()),
         })
     }
 
@@ -1192,33 +1486,111 @@ where
         Rc::try_unwrap(self).ok()
     }
 
+    /// Returns a copy of the netlist, down to the positions and stable identifiers of its objects, without its observers
+    pub(crate) fn duplicate(&self) -> Rc<Self> {
+        let copy = Self::new(self.get_name().to_string());
+        let weak = Rc::downgrade(&copy);
+        *copy.objects.borrow_mut() = self
+            .objects
+            .borrow()
+            .iter()
+            .map(|obj| {
+                let obj = obj.borrow();
+                Rc::new(RefCell::new(OwnedObject {
+                    object: obj.object.clone(),
+                    owner: weak.clone(),
+                    operands: obj.operands.clone(),
+                    attributes: obj.attributes.clone(),
+                    provenance: obj.provenance.clone(),
+                    payloads: Default::default(),
+                    mark: 0,
+                    index: obj.index,
+                    id: obj.id,
+                }))
+            })
+            .collect();
+        *copy.outputs.borrow_mut() = self.outputs.borrow().clone();
+        *copy.aliases.borrow_mut() = self.aliases.borrow().clone();
+        *copy.port_order.borrow_mut() = self.port_order.borrow().clone();
+        *copy.resolutions.borrow_mut() = self.resolutions.borrow().clone();
+        *copy.attributes.borrow_mut() = self.attributes.borrow().clone();
+        *copy.net_attributes.borrow_mut() = self.net_attributes.borrow().clone();
+        *copy.net_provenance.borrow_mut() = self.net_provenance.borrow().clone();
+        *copy.current_pass.borrow_mut() = self.current_pass.borrow().clone();
+        copy.enforce_dont_touch.set(self.enforce_dont_touch.get());
+        copy.next_id.set(self.next_id.get());
+        *copy.id_index.borrow_mut() = self.id_index.borrow().clone();
+        *copy.name_origins.borrow_mut() = self.name_origins.borrow().clone();
+        *copy.strash.borrow_mut() = self.strash.borrow().clone();
+        *copy.uses.borrow_mut() = self.uses.borrow().clone();
+        copy
+    }
+
+    /// Appends an object with a fresh identifier, keeping the index by identifier up to date
+    fn push_object(&self, object: NetRefT<I>) {
+        if let Some(index) = self.id_index.borrow_mut().as_mut() {
+            index.insert(object.borrow().id, self.objects.borrow().len());
+        }
+        self.objects.borrow_mut().push(object);
+    }
+
+    /// Rebuilds the index by identifier after the objects moved, or drops it if they are sorted by identifier
+    fn rebuild_id_index(&self) {
+        let objects = self.objects.borrow();
+        let sorted = objects
+            .windows(2)
+            .all(|w| w[0].borrow().id < w[1].borrow().id);
+        *self.id_index.borrow_mut() = (!sorted).then(|| {
+            objects
+                .iter()
+                .enumerate()
+                .map(|(i, o)| (o.borrow().id, i))
+                .collect()
+        });
+    }
+
+    /// Returns a fresh object identifier
+    fn new_id(&self) -> ObjectId {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        ObjectId(id)
+    }
+
     /// Use interior mutability to add an object to the netlist. Returns a mutable reference to the created object.
     fn insert_object(
         self: &Rc<Self>,
         object: Object<I>,
-        operands: &[DrivenNet<I>],
+        operands: Operands,
     ) -> Result<NetRef<I>, Error> {
         let index = self.objects.borrow().len();
         let weak = Rc::downgrade(self);
-        let operands = operands
-            .iter()
-            .map(|net| Some(net.get_operand()))
-            .collect::<Vec<_>>();
         let owned_object = Rc::new(RefCell::new(OwnedObject {
             object,
             owner: weak,
             operands,
             attributes: HashMap::new(),
+            provenance: self.new_provenance(),
+            payloads: Default::default(),
+            mark: 0,
             index,
+            id: self.new_id(),
         }));
-        self.objects.borrow_mut().push(owned_object.clone());
-        Ok(NetRef::wrap(owned_object))
+        for (pos, operand) in owned_object.borrow().operands.iter().enumerate() {
+            if let Some(operand) = operand {
+                self.add_use(operand, index, pos);
+            }
+        }
+        self.push_object(owned_object.clone());
+        let netref = NetRef::wrap(owned_object);
+        self.stale_names(netref.get_id());
+        self.notify_insert(&netref);
+        Ok(netref)
     }
 
     /// Inserts an input net to the netlist
     pub fn insert_input(self: &Rc<Self>, net: Net) -> DrivenNet<I> {
         let obj = Object::Input(net);
-        self.insert_object(obj, &[]).unwrap().into()
+        self.insert_object(obj, Operands::new()).unwrap().into()
     }
 
     /// Inserts a four-state logic input port to the netlist
@@ -1233,22 +1605,77 @@ where
             .collect()
     }
 
-    /// Inserts a gate to the netlist
+    /// Inserts a gate to the netlist.
+    /// While [Netlist::set_strash] is on, returns the existing instance with the same cell and inputs instead, if any.
     pub fn insert_gate(
         self: &Rc<Self>,
         inst_type: I,
         inst_name: Identifier,
         operands: &[DrivenNet<I>],
     ) -> Result<NetRef<I>, Error> {
+        let input_count = inst_type.get_input_ports().into_iter().count();
+        if operands.len() != input_count {
+            return Err(Error::ArgumentMismatch(input_count, operands.len()));
+        }
+        let operands: Operands = operands.iter().map(|net| Some(net.get_operand())).collect();
+        if let Some(existing) = self.strash_lookup(&inst_type, &operands) {
+            trace_event!(trace, node = %existing, "sharing a structurally equal instance");
+            return Ok(existing);
+        }
         let nets = inst_type
             .get_output_ports()
             .into_iter()
             .map(|pnet| pnet.with_name(&inst_name + pnet.get_identifier()))
             .collect::<Vec<_>>();
+        let obj = Object::Instance(nets, inst_name, inst_type);
+        let netref = self.insert_object(obj, operands)?;
+        self.strash_record(&netref);
+        Ok(netref)
+    }
+
+    /// Inserts a gate to the netlist, connecting its input ports by name.
+    /// Every input port must be listed exactly once, either with its driver or as [Pin::Unconnected].
+    /// Returns [Error::PortNotFound] for a name that is not an input port of `inst_type`,
+    /// and [Error::UnconnectedInputs] for the input ports that are not listed.
+    pub fn insert_gate_named(
+        self: &Rc<Self>,
+        inst_type: I,
+        inst_name: Identifier,
+        connections: &[(&Identifier, Pin<I>)],
+    ) -> Result<NetRef<I>, Error> {
         let input_count = inst_type.get_input_ports().into_iter().count();
-        if operands.len() != input_count {
-            return Err(Error::ArgumentMismatch(input_count, operands.len()));
+        let mut pins: Vec<Option<&Pin<I>>> = vec![None; input_count];
+        for (id, pin) in connections {
+            let pos = inst_type
+                .find_input(id)
+                .ok_or_else(|| Error::PortNotFound((*id).clone()))?;
+            if pins[pos].replace(pin).is_some() {
+                return Err(Error::DuplicateConnection((*id).clone()));
+            }
+        }
+        let missing: Vec<(Identifier, Net)> = inst_type
+            .get_input_ports()
+            .into_iter()
+            .zip(pins.iter())
+            .filter(|(_, pin)| pin.is_none())
+            .map(|(port, _)| (inst_name.clone(), port.clone()))
+            .collect();
+        if !missing.is_empty() {
+            return Err(Error::UnconnectedInputs(missing));
         }
+
+        let operands = pins
+            .into_iter()
+            .map(|pin| match pin {
+                Some(Pin::Driven(net)) => Some(net.get_operand()),
+                _ => None,
+            })
+            .collect();
+        let nets = inst_type
+            .get_output_ports()
+            .into_iter()
+            .map(|pnet| pnet.with_name(&inst_name + pnet.get_identifier()))
+            .collect::<Vec<_>>();
         let obj = Object::Instance(nets, inst_name, inst_type);
         self.insert_object(obj, operands)
     }
@@ -1273,16 +1700,23 @@ where
             .get_input_ports()
             .into_iter()
             .count();
-        let operands = vec![None; input_count];
+        let operands = Operands::from_elem(None, input_count);
         let owned_object = Rc::new(RefCell::new(OwnedObject {
             object,
             owner: weak,
             operands,
             attributes: HashMap::new(),
+            provenance: self.new_provenance(),
+            payloads: Default::default(),
+            mark: 0,
             index,
+            id: self.new_id(),
         }));
-        self.objects.borrow_mut().push(owned_object.clone());
-        NetRef::wrap(owned_object)
+        self.push_object(owned_object.clone());
+        let netref = NetRef::wrap(owned_object);
+        self.stale_names(netref.get_id());
+        self.notify_insert(&netref);
+        netref
     }
 
     /// Inserts a constant [Logic] value to the netlist
@@ -1298,6 +1732,58 @@ where
         Ok(self.insert_gate_disconnected(obj, inst_name).into())
     }
 
+    /// Inserts a constant driver for each bit of `value`, named `inst_name` suffixed with the bit index.
+    /// Returns the nets of the bits, starting from the least significant bit.
+    pub fn insert_constant_vec(
+        self: &Rc<Self>,
+        value: &LogicVec,
+        inst_name: Identifier,
+    ) -> Result<Vec<DrivenNet<I>>, Error> {
+        value
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| self.insert_constant(bit, &inst_name + &format_id!("{i}")))
+            .collect()
+    }
+
+    /// Returns a driver of the constant `value`, reusing a constant cell of the netlist if there is one
+    fn constant_driver(self: &Rc<Self>, value: Logic) -> Result<DrivenNet<I>, Error> {
+        let existing = self.objects().find(|o| {
+            o.get_instance_type()
+                .is_some_and(|i| i.get_constant() == Some(value))
+        });
+        match existing {
+            Some(node) => Ok(node.get_output(0)),
+            None => {
+                let name = match value {
+                    Logic::False => "tie_0",
+                    Logic::True => "tie_1",
+                    Logic::X => "tie_x",
+                    Logic::Z => "tie_z",
+                };
+                self.insert_constant(value, name.into())
+            }
+        }
+    }
+
+    /// Drives the output bus `bus` with the constant `value`, where bit `i` of `value` drives the port `bus[i]`.
+    /// The bits share a single constant cell per value, and reuse the constant cells already in the netlist.
+    /// Returns the drivers of the bits, starting from the least significant bit.
+    pub fn tie_bus(
+        self: &Rc<Self>,
+        bus: &str,
+        value: &LogicVec,
+    ) -> Result<Vec<DrivenNet<I>>, Error> {
+        value
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| {
+                let driver = self.constant_driver(bit)?;
+                Ok(self.expose_net_with_name(driver, format_id!("{bus}[{i}]")))
+            })
+            .collect()
+    }
+
     /// Returns the driving node at input position `index` for `netref`
     ///
     /// # Panics
@@ -1308,10 +1794,22 @@ where
         Some(NetRef::wrap(self.index_weak(&op.root()).clone()))
     }
 
+    /// Drives the output port `port` with `operand`, inserting the port if it does not exist
+    fn bind_output(&self, operand: Option<Operand>, port: Net) {
+        let mut outputs = self.outputs.borrow_mut();
+        match outputs
+            .iter_mut()
+            .find(|(_, n)| n.get_identifier() == port.get_identifier())
+        {
+            Some(output) => *output = (operand, port),
+            None => outputs.push((operand, port)),
+        }
+    }
+
     /// Set an added object as a top-level output.
+    /// A net may drive several outputs with different names, and an existing output named `name` is driven by `net` instead.
     pub fn expose_net_with_name(&self, net: DrivenNet<I>, name: Identifier) -> DrivenNet<I> {
-        let mut outputs = self.outputs.borrow_mut();
-        outputs.insert(net.get_operand(), net.as_net().with_name(name));
+        self.bind_output(Some(net.get_operand()), net.as_net().with_name(name));
         net
     }
 
@@ -1320,50 +1818,354 @@ where
         if net.is_an_input() {
             return Err(Error::InputNeedsAlias(net.as_net().clone()));
         }
-        let mut outputs = self.outputs.borrow_mut();
-        outputs.insert(net.get_operand(), net.as_net().clone());
+        self.bind_output(Some(net.get_operand()), net.as_net().clone());
         Ok(net)
     }
 
+    /// Inserts an output port named `id` which is not yet driven.
+    /// Use [Netlist::drive_output] to connect it.
+    /// Returns an error if the name is already taken by a port.
+    pub fn insert_output(&self, id: Identifier) -> Result<(), Error> {
+        if self
+            .get_input_ports()
+            .chain(self.get_output_ports())
+            .any(|n| *n.get_identifier() == id)
+        {
+            return Err(Error::NonuniqueNets(vec![Net::new_logic(id)]));
+        }
+        self.outputs.borrow_mut().push((None, Net::new_logic(id)));
+        Ok(())
+    }
+
+    /// Drives the output port named `id` with `net`.
+    /// Returns an error if there is no such output port.
+    pub fn drive_output(&self, id: &Identifier, net: DrivenNet<I>) -> Result<(), Error> {
+        let mut outputs = self.outputs.borrow_mut();
+        let output = outputs
+            .iter_mut()
+            .find(|(_, n)| n.get_identifier() == id)
+            .ok_or(Error::PortNotFound(id.clone()))?;
+        output.0 = Some(net.get_operand());
+        Ok(())
+    }
+
+    /// Disconnects the output port named `id` from its driver, returning the driver.
+    /// Returns an error if there is no such output port.
+    pub fn disconnect_output(&self, id: &Identifier) -> Result<Option<DrivenNet<I>>, Error> {
+        let operand = self
+            .outputs
+            .borrow_mut()
+            .iter_mut()
+            .find(|(_, n)| n.get_identifier() == id)
+            .ok_or(Error::PortNotFound(id.clone()))?
+            .0
+            .take();
+        Ok(operand.map(|o| self.driven_net(&o)))
+    }
+
+    /// Removes the output port named `id` from the module.
+    /// Returns an error if there is no such output port.
+    pub fn remove_output(&self, id: &Identifier) -> Result<(), Error> {
+        let mut outputs = self.outputs.borrow_mut();
+        let pos = outputs
+            .iter()
+            .position(|(_, n)| n.get_identifier() == id)
+            .ok_or(Error::PortNotFound(id.clone()))?;
+        outputs.remove(pos);
+        Ok(())
+    }
+
+    /// Returns the net driving the output port named `id`, if it exists and is connected
+    pub fn get_output_driver(&self, id: &Identifier) -> Option<DrivenNet<I>> {
+        let outputs = self.outputs.borrow();
+        let (operand, _) = outputs.iter().find(|(_, n)| n.get_identifier() == id)?;
+        operand.as_ref().map(|o| self.driven_net(o))
+    }
+
+    /// Returns the net referred to by `operand`
+    fn driven_net(&self, operand: &Operand) -> DrivenNet<I> {
+        DrivenNet::new(
+            operand.secondary(),
+            NetRef::wrap(self.index_weak(&operand.root())),
+        )
+    }
+
+    /// Returns `true` if `obj` is protected from edits by a [DONT_TOUCH] attribute,
+    /// either on the object itself or on one of the nets it drives, or is a [SPARE] cell.
+    fn is_protected(&self, obj: &OwnedObject<I, Self>) -> bool {
+        let key = DONT_TOUCH.to_string();
+        self.enforce_dont_touch.get()
+            && (obj.attributes.contains_key(&key)
+                || obj.attributes.contains_key(SPARE)
+                || obj
+                    .get()
+                    .get_nets()
+                    .iter()
+                    .any(|n| self.net_has_attribute(n, &key)))
+    }
+
+    /// Returns `true` if `node` is protected from edits, like [Netlist::is_protected]
+    pub(crate) fn is_node_protected(&self, node: &NetRef<I>) -> bool {
+        self.is_protected(&node.netref.borrow())
+    }
+
+    /// Returns an error if the object at `index`, or any object of the input ports in `readers`, is protected.
+    fn check_dont_touch(&self, index: usize, readers: &[(usize, usize)]) -> Result<(), Error> {
+        let mut indices: Vec<usize> = readers.iter().map(|(i, _)| *i).collect();
+        indices.push(index);
+        indices.sort_unstable();
+        indices.dedup();
+        let objects = self.objects.borrow();
+        let protected: Vec<Net> = indices
+            .into_iter()
+            .map(|i| objects[i].borrow())
+            .filter(|obj| self.is_protected(obj))
+            .flat_map(|obj| obj.get().get_nets().to_vec())
+            .collect();
+        if protected.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::DontTouch(protected))
+        }
+    }
+
+    /// Records that input `pos` of the object at `index` reads `operand`
+    fn add_use(&self, operand: &Operand, index: usize, pos: usize) {
+        self.uses
+            .borrow_mut()
+            .entry(operand.clone())
+            .or_default()
+            .push((index, pos));
+    }
+
+    /// Forgets that input `pos` of the object at `index` reads `operand`
+    fn remove_use(&self, operand: &Operand, index: usize, pos: usize) {
+        let mut uses = self.uses.borrow_mut();
+        if let Some(readers) = uses.get_mut(operand) {
+            readers.retain(|r| *r != (index, pos));
+            if readers.is_empty() {
+                uses.remove(operand);
+            }
+        }
+    }
+
+    /// Sets the operand of input `pos` of the object at `index` and updates the use lists. Returns the previous operand.
+    fn set_operand(&self, index: usize, pos: usize, operand: Option<Operand>) -> Option<Operand> {
+        let old = std::mem::replace(
+            &mut self.index_weak(&index).borrow_mut().operands[pos],
+            operand.clone(),
+        );
+        if let Some(old) = &old {
+            self.remove_use(old, index, pos);
+        }
+        if let Some(new) = &operand {
+            self.add_use(new, index, pos);
+        }
+        old
+    }
+
+    /// Returns the use lists that the operands of the objects imply
+    fn collect_uses(&self) -> UseLists {
+        let mut uses = UseLists::new();
+        for obj in self.objects.borrow().iter() {
+            let obj = obj.borrow();
+            for (pos, operand) in obj.operands.iter().enumerate() {
+                if let Some(operand) = operand {
+                    uses.entry(operand.clone())
+                        .or_default()
+                        .push((obj.index, pos));
+                }
+            }
+        }
+        uses
+    }
+
+    /// Rebuilds the use lists from the operands of the objects, after they were changed in bulk
+    fn rebuild_uses(&self) {
+        *self.uses.borrow_mut() = self.collect_uses();
+    }
+
+    /// Returns `true` if the use lists agree with the operands of the objects
+    pub(crate) fn uses_consistent(&self) -> bool {
+        let mut expected = self.collect_uses();
+        let mut actual = self.uses.borrow().clone();
+        for readers in expected.values_mut().chain(actual.values_mut()) {
+            readers.sort_unstable();
+        }
+        expected == actual
+    }
+
+    /// Returns the operands that refer to the outputs of the object at `index`
+    fn operands_of(&self, index: usize) -> impl Iterator<Item = Operand> {
+        let n = self.objects.borrow()[index].borrow().get().get_nets().len();
+        std::iter::once(Operand::DirectIndex(index))
+            .chain((0..n).map(move |j| Operand::CellIndex(index, j)))
+    }
+
+    /// Returns the input ports that read `operand`, as object and input indices in netlist order
+    fn readers_of(&self, operand: &Operand) -> Vec<(usize, usize)> {
+        let mut readers = self.uses.borrow().get(operand).cloned().unwrap_or_default();
+        readers.sort_unstable();
+        readers
+    }
+
+    /// Returns the input ports that read any output of the object at `index`, in netlist order
+    fn readers_of_object(&self, index: usize) -> Vec<(usize, usize)> {
+        let uses = self.uses.borrow();
+        let mut readers: Vec<(usize, usize)> = self
+            .operands_of(index)
+            .filter_map(|op| uses.get(&op))
+            .flatten()
+            .copied()
+            .collect();
+        readers.sort_unstable();
+        readers
+    }
+
+    /// Returns the input ports that read `net`, in the order of their circuit nodes.
+    /// The readers of every driver are kept up to date by the edits, so this takes time in the number of readers
+    /// rather than in the size of the netlist. Top-level outputs are not input ports, see [DrivenNet::is_top_level_output].
+    pub fn get_uses(&self, net: &DrivenNet<I>) -> Vec<InputPort<I>> {
+        let objects = self.objects.borrow();
+        self.readers_of(&net.get_operand())
+            .into_iter()
+            .map(|(index, pos)| InputPort::new(pos, NetRef::wrap(objects[index].clone())))
+            .collect()
+    }
+
+    /// Sets whether edits to nodes marked [DONT_TOUCH] or [SPARE] are rejected, which is the default.
+    /// While enforced, [Netlist::delete_net_uses] and [Netlist::replace_net_uses] return an error
+    /// when they would disconnect or reconnect a protected node, and [Netlist::clean] keeps them.
+    pub fn enforce_dont_touch(&self, enforce: bool) {
+        self.enforce_dont_touch.set(enforce);
+    }
+
+    /// Returns `true` if edits to nodes marked [DONT_TOUCH] are rejected
+    pub fn is_dont_touch_enforced(&self) -> bool {
+        self.enforce_dont_touch.get()
+    }
+
     /// Unlink a circuit node from the rest of the netlist. Return the object that was being stored.
     pub fn delete_net_uses(&self, netref: NetRef<I>) -> Result<Object<I>, Error> {
         let unwrapped = netref.clone().unwrap();
+        let old_index = unwrapped.borrow().get_index();
+        let readers = self.readers_of_object(old_index);
+        self.check_dont_touch(old_index, &readers)?;
         if Rc::strong_count(&unwrapped) > 3 {
             return Err(Error::DanglingReference(netref.nets().collect()));
         }
-        let old_index = unwrapped.borrow().get_index();
+        let mut reconnected = Vec::new();
         let objects = self.objects.borrow();
-        for oref in objects.iter() {
-            let operands = &mut oref.borrow_mut().operands;
-            for operand in operands.iter_mut() {
-                if let Some(op) = operand {
-                    match op {
-                        Operand::DirectIndex(idx) | Operand::CellIndex(idx, _)
-                            if *idx == old_index =>
-                        {
-                            *operand = None;
-                        }
-                        _ => (),
-                    }
+        for (index, pos) in readers {
+            objects[index].borrow_mut().operands[pos] = None;
+            reconnected.push(InputPort::new(pos, NetRef::wrap(objects[index].clone())));
+        }
+        drop(objects);
+        let operands: Vec<Operand> = self.operands_of(old_index).collect();
+        let mut uses = self.uses.borrow_mut();
+        for operand in operands {
+            uses.remove(&operand);
+        }
+        drop(uses);
+
+        self.outputs
+            .borrow_mut()
+            .retain(|(operand, _)| operand.as_ref().is_none_or(|o| o.root() != old_index));
+        self.aliases
+            .borrow_mut()
+            .retain(|(operand, _)| operand.root() != old_index);
+
+        for port in reconnected {
+            self.notify_reconnect(&port);
+        }
+
+        Ok(netref.unwrap().borrow().get().clone())
+    }
+
+    /// Removes the instance at `netref` from the netlist, handling the remaining uses of its outputs according to `policy`.
+    /// A constant is only inserted to tie uses that remain, and an instance that drives its own input cannot be stitched through.
+    /// The [Object] stored at `netref` is returned.
+    pub fn remove_instance(
+        self: &Rc<Self>,
+        netref: NetRef<I>,
+        policy: RemovePolicy,
+    ) -> Result<Object<I>, Error> {
+        if netref.is_an_input() {
+            return Err(Error::InstantiableError(format!(
+                "Cannot remove the input {} as an instance",
+                netref.get_net(0)
+            )));
+        }
+        let unwrapped = netref.clone().unwrap();
+        let index = unwrapped.borrow().get_index();
+        let readers = self.readers_of_object(index);
+        self.check_dont_touch(index, &readers)?;
+        if Rc::strong_count(&unwrapped) > 3 {
+            return Err(Error::DanglingReference(netref.nets().collect()));
+        }
+
+        let is_use = |op: &Option<Operand>| op.as_ref().is_some_and(|o| o.root() == index);
+        let has_uses = !readers.is_empty() || self.outputs.borrow().iter().any(|(o, _)| is_use(o));
+
+        // The net that takes the place of each output. A constant is only made if the outputs have uses
+        let replacement: Option<DrivenNet<I>> = match policy {
+            RemovePolicy::Reject => None,
+            RemovePolicy::Tie(value) if has_uses => Some(self.constant_driver(value)?),
+            RemovePolicy::Tie(_) => None,
+            RemovePolicy::StitchThrough => {
+                let inputs = netref.get_num_input_ports();
+                if inputs != 1 {
+                    return Err(Error::ArgumentMismatch(1, inputs));
                 }
+                let driver = netref.get_input(0).get_driver().ok_or_else(|| {
+                    let name = netref.get_instance_name().unwrap();
+                    Error::UnconnectedInputs(vec![(name, netref.get_input(0).get_port())])
+                })?;
+                // An instance that drives its own input has no driver to stitch its uses to
+                if driver.netref == netref {
+                    return Err(Error::CycleDetected(netref.nets().collect()));
+                }
+                Some(driver)
             }
+        };
+        if has_uses && replacement.is_none() {
+            return Err(Error::DanglingReference(netref.nets().collect()));
         }
 
-        let outputs: Vec<Operand> = self
-            .outputs
-            .borrow()
-            .keys()
-            .filter(|operand| match operand {
-                Operand::DirectIndex(idx) | Operand::CellIndex(idx, _) => *idx == old_index,
-            })
-            .cloned()
-            .collect();
-
-        for operand in outputs {
-            self.outputs.borrow_mut().remove(&operand);
+        // The use lists are rebuilt when the object is removed
+        let mut reconnected = Vec::new();
+        if let Some(replacement) = replacement {
+            for output in netref.outputs() {
+                self.record_merge(output.get_id(), replacement.get_id());
+            }
+            let replacement = replacement.get_operand();
+            let objects = self.objects.borrow();
+            for (i, pos) in readers.into_iter().filter(|(i, _)| *i != index) {
+                objects[i].borrow_mut().operands[pos] = Some(replacement.clone());
+                reconnected.push((objects[i].clone(), pos));
+            }
+            drop(objects);
+            for (operand, _) in self.outputs.borrow_mut().iter_mut() {
+                if is_use(operand) {
+                    *operand = Some(replacement.clone());
+                }
+            }
+            for (operand, _) in self.aliases.borrow_mut().iter_mut() {
+                if operand.root() == index {
+                    *operand = replacement.clone();
+                }
+            }
         }
 
-        Ok(netref.unwrap().borrow().get().clone())
+        let object = unwrapped.borrow().get().clone();
+        drop(unwrapped);
+        drop(netref);
+        self.remove_objects(&HashSet::from([index]))?;
+
+        for (oref, pos) in reconnected {
+            self.notify_reconnect(&InputPort::new(pos, NetRef::wrap(oref)));
+        }
+        Ok(object)
     }
 
     /// Replaces the uses of a circuit node with another circuit node. The [Object] stored at `of` is returned.
@@ -1372,6 +2174,11 @@ where
         of: DrivenNet<I>,
         with: &DrivenNet<I>,
     ) -> Result<Object<I>, Error> {
+        debug_span!("replace_net_uses", of = %of, with = %with);
+        let old_index = of.get_operand();
+        let readers = self.readers_of(&old_index);
+        self.check_dont_touch(old_index.root(), &readers)?;
+
         let unwrapped = of.clone().unwrap().unwrap();
         let i = of.get_output_index();
         let k = with.get_output_index();
@@ -1384,34 +2191,63 @@ where
             return Err(Error::DanglingReference(of.unwrap().nets().collect()));
         }
 
-        let old_index = of.get_operand();
-
-        if let Some(v) = self.outputs.borrow().get(&old_index)
-            && *v == *of.as_net()
+        if let Some((_, v)) = self
+            .outputs
+            .borrow()
+            .iter()
+            .find(|(o, v)| o.as_ref() == Some(&old_index) && *v == *of.as_net())
         {
             return Err(Error::NonuniqueNets(vec![v.clone()]));
         }
 
         let new_index = with.get_operand();
+        let mut reconnected = Vec::new();
         let objects = self.objects.borrow();
-        for oref in objects.iter() {
-            let operands = &mut oref.borrow_mut().operands;
-            for operand in operands.iter_mut() {
-                if let Some(op) = operand
-                    && *op == old_index
-                {
-                    *operand = Some(new_index.clone());
-                }
+        for (index, pos) in readers {
+            objects[index].borrow_mut().operands[pos] = Some(new_index.clone());
+            reconnected.push((objects[index].clone(), pos));
+        }
+        drop(objects);
+        let mut uses = self.uses.borrow_mut();
+        if let Some(moved) = uses.remove(&old_index) {
+            uses.entry(new_index.clone()).or_default().extend(moved);
+        }
+        drop(uses);
+
+        for (operand, _) in self.outputs.borrow_mut().iter_mut() {
+            if *operand == Some(old_index.clone()) {
+                *operand = Some(new_index.clone());
+            }
+        }
+        for (operand, _) in self.aliases.borrow_mut().iter_mut() {
+            if *operand == old_index {
+                *operand = new_index.clone();
             }
         }
 
-        let already_mapped = self.outputs.borrow().contains_key(&new_index);
-        let old_mapping = self.outputs.borrow_mut().remove(&old_index);
+        // The replacement inherits the provenance that it does not know itself
+        let replacement = with.clone().unwrap().unwrap();
+        if !Rc::ptr_eq(&unwrapped, &replacement)
+            && let Some(old) = unwrapped.borrow().provenance.clone()
+        {
+            replacement
+                .borrow_mut()
+                .provenance
+                .get_or_insert_default()
+                .inherit(&old);
+        }
+        let mut net_provenance = self.net_provenance.borrow_mut();
+        if let Some(old) = net_provenance.get(of.as_net().get_identifier()).cloned() {
+            net_provenance
+                .entry(with.as_net().get_identifier().clone())
+                .or_default()
+                .inherit(&old);
+        }
+        drop(net_provenance);
+        self.record_merge(of.get_id(), with.get_id());
 
-        if already_mapped {
-            self.outputs.borrow_mut().remove(&old_index);
-        } else if let Some(v) = old_mapping {
-            self.outputs.borrow_mut().insert(new_index, v.clone());
+        for (oref, pos) in reconnected {
+            self.notify_reconnect(&InputPort::new(pos, NetRef::wrap(oref)));
         }
 
         Ok(of.unwrap().unwrap().borrow().get().clone())
@@ -1427,6 +2263,16 @@ where
         self.name.borrow()
     }
 
+    /// Returns the hierarchical path of the net or instance named `id`, rooted at this module.
+    /// The hierarchy is recovered from the flattened name by splitting it on `sep`, as in `u_core/g3`.
+    pub fn get_hier_path(&self, id: &Identifier, sep: char) -> Result<HierPath, Error> {
+        let top = Identifier::new(self.get_name().clone());
+        let path = HierPath::split(id, sep)?;
+        Ok(HierPath::new(
+            std::iter::once(top).chain(path.components().iter().cloned()),
+        ))
+    }
+
     /// Sets the name of the netlist module
     /// # Panics
     ///
@@ -1446,9 +2292,87 @@ where
         })
     }
 
-    /// Returns a list of output nets
+    /// Returns a list of output nets, including the outputs which are not driven
     pub fn get_output_ports(&self) -> Vec<Net> {
-        self.outputs.borrow().values().cloned().collect::<Vec<_>>()
+        self.outputs
+            .borrow()
+            .iter()
+            .map(|(_, n)| n.clone())
+            .collect::<Vec<_>>()
+    }
+
+    /// Returns the module ports in the order they are emitted.
+    /// The ports named by [Netlist::set_port_order] come first, followed by the remaining inputs and then the remaining outputs.
+    pub fn get_ports(&self) -> Vec<(PortDirection, Net)> {
+        let mut ports: Vec<_> = self
+            .get_input_ports()
+            .map(|n| (PortDirection::Input, n))
+            .chain(
+                self.get_output_ports()
+                    .into_iter()
+                    .map(|n| (PortDirection::Output, n)),
+            )
+            .collect();
+        let order = self.port_order.borrow();
+        ports.sort_by_key(|(_, n)| {
+            order
+                .iter()
+                .position(|id| id == n.get_identifier())
+                .unwrap_or(order.len())
+        });
+        ports
+    }
+
+    /// Sets the order in which the module ports are emitted, so that the module can match an existing port list.
+    /// Ports left out of `order` follow in their default order.
+    /// Returns an error if an identifier in `order` does not name a port.
+    pub fn set_port_order(&self, order: impl IntoIterator<Item = Identifier>) -> Result<(), Error> {
+        let order: Vec<Identifier> = order.into_iter().collect();
+        let ports: HashSet<Identifier> = self
+            .get_input_ports()
+            .chain(self.get_output_ports())
+            .map(|n| n.take_identifier())
+            .collect();
+        if let Some(missing) = order.iter().find(|id| !ports.contains(*id)) {
+            return Err(Error::PortNotFound(missing.clone()));
+        }
+        *self.port_order.borrow_mut() = order;
+        Ok(())
+    }
+
+    /// Orders the module ports into groups: first the inputs selected by `is_clock`, then the other inputs, and then the outputs.
+    /// Within a group, the bits of a bus are kept together in ascending order, at the position of the first bit of the bus.
+    pub fn group_ports(&self, is_clock: impl Fn(&Net) -> bool) {
+        let ports = self.get_ports();
+        let group = |(dir, net): &(PortDirection, Net)| match dir {
+            PortDirection::Input if is_clock(net) => 0,
+            PortDirection::Input => 1,
+            PortDirection::Output => 2,
+        };
+        let mut order: Vec<Identifier> = Vec::with_capacity(ports.len());
+        for g in 0..3 {
+            let members: Vec<&Identifier> = ports
+                .iter()
+                .filter(|p| group(p) == g)
+                .map(|(_, n)| n.get_identifier())
+                .collect();
+            for (i, id) in members.iter().enumerate() {
+                if !id.is_sliced() {
+                    order.push((*id).clone());
+                    continue;
+                }
+                let same_bus =
+                    |other: &&Identifier| other.is_sliced() && other.get_name() == id.get_name();
+                if members[..i].iter().any(same_bus) {
+                    continue;
+                }
+                let mut bus: Vec<&Identifier> =
+                    members[i..].iter().copied().filter(same_bus).collect();
+                bus.sort_by_key(|b| b.get_bit_index());
+                order.extend(bus.into_iter().cloned());
+            }
+        }
+        *self.port_order.borrow_mut() = order;
     }
 
     /// Constructs an analysis of the netlist.
@@ -1469,6 +2393,240 @@ where
         None
     }
 
+    /// Finds all the circuit nodes that drive `net`. This operation is O(n).
+    /// Only tri-state buses have more than one driver, provided the netlist is well-formed.
+    pub fn find_drivers(&self, net: &Net) -> Vec<DrivenNet<I>> {
+        self.objects()
+            .flat_map(|obj| obj.outputs().collect::<Vec<_>>())
+            .filter(|o| o.as_net().get_identifier() == net.get_identifier())
+            .collect()
+    }
+
+    /// Sets how the tri-state bus `net` resolves its drivers.
+    pub fn set_resolution(&self, net: &Net, resolution: Resolution) {
+        let mut resolutions = self.resolutions.borrow_mut();
+        if resolution == Resolution::default() {
+            resolutions.remove(net.get_identifier());
+        } else {
+            resolutions.insert(net.get_identifier().clone(), resolution);
+        }
+    }
+
+    /// Returns how the tri-state bus `net` resolves its drivers.
+    pub fn get_resolution(&self, net: &Net) -> Resolution {
+        self.resolutions
+            .borrow()
+            .get(net.get_identifier())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Renames every net for which `rename` returns a new identifier, including output ports and net annotations.
+    /// Returns the number of renamed identifiers, or an error if a new identifier is already taken.
+    fn rename_nets(
+        &self,
+        rename: impl Fn(&Identifier) -> Option<Identifier>,
+    ) -> Result<usize, Error> {
+        let mut names: HashMap<Identifier, Identifier> = HashMap::new();
+        let mut kept: HashSet<Identifier> = HashSet::new();
+        let mut visit = |id: &Identifier| match rename(id) {
+            Some(new) => {
+                names.insert(id.clone(), new);
+            }
+            None => {
+                kept.insert(id.clone());
+            }
+        };
+        for obj in self.objects.borrow().iter() {
+            obj.borrow()
+                .get()
+                .get_nets()
+                .iter()
+                .for_each(|n| visit(n.get_identifier()));
+        }
+        for (_, net) in self.outputs.borrow().iter() {
+            visit(net.get_identifier());
+        }
+
+        let mut taken = HashSet::new();
+        let clashes: Vec<Net> = names
+            .values()
+            .filter(|new| kept.contains(*new) || !taken.insert(*new))
+            .map(|new| Net::new_logic(new.clone()))
+            .collect();
+        if !clashes.is_empty() {
+            return Err(Error::NonuniqueNets(clashes));
+        }
+
+        let apply = |net: &mut Net| {
+            if let Some(new) = names.get(net.get_identifier()) {
+                net.set_identifier(new.clone());
+            }
+        };
+        self.invalidate_names();
+        for obj in self.objects.borrow().iter() {
+            obj.borrow_mut()
+                .get_mut()
+                .get_nets_mut()
+                .iter_mut()
+                .for_each(apply);
+        }
+        for (_, net) in self.outputs.borrow_mut().iter_mut() {
+            apply(net);
+        }
+        let rekey = |id: Identifier| names.get(&id).cloned().unwrap_or(id);
+        let resolutions = self.resolutions.take();
+        *self.resolutions.borrow_mut() = resolutions
+            .into_iter()
+            .map(|(k, v)| (rekey(k), v))
+            .collect();
+        let net_attributes = self.net_attributes.take();
+        *self.net_attributes.borrow_mut() = net_attributes
+            .into_iter()
+            .map(|(k, v)| (rekey(k), v))
+            .collect();
+        let net_provenance = self.net_provenance.take();
+        *self.net_provenance.borrow_mut() = net_provenance
+            .into_iter()
+            .map(|(k, v)| (rekey(k), v))
+            .collect();
+        let port_order = self.port_order.take();
+        *self.port_order.borrow_mut() = port_order.into_iter().map(rekey).collect();
+        Ok(names.len())
+    }
+
+    /// Splits the buses of the netlist into scalar nets: each bus bit `x[i]` becomes a scalar net named `\x[i] `.
+    /// Returns the number of renamed nets, or an error if a scalar name is already taken.
+    pub fn bitblast(&self) -> Result<usize, Error> {
+        self.rename_nets(|id| {
+            let index = id.get_bit_index()?;
+            Some(Identifier::new(format!("\\{}[{}]", id.get_name(), index)))
+        })
+    }
+
+    /// Regroups scalar nets named `x[0]`..`x[n]` into bus bits, where the name `x` of the bus matches the glob `pattern`
+    /// (`*` matches any sequence of characters and `?` any single character).
+    /// This is the inverse of [Netlist::bitblast].
+    /// Returns the number of renamed nets, or an error if a bus bit is already taken.
+    pub fn reaggregate(&self, pattern: &str) -> Result<usize, Error> {
+        self.rename_nets(|id| {
+            if !id.is_escaped() {
+                return None;
+            }
+            let (bus, index) = id.get_name().strip_suffix(']')?.rsplit_once('[')?;
+            if !glob_match(pattern, bus) {
+                return None;
+            }
+            let index: usize = index.parse().ok()?;
+            let new = Identifier::new(format!("{bus}[{index}]"));
+            new.is_sliced().then_some(new)
+        })
+    }
+
+    /// Clears the attribute with the given key on the module.
+    pub fn clear_attribute(&self, k: &AttributeKey) -> Option<AttributeValue> {
+        self.attributes.borrow_mut().remove(k)
+    }
+
+    /// Set an attribute on the module without a value
+    pub fn set_attribute(&self, k: AttributeKey) {
+        self.attributes.borrow_mut().insert(k, None);
+    }
+
+    /// Insert an attribute on the module with a value
+    pub fn insert_attribute(
+        &self,
+        k: AttributeKey,
+        v: impl Into<Parameter>,
+    ) -> Option<AttributeValue> {
+        self.attributes.borrow_mut().insert(k, Some(v.into()))
+    }
+
+    /// Returns an iterator to the attributes of the module
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute> {
+        let v: Vec<_> =
+            Attribute::from_pairs(self.attributes.borrow().clone().into_iter()).collect();
+        v.into_iter()
+    }
+
+    /// Returns `true` if `net` has an attribute with the given key
+    pub fn net_has_attribute(&self, net: &Net, k: &AttributeKey) -> bool {
+        self.net_attributes
+            .borrow()
+            .get(net.get_identifier())
+            .is_some_and(|a| a.contains_key(k))
+    }
+
+    /// Returns an iterator to the circuit nodes that have an attribute with the given key,
+    /// either on the node itself or on one of the nets it drives.
+    pub fn objects_with_attribute(&self, k: &AttributeKey) -> impl Iterator<Item = NetRef<I>> {
+        self.objects().filter(move |obj| {
+            obj.has_attribute(k) || obj.nets().any(|net| self.net_has_attribute(&net, k))
+        })
+    }
+
+    /// Clears the attribute with the given key on `net`.
+    pub fn clear_net_attribute(&self, net: &Net, k: &AttributeKey) -> Option<AttributeValue> {
+        let mut net_attributes = self.net_attributes.borrow_mut();
+        let attributes = net_attributes.get_mut(net.get_identifier())?;
+        let old = attributes.remove(k);
+        if attributes.is_empty() {
+            net_attributes.remove(net.get_identifier());
+        }
+        old
+    }
+
+    /// Set an attribute on `net` without a value
+    pub fn set_net_attribute(&self, net: &Net, k: AttributeKey) {
+        self.net_attributes
+            .borrow_mut()
+            .entry(net.get_identifier().clone())
+            .or_default()
+            .insert(k, None);
+    }
+
+    /// Insert an attribute on `net` with a value
+    pub fn insert_net_attribute(
+        &self,
+        net: &Net,
+        k: AttributeKey,
+        v: impl Into<Parameter>,
+    ) -> Option<AttributeValue> {
+        self.net_attributes
+            .borrow_mut()
+            .entry(net.get_identifier().clone())
+            .or_default()
+            .insert(k, Some(v.into()))
+    }
+
+    /// Returns an iterator to the attributes of `net`
+    pub fn net_attributes(&self, net: &Net) -> impl Iterator<Item = Attribute> {
+        let v: Vec<_> = self
+            .net_attributes
+            .borrow()
+            .get(net.get_identifier())
+            .map(|a| Attribute::from_pairs(a.clone().into_iter()).collect())
+            .unwrap_or_default();
+        v.into_iter()
+    }
+
+    /// Finds the circuit node with the stable identifier `id`, if it is still in the netlist.
+    pub fn find_object(&self, id: ObjectId) -> Option<NetRef<I>> {
+        let objects = self.objects.borrow();
+        let index = match self.id_index.borrow().as_ref() {
+            Some(index) => *index.get(&id)?,
+            // Objects that are only ever appended or removed remain sorted by identifier
+            None => objects.binary_search_by_key(&id, |o| o.borrow().id).ok()?,
+        };
+        Some(NetRef::wrap(objects[index].clone()))
+    }
+
+    /// Finds the net with the stable identifier `id`, if it is still in the netlist.
+    pub fn find_net_by_id(&self, id: NetId) -> Option<DrivenNet<I>> {
+        let node = self.find_object(id.object)?;
+        (id.output < node.outputs().count()).then(|| DrivenNet::new(id.output, node))
+    }
+
     /// Returns a `NetRef` to the first circuit node
     pub fn first(&self) -> Option<NetRef<I>> {
         self.objects
@@ -1488,48 +2646,27 @@ where
     /// Returns `true` if an output of `netref` which is driving a module output.
     pub fn drives_an_output(&self, netref: NetRef<I>) -> bool {
         let my_index = netref.unwrap().borrow().get_index();
-        for key in self.outputs.borrow().keys() {
-            if key.root() == my_index {
-                return true;
-            }
-        }
-        false
+        self.outputs
+            .borrow()
+            .iter()
+            .any(|(o, _)| o.as_ref().is_some_and(|o| o.root() == my_index))
     }
 
-    /// Cleans unused nodes from the netlist, returning `Ok(true)` if the netlist changed.
-    pub fn clean_once(&self) -> Result<bool, Error> {
-        let mut dead_objs = HashSet::new();
-        {
-            let fan_out = self.get_analysis::<FanOutTable<I>>()?;
-            for obj in self.objects() {
-                let mut is_dead = true;
-                for net in obj.nets() {
-                    // This should account for outputs
-                    if fan_out.net_has_uses(&net) {
-                        is_dead = false;
-                        break;
-                    }
-                }
-                if is_dead && !obj.is_an_input() {
-                    dead_objs.insert(obj.unwrap().borrow().index);
-                }
-            }
-        }
-
-        if dead_objs.is_empty() {
-            return Ok(false);
-        }
-
+    /// Removes the objects at the indices in `dead` from the netlist, and remaps the operands of the others.
+    /// The uses of the removed objects must already be disconnected.
+    fn remove_objects(&self, dead: &HashSet<usize>) -> Result<(), Error> {
         let old_objects = self.objects.take();
         let mut remap: HashMap<usize, usize> = HashMap::new();
+        let mut removed = Vec::new();
         for (old_index, obj) in old_objects.into_iter().enumerate() {
-            if dead_objs.contains(&old_index) {
+            if dead.contains(&old_index) {
                 // 1. this ref, 2. as an output
                 if Rc::strong_count(&obj) > 2 {
                     return Err(Error::DanglingReference(
                         obj.borrow().get().get_nets().to_vec(),
                     ));
                 }
+                removed.push(obj.borrow().id);
                 continue;
             }
             let new_index = self.objects.borrow().len();
@@ -1546,20 +2683,79 @@ where
             }
         }
 
-        let pairs: Vec<_> = self.outputs.take().into_iter().collect();
-        for (operand, net) in pairs {
+        for operand in self.outputs.borrow_mut().iter_mut().flat_map(|(o, _)| o) {
             let root = operand.root();
             let root = *remap.get(&root).unwrap_or(&root);
-            let new_operand = operand.clone().remap(root);
-            self.outputs.borrow_mut().insert(new_operand, net);
+            *operand = operand.clone().remap(root);
+        }
+
+        // The aliases of removed objects are removed with them
+        let mut aliases = self.aliases.borrow_mut();
+        aliases.retain(|(operand, _)| !dead.contains(&operand.root()));
+        for (operand, _) in aliases.iter_mut() {
+            *operand = operand.clone().remap(remap[&operand.root()]);
+        }
+        drop(aliases);
+
+        // Removing objects keeps the others in order, so only an existing index needs rebuilding
+        let indexed = self.id_index.borrow().is_some();
+        if indexed {
+            self.rebuild_id_index();
+        }
+        self.rebuild_uses();
+        self.strash_rebuild();
+        for id in removed {
+            self.stale_names(id);
+            self.notify_remove(id);
+        }
+
+        Ok(())
+    }
+
+    /// Cleans unused nodes from the netlist, returning `Ok(true)` if the netlist changed.
+    pub fn clean_once(&self) -> Result<bool, Error> {
+        self.clean_once_where(|_| true)
+    }
+
+    /// Cleans the unused nodes for which `scope` holds, returning `Ok(true)` if the netlist changed.
+    fn clean_once_where(&self, scope: impl Fn(&NetRef<I>) -> bool) -> Result<bool, Error> {
+        let mut dead_objs = HashSet::new();
+        {
+            let fan_out = self.get_analysis::<FanOutTable<I>>()?;
+            for obj in self.objects() {
+                let mut is_dead = true;
+                for net in obj.nets() {
+                    // This should account for outputs
+                    if fan_out.net_has_uses(&net) {
+                        is_dead = false;
+                        break;
+                    }
+                }
+                if is_dead
+                    && !obj.is_an_input()
+                    && scope(&obj)
+                    && !self.is_protected(&obj.netref.borrow())
+                {
+                    trace_event!(debug, node = %obj, "removing a circuit node without uses");
+                    dead_objs.insert(obj.unwrap().borrow().index);
+                }
+            }
+        }
+
+        if dead_objs.is_empty() {
+            return Ok(false);
         }
 
+        self.remove_objects(&dead_objs)?;
+        trace_event!(debug, removed = dead_objs.len(), "clean round");
+
         Ok(true)
     }
 
     /// Greedly removes unused nodes from the netlist, until it stops changing.
     /// Returns true if the netlist was changed.
     pub fn clean(&self) -> Result<bool, Error> {
+        debug_span!("clean", netlist = %self.get_name());
         if !self.clean_once()? {
             Ok(false)
         } else {
@@ -1571,41 +2767,270 @@ where
         }
     }
 
-    /// Returns `true` if all the nets are uniquely named
-    fn nets_unique(&self) -> Result<(), Error> {
-        let mut nets = HashSet::new();
-        for net in self.into_iter() {
-            if !nets.insert(net.clone().take_identifier()) {
-                return Err(Error::NonuniqueNets(vec![net]));
+    /// Removes the unused circuit nodes like [Netlist::clean], except those that `options` keeps,
+    /// and reports the names of the removed instances in netlist order. Removing a node may leave its drivers unused,
+    /// so they are removed as well. With [CleanOptions::dry_run], the netlist is left as it is and the report lists
+    /// what would be removed. Returns an error if the netlist does not verify.
+    pub fn clean_with(&self, options: &CleanOptions) -> Result<PassReport, Error> {
+        debug_span!("clean_with", netlist = %self.get_name(), dry_run = options.dry_run);
+        self.verify()?;
+        let keep = |obj: &NetRef<I>| {
+            obj.is_an_input()
+                || self.is_protected(&obj.netref.borrow())
+                || options.keep_attributes.iter().any(|k| obj.has_attribute(k))
+                || obj.nets().any(|net| {
+                    let name = select::plain_name(net.get_identifier());
+                    options.keep_nets.iter().any(|p| glob_match(p, &name))
+                })
+        };
+
+        // The number of uses of each object, by input ports and top-level outputs
+        let objects: Vec<NetRef<I>> = self.objects().collect();
+        let mut uses: Vec<usize> = objects
+            .iter()
+            .map(|obj| {
+                obj.outputs()
+                    .map(|o| self.get_uses(&o).len() + usize::from(o.is_top_level_output()))
+                    .sum()
+            })
+            .collect();
+        let mut dead = HashSet::new();
+        let mut stack: Vec<usize> = (0..objects.len()).filter(|i| uses[*i] == 0).collect();
+        stack.reverse();
+        while let Some(index) = stack.pop() {
+            let obj = &objects[index];
+            if keep(obj) || !dead.insert(index) {
+                continue;
+            }
+            for driver in obj.drivers().flatten() {
+                let driver = driver.netref.borrow().get_index();
+                uses[driver] -= 1;
+                if uses[driver] == 0 {
+                    stack.push(driver);
+                }
+            }
+        }
+
+        let mut dead: Vec<usize> = dead.into_iter().collect();
+        dead.sort_unstable();
+        let removed = dead
+            .iter()
+            .filter_map(|i| objects[*i].get_instance_name())
+            .collect();
+        drop(objects);
+        if !options.dry_run && !dead.is_empty() {
+            self.remove_objects(&dead.iter().copied().collect())?;
+        }
+        trace_event!(debug, removed = dead.len(), "cleaned");
+        Ok(PassReport {
+            removed,
+            ..PassReport::default()
+        })
+    }
+
+    /// Reports each net or alias named like an earlier one, and each instance named like an earlier one.
+    /// A name may only be shared by the outputs of instances which are all tri-statable.
+    pub(crate) fn diagnose_names(&self, diagnostics: &mut Diagnostics) {
+        // Whether all the drivers of a name seen so far are tri-statable
+        let mut nets: HashMap<Identifier, bool> = HashMap::new();
+        for obj in self.objects() {
+            for (i, net) in obj.nets().enumerate() {
+                let tristate = obj
+                    .get_instance_type()
+                    .is_some_and(|inst| inst.is_tristate_output(i));
+                match nets.get(net.get_identifier()) {
+                    None => {
+                        nets.insert(net.get_identifier().clone(), tristate);
+                    }
+                    Some(true) if tristate => (),
+                    Some(_) => diagnostics.push(
+                        Error::NonuniqueNets(vec![net])
+                            .context(format!("In {obj}"), [obj.get_id()]),
+                    ),
+                }
+            }
+        }
+        for (net, alias) in self.aliases() {
+            if nets.insert(alias.get_identifier().clone(), false).is_some() {
+                let obj = net.unwrap();
+                diagnostics.push(
+                    Error::NonuniqueNets(vec![alias])
+                        .context(format!("Alias of {obj}"), [obj.get_id()]),
+                );
+            }
+        }
+
+        let mut insts: HashMap<Identifier, ObjectId> = HashMap::new();
+        for inst in self.objects() {
+            if let Some(name) = inst.get_instance_name() {
+                match insts.entry(name.clone()) {
+                    Entry::Vacant(e) => {
+                        e.insert(inst.get_id());
+                    }
+                    Entry::Occupied(e) => diagnostics.push(
+                        Error::NonuniqueInsts(vec![name])
+                            .context(format!("In {inst}"), [*e.get(), inst.get_id()]),
+                    ),
+                }
             }
         }
-        Ok(())
     }
 
-    /// Returns `true` if all the nets are uniquely named
-    fn insts_unique(&self) -> Result<(), Error> {
-        let mut insts = HashSet::new();
+    /// Reports the input ports that are not connected to a driver, as one error for all of them.
+    pub(crate) fn diagnose_connections(&self, diagnostics: &mut Diagnostics) {
+        let mut objects = Vec::new();
+        let mut unconnected: Vec<(Identifier, Net)> = Vec::new();
         for inst in self.objects() {
-            if let Some(name) = inst.get_instance_name()
-                && !insts.insert(name.clone())
-            {
-                return Err(Error::NonuniqueInsts(vec![name]));
+            let len = unconnected.len();
+            let name = inst.get_instance_name();
+            unconnected.extend(
+                inst.unconnected_inputs()
+                    .map(|input| (name.clone().unwrap(), input.get_port())),
+            );
+            if unconnected.len() > len {
+                objects.push(inst.get_id());
             }
         }
-        Ok(())
+        if !unconnected.is_empty() {
+            let context = format!("{} instances have unconnected inputs", objects.len());
+            diagnostics.push(Error::UnconnectedInputs(unconnected).context(context, objects));
+        }
+    }
+
+    /// Returns an error listing the input ports that are not connected to a driver, if there are any.
+    pub(crate) fn inputs_connected(&self) -> Result<(), Error> {
+        let mut diagnostics = Diagnostics::new();
+        self.diagnose_connections(&mut diagnostics);
+        diagnostics.into_result().map_err(Error::into_root)
     }
 
     /// Verifies that a netlist is well-formed.
+    /// Input ports that are not connected are allowed, see [Netlist::verify_with] to flag them.
     pub fn verify(&self) -> Result<(), Error> {
-        if self.outputs.borrow().is_empty() {
-            return Err(Error::NoOutputs);
+        self.verify_with(&VerifyOptions::default())
+    }
+
+    /// Verifies that a netlist is well-formed, with the additional checks enabled in `options`.
+    /// Returns the first problem found, see [Netlist::diagnose] for all of them.
+    pub fn verify_with(&self, options: &VerifyOptions) -> Result<(), Error> {
+        self.diagnose(options)
+            .into_result()
+            .map_err(Error::into_root)
+    }
+
+    /// Checks the netlist like [Netlist::verify_with], but collects every problem instead of stopping at the first.
+    /// Problems with particular circuit nodes are wrapped in an [Error::Context] with the handles of the nodes.
+    pub fn diagnose(&self, options: &VerifyOptions) -> Diagnostics {
+        debug_span!("verify", netlist = %self.get_name());
+        let mut diagnostics = Diagnostics::new();
+        if self.outputs.borrow().is_empty() && !options.allow_no_outputs {
+            diagnostics.push(Error::NoOutputs);
         }
 
-        self.nets_unique()?;
+        self.diagnose_names(&mut diagnostics);
+
+        if options.deny_unconnected_inputs {
+            self.diagnose_connections(&mut diagnostics);
+        }
 
-        self.insts_unique()?;
+        if options.deny_undriven_outputs {
+            let undriven: Vec<Net> = self
+                .outputs
+                .borrow()
+                .iter()
+                .filter(|(operand, _)| operand.is_none())
+                .map(|(_, net)| net.clone())
+                .collect();
+            if !undriven.is_empty() {
+                diagnostics.push(Error::UndrivenOutputs(undriven));
+            }
+        }
 
-        Ok(())
+        if options.deny_cycles {
+            let breaks_loops = |inst: &I| options.cycle_breakers.contains(inst.get_name());
+            diagnostics.check(topo_sort(self, breaks_loops).map(|_| ()));
+        }
+
+        diagnostics
+    }
+}
+
+/// Options for [Netlist::clean_with], which keep circuit nodes that have no uses
+#[derive(Debug, Clone, Default)]
+pub struct CleanOptions {
+    /// Keep the instances with any of these attributes, like `keep`
+    pub keep_attributes: Vec<AttributeKey>,
+    /// Keep the circuit nodes that drive a net whose name matches any of these glob patterns, like `dbg_*`
+    pub keep_nets: Vec<String>,
+    /// Report what would be removed without removing it
+    pub dry_run: bool,
+}
+
+/// Options for verifying a netlist.
+/// The default only checks that there are outputs and that names are unique, see [VerifyOptions::signoff] for the strictest checks.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    /// Report the input ports of instances that are not connected with [Error::UnconnectedInputs].
+    /// Netlists under construction may leave ports unconnected until they are hooked up.
+    pub deny_unconnected_inputs: bool,
+    /// Report the output ports of the module that are not driven with [Error::UndrivenOutputs]
+    pub deny_undriven_outputs: bool,
+    /// Accept a netlist without outputs, instead of reporting [Error::NoOutputs]
+    pub allow_no_outputs: bool,
+    /// Report combinational loops with [Error::CycleDetected]
+    pub deny_cycles: bool,
+    /// The cells that loops may pass through when cycles are denied, like latches.
+    /// Loops through sequential cells and black boxes are always allowed.
+    pub cycle_breakers: Vec<Identifier>,
+}
+
+impl VerifyOptions {
+    /// Returns the options for a netlist under construction, which may have no outputs yet
+    pub fn construction() -> Self {
+        Self {
+            allow_no_outputs: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the options for a finished netlist, denying every problem
+    pub fn signoff() -> Self {
+        Self {
+            deny_unconnected_inputs: true,
+            deny_undriven_outputs: true,
+            allow_no_outputs: false,
+            deny_cycles: true,
+            cycle_breakers: Vec::new(),
+        }
+    }
+}
+
+/// How [Netlist::remove_instance] handles the uses of the outputs of the removed instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovePolicy {
+    /// Removal fails if the outputs have any use
+    Reject,
+    /// The uses are tied to a constant value
+    Tie(Logic),
+    /// The uses are connected to the driver of the single input, as for a buffer
+    StitchThrough,
+}
+
+/// The connection of an input port by name, see [Netlist::insert_gate_named]
+#[derive(Debug, Clone)]
+pub enum Pin<I: Instantiable> {
+    /// The port is driven by the net
+    Driven(DrivenNet<I>),
+    /// The port is intentionally left unconnected
+    Unconnected,
+}
+
+impl<I> From<DrivenNet<I>> for Pin<I>
+where
+    I: Instantiable,
+{
+    fn from(net: DrivenNet<I>) -> Self {
+        Pin::Driven(net)
     }
 }
 
@@ -1973,6 +3398,30 @@ where
         })
     }
 
+    /// Returns an iterator over the circuit nodes that are instances, skipping the principal inputs.
+    pub fn instances(&self) -> impl Iterator<Item = NetRef<I>> {
+        self.objects().filter(|n| !n.is_an_input())
+    }
+
+    /// Returns an iterator over the circuit nodes whose instance type is a `T`, as recognized by [AsCell].
+    /// For an enum of cell types, this selects the nodes of one variant, like `netlist.instances_of::<FlipFlop>()`.
+    pub fn instances_of<T>(&self) -> impl Iterator<Item = NetRef<I>>
+    where
+        I: AsCell<T>,
+    {
+        self.matches(|inst| inst.as_cell().is_some())
+    }
+
+    /// Returns an iterator over the circuit nodes that are sequential cells.
+    pub fn seq_cells(&self) -> impl Iterator<Item = NetRef<I>> {
+        self.matches(|inst| inst.is_seq())
+    }
+
+    /// Returns an iterator over the circuit nodes that are constant cells.
+    pub fn constants(&self) -> impl Iterator<Item = NetRef<I>> {
+        self.matches(|inst| inst.get_constant().is_some())
+    }
+
     /// Returns an iterator to principal inputs in the netlist as references.
     pub fn inputs(&self) -> impl Iterator<Item = DrivenNet<I>> {
         self.objects()
@@ -1980,17 +3429,13 @@ where
             .map(|n| DrivenNet::new(0, n))
     }
 
-    /// Returns an iterator to circuit nodes that drive an output in the netlist.
+    /// Returns the circuit nodes that drive an output in the netlist, paired with the output port.
+    /// Outputs which are not driven are skipped.
     pub fn outputs(&self) -> Vec<(DrivenNet<I>, Net)> {
         self.outputs
             .borrow()
             .iter()
-            .map(|(k, n)| {
-                (
-                    DrivenNet::new(k.secondary(), NetRef::wrap(self.index_weak(&k.root()))),
-                    n.clone(),
-                )
-            })
+            .filter_map(|(k, n)| Some((self.driven_net(k.as_ref()?), n.clone())))
             .collect()
     }
 
@@ -2014,112 +3459,492 @@ where
     }
 }
 
-impl<I> std::fmt::Display for Netlist<I>
+/// Options for emitting a netlist as Verilog
+#[derive(Debug, Clone)]
+pub struct VerilogOptions {
+    /// Omit the instance parameters that are set to their default value (see [Instantiable::get_default_parameter])
+    pub omit_default_parameters: bool,
+    /// Declare the ports in the module header (`module m (input wire a, output wire y);`) instead of the module body
+    pub ansi_ports: bool,
+    /// How to emit the [Provenance] of nodes and nets
+    pub provenance: ProvenanceStyle,
+    /// Emit an empty module after the netlist for each black-box cell type (see [Instantiable::is_blackbox]),
+    /// so that the netlist elaborates without the sources of the black boxes
+    pub blackbox_stubs: bool,
+    /// The number of spaces to indent by, which is `2` by default
+    pub indent: usize,
+    /// Declare a `wire` for each port, as in `input a; wire a;`, which is the default.
+    /// Ports that are not plain wires, like `wand` nets, are always declared.
+    pub port_wires: bool,
+    /// Emit instances on a single line, as in `AND inst_0 (.A(a), .B(b), .Y(y));`, when the line fits within this width
+    pub line_width: Option<usize>,
+    /// Emit the attributes of the module, its nets and its instances, which is the default
+    pub attributes: bool,
+    /// Emit aliases as wires with `assign` statements, like `assign y = a;`, which is the default.
+    /// Otherwise they are collapsed onto the nets they name.
+    pub aliases: bool,
+    /// How constant cells and their uses are emitted, as literals by default
+    pub constants: ConstantStyle,
+    /// A comment to emit before the module, one `//` line per line of the header
+    pub header: Option<String>,
+    /// Emit the time of emission as a comment before the module, as in `// Generated on 2025-01-31 12:00:00 UTC`
+    pub timestamp: bool,
+    /// Emit ports, wires, instances and assignments sorted by name instead of in order of insertion,
+    /// so that the output does not depend on the order in which the netlist was built.
+    /// The ports named by [Netlist::set_port_order] still come first, and inputs still come before outputs.
+    pub sorted: bool,
+}
+
+impl Default for VerilogOptions {
+    fn default() -> Self {
+        Self {
+            omit_default_parameters: false,
+            ansi_ports: false,
+            provenance: ProvenanceStyle::default(),
+            blackbox_stubs: false,
+            indent: 2,
+            port_wires: true,
+            line_width: None,
+            attributes: true,
+            aliases: true,
+            constants: ConstantStyle::default(),
+            header: None,
+            timestamp: false,
+            sorted: false,
+        }
+    }
+}
+
+/// How constant cells and their uses are emitted in Verilog, since backend tools expect different styles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConstantStyle {
+    /// Constant cells are left out and their uses are literals, as in `.A(1'b1)`
+    #[default]
+    Literal,
+    /// Constant cells are emitted as instances, like `VDD` and `GND` tie cells, and their uses read their nets
+    TieCells,
+    /// The nets of constant cells are declared as wires and driven by `assign` statements, as in `assign zero_Y = 1'b0;`
+    Assign,
+}
+
+/// The key to sort identifiers by name, where the bits of a bus are in ascending order
+fn sort_key(id: &Identifier) -> (&str, Option<usize>) {
+    (id.get_name(), id.get_bit_index())
+}
+
+/// Writes the header comment and timestamp of `options`
+fn write_header(f: &mut std::fmt::Formatter<'_>, options: &VerilogOptions) -> std::fmt::Result {
+    for line in options.header.iter().flat_map(|h| h.lines()) {
+        writeln!(f, "// {line}")?;
+    }
+    if options.timestamp {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        writeln!(f, "// Generated on {}", utc_timestamp(secs))?;
+    }
+    Ok(())
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time, like `2025-01-31 12:00:00 UTC`
+fn utc_timestamp(secs: u64) -> String {
+    // Converts days to a civil date, counting in 400-year eras from 0000-03-01
+    let days = secs / 86400 + 719468;
+    let era = days / 146097;
+    let doe = days % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    let time = secs % 86400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Writes `attributes` on their own line, sorted by key
+fn write_attributes(
+    f: &mut std::fmt::Formatter<'_>,
+    indent: &str,
+    attributes: &HashMap<AttributeKey, AttributeValue>,
+) -> std::fmt::Result {
+    let mut attributes: Vec<_> = Attribute::from_pairs(attributes.clone().into_iter()).collect();
+    attributes.sort_by(|a, b| a.key().cmp(b.key()));
+    for attribute in attributes {
+        writeln!(f, "{indent}{attribute}")?;
+    }
+    Ok(())
+}
+
+/// Returns the port connections of an instance, one per line.
+/// The bits of a bus port are connected with a concatenation, where unconnected bits are left floating.
+/// Unconnected ports are left out.
+fn connection_lines(connections: &[(&Identifier, Option<String>)]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut i = 0;
+    while i < connections.len() {
+        let (port, conn) = &connections[i];
+        let same_bus = |p: &Identifier| p.is_sliced() && p.get_name() == port.get_name();
+        if !same_bus(port) {
+            if let Some(conn) = conn {
+                lines.push(format!(".{}({})", port.emit_name(), conn));
+            }
+            i += 1;
+            continue;
+        }
+
+        let len = connections[i..]
+            .iter()
+            .take_while(|(p, _)| same_bus(p))
+            .count();
+        let mut bits: Vec<_> = connections[i..i + len].iter().collect();
+        if bits.iter().any(|(_, c)| c.is_some()) {
+            bits.sort_by_key(|(p, _)| std::cmp::Reverse(p.get_bit_index()));
+            let bits: Vec<&str> = bits
+                .iter()
+                .map(|(_, c)| c.as_deref().unwrap_or("1'bz"))
+                .collect();
+            lines.push(format!(".{}({{{}}})", port.get_name(), bits.join(", ")));
+        }
+        i += len;
+    }
+    lines
+}
+
+/// Tracks the nets declared while emitting Verilog, so that the bits of a bus are declared once as a vector
+#[derive(Default)]
+struct Declarations {
+    /// The lowest and highest bit index of each bus
+    buses: HashMap<String, (usize, usize)>,
+    /// The names declared so far
+    declared: HashSet<String>,
+}
+
+impl Declarations {
+    /// Widens the bus of `net`, if it is the bit of a bus
+    fn add_net(&mut self, net: &Net) {
+        let id = net.get_identifier();
+        if let Some(index) = id.get_bit_index() {
+            let range = self
+                .buses
+                .entry(id.get_name().to_string())
+                .or_insert((index, index));
+            range.0 = range.0.min(index);
+            range.1 = range.1.max(index);
+        }
+    }
+
+    /// Returns the name of the port in the module header
+    fn port_name(net: &Net) -> String {
+        let id = net.get_identifier();
+        if id.is_sliced() {
+            id.get_name().to_string()
+        } else {
+            id.emit_name()
+        }
+    }
+
+    /// Returns the declared name of `net`, which includes the range of a bus,
+    /// or `None` if it (or its bus) is already declared.
+    fn declare(&mut self, net: &Net) -> Option<String> {
+        let id = net.get_identifier();
+        let name = Self::port_name(net);
+        if !self.declared.insert(name.clone()) {
+            return None;
+        }
+        match self.buses.get(id.get_name()) {
+            Some((lo, hi)) if id.is_sliced() => Some(format!("[{hi}:{lo}] {name}")),
+            _ => Some(name),
+        }
+    }
+}
+
+/// Writes an empty module with the ports of `inst_type`, marked as a black box
+fn write_stub<I: Instantiable>(
+    f: &mut std::fmt::Formatter<'_>,
+    inst_type: &I,
+    options: &VerilogOptions,
+) -> std::fmt::Result {
+    let ports: Vec<(PortDirection, &Net)> = inst_type
+        .get_input_ports()
+        .into_iter()
+        .map(|net| (PortDirection::Input, net))
+        .chain(
+            inst_type
+                .get_output_ports()
+                .into_iter()
+                .map(|net| (PortDirection::Output, net)),
+        )
+        .collect();
+    let mut decls = Declarations::default();
+    for (_, net) in ports.iter() {
+        decls.add_net(net);
+    }
+
+    let indent = " ".repeat(options.indent);
+    writeln!(f, "(* blackbox *)")?;
+    writeln!(f, "module {} (", inst_type.get_name())?;
+    if options.ansi_ports {
+        let ports: Vec<_> = ports
+            .iter()
+            .filter_map(|(dir, net)| Some((dir, decls.declare(net)?)))
+            .collect();
+        for (i, (dir, name)) in ports.iter().enumerate() {
+            let sep = if i == ports.len() - 1 { "" } else { "," };
+            writeln!(f, "{}{} wire {}{}", indent, dir.as_str(), name, sep)?;
+        }
+        writeln!(f, ");")?;
+    } else {
+        let mut listed = HashSet::new();
+        let names: Vec<_> = ports
+            .iter()
+            .map(|(_, net)| Declarations::port_name(net))
+            .filter(|name| listed.insert(name.clone()))
+            .collect();
+        for (i, name) in names.iter().enumerate() {
+            let sep = if i == names.len() - 1 { "" } else { "," };
+            writeln!(f, "{indent}{name}{sep}")?;
+        }
+        writeln!(f, ");")?;
+        for (dir, net) in ports.iter() {
+            if let Some(name) = decls.declare(net) {
+                writeln!(f, "{}{} {};", indent, dir.as_str(), name)?;
+            }
+        }
+    }
+    // The values of the instance are the defaults, so that the overrides of every instance are declared
+    for (id, value) in inst_type.parameters() {
+        writeln!(f, "{indent}parameter {id} = {value};")?;
+    }
+    writeln!(f, "endmodule")
+}
+
+/// Displays a netlist as Verilog with non-default options
+struct VerilogWriter<'a, I: Instantiable>(&'a Netlist<I>, &'a VerilogOptions);
+
+impl<I> std::fmt::Display for VerilogWriter<'_, I>
 where
     I: Instantiable,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt_verilog(f, self.1)
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Returns the netlist as Verilog, emitted with `options`.
+    /// Displaying the netlist is the same as emitting it with the default options.
+    pub fn to_verilog(&self, options: &VerilogOptions) -> String {
+        VerilogWriter(self, options).to_string()
+    }
+
+    /// Writes the netlist as Verilog to `f`
+    fn fmt_verilog(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        options: &VerilogOptions,
+    ) -> std::fmt::Result {
         // Borrow everything first
         let objects = self.objects.borrow();
         let outputs = self.outputs.borrow();
-
+        let mut aliases: Vec<_> = if options.aliases {
+            self.aliases.borrow().clone()
+        } else {
+            Vec::new()
+        };
+        let net_attributes = self.net_attributes.borrow();
+        let net_provenance = self.net_provenance.borrow();
+
+        let write_attributes =
+            |f: &mut std::fmt::Formatter<'_>,
+             indent: &str,
+             attributes: &HashMap<AttributeKey, AttributeValue>| {
+                if options.attributes {
+                    write_attributes(f, indent, attributes)
+                } else {
+                    Ok(())
+                }
+            };
+        write_header(f, options)?;
+        write_attributes(f, "", &self.attributes.borrow())?;
         writeln!(f, "module {} (", self.get_name())?;
 
+        // Make wire decls
+        let indent = " ".repeat(options.indent);
+        let resolutions = self.resolutions.borrow();
+        let net_type = |net: &Net| {
+            resolutions
+                .get(net.get_identifier())
+                .map_or("wire", |r| r.as_str())
+        };
+        let annotate = |f: &mut std::fmt::Formatter<'_>, net: &Net| {
+            let id = net.get_identifier();
+            let mut attributes = net_attributes.get(id).cloned().unwrap_or_default();
+            options
+                .provenance
+                .annotate(f, &indent, net_provenance.get(id), &mut attributes)?;
+            write_attributes(f, &indent, &attributes)
+        };
+        let declare = |f: &mut std::fmt::Formatter<'_>, net: &Net, name: &str| {
+            annotate(f, net)?;
+            writeln!(f, "{}{} {};", indent, net_type(net), name)
+        };
+
         // Print inputs and outputs
-        let level = 2;
-        let indent = " ".repeat(level);
-        for oref in objects.iter() {
-            let owned = oref.borrow();
-            let obj = owned.get();
-            if let Object::Input(net) = obj {
-                writeln!(f, "{}{},", indent, net.get_identifier().emit_name())?;
-            }
+        let mut ports = self.get_ports();
+        // The objects in order of emission
+        let mut instances: Vec<_> = objects.iter().collect();
+        if options.sorted {
+            let order = self.port_order.borrow();
+            ports.sort_by(|(d0, n0), (d1, n1)| {
+                let key = |d: &PortDirection, n: &Net| {
+                    let position = order.iter().position(|id| id == n.get_identifier());
+                    (position.unwrap_or(order.len()), *d == PortDirection::Output)
+                };
+                key(d0, n0)
+                    .cmp(&key(d1, n1))
+                    .then_with(|| sort_key(n0.get_identifier()).cmp(&sort_key(n1.get_identifier())))
+            });
+            instances.sort_by_cached_key(|oref| match oref.borrow().get() {
+                Object::Instance(_, name, _) => {
+                    Some((name.get_name().to_string(), name.get_bit_index()))
+                }
+                Object::Input(_) => None,
+            });
         }
-        for (i, (_, net)) in outputs.iter().enumerate() {
-            if i == outputs.len() - 1 {
-                writeln!(f, "{}{}", indent, net.get_identifier().emit_name())?;
-            } else {
-                writeln!(f, "{}{},", indent, net.get_identifier().emit_name())?;
-            }
+        let mut decls = Declarations::default();
+        for net in ports
+            .iter()
+            .map(|(_, n)| n)
+            .chain(aliases.iter().map(|(_, n)| n))
+        {
+            decls.add_net(net);
         }
-        writeln!(f, ");")?;
-
-        // Make wire decls
-        let mut already_decl = HashSet::new();
+        // Constant cells only drive nets in the styles other than literals
+        let literals = options.constants == ConstantStyle::Literal;
         for oref in objects.iter() {
             let owned = oref.borrow();
-            let obj = owned.get();
-            if let Object::Input(net) = obj {
-                writeln!(f, "{}input {};", indent, net.get_identifier().emit_name())?;
-                writeln!(f, "{}wire {};", indent, net.get_identifier().emit_name())?;
-                already_decl.insert(net.clone());
+            if let Object::Instance(nets, _, inst_type) = owned.get()
+                && (inst_type.get_constant().is_none() || !literals)
+            {
+                nets.iter().for_each(|n| decls.add_net(n));
             }
         }
-        for (_, net) in outputs.iter() {
-            if !already_decl.contains(net) {
-                writeln!(f, "{}output {};", indent, net.get_identifier().emit_name())?;
-                writeln!(f, "{}wire {};", indent, net.get_identifier().emit_name())?;
-                already_decl.insert(net.clone());
+        if options.ansi_ports {
+            let ports: Vec<_> = ports
+                .iter()
+                .filter_map(|(dir, net)| Some((dir, net, decls.declare(net)?)))
+                .collect();
+            for (i, (dir, net, name)) in ports.iter().enumerate() {
+                annotate(f, net)?;
+                let sep = if i == ports.len() - 1 { "" } else { "," };
+                writeln!(
+                    f,
+                    "{}{} {} {}{}",
+                    indent,
+                    dir.as_str(),
+                    net_type(net),
+                    name,
+                    sep
+                )?;
+            }
+            writeln!(f, ");")?;
+        } else {
+            let mut listed = HashSet::new();
+            let names: Vec<_> = ports
+                .iter()
+                .map(|(_, net)| Declarations::port_name(net))
+                .filter(|name| listed.insert(name.clone()))
+                .collect();
+            for (i, name) in names.iter().enumerate() {
+                let sep = if i == names.len() - 1 { "" } else { "," };
+                writeln!(f, "{indent}{name}{sep}")?;
+            }
+            writeln!(f, ");")?;
+
+            for (dir, net) in ports.iter() {
+                if let Some(name) = decls.declare(net) {
+                    if options.port_wires || net_type(net) != "wire" {
+                        writeln!(f, "{}{} {};", indent, dir.as_str(), name)?;
+                        declare(f, net, &name)?;
+                    } else {
+                        annotate(f, net)?;
+                        writeln!(f, "{}{} {};", indent, dir.as_str(), name)?;
+                    }
+                }
             }
         }
-        for oref in objects.iter() {
+        let mut wires = Vec::new();
+        for oref in instances.iter() {
             let owned = oref.borrow();
             let obj = owned.get();
             if let Object::Instance(nets, _, inst_type) = obj
-                && inst_type.get_constant().is_none()
+                && (inst_type.get_constant().is_none() || !literals)
             {
-                for net in nets.iter() {
-                    if !already_decl.contains(net) {
-                        writeln!(f, "{}wire {};", indent, net.get_identifier().emit_name())?;
-                        already_decl.insert(net.clone());
-                    }
-                }
+                wires.extend(nets.iter().cloned());
+            }
+        }
+        if options.sorted {
+            wires.sort_by(|a, b| sort_key(a.get_identifier()).cmp(&sort_key(b.get_identifier())));
+            aliases.sort_by(|(_, a), (_, b)| {
+                sort_key(a.get_identifier()).cmp(&sort_key(b.get_identifier()))
+            });
+        }
+        wires.extend(aliases.iter().map(|(_, net)| net.clone()));
+        for net in wires.iter() {
+            if let Some(name) = decls.declare(net) {
+                declare(f, net, &name)?;
             }
         }
 
-        for oref in objects.iter() {
+        let mut constant_assigns = Vec::new();
+        for oref in instances.iter() {
             let owned = oref.borrow();
             let obj = owned.get();
 
-            // Skip emitting constants as their uses will be hard-wired
+            // Skip emitting constants as their uses will be hard-wired, or assign their nets
             if let Some(inst_type) = obj.get_instance_type()
-                && inst_type.get_constant().is_some()
+                && let Some(logic) = inst_type.get_constant()
             {
-                continue;
+                match options.constants {
+                    ConstantStyle::Literal => continue,
+                    ConstantStyle::Assign => {
+                        constant_assigns.push((obj.get_single_net().clone(), logic));
+                        continue;
+                    }
+                    ConstantStyle::TieCells => (),
+                }
             }
 
             if let Object::Instance(nets, inst_name, inst_type) = obj {
-                for (k, v) in owned.attributes.iter() {
-                    if let Some(value) = v {
-                        writeln!(f, "{indent}(* {k} = \"{value}\" *)")?;
-                    } else {
-                        writeln!(f, "{indent}(* {k} *)")?;
-                    }
-                }
+                let mut attributes = owned.attributes.clone();
+                options.provenance.annotate(
+                    f,
+                    &indent,
+                    owned.provenance.as_ref(),
+                    &mut attributes,
+                )?;
+                write_attributes(f, &indent, &attributes)?;
 
-                write!(f, "{}{} ", indent, inst_type.get_name())?;
-                if inst_type.is_parameterized() {
-                    writeln!(f, "#(")?;
-                    let level = 4;
-                    let indent = " ".repeat(level);
-                    let params: Vec<_> = inst_type.parameters().collect();
-                    for (i, (k, v)) in params.iter().enumerate() {
-                        if i == params.len() - 1 {
-                            writeln!(f, "{indent}.{k}({v})")?;
-                        } else {
-                            writeln!(f, "{indent}.{k}({v}),")?;
-                        }
-                    }
-                    let level = 2;
-                    let indent = " ".repeat(level);
-                    write!(f, "{indent}) ")?;
-                }
-                writeln!(f, "{} (", inst_name.emit_name())?;
-                let level = 4;
-                let indent = " ".repeat(level);
+                let params: Vec<_> = inst_type
+                    .parameters()
+                    .filter(|(k, v)| {
+                        !options.omit_default_parameters
+                            || inst_type.get_default_parameter(k).as_ref() != Some(v)
+                    })
+                    .map(|(k, v)| format!(".{k}({v})"))
+                    .collect();
+                let mut connections = Vec::new();
                 for (idx, port) in inst_type.get_input_ports().into_iter().enumerate() {
-                    let port_name = port.get_identifier().emit_name();
-                    if let Some(operand) = owned.operands[idx].as_ref() {
+                    let operand_str = owned.operands[idx].as_ref().map(|operand| {
                         let operand_net = match operand {
                             Operand::DirectIndex(idx) => objects[*idx].borrow().as_net().clone(),
                             Operand::CellIndex(idx, j) => {
@@ -2127,63 +3952,143 @@ where
                             }
                         };
 
-                        let operand_str = if let Some(inst_type) =
+                        if let Some(inst_type) =
                             objects[operand.root()].borrow().get().get_instance_type()
                             && let Some(logic) = inst_type.get_constant()
+                            && literals
                         {
                             logic.to_string()
                         } else {
                             operand_net.get_identifier().emit_name()
-                        };
-
-                        writeln!(f, "{}.{}({}),", indent, port_name, operand_str)?;
-                    }
+                        }
+                    });
+                    connections.push((port.get_identifier(), operand_str));
                 }
 
                 for (idx, net) in nets.iter().enumerate() {
-                    let port_name = inst_type.get_output_port(idx).get_identifier().emit_name();
-                    if idx == nets.len() - 1 {
-                        writeln!(
-                            f,
-                            "{}.{}({})",
-                            indent,
-                            port_name,
-                            net.get_identifier().emit_name()
-                        )?;
-                    } else {
-                        writeln!(
-                            f,
-                            "{}.{}({}),",
-                            indent,
-                            port_name,
-                            net.get_identifier().emit_name()
-                        )?;
-                    }
+                    let port = inst_type.get_output_port(idx).get_identifier();
+                    connections.push((port, Some(net.get_identifier().emit_name())));
                 }
+                let lines = connection_lines(&connections);
 
-                let level = 2;
-                let indent = " ".repeat(level);
+                let params_str = if params.is_empty() {
+                    String::new()
+                } else {
+                    format!("#({}) ", params.join(", "))
+                };
+                let single = format!(
+                    "{}{} {}{} ({});",
+                    indent,
+                    inst_type.get_name(),
+                    params_str,
+                    inst_name.emit_name(),
+                    lines.join(", ")
+                );
+                if options.line_width.is_some_and(|w| single.len() <= w) {
+                    writeln!(f, "{single}")?;
+                    continue;
+                }
+
+                let inner = " ".repeat(2 * options.indent);
+                write!(f, "{}{} ", indent, inst_type.get_name())?;
+                if !params.is_empty() {
+                    writeln!(f, "#(")?;
+                    writeln!(f, "{inner}{}", params.join(&format!(",\n{inner}")))?;
+                    write!(f, "{indent}) ")?;
+                }
+                writeln!(f, "{} (", inst_name.emit_name())?;
+                for (i, line) in lines.iter().enumerate() {
+                    let sep = if i == lines.len() - 1 { "" } else { "," };
+                    writeln!(f, "{inner}{line}{sep}")?;
+                }
                 writeln!(f, "{indent});")?;
             }
         }
 
+        // Output buses that are entirely driven by constants are assigned at once, like `assign y = 8'hA5;`
+        let constant_of = |driver: &Operand| {
+            self.index_weak(&driver.root())
+                .borrow()
+                .get()
+                .get_instance_type()
+                .and_then(|i| i.get_constant())
+                .filter(|_| literals)
+        };
+        let mut constant_buses: HashMap<&str, Option<Vec<Option<Logic>>>> = HashMap::new();
         for (driver, net) in outputs.iter() {
+            let id = net.get_identifier();
+            if let Some(index) = id.get_bit_index()
+                && let Some((lo, hi)) = decls.buses.get(id.get_name())
+            {
+                let bits = constant_buses
+                    .entry(id.get_name())
+                    .or_insert_with(|| Some(vec![None; hi - lo + 1]));
+                match (bits.as_mut(), driver.as_ref().and_then(constant_of)) {
+                    (Some(bits), Some(logic)) if bits[index - lo].is_none() => {
+                        bits[index - lo] = Some(logic)
+                    }
+                    _ => *bits = None,
+                }
+            }
+        }
+        let constant_buses: HashMap<&str, LogicVec> = constant_buses
+            .into_iter()
+            .filter_map(|(bus, bits)| Some((bus, bits?.into_iter().collect::<Option<_>>()?)))
+            .collect();
+
+        // The name of the net of `driver`, or the value of a constant
+        let driver_str = |driver: &Operand| {
             let driver_net = match driver {
                 Operand::DirectIndex(idx) => self.index_weak(idx).borrow().as_net().clone(),
                 Operand::CellIndex(idx, j) => self.index_weak(idx).borrow().get_net(*j).clone(),
             };
+            let value = constant_of(driver).map(|logic| logic.to_string());
+            (driver_net, value)
+        };
+        for (net, logic) in constant_assigns {
+            writeln!(
+                f,
+                "{}assign {} = {};",
+                indent,
+                net.get_identifier().emit_name(),
+                logic
+            )?;
+        }
+        for (driver, net) in aliases.iter() {
+            let (driver_net, value) = driver_str(driver);
+            let value = value.unwrap_or_else(|| driver_net.get_identifier().emit_name());
+            writeln!(
+                f,
+                "{}assign {} = {};",
+                indent,
+                net.get_identifier().emit_name(),
+                value
+            )?;
+        }
 
-            let driver_str = if let Some(inst_type) = self
-                .index_weak(&driver.root())
-                .borrow()
-                .get()
-                .get_instance_type()
-                && let Some(logic) = inst_type.get_constant()
+        let mut assigned = HashSet::new();
+        let mut assigns: Vec<_> = outputs
+            .iter()
+            .filter_map(|(d, n)| Some((d.as_ref()?, n)))
+            .collect();
+        if options.sorted {
+            assigns.sort_by(|(_, a), (_, b)| {
+                sort_key(a.get_identifier()).cmp(&sort_key(b.get_identifier()))
+            });
+        }
+        for (driver, net) in assigns {
+            let id = net.get_identifier();
+            if let Some(value) = constant_buses.get(id.get_name())
+                && id.is_sliced()
             {
-                logic.to_string()
-            } else {
-                driver_net.get_identifier().emit_name()
-            };
+                if assigned.insert(id.get_name()) {
+                    writeln!(f, "{}assign {} = {:X};", indent, id.get_name(), value)?;
+                }
+                continue;
+            }
+
+            let (driver_net, value) = driver_str(driver);
+            let driver_str = value.unwrap_or_else(|| driver_net.get_identifier().emit_name());
 
             if net.get_identifier() != driver_net.get_identifier() {
                 writeln!(
@@ -2196,7 +4101,31 @@ where
             }
         }
 
-        writeln!(f, "endmodule")
+        writeln!(f, "endmodule")?;
+
+        if options.blackbox_stubs {
+            let mut stubbed = HashSet::new();
+            for oref in instances.iter() {
+                let owned = oref.borrow();
+                if let Some(inst_type) = owned.get().get_instance_type()
+                    && inst_type.is_blackbox()
+                    && stubbed.insert(inst_type.get_name().clone())
+                {
+                    writeln!(f)?;
+                    write_stub(f, inst_type, options)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<I> std::fmt::Display for Netlist<I>
+where
+    I: Instantiable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_verilog(f, &VerilogOptions::default())
     }
 }
 
@@ -2259,6 +4188,13 @@ mod tests {
         assert_eq!(operand, parsed);
     }
 
+    #[test]
+    fn timestamps() {
+        assert_eq!(utc_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(utc_timestamp(951_827_696), "2000-02-29 12:34:56 UTC");
+        assert_eq!(utc_timestamp(1_735_689_599), "2024-12-31 23:59:59 UTC");
+    }
+
     #[test]
     #[should_panic(expected = "out of bounds for netref")]
     fn test_bad_output() {
@@ -2271,10 +4207,12 @@ mod tests {
 #[cfg(feature = "serde")]
 /// Serde support for netlists
 pub mod serde {
-    use super::{Netlist, Operand, OwnedObject, WeakIndex};
+    use super::{Netlist, ObjectId, Operand, Operands, OwnedObject, Provenance, WeakIndex};
     use crate::{
         attribute::{AttributeKey, AttributeValue},
-        circuit::{Instantiable, Net, Object},
+        circuit::{Identifier, Instantiable, Net, Object},
+        error::Error,
+        logic::Resolution,
     };
     use serde::{Deserialize, Serialize, de::DeserializeOwned};
     use std::cell::RefCell;
@@ -2291,6 +4229,9 @@ pub mod serde {
         operands: Vec<Option<Operand>>,
         /// A collection of attributes for the object
         attributes: HashMap<AttributeKey, AttributeValue>,
+        /// Where the object came from
+        #[serde(default)]
+        provenance: Option<Provenance>,
     }
 
     impl<I, O> From<OwnedObject<I, O>> for SerdeObject<I>
@@ -2301,8 +4242,9 @@ pub mod serde {
         fn from(value: OwnedObject<I, O>) -> Self {
             SerdeObject {
                 object: value.object,
-                operands: value.operands,
+                operands: value.operands.into_vec(),
                 attributes: value.attributes,
+                provenance: value.provenance,
             }
         }
     }
@@ -2318,9 +4260,13 @@ pub mod serde {
             OwnedObject {
                 object: self.object,
                 owner: Rc::downgrade(owner),
-                operands: self.operands,
+                operands: Operands::from_vec(self.operands),
                 attributes: self.attributes,
+                provenance: self.provenance,
+                payloads: Default::default(),
+                mark: 0,
                 index,
+                id: ObjectId(index),
             }
         }
     }
@@ -2334,9 +4280,27 @@ pub mod serde {
         name: String,
         /// The list of objects in the netlist, such as inputs, modules, and primitives
         objects: Vec<SerdeObject<I>>,
-        /// The list of operands that point to objects which are outputs.
+        /// The output ports and the operands that drive them.
         /// Indices must be a string if we want to support JSON.
-        outputs: HashMap<String, Net>,
+        outputs: Vec<(Option<String>, Net)>,
+        /// The aliases of nets and the operands they name
+        #[serde(default)]
+        aliases: Vec<(String, Net)>,
+        /// The names of the ports to emit first, in order
+        #[serde(default)]
+        port_order: Vec<Identifier>,
+        /// How tri-state buses resolve their drivers
+        #[serde(default)]
+        resolutions: Vec<(Identifier, Resolution)>,
+        /// A collection of attributes for the module
+        #[serde(default)]
+        attributes: HashMap<AttributeKey, AttributeValue>,
+        /// A collection of attributes for each net
+        #[serde(default)]
+        net_attributes: Vec<(Identifier, HashMap<AttributeKey, AttributeValue>)>,
+        /// Where each net came from
+        #[serde(default)]
+        net_provenance: Vec<(Identifier, Provenance)>,
     }
 
     impl<I> From<Netlist<I>> for SerdeNetlist<I>
@@ -2363,8 +4327,19 @@ pub mod serde {
                     .into_inner()
                     .into_iter()
                     // Indices must be a string if we want to support JSON.
+                    .map(|(o, n)| (o.map(|o| o.to_string()), n))
+                    .collect(),
+                aliases: value
+                    .aliases
+                    .into_inner()
+                    .into_iter()
                     .map(|(o, n)| (o.to_string(), n))
                     .collect(),
+                port_order: value.port_order.into_inner(),
+                resolutions: value.resolutions.into_inner().into_iter().collect(),
+                attributes: value.attributes.into_inner(),
+                net_attributes: value.net_attributes.into_inner().into_iter().collect(),
+                net_provenance: value.net_provenance.into_inner().into_iter().collect(),
             }
         }
     }
@@ -2376,11 +4351,11 @@ pub mod serde {
         /// Convert the serialized netlist back into a reference-counted netlist.
         fn into_netlist(self) -> Rc<Netlist<I>> {
             let netlist = Netlist::new(self.name);
-            let outputs: HashMap<Operand, Net> = self
+            let outputs: Vec<(Option<Operand>, Net)> = self
                 .outputs
                 .into_iter()
                 .map(|(k, v)| {
-                    let operand = k.parse::<Operand>().expect("Invalid index");
+                    let operand = k.map(|k| k.parse::<Operand>().expect("Invalid index"));
                     (operand, v)
                 })
                 .collect();
@@ -2398,7 +4373,23 @@ pub mod serde {
                 *objs_mut = objects;
                 let mut outputs_mut = netlist.outputs.borrow_mut();
                 *outputs_mut = outputs;
+                *netlist.aliases.borrow_mut() = self
+                    .aliases
+                    .into_iter()
+                    .map(|(k, v)| (k.parse::<Operand>().expect("Invalid index"), v))
+                    .collect();
+                netlist.next_id.set(objs_mut.len());
+                *netlist.port_order.borrow_mut() = self.port_order;
+                let mut resolutions_mut = netlist.resolutions.borrow_mut();
+                *resolutions_mut = self.resolutions.into_iter().collect();
+                let mut attributes_mut = netlist.attributes.borrow_mut();
+                *attributes_mut = self.attributes;
+                let mut net_attributes_mut = netlist.net_attributes.borrow_mut();
+                *net_attributes_mut = self.net_attributes.into_iter().collect();
+                let mut net_provenance_mut = netlist.net_provenance.borrow_mut();
+                *net_provenance_mut = self.net_provenance.into_iter().collect();
             }
+            netlist.rebuild_uses();
             netlist
         }
     }
@@ -2412,6 +4403,18 @@ pub mod serde {
         serde_json::to_writer_pretty(writer, &sobj)
     }
 
+    /// Serialize a copy of the netlist into the writer, for netlists that are shared and cannot be consumed.
+    /// The copy is made through a `.snet` snapshot, so object identifiers are renumbered.
+    pub fn netlist_serialize_copy<I: Instantiable + Serialize + DeserializeOwned>(
+        netlist: &Netlist<I>,
+        writer: impl std::io::Write,
+    ) -> Result<(), Error> {
+        let copy = Netlist::<I>::from_snet(&netlist.to_snet(None)?)?
+            .reclaim()
+            .expect("The copy is not shared");
+        netlist_serialize(copy, writer).map_err(|e| Error::ParseError(e.to_string()))
+    }
+
     /// Deserialize a netlist from the reader.
     pub fn netlist_deserialize<I: Instantiable + Serialize + DeserializeOwned>(
         reader: impl std::io::Read,
@@ -0,0 +1,168 @@
+/*!
+
+  Optimization of combinational netlists with the external [ABC](https://github.com/berkeley-abc/abc) tool, to
+  compare passes against it.
+
+  [abc] writes the netlist as a binary AIGER file, runs a script of ABC commands on it, and reads the resulting AIG
+  back as a netlist of [Gate]s with the names of the original inputs and outputs.
+
+*/
+
+use super::{
+    Gate, Netlist,
+    network::{LogicNetwork, NetworkBuilder, Signal},
+};
+use crate::{
+    circuit::{Evaluate, Identifier},
+    error::Error,
+};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Options for [abc_with]
+#[derive(Debug, Clone, Default)]
+pub struct AbcOptions {
+    /// The ABC executable. By default, `abc` is looked up in the path.
+    pub binary: Option<PathBuf>,
+    /// The directory of the AIGER files, which is kept. By default, a fresh temporary directory is removed afterwards.
+    pub dir: Option<PathBuf>,
+}
+
+/// Runs the ABC `script` on `netlist`, see [abc_with]
+pub fn abc<I>(netlist: &Netlist<I>, script: &str) -> Result<Rc<Netlist<Gate>>, Error>
+where
+    I: Evaluate,
+{
+    abc_with(netlist, script, &AbcOptions::default())
+}
+
+/// Runs the ABC `script`, a list of commands separated by `;` like `"dc2; dch; balance"`, on `netlist`:
+/// writes the netlist as an AIG, runs `read_aiger`, `strash`, the script, `strash` and `write_aiger` in ABC,
+/// and reads the result back as a netlist with the name, inputs and outputs of `netlist`.
+/// Returns the errors of [Netlist::to_logic_network] for sequential netlists, [Error::IoError] if ABC cannot be run
+/// or fails, and [Error::ParseError] if its result does not have the same inputs and outputs.
+pub fn abc_with<I>(
+    netlist: &Netlist<I>,
+    script: &str,
+    options: &AbcOptions,
+) -> Result<Rc<Netlist<Gate>>, Error>
+where
+    I: Evaluate,
+{
+    let network = netlist.to_logic_network()?;
+    let dir = match &options.dir {
+        Some(dir) => dir.clone(),
+        None => {
+            static RUNS: AtomicUsize = AtomicUsize::new(0);
+            let run = RUNS.fetch_add(1, Ordering::Relaxed);
+            std::env::temp_dir().join(format!("safety-net-abc-{}-{run}", std::process::id()))
+        }
+    };
+    std::fs::create_dir_all(&dir)?;
+    let result = run(&network, script, options, &dir);
+    if options.dir.is_none() {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    let optimized = result?;
+
+    let inputs: Vec<Identifier> = network.inputs().map(|(_, name)| name.clone()).collect();
+    if optimized.inputs().count() != inputs.len()
+        || optimized.outputs().len() != network.outputs().len()
+    {
+        return Err(Error::ParseError(format!(
+            "ABC returned {} inputs and {} outputs instead of {} and {}",
+            optimized.inputs().count(),
+            optimized.outputs().len(),
+            inputs.len(),
+            network.outputs().len()
+        )));
+    }
+    let mut renamed = Renamed {
+        network: LogicNetwork::new(),
+        inputs: inputs.into_iter(),
+        outputs: network
+            .outputs()
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>()
+            .into_iter(),
+    };
+    optimized.replay(&mut renamed);
+    renamed.network.to_netlist(&netlist.get_name())
+}
+
+/// Writes `network` to `dir`, runs ABC on it and returns its result
+fn run(
+    network: &LogicNetwork,
+    script: &str,
+    options: &AbcOptions,
+    dir: &Path,
+) -> Result<LogicNetwork, Error> {
+    let input = dir.join("input.aig");
+    let output = dir.join("output.aig");
+    std::fs::write(&input, network.to_aig_bytes())?;
+    let commands = format!(
+        "read_aiger {}; strash; {}; strash; write_aiger -s {}",
+        input.display(),
+        script.trim().trim_end_matches(';'),
+        output.display()
+    );
+    let mut command = Command::new(options.binary.as_deref().unwrap_or(Path::new("abc")));
+    command.args(["-q", &commands]);
+    let result = command.output()?;
+    // ABC reports errors on its standard output and exits successfully, but then writes no result
+    if !result.status.success() || !output.exists() {
+        let log = [result.stdout, result.stderr]
+            .iter()
+            .map(|o| String::from_utf8_lossy(o).trim().to_string())
+            .filter(|o| !o.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(Error::IoError(std::io::Error::other(format!(
+            "{:?} failed: {log}",
+            command.get_program()
+        ))));
+    }
+    LogicNetwork::from_aig_bytes(&std::fs::read(&output)?)
+}
+
+/// Copies a network while renaming its inputs and outputs in order
+struct Renamed<N, O> {
+    network: LogicNetwork,
+    inputs: N,
+    outputs: O,
+}
+
+impl<N, O> NetworkBuilder for Renamed<N, O>
+where
+    N: Iterator<Item = Identifier>,
+    O: Iterator<Item = Identifier>,
+{
+    type Signal = Signal;
+
+    fn constant(&mut self, value: bool) -> Signal {
+        self.network.constant(value)
+    }
+
+    fn create_pi(&mut self, name: &Identifier) -> Signal {
+        let name = self.inputs.next().unwrap_or_else(|| name.clone());
+        self.network.create_pi(&name)
+    }
+
+    fn create_po(&mut self, signal: Signal, name: &Identifier) {
+        let name = self.outputs.next().unwrap_or_else(|| name.clone());
+        self.network.create_po(signal, &name)
+    }
+
+    fn create_not(&mut self, a: &Signal) -> Signal {
+        self.network.create_not(a)
+    }
+
+    fn create_and(&mut self, a: &Signal, b: &Signal) -> Signal {
+        self.network.create_and(a, b)
+    }
+}
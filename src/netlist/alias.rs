@@ -0,0 +1,79 @@
+/*!
+
+  Aliases of nets, as in `assign y = a;`.
+
+  An alias is another name for a net, which is neither a gate nor a port. Loads read the net itself, and the alias
+  follows it through edits: it moves with the uses of the net when they are replaced, and is removed with its driver.
+  An alias is not a load, so it does not keep its driver from being removed by [Netlist::clean].
+  Aliases are emitted as wires with `assign` statements, unless [super::VerilogOptions::aliases] collapses them.
+
+*/
+
+use super::{DrivenNet, Netlist};
+use crate::{
+    circuit::{Identifier, Instantiable, Net},
+    error::Error,
+};
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Inserts an alias named `name` for `net`, as in `assign name = net;`, without making it a port.
+    /// Returns the net of the alias, which has the data type of `net`, or [Error::NonuniqueNets] if a net,
+    /// a port or another alias is already named `name`.
+    pub fn insert_alias(&self, net: &DrivenNet<I>, name: Identifier) -> Result<Net, Error> {
+        let alias = net.as_net().with_name(name.clone());
+        let taken = self
+            .objects()
+            .any(|o| o.nets().any(|n| *n.get_identifier() == name))
+            || self
+                .outputs
+                .borrow()
+                .iter()
+                .any(|(_, n)| *n.get_identifier() == name)
+            || self.resolve_alias(&name).is_some();
+        if taken {
+            return Err(Error::NonuniqueNets(vec![alias]));
+        }
+        self.aliases
+            .borrow_mut()
+            .push((net.get_operand(), alias.clone()));
+        Ok(alias)
+    }
+
+    /// Returns the nets that are aliased and their aliases, in order of insertion
+    pub fn aliases(&self) -> Vec<(DrivenNet<I>, Net)> {
+        self.aliases
+            .borrow()
+            .iter()
+            .map(|(operand, alias)| (self.driven_net(operand), alias.clone()))
+            .collect()
+    }
+
+    /// Returns the net that the alias `name` stands for, if there is such an alias
+    pub fn resolve_alias(&self, name: &Identifier) -> Option<DrivenNet<I>> {
+        self.aliases
+            .borrow()
+            .iter()
+            .find(|(_, alias)| alias.get_identifier() == name)
+            .map(|(operand, _)| self.driven_net(operand))
+    }
+
+    /// Removes the alias `name`, returning the net it stood for, or [Error::NetNotFound] if there is no such alias
+    pub fn remove_alias(&self, name: &Identifier) -> Result<DrivenNet<I>, Error> {
+        let mut aliases = self.aliases.borrow_mut();
+        let pos = aliases
+            .iter()
+            .position(|(_, alias)| alias.get_identifier() == name)
+            .ok_or_else(|| Error::NetNotFound(Net::new_logic(name.clone())))?;
+        let (operand, _) = aliases.remove(pos);
+        drop(aliases);
+        Ok(self.driven_net(&operand))
+    }
+
+    /// Removes every alias, so that only the nets they stand for remain. Returns the number of removed aliases.
+    pub fn collapse_aliases(&self) -> usize {
+        self.aliases.take().len()
+    }
+}
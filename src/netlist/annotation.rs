@@ -0,0 +1,147 @@
+/*!
+
+  Stable identifiers for circuit nodes and side tables keyed by them.
+
+*/
+
+use super::Netlist;
+use crate::circuit::Instantiable;
+use std::collections::{HashMap, HashSet, hash_map};
+
+/// An identifier of a circuit node which is stable across edits to the rest of the netlist.
+/// Unlike the position of the node, it is not reused after the node is cleaned away.
+/// Identifiers are only unique within a netlist, and are renumbered by serialization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectId(pub(crate) usize);
+
+impl std::fmt::Display for ObjectId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// A stable identifier of a net: the driving circuit node and its output position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NetId {
+    /// The circuit node driving the net
+    pub object: ObjectId,
+    /// The output position of the net on its driver
+    pub output: usize,
+}
+
+impl std::fmt::Display for NetId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.object, self.output)
+    }
+}
+
+/// A key of an [AnnotationMap], which refers to a circuit node
+pub trait AnnotationKey: Copy + Eq + std::hash::Hash {
+    /// Returns the circuit node the key refers to
+    fn object(&self) -> ObjectId;
+}
+
+impl AnnotationKey for ObjectId {
+    fn object(&self) -> ObjectId {
+        *self
+    }
+}
+
+impl AnnotationKey for NetId {
+    fn object(&self) -> ObjectId {
+        self.object
+    }
+}
+
+/// A side table of data about circuit nodes (or their nets, with [NetId] keys), like placement or timing.
+/// Entries stay valid across edits, and entries of deleted nodes can be dropped with [AnnotationMap::retain_live].
+#[derive(Debug, Clone)]
+pub struct AnnotationMap<T, K: AnnotationKey = ObjectId> {
+    map: HashMap<K, T>,
+}
+
+impl<T, K> Default for AnnotationMap<T, K>
+where
+    K: AnnotationKey,
+{
+    fn default() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+}
+
+impl<T, K> AnnotationMap<T, K>
+where
+    K: AnnotationKey,
+{
+    /// Creates an empty annotation map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Annotates `key` with `value`, returning the old annotation
+    pub fn insert(&mut self, key: K, value: T) -> Option<T> {
+        self.map.insert(key, value)
+    }
+
+    /// Returns the annotation of `key`
+    pub fn get(&self, key: &K) -> Option<&T> {
+        self.map.get(key)
+    }
+
+    /// Returns a mutable reference to the annotation of `key`
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut T> {
+        self.map.get_mut(key)
+    }
+
+    /// Returns the entry of `key` for in-place manipulation
+    pub fn entry(&mut self, key: K) -> hash_map::Entry<'_, K, T> {
+        self.map.entry(key)
+    }
+
+    /// Removes the annotation of `key`, returning it
+    pub fn remove(&mut self, key: &K) -> Option<T> {
+        self.map.remove(key)
+    }
+
+    /// Returns `true` if `key` is annotated
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Returns the number of annotations
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if there are no annotations
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the annotations, in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &T)> {
+        self.map.iter()
+    }
+
+    /// Drops the annotations of circuit nodes which are no longer in `netlist`
+    pub fn retain_live<I>(&mut self, netlist: &Netlist<I>)
+    where
+        I: Instantiable,
+    {
+        let live: HashSet<ObjectId> = netlist.objects().map(|o| o.get_id()).collect();
+        self.map.retain(|k, _| live.contains(&k.object()));
+    }
+}
+
+impl<T, K> FromIterator<(K, T)> for AnnotationMap<T, K>
+where
+    K: AnnotationKey,
+{
+    fn from_iter<It: IntoIterator<Item = (K, T)>>(iter: It) -> Self {
+        Self {
+            map: iter.into_iter().collect(),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+/*!
+
+  Standard benchmark circuits for tests, like the ISCAS and EPFL suites, downloaded once into a local cache.
+
+  A [Benchmark] names a file by its URL and its SHA-256 checksum, and a [BenchmarkCache] loads it as a netlist of
+  [Gate]s, from the cache when the cached file still has that checksum, or else by fetching it again.
+  Only ASCII AIGER files can be read for now, so `.bench` and BLIF benchmarks are cached but not loaded.
+
+*/
+
+use super::{Gate, Netlist, network::LogicNetwork};
+use crate::error::Error;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    rc::Rc,
+};
+
+/// The environment variable with the directory of the default cache
+pub const CACHE_VAR: &str = "SAFETY_NET_BENCHMARKS";
+
+/// The format of a benchmark file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchmarkFormat {
+    /// ASCII AIGER, with the `.aag` extension
+    Aag,
+    /// The ISCAS `.bench` format
+    Bench,
+    /// The Berkeley Logic Interchange Format, with the `.blif` extension
+    Blif,
+}
+
+impl BenchmarkFormat {
+    /// Returns the extension of files in the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BenchmarkFormat::Aag => "aag",
+            BenchmarkFormat::Bench => "bench",
+            BenchmarkFormat::Blif => "blif",
+        }
+    }
+}
+
+/// A benchmark circuit that can be downloaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Benchmark {
+    /// The name of the benchmark, which is the name of the netlist and of the cached file
+    pub name: String,
+    /// Where to download the benchmark from
+    pub url: String,
+    /// The SHA-256 checksum of the file, in hexadecimal
+    pub sha256: String,
+    /// The format of the file
+    pub format: BenchmarkFormat,
+}
+
+/// A function that downloads the contents at a URL
+type Fetch = Box<dyn Fn(&str) -> Result<Vec<u8>, Error>>;
+
+/// A directory of downloaded benchmark files, checked against their checksums before every use
+pub struct BenchmarkCache {
+    dir: PathBuf,
+    fetch: Fetch,
+}
+
+impl std::fmt::Debug for BenchmarkCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BenchmarkCache")
+            .field("dir", &self.dir)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BenchmarkCache {
+    /// Creates a cache in `dir`, which downloads files with `curl`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            fetch: Box::new(curl),
+        }
+    }
+
+    /// Creates a cache in the directory of the [CACHE_VAR] environment variable,
+    /// or in `safety-net-benchmarks` under the temporary directory
+    pub fn from_env() -> Self {
+        match std::env::var_os(CACHE_VAR) {
+            Some(dir) => Self::new(dir),
+            None => Self::new(std::env::temp_dir().join("safety-net-benchmarks")),
+        }
+    }
+
+    /// Downloads files with `fetch` instead, like from a mirror or from memory
+    pub fn with_fetch(mut self, fetch: impl Fn(&str) -> Result<Vec<u8>, Error> + 'static) -> Self {
+        self.fetch = Box::new(fetch);
+        self
+    }
+
+    /// Returns the directory of the cache
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the path of the cached file of `benchmark`
+    pub fn path(&self, benchmark: &Benchmark) -> PathBuf {
+        self.dir.join(format!(
+            "{}.{}",
+            benchmark.name,
+            benchmark.format.extension()
+        ))
+    }
+
+    /// Returns the contents of `benchmark`, from the cache if the cached file has the right checksum,
+    /// or else downloaded and cached. Returns [Error::ChecksumMismatch] if the download has the wrong checksum,
+    /// which is then not cached.
+    pub fn get(&self, benchmark: &Benchmark) -> Result<Vec<u8>, Error> {
+        let path = self.path(benchmark);
+        if let Ok(data) = std::fs::read(&path)
+            && sha256(&data).eq_ignore_ascii_case(&benchmark.sha256)
+        {
+            return Ok(data);
+        }
+        let data = (self.fetch)(&benchmark.url)?;
+        let actual = sha256(&data);
+        if !actual.eq_ignore_ascii_case(&benchmark.sha256) {
+            return Err(Error::ChecksumMismatch {
+                name: benchmark.url.clone(),
+                expected: benchmark.sha256.clone(),
+                actual,
+            });
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(&path, &data)?;
+        Ok(data)
+    }
+
+    /// Returns `benchmark` as a netlist named after it, see [BenchmarkCache::get].
+    /// Returns [Error::Unsupported] for formats without a parser.
+    pub fn load(&self, benchmark: &Benchmark) -> Result<Rc<Netlist<Gate>>, Error> {
+        let data = self.get(benchmark)?;
+        match benchmark.format {
+            BenchmarkFormat::Aag => {
+                let text = String::from_utf8(data)
+                    .map_err(|e| Error::ParseError(format!("{}: {e}", benchmark.name)))?;
+                LogicNetwork::from_aag(&text)?.to_netlist(&benchmark.name)
+            }
+            format => Err(Error::Unsupported(format!(
+                "Reading .{} benchmarks",
+                format.extension()
+            ))),
+        }
+    }
+}
+
+/// Downloads `url` with the `curl` command
+fn curl(url: &str) -> Result<Vec<u8>, Error> {
+    let output = Command::new("curl").args(["-fsSL", url]).output()?;
+    if !output.status.success() {
+        return Err(Error::IoError(std::io::Error::other(format!(
+            "curl failed to download {url}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(output.stdout)
+}
+
+/// The round constants of SHA-256
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Returns the SHA-256 checksum of `data`, in lowercase hexadecimal
+pub fn sha256(data: &[u8]) -> String {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes(word.try_into().unwrap());
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *h = h.wrapping_add(v);
+        }
+    }
+    h.iter().map(|v| format!("{v:08x}")).collect()
+}
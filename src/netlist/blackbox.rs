@@ -0,0 +1,118 @@
+/*!
+
+  Black-box cells, whose function is unknown to the tool.
+
+*/
+
+use crate::{
+    attribute::Parameter,
+    circuit::{Evaluate, Identifier, Instantiable, Net},
+    logic::Logic,
+};
+
+/// A cell that is only known by its name and ports, such as an IP block.
+/// The outputs of black boxes are the free variables of simulation, like those of sequential cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlackBox {
+    /// The name of the module
+    name: Identifier,
+    /// Input ports, order matters
+    inputs: Vec<Net>,
+    /// Output ports, order matters
+    outputs: Vec<Net>,
+    /// Parameter overrides of the instance, in order of insertion
+    parameters: Vec<(Identifier, Parameter)>,
+}
+
+impl BlackBox {
+    /// Creates a black box with four-state ports
+    pub fn new(name: Identifier, inputs: Vec<Identifier>, outputs: Vec<Identifier>) -> Self {
+        if name.is_sliced() {
+            panic!("Attempted to create a black box with a sliced identifier: {name}");
+        }
+
+        Self {
+            name,
+            inputs: inputs.into_iter().map(Net::new_logic).collect(),
+            outputs: outputs.into_iter().map(Net::new_logic).collect(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Renames the module that the black box instantiates
+    pub fn set_name(&mut self, name: Identifier) {
+        if name.is_sliced() {
+            panic!("Attempted to rename a black box with a sliced identifier: {name}");
+        }
+        self.name = name;
+    }
+
+    /// Removes the parameter overrides of the instance
+    pub fn clear_parameters(&mut self) {
+        self.parameters.clear();
+    }
+}
+
+impl Instantiable for BlackBox {
+    fn get_name(&self) -> &Identifier {
+        &self.name
+    }
+
+    fn get_input_ports(&self) -> impl IntoIterator<Item = &Net> {
+        &self.inputs
+    }
+
+    fn get_output_ports(&self) -> impl IntoIterator<Item = &Net> {
+        &self.outputs
+    }
+
+    fn has_parameter(&self, id: &Identifier) -> bool {
+        self.parameters.iter().any(|(k, _)| k == id)
+    }
+
+    fn get_parameter(&self, id: &Identifier) -> Option<Parameter> {
+        self.parameters
+            .iter()
+            .find(|(k, _)| k == id)
+            .map(|(_, v)| v.clone())
+    }
+
+    /// The parameters of a black box are unknown, so any parameter can be overridden
+    fn set_parameter(&mut self, id: &Identifier, val: Parameter) -> Option<Parameter> {
+        match self.parameters.iter_mut().find(|(k, _)| k == id) {
+            Some((_, v)) => Some(std::mem::replace(v, val)),
+            None => {
+                self.parameters.push((id.clone(), val));
+                None
+            }
+        }
+    }
+
+    fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)> {
+        self.parameters.clone().into_iter()
+    }
+
+    fn from_constant(_val: Logic) -> Option<Self> {
+        None
+    }
+
+    fn get_constant(&self) -> Option<Logic> {
+        None
+    }
+
+    fn is_seq(&self) -> bool {
+        false
+    }
+
+    fn is_blackbox(&self) -> bool {
+        true
+    }
+}
+
+/// The outputs of a black box are unknown
+impl Evaluate for BlackBox {
+    fn eval(&self, _inputs: &[Logic]) -> Vec<Logic> {
+        vec![Logic::X; self.outputs.len()]
+    }
+}
@@ -0,0 +1,183 @@
+/*!
+
+  Writing netlists as BTOR2 word-level models, for sequential model checking and equivalence checking with external tools.
+
+  Every net is a bit-vector of width 1. Principal inputs and the outputs of black boxes are `input`s, and each
+  sequential cell is a `state` whose `next` value is its [Evaluate] function of its inputs, as if all registers
+  shared a single clock. Top-level outputs are written as `output`s, or as `bad`-state properties that a model
+  checker tries to reach.
+
+*/
+
+use super::{
+    Netlist,
+    network::{NetworkBuilder, cell_outputs},
+    sim::init_value,
+};
+use crate::{
+    circuit::{Evaluate, Identifier},
+    error::Error,
+    graph::TopoOrder,
+};
+use std::{collections::HashMap, io::Write};
+
+/// The node of the sort of every net: a bit-vector of width 1
+const BIT: i64 = 1;
+
+/// Options for [Netlist::to_btor2]
+#[derive(Debug, Clone, Default)]
+pub struct Btor2Options {
+    /// The top-level outputs written as `bad`-state properties instead of `output`s.
+    /// A property is violated when its output can become high.
+    pub bad: Vec<Identifier>,
+}
+
+/// The lines of a BTOR2 model being built, where negative node references are complements
+struct Btor2Builder {
+    lines: Vec<String>,
+    constants: [Option<i64>; 2],
+}
+
+impl Btor2Builder {
+    fn new() -> Self {
+        Self {
+            lines: vec![format!("{BIT} sort bitvec 1")],
+            constants: [None; 2],
+        }
+    }
+
+    /// Adds the node `op` with `args`, returning its reference
+    fn node(&mut self, op: &str, args: &str) -> i64 {
+        let id = self.lines.len() as i64 + 1;
+        self.lines.push(format!("{id} {op} {args}"));
+        id
+    }
+}
+
+/// Returns the name of `id` as a BTOR2 symbol, which cannot contain whitespace
+fn symbol(id: &Identifier) -> String {
+    id.get_name().replace(char::is_whitespace, "_")
+}
+
+impl NetworkBuilder for Btor2Builder {
+    type Signal = i64;
+
+    fn constant(&mut self, value: bool) -> i64 {
+        if let Some(id) = self.constants[usize::from(value)] {
+            return id;
+        }
+        let id = self.node(if value { "one" } else { "zero" }, &BIT.to_string());
+        self.constants[usize::from(value)] = Some(id);
+        id
+    }
+
+    fn create_pi(&mut self, name: &Identifier) -> i64 {
+        self.node("input", &format!("{BIT} {}", symbol(name)))
+    }
+
+    fn create_po(&mut self, signal: i64, name: &Identifier) {
+        self.node("output", &format!("{signal} {}", symbol(name)));
+    }
+
+    fn create_not(&mut self, a: &i64) -> i64 {
+        -a
+    }
+
+    fn create_and(&mut self, a: &i64, b: &i64) -> i64 {
+        self.node("and", &format!("{BIT} {a} {b}"))
+    }
+
+    fn create_or(&mut self, a: &i64, b: &i64) -> i64 {
+        self.node("or", &format!("{BIT} {a} {b}"))
+    }
+
+    fn create_xor(&mut self, a: &i64, b: &i64) -> i64 {
+        self.node("xor", &format!("{BIT} {a} {b}"))
+    }
+
+    fn create_mux(&mut self, sel: &i64, a: &i64, b: &i64) -> i64 {
+        self.node("ite", &format!("{BIT} {sel} {b} {a}"))
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Evaluate,
+{
+    /// Writes the netlist to `writer` as a BTOR2 model. Registers start from the value of their `INIT` parameter when
+    /// it is `0` or `1`, and are unconstrained otherwise. Each cell needs at most 6 inputs.
+    /// Returns [Error::Unsupported] for latches, multi-output sequential cells, tri-state outputs and unknown constants,
+    /// [Error::PortNotFound] for a `bad` output that does not exist, [Error::UnconnectedInputs] for unconnected input ports,
+    /// or an error if the netlist has combinational cycles.
+    pub fn to_btor2(&self, writer: &mut impl Write, options: &Btor2Options) -> Result<(), Error> {
+        let outputs = self.outputs();
+        if let Some(missing) = options.bad.iter().find(|bad| {
+            !outputs
+                .iter()
+                .any(|(_, port)| port.get_identifier() == *bad)
+        }) {
+            return Err(Error::PortNotFound(missing.clone()));
+        }
+
+        let order = self.get_analysis::<TopoOrder<I>>()?.get_order().to_vec();
+        let mut builder = Btor2Builder::new();
+        let mut signals = HashMap::new();
+        let mut registers = Vec::new();
+        for node in order {
+            let Some(cell) = node.get_instance_type().map(|c| c.clone()) else {
+                let net = node.get_output(0);
+                signals.insert(net.clone(), builder.create_pi(&net.get_identifier()));
+                continue;
+            };
+            let inst_name = node.get_instance_name().expect("Instance has a name");
+            if cell.is_latch() {
+                return Err(Error::Unsupported(format!("latch {inst_name}")));
+            } else if cell.is_seq() {
+                if node.is_multi_output() {
+                    return Err(Error::Unsupported(format!(
+                        "multi-output sequential cell {inst_name}"
+                    )));
+                }
+                let net = node.get_output(0);
+                let state = builder.node("state", &format!("{BIT} {}", symbol(&inst_name)));
+                signals.insert(net, state);
+                registers.push((node, state, init_value(&cell)));
+            } else if cell.is_blackbox() {
+                for net in node.outputs() {
+                    signals.insert(net.clone(), builder.create_pi(&net.get_identifier()));
+                }
+            } else {
+                for (pos, signal) in cell_outputs(&mut builder, &node, &signals)?
+                    .into_iter()
+                    .enumerate()
+                {
+                    signals.insert(node.get_output(pos), signal);
+                }
+            }
+        }
+
+        for (node, state, init) in registers {
+            if let Some(value) = init {
+                let value = builder.constant(value);
+                builder.node("init", &format!("{BIT} {state} {value}"));
+            }
+            let next = cell_outputs(&mut builder, &node, &signals)?[0];
+            builder.node("next", &format!("{BIT} {state} {next}"));
+        }
+
+        for (net, port) in outputs {
+            let id = port.get_identifier();
+            let signal = signals[&net];
+            if options.bad.contains(id) {
+                builder.node("bad", &format!("{signal} {}", symbol(id)));
+            } else {
+                builder.create_po(signal, id);
+            }
+        }
+
+        for line in builder.lines {
+            writeln!(writer, "{line}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+/*!
+
+  Canonical forms of netlists, for golden files that compare byte for byte.
+
+  [Netlist::canonicalize] reorders the circuit nodes and renames the anonymous instances and nets without changing
+  the connections, so that two netlists built in a different order, or with different generated names, end up
+  identical when emitted as Verilog or serialized.
+
+*/
+
+use super::{Netlist, Object, sort_key};
+use crate::{
+    circuit::{Identifier, Instantiable},
+    error::Error,
+    util::{debug_span, glob_match, trace_event},
+};
+use std::collections::{HashMap, HashSet};
+
+/// Options for [Netlist::canonicalize]
+#[derive(Debug, Clone, Default)]
+pub struct CanonicalOptions {
+    /// The instances and nets whose names match any of these glob patterns are anonymous, like `rewrite_*` or `_*_`.
+    /// Anonymous instances are renamed `_0_`, `_1_` and so on in canonical order, and anonymous nets are named after
+    /// the instance and port that drive them. Ports are never renamed.
+    pub anonymous: Vec<String>,
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Returns the canonical order of the instances: a depth-first search through the drivers of each instance,
+    /// starting from the top-level outputs by name, then from the named instances by name.
+    /// Instances that reach neither are visited last, in netlist order.
+    fn canonical_order(&self, is_anonymous: &impl Fn(&Identifier) -> bool) -> Vec<usize> {
+        let objects = self.objects.borrow();
+        let mut roots: Vec<usize> = Vec::new();
+        let mut outputs = self.outputs.borrow().clone();
+        outputs.sort_by(|(_, a), (_, b)| {
+            sort_key(a.get_identifier()).cmp(&sort_key(b.get_identifier()))
+        });
+        roots.extend(
+            outputs
+                .iter()
+                .filter_map(|(o, _)| o.as_ref().map(|o| o.root())),
+        );
+        let mut named: Vec<(Identifier, usize)> = objects
+            .iter()
+            .enumerate()
+            .filter_map(|(i, obj)| match obj.borrow().get() {
+                Object::Instance(_, name, _) if !is_anonymous(name) => Some((name.clone(), i)),
+                _ => None,
+            })
+            .collect();
+        named.sort_by(|(a, _), (b, _)| sort_key(a).cmp(&sort_key(b)));
+        roots.extend(named.into_iter().map(|(_, i)| i));
+        roots.extend(0..objects.len());
+
+        let mut visited: Vec<bool> = objects
+            .iter()
+            .map(|obj| matches!(obj.borrow().get(), Object::Input(_)))
+            .collect();
+        let mut order = Vec::with_capacity(objects.len());
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        for root in roots {
+            if visited[root] {
+                continue;
+            }
+            visited[root] = true;
+            stack.push((root, 0));
+            while let Some((index, pos)) = stack.pop() {
+                let driver = objects[index]
+                    .borrow()
+                    .operands
+                    .get(pos)
+                    .map(|o| o.as_ref().map(|o| o.root()));
+                match driver {
+                    None => order.push(index),
+                    Some(driver) => {
+                        stack.push((index, pos + 1));
+                        if let Some(driver) = driver.filter(|d| !visited[*d]) {
+                            visited[driver] = true;
+                            stack.push((driver, 0));
+                        }
+                    }
+                }
+            }
+        }
+        order
+    }
+
+    /// Moves the object at `order[i]` to index `i`, and remaps the operands, outputs, aliases, the index by identifier
+    /// and the structural hashing table
+    fn reorder(&self, order: &[usize]) {
+        let old_objects = self.objects.take();
+        let mut remap = vec![0; old_objects.len()];
+        for (new_index, old_index) in order.iter().enumerate() {
+            remap[*old_index] = new_index;
+            let obj = old_objects[*old_index].clone();
+            obj.borrow_mut().index = new_index;
+            self.objects.borrow_mut().push(obj);
+        }
+        for obj in self.objects.borrow().iter() {
+            for operand in obj.borrow_mut().inds_mut() {
+                *operand = operand.clone().remap(remap[operand.root()]);
+            }
+        }
+        for operand in self.outputs.borrow_mut().iter_mut().flat_map(|(o, _)| o) {
+            *operand = operand.clone().remap(remap[operand.root()]);
+        }
+        for (operand, _) in self.aliases.borrow_mut().iter_mut() {
+            *operand = operand.clone().remap(remap[operand.root()]);
+        }
+        self.rebuild_id_index();
+        self.rebuild_uses();
+        self.strash_rebuild();
+    }
+
+    /// Rewrites the netlist into a canonical form without changing its connections:
+    /// the principal inputs come first by name, followed by the instances in a depth-first order from the outputs,
+    /// the anonymous instances and nets of `options` get names after their position in that order,
+    /// and the outputs, aliases and module ports are sorted by name.
+    /// The result does not depend on the order in which the netlist was built, so long as every instance reaches an output
+    /// or a named instance, as after [Netlist::clean]. Stable identifiers are kept.
+    /// Returns the number of renamed instances and nets, or an error if a canonical name is already taken.
+    pub fn canonicalize(&self, options: &CanonicalOptions) -> Result<usize, Error> {
+        debug_span!("canonicalize", netlist = %self.get_name());
+        let is_anonymous = |id: &Identifier| {
+            options
+                .anonymous
+                .iter()
+                .any(|p| glob_match(p, &id.to_string()))
+        };
+
+        let mut inputs: Vec<(Identifier, usize)> = self
+            .objects()
+            .filter(|o| o.is_an_input())
+            .map(|o| (o.get_identifier(), o.netref.borrow().get_index()))
+            .collect();
+        inputs.sort_by(|(a, _), (b, _)| sort_key(a).cmp(&sort_key(b)));
+        let mut order: Vec<usize> = inputs.into_iter().map(|(_, i)| i).collect();
+        order.extend(self.canonical_order(&is_anonymous));
+        self.reorder(&order);
+        self.outputs.borrow_mut().sort_by(|(_, a), (_, b)| {
+            sort_key(a.get_identifier()).cmp(&sort_key(b.get_identifier()))
+        });
+        self.aliases.borrow_mut().sort_by(|(_, a), (_, b)| {
+            sort_key(a.get_identifier()).cmp(&sort_key(b.get_identifier()))
+        });
+        self.port_order.borrow_mut().clear();
+
+        // The names that stay, which canonical names must avoid
+        let ports: HashSet<Identifier> = self
+            .get_input_ports()
+            .chain(self.get_output_ports())
+            .map(|n| n.take_identifier())
+            .collect();
+        let mut taken: HashSet<Identifier> = ports.clone();
+        for obj in self.objects() {
+            if let Some(name) = obj.get_instance_name().filter(|n| !is_anonymous(n)) {
+                taken.insert(name);
+            }
+            taken.extend(
+                obj.nets()
+                    .map(|n| n.take_identifier())
+                    .filter(|n| !is_anonymous(n)),
+            );
+        }
+        taken.extend(
+            self.aliases
+                .borrow()
+                .iter()
+                .map(|(_, n)| n.get_identifier().clone()),
+        );
+        let mut count = 0;
+        let mut fresh = |taken: &mut HashSet<Identifier>| loop {
+            let name = Identifier::new(format!("_{count}_"));
+            count += 1;
+            if taken.insert(name.clone()) {
+                return name;
+            }
+        };
+
+        let mut instances = 0;
+        let mut nets: HashMap<Identifier, Identifier> = HashMap::new();
+        for obj in self.instances() {
+            let mut name = obj.get_instance_name().unwrap();
+            if is_anonymous(&name) {
+                name = fresh(&mut taken);
+                obj.set_instance_name(name.clone());
+                instances += 1;
+            }
+            let pins: Vec<Identifier> = obj
+                .get_instance_type()
+                .unwrap()
+                .get_output_ports()
+                .into_iter()
+                .map(|p| p.get_identifier().clone())
+                .collect();
+            for (net, pin) in obj.nets().zip(pins) {
+                let old = net.get_identifier();
+                if !is_anonymous(old) || ports.contains(old) {
+                    continue;
+                }
+                let mut new = &name + &pin;
+                if *old != new && !taken.insert(new.clone()) {
+                    new = fresh(&mut taken);
+                }
+                if *old != new {
+                    nets.insert(old.clone(), new);
+                }
+            }
+        }
+        let renamed = self.rename_nets(|id| nets.get(id).cloned())?;
+        trace_event!(debug, instances, nets = renamed, "canonicalized");
+        Ok(instances + renamed)
+    }
+}
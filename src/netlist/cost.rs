@@ -0,0 +1,83 @@
+/*!
+
+  Area and cost reporting with cell cost models.
+
+*/
+
+use super::{Gate, Netlist, select::Selection};
+use crate::circuit::Instantiable;
+use std::collections::BTreeMap;
+
+/// Every instance costs one, so the total is the cell count
+pub fn unit<I: Instantiable>(_cell: &I) -> f64 {
+    1.0
+}
+
+/// Counts the lookup tables of an FPGA netlist, which are the cells named `LUT1` through `LUT6` and the like
+pub fn lut_count<I: Instantiable>(cell: &I) -> f64 {
+    let name = cell.get_name().get_name();
+    match name.strip_prefix("LUT") {
+        Some(size) if !size.is_empty() && size.bytes().all(|b| b.is_ascii_digit()) => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// The area of a gate in gate-equivalents, which is the transistor count of its static CMOS implementation divided by
+/// the four transistors of a two-input NAND gate. Constants are free, and unknown gates count as one NAND gate.
+pub fn gate_equivalents(gate: &Gate) -> f64 {
+    let n = gate.inputs.len() as f64;
+    match gate.get_gate_name().get_name() {
+        "NAND" | "NOR" => n / 2.0,
+        "AND" | "OR" => n / 2.0 + 0.5,
+        "XOR" | "XNOR" => 2.5 * (n - 1.0).max(1.0),
+        "INV" | "NOT" => 0.5,
+        "BUF" => 1.0,
+        "MUX" => 3.0,
+        "TBUF" => 2.5,
+        "VDD" | "GND" => 0.0,
+        _ => 1.0,
+    }
+}
+
+/// The total cost of some cells under a cost model
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cost {
+    /// The cost of all the cells
+    pub total: f64,
+    /// The cost of each cell type
+    pub cells: BTreeMap<String, f64>,
+}
+
+impl std::fmt::Display for Cost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "total: {}", self.total)?;
+        for (cell, cost) in &self.cells {
+            writeln!(f, "  {cell}: {cost}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Adds up the cost of the instances in `selection`, where `model` gives the cost of each cell, like [unit] or [gate_equivalents]
+    pub fn cost_of(&self, selection: &Selection, model: &impl Fn(&I) -> f64) -> Cost {
+        let mut cost = Cost::default();
+        for obj in selection.objects(self) {
+            let Some(inst) = obj.get_instance_type() else {
+                continue;
+            };
+            let c = model(&inst);
+            cost.total += c;
+            *cost.cells.entry(inst.get_name().to_string()).or_default() += c;
+        }
+        cost
+    }
+
+    /// Adds up the cost of every instance in the netlist, where `model` gives the cost of each cell
+    pub fn cost(&self, model: &impl Fn(&I) -> f64) -> Cost {
+        self.cost_of(&Selection::all("all", self), model)
+    }
+}
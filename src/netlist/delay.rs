@@ -0,0 +1,134 @@
+/*!
+
+  Delay models of cells and wire-load models of nets, for [super::timing].
+
+*/
+
+use super::{DrivenNet, Netlist};
+use crate::circuit::Instantiable;
+use std::collections::HashMap;
+
+/// A model of the delay of cells, from any input to any output, in the time unit of the constraints
+pub trait DelayModel<I: Instantiable> {
+    /// Returns the delay of `cell` when its latest input has the transition time `input_slew` and its outputs drive `load`
+    fn delay(&self, cell: &I, input_slew: f64, load: f64) -> f64;
+
+    /// Returns the transition time of the outputs of `cell`. The default is an ideal step.
+    fn slew(&self, _cell: &I, _input_slew: f64, _load: f64) -> f64 {
+        0.0
+    }
+
+    /// Returns the load on a net read by `fanout` input ports or top-level outputs. The default counts each one as one.
+    fn load(&self, fanout: usize) -> f64 {
+        fanout as f64
+    }
+}
+
+/// The delay of a cell only depends on the cell, like `|_| 1.0`
+impl<I, F> DelayModel<I> for F
+where
+    I: Instantiable,
+    F: Fn(&I) -> f64,
+{
+    fn delay(&self, cell: &I, _input_slew: f64, _load: f64) -> f64 {
+        self(cell)
+    }
+}
+
+/// Every cell has a delay of one, so arrival times count the logic levels
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnitDelay;
+
+impl<I: Instantiable> DelayModel<I> for UnitDelay {
+    fn delay(&self, _cell: &I, _input_slew: f64, _load: f64) -> f64 {
+        1.0
+    }
+}
+
+/// A wire-load model, which estimates the capacitance of a net from its fanout before placement
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WireLoad {
+    /// The capacitance of the wire of a net with a fanout of one, two, and so on
+    pub capacitance: Vec<f64>,
+    /// The capacitance added by each load beyond the end of the table
+    pub slope: f64,
+}
+
+impl WireLoad {
+    /// Returns the capacitance of the wire of a net with `fanout` loads, extrapolated past the table with the slope
+    pub fn capacitance(&self, fanout: usize) -> f64 {
+        match (fanout, self.capacitance.last()) {
+            (0, _) => 0.0,
+            (_, None) => self.slope * fanout as f64,
+            (n, Some(last)) => match self.capacitance.get(n - 1) {
+                Some(c) => *c,
+                None => last + self.slope * (n - self.capacitance.len()) as f64,
+            },
+        }
+    }
+}
+
+/// The timing of a cell in a [LinearDelay] model
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CellTiming {
+    /// The delay of the cell without load
+    pub intrinsic: f64,
+    /// The drive resistance of the outputs, which adds its product with the load to the delay and the output transition
+    pub resistance: f64,
+}
+
+/// A linear delay model looked up by cell name: the delay of a cell is its intrinsic delay, plus its drive resistance
+/// times its load, plus half the transition time of its inputs, as cells switch at mid-rail.
+/// The load of a net is the input capacitance of its loads plus the capacitance of the wire from a [WireLoad] model.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinearDelay {
+    /// The timing of each cell, by name
+    pub cells: HashMap<String, CellTiming>,
+    /// The timing of the cells that are not listed
+    pub default: CellTiming,
+    /// The input capacitance of every input port
+    pub pin_capacitance: f64,
+    /// The capacitance of the wires
+    pub wire_load: WireLoad,
+}
+
+impl LinearDelay {
+    /// Returns the timing of `cell`
+    fn timing<I: Instantiable>(&self, cell: &I) -> CellTiming {
+        self.cells
+            .get(cell.get_name().get_name())
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+impl<I: Instantiable> DelayModel<I> for LinearDelay {
+    fn delay(&self, cell: &I, input_slew: f64, load: f64) -> f64 {
+        let timing = self.timing(cell);
+        timing.intrinsic + timing.resistance * load + input_slew / 2.0
+    }
+
+    fn slew(&self, cell: &I, _input_slew: f64, load: f64) -> f64 {
+        self.timing(cell).resistance * load
+    }
+
+    fn load(&self, fanout: usize) -> f64 {
+        self.pin_capacitance * fanout as f64 + self.wire_load.capacitance(fanout)
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Returns the number of input ports and top-level outputs that read `net`, which a [DelayModel] turns into a load
+    pub fn fanout(&self, net: &DrivenNet<I>) -> usize {
+        let outputs = self
+            .outputs
+            .borrow()
+            .iter()
+            .filter(|(o, _)| o.as_ref() == Some(&net.get_operand()))
+            .count();
+        self.get_uses(net).len() + outputs
+    }
+}
@@ -0,0 +1,658 @@
+/*!
+
+  Designs of several modules, which instantiate each other by name.
+
+*/
+
+use super::{
+    DrivenNet, NetRef, Netlist, VerilogOptions, blackbox::BlackBox, write_header, write_stub,
+};
+use crate::{
+    attribute::Parameter,
+    circuit::{HierPath, Identifier, Instantiable},
+    error::Error,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+/// Maps the names of the instances and nets of a flattened netlist to their hierarchical paths in the design
+pub type HierMap = HashMap<Identifier, HierPath>;
+
+/// Builds the module of a parameterized instance from the parameters of the instance, like `#(.WIDTH(8))`
+pub type Generator<I> = Box<dyn Fn(&[(Identifier, Parameter)]) -> Result<Rc<Netlist<I>>, Error>>;
+
+/// The parameters of an instance, in the order of [Instantiable::parameters]
+type Parameters = Vec<(Identifier, Parameter)>;
+
+/// A collection of named netlists, one of which is the top module.
+/// An instance refers to the module of the design with the same name as its cell type, like a black box of [Netlist::to_blackbox].
+/// Instances of parameterized modules refer to a [Generator] instead, until the design is elaborated with [Design::elaborate].
+pub struct Design<I: Instantiable> {
+    /// The modules, in order of insertion
+    modules: Vec<Rc<Netlist<I>>>,
+    /// The name of the top module, if it was set
+    top: Option<String>,
+    /// The parameterized modules, by name
+    generators: Vec<(String, Generator<I>)>,
+    /// The modules built by the generators, with the name of the generator and the parameters they were built with
+    elaborated: Vec<(String, Parameters, String)>,
+}
+
+impl<I> Default for Design<I>
+where
+    I: Instantiable,
+{
+    fn default() -> Self {
+        Self {
+            modules: Vec::new(),
+            top: None,
+            generators: Vec::new(),
+            elaborated: Vec::new(),
+        }
+    }
+}
+
+impl<I> std::fmt::Debug for Design<I>
+where
+    I: Instantiable + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let generators: Vec<&String> = self.generators.iter().map(|(name, _)| name).collect();
+        f.debug_struct("Design")
+            .field("modules", &self.modules)
+            .field("top", &self.top)
+            .field("generators", &generators)
+            .finish()
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Returns a black box with the name and ports of the netlist, to instantiate it in another module of a [Design]
+    pub fn to_blackbox(&self) -> BlackBox {
+        BlackBox::new(
+            self.get_name().as_str().into(),
+            self.get_input_ports()
+                .map(|n| n.take_identifier())
+                .collect(),
+            self.get_output_ports()
+                .into_iter()
+                .map(|n| n.take_identifier())
+                .collect(),
+        )
+    }
+}
+
+impl<I> Design<I>
+where
+    I: Instantiable,
+{
+    /// Creates an empty design
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `netlist` as a module of the design.
+    /// Returns [Error::DuplicateModule] if the design already has a module with the same name.
+    pub fn add(&mut self, netlist: Rc<Netlist<I>>) -> Result<(), Error> {
+        let name = netlist.get_name().clone();
+        self.check_unique(&name)?;
+        self.modules.push(netlist);
+        Ok(())
+    }
+
+    /// Adds the parameterized module `name`, whose instances are built by `generator` when the design is elaborated.
+    /// Returns [Error::DuplicateModule] if the design already has a module with the same name.
+    pub fn add_generator(
+        &mut self,
+        name: &str,
+        generator: impl Fn(&[(Identifier, Parameter)]) -> Result<Rc<Netlist<I>>, Error> + 'static,
+    ) -> Result<(), Error> {
+        self.check_unique(name)?;
+        self.generators
+            .push((name.to_string(), Box::new(generator)));
+        Ok(())
+    }
+
+    fn check_unique(&self, name: &str) -> Result<(), Error> {
+        if self.get(name).is_some() || self.generators.iter().any(|(n, _)| n == name) {
+            return Err(Error::DuplicateModule(name.into()));
+        }
+        Ok(())
+    }
+
+    /// Returns a name for a new module, which is `name` suffixed with the first count that is not taken, like `adder_1`
+    fn fresh_name(&self, name: &str) -> String {
+        (1..)
+            .map(|count| format!("{name}_{count}"))
+            .find(|n| self.check_unique(n).is_ok())
+            .unwrap()
+    }
+
+    /// Builds the module of every instance of a parameterized module, with the generator of [Design::add_generator].
+    /// The generator is called once for each set of parameters, and the modules it builds are named after it with a count, like `adder_1`.
+    /// The modules are elaborated too, in case they instantiate parameterized modules.
+    /// `rename` returns the cell of an instance, renamed to instantiate the module that was built for it.
+    /// The parameters of the instance are not parameters of that module, so the cell should usually drop them.
+    ///
+    /// Returns the number of modules that were built, or [Error::PortNotFound] if the ports of an instance are not the ports of its module.
+    pub fn elaborate(&mut self, rename: impl Fn(&I, &Identifier) -> I) -> Result<usize, Error> {
+        let mut built = 0;
+        // Modules are added to the end while their parents are elaborated
+        let mut i = 0;
+        while i < self.modules.len() {
+            let module = self.modules[i].clone();
+            for node in module.objects() {
+                let Some(inst_type) = node.get_instance_type().map(|t| t.clone()) else {
+                    continue;
+                };
+                let generator = inst_type.get_name().get_name().to_string();
+                if !self.generators.iter().any(|(n, _)| *n == generator) {
+                    continue;
+                }
+                let parameters: Vec<_> = inst_type.parameters().collect();
+                let cached = self
+                    .elaborated
+                    .iter()
+                    .find(|(g, p, _)| *g == generator && *p == parameters);
+                let name = match cached {
+                    Some((_, _, name)) => name.clone(),
+                    None => {
+                        let (_, build) = self
+                            .generators
+                            .iter()
+                            .find(|(n, _)| *n == generator)
+                            .unwrap();
+                        let netlist = build(&parameters)?;
+                        let name = self.fresh_name(&generator);
+                        netlist.set_name(name.clone());
+                        self.modules.push(netlist);
+                        self.elaborated.push((generator, parameters, name.clone()));
+                        built += 1;
+                        name
+                    }
+                };
+                let cell = rename(&inst_type, &Identifier::new(name.clone()));
+                check_ports(&cell, self.get(&name).unwrap())?;
+                *node.get_instance_type_mut().unwrap() = cell;
+            }
+            i += 1;
+        }
+        Ok(built)
+    }
+
+    /// Returns the module named `name`
+    pub fn get(&self, name: &str) -> Option<&Rc<Netlist<I>>> {
+        self.modules.iter().find(|m| *m.get_name() == name)
+    }
+
+    /// Iterates over the modules of the design, in order of insertion
+    pub fn modules(&self) -> impl Iterator<Item = &Rc<Netlist<I>>> {
+        self.modules.iter()
+    }
+
+    /// Designates the module named `name` as the top module.
+    /// Returns [Error::ModuleNotFound] if the design has no such module.
+    pub fn set_top(&mut self, name: &str) -> Result<(), Error> {
+        if self.get(name).is_none() {
+            return Err(Error::ModuleNotFound(name.into()));
+        }
+        self.top = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Returns the top module, which is the one set by [Design::set_top],
+    /// or else the only module that is not instantiated by another one
+    pub fn get_top(&self) -> Option<&Rc<Netlist<I>>> {
+        if let Some(top) = &self.top {
+            return self.get(top);
+        }
+        let instantiated: HashSet<String> = self
+            .modules
+            .iter()
+            .flat_map(|m| self.children(m))
+            .map(|m| m.get_name().clone())
+            .collect();
+        let mut roots = self
+            .modules
+            .iter()
+            .filter(|m| !instantiated.contains(&*m.get_name()));
+        match (roots.next(), roots.next()) {
+            (Some(top), None) => Some(top),
+            _ => None,
+        }
+    }
+
+    /// Returns the module that the instance `inst` refers to, if it is a module of the design
+    pub fn resolve(&self, inst: &NetRef<I>) -> Option<&Rc<Netlist<I>>> {
+        let inst_type = inst.get_instance_type()?;
+        self.get(inst_type.get_name().get_name())
+    }
+
+    /// Returns the modules of the design instantiated by `module`, in order of first instantiation
+    pub fn children(&self, module: &Netlist<I>) -> Vec<&Rc<Netlist<I>>> {
+        let mut children: Vec<&Rc<Netlist<I>>> = Vec::new();
+        for node in module.objects() {
+            if let Some(child) = self.resolve(&node)
+                && !children.iter().any(|c| Rc::ptr_eq(c, child))
+            {
+                children.push(child);
+            }
+        }
+        children
+    }
+
+    /// Returns the modules so that every module comes after the modules it instantiates.
+    /// Returns [Error::RecursiveModules] if a module instantiates itself through its submodules.
+    pub fn order(&self) -> Result<Vec<&Rc<Netlist<I>>>, Error> {
+        self.order_with(false)
+    }
+
+    /// Orders the modules like [Design::order], visiting modules by name when `sorted` instead of in order of insertion
+    fn order_with(&self, sorted: bool) -> Result<Vec<&Rc<Netlist<I>>>, Error> {
+        fn by_name<I: Instantiable>(modules: &mut [&Rc<Netlist<I>>]) {
+            modules.sort_by(|a, b| a.get_name().cmp(&b.get_name()));
+        }
+
+        // Depth-first, where `visiting` is the path from the current root
+        fn visit<'a, I: Instantiable>(
+            design: &'a Design<I>,
+            module: &'a Rc<Netlist<I>>,
+            sorted: bool,
+            visiting: &mut Vec<&'a Rc<Netlist<I>>>,
+            order: &mut Vec<&'a Rc<Netlist<I>>>,
+        ) -> Result<(), Error> {
+            if order.iter().any(|m| Rc::ptr_eq(m, module)) {
+                return Ok(());
+            }
+            if let Some(start) = visiting.iter().position(|m| Rc::ptr_eq(m, module)) {
+                let cycle = visiting[start..]
+                    .iter()
+                    .map(|m| m.get_name().as_str().into())
+                    .collect();
+                return Err(Error::RecursiveModules(cycle));
+            }
+            visiting.push(module);
+            let mut children = design.children(module);
+            if sorted {
+                by_name(&mut children);
+            }
+            for child in children {
+                visit(design, child, sorted, visiting, order)?;
+            }
+            visiting.pop();
+            order.push(module);
+            Ok(())
+        }
+
+        let mut roots: Vec<_> = self.modules.iter().collect();
+        if sorted {
+            by_name(&mut roots);
+        }
+        let mut order = Vec::with_capacity(self.modules.len());
+        for module in roots {
+            visit(self, module, sorted, &mut Vec::new(), &mut order)?;
+        }
+        Ok(order)
+    }
+
+    /// Verifies every module of the design, and that the instances of a module have the same input and output ports as the module.
+    /// Returns [Error::PortNotFound] for a port of an instance that the module does not have, or the other way around.
+    pub fn verify(&self) -> Result<(), Error> {
+        self.order()?;
+        for module in &self.modules {
+            module.verify()?;
+            for node in module.objects() {
+                if let (Some(child), Some(inst_type)) =
+                    (self.resolve(&node), node.get_instance_type())
+                {
+                    check_ports(&*inst_type, child)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the modules that are instantiated with different parameters, so that each copy is instantiated with a single set of parameters.
+    /// The instances with the first set of parameters keep the module, and the copies are named after the module with a count, like `adder_1`.
+    /// `rename` returns the cell of an instance, renamed to instantiate the copy.
+    /// Returns the number of modules that were added to the design.
+    pub fn uniquify(&mut self, rename: impl Fn(&I, &Identifier) -> I) -> Result<usize, Error> {
+        // Parents come first, so that the instances in the copies of a module are uniquified too
+        let mut order: Vec<Rc<Netlist<I>>> = self.order()?.into_iter().cloned().collect();
+        order.reverse();
+        let mut added = 0;
+        for module in order {
+            let name = module.get_name().clone();
+            let instances: Vec<NetRef<I>> = self
+                .modules
+                .iter()
+                .flat_map(|m| m.objects())
+                .filter(|n| self.resolve(n).is_some_and(|m| Rc::ptr_eq(m, &module)))
+                .collect();
+            let mut groups: Vec<(Vec<_>, Vec<NetRef<I>>)> = Vec::new();
+            for inst in instances {
+                let parameters: Vec<_> = inst.get_instance_type().unwrap().parameters().collect();
+                match groups.iter_mut().find(|(p, _)| *p == parameters) {
+                    Some((_, group)) => group.push(inst),
+                    None => groups.push((parameters, vec![inst])),
+                }
+            }
+
+            for (_, group) in groups.into_iter().skip(1) {
+                let copy_name = self.fresh_name(&name);
+                let copy = Netlist::new(copy_name.clone());
+                Inliner::new(self, &copy, false).inline(&module)?;
+                *copy.port_order.borrow_mut() = module.port_order.borrow().clone();
+                *copy.attributes.borrow_mut() = module.attributes.borrow().clone();
+                let id = Identifier::new(copy_name);
+                for inst in group {
+                    let cell = rename(&inst.get_instance_type().unwrap(), &id);
+                    *inst.get_instance_type_mut().unwrap() = cell;
+                }
+                self.modules.push(copy);
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    /// Returns the top module with its submodules inlined, and the hierarchical paths of its instances and nets.
+    /// The instances and nets of submodules are named with the path of instances they are in, joined with `sep`, as in `u_core/g3`.
+    /// Black boxes that are not modules of the design are kept as instances.
+    /// Returns [Error::NoTopModule] if the design has no top module.
+    pub fn flatten(&self, sep: char) -> Result<(Rc<Netlist<I>>, HierMap), Error> {
+        self.order()?;
+        let top = self.get_top().ok_or(Error::NoTopModule)?;
+        let flat = Netlist::new(top.get_name().clone());
+        let mut inliner = Inliner::new(self, &flat, true);
+        inliner.sep = sep;
+        inliner.inline(top)?;
+        *flat.port_order.borrow_mut() = top.port_order.borrow().clone();
+        *flat.attributes.borrow_mut() = top.attributes.borrow().clone();
+        let paths = inliner.paths;
+        Ok((flat, paths))
+    }
+
+    /// Returns all the modules of the design as Verilog, emitted with `options`.
+    /// Submodules come before the modules that instantiate them, or the modules are in order of insertion if the hierarchy is recursive.
+    /// With [VerilogOptions::sorted], modules that do not depend on each other are emitted by name.
+    /// Black-box stubs are only emitted for the black boxes that are not modules of the design.
+    pub fn to_verilog(&self, options: &VerilogOptions) -> String {
+        DesignWriter(self, options).to_string()
+    }
+}
+
+/// Returns [Error::PortNotFound] for a port of the instance `inst_type` that `module` does not have, or the other way around
+fn check_ports<I: Instantiable>(inst_type: &I, module: &Netlist<I>) -> Result<(), Error> {
+    let pairs = [
+        (
+            inst_type
+                .get_input_ports()
+                .into_iter()
+                .map(|n| n.get_identifier().clone())
+                .collect::<Vec<Identifier>>(),
+            module
+                .get_input_ports()
+                .map(|n| n.take_identifier())
+                .collect::<Vec<Identifier>>(),
+        ),
+        (
+            inst_type
+                .get_output_ports()
+                .into_iter()
+                .map(|n| n.get_identifier().clone())
+                .collect(),
+            module
+                .get_output_ports()
+                .into_iter()
+                .map(|n| n.take_identifier())
+                .collect(),
+        ),
+    ];
+    for (inst_ports, module_ports) in pairs {
+        let missing = inst_ports
+            .iter()
+            .find(|p| !module_ports.contains(p))
+            .or_else(|| module_ports.iter().find(|p| !inst_ports.contains(p)));
+        if let Some(port) = missing {
+            return Err(Error::PortNotFound(port.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Where a net of a module instance is driven from
+enum Source<I: Instantiable> {
+    /// A net of the netlist being built
+    Net(DrivenNet<I>),
+    /// A net of another module instance, connected through a port
+    Alias(usize, DrivenNet<I>),
+}
+
+/// Copies the contents of modules into a netlist, prefixed with the path of instances they are in
+struct Inliner<'a, I: Instantiable> {
+    design: &'a Design<I>,
+    target: &'a Rc<Netlist<I>>,
+    /// Inline the submodules of the design, instead of keeping their instances
+    descend: bool,
+    sep: char,
+    /// The module instances that were inlined, and their instance paths
+    contexts: Vec<(Rc<Netlist<I>>, Vec<Identifier>)>,
+    sources: HashMap<(usize, DrivenNet<I>), Source<I>>,
+    /// The cells that were copied, with the instance they were copied in
+    cells: Vec<(usize, NetRef<I>, NetRef<I>)>,
+    paths: HierMap,
+}
+
+impl<'a, I> Inliner<'a, I>
+where
+    I: Instantiable,
+{
+    fn new(design: &'a Design<I>, target: &'a Rc<Netlist<I>>, descend: bool) -> Self {
+        Self {
+            design,
+            target,
+            descend,
+            sep: '/',
+            contexts: Vec::new(),
+            sources: HashMap::new(),
+            cells: Vec::new(),
+            paths: HashMap::new(),
+        }
+    }
+
+    /// Returns the name of `id` in the module instance `ctx`, and records its path
+    fn name(&mut self, ctx: usize, id: &Identifier) -> Identifier {
+        let (_, prefix) = &self.contexts[ctx];
+        let path = HierPath::new(prefix.iter().chain([id]).cloned());
+        let name = if prefix.is_empty() {
+            id.clone()
+        } else {
+            path.join(self.sep).unwrap()
+        };
+        let top = Identifier::new(self.target.get_name().clone());
+        let path = HierPath::new([top].into_iter().chain(path.components().iter().cloned()));
+        self.paths.insert(name.clone(), path);
+        name
+    }
+
+    /// Copies the attributes of net `id` of `module` to the net `name` of the target netlist
+    fn copy_net_attributes(&self, module: &Netlist<I>, id: &Identifier, name: &Identifier) {
+        if let Some(attributes) = module.net_attributes.borrow().get(id) {
+            self.target
+                .net_attributes
+                .borrow_mut()
+                .insert(name.clone(), attributes.clone());
+        }
+    }
+
+    /// Copies `module` into the target netlist, with its ports as the ports of the target
+    fn inline(&mut self, module: &Rc<Netlist<I>>) -> Result<(), Error> {
+        self.contexts.push((module.clone(), Vec::new()));
+        for input in module.inputs() {
+            let name = self.name(0, &input.get_identifier());
+            self.copy_net_attributes(module, &input.get_identifier(), &name);
+            let net = self.target.insert_input(input.as_net().with_name(name));
+            self.sources.insert((0, input), Source::Net(net));
+        }
+        self.instantiate(0)?;
+
+        for (ctx, node, cell) in std::mem::take(&mut self.cells) {
+            for (i, input) in node.inputs().enumerate() {
+                if let Some(driver) = input.get_driver()
+                    && let Some(net) = self.resolve(ctx, driver)?
+                {
+                    cell.get_input(i).connect(net);
+                }
+            }
+        }
+        for port in module.get_output_ports() {
+            let id = port.get_identifier();
+            let driver = match module.get_output_driver(id) {
+                Some(driver) => self.resolve(0, driver)?,
+                None => None,
+            };
+            match driver {
+                Some(net) => {
+                    self.target.expose_net_with_name(net, id.clone());
+                }
+                None => self.target.insert_output(id.clone())?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the cells of the module instance `ctx`, inlining its submodules
+    fn instantiate(&mut self, ctx: usize) -> Result<(), Error> {
+        let (module, prefix) = self.contexts[ctx].clone();
+        for node in module.objects() {
+            if node.is_an_input() {
+                continue;
+            }
+            let inst_name = node.get_instance_name().unwrap();
+            let child = self.descend.then(|| self.design.resolve(&node)).flatten();
+            if let Some(child) = child.cloned() {
+                let inst_type = node.get_instance_type().unwrap().clone();
+                let child_ctx = self.contexts.len();
+                let path = prefix.iter().chain([&inst_name]).cloned().collect();
+                self.contexts.push((child.clone(), path));
+                for input in child.inputs() {
+                    if let Some(i) = inst_type.find_input(&input.get_identifier())
+                        && let Some(driver) = node.get_input(i).get_driver()
+                    {
+                        self.sources
+                            .insert((child_ctx, input), Source::Alias(ctx, driver));
+                    }
+                }
+                self.instantiate(child_ctx)?;
+                for (i, output) in node.outputs().enumerate() {
+                    let port = inst_type.get_output_port(i).get_identifier();
+                    if let Some(driver) = child.get_output_driver(port) {
+                        self.sources
+                            .insert((ctx, output), Source::Alias(child_ctx, driver));
+                    }
+                }
+                continue;
+            }
+
+            let inst_type = node.get_instance_type().unwrap().clone();
+            let name = self.name(ctx, &inst_name);
+            let cell = self.target.insert_gate_disconnected(inst_type, name);
+            cell.netref.borrow_mut().attributes = node.netref.borrow().attributes.clone();
+            for (output, copy) in node.outputs().zip(cell.outputs()) {
+                let name = self.name(ctx, &output.get_identifier());
+                self.copy_net_attributes(&module, &output.get_identifier(), &name);
+                copy.as_net_mut().set_identifier(name);
+                self.sources.insert((ctx, output), Source::Net(copy));
+            }
+            self.cells.push((ctx, node, cell));
+        }
+        Ok(())
+    }
+
+    /// Returns the net of the target netlist that drives `net` of the module instance `ctx`,
+    /// or [None] if it is an undriven input of a submodule
+    fn resolve(
+        &self,
+        mut ctx: usize,
+        mut net: DrivenNet<I>,
+    ) -> Result<Option<DrivenNet<I>>, Error> {
+        let mut visited = Vec::new();
+        loop {
+            match self.sources.get(&(ctx, net.clone())) {
+                None => return Ok(None),
+                Some(Source::Net(n)) => return Ok(Some(n.clone())),
+                Some(Source::Alias(c, n)) => {
+                    // Ports that are only wired to each other
+                    if visited.contains(&(*c, n.clone())) {
+                        let nets = visited.into_iter().map(|(_, n)| n.as_net().clone());
+                        return Err(Error::CycleDetected(nets.collect()));
+                    }
+                    visited.push((*c, n.clone()));
+                    (ctx, net) = (*c, n.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Displays a design as Verilog with non-default options
+struct DesignWriter<'a, I: Instantiable>(&'a Design<I>, &'a VerilogOptions);
+
+impl<I> std::fmt::Display for DesignWriter<'_, I>
+where
+    I: Instantiable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let DesignWriter(design, options) = self;
+        let order = design
+            .order_with(options.sorted)
+            .unwrap_or_else(|_| design.modules.iter().collect());
+        // The header is emitted once for the whole design
+        write_header(f, options)?;
+        let module_options = VerilogOptions {
+            blackbox_stubs: false,
+            header: None,
+            timestamp: false,
+            ..(*options).clone()
+        };
+        for (i, module) in order.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            module.fmt_verilog(f, &module_options)?;
+        }
+
+        if options.blackbox_stubs {
+            let mut stubbed = HashSet::new();
+            for module in &order {
+                for node in module.objects() {
+                    if let Some(inst_type) = node.get_instance_type()
+                        && inst_type.is_blackbox()
+                        && design.resolve(&node).is_none()
+                        && stubbed.insert(inst_type.get_name().clone())
+                    {
+                        writeln!(f)?;
+                        write_stub(f, &*inst_type, options)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Displaying a design is the same as emitting it with the default options
+impl<I> std::fmt::Display for Design<I>
+where
+    I: Instantiable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        DesignWriter(self, &VerilogOptions::default()).fmt(f)
+    }
+}
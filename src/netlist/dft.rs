@@ -0,0 +1,691 @@
+/*!
+
+  Design-for-test transformations, like scan-chain insertion, and testability analyses.
+
+*/
+
+use super::{
+    DrivenNet, InputPort, NetRef, Netlist, Operands,
+    annotation::{AnnotationMap, NetId, ObjectId},
+    sim::{Force, Simulator},
+};
+use crate::{
+    circuit::{Evaluate, Identifier, Instantiable, Net, Object},
+    error::Error,
+    format_id,
+    graph::TopoOrder,
+    logic::Logic,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+/// The scan equivalent of a flip-flop
+#[derive(Debug, Clone)]
+pub struct ScanCell<I: Instantiable> {
+    /// The scan flip-flop. Its inputs are connected like the inputs of the flip-flop with the same name,
+    /// and it must have the same outputs as the flip-flop.
+    pub cell: I,
+    /// The input that is captured in scan mode
+    pub scan_in: Identifier,
+    /// The input that selects scan mode
+    pub scan_enable: Identifier,
+    /// The output that drives the next scan input of the chain
+    pub scan_out: Identifier,
+}
+
+/// How the flip-flops are ordered along the scan chains
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScanOrder {
+    /// In the order the flip-flops were inserted
+    Insertion,
+    /// By instance name, which keeps the flip-flops of a hierarchical block together
+    #[default]
+    Name,
+    /// In the order the flip-flops are reached by a depth-first search from the top-level outputs,
+    /// which keeps the flip-flops that feed each other together
+    Connectivity,
+}
+
+/// Options for [insert_scan_chains]
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// The number of scan chains, which are balanced in length
+    pub chains: usize,
+    /// How the flip-flops are ordered along the chains
+    pub order: ScanOrder,
+    /// The name of the scan input port, which is suffixed by the chain number when there are several chains
+    pub scan_in: String,
+    /// The name of the scan output port, which is suffixed by the chain number when there are several chains
+    pub scan_out: String,
+    /// The name of the scan enable port, which is shared by all chains
+    pub scan_enable: String,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            chains: 1,
+            order: ScanOrder::default(),
+            scan_in: "scan_in".to_string(),
+            scan_out: "scan_out".to_string(),
+            scan_enable: "scan_en".to_string(),
+        }
+    }
+}
+
+/// A flip-flop and how it is converted to its scan equivalent
+struct Conversion<I: Instantiable> {
+    /// The flip-flop
+    node: NetRef<I>,
+    /// The scan cell that replaces it
+    scan: ScanCell<I>,
+    /// For each input of the scan cell, the input of the flip-flop it takes the connection of
+    inputs: Vec<Option<usize>>,
+    /// The positions of the scan input and scan enable on the scan cell
+    scan_ports: (usize, usize),
+    /// The position of the scan output on the scan cell
+    scan_out: usize,
+}
+
+/// Plans the conversion of `node` to `scan`, or returns an error if the scan cell does not fit the flip-flop
+fn plan<I>(node: NetRef<I>, scan: ScanCell<I>) -> Result<Conversion<I>, Error>
+where
+    I: Instantiable,
+{
+    let ff = node.get_instance_type().unwrap().clone();
+    let ports = |nets: Vec<&Net>| -> Vec<Identifier> {
+        nets.into_iter()
+            .map(|n| n.get_identifier().clone())
+            .collect()
+    };
+    let ff_outputs = ports(ff.get_output_ports().into_iter().collect());
+    if ports(scan.cell.get_output_ports().into_iter().collect()) != ff_outputs {
+        return Err(Error::InstantiableError(format!(
+            "Scan cell {} does not have the outputs of {}",
+            scan.cell.get_name(),
+            ff.get_name()
+        )));
+    }
+
+    let find = |id: &Identifier| {
+        scan.cell
+            .find_input(id)
+            .ok_or(Error::PortNotFound(id.clone()))
+    };
+    let scan_ports = (find(&scan.scan_in)?, find(&scan.scan_enable)?);
+    let scan_out = scan
+        .cell
+        .find_output(&scan.scan_out)
+        .ok_or(Error::PortNotFound(scan.scan_out.clone()))?;
+    let inputs = scan
+        .cell
+        .get_input_ports()
+        .into_iter()
+        .enumerate()
+        .map(|(pos, port)| {
+            if pos == scan_ports.0 || pos == scan_ports.1 {
+                return Ok(None);
+            }
+            ff.find_input(port.get_identifier())
+                .map(Some)
+                .ok_or(Error::PortNotFound(port.get_identifier().clone()))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(Conversion {
+        node,
+        scan,
+        inputs,
+        scan_ports,
+        scan_out,
+    })
+}
+
+/// Orders the flip-flops along the scan chains
+fn order<I>(
+    netlist: &Netlist<I>,
+    mut ffs: Vec<Conversion<I>>,
+    order: ScanOrder,
+) -> Vec<Conversion<I>>
+where
+    I: Instantiable,
+{
+    match order {
+        ScanOrder::Insertion => ffs,
+        ScanOrder::Name => {
+            ffs.sort_by_cached_key(|c| c.node.get_instance_name().unwrap().to_string());
+            ffs
+        }
+        ScanOrder::Connectivity => {
+            let mut rank = HashMap::new();
+            let mut visited = HashSet::new();
+            let mut stack: Vec<NetRef<I>> = netlist
+                .outputs()
+                .into_iter()
+                .rev()
+                .map(|(net, _)| net.unwrap())
+                .collect();
+            while let Some(node) = stack.pop() {
+                if !visited.insert(node.get_id()) || node.is_an_input() {
+                    continue;
+                }
+                let next = rank.len();
+                rank.entry(node.get_id()).or_insert(next);
+                let drivers: Vec<_> = node.inputs().filter_map(|i| i.get_driver()).collect();
+                stack.extend(drivers.into_iter().rev().map(|d| d.unwrap()));
+            }
+            // The flip-flops that do not reach an output go last, in the order they were inserted
+            ffs.sort_by_key(|c| rank.get(&c.node.get_id()).copied().unwrap_or(usize::MAX));
+            ffs
+        }
+    }
+}
+
+/// Replaces the flip-flops of the netlist by their scan equivalents and stitches them into scan chains.
+/// `scan_cell` maps each sequential cell to its scan equivalent, or returns `None` to leave it out of the chains,
+/// as are the flip-flops protected by a [crate::attribute::DONT_TOUCH] attribute.
+/// The chains are driven by new `scan_in` ports, end at new `scan_out` ports, and share a new `scan_en` port.
+/// Netlists without such flip-flops are left as they are.
+/// The netlist is unchanged if any scan cell does not fit its flip-flop, or if any port name is already taken.
+/// Returns the flip-flops of each chain, from scan input to scan output.
+pub fn insert_scan_chains<I>(
+    netlist: &Rc<Netlist<I>>,
+    options: &ScanOptions,
+    scan_cell: impl Fn(&I) -> Option<ScanCell<I>>,
+) -> Result<Vec<Vec<NetRef<I>>>, Error>
+where
+    I: Instantiable,
+{
+    if options.chains == 0 {
+        return Err(Error::InstantiableError(
+            "At least one scan chain is needed".to_string(),
+        ));
+    }
+
+    let mut ffs = Vec::new();
+    for node in netlist.objects() {
+        let scan = match node.get_instance_type() {
+            Some(inst) if inst.is_seq() && !netlist.is_protected(&node.netref.borrow()) => {
+                scan_cell(&inst)
+            }
+            _ => None,
+        };
+        if let Some(scan) = scan {
+            ffs.push(plan(node, scan)?);
+        }
+    }
+    if ffs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ffs = order(netlist, ffs, options.order);
+    let chains = options.chains.min(ffs.len());
+
+    let port = |name: &str, chain: usize| -> Identifier {
+        if chains == 1 {
+            name.into()
+        } else {
+            format_id!("{name}{chain}")
+        }
+    };
+    let mut names: Vec<Identifier> = vec![options.scan_enable.as_str().into()];
+    for chain in 0..chains {
+        names.push(port(&options.scan_in, chain));
+        names.push(port(&options.scan_out, chain));
+    }
+    let taken: Vec<Net> = netlist
+        .get_input_ports()
+        .chain(netlist.get_output_ports())
+        .filter(|n| names.contains(n.get_identifier()))
+        .collect();
+    if !taken.is_empty() {
+        return Err(Error::NonuniqueNets(taken));
+    }
+
+    let scan_enable = netlist.insert_input(Net::new_logic(names[0].clone()));
+    let mut result = Vec::with_capacity(chains);
+    for (chain, group) in super::opt::balanced_groups(ffs, chains)
+        .into_iter()
+        .enumerate()
+    {
+        let mut scan_in = netlist.insert_input(Net::new_logic(port(&options.scan_in, chain)));
+        let mut nodes = Vec::with_capacity(group.len());
+        for ff in group {
+            let old = ff.node.netref.borrow().operands.clone();
+            let operands: Operands = ff
+                .inputs
+                .iter()
+                .enumerate()
+                .map(|(pos, input)| match input {
+                    Some(i) => old[*i].clone(),
+                    None if pos == ff.scan_ports.0 => Some(scan_in.get_operand()),
+                    None => Some(scan_enable.get_operand()),
+                })
+                .collect();
+            let index = ff.node.netref.borrow().get_index();
+            for (pos, operand) in old.iter().enumerate() {
+                if let Some(operand) = operand {
+                    netlist.remove_use(operand, index, pos);
+                }
+            }
+            for (pos, operand) in operands.iter().enumerate() {
+                if let Some(operand) = operand {
+                    netlist.add_use(operand, index, pos);
+                }
+            }
+            {
+                let mut obj = ff.node.netref.borrow_mut();
+                obj.operands = operands;
+                if let Object::Instance(_, _, inst) = obj.get_mut() {
+                    *inst = ff.scan.cell;
+                }
+            }
+            for pos in 0..ff.inputs.len() {
+                netlist.notify_reconnect(&InputPort::new(pos, ff.node.clone()));
+            }
+            scan_in = ff.node.get_output(ff.scan_out);
+            nodes.push(ff.node);
+        }
+        netlist.expose_net_with_name(scan_in, port(&options.scan_out, chain));
+        result.push(nodes);
+    }
+    Ok(result)
+}
+
+/// The widest cell whose testability is computed, as every input pattern is evaluated
+const MAX_SCOAP_INPUTS: usize = 10;
+
+/// The SCOAP testability measures of a net.
+/// The controllabilities are the costs of setting the net to 0 or 1 from the inputs,
+/// and the observability is the cost of propagating its value to an output.
+/// Goals that cannot be met cost [u32::MAX].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Scoap {
+    /// The combinational 0-controllability
+    pub cc0: u32,
+    /// The combinational 1-controllability
+    pub cc1: u32,
+    /// The combinational observability
+    pub co: u32,
+}
+
+impl Scoap {
+    /// Returns the controllability of `value`
+    pub fn cc(&self, value: bool) -> u32 {
+        if value { self.cc1 } else { self.cc0 }
+    }
+}
+
+impl std::fmt::Display for Scoap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cost = |c: u32| {
+            if c == u32::MAX {
+                "-".to_string()
+            } else {
+                c.to_string()
+            }
+        };
+        write!(
+            f,
+            "({}, {}) {}",
+            cost(self.cc0),
+            cost(self.cc1),
+            cost(self.co)
+        )
+    }
+}
+
+/// Returns the input patterns of a cell with `n` inputs, where each input is set to a value or left unknown
+fn patterns(n: usize) -> impl Iterator<Item = Vec<Option<bool>>> {
+    (0..3u32.pow(n as u32)).map(move |mut p| {
+        (0..n)
+            .map(|_| {
+                let digit = p % 3;
+                p /= 3;
+                (digit < 2).then_some(digit == 1)
+            })
+            .collect()
+    })
+}
+
+/// Returns the cost of setting the inputs of `pattern` to their values, with `controls` the testability of the inputs
+fn control_cost(pattern: &[Option<bool>], controls: &[Scoap], skip: Option<usize>) -> u32 {
+    pattern
+        .iter()
+        .zip(controls)
+        .enumerate()
+        .filter(|(i, _)| Some(*i) != skip)
+        .filter_map(|(_, (v, s))| v.map(|v| s.cc(v)))
+        .fold(0, u32::saturating_add)
+}
+
+/// Returns the four-state values of `pattern`
+fn pattern_values(pattern: &[Option<bool>]) -> Vec<Logic> {
+    pattern
+        .iter()
+        .map(|v| v.map_or(Logic::X, Logic::from_bool))
+        .collect()
+}
+
+/// Computes the SCOAP testability measures of every net by evaluating the input patterns of each cell,
+/// where the inputs that are left unknown cost nothing to control.
+/// Principal inputs cost 1 to control and top-level outputs cost 0 to observe.
+/// Sequential cells and black boxes are treated as scanned: their outputs are controlled like inputs
+/// and their inputs are observed like outputs.
+/// Returns an error if the netlist has a combinational loop, or a cell has more than 10 inputs.
+pub fn testability<I>(netlist: &Netlist<I>) -> Result<AnnotationMap<Scoap, NetId>, Error>
+where
+    I: Evaluate,
+{
+    let order: Vec<NetRef<I>> = netlist
+        .get_analysis::<TopoOrder<I>>()?
+        .iter()
+        .cloned()
+        .collect();
+    let comb = |node: &NetRef<I>| {
+        node.get_instance_type()
+            .is_some_and(|i| !i.is_seq() && !i.is_blackbox() && !i.is_latch())
+    };
+    let mut scoap: AnnotationMap<Scoap, NetId> = AnnotationMap::new();
+    let unknown = Scoap {
+        cc0: u32::MAX,
+        cc1: u32::MAX,
+        co: u32::MAX,
+    };
+
+    for node in order.iter() {
+        if !comb(node) {
+            for net in node.outputs() {
+                scoap.insert(
+                    net.get_id(),
+                    Scoap {
+                        cc0: 1,
+                        cc1: 1,
+                        ..unknown
+                    },
+                );
+            }
+            continue;
+        }
+        let inst = node.get_instance_type().unwrap().clone();
+        let controls: Vec<Scoap> = node
+            .inputs()
+            .map(|i| {
+                i.get_driver()
+                    .and_then(|d| scoap.get(&d.get_id()).copied())
+                    .unwrap_or(unknown)
+            })
+            .collect();
+        if controls.len() > MAX_SCOAP_INPUTS {
+            return Err(Error::InstantiableError(format!(
+                "Cell {} has too many inputs to compute its testability",
+                inst.get_name()
+            )));
+        }
+        let mut outputs = vec![Scoap { ..unknown }; node.outputs().count()];
+        for pattern in patterns(controls.len()) {
+            let cost = control_cost(&pattern, &controls, None).saturating_add(1);
+            for (out, value) in outputs.iter_mut().zip(inst.eval(&pattern_values(&pattern))) {
+                match value {
+                    Logic::True => out.cc1 = out.cc1.min(cost),
+                    Logic::False => out.cc0 = out.cc0.min(cost),
+                    _ => {}
+                }
+            }
+        }
+        for (net, out) in node.outputs().zip(outputs) {
+            scoap.insert(net.get_id(), out);
+        }
+    }
+
+    let observe = |scoap: &mut AnnotationMap<Scoap, NetId>, net: Option<NetId>, co: u32| {
+        if let Some(s) = net.and_then(|n| scoap.get_mut(&n)) {
+            s.co = s.co.min(co);
+        }
+    };
+    for (net, _) in netlist.outputs() {
+        observe(&mut scoap, Some(net.get_id()), 0);
+    }
+    for node in order.iter().filter(|n| !n.is_an_input() && !comb(n)) {
+        for input in node.inputs() {
+            observe(&mut scoap, input.get_driver().map(|d| d.get_id()), 0);
+        }
+    }
+
+    for node in order.iter().rev().filter(|n| comb(n)) {
+        let inst = node.get_instance_type().unwrap().clone();
+        let drivers: Vec<Option<NetId>> = node
+            .inputs()
+            .map(|i| i.get_driver().map(|d| d.get_id()))
+            .collect();
+        let controls: Vec<Scoap> = drivers
+            .iter()
+            .map(|d| d.and_then(|d| scoap.get(&d).copied()).unwrap_or(unknown))
+            .collect();
+        let observed: Vec<u32> = node
+            .outputs()
+            .map(|o| scoap.get(&o.get_id()).unwrap().co)
+            .collect();
+        let mut costs = vec![u32::MAX; drivers.len()];
+        for pattern in patterns(drivers.len()) {
+            let values = pattern_values(&pattern);
+            let base = inst.eval(&values);
+            for (i, cost) in costs.iter_mut().enumerate() {
+                if pattern[i].is_none() {
+                    continue;
+                }
+                let mut flipped = values.clone();
+                flipped[i] = !flipped[i];
+                let Some(co) = inst
+                    .eval(&flipped)
+                    .iter()
+                    .zip(&base)
+                    .zip(&observed)
+                    .filter(|((a, b), _)| {
+                        matches!(
+                            (a, b),
+                            (Logic::True, Logic::False) | (Logic::False, Logic::True)
+                        )
+                    })
+                    .map(|(_, co)| *co)
+                    .min()
+                else {
+                    continue;
+                };
+                // The other inputs are set to sensitize the path from input `i`
+                let sensitize = control_cost(&pattern, &controls, Some(i));
+                *cost = (*cost).min(sensitize.saturating_add(co).saturating_add(1));
+            }
+        }
+        for (driver, cost) in drivers.into_iter().zip(costs) {
+            observe(&mut scoap, driver, cost);
+        }
+    }
+    Ok(scoap)
+}
+
+/// A location of a stuck-at fault
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FaultSite {
+    /// The stem of a net, at the output of its driver
+    Net(NetId),
+    /// A branch of a net with several loads, at the input of a circuit node at the given position
+    Pin(ObjectId, usize),
+}
+
+impl std::fmt::Display for FaultSite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FaultSite::Net(net) => write!(f, "{net}"),
+            FaultSite::Pin(obj, pos) => write!(f, "{obj}/{pos}"),
+        }
+    }
+}
+
+/// A fault that holds a net or pin at a constant value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StuckAt {
+    /// Where the fault is
+    pub site: FaultSite,
+    /// The value the site is stuck at
+    pub value: bool,
+}
+
+impl std::fmt::Display for StuckAt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} SA{}", self.site, self.value as u8)
+    }
+}
+
+/// Returns the stuck-at-0 and stuck-at-1 faults of every net, followed by those of the branches of nets with several loads.
+/// The faults of the only branch of a net are equivalent to the faults of the net, so they are left out.
+/// Top-level outputs count as loads.
+pub fn fault_list<I>(netlist: &Netlist<I>) -> Vec<StuckAt>
+where
+    I: Instantiable,
+{
+    let mut loads: HashMap<NetId, usize> = HashMap::new();
+    for input in netlist
+        .objects()
+        .flat_map(|o| o.inputs().collect::<Vec<_>>())
+    {
+        if let Some(driver) = input.get_driver() {
+            *loads.entry(driver.get_id()).or_default() += 1;
+        }
+    }
+    for (net, _) in netlist.outputs() {
+        *loads.entry(net.get_id()).or_default() += 1;
+    }
+
+    let both = |site| [false, true].map(|value| StuckAt { site, value });
+    let mut faults = Vec::new();
+    for obj in netlist.objects() {
+        for net in obj.outputs() {
+            faults.extend(both(FaultSite::Net(net.get_id())));
+        }
+    }
+    for obj in netlist.objects() {
+        for (pos, input) in obj.inputs().enumerate() {
+            if input.get_driver().is_some_and(|d| loads[&d.get_id()] > 1) {
+                faults.extend(both(FaultSite::Pin(obj.get_id(), pos)));
+            }
+        }
+    }
+    faults
+}
+
+/// The result of a fault simulation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FaultCoverage {
+    /// The detected faults, in the order they were detected
+    pub detected: Vec<StuckAt>,
+    /// The faults no pattern detected
+    pub undetected: Vec<StuckAt>,
+}
+
+impl FaultCoverage {
+    /// Returns the fraction of the faults that were detected, which is one if there were none
+    pub fn coverage(&self) -> f64 {
+        let total = self.detected.len() + self.undetected.len();
+        if total == 0 {
+            return 1.0;
+        }
+        self.detected.len() as f64 / total as f64
+    }
+}
+
+impl std::fmt::Display for FaultCoverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "faults: {}", self.detected.len() + self.undetected.len())?;
+        writeln!(f, "detected: {}", self.detected.len())?;
+        writeln!(f, "coverage: {:.2}%", 100.0 * self.coverage())
+    }
+}
+
+/// Simulates the `faults` one at a time under `n_patterns` patterns, 64 at a time, and reports the ones that are detected.
+/// `source` supplies the `word`-th word of patterns of each free variable of the [Simulator], like [super::sim::random_word].
+/// A fault is detected when it changes a top-level output or an input of a sequential cell or black box, which are assumed to be scanned.
+/// Detected faults are not simulated again, and faults of nodes that are no longer in the netlist are undetected.
+/// Returns an error if the netlist has combinational cycles.
+pub fn fault_simulate<I: Evaluate>(
+    netlist: &Netlist<I>,
+    faults: &[StuckAt],
+    n_patterns: usize,
+    mut source: impl FnMut(&DrivenNet<I>, usize) -> u64,
+) -> Result<FaultCoverage, Error> {
+    let mut sim = Simulator::new(netlist)?;
+    let sources: Vec<DrivenNet<I>> = sim.sources().collect();
+    let mut observed: Vec<DrivenNet<I>> = netlist.outputs().into_iter().map(|(o, _)| o).collect();
+    for obj in netlist.objects() {
+        if obj
+            .get_instance_type()
+            .is_some_and(|inst| inst.is_seq() || inst.is_blackbox() || inst.is_latch())
+        {
+            observed.extend(obj.inputs().filter_map(|i| i.get_driver()));
+        }
+    }
+
+    // Each fault is forced onto the simulation, and faults on the inputs of scanned cells are observed at the pin
+    let mut pending: Vec<(StuckAt, Force, Option<DrivenNet<I>>)> = Vec::new();
+    let mut coverage = FaultCoverage::default();
+    for fault in faults {
+        let word = if fault.value { u64::MAX } else { 0 };
+        let resolved = match fault.site {
+            FaultSite::Net(id) => netlist.find_net_by_id(id).map(|net| {
+                let index = net.netref.netref.borrow().get_index();
+                (Force::Net(index, net.pos, word), None)
+            }),
+            FaultSite::Pin(id, pos) => netlist.find_object(id).and_then(|node| {
+                let index = node.netref.borrow().get_index();
+                let scanned = node
+                    .get_instance_type()
+                    .is_some_and(|inst| inst.is_seq() || inst.is_blackbox() || inst.is_latch());
+                let driver = node.inputs().nth(pos)?.get_driver();
+                Some((Force::Pin(index, pos, word), driver.filter(|_| scanned)))
+            }),
+        };
+        match resolved {
+            Some((force, pin)) => pending.push((*fault, force, pin)),
+            None => coverage.undetected.push(*fault),
+        }
+    }
+
+    for word in 0..n_patterns.div_ceil(64) {
+        let words: HashMap<DrivenNet<I>, u64> = sources
+            .iter()
+            .map(|s| (s.clone(), source(s, word)))
+            .collect();
+        let lanes = (n_patterns - word * 64).min(64);
+        let mask = u64::MAX >> (64 - lanes);
+        sim.run(|n| words[n]);
+        let good: Vec<u64> = observed.iter().map(|o| sim.get_word(o)).collect();
+        let pins: Vec<Option<u64>> = pending
+            .iter()
+            .map(|(_, _, pin)| pin.as_ref().map(|p| sim.get_word(p)))
+            .collect();
+
+        let mut remaining = Vec::new();
+        for ((fault, force, pin), good_pin) in pending.into_iter().zip(pins) {
+            let detected = match (force, good_pin) {
+                (Force::Pin(_, _, forced), Some(value)) => (value ^ forced) & mask != 0,
+                _ => {
+                    sim.run_forced(|n| words[n], Some(force));
+                    observed
+                        .iter()
+                        .zip(good.iter())
+                        .any(|(o, g)| (sim.get_word(o) ^ g) & mask != 0)
+                }
+            };
+            if detected {
+                coverage.detected.push(fault);
+            } else {
+                remaining.push((fault, force, pin));
+            }
+        }
+        pending = remaining;
+    }
+    coverage
+        .undetected
+        .extend(pending.into_iter().map(|(fault, _, _)| fault));
+    Ok(coverage)
+}
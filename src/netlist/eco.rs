@@ -0,0 +1,111 @@
+/*!
+
+  Spare cells for engineering changes.
+
+  Spare cells are instances left in a design without loads, usually with their inputs tied to constants, so that a
+  late change can be made by rewiring them instead of inserting new cells. They are marked with the [SPARE] attribute,
+  which protects them from passes and [Netlist::clean] like [crate::attribute::DONT_TOUCH], until [Netlist::use_spare]
+  puts one of them to use.
+
+*/
+
+use super::{DrivenNet, NetRef, Netlist};
+use crate::{
+    attribute::SPARE,
+    circuit::{Identifier, Instantiable},
+    error::Error,
+};
+use std::collections::{HashSet, VecDeque};
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Marks `node` as a spare cell
+    pub fn mark_spare(&self, node: &NetRef<I>) {
+        node.set_attribute(SPARE.to_string());
+    }
+
+    /// Returns `true` if `node` is a spare cell
+    pub fn is_spare(&self, node: &NetRef<I>) -> bool {
+        node.has_attribute(&SPARE.to_string())
+    }
+
+    /// Returns an iterator over the spare cells, in netlist order
+    pub fn spares(&self) -> impl Iterator<Item = NetRef<I>> {
+        self.objects().filter(|node| self.is_spare(node))
+    }
+
+    /// Returns the spare cells for which `filter` holds within `max_distance` connections of `location`, with their distance,
+    /// from the nearest. Distances count the connections between a driver and its loads in either direction, so spare
+    /// cells are reached through the constants that tie off their inputs. Local tie-off cells keep the distances meaningful.
+    pub fn nearest_spares(
+        &self,
+        location: &NetRef<I>,
+        max_distance: usize,
+        filter: impl Fn(&I) -> bool,
+    ) -> Vec<(NetRef<I>, usize)> {
+        let mut found = Vec::new();
+        let mut visited = HashSet::from([location.clone()]);
+        let mut queue = VecDeque::from([(location.clone(), 0)]);
+        while let Some((node, distance)) = queue.pop_front() {
+            if node != *location
+                && self.is_spare(&node)
+                && node.get_instance_type().is_some_and(|inst| filter(&inst))
+            {
+                found.push((node.clone(), distance));
+            }
+            if distance == max_distance {
+                continue;
+            }
+            let drivers = node
+                .inputs()
+                .filter_map(|i| i.get_driver())
+                .map(|d| d.unwrap());
+            let loads: Vec<_> = node
+                .outputs()
+                .flat_map(|o| self.get_uses(&o))
+                .map(|i| i.unwrap())
+                .collect();
+            for next in drivers.chain(loads) {
+                if visited.insert(next.clone()) {
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+        found
+    }
+
+    /// Puts the spare cell `spare` to use, connecting each listed input port to its driver. The other inputs keep their
+    /// drivers, like the constants that tie them off, and the loads of `spare` are left to the caller.
+    /// The spare cell is no longer marked, so it may be edited like the rest of the netlist.
+    /// Returns [Error::InstantiableError] if `spare` is not a spare cell, or [Error::PortNotFound] for a name that is not one
+    /// of its input ports, before any edit.
+    pub fn use_spare(
+        &self,
+        spare: &NetRef<I>,
+        connections: &[(&Identifier, DrivenNet<I>)],
+    ) -> Result<(), Error> {
+        if !self.is_spare(spare) {
+            return Err(Error::InstantiableError(format!(
+                "{} is not a spare cell",
+                spare
+                    .get_instance_name()
+                    .unwrap_or_else(|| spare.get_identifier())
+            )));
+        }
+        let inputs = connections
+            .iter()
+            .map(|(id, _)| {
+                spare
+                    .find_input(id)
+                    .ok_or_else(|| Error::PortNotFound((*id).clone()))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        spare.clear_attribute(&SPARE.to_string());
+        for (input, (_, driver)) in inputs.into_iter().zip(connections) {
+            input.reconnect(driver.clone());
+        }
+        Ok(())
+    }
+}
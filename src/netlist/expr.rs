@@ -0,0 +1,256 @@
+/*!
+
+  Boolean expressions, and their synthesis into gates.
+
+*/
+
+use super::{DrivenNet, Gate, Netlist};
+use crate::{
+    circuit::{Identifier, Net},
+    error::Error,
+    format_id,
+    logic::Logic,
+};
+use std::{collections::HashMap, rc::Rc};
+
+/// A boolean expression over named variables, built with constructors like [Expr::and] or the `!`, `&`, `|` and `^` operators
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr {
+    /// A constant value
+    Const(bool),
+    /// The value of the net with this name
+    Var(Identifier),
+    /// The negation of an expression
+    Not(Box<Expr>),
+    /// The conjunction of two expressions
+    And(Box<Expr>, Box<Expr>),
+    /// The disjunction of two expressions
+    Or(Box<Expr>, Box<Expr>),
+    /// The exclusive or of two expressions
+    Xor(Box<Expr>, Box<Expr>),
+    /// The second expression when the first is high, and the third otherwise
+    Mux(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Returns a constant expression
+    pub fn constant(value: bool) -> Self {
+        Expr::Const(value)
+    }
+
+    /// Returns the value of the net named `name`
+    pub fn var(name: impl Into<Identifier>) -> Self {
+        Expr::Var(name.into())
+    }
+
+    /// Returns the conjunction of `a` and `b`
+    pub fn and(a: Expr, b: Expr) -> Self {
+        Expr::And(Box::new(a), Box::new(b))
+    }
+
+    /// Returns the disjunction of `a` and `b`
+    pub fn or(a: Expr, b: Expr) -> Self {
+        Expr::Or(Box::new(a), Box::new(b))
+    }
+
+    /// Returns the exclusive or of `a` and `b`
+    pub fn xor(a: Expr, b: Expr) -> Self {
+        Expr::Xor(Box::new(a), Box::new(b))
+    }
+
+    /// Returns `then` when `sel` is high, and `other` otherwise
+    pub fn mux(sel: Expr, then: Expr, other: Expr) -> Self {
+        Expr::Mux(Box::new(sel), Box::new(then), Box::new(other))
+    }
+
+    /// Returns the variables of the expression, in order of first appearance
+    pub fn vars(&self) -> Vec<&Identifier> {
+        let mut vars = Vec::new();
+        self.collect_vars(&mut vars);
+        vars
+    }
+
+    fn collect_vars<'a>(&'a self, vars: &mut Vec<&'a Identifier>) {
+        match self {
+            Expr::Const(_) => (),
+            Expr::Var(v) => {
+                if !vars.contains(&v) {
+                    vars.push(v);
+                }
+            }
+            Expr::Not(a) => a.collect_vars(vars),
+            Expr::And(a, b) | Expr::Or(a, b) | Expr::Xor(a, b) => {
+                a.collect_vars(vars);
+                b.collect_vars(vars);
+            }
+            Expr::Mux(s, a, b) => {
+                s.collect_vars(vars);
+                a.collect_vars(vars);
+                b.collect_vars(vars);
+            }
+        }
+    }
+
+    /// Evaluates the expression, where `value` gives the value of each variable
+    pub fn eval(&self, value: &impl Fn(&Identifier) -> Logic) -> Logic {
+        match self {
+            Expr::Const(c) => Logic::from_bool(*c),
+            Expr::Var(v) => value(v),
+            Expr::Not(a) => !a.eval(value),
+            Expr::And(a, b) => a.eval(value) & b.eval(value),
+            Expr::Or(a, b) => a.eval(value) | b.eval(value),
+            Expr::Xor(a, b) => a.eval(value) ^ b.eval(value),
+            Expr::Mux(s, a, b) => match s.eval(value) {
+                Logic::True => a.eval(value),
+                Logic::False => b.eval(value),
+                _ => match (a.eval(value), b.eval(value)) {
+                    (a, b) if a == b => a,
+                    _ => Logic::X,
+                },
+            },
+        }
+    }
+}
+
+impl std::ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        Expr::Not(Box::new(self))
+    }
+}
+
+impl std::ops::BitAnd for Expr {
+    type Output = Expr;
+
+    fn bitand(self, rhs: Expr) -> Expr {
+        Expr::and(self, rhs)
+    }
+}
+
+impl std::ops::BitOr for Expr {
+    type Output = Expr;
+
+    fn bitor(self, rhs: Expr) -> Expr {
+        Expr::or(self, rhs)
+    }
+}
+
+impl std::ops::BitXor for Expr {
+    type Output = Expr;
+
+    fn bitxor(self, rhs: Expr) -> Expr {
+        Expr::xor(self, rhs)
+    }
+}
+
+/// Expressions print with Verilog operators, like `(a & ~b)`
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Const(c) => write!(f, "1'b{}", u8::from(*c)),
+            Expr::Var(v) => write!(f, "{v}"),
+            Expr::Not(a) => write!(f, "~{a}"),
+            Expr::And(a, b) => write!(f, "({a} & {b})"),
+            Expr::Or(a, b) => write!(f, "({a} | {b})"),
+            Expr::Xor(a, b) => write!(f, "({a} ^ {b})"),
+            Expr::Mux(s, a, b) => write!(f, "({s} ? {a} : {b})"),
+        }
+    }
+}
+
+/// Inserts the gates of an expression, named with a prefix and a count
+struct Synthesis<'a> {
+    netlist: &'a Rc<Netlist<Gate>>,
+    prefix: Identifier,
+    count: usize,
+    vars: HashMap<Identifier, DrivenNet<Gate>>,
+    /// The nets of the subexpressions that were already built
+    built: HashMap<&'a Expr, DrivenNet<Gate>>,
+}
+
+impl<'a> Synthesis<'a> {
+    fn next_name(&mut self) -> Identifier {
+        let name = format_id!("{}_{}", self.prefix, self.count);
+        self.count += 1;
+        name
+    }
+
+    fn gate(
+        &mut self,
+        name: &str,
+        ports: &[&str],
+        inputs: &[DrivenNet<Gate>],
+    ) -> Result<DrivenNet<Gate>, Error> {
+        let inst_name = self.next_name();
+        self.netlist.insert_named_op(name, ports, inputs, inst_name)
+    }
+
+    fn build(&mut self, expr: &'a Expr) -> Result<DrivenNet<Gate>, Error> {
+        if let Some(net) = self.built.get(expr) {
+            return Ok(net.clone());
+        }
+        let net = match expr {
+            Expr::Const(c) => {
+                let inst_name = self.next_name();
+                self.netlist
+                    .insert_constant(Logic::from_bool(*c), inst_name)?
+            }
+            Expr::Var(v) => self.vars[v].clone(),
+            Expr::Not(a) => {
+                let a = self.build(a)?;
+                self.gate("INV", &["A"], &[a])?
+            }
+            Expr::And(a, b) | Expr::Or(a, b) | Expr::Xor(a, b) => {
+                let name = match expr {
+                    Expr::And(..) => "AND",
+                    Expr::Or(..) => "OR",
+                    _ => "XOR",
+                };
+                let a = self.build(a)?;
+                let b = self.build(b)?;
+                self.gate(name, &["A", "B"], &[a, b])?
+            }
+            Expr::Mux(s, a, b) => {
+                let s = self.build(s)?;
+                let a = self.build(a)?;
+                let b = self.build(b)?;
+                // The MUX gate selects its last input when the select is high
+                self.gate("MUX", &["S", "A", "B"], &[s, b, a])?
+            }
+        };
+        self.built.insert(expr, net.clone());
+        Ok(net)
+    }
+}
+
+impl Netlist<Gate> {
+    /// Synthesizes `expr` into gates, returning the net of its value. Variables are the nets of the netlist with the same name,
+    /// and the new instances are named `prefix` suffixed with a count, like `prefix_0`. Identical subexpressions share their gates.
+    /// Returns [Error::NetNotFound] if a variable does not name a net of the netlist.
+    pub fn insert_expr(
+        self: &Rc<Self>,
+        expr: &Expr,
+        prefix: Identifier,
+    ) -> Result<DrivenNet<Gate>, Error> {
+        let names = expr.vars();
+        let mut vars = HashMap::new();
+        for net in self.objects().flat_map(|o| o.outputs().collect::<Vec<_>>()) {
+            let id = net.get_identifier();
+            if names.contains(&&id) {
+                vars.entry(id).or_insert(net);
+            }
+        }
+        if let Some(missing) = names.into_iter().find(|v| !vars.contains_key(*v)) {
+            return Err(Error::NetNotFound(Net::new_logic(missing.clone())));
+        }
+        let mut synthesis = Synthesis {
+            netlist: self,
+            prefix,
+            count: 0,
+            vars,
+            built: HashMap::new(),
+        };
+        synthesis.build(expr)
+    }
+}
@@ -0,0 +1,194 @@
+/*!
+
+  Structural fingerprints of netlists, for caching the results of passes and telling whether a design really changed.
+
+  [Netlist::fingerprint] hashes the structure of a netlist with color refinement, also known as the Weisfeiler-Leman
+  test: every circuit node starts with a hash of its cell and parameters, and then repeatedly hashes its own hash with
+  those of its drivers, port by port, and the multiset of its loads, until the partition of the nodes stops getting
+  finer. The fingerprint hashes the multiset of the final node hashes and of the drivers of the top-level outputs.
+
+  Names of instances, nets and ports, attributes, and the order in which the netlist was built do not change the
+  fingerprint, while the cell types, their parameters and which port of a cell a net connects to do.
+
+  ## Collisions
+
+  Netlists that are the same up to names and order always have the same fingerprint. Netlists with different
+  structures have different fingerprints except for two kinds of collisions:
+  - hash collisions of the 64-bit hashes, which happen with a probability of about `n² / 2^64` among `n` designs;
+  - structures that color refinement cannot tell apart, where every node sees the same neighborhood at any depth,
+    like a ring of eight inverters and two rings of four. Real designs rarely have such symmetries, but a matching
+    fingerprint is evidence, not a proof, that two designs are the same.
+
+  Two designs with the same function and a different structure have different fingerprints, see [super::verify]
+  for comparing functions.
+
+*/
+
+use super::{NetRef, Netlist};
+use crate::circuit::Instantiable;
+use std::collections::{HashMap, HashSet};
+
+/// The structural fingerprint of a netlist, see [Netlist::fingerprint]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint(pub u64);
+
+/// Fingerprints print as 16 hexadecimal digits, for file names and cache keys
+impl std::fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// A hasher that is stable across platforms and runs, unlike the std hasher
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(mut self, bytes: &[u8]) -> Self {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+        self
+    }
+
+    fn word(self, word: u64) -> Self {
+        self.bytes(&word.to_le_bytes())
+    }
+
+    fn words(self, words: impl IntoIterator<Item = u64>) -> Self {
+        words.into_iter().fold(self, Self::word)
+    }
+}
+
+/// The hash of a port without a driver
+const UNDRIVEN: u64 = u64::MAX;
+
+/// The port of the loads that are top-level outputs
+const OUTPUT: u64 = u64::MAX;
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Returns the initial hash of a circuit node: its cell type, parameters and number of ports,
+    /// or the same hash for every principal input
+    fn node_label(node: &NetRef<I>) -> u64 {
+        let Some(inst) = node.get_instance_type() else {
+            return Fnv::new().bytes(b"input").0;
+        };
+        let mut parameters: Vec<(String, String)> = inst
+            .parameters()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        parameters.sort();
+        let mut hash = Fnv::new()
+            .bytes(inst.get_name().to_string().as_bytes())
+            .word(inst.get_input_ports().into_iter().count() as u64)
+            .word(inst.get_output_ports().into_iter().count() as u64);
+        for (k, v) in parameters {
+            hash = hash.bytes(k.as_bytes()).word(0).bytes(v.as_bytes()).word(0);
+        }
+        hash.0
+    }
+
+    /// Returns a structural hash of the netlist that does not depend on names, attributes or the order of the
+    /// circuit nodes. See the [module documentation](self) for how it is computed and when different netlists collide.
+    pub fn fingerprint(&self) -> Fingerprint {
+        let nodes: Vec<NetRef<I>> = self.objects().collect();
+        let index: HashMap<_, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.get_id(), i))
+            .collect();
+        // The driver of each input port, as the node and its output position
+        let fanins: Vec<Vec<Option<(usize, u64)>>> = nodes
+            .iter()
+            .map(|n| {
+                n.inputs()
+                    .map(|port| {
+                        port.get_driver().map(|d| {
+                            let id = d.get_id();
+                            (index[&id.object], id.output as u64)
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        // The loads of each node, as the load, its input port and the output position of the node
+        let mut fanouts: Vec<Vec<(usize, u64, u64)>> = vec![Vec::new(); nodes.len()];
+        for (load, drivers) in fanins.iter().enumerate() {
+            for (port, driver) in drivers.iter().enumerate() {
+                if let Some((driver, output)) = driver {
+                    fanouts[*driver].push((load, port as u64, *output));
+                }
+            }
+        }
+        let outputs: Vec<(usize, u64)> = self
+            .outputs()
+            .into_iter()
+            .map(|(d, _)| {
+                let id = d.get_id();
+                (index[&id.object], id.output as u64)
+            })
+            .collect();
+        let mut output_counts: Vec<Vec<u64>> = vec![Vec::new(); nodes.len()];
+        for (driver, output) in &outputs {
+            output_counts[*driver].push(*output);
+        }
+
+        let mut colors: Vec<u64> = nodes.iter().map(Self::node_label).collect();
+        let mut classes = colors.iter().collect::<HashSet<_>>().len();
+        loop {
+            let refined: Vec<u64> = (0..nodes.len())
+                .map(|n| {
+                    let drivers = fanins[n].iter().map(|d| match d {
+                        Some((d, output)) => Fnv::new().word(colors[*d]).word(*output).0,
+                        None => UNDRIVEN,
+                    });
+                    let mut loads: Vec<u64> = fanouts[n]
+                        .iter()
+                        .map(|(l, port, output)| Fnv::new().words([colors[*l], *port, *output]).0)
+                        .chain(
+                            output_counts[n]
+                                .iter()
+                                .map(|output| Fnv::new().words([OUTPUT, *output]).0),
+                        )
+                        .collect();
+                    loads.sort_unstable();
+                    Fnv::new()
+                        .word(colors[n])
+                        .words(drivers)
+                        .word(loads.len() as u64)
+                        .words(loads)
+                        .0
+                })
+                .collect();
+            let refined_classes = refined.iter().collect::<HashSet<_>>().len();
+            colors = refined;
+            // Refinement only splits classes, so the partition is stable once their number stops growing
+            if refined_classes == classes {
+                break;
+            }
+            classes = refined_classes;
+        }
+
+        let mut outputs: Vec<u64> = outputs
+            .iter()
+            .map(|(d, output)| Fnv::new().word(colors[*d]).word(*output).0)
+            .collect();
+        outputs.sort_unstable();
+        colors.sort_unstable();
+        Fingerprint(
+            Fnv::new()
+                .word(colors.len() as u64)
+                .words(colors)
+                .word(outputs.len() as u64)
+                .words(outputs)
+                .0,
+        )
+    }
+}
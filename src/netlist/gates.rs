@@ -0,0 +1,154 @@
+/*!
+
+  Expression-style construction of gate netlists.
+
+*/
+
+use super::{DrivenNet, Gate, Netlist};
+use crate::{circuit::Identifier, error::Error, format_id};
+use std::rc::Rc;
+
+/// A method inserting a two-input gate, like [Netlist::and]
+type BinaryOp =
+    fn(&Rc<Netlist<Gate>>, &DrivenNet<Gate>, &DrivenNet<Gate>) -> Result<DrivenNet<Gate>, Error>;
+
+impl Netlist<Gate> {
+    /// Inserts a gate named `name` with input ports `ports` and output `Y`, driven by `inputs`.
+    /// The instance is named after the gate and the identifier of the new object, like `and_4`.
+    fn insert_op(
+        self: &Rc<Self>,
+        name: &str,
+        ports: &[&str],
+        inputs: &[DrivenNet<Gate>],
+    ) -> Result<DrivenNet<Gate>, Error> {
+        let inst_name = format_id!("{}_{}", name.to_lowercase(), self.next_id.get());
+        self.insert_named_op(name, ports, inputs, inst_name)
+    }
+
+    /// Inserts a gate named `name` with input ports `ports` and output `Y`, driven by `inputs`, as instance `inst_name`
+    pub(super) fn insert_named_op(
+        self: &Rc<Self>,
+        name: &str,
+        ports: &[&str],
+        inputs: &[DrivenNet<Gate>],
+        inst_name: Identifier,
+    ) -> Result<DrivenNet<Gate>, Error> {
+        let ports = ports.iter().map(|p| Identifier::from(*p)).collect();
+        let gate = Gate::new_logical(name.into(), ports, "Y".into());
+        Ok(self.insert_gate(gate, inst_name, inputs)?.get_output(0))
+    }
+
+    /// Inserts an `AND` gate of `a` and `b`, returning its output
+    pub fn and(
+        self: &Rc<Self>,
+        a: &DrivenNet<Gate>,
+        b: &DrivenNet<Gate>,
+    ) -> Result<DrivenNet<Gate>, Error> {
+        self.insert_op("AND", &["A", "B"], &[a.clone(), b.clone()])
+    }
+
+    /// Inserts an `OR` gate of `a` and `b`, returning its output
+    pub fn or(
+        self: &Rc<Self>,
+        a: &DrivenNet<Gate>,
+        b: &DrivenNet<Gate>,
+    ) -> Result<DrivenNet<Gate>, Error> {
+        self.insert_op("OR", &["A", "B"], &[a.clone(), b.clone()])
+    }
+
+    /// Inserts an `XOR` gate of `a` and `b`, returning its output
+    pub fn xor(
+        self: &Rc<Self>,
+        a: &DrivenNet<Gate>,
+        b: &DrivenNet<Gate>,
+    ) -> Result<DrivenNet<Gate>, Error> {
+        self.insert_op("XOR", &["A", "B"], &[a.clone(), b.clone()])
+    }
+
+    /// Inserts a `NAND` gate of `a` and `b`, returning its output
+    pub fn nand(
+        self: &Rc<Self>,
+        a: &DrivenNet<Gate>,
+        b: &DrivenNet<Gate>,
+    ) -> Result<DrivenNet<Gate>, Error> {
+        self.insert_op("NAND", &["A", "B"], &[a.clone(), b.clone()])
+    }
+
+    /// Inserts a `NOR` gate of `a` and `b`, returning its output
+    pub fn nor(
+        self: &Rc<Self>,
+        a: &DrivenNet<Gate>,
+        b: &DrivenNet<Gate>,
+    ) -> Result<DrivenNet<Gate>, Error> {
+        self.insert_op("NOR", &["A", "B"], &[a.clone(), b.clone()])
+    }
+
+    /// Inserts an `XNOR` gate of `a` and `b`, returning its output
+    pub fn xnor(
+        self: &Rc<Self>,
+        a: &DrivenNet<Gate>,
+        b: &DrivenNet<Gate>,
+    ) -> Result<DrivenNet<Gate>, Error> {
+        self.insert_op("XNOR", &["A", "B"], &[a.clone(), b.clone()])
+    }
+
+    /// Inserts an `INV` gate of `a`, returning its output
+    pub fn not(self: &Rc<Self>, a: &DrivenNet<Gate>) -> Result<DrivenNet<Gate>, Error> {
+        self.insert_op("INV", &["A"], std::slice::from_ref(a))
+    }
+
+    /// Inserts a `BUF` gate of `a`, returning its output
+    pub fn buf(self: &Rc<Self>, a: &DrivenNet<Gate>) -> Result<DrivenNet<Gate>, Error> {
+        self.insert_op("BUF", &["A"], std::slice::from_ref(a))
+    }
+
+    /// Inserts a `MUX` gate that selects `b` when `sel` is high and `a` otherwise, returning its output
+    pub fn mux(
+        self: &Rc<Self>,
+        sel: &DrivenNet<Gate>,
+        a: &DrivenNet<Gate>,
+        b: &DrivenNet<Gate>,
+    ) -> Result<DrivenNet<Gate>, Error> {
+        self.insert_op(
+            "MUX",
+            &["S", "A", "B"],
+            &[sel.clone(), a.clone(), b.clone()],
+        )
+    }
+
+    /// Reduces `inputs` with a balanced tree of two-input `AND` gates, returning the output.
+    /// A single input is returned as is, and there must be at least one.
+    pub fn and_all(self: &Rc<Self>, inputs: &[DrivenNet<Gate>]) -> Result<DrivenNet<Gate>, Error> {
+        self.reduce(inputs, Self::and)
+    }
+
+    /// Reduces `inputs` with a balanced tree of two-input `OR` gates, returning the output.
+    /// A single input is returned as is, and there must be at least one.
+    pub fn or_all(self: &Rc<Self>, inputs: &[DrivenNet<Gate>]) -> Result<DrivenNet<Gate>, Error> {
+        self.reduce(inputs, Self::or)
+    }
+
+    /// Reduces `inputs` with a balanced tree of two-input `XOR` gates, returning the output.
+    /// A single input is returned as is, and there must be at least one.
+    pub fn xor_all(self: &Rc<Self>, inputs: &[DrivenNet<Gate>]) -> Result<DrivenNet<Gate>, Error> {
+        self.reduce(inputs, Self::xor)
+    }
+
+    /// Reduces `inputs` with a balanced tree of `op`
+    fn reduce(
+        self: &Rc<Self>,
+        inputs: &[DrivenNet<Gate>],
+        op: BinaryOp,
+    ) -> Result<DrivenNet<Gate>, Error> {
+        match inputs {
+            [] => Err(Error::ArgumentMismatch(1, 0)),
+            [a] => Ok(a.clone()),
+            _ => {
+                let (l, r) = inputs.split_at(inputs.len() / 2);
+                let l = self.reduce(l, op)?;
+                let r = self.reduce(r, op)?;
+                op(self, &l, &r)
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+/*!
+
+  Levelization of netlists, and level-by-level traversals which run on rayon with the `parallel` feature.
+
+*/
+
+use super::{NetRef, Netlist, sim::is_source};
+use crate::{circuit::Instantiable, error::Error, graph::Analysis, graph::TopoOrder};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// The levels of a netlist as plain indices, which can be shared across threads
+#[derive(Debug, Clone)]
+pub(super) struct Schedule {
+    /// The indices of the objects at each level
+    pub(super) levels: Vec<Vec<usize>>,
+    /// Whether each object is a free variable, like in [is_source]
+    pub(super) sources: Vec<bool>,
+    /// The object and output indices that drive each input of each object
+    pub(super) operands: Vec<Vec<Option<(usize, usize)>>>,
+}
+
+impl Schedule {
+    /// Computes a value for every object, level by level, where `f` gets the index of an object and the values of the lower levels.
+    /// The objects of a level are mapped in parallel with the `parallel` feature.
+    pub(super) fn map_levels<T, F>(&self, init: T, f: F) -> Vec<T>
+    where
+        T: Clone + Send + Sync,
+        F: Fn(usize, &[T]) -> T + Sync,
+    {
+        let mut values = vec![init; self.sources.len()];
+        for level in self.levels.iter() {
+            #[cfg(feature = "parallel")]
+            let computed: Vec<T> = level.par_iter().map(|i| f(*i, &values)).collect();
+            #[cfg(not(feature = "parallel"))]
+            let computed: Vec<T> = level.iter().map(|i| f(*i, &values)).collect();
+            for (i, value) in level.iter().zip(computed) {
+                values[*i] = value;
+            }
+        }
+        values
+    }
+}
+
+/// Groups the circuit nodes by logic level. Principal inputs, sequential cells and black boxes are at level 0,
+/// and a combinational node is one level above its latest driver, so that the nodes of a level only depend on lower levels.
+/// Cells without connected inputs, like constants, are also at level 0.
+pub struct Levels<'a, I: Instantiable> {
+    /// A reference to the underlying netlist
+    _netlist: &'a Netlist<I>,
+    /// The circuit nodes at each level
+    levels: Vec<Vec<NetRef<I>>>,
+    /// The level of each circuit node, indexed by object
+    level: Vec<usize>,
+    /// The same levels as indices
+    schedule: Schedule,
+}
+
+impl<I> Levels<'_, I>
+where
+    I: Instantiable,
+{
+    /// Returns the circuit nodes at each level, in topological order within a level.
+    pub fn get_levels(&self) -> &[Vec<NetRef<I>>] {
+        &self.levels
+    }
+
+    /// Returns the level of a circuit node.
+    pub fn get_level(&self, node: &NetRef<I>) -> usize {
+        self.level[node.netref.borrow().get_index()]
+    }
+
+    /// Returns the highest level of the netlist.
+    pub fn get_max_level(&self) -> usize {
+        self.levels.len().saturating_sub(1)
+    }
+
+    /// Returns the levels as indices
+    pub(super) fn get_schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+}
+
+impl<'a, I> Analysis<'a, I> for Levels<'a, I>
+where
+    I: Instantiable,
+{
+    fn build(netlist: &'a Netlist<I>) -> Result<Self, Error> {
+        let topo = netlist.get_analysis::<TopoOrder<I>>()?;
+        let n = netlist.objects().count();
+        let mut level = vec![0; n];
+        let mut sources = vec![false; n];
+        let mut operands = vec![Vec::new(); n];
+        let mut levels: Vec<Vec<NetRef<I>>> = Vec::new();
+        let mut indices: Vec<Vec<usize>> = Vec::new();
+        for node in topo.iter() {
+            let index = node.netref.borrow().get_index();
+            operands[index] = node
+                .netref
+                .borrow()
+                .operands
+                .iter()
+                .map(|o| o.as_ref().map(|o| (o.root(), o.secondary())))
+                .collect();
+            sources[index] = is_source(node);
+            if !sources[index] {
+                level[index] = operands[index]
+                    .iter()
+                    .flatten()
+                    .map(|(root, _)| level[*root] + 1)
+                    .max()
+                    .unwrap_or(0);
+            }
+            if levels.len() <= level[index] {
+                levels.resize(level[index] + 1, Vec::new());
+                indices.resize(level[index] + 1, Vec::new());
+            }
+            levels[level[index]].push(node.clone());
+            indices[level[index]].push(index);
+        }
+
+        Ok(Levels {
+            _netlist: netlist,
+            levels,
+            level,
+            schedule: Schedule {
+                levels: indices,
+                sources,
+                operands,
+            },
+        })
+    }
+}
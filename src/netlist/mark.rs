@@ -0,0 +1,58 @@
+/*!
+
+  Visited flags on circuit nodes, for traversals that would otherwise keep a set of the nodes they visited.
+
+  Each circuit node remembers the traversal that last marked it, so [Netlist::new_traversal] clears every mark at once
+  by starting a new one. There is a single current traversal per netlist, which belongs to the caller:
+  the analyses and passes of the library do not use marks, but a traversal nested in another one clears its marks.
+
+*/
+
+use super::{NetRef, Netlist};
+use crate::circuit::Instantiable;
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Starts a new traversal, which unmarks every circuit node in constant time
+    pub fn new_traversal(&self) {
+        self.epoch.set(self.epoch.get() + 1);
+    }
+}
+
+impl<I> NetRef<I>
+where
+    I: Instantiable,
+{
+    /// Returns the current traversal of the netlist that owns the circuit node
+    fn epoch(&self) -> u64 {
+        self.netref
+            .borrow()
+            .owner
+            .upgrade()
+            .expect("Object is unlinked from netlist")
+            .epoch
+            .get()
+    }
+
+    /// Marks the circuit node as visited by the current traversal.
+    /// Returns `true` if it was not marked yet, like [std::collections::HashSet::insert].
+    pub fn mark(&self) -> bool {
+        let epoch = self.epoch();
+        let mark = &mut self.netref.borrow_mut().mark;
+        let unmarked = *mark != epoch;
+        *mark = epoch;
+        unmarked
+    }
+
+    /// Returns `true` if the circuit node was marked by the current traversal
+    pub fn is_marked(&self) -> bool {
+        self.netref.borrow().mark == self.epoch()
+    }
+
+    /// Unmarks the circuit node in the current traversal
+    pub fn unmark(&self) {
+        self.netref.borrow_mut().mark = 0;
+    }
+}
@@ -0,0 +1,174 @@
+/*!
+
+  Estimates of the memory used by a netlist.
+
+*/
+
+use super::{Netlist, Operand, OwnedObject, select::NameIndex};
+use crate::{
+    attribute::{AttributeKey, AttributeValue, Parameter},
+    circuit::{Identifier, Instantiable, Object},
+    netlist::provenance::Provenance,
+};
+use std::{cell::RefCell, collections::HashMap, mem::size_of};
+
+/// The bytes used by a netlist, by category. Sizes are estimated from the capacity of the containers,
+/// and hash maps are counted with one control byte per bucket. The memory owned by the cell types
+/// themselves, like the port lists of a [super::Gate], is not visible to the netlist, so only their inline size is counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Footprint {
+    /// The circuit node allocations, including their cell types and the operands of their input pins
+    pub objects: usize,
+    /// The output nets of the circuit nodes, the top-level outputs and the aliases, without their names
+    pub nets: usize,
+    /// The text of the names of the netlist, its nets and its instances
+    pub names: usize,
+    /// The attributes and provenance of the netlist, its circuit nodes and its nets
+    pub attributes: usize,
+    /// The port order, the bus resolutions, the use lists and the name index, when it is built
+    pub indices: usize,
+    /// The number of reference-counted allocations, one per circuit node
+    pub rc_allocations: usize,
+    /// The number of circuit nodes with more than [super::INLINE_PINS] input pins, whose operands are allocated separately
+    pub spilled_pins: usize,
+}
+
+impl Footprint {
+    /// Returns the total number of bytes
+    pub fn total(&self) -> usize {
+        self.objects + self.nets + self.names + self.attributes + self.indices
+    }
+}
+
+impl std::fmt::Display for Footprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "objects: {} B", self.objects)?;
+        writeln!(f, "nets: {} B", self.nets)?;
+        writeln!(f, "names: {} B", self.names)?;
+        writeln!(f, "attributes: {} B", self.attributes)?;
+        writeln!(f, "indices: {} B", self.indices)?;
+        writeln!(f, "total: {} B", self.total())?;
+        writeln!(f, "rc allocations: {}", self.rc_allocations)?;
+        writeln!(f, "spilled pin lists: {}", self.spilled_pins)
+    }
+}
+
+/// Returns the heap bytes of a vector
+pub(super) fn vec_bytes<T>(v: &Vec<T>) -> usize {
+    v.capacity() * size_of::<T>()
+}
+
+/// Returns the heap bytes of a hash map, without those owned by its keys and values
+fn map_bytes<K, V>(map: &HashMap<K, V>) -> usize {
+    map.capacity() * (size_of::<(K, V)>() + 1)
+}
+
+/// Returns the bytes of the text of a name
+fn name_bytes(id: &Identifier) -> usize {
+    id.get_name().len()
+}
+
+/// Returns the heap bytes owned by a parameter
+fn parameter_bytes(p: &Parameter) -> usize {
+    match p {
+        Parameter::Integer(_) | Parameter::Real(_) | Parameter::Logic(_) => 0,
+        Parameter::BitVec(bv) => bv.capacity().div_ceil(8),
+        Parameter::LogicVec(lv) => 2 * lv.len().div_ceil(8),
+        Parameter::String(s) => s.capacity(),
+    }
+}
+
+/// Returns the heap bytes of an attribute map and its contents
+fn attribute_bytes(map: &HashMap<AttributeKey, AttributeValue>) -> usize {
+    map_bytes(map)
+        + map
+            .iter()
+            .map(|(k, v)| k.capacity() + v.as_ref().map_or(0, parameter_bytes))
+            .sum::<usize>()
+}
+
+/// Returns the heap bytes owned by a provenance record
+fn provenance_bytes(p: &Provenance) -> usize {
+    [&p.file, &p.rtl_name, &p.pass]
+        .into_iter()
+        .flatten()
+        .map(String::capacity)
+        .sum()
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Estimates the memory used by the netlist, to compare representations before and after structural changes.
+    /// See [Footprint] for what is counted.
+    pub fn memory_footprint(&self) -> Footprint {
+        let mut fp = Footprint {
+            objects: vec_bytes(&self.objects.borrow()),
+            ..Default::default()
+        };
+        // Each node is a reference count pair around its cell
+        let rc_box = 2 * size_of::<usize>() + size_of::<RefCell<OwnedObject<I, Self>>>();
+        for obj in self.objects.borrow().iter() {
+            let obj = obj.borrow();
+            fp.rc_allocations += 1;
+            fp.objects += rc_box;
+            if obj.operands.spilled() {
+                fp.spilled_pins += 1;
+                fp.objects += obj.operands.capacity() * size_of::<Option<Operand>>();
+            }
+            if let Object::Instance(nets, name, _) = &obj.object {
+                fp.nets += vec_bytes(nets);
+                fp.names += name_bytes(name);
+            }
+            fp.names += obj
+                .object
+                .get_nets()
+                .iter()
+                .map(|n| name_bytes(n.get_identifier()))
+                .sum::<usize>();
+            fp.attributes += attribute_bytes(&obj.attributes);
+            fp.attributes += obj.provenance.as_ref().map_or(0, provenance_bytes);
+        }
+
+        let outputs = self.outputs.borrow();
+        fp.nets += vec_bytes(&outputs);
+        fp.names += outputs
+            .iter()
+            .map(|(_, n)| name_bytes(n.get_identifier()))
+            .sum::<usize>();
+        let aliases = self.aliases.borrow();
+        fp.nets += vec_bytes(&aliases);
+        fp.names += aliases
+            .iter()
+            .map(|(_, n)| name_bytes(n.get_identifier()))
+            .sum::<usize>();
+        fp.names += self.name.borrow().capacity();
+
+        let net_attributes = self.net_attributes.borrow();
+        fp.attributes += attribute_bytes(&self.attributes.borrow()) + map_bytes(&net_attributes);
+        fp.attributes += net_attributes
+            .iter()
+            .map(|(k, v)| name_bytes(k) + attribute_bytes(v))
+            .sum::<usize>();
+        let net_provenance = self.net_provenance.borrow();
+        fp.attributes += map_bytes(&net_provenance);
+        fp.attributes += net_provenance
+            .iter()
+            .map(|(k, v)| name_bytes(k) + provenance_bytes(v))
+            .sum::<usize>();
+
+        let port_order = self.port_order.borrow();
+        let resolutions = self.resolutions.borrow();
+        fp.indices += vec_bytes(&port_order) + port_order.iter().map(name_bytes).sum::<usize>();
+        fp.indices += map_bytes(&resolutions) + resolutions.keys().map(name_bytes).sum::<usize>();
+        let uses = self.uses.borrow();
+        fp.indices += map_bytes(&uses) + uses.values().map(vec_bytes).sum::<usize>();
+        fp.indices += self
+            .name_index
+            .borrow()
+            .as_ref()
+            .map_or(0, NameIndex::footprint);
+        fp
+    }
+}
@@ -0,0 +1,185 @@
+/*!
+
+  Name maps between an original design and the netlist it was transformed into, for cross-probing.
+
+  [Netlist::record_names] takes the names of the instances and nets as the original ones. From then on, the netlist
+  keeps the original names of each circuit node and net by their stable identifiers, through the edits that lose them:
+  a renamed node keeps its original name, a node that takes the uses of another in [Netlist::replace_net_uses] or
+  [Netlist::remove_instance] takes its original names too, and a copy made by [super::opt::replicate_registers]
+  has the original names of the cell it copies. [Netlist::name_map] then lists where each original name ended up.
+
+*/
+
+use super::{
+    Netlist,
+    annotation::{NetId, ObjectId},
+    sort_key,
+};
+use crate::circuit::{Identifier, Instantiable};
+use std::collections::{HashMap, HashSet};
+
+/// The original names of the circuit nodes and nets of a netlist
+#[derive(Debug, Clone, Default)]
+pub(super) struct NameOrigins {
+    /// The original names of the instances
+    instances: HashMap<ObjectId, HashSet<Identifier>>,
+    /// The original names of the nets
+    nets: HashMap<NetId, HashSet<Identifier>>,
+    /// Every original name of an instance, then of a net
+    originals: [Vec<Identifier>; 2],
+}
+
+/// The correspondence between the names of an original design and of the netlist it was transformed into
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NameMap {
+    /// Each original instance name with a current instance that it became, or [None] if the instance is gone.
+    /// An instance that was copied has an entry for each copy. Sorted by original name, then current name.
+    pub instances: Vec<(Identifier, Option<Identifier>)>,
+    /// Each original net name with a current net that it became, or [None] if the net is gone.
+    /// The input ports keep their names, so they map to themselves. Sorted by original name, then current name.
+    pub nets: Vec<(Identifier, Option<Identifier>)>,
+}
+
+impl NameMap {
+    /// Returns the current names of the instance originally named `name`
+    pub fn instance(&self, name: &Identifier) -> Vec<&Identifier> {
+        lookup(&self.instances, name)
+    }
+
+    /// Returns the current names of the net originally named `name`
+    pub fn net(&self, name: &Identifier) -> Vec<&Identifier> {
+        lookup(&self.nets, name)
+    }
+}
+
+/// Returns the current names of `name` in the sorted list of `entries`
+fn lookup<'a>(
+    entries: &'a [(Identifier, Option<Identifier>)],
+    name: &Identifier,
+) -> Vec<&'a Identifier> {
+    entries
+        .iter()
+        .filter(|(original, _)| original == name)
+        .filter_map(|(_, current)| current.as_ref())
+        .collect()
+}
+
+/// Sorts the entries of a name map, and lists the original names that are gone
+fn finish(
+    mut entries: Vec<(Identifier, Option<Identifier>)>,
+    originals: &[Identifier],
+) -> Vec<(Identifier, Option<Identifier>)> {
+    let mapped: HashSet<&Identifier> = entries.iter().map(|(o, _)| o).collect();
+    let gone: Vec<Identifier> = originals
+        .iter()
+        .filter(|o| !mapped.contains(o))
+        .cloned()
+        .collect();
+    entries.extend(gone.into_iter().map(|o| (o, None)));
+    entries.sort_by(|(a, x), (b, y)| {
+        sort_key(a)
+            .cmp(&sort_key(b))
+            .then_with(|| x.as_ref().map(sort_key).cmp(&y.as_ref().map(sort_key)))
+    });
+    entries.dedup();
+    entries
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Takes the current names of the instances and nets as the original names of the design,
+    /// and starts keeping track of them through the edits that follow. See [Netlist::name_map].
+    pub fn record_names(&self) {
+        let mut origins = NameOrigins::default();
+        for node in self.objects() {
+            let id = node.get_id();
+            if let Some(name) = node.get_instance_name() {
+                origins.instances.insert(id, HashSet::from([name.clone()]));
+                origins.originals[0].push(name);
+            }
+            for net in node.outputs() {
+                let name = net.get_identifier();
+                origins
+                    .nets
+                    .insert(net.get_id(), HashSet::from([name.clone()]));
+                origins.originals[1].push(name);
+            }
+        }
+        *self.name_origins.borrow_mut() = Some(origins);
+    }
+
+    /// Stops keeping track of the original names
+    pub fn clear_names(&self) {
+        *self.name_origins.borrow_mut() = None;
+    }
+
+    /// Returns where each original name of [Netlist::record_names] ended up, or [None] if names are not recorded.
+    /// Instances and nets inserted since then have no original name, and are left out.
+    pub fn name_map(&self) -> Option<NameMap> {
+        let origins = self.name_origins.borrow();
+        let origins = origins.as_ref()?;
+        let mut instances = Vec::new();
+        let mut nets = Vec::new();
+        for node in self.objects() {
+            if let Some(name) = node.get_instance_name()
+                && let Some(originals) = origins.instances.get(&node.get_id())
+            {
+                instances.extend(originals.iter().map(|o| (o.clone(), Some(name.clone()))));
+            }
+            for net in node.outputs() {
+                if let Some(originals) = origins.nets.get(&net.get_id()) {
+                    let name = net.get_identifier();
+                    nets.extend(originals.iter().map(|o| (o.clone(), Some(name.clone()))));
+                }
+            }
+        }
+        Some(NameMap {
+            instances: finish(instances, &origins.originals[0]),
+            nets: finish(nets, &origins.originals[1]),
+        })
+    }
+
+    /// Records that the net `to` takes the place of the net `from`, with its original names
+    pub(super) fn record_merge(&self, from: NetId, to: NetId) {
+        if let Some(origins) = self.name_origins.borrow_mut().as_mut()
+            && from != to
+        {
+            let names = origins.nets.get(&from).cloned().unwrap_or_default();
+            origins.nets.entry(to).or_default().extend(names);
+            if from.object != to.object {
+                let names = origins
+                    .instances
+                    .get(&from.object)
+                    .cloned()
+                    .unwrap_or_default();
+                origins
+                    .instances
+                    .entry(to.object)
+                    .or_default()
+                    .extend(names);
+            }
+        }
+    }
+
+    /// Records that the circuit node `to` is a copy of `from`, with the same original names for it and its nets
+    pub(super) fn record_copy(&self, from: ObjectId, to: ObjectId) {
+        if let Some(origins) = self.name_origins.borrow_mut().as_mut() {
+            if let Some(names) = origins.instances.get(&from).cloned() {
+                origins.instances.entry(to).or_default().extend(names);
+            }
+            let nets: Vec<(usize, HashSet<Identifier>)> = origins
+                .nets
+                .iter()
+                .filter(|(id, _)| id.object == from)
+                .map(|(id, names)| (id.output, names.clone()))
+                .collect();
+            for (output, names) in nets {
+                let id = NetId { object: to, output };
+                origins.nets.entry(id).or_default().extend(names);
+            }
+        }
+    }
+}
@@ -0,0 +1,912 @@
+/*!
+
+  Interchange with algorithm-centric logic network libraries, through and-inverter and majority-inverter graphs.
+
+  A [LogicNetwork] is a minimal AIG/MIG that other libraries can read node by node, and [NetworkBuilder] is the
+  interface of a library that can be built node by node, like the network interface of mockturtle.
+  Netlists build any [NetworkBuilder] with [Netlist::to_network], and a [LogicNetwork] is turned back into
+  a netlist with [LogicNetwork::to_netlist], or written and read as ASCII AIGER with [LogicNetwork::to_aag] and [LogicNetwork::from_aag],
+  or as binary AIGER with [LogicNetwork::to_aig_bytes] and [LogicNetwork::from_aig_bytes].
+
+*/
+
+use super::{DrivenNet, Gate, NetRef, Netlist};
+use crate::{
+    circuit::{Evaluate, Identifier, Instantiable, Net},
+    error::Error,
+    format_id,
+    graph::TopoOrder,
+    logic::Logic,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+/// The truth tables of six inputs over 64 patterns, where input `i` is bit `i` of the pattern index
+pub(super) const PROJECTIONS: [u64; 6] = [
+    0xAAAA_AAAA_AAAA_AAAA,
+    0xCCCC_CCCC_CCCC_CCCC,
+    0xF0F0_F0F0_F0F0_F0F0,
+    0xFF00_FF00_FF00_FF00,
+    0xFFFF_0000_FFFF_0000,
+    0xFFFF_FFFF_0000_0000,
+];
+
+/// A node of a [LogicNetwork] that may be complemented, encoded like an AIGER literal:
+/// twice the index of the node, plus one when it is complemented
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Signal(u32);
+
+impl Signal {
+    /// The constant false, which is node 0
+    pub const FALSE: Self = Signal(0);
+    /// The constant true, which is the complement of node 0
+    pub const TRUE: Self = Signal(1);
+
+    /// Returns the signal of node `node`, complemented when `complemented` is set
+    pub fn new(node: usize, complemented: bool) -> Self {
+        Signal(((node as u32) << 1) | u32::from(complemented))
+    }
+
+    /// Returns the signal of an AIGER literal
+    pub fn from_literal(literal: u32) -> Self {
+        Signal(literal)
+    }
+
+    /// Returns the AIGER literal of the signal
+    pub fn literal(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns the index of the node of the signal
+    pub fn node(&self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    /// Returns `true` if the signal is the complement of its node
+    pub fn is_complemented(&self) -> bool {
+        self.0 & 1 == 1
+    }
+}
+
+impl std::ops::Not for Signal {
+    type Output = Signal;
+
+    fn not(self) -> Signal {
+        Signal(self.0 ^ 1)
+    }
+}
+
+/// A node of a [LogicNetwork]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NetworkNode {
+    /// The constant false, which is always node 0
+    Constant,
+    /// A primary input, by name
+    Input(Identifier),
+    /// The AND of two signals
+    And(Signal, Signal),
+    /// The majority of three signals
+    Maj(Signal, Signal, Signal),
+}
+
+/// A library of logic networks that can be built node by node, like the network interface of mockturtle.
+/// Only [NetworkBuilder::create_and] and [NetworkBuilder::create_not] are needed, and the other gates
+/// are built from them unless the library has them.
+pub trait NetworkBuilder {
+    /// A reference to a node of the network, which may be complemented
+    type Signal: Clone;
+
+    /// Returns the constant `value`
+    fn constant(&mut self, value: bool) -> Self::Signal;
+
+    /// Creates a primary input named `name`
+    fn create_pi(&mut self, name: &Identifier) -> Self::Signal;
+
+    /// Creates a primary output named `name`, driven by `signal`
+    fn create_po(&mut self, signal: Self::Signal, name: &Identifier);
+
+    /// Returns the complement of `a`
+    fn create_not(&mut self, a: &Self::Signal) -> Self::Signal;
+
+    /// Returns the AND of `a` and `b`
+    fn create_and(&mut self, a: &Self::Signal, b: &Self::Signal) -> Self::Signal;
+
+    /// Returns the OR of `a` and `b`
+    fn create_or(&mut self, a: &Self::Signal, b: &Self::Signal) -> Self::Signal {
+        let (na, nb) = (self.create_not(a), self.create_not(b));
+        let and = self.create_and(&na, &nb);
+        self.create_not(&and)
+    }
+
+    /// Returns the XOR of `a` and `b`
+    fn create_xor(&mut self, a: &Self::Signal, b: &Self::Signal) -> Self::Signal {
+        let (na, nb) = (self.create_not(a), self.create_not(b));
+        let a_nb = self.create_and(a, &nb);
+        let na_b = self.create_and(&na, b);
+        self.create_or(&a_nb, &na_b)
+    }
+
+    /// Returns `b` when `sel` is high and `a` otherwise, like a `MUX` gate
+    fn create_mux(
+        &mut self,
+        sel: &Self::Signal,
+        a: &Self::Signal,
+        b: &Self::Signal,
+    ) -> Self::Signal {
+        let nsel = self.create_not(sel);
+        let low = self.create_and(&nsel, a);
+        let high = self.create_and(sel, b);
+        self.create_or(&low, &high)
+    }
+
+    /// Returns the majority of `a`, `b` and `c`
+    fn create_maj(&mut self, a: &Self::Signal, b: &Self::Signal, c: &Self::Signal) -> Self::Signal {
+        let ab = self.create_and(a, b);
+        let a_or_b = self.create_or(a, b);
+        let c_ab = self.create_and(c, &a_or_b);
+        self.create_or(&ab, &c_ab)
+    }
+}
+
+/// A network of two-input ANDs and three-input majority gates with complemented edges, which is an
+/// and-inverter graph (AIG) when it only has ANDs and a majority-inverter graph (MIG) otherwise.
+/// Node 0 is the constant false, and every node comes after its fanins. Structurally equal gates are shared,
+/// and the gates with constant or repeated fanins are simplified as they are created.
+#[derive(Debug, Clone)]
+pub struct LogicNetwork {
+    nodes: Vec<NetworkNode>,
+    outputs: Vec<(Identifier, Signal)>,
+    /// The index of each gate, for structural hashing
+    strash: HashMap<NetworkNode, usize>,
+}
+
+impl Default for LogicNetwork {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogicNetwork {
+    /// Creates an empty network, with only the constant node
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![NetworkNode::Constant],
+            outputs: Vec::new(),
+            strash: HashMap::new(),
+        }
+    }
+
+    /// Returns the nodes of the network, in topological order
+    pub fn nodes(&self) -> &[NetworkNode] {
+        &self.nodes
+    }
+
+    /// Returns the primary inputs of the network, in order of creation
+    pub fn inputs(&self) -> impl Iterator<Item = (Signal, &Identifier)> {
+        self.nodes.iter().enumerate().filter_map(|(i, n)| match n {
+            NetworkNode::Input(name) => Some((Signal::new(i, false), name)),
+            _ => None,
+        })
+    }
+
+    /// Returns the primary outputs of the network with their drivers
+    pub fn outputs(&self) -> &[(Identifier, Signal)] {
+        &self.outputs
+    }
+
+    /// Returns the number of AND and majority gates
+    pub fn num_gates(&self) -> usize {
+        self.strash.len()
+    }
+
+    /// Returns `true` if the network only has AND gates
+    pub fn is_aig(&self) -> bool {
+        !self.nodes.iter().any(|n| matches!(n, NetworkNode::Maj(..)))
+    }
+
+    /// Adds a gate, or returns the equal gate that the network already has
+    fn insert(&mut self, node: NetworkNode) -> Signal {
+        if let Some(index) = self.strash.get(&node) {
+            return Signal::new(*index, false);
+        }
+        let index = self.nodes.len();
+        self.strash.insert(node.clone(), index);
+        self.nodes.push(node);
+        Signal::new(index, false)
+    }
+
+    /// Simulates 64 patterns at once, where `inputs[i]` holds the patterns of input `i`, in the order of [LogicNetwork::inputs].
+    /// Returns the words of the outputs, or [Error::ArgumentMismatch] if there is not a word for every input.
+    pub fn simulate(&self, inputs: &[u64]) -> Result<Vec<u64>, Error> {
+        let n = self.inputs().count();
+        if inputs.len() != n {
+            return Err(Error::ArgumentMismatch(n, inputs.len()));
+        }
+        let mut words = Vec::with_capacity(self.nodes.len());
+        let mut next_input = inputs.iter();
+        for node in &self.nodes {
+            let value = |s: &Signal, words: &[u64]| {
+                words[s.node()] ^ if s.is_complemented() { u64::MAX } else { 0 }
+            };
+            let word = match node {
+                NetworkNode::Constant => 0,
+                NetworkNode::Input(_) => *next_input.next().unwrap(),
+                NetworkNode::And(a, b) => value(a, &words) & value(b, &words),
+                NetworkNode::Maj(a, b, c) => {
+                    let (a, b, c) = (value(a, &words), value(b, &words), value(c, &words));
+                    (a & b) | (a & c) | (b & c)
+                }
+            };
+            words.push(word);
+        }
+        Ok(self
+            .outputs
+            .iter()
+            .map(|(_, s)| words[s.node()] ^ if s.is_complemented() { u64::MAX } else { 0 })
+            .collect())
+    }
+
+    /// Builds the network in `builder`, node by node
+    pub fn replay<B: NetworkBuilder>(&self, builder: &mut B) {
+        let mut signals: Vec<B::Signal> = Vec::with_capacity(self.nodes.len());
+        fn signal<B: NetworkBuilder>(
+            builder: &mut B,
+            signals: &[B::Signal],
+            s: &Signal,
+        ) -> B::Signal {
+            let regular = &signals[s.node()];
+            if s.is_complemented() {
+                builder.create_not(regular)
+            } else {
+                regular.clone()
+            }
+        }
+        for node in &self.nodes {
+            let built = match node {
+                NetworkNode::Constant => builder.constant(false),
+                NetworkNode::Input(name) => builder.create_pi(name),
+                NetworkNode::And(a, b) => {
+                    let (a, b) = (signal(builder, &signals, a), signal(builder, &signals, b));
+                    builder.create_and(&a, &b)
+                }
+                NetworkNode::Maj(a, b, c) => {
+                    let a = signal(builder, &signals, a);
+                    let b = signal(builder, &signals, b);
+                    let c = signal(builder, &signals, c);
+                    builder.create_maj(&a, &b, &c)
+                }
+            };
+            signals.push(built);
+        }
+        for (name, s) in &self.outputs {
+            let s = signal(builder, &signals, s);
+            builder.create_po(s, name);
+        }
+    }
+
+    /// Returns the network with its majority gates expanded into ANDs
+    pub fn to_aig(&self) -> LogicNetwork {
+        let mut aig = Aig(LogicNetwork::new());
+        self.replay(&mut aig);
+        aig.0
+    }
+
+    /// Builds a netlist named `name` with an `AND` gate for every AND and four gates for every majority,
+    /// sharing an `INV` gate between the complemented uses of each node. The input nets and the output ports
+    /// are named after the primary inputs and outputs.
+    pub fn to_netlist<I>(&self, name: &str) -> Result<Rc<Netlist<I>>, Error>
+    where
+        I: Instantiable + From<Gate>,
+    {
+        let mut import = Import {
+            netlist: Netlist::new(name.to_string()),
+            nets: Vec::with_capacity(self.nodes.len()),
+            inverted: HashMap::new(),
+        };
+        for (i, node) in self.nodes.iter().enumerate() {
+            let net = match node {
+                NetworkNode::Constant => None,
+                NetworkNode::Input(name) => {
+                    Some(import.netlist.insert_input(Net::new_logic(name.clone())))
+                }
+                NetworkNode::And(a, b) => {
+                    let inputs = [import.signal(a)?, import.signal(b)?];
+                    Some(import.gate("AND", &["A", "B"], &inputs, format_id!("and_{i}"))?)
+                }
+                NetworkNode::Maj(a, b, c) => {
+                    let (a, b, c) = (import.signal(a)?, import.signal(b)?, import.signal(c)?);
+                    let ab = [a.clone(), b.clone()];
+                    let and = import.gate("AND", &["A", "B"], &ab, format_id!("maj_{i}_0"))?;
+                    let or = import.gate("OR", &["A", "B"], &ab, format_id!("maj_{i}_1"))?;
+                    let c_or =
+                        import.gate("AND", &["A", "B"], &[c, or], format_id!("maj_{i}_2"))?;
+                    Some(import.gate("OR", &["A", "B"], &[and, c_or], format_id!("maj_{i}_3"))?)
+                }
+            };
+            import.nets.push(net);
+        }
+        for (name, s) in &self.outputs {
+            let net = import.signal(s)?;
+            import.netlist.expose_net_with_name(net, name.clone());
+        }
+        Ok(import.netlist)
+    }
+
+    /// Returns the network as an AIG with the AIGER variable of each node, where the inputs come before the AND gates
+    fn aiger_numbering(&self) -> (std::borrow::Cow<'_, LogicNetwork>, Vec<u32>) {
+        let aig = if self.is_aig() {
+            std::borrow::Cow::Borrowed(self)
+        } else {
+            std::borrow::Cow::Owned(self.to_aig())
+        };
+        let mut var = vec![0; aig.nodes.len()];
+        let ands = (0..aig.nodes.len()).filter(|i| matches!(aig.nodes[*i], NetworkNode::And(..)));
+        let order: Vec<usize> = aig.inputs().map(|(s, _)| s.node()).chain(ands).collect();
+        for (k, i) in order.into_iter().enumerate() {
+            var[i] = k as u32 + 1;
+        }
+        (aig, var)
+    }
+
+    /// Returns the header of an AIGER file in `format`, `aag` or `aig`
+    fn aiger_header(&self, format: &str) -> String {
+        let inputs = self.inputs().count();
+        format!(
+            "{format} {} {inputs} 0 {} {}\n",
+            inputs + self.num_gates(),
+            self.outputs.len(),
+            self.num_gates()
+        )
+    }
+
+    /// Returns the symbol table of an AIGER file, with a symbol for every input and output
+    fn aiger_symbols(&self) -> String {
+        let mut out = String::new();
+        for (k, (_, name)) in self.inputs().enumerate() {
+            out.push_str(&format!("i{k} {name}\n"));
+        }
+        for (k, (name, _)) in self.outputs.iter().enumerate() {
+            out.push_str(&format!("o{k} {name}\n"));
+        }
+        out
+    }
+
+    /// Writes the network in the ASCII AIGER format, with a symbol for every input and output.
+    /// The majority gates are expanded into ANDs first, see [LogicNetwork::to_aig].
+    pub fn to_aag(&self) -> String {
+        let (aig, var) = self.aiger_numbering();
+        let literal = |s: &Signal| 2 * var[s.node()] + u32::from(s.is_complemented());
+        let mut out = aig.aiger_header("aag");
+        for (s, _) in aig.inputs() {
+            out.push_str(&format!("{}\n", 2 * var[s.node()]));
+        }
+        for (_, s) in &aig.outputs {
+            out.push_str(&format!("{}\n", literal(s)));
+        }
+        for (i, node) in aig.nodes.iter().enumerate() {
+            if let NetworkNode::And(a, b) = node {
+                let (a, b) = (literal(a).max(literal(b)), literal(a).min(literal(b)));
+                out.push_str(&format!("{} {a} {b}\n", 2 * var[i]));
+            }
+        }
+        out.push_str(&aig.aiger_symbols());
+        out
+    }
+
+    /// Writes the network in the binary AIGER format, with a symbol for every input and output.
+    /// The majority gates are expanded into ANDs first, see [LogicNetwork::to_aig].
+    pub fn to_aig_bytes(&self) -> Vec<u8> {
+        let (aig, var) = self.aiger_numbering();
+        let literal = |s: &Signal| 2 * var[s.node()] + u32::from(s.is_complemented());
+        let mut out = aig.aiger_header("aig").into_bytes();
+        for (_, s) in &aig.outputs {
+            out.extend_from_slice(format!("{}\n", literal(s)).as_bytes());
+        }
+        for (i, node) in aig.nodes.iter().enumerate() {
+            if let NetworkNode::And(a, b) = node {
+                let (a, b) = (literal(a).max(literal(b)), literal(a).min(literal(b)));
+                encode_delta(&mut out, 2 * var[i] - a);
+                encode_delta(&mut out, a - b);
+            }
+        }
+        out.extend_from_slice(aig.aiger_symbols().as_bytes());
+        out
+    }
+
+    /// Reads a network in the ASCII AIGER format. The inputs and outputs without a symbol are named `i` and `o` followed by their position.
+    /// Returns [Error::Unsupported] for latches, and [Error::ParseError] for malformed text, variables defined more than once,
+    /// empty symbols or AND gates that depend on themselves.
+    pub fn from_aag(text: &str) -> Result<Self, Error> {
+        let mut lines = text.lines();
+        let header: Vec<&str> = lines
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect();
+        if header.len() != 6 || header[0] != "aag" {
+            return Err(Error::ParseError("Expected an `aag` header".to_string()));
+        }
+        let counts = header[1..]
+            .iter()
+            .map(|c| parse_number(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [_, n_inputs, n_latches, n_outputs, n_ands] = counts[..] else {
+            unreachable!()
+        };
+        if n_latches != 0 {
+            return Err(Error::Unsupported("AIGER latches".to_string()));
+        }
+        let mut read = |n: usize| {
+            let line = lines
+                .next()
+                .ok_or_else(|| Error::ParseError("Truncated AIGER file".to_string()))?;
+            let literals = line
+                .split_whitespace()
+                .map(parse_number)
+                .collect::<Result<Vec<_>, _>>()?;
+            if literals.len() != n {
+                return Err(Error::ParseError(format!(
+                    "Expected {n} literals in `{line}`"
+                )));
+            }
+            Ok(literals)
+        };
+        let inputs: Vec<u32> = (0..n_inputs)
+            .map(|_| Ok(read(1)?[0]))
+            .collect::<Result<_, Error>>()?;
+        let outputs: Vec<u32> = (0..n_outputs)
+            .map(|_| Ok(read(1)?[0]))
+            .collect::<Result<_, Error>>()?;
+        let ands: Vec<Vec<u32>> = (0..n_ands).map(|_| read(3)).collect::<Result<_, _>>()?;
+
+        Self::from_aiger(&inputs, &outputs, &ands, lines)
+    }
+
+    /// Reads a network in the binary AIGER format. The inputs and outputs without a symbol are named `i` and `o` followed by their position.
+    /// Returns [Error::Unsupported] for latches, and [Error::ParseError] for malformed files.
+    pub fn from_aig_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let truncated = || Error::ParseError("Truncated AIGER file".to_string());
+        let mut rest = bytes;
+        let mut line = || -> Result<&str, Error> {
+            let end = rest
+                .iter()
+                .position(|b| *b == b'\n')
+                .ok_or_else(truncated)?;
+            let line = std::str::from_utf8(&rest[..end])
+                .map_err(|_| Error::ParseError("Expected text in the AIGER header".to_string()))?;
+            rest = &rest[end + 1..];
+            Ok(line)
+        };
+        let header: Vec<&str> = line()?.split_whitespace().collect();
+        if header.len() != 6 || header[0] != "aig" {
+            return Err(Error::ParseError("Expected an `aig` header".to_string()));
+        }
+        let counts = header[1..]
+            .iter()
+            .map(|c| parse_number(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [_, n_inputs, n_latches, n_outputs, n_ands] = counts[..] else {
+            unreachable!()
+        };
+        if n_latches != 0 {
+            return Err(Error::Unsupported("AIGER latches".to_string()));
+        }
+        // Each output and AND gate takes at least two bytes, and every literal fits in a word
+        if (u64::from(n_outputs) + u64::from(n_ands)) * 2 > bytes.len() as u64 {
+            return Err(truncated());
+        }
+        n_inputs
+            .checked_add(n_ands)
+            .and_then(|n| n.checked_mul(2))
+            .and_then(|n| n.checked_add(1))
+            .ok_or_else(|| Error::ParseError("Too many AIGER variables".to_string()))?;
+        let inputs: Vec<u32> = (1..=n_inputs).map(|v| 2 * v).collect();
+        let outputs: Vec<u32> = (0..n_outputs)
+            .map(|_| parse_number(line()?.trim()))
+            .collect::<Result<_, _>>()?;
+        let mut ands = Vec::with_capacity(n_ands as usize);
+        for k in 0..n_ands {
+            let lhs = 2 * (n_inputs + k + 1);
+            let a = lhs
+                .checked_sub(decode_delta(&mut rest)?)
+                .ok_or_else(|| Error::ParseError(format!("Invalid AND gate {lhs}")))?;
+            let b = a
+                .checked_sub(decode_delta(&mut rest)?)
+                .ok_or_else(|| Error::ParseError(format!("Invalid AND gate {lhs}")))?;
+            ands.push(vec![lhs, a, b]);
+        }
+        let symbols = String::from_utf8_lossy(rest);
+        Self::from_aiger(&inputs, &outputs, &ands, symbols.lines())
+    }
+
+    /// Builds a network from the literals of the inputs, outputs and AND gates of an AIGER file, and its symbol lines
+    fn from_aiger<'a>(
+        inputs: &[u32],
+        outputs: &[u32],
+        ands: &[Vec<u32>],
+        lines: impl Iterator<Item = &'a str>,
+    ) -> Result<Self, Error> {
+        let mut input_names = vec![None; inputs.len()];
+        let mut output_names = vec![None; outputs.len()];
+        for line in lines {
+            if line.starts_with('c') {
+                break;
+            }
+            let (symbol, name) = line
+                .split_once(' ')
+                .ok_or_else(|| Error::ParseError(format!("Malformed symbol `{line}`")))?;
+            let (names, position) = match symbol.split_at_checked(1) {
+                Some(("i", position)) => (&mut input_names, position),
+                Some(("o", position)) => (&mut output_names, position),
+                _ => return Err(Error::ParseError(format!("Unsupported symbol `{line}`"))),
+            };
+            let slot = names
+                .get_mut(parse_number(position)? as usize)
+                .ok_or_else(|| Error::ParseError(format!("Symbol out of range `{line}`")))?;
+            let empty = match name.strip_prefix('\\') {
+                Some(root) => root.split_whitespace().next().is_none(),
+                None => name.is_empty(),
+            };
+            if empty {
+                return Err(Error::ParseError(format!("Empty symbol name `{line}`")));
+            }
+            *slot = Some(Identifier::new(name.to_string()));
+        }
+
+        // Every variable is defined once, by an even literal of an input or an AND gate
+        let mut defined = HashSet::from([0]);
+        for literal in inputs.iter().chain(ands.iter().map(|and| &and[0])) {
+            if literal & 1 == 1 || !defined.insert(literal >> 1) {
+                return Err(Error::ParseError(format!(
+                    "Invalid definition of literal {literal}"
+                )));
+            }
+        }
+
+        let mut network = LogicNetwork::new();
+        let mut signals: HashMap<u32, Signal> = HashMap::from([(0, Signal::FALSE)]);
+        for (k, literal) in inputs.iter().enumerate() {
+            let name = input_names[k].clone().unwrap_or_else(|| format_id!("i{k}"));
+            signals.insert(literal >> 1, network.create_pi(&name));
+        }
+        // The AND gates of an ASCII file may be in any order
+        let mut pending = ands.to_vec();
+        while !pending.is_empty() {
+            let before = pending.len();
+            pending.retain(|and| {
+                let fanin = |l: u32| {
+                    signals
+                        .get(&(l >> 1))
+                        .map(|s| if l & 1 == 1 { !*s } else { *s })
+                };
+                match (fanin(and[1]), fanin(and[2])) {
+                    (Some(a), Some(b)) => {
+                        let s = network.create_and(&a, &b);
+                        signals.insert(and[0] >> 1, s);
+                        false
+                    }
+                    _ => true,
+                }
+            });
+            if pending.len() == before {
+                return Err(Error::ParseError(
+                    "AND gates with undefined or cyclic fanins".to_string(),
+                ));
+            }
+        }
+        for (k, literal) in outputs.iter().enumerate() {
+            let s = signals
+                .get(&(literal >> 1))
+                .ok_or_else(|| Error::ParseError(format!("Undefined output literal {literal}")))?;
+            let s = if literal & 1 == 1 { !*s } else { *s };
+            let name = output_names[k]
+                .clone()
+                .unwrap_or_else(|| format_id!("o{k}"));
+            network.create_po(s, &name);
+        }
+        Ok(network)
+    }
+}
+
+/// Appends `delta` to a binary AIGER file, seven bits at a time from the least significant
+fn encode_delta(out: &mut Vec<u8>, mut delta: u32) {
+    while delta >= 0x80 {
+        out.push((delta & 0x7f) as u8 | 0x80);
+        delta >>= 7;
+    }
+    out.push(delta as u8);
+}
+
+/// Reads a delta of a binary AIGER file from the start of `bytes`, and advances past it
+fn decode_delta(bytes: &mut &[u8]) -> Result<u32, Error> {
+    let mut delta = 0u32;
+    for shift in (0..35).step_by(7) {
+        let (byte, rest) = bytes
+            .split_first()
+            .ok_or_else(|| Error::ParseError("Truncated AIGER file".to_string()))?;
+        *bytes = rest;
+        delta |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(delta);
+        }
+    }
+    Err(Error::ParseError("Invalid delta in AIGER file".to_string()))
+}
+
+/// Parses a number of an AIGER file
+fn parse_number(text: &str) -> Result<u32, Error> {
+    text.parse()
+        .map_err(|_| Error::ParseError(format!("Expected a number, got `{text}`")))
+}
+
+impl NetworkBuilder for LogicNetwork {
+    type Signal = Signal;
+
+    fn constant(&mut self, value: bool) -> Signal {
+        if value { Signal::TRUE } else { Signal::FALSE }
+    }
+
+    fn create_pi(&mut self, name: &Identifier) -> Signal {
+        self.nodes.push(NetworkNode::Input(name.clone()));
+        Signal::new(self.nodes.len() - 1, false)
+    }
+
+    fn create_po(&mut self, signal: Signal, name: &Identifier) {
+        self.outputs.push((name.clone(), signal));
+    }
+
+    fn create_not(&mut self, a: &Signal) -> Signal {
+        !*a
+    }
+
+    fn create_and(&mut self, a: &Signal, b: &Signal) -> Signal {
+        let (a, b) = (*a.min(b), *a.max(b));
+        if a == Signal::FALSE || a == !b {
+            Signal::FALSE
+        } else if a == Signal::TRUE || a == b {
+            b
+        } else {
+            self.insert(NetworkNode::And(a, b))
+        }
+    }
+
+    fn create_maj(&mut self, a: &Signal, b: &Signal, c: &Signal) -> Signal {
+        let mut fanins = [*a, *b, *c];
+        fanins.sort_unstable();
+        let [a, b, c] = fanins;
+        if a == b || b == c {
+            b
+        } else if a == c {
+            a
+        } else if a == !b {
+            c
+        } else if b == !c {
+            a
+        } else if a == !c {
+            b
+        } else if a == Signal::FALSE {
+            self.create_and(&b, &c)
+        } else if a == Signal::TRUE {
+            self.create_or(&b, &c)
+        } else {
+            self.insert(NetworkNode::Maj(a, b, c))
+        }
+    }
+}
+
+/// A [LogicNetwork] that builds majority gates out of ANDs
+struct Aig(LogicNetwork);
+
+impl NetworkBuilder for Aig {
+    type Signal = Signal;
+
+    fn constant(&mut self, value: bool) -> Signal {
+        self.0.constant(value)
+    }
+
+    fn create_pi(&mut self, name: &Identifier) -> Signal {
+        self.0.create_pi(name)
+    }
+
+    fn create_po(&mut self, signal: Signal, name: &Identifier) {
+        self.0.create_po(signal, name)
+    }
+
+    fn create_not(&mut self, a: &Signal) -> Signal {
+        self.0.create_not(a)
+    }
+
+    fn create_and(&mut self, a: &Signal, b: &Signal) -> Signal {
+        self.0.create_and(a, b)
+    }
+}
+
+/// The nets of the nodes of a [LogicNetwork] while it is turned into a netlist
+struct Import<I: Instantiable> {
+    netlist: Rc<Netlist<I>>,
+    /// The net of each node, which is [None] for the constant
+    nets: Vec<Option<DrivenNet<I>>>,
+    /// The inverters of the nodes that are used complemented
+    inverted: HashMap<usize, DrivenNet<I>>,
+}
+
+impl<I> Import<I>
+where
+    I: Instantiable + From<Gate>,
+{
+    fn gate(
+        &self,
+        name: &str,
+        ports: &[&str],
+        inputs: &[DrivenNet<I>],
+        inst_name: Identifier,
+    ) -> Result<DrivenNet<I>, Error> {
+        let ports = ports.iter().map(|p| Identifier::from(*p)).collect();
+        let gate = Gate::new_logical(name.into(), ports, "Y".into());
+        Ok(self
+            .netlist
+            .insert_gate(gate.into(), inst_name, inputs)?
+            .get_output(0))
+    }
+
+    /// Returns the net of signal `s`, inserting the constant cells and the inverters on first use
+    fn signal(&mut self, s: &Signal) -> Result<DrivenNet<I>, Error> {
+        let node = s.node();
+        let Some(net) = self.nets[node].clone() else {
+            let value = Logic::from_bool(s.is_complemented());
+            return self.netlist.constant_driver(value);
+        };
+        if !s.is_complemented() {
+            return Ok(net);
+        }
+        if let Some(inverted) = self.inverted.get(&node) {
+            return Ok(inverted.clone());
+        }
+        let inverted = self.gate("INV", &["A"], &[net], format_id!("inv_{node}"))?;
+        self.inverted.insert(node, inverted.clone());
+        Ok(inverted)
+    }
+}
+
+/// Builds the function of `table` over `inputs` by Shannon expansion on the last input,
+/// where input `i` is bit `i` of the index into `table`
+fn expand<B: NetworkBuilder>(builder: &mut B, table: u64, inputs: &[B::Signal]) -> B::Signal {
+    let Some((last, rest)) = inputs.split_last() else {
+        return builder.constant(table & 1 == 1);
+    };
+    let half = 1 << rest.len();
+    let mask = (1u64 << half) - 1;
+    let (t0, t1) = (table & mask, (table >> half) & mask);
+    if t0 == t1 {
+        return expand(builder, t0, rest);
+    }
+    let nlast = builder.create_not(last);
+    match (t0, t1) {
+        (0, t1) if t1 == mask => last.clone(),
+        (t0, 0) if t0 == mask => nlast,
+        (0, t1) => {
+            let f1 = expand(builder, t1, rest);
+            builder.create_and(last, &f1)
+        }
+        (t0, 0) => {
+            let f0 = expand(builder, t0, rest);
+            builder.create_and(&nlast, &f0)
+        }
+        (t0, t1) if t0 == mask => {
+            let f1 = expand(builder, t1, rest);
+            builder.create_or(&nlast, &f1)
+        }
+        (t0, t1) if t1 == mask => {
+            let f0 = expand(builder, t0, rest);
+            builder.create_or(last, &f0)
+        }
+        (t0, t1) if t1 == !t0 & mask => {
+            let f0 = expand(builder, t0, rest);
+            builder.create_xor(last, &f0)
+        }
+        (t0, t1) => {
+            let f0 = expand(builder, t0, rest);
+            let f1 = expand(builder, t1, rest);
+            builder.create_mux(last, &f0, &f1)
+        }
+    }
+}
+
+/// Builds the outputs of the cell at `node` from the `signals` of its drivers, by Shannon expansion of its truth tables.
+/// Returns [Error::Unsupported] for unknown constants, tri-state outputs and cells with more than 6 inputs,
+/// and [Error::UnconnectedInputs] for unconnected input ports.
+pub(super) fn cell_outputs<I: Evaluate, B: NetworkBuilder>(
+    builder: &mut B,
+    node: &NetRef<I>,
+    signals: &HashMap<DrivenNet<I>, B::Signal>,
+) -> Result<Vec<B::Signal>, Error> {
+    let cell = node
+        .get_instance_type()
+        .expect("Node is an instance")
+        .clone();
+    let inst_name = node.get_instance_name().expect("Instance has a name");
+    if let Some(value) = cell.get_constant() {
+        return match value {
+            Logic::True => Ok(vec![builder.constant(true)]),
+            Logic::False => Ok(vec![builder.constant(false)]),
+            _ => Err(Error::Unsupported(format!(
+                "constant {value} of {inst_name}"
+            ))),
+        };
+    }
+    let mut inputs = Vec::new();
+    for input in node.inputs() {
+        match input.get_driver().and_then(|d| signals.get(&d).cloned()) {
+            Some(s) => inputs.push(s),
+            None => {
+                return Err(Error::UnconnectedInputs(vec![(
+                    inst_name,
+                    input.get_port(),
+                )]));
+            }
+        }
+    }
+    if inputs.len() > PROJECTIONS.len() {
+        return Err(Error::Unsupported(format!(
+            "cell {inst_name} with more than {} inputs",
+            PROJECTIONS.len()
+        )));
+    }
+    let tables = cell.eval_words(&PROJECTIONS[..inputs.len()]);
+    let mut outputs = Vec::with_capacity(tables.len());
+    for (pos, table) in tables.into_iter().enumerate() {
+        if cell.is_tristate_output(pos) {
+            return Err(Error::Unsupported(format!(
+                "tri-state output of {inst_name}"
+            )));
+        }
+        outputs.push(expand(builder, table, &inputs));
+    }
+    Ok(outputs)
+}
+
+impl<I> Netlist<I>
+where
+    I: Evaluate,
+{
+    /// Builds the netlist in `builder`, with a primary input for every input net and a primary output for every top-level output.
+    /// Each output of a cell is built from its truth table by Shannon expansion, so cells need at most 6 inputs.
+    /// Returns [Error::Unsupported] for sequential cells, black boxes, tri-state outputs and unknown constants,
+    /// [Error::UnconnectedInputs] for unconnected input ports, or an error if the netlist has combinational cycles.
+    pub fn to_network<B: NetworkBuilder>(&self, builder: &mut B) -> Result<(), Error> {
+        let order = self.get_analysis::<TopoOrder<I>>()?.get_order().to_vec();
+        let mut signals: HashMap<DrivenNet<I>, B::Signal> = HashMap::new();
+        for node in order {
+            let Some(cell) = node.get_instance_type().map(|c| c.clone()) else {
+                let net = node.get_output(0);
+                signals.insert(net.clone(), builder.create_pi(&net.get_identifier()));
+                continue;
+            };
+            let inst_name = node.get_instance_name().expect("Instance has a name");
+            if cell.is_seq() || cell.is_blackbox() || cell.is_latch() {
+                return Err(Error::Unsupported(format!(
+                    "sequential or black-box cell {inst_name}"
+                )));
+            }
+            for (pos, signal) in cell_outputs(builder, &node, &signals)?
+                .into_iter()
+                .enumerate()
+            {
+                signals.insert(node.get_output(pos), signal);
+            }
+        }
+        for (net, port) in self.outputs() {
+            builder.create_po(signals[&net].clone(), port.get_identifier());
+        }
+        Ok(())
+    }
+
+    /// Returns the netlist as a [LogicNetwork], see [Netlist::to_network]
+    pub fn to_logic_network(&self) -> Result<LogicNetwork, Error> {
+        let mut network = LogicNetwork::new();
+        self.to_network(&mut network)?;
+        Ok(network)
+    }
+}
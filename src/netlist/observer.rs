@@ -0,0 +1,148 @@
+/*!
+
+  Callbacks on netlist mutation, so that incremental analyses can stay in sync with edits.
+
+*/
+
+use super::{InputPort, NetRef, Netlist, annotation::ObjectId};
+use crate::{circuit::Instantiable, util::trace_event};
+
+/// A handle to a registered observer, used to unregister it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(usize);
+
+/// A list of callbacks for one kind of event
+type Callbacks<T> = Vec<(ObserverId, Box<T>)>;
+/// A callback on a circuit node
+type NodeCallback<I> = dyn FnMut(&NetRef<I>);
+/// A callback on an input port
+type PortCallback<I> = dyn FnMut(&InputPort<I>);
+
+/// The observers registered on a netlist
+pub(crate) struct Observers<I: Instantiable> {
+    /// The identifier of the next observer
+    next_id: usize,
+    /// Called after a circuit node is inserted
+    insert: Callbacks<NodeCallback<I>>,
+    /// Called after a circuit node is removed
+    remove: Callbacks<dyn FnMut(ObjectId)>,
+    /// Called after an input port changes driver
+    reconnect: Callbacks<PortCallback<I>>,
+}
+
+impl<I> Default for Observers<I>
+where
+    I: Instantiable,
+{
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            insert: Vec::new(),
+            remove: Vec::new(),
+            reconnect: Vec::new(),
+        }
+    }
+}
+
+impl<I> std::fmt::Debug for Observers<I>
+where
+    I: Instantiable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Observers")
+            .field("insert", &self.insert.len())
+            .field("remove", &self.remove.len())
+            .field("reconnect", &self.reconnect.len())
+            .finish()
+    }
+}
+
+impl<I> Observers<I>
+where
+    I: Instantiable,
+{
+    /// Returns a fresh observer identifier
+    fn new_id(&mut self) -> ObserverId {
+        self.next_id += 1;
+        ObserverId(self.next_id - 1)
+    }
+}
+
+/// Calls each callback of the list selected by `select`.
+/// The list is moved out while the callbacks run, so that they may edit the netlist.
+fn notify<I, T, F>(netlist: &Netlist<I>, select: F, mut call: impl FnMut(&mut T))
+where
+    I: Instantiable,
+    T: ?Sized,
+    F: Fn(&mut Observers<I>) -> &mut Callbacks<T>,
+{
+    let mut callbacks = std::mem::take(select(&mut netlist.observers.borrow_mut()));
+    if callbacks.is_empty() {
+        return;
+    }
+    for (_, callback) in callbacks.iter_mut() {
+        call(callback);
+    }
+    let mut observers = netlist.observers.borrow_mut();
+    let registered = select(&mut observers);
+    callbacks.append(registered);
+    *registered = callbacks;
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Registers `callback` to be called after a circuit node is inserted.
+    pub fn on_insert(&self, callback: impl FnMut(&NetRef<I>) + 'static) -> ObserverId {
+        let mut observers = self.observers.borrow_mut();
+        let id = observers.new_id();
+        observers.insert.push((id, Box::new(callback)));
+        id
+    }
+
+    /// Registers `callback` to be called after a circuit node is removed from the netlist by [Netlist::clean].
+    pub fn on_remove(&self, callback: impl FnMut(ObjectId) + 'static) -> ObserverId {
+        let mut observers = self.observers.borrow_mut();
+        let id = observers.new_id();
+        observers.remove.push((id, Box::new(callback)));
+        id
+    }
+
+    /// Registers `callback` to be called after an input port is connected to a different driver or disconnected.
+    pub fn on_reconnect(&self, callback: impl FnMut(&InputPort<I>) + 'static) -> ObserverId {
+        let mut observers = self.observers.borrow_mut();
+        let id = observers.new_id();
+        observers.reconnect.push((id, Box::new(callback)));
+        id
+    }
+
+    /// Unregisters an observer. Returns `false` if it was not registered.
+    /// Observers cannot unregister themselves from within their callback.
+    pub fn remove_observer(&self, id: ObserverId) -> bool {
+        let mut observers = self.observers.borrow_mut();
+        let before = observers.insert.len() + observers.remove.len() + observers.reconnect.len();
+        observers.insert.retain(|(i, _)| *i != id);
+        observers.remove.retain(|(i, _)| *i != id);
+        observers.reconnect.retain(|(i, _)| *i != id);
+        before != observers.insert.len() + observers.remove.len() + observers.reconnect.len()
+    }
+
+    /// Notifies the observers that `node` was inserted
+    pub(crate) fn notify_insert(&self, node: &NetRef<I>) {
+        trace_event!(trace, node = %node, "insert");
+        notify(self, |o| &mut o.insert, |callback| callback(node));
+    }
+
+    /// Notifies the observers that the node `id` was removed
+    pub(crate) fn notify_remove(&self, id: ObjectId) {
+        trace_event!(trace, node = %id, "remove");
+        notify(self, |o| &mut o.remove, |callback| callback(id));
+    }
+
+    /// Notifies the observers that `port` changed driver
+    pub(crate) fn notify_reconnect(&self, port: &InputPort<I>) {
+        trace_event!(trace, port = %port, "reconnect");
+        notify(self, |o| &mut o.reconnect, |callback| callback(port));
+    }
+}
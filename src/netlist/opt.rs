@@ -0,0 +1,1030 @@
+/*!
+
+  Optimization passes that transform a netlist in place.
+
+*/
+
+use super::{
+    DrivenNet, Gate, InputPort, NetRef, Netlist,
+    dft::{FaultSite, StuckAt, fault_simulate},
+    network::{NetworkBuilder, PROJECTIONS, cell_outputs},
+    sat::{Cnf, Lit, SatResult},
+    sim::random_word,
+};
+use crate::{
+    circuit::{Evaluate, Identifier, Instantiable},
+    error::Error,
+    format_id,
+    graph::TopoOrder,
+    logic::Logic,
+    util::{debug_span, trace_event},
+};
+use bitvec::vec::BitVec;
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+/// Cells whose polarity is known, so that [push_inverters] can move inverters through them
+pub trait Polarity: Instantiable {
+    /// Returns `true` if the cell is an inverter with a single input and output
+    fn is_inverter(&self) -> bool;
+
+    /// Returns the cell that computes the complement of this cell, like `NAND` for `AND`
+    fn complement_output(&self) -> Option<Self>;
+
+    /// Returns the cell that computes this cell on complemented inputs, like `NOR` for `AND`
+    fn complement_inputs(&self) -> Option<Self>;
+}
+
+/// The gate library is complemented by name, with the De Morgan duals of `AND`, `NAND`, `OR` and `NOR`.
+/// Complementing an odd number of inputs to `XOR` or `XNOR` complements the output.
+impl Polarity for Gate {
+    fn is_inverter(&self) -> bool {
+        matches!(self.name.get_name(), "INV" | "NOT") && self.inputs.len() == 1
+    }
+
+    fn complement_output(&self) -> Option<Self> {
+        let name = match self.name.get_name() {
+            "AND" => "NAND",
+            "NAND" => "AND",
+            "OR" => "NOR",
+            "NOR" => "OR",
+            "XOR" => "XNOR",
+            "XNOR" => "XOR",
+            "BUF" => "INV",
+            "INV" | "NOT" => "BUF",
+            _ => return None,
+        };
+        let mut gate = self.clone();
+        gate.set_gate_name(name.into());
+        Some(gate)
+    }
+
+    fn complement_inputs(&self) -> Option<Self> {
+        let name = match (self.name.get_name(), self.inputs.len() % 2) {
+            ("AND", _) => "NOR",
+            ("NAND", _) => "OR",
+            ("OR", _) => "NAND",
+            ("NOR", _) => "AND",
+            ("XOR", 0) | ("XNOR", 1) => "XOR",
+            ("XOR", 1) | ("XNOR", 0) => "XNOR",
+            ("BUF", _) => "INV",
+            ("INV" | "NOT", _) => "BUF",
+            _ => return None,
+        };
+        let mut gate = self.clone();
+        gate.set_gate_name(name.into());
+        Some(gate)
+    }
+}
+
+/// Splits `items` into `n` groups whose sizes differ by at most one
+pub(super) fn balanced_groups<T>(mut items: Vec<T>, n: usize) -> Vec<Vec<T>> {
+    let mut groups = Vec::with_capacity(n);
+    for i in 0..n {
+        let size = items.len().div_ceil(n - i);
+        groups.push(items.drain(..size).collect());
+    }
+    groups
+}
+
+/// Inserts buffer trees on the nets that drive more than `max_fanout` loads, where `buffer` creates a
+/// cell with a single input and a single output. Input ports and top-level outputs both count as loads.
+/// The loads of each net are split evenly between the buffers, which are themselves buffered until the net
+/// drives at most `max_fanout` loads, so that the tree has the least depth.
+/// Top-level outputs stay on their driver, as do the loads that are protected by a [crate::attribute::DONT_TOUCH] attribute.
+/// Nets whose driver is protected are left as they are.
+/// Returns the number of inserted buffers.
+pub fn limit_fanout<I>(
+    netlist: &Rc<Netlist<I>>,
+    max_fanout: usize,
+    buffer: impl Fn() -> I,
+) -> Result<usize, Error>
+where
+    I: Instantiable,
+{
+    debug_span!("limit_fanout", netlist = %netlist.get_name(), max_fanout);
+    if max_fanout < 2 {
+        return Err(Error::InstantiableError(format!(
+            "A fanout limit of {max_fanout} cannot be met with buffers"
+        )));
+    }
+    let cell = buffer();
+    let (inputs, outputs) = (
+        cell.get_input_ports().into_iter().count(),
+        cell.get_output_ports().into_iter().count(),
+    );
+    if inputs != 1 || outputs != 1 {
+        return Err(Error::InstantiableError(format!(
+            "Buffer {} must have a single input and output",
+            cell.get_name()
+        )));
+    }
+
+    // The loads that may be moved onto buffers, and the number of those that may not
+    let mut loads: HashMap<DrivenNet<I>, Vec<InputPort<I>>> = HashMap::new();
+    let mut fixed: HashMap<DrivenNet<I>, usize> = HashMap::new();
+    let mut order = Vec::new();
+    for node in netlist.objects() {
+        let protected = netlist.is_protected(&node.netref.borrow());
+        for input in node.inputs() {
+            let Some(driver) = input.get_driver() else {
+                continue;
+            };
+            if protected {
+                *fixed.entry(driver).or_default() += 1;
+                continue;
+            }
+            if !loads.contains_key(&driver) {
+                order.push(driver.clone());
+            }
+            loads.entry(driver).or_default().push(input);
+        }
+    }
+    for (operand, _) in netlist.outputs.borrow().iter() {
+        if let Some(operand) = operand {
+            let driver = netlist.driven_net(operand);
+            *fixed.entry(driver).or_default() += 1;
+        }
+    }
+
+    let mut inserted = 0;
+    for driver in order {
+        let mut level = loads.remove(&driver).unwrap();
+        // The driver keeps at least one load of its own
+        let budget = max_fanout
+            .saturating_sub(fixed.get(&driver).copied().unwrap_or(0))
+            .max(1);
+        if level.len() <= budget || netlist.is_protected(&driver.netref.netref.borrow()) {
+            continue;
+        }
+        while level.len() > budget {
+            let n = level.len().div_ceil(max_fanout);
+            let mut next = Vec::with_capacity(n);
+            for group in balanced_groups(level, n) {
+                let name = driver.as_net().get_identifier() + &format_id!("fo{}", inserted);
+                inserted += 1;
+                let buf = netlist.insert_gate_disconnected(buffer(), name);
+                for sink in group {
+                    sink.reconnect(buf.get_output(0));
+                }
+                next.push(buf.get_input(0));
+            }
+            level = next;
+        }
+        for sink in level {
+            sink.reconnect(driver.clone());
+        }
+    }
+    trace_event!(debug, inserted, "buffers inserted");
+    Ok(inserted)
+}
+
+/// Replicates the sequential cells that drive more than `max_fanout` loads, so that each copy drives at most
+/// `max_fanout` of them. Input ports and top-level outputs both count as loads.
+/// Every copy is a clone of the cell, with its parameters like `INIT`, its attributes and the drivers of all its
+/// inputs, clock and reset included, so the copies hold the same state on every cycle.
+/// The original cell keeps the top-level outputs, the loads that are protected by a [crate::attribute::DONT_TOUCH]
+/// attribute and the first loads up to the limit. The other loads are split evenly between copies named like `ff_rep0`.
+/// Latches, multi-output cells and protected cells are left as they are.
+/// Returns the number of inserted copies.
+pub fn replicate_registers<I>(netlist: &Rc<Netlist<I>>, max_fanout: usize) -> Result<usize, Error>
+where
+    I: Instantiable,
+{
+    debug_span!("replicate_registers", netlist = %netlist.get_name(), max_fanout);
+    if max_fanout == 0 {
+        return Err(Error::InstantiableError(
+            "A fanout limit of 0 cannot be met with copies".to_string(),
+        ));
+    }
+
+    let registers: Vec<NetRef<I>> = netlist
+        .seq_cells()
+        .filter(|node| {
+            !node.get_instance_type().unwrap().is_latch()
+                && !node.is_multi_output()
+                && !netlist.is_protected(&node.netref.borrow())
+        })
+        .collect();
+    let mut inserted = 0;
+    for node in registers {
+        let driver = node.get_output(0);
+        let (fixed, mut loads): (Vec<_>, Vec<_>) = netlist
+            .get_uses(&driver)
+            .into_iter()
+            .partition(|load| netlist.is_protected(&load.netref.netref.borrow()));
+        let outputs = netlist
+            .outputs
+            .borrow()
+            .iter()
+            .filter(|(operand, _)| operand.as_ref() == Some(&driver.get_operand()))
+            .count();
+        // The original cell keeps at least one load of its own
+        let budget = max_fanout
+            .saturating_sub(fixed.len() + outputs)
+            .max(1)
+            .min(loads.len());
+        let rest = loads.split_off(budget);
+        if rest.is_empty() {
+            continue;
+        }
+        let name = node.get_instance_name().expect("Instance has a name");
+        let n = rest.len().div_ceil(max_fanout);
+        for (k, group) in balanced_groups(rest, n).into_iter().enumerate() {
+            let cell = node.get_instance_type().unwrap().clone();
+            let copy = netlist.insert_gate_disconnected(cell, &name + &format_id!("rep{}", k));
+            copy.netref.borrow_mut().attributes = node.netref.borrow().attributes.clone();
+            netlist.record_copy(node.get_id(), copy.get_id());
+            for (input, copy_input) in node.inputs().zip(copy.inputs()) {
+                if let Some(d) = input.get_driver() {
+                    copy_input.connect(d);
+                }
+            }
+            for load in group {
+                load.reconnect(copy.get_output(0));
+            }
+            inserted += 1;
+        }
+    }
+    trace_event!(debug, inserted, "registers replicated");
+    Ok(inserted)
+}
+
+/// Returns the loads of every net that drives an input port
+fn loads_by_driver<I>(netlist: &Netlist<I>) -> HashMap<DrivenNet<I>, Vec<InputPort<I>>>
+where
+    I: Instantiable,
+{
+    let mut loads: HashMap<DrivenNet<I>, Vec<InputPort<I>>> = HashMap::new();
+    for input in netlist
+        .objects()
+        .flat_map(|o| o.inputs().collect::<Vec<_>>())
+    {
+        if let Some(driver) = input.get_driver() {
+            loads.entry(driver).or_default().push(input);
+        }
+    }
+    loads
+}
+
+/// Returns the input of `net`, if it is driven by an inverter that may be edited
+fn inverted<I>(netlist: &Netlist<I>, net: &DrivenNet<I>) -> Option<DrivenNet<I>>
+where
+    I: Polarity,
+{
+    let node = &net.netref;
+    let inverter = node.get_instance_type().is_some_and(|i| i.is_inverter());
+    if !inverter || netlist.is_protected(&node.netref.borrow()) {
+        return None;
+    }
+    node.get_input(0).get_driver()
+}
+
+/// Moves `loads` and the top-level outputs driven by `from` onto `to`, except the loads of cells protected
+/// by a [crate::attribute::DONT_TOUCH] attribute. Returns `true` if every load moved.
+pub(super) fn move_loads<I>(
+    netlist: &Netlist<I>,
+    from: &DrivenNet<I>,
+    to: &DrivenNet<I>,
+    loads: &[InputPort<I>],
+) -> bool
+where
+    I: Instantiable,
+{
+    let mut moved = true;
+    for load in loads {
+        if netlist.is_protected(&load.netref.netref.borrow()) {
+            moved = false;
+        } else {
+            load.reconnect(to.clone());
+        }
+    }
+    let (from, to) = (from.get_operand(), to.get_operand());
+    for (operand, _) in netlist.outputs.borrow_mut().iter_mut() {
+        if operand.as_ref() == Some(&from) {
+            *operand = Some(to.clone());
+        }
+    }
+    moved
+}
+
+/// Pushes inverters through the cells of the netlist until none of these rewrites apply:
+/// - The loads of back-to-back inverters are moved onto the net before them.
+/// - A cell whose loads are all inverters is complemented and drives the loads of the inverters instead.
+/// - A cell whose inputs are all driven by inverters, that drive nothing else, is replaced by its De Morgan dual
+///   on the inputs of the inverters.
+///
+/// The inverters that are left without loads are removed. Cells protected by a [crate::attribute::DONT_TOUCH] attribute are left as they are,
+/// and so are their inputs: no rewrite moves a protected load.
+/// Returns the number of rewrites.
+pub fn push_inverters<I>(netlist: &Rc<Netlist<I>>) -> Result<usize, Error>
+where
+    I: Polarity,
+{
+    debug_span!("push_inverters", netlist = %netlist.get_name());
+    let index = |node: &NetRef<I>| node.netref.borrow().index;
+    let mut rewrites = 0;
+    // The inverters that may be left without loads
+    let mut bypassed = HashSet::new();
+    loop {
+        let loads = loads_by_driver(netlist);
+        let none = Vec::new();
+        let loads_of = |net: &DrivenNet<I>| loads.get(net).unwrap_or(&none);
+        let any_protected = |loads: &[InputPort<I>]| {
+            loads
+                .iter()
+                .any(|l| netlist.is_protected(&l.netref.netref.borrow()))
+        };
+        // The nodes whose neighbourhood was rewritten in this round, so that `loads` may be stale
+        let mut dirty = HashSet::new();
+        let round = rewrites;
+
+        for node in netlist.objects() {
+            let Some(cell) = node.get_instance_type().map(|c| c.clone()) else {
+                continue;
+            };
+            if node.is_multi_output() || netlist.is_protected(&node.netref.borrow()) {
+                continue;
+            }
+            let Some(drivers) = node
+                .inputs()
+                .map(|i| i.get_driver())
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            let out = node.get_output(0);
+            let out_loads = loads_of(&out);
+            let mut involved: Vec<NetRef<I>> = vec![node.clone()];
+            involved.extend(drivers.iter().map(|d| d.netref.clone()));
+            involved.extend(out_loads.iter().map(|l| l.netref.clone()));
+
+            if cell.is_inverter() {
+                let Some(source) = inverted(netlist, &drivers[0]) else {
+                    continue;
+                };
+                if (out_loads.is_empty() && !out.is_top_level_output()) || any_protected(out_loads)
+                {
+                    continue;
+                }
+                involved.push(source.netref.clone());
+                if involved.iter().any(|n| dirty.contains(&index(n))) {
+                    continue;
+                }
+                move_loads(netlist, &out, &source, out_loads);
+                bypassed.extend([node.get_id(), drivers[0].netref.get_id()]);
+            } else if let Some(complement) = cell.complement_output()
+                && !out_loads.is_empty()
+                && !out.is_top_level_output()
+                && out_loads
+                    .iter()
+                    .all(|l| inverted(netlist, &l.netref.get_output(0)).is_some())
+                && !any_protected(out_loads)
+                && !out_loads
+                    .iter()
+                    .any(|l| any_protected(loads_of(&l.netref.get_output(0))))
+            {
+                let inverters: Vec<_> = out_loads.iter().map(|l| l.netref.get_output(0)).collect();
+                involved.extend(
+                    inverters
+                        .iter()
+                        .flat_map(loads_of)
+                        .map(|l| l.netref.clone()),
+                );
+                if involved.iter().any(|n| dirty.contains(&index(n))) {
+                    continue;
+                }
+                for inverter in &inverters {
+                    move_loads(netlist, inverter, &out, loads_of(inverter));
+                    bypassed.insert(inverter.netref.get_id());
+                }
+                *node.get_instance_type_mut().unwrap() = complement;
+            } else if let Some(complement) = cell.complement_inputs()
+                && !drivers.is_empty()
+                && let Some(sources) = drivers
+                    .iter()
+                    .map(|d| inverted(netlist, d))
+                    .collect::<Option<Vec<_>>>()
+                && drivers.iter().all(|d| {
+                    !d.is_top_level_output() && loads_of(d).iter().all(|l| l.netref == node)
+                })
+            {
+                involved.extend(sources.iter().map(|s| s.netref.clone()));
+                if involved.iter().any(|n| dirty.contains(&index(n))) {
+                    continue;
+                }
+                for (input, source) in node.inputs().zip(sources) {
+                    input.reconnect(source);
+                }
+                bypassed.extend(drivers.iter().map(|d| d.netref.get_id()));
+                *node.get_instance_type_mut().unwrap() = complement;
+            } else {
+                continue;
+            }
+
+            rewrites += 1;
+            dirty.extend(involved.iter().map(index));
+        }
+
+        if rewrites == round {
+            break;
+        }
+    }
+
+    // Only the inverters bypassed by this pass are removed, which may leave the inverters before them without loads
+    loop {
+        let loads = loads_by_driver(netlist);
+        let dead: HashSet<usize> = netlist
+            .objects()
+            .filter(|n| {
+                bypassed.contains(&n.get_id())
+                    && n.outputs()
+                        .all(|o| !loads.contains_key(&o) && !o.is_top_level_output())
+            })
+            .map(|n| index(&n))
+            .collect();
+        drop(loads);
+        if dead.is_empty() {
+            break;
+        }
+        netlist.remove_objects(&dead)?;
+    }
+    trace_event!(debug, rewrites, "inverters pushed");
+    Ok(rewrites)
+}
+
+/// Folds the cells of the netlist whose output is constant, given the constant cells and [Instantiable::fold].
+/// The loads and top-level outputs of a folded cell are moved onto a constant cell of its value, which is inserted
+/// if the netlist has none, and then the folded cell is removed. Its drivers may be left without loads, for [Netlist::clean].
+/// Sequential cells, black boxes, multi-output cells and the cells protected by a [crate::attribute::DONT_TOUCH]
+/// attribute are not folded, and a folded cell with protected loads keeps driving them. Returns the number of folded cells, or an error if the netlist has combinational cycles.
+pub fn propagate_constants<I>(netlist: &Rc<Netlist<I>>) -> Result<usize, Error>
+where
+    I: Instantiable,
+{
+    debug_span!("propagate_constants", netlist = %netlist.get_name());
+    let order = netlist.get_analysis::<TopoOrder<I>>()?.get_order().to_vec();
+    let mut values: HashMap<DrivenNet<I>, Logic> = HashMap::new();
+    let mut folded: Vec<(DrivenNet<I>, Logic)> = Vec::new();
+    for node in order {
+        let Some(cell) = node.get_instance_type().map(|c| c.clone()) else {
+            continue;
+        };
+        if let Some(value) = cell.get_constant() {
+            values.insert(node.get_output(0), value);
+            continue;
+        }
+        if cell.is_seq()
+            || cell.is_blackbox()
+            || cell.is_latch()
+            || node.is_multi_output()
+            || netlist.is_protected(&node.netref.borrow())
+        {
+            continue;
+        }
+        let inputs: Vec<Option<Logic>> = node
+            .inputs()
+            .map(|i| i.get_driver().and_then(|d| values.get(&d).copied()))
+            .collect();
+        if inputs.iter().all(Option::is_none) && !inputs.is_empty() {
+            continue;
+        }
+        if let Some(value) = cell.fold(&inputs) {
+            values.insert(node.get_output(0), value);
+            folded.push((node.get_output(0), value));
+        }
+    }
+
+    drop(values);
+
+    let loads = loads_by_driver(netlist);
+    let none = Vec::new();
+    let mut dead = HashSet::new();
+    for (out, value) in &folded {
+        let constant = netlist.constant_driver(*value)?;
+        // A cell that still drives protected loads stays in place
+        if move_loads(netlist, out, &constant, loads.get(out).unwrap_or(&none)) {
+            dead.insert(out.netref.netref.borrow().index);
+        }
+    }
+    drop((loads, folded));
+    netlist.remove_objects(&dead)?;
+    trace_event!(debug, folded = dead.len(), "constants propagated");
+    Ok(dead.len())
+}
+
+/// Options for [fraig_with]
+#[derive(Debug, Clone)]
+pub struct FraigOptions {
+    /// The number of random patterns of the simulation signatures, which group the candidates for merging
+    pub patterns: usize,
+    /// The seed of the random patterns
+    pub seed: u64,
+    /// The largest number of free variables for which two nets are compared exhaustively rather than with SAT
+    pub exhaustive_inputs: usize,
+    /// The number of conflicts after which the SAT solver gives up on two nets, which are then not merged
+    pub conflicts: usize,
+}
+
+impl Default for FraigOptions {
+    fn default() -> Self {
+        Self {
+            patterns: 256,
+            seed: 0,
+            exhaustive_inputs: 12,
+            conflicts: 1000,
+        }
+    }
+}
+
+/// Builds the truth tables of signals over all the assignments of their free variables, 64 per word
+struct TruthTables {
+    words: usize,
+    inputs: usize,
+}
+
+impl NetworkBuilder for TruthTables {
+    type Signal = Vec<u64>;
+
+    fn constant(&mut self, value: bool) -> Vec<u64> {
+        vec![if value { u64::MAX } else { 0 }; self.words]
+    }
+
+    fn create_pi(&mut self, _name: &Identifier) -> Vec<u64> {
+        let var = self.inputs;
+        self.inputs += 1;
+        (0..self.words)
+            .map(|w| match PROJECTIONS.get(var) {
+                Some(projection) => *projection,
+                None if (w >> (var - PROJECTIONS.len())) & 1 == 1 => u64::MAX,
+                None => 0,
+            })
+            .collect()
+    }
+
+    fn create_po(&mut self, _signal: Vec<u64>, _name: &Identifier) {}
+
+    fn create_not(&mut self, a: &Vec<u64>) -> Vec<u64> {
+        a.iter().map(|w| !w).collect()
+    }
+
+    fn create_and(&mut self, a: &Vec<u64>, b: &Vec<u64>) -> Vec<u64> {
+        a.iter().zip(b).map(|(a, b)| a & b).collect()
+    }
+}
+
+/// Returns `true` if [fraig] treats the outputs of `node` as free variables rather than as functions of its inputs:
+/// for inputs, sequential cells, black boxes, unconnected inputs, cells with more inputs than [cell_outputs]
+/// supports, and cells with an output that is neither 0 nor 1 for some inputs, like unknown or tri-state cells
+fn is_free<I: Evaluate>(node: &NetRef<I>) -> bool {
+    let Some(cell) = node.get_instance_type().map(|c| c.clone()) else {
+        return true;
+    };
+    let inputs = node.inputs().count();
+    if cell.is_seq()
+        || cell.is_blackbox()
+        || cell.is_latch()
+        || inputs > PROJECTIONS.len()
+        || node.inputs().any(|i| i.get_driver().is_none())
+    {
+        return true;
+    }
+    (0..1usize << inputs).any(|m| {
+        let values: Vec<Logic> = (0..inputs)
+            .map(|i| Logic::from_bool((m >> i) & 1 == 1))
+            .collect();
+        cell.eval(&values)
+            .iter()
+            .any(|v| !matches!(v, Logic::True | Logic::False))
+    })
+}
+
+/// The cones of logic that [fraig] compares
+struct Cones<I: Evaluate> {
+    /// The position of every circuit node in topological order
+    position: HashMap<NetRef<I>, usize>,
+    /// The nodes whose outputs are free variables, see [is_free]
+    free: HashSet<NetRef<I>>,
+}
+
+impl<I> Cones<I>
+where
+    I: Evaluate,
+{
+    fn new(order: &[NetRef<I>]) -> Self {
+        Self {
+            position: order
+                .iter()
+                .enumerate()
+                .map(|(i, n)| (n.clone(), i))
+                .collect(),
+            free: order.iter().filter(|n| is_free(n)).cloned().collect(),
+        }
+    }
+
+    /// Returns the transitive fanin of `roots` up to the free nodes, in topological order
+    fn cone(&self, roots: &[&DrivenNet<I>]) -> Vec<NetRef<I>> {
+        let mut seen = HashSet::new();
+        let mut stack: Vec<NetRef<I>> = roots.iter().map(|r| r.netref.clone()).collect();
+        let mut nodes = Vec::new();
+        while let Some(node) = stack.pop() {
+            if !seen.insert(node.clone()) {
+                continue;
+            }
+            if !self.free.contains(&node) {
+                stack.extend(
+                    node.inputs()
+                        .filter_map(|i| i.get_driver())
+                        .map(|d| d.netref),
+                );
+            }
+            nodes.push(node);
+        }
+        nodes.sort_by_key(|n| self.position[n]);
+        nodes
+    }
+
+    /// Builds the functions of the outputs of the `nodes` of a cone in `builder`, with the free nodes as inputs
+    fn build<B: NetworkBuilder>(
+        &self,
+        builder: &mut B,
+        nodes: &[NetRef<I>],
+    ) -> Result<HashMap<DrivenNet<I>, B::Signal>, Error> {
+        let mut signals = HashMap::new();
+        for node in nodes {
+            if self.free.contains(node) {
+                for net in node.outputs() {
+                    let signal = builder.create_pi(&net.get_identifier());
+                    signals.insert(net, signal);
+                }
+                continue;
+            }
+            for (pos, signal) in cell_outputs(builder, node, &signals)?
+                .into_iter()
+                .enumerate()
+            {
+                signals.insert(node.get_output(pos), signal);
+            }
+        }
+        Ok(signals)
+    }
+
+    /// Returns `true` if `a` and `b` are proven equivalent: exhaustively if their cones have at most
+    /// [FraigOptions::exhaustive_inputs] free variables, or else with SAT on the miter of their cones
+    fn prove_equivalent(
+        &self,
+        a: &DrivenNet<I>,
+        b: &DrivenNet<I>,
+        options: &FraigOptions,
+    ) -> Result<bool, Error> {
+        let nodes = self.cone(&[a, b]);
+        let free: usize = nodes
+            .iter()
+            .filter(|n| self.free.contains(n))
+            .map(|n| n.outputs().count())
+            .sum();
+        if free <= options.exhaustive_inputs {
+            let mut tables = TruthTables {
+                words: 1 << free.saturating_sub(PROJECTIONS.len()),
+                inputs: 0,
+            };
+            let signals = self.build(&mut tables, &nodes)?;
+            return Ok(signals[a] == signals[b]);
+        }
+        let mut cnf = Cnf::new();
+        let signals = self.build(&mut cnf, &nodes)?;
+        let miter = cnf.create_xor(&signals[a], &signals[b]);
+        Ok(cnf.solve(&[miter], Some(options.conflicts)) == SatResult::Unsat)
+    }
+}
+
+/// Merges the nets that compute the same function, see [fraig_with]
+pub fn fraig<I>(netlist: &Rc<Netlist<I>>) -> Result<usize, Error>
+where
+    I: Evaluate,
+{
+    fraig_with(netlist, &FraigOptions::default())
+}
+
+/// Merges the nets of the netlist that compute the same function of the inputs, registers and black boxes,
+/// even when their logic differs: the nets are grouped by their [Netlist::signatures] under random patterns, and
+/// each net of a group is compared with the earlier nets of its group in topological order, exhaustively when
+/// their cones have few free variables and with SAT otherwise. The loads and top-level outputs of a proven net are
+/// moved onto the earlier net, and then its cell is removed. Its drivers may be left without loads, for [Netlist::clean].
+///
+/// Only single-output combinational cells whose function is known are merged, and never those protected by a
+/// [crate::attribute::DONT_TOUCH] attribute. The loads of protected cells are not moved either, and a merged
+/// cell that drives some of them stays in place. Nets that are equivalent up to complement are not merged, since the
+/// cell library may have no inverter, and neither are nets for which the SAT solver reaches its conflict limit.
+/// Returns the number of merged nets, or an error if the netlist has combinational cycles.
+pub fn fraig_with<I>(netlist: &Rc<Netlist<I>>, options: &FraigOptions) -> Result<usize, Error>
+where
+    I: Evaluate,
+{
+    debug_span!("fraig", netlist = %netlist.get_name());
+    let order = netlist.get_analysis::<TopoOrder<I>>()?.get_order().to_vec();
+    let cones = Cones::new(&order);
+    let mut signatures = netlist.signatures(options.patterns, options.seed)?;
+    let loads = loads_by_driver(netlist);
+    let none = Vec::new();
+    let mut classes: HashMap<BitVec, Vec<DrivenNet<I>>> = HashMap::new();
+    let mut dead = HashSet::new();
+    let mut merges = 0;
+    for node in &order {
+        if node.outputs().count() != 1 {
+            continue;
+        }
+        let net = node.get_output(0);
+        let Some(signature) = signatures.remove(&net) else {
+            continue;
+        };
+        let class = classes.entry(signature).or_default();
+        let mergeable = !cones.free.contains(node)
+            && node
+                .get_instance_type()
+                .is_some_and(|c| c.get_constant().is_none())
+            && !netlist.is_protected(&node.netref.borrow());
+        if mergeable {
+            let mut merged = false;
+            for earlier in class.iter() {
+                if cones.prove_equivalent(earlier, &net, options)? {
+                    // A cell that still drives protected loads stays in place
+                    if move_loads(netlist, &net, earlier, loads.get(&net).unwrap_or(&none)) {
+                        dead.insert(node.netref.borrow().index);
+                    }
+                    merges += 1;
+                    merged = true;
+                    break;
+                }
+            }
+            if merged {
+                continue;
+            }
+        }
+        class.push(net);
+    }
+
+    drop((loads, classes, signatures, cones, order));
+    netlist.remove_objects(&dead)?;
+    trace_event!(
+        debug,
+        merged = merges,
+        "functionally equivalent nets merged"
+    );
+    Ok(merges)
+}
+
+/// Options for [remove_redundancies_with]
+#[derive(Debug, Clone)]
+pub struct RedundancyOptions {
+    /// The number of random patterns of the fault simulation, which discards the faults that are detected
+    pub patterns: usize,
+    /// The seed of the random patterns
+    pub seed: u64,
+    /// The number of conflicts after which the SAT solver gives up on a fault, which is then kept
+    pub conflicts: usize,
+}
+
+impl Default for RedundancyOptions {
+    fn default() -> Self {
+        Self {
+            patterns: 256,
+            seed: 0,
+            conflicts: 1000,
+        }
+    }
+}
+
+impl<I> Cones<I>
+where
+    I: Evaluate,
+{
+    /// Returns `true` if `fault` is proven undetectable: no assignment of the free variables makes the netlist with
+    /// the fault differ from the netlist without it, on a top-level output or an input of a free node.
+    /// This is the case when the values the fault changes are observability don't cares.
+    fn prove_redundant(
+        &self,
+        netlist: &Netlist<I>,
+        fault: &StuckAt,
+        loads: &HashMap<DrivenNet<I>, Vec<InputPort<I>>>,
+        conflicts: usize,
+    ) -> Result<bool, Error> {
+        // The node whose outputs the fault changes, and the net it drives or the pin it forces
+        let (node, pin) = match fault.site {
+            FaultSite::Net(id) => match netlist.find_net_by_id(id) {
+                Some(net) => (net.netref.clone(), None),
+                None => return Ok(false),
+            },
+            FaultSite::Pin(id, pos) => match netlist.find_object(id) {
+                Some(node) => (node, Some(pos)),
+                None => return Ok(false),
+            },
+        };
+
+        // The transitive fanout of the node up to the free nodes, and the nets observed there
+        let outputs: HashSet<DrivenNet<I>> =
+            netlist.outputs().into_iter().map(|(d, _)| d).collect();
+        let none = Vec::new();
+        let mut fanout = vec![node.clone()];
+        let mut seen = HashSet::from([node.clone()]);
+        let mut observed = HashSet::new();
+        let mut next = 0;
+        while next < fanout.len() {
+            let current = fanout[next].clone();
+            next += 1;
+            for net in current.outputs() {
+                if outputs.contains(&net) {
+                    observed.insert(net.clone());
+                }
+                for load in loads.get(&net).unwrap_or(&none) {
+                    let load = load.netref.clone();
+                    if self.free.contains(&load) {
+                        observed.insert(net.clone());
+                    } else if seen.insert(load.clone()) {
+                        fanout.push(load);
+                    }
+                }
+            }
+        }
+        if observed.is_empty() {
+            return Ok(true);
+        }
+        fanout.sort_by_key(|n| self.position[n]);
+
+        let mut cnf = Cnf::new();
+        let roots: Vec<DrivenNet<I>> = observed
+            .iter()
+            .cloned()
+            .chain(fanout.iter().flat_map(|n| {
+                n.inputs()
+                    .filter_map(|i| i.get_driver())
+                    .collect::<Vec<_>>()
+            }))
+            .collect();
+        let good = self.build(&mut cnf, &self.cone(&roots.iter().collect::<Vec<_>>()))?;
+
+        // The copy of the fanout with the fault
+        let mut faulty: HashMap<DrivenNet<I>, Lit> = HashMap::new();
+        let forced = cnf.constant(fault.value);
+        if pin.is_none() {
+            for net in node.outputs() {
+                faulty.insert(net, forced);
+            }
+        }
+        for current in &fanout {
+            if pin.is_none() && current == &node {
+                continue;
+            }
+            let mut signals = HashMap::new();
+            for (pos, input) in current.inputs().enumerate() {
+                let Some(driver) = input.get_driver() else {
+                    continue;
+                };
+                let signal = match faulty.get(&driver) {
+                    _ if current == &node && pin == Some(pos) => forced,
+                    Some(signal) => *signal,
+                    None => good[&driver],
+                };
+                // A pin cannot be forced apart from the other pins of the node on the same net
+                if signals.insert(driver, signal).is_some_and(|s| s != signal) {
+                    return Ok(false);
+                }
+            }
+            for (pos, signal) in cell_outputs(&mut cnf, current, &signals)?
+                .into_iter()
+                .enumerate()
+            {
+                faulty.insert(current.get_output(pos), signal);
+            }
+        }
+
+        let differences: Vec<Lit> = observed
+            .iter()
+            .map(|net| cnf.create_xor(&good[net], &faulty[net]))
+            .collect();
+        cnf.add_clause(&differences);
+        Ok(cnf.solve(&[], Some(conflicts)) == SatResult::Unsat)
+    }
+}
+
+/// Removes redundant connections and cells, see [remove_redundancies_with]
+pub fn remove_redundancies<I>(netlist: &Rc<Netlist<I>>) -> Result<usize, Error>
+where
+    I: Evaluate,
+{
+    remove_redundancies_with(netlist, &RedundancyOptions::default())
+}
+
+/// Removes the connections and cells whose stuck-at faults are undetectable, because the values they carry are
+/// observability don't cares: the outputs of a cell and the input pins of cells are candidates for stuck-at-0 and
+/// stuck-at-1 faults, the faults that [fault_simulate] detects under random patterns are discarded, and the others
+/// are proven undetectable with SAT on a miter of the netlist with and without the fault. A fault is observed on the
+/// top-level outputs and the inputs of sequential cells and black boxes, like in [fault_simulate].
+///
+/// The faults are proven one at a time on the netlist as edited so far, since removing a redundancy can make
+/// another one necessary. A redundant input pin is connected to a constant cell of the faulty value, and the loads
+/// and top-level outputs of a redundant cell are moved onto one, so the cell is left without loads for
+/// [Netlist::clean]. The constants are left for [propagate_constants] and other passes to simplify.
+/// Only single-output combinational cells whose function is known are edited, and never those protected by a
+/// [crate::attribute::DONT_TOUCH] attribute, whose inputs keep their drivers. Faults for which the SAT solver reaches its conflict limit are kept.
+/// Returns the number of removed redundancies, or an error if the netlist has combinational cycles.
+pub fn remove_redundancies_with<I>(
+    netlist: &Rc<Netlist<I>>,
+    options: &RedundancyOptions,
+) -> Result<usize, Error>
+where
+    I: Evaluate,
+{
+    debug_span!("remove_redundancies", netlist = %netlist.get_name());
+    let order = netlist.get_analysis::<TopoOrder<I>>()?.get_order().to_vec();
+    let cones = Cones::new(&order);
+    let mut faults = Vec::new();
+    for node in &order {
+        let editable = !cones.free.contains(node)
+            && node.outputs().count() == 1
+            && node
+                .get_instance_type()
+                .is_some_and(|c| c.get_constant().is_none())
+            && !netlist.is_protected(&node.netref.borrow());
+        if !editable {
+            continue;
+        }
+        let both = |site| [false, true].map(|value| StuckAt { site, value });
+        faults.extend(both(FaultSite::Net(node.get_output(0).get_id())));
+        for (pos, input) in node.inputs().enumerate() {
+            let constant = input.get_driver().is_some_and(|d| {
+                d.netref
+                    .get_instance_type()
+                    .is_some_and(|c| c.get_constant().is_some())
+            });
+            if !constant {
+                faults.extend(both(FaultSite::Pin(node.get_id(), pos)));
+            }
+        }
+    }
+    drop((cones, order));
+    let coverage = fault_simulate(netlist, &faults, options.patterns, |net, word| {
+        random_word(&net.as_net(), options.seed, word)
+    })?;
+
+    let none = Vec::new();
+    let mut removed = 0;
+    let mut cones: Option<Cones<I>> = None;
+    for fault in coverage.undetected {
+        if cones.is_none() {
+            let order = netlist.get_analysis::<TopoOrder<I>>()?.get_order().to_vec();
+            cones = Some(Cones::new(&order));
+        }
+        let loads = loads_by_driver(netlist);
+        let proven =
+            cones
+                .as_ref()
+                .unwrap()
+                .prove_redundant(netlist, &fault, &loads, options.conflicts)?;
+        if !proven {
+            continue;
+        }
+        let value = Logic::from_bool(fault.value);
+        let outputs: HashSet<DrivenNet<I>> =
+            netlist.outputs().into_iter().map(|(d, _)| d).collect();
+        let live = |net: &DrivenNet<I>| loads.contains_key(net) || outputs.contains(net);
+        match fault.site {
+            FaultSite::Net(id) => {
+                let net = netlist
+                    .find_net_by_id(id)
+                    .expect("Proven faults are in the netlist");
+                // The loads of protected cells keep the net, so the fault is removed only if another load moves
+                let net_loads = loads.get(&net).unwrap_or(&none);
+                let movable = net_loads
+                    .iter()
+                    .any(|l| !netlist.is_protected(&l.netref.netref.borrow()));
+                if !movable && !outputs.contains(&net) {
+                    continue;
+                }
+                let constant = netlist.constant_driver(value)?;
+                move_loads(netlist, &net, &constant, net_loads);
+            }
+            FaultSite::Pin(id, pos) => {
+                let node = netlist
+                    .find_object(id)
+                    .expect("Proven faults are in the netlist");
+                let input = node.get_input(pos);
+                let tied = input.get_driver().is_some_and(|d| {
+                    d.netref
+                        .get_instance_type()
+                        .is_some_and(|c| c.get_constant() == Some(value))
+                });
+                if tied || !node.outputs().any(|o| live(&o)) {
+                    continue;
+                }
+                input.reconnect(netlist.constant_driver(value)?);
+            }
+        }
+        removed += 1;
+        cones = None;
+    }
+    trace_event!(debug, removed, "redundancies removed");
+    Ok(removed)
+}
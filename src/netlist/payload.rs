@@ -0,0 +1,139 @@
+/*!
+
+  User data stored directly on circuit nodes and nets.
+
+  Each circuit node has one payload slot for itself and one for each of its output nets, which hold a value of any
+  type, like a placement or a mark. Looking up a payload only borrows the node, unlike the side tables of
+  [super::annotation] that hash the stable identifier of the node.
+  Payloads belong to the node: they are dropped when it is removed, and are neither copied nor serialized.
+
+*/
+
+use super::{DrivenNet, NetRef};
+use crate::circuit::Instantiable;
+use std::{
+    any::Any,
+    cell::{Ref, RefMut},
+};
+
+/// The payload slots of a circuit node
+#[derive(Debug, Default)]
+pub(super) struct Payloads {
+    /// The payload of the circuit node
+    object: Option<Box<dyn Any>>,
+    /// The payload of each output net, up to the last one that was set
+    nets: Vec<Option<Box<dyn Any>>>,
+}
+
+impl Payloads {
+    /// Returns the slot of the circuit node, or of its output `net`
+    fn slot(&self, net: Option<usize>) -> Option<&dyn Any> {
+        match net {
+            None => self.object.as_deref(),
+            Some(pos) => self.nets.get(pos)?.as_deref(),
+        }
+    }
+
+    /// Returns the slot of the circuit node, or of its output `net`, for mutation
+    fn slot_mut(&mut self, net: Option<usize>) -> &mut Option<Box<dyn Any>> {
+        match net {
+            None => &mut self.object,
+            Some(pos) => {
+                if self.nets.len() <= pos {
+                    self.nets.resize_with(pos + 1, || None);
+                }
+                &mut self.nets[pos]
+            }
+        }
+    }
+}
+
+impl<I> NetRef<I>
+where
+    I: Instantiable,
+{
+    /// Stores `value` as the payload of the circuit node, returning the previous payload of any type
+    pub fn set_payload<T: Any>(&self, value: T) -> Option<Box<dyn Any>> {
+        self.netref
+            .borrow_mut()
+            .payloads
+            .slot_mut(None)
+            .replace(Box::new(value))
+    }
+
+    /// Returns the payload of the circuit node, if it has one of type `T`
+    pub fn payload<T: Any>(&self) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.netref.borrow(), |o| {
+            o.payloads.slot(None)?.downcast_ref::<T>()
+        })
+        .ok()
+    }
+
+    /// Returns the payload of the circuit node for mutation, if it has one of type `T`
+    pub fn payload_mut<T: Any>(&self) -> Option<RefMut<'_, T>> {
+        RefMut::filter_map(self.netref.borrow_mut(), |o| {
+            o.payloads.slot_mut(None).as_mut()?.downcast_mut::<T>()
+        })
+        .ok()
+    }
+
+    /// Removes the payload of the circuit node and returns it, if it has one of type `T`.
+    /// A payload of another type is left in place.
+    pub fn take_payload<T: Any>(&self) -> Option<T> {
+        take(self.netref.borrow_mut().payloads.slot_mut(None))
+    }
+}
+
+impl<I> DrivenNet<I>
+where
+    I: Instantiable,
+{
+    /// Stores `value` as the payload of the net, returning the previous payload of any type
+    pub fn set_payload<T: Any>(&self, value: T) -> Option<Box<dyn Any>> {
+        self.netref
+            .netref
+            .borrow_mut()
+            .payloads
+            .slot_mut(Some(self.pos))
+            .replace(Box::new(value))
+    }
+
+    /// Returns the payload of the net, if it has one of type `T`
+    pub fn payload<T: Any>(&self) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.netref.netref.borrow(), |o| {
+            o.payloads.slot(Some(self.pos))?.downcast_ref::<T>()
+        })
+        .ok()
+    }
+
+    /// Returns the payload of the net for mutation, if it has one of type `T`
+    pub fn payload_mut<T: Any>(&self) -> Option<RefMut<'_, T>> {
+        RefMut::filter_map(self.netref.netref.borrow_mut(), |o| {
+            o.payloads
+                .slot_mut(Some(self.pos))
+                .as_mut()?
+                .downcast_mut::<T>()
+        })
+        .ok()
+    }
+
+    /// Removes the payload of the net and returns it, if it has one of type `T`.
+    /// A payload of another type is left in place.
+    pub fn take_payload<T: Any>(&self) -> Option<T> {
+        take(
+            self.netref
+                .netref
+                .borrow_mut()
+                .payloads
+                .slot_mut(Some(self.pos)),
+        )
+    }
+}
+
+/// Takes the value out of `slot` if it has type `T`
+fn take<T: Any>(slot: &mut Option<Box<dyn Any>>) -> Option<T> {
+    if !slot.as_ref()?.is::<T>() {
+        return None;
+    }
+    slot.take()?.downcast::<T>().ok().map(|b| *b)
+}
@@ -0,0 +1,253 @@
+/*!
+
+  Placements of circuit nodes, and their exchange with academic placers in the Bookshelf format.
+
+  A placement is an [AnnotationMap] of the instances and principal inputs to their positions. [Netlist::to_bookshelf]
+  writes the `.nodes`, `.nets` and `.pl` files of a design with its placement, where the principal inputs and top-level
+  outputs are fixed terminals, and [Netlist::read_bookshelf_pl] reads the placement computed by a placer back.
+
+*/
+
+use super::{
+    NetRef, Netlist,
+    annotation::{AnnotationMap, ObjectId},
+};
+use crate::{
+    circuit::{Identifier, Instantiable},
+    error::Error,
+};
+use std::{collections::HashMap, fmt::Write as _, path::Path};
+
+/// The position of a circuit node, in the units of the placer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Placement {
+    /// The x coordinate of the lower left corner
+    pub x: f64,
+    /// The y coordinate of the lower left corner
+    pub y: f64,
+    /// The site the node is placed on, like `SLICE_X3Y12`. Bookshelf has no sites, so they are not exported.
+    pub site: Option<String>,
+    /// Whether the placer must leave the node where it is
+    pub fixed: bool,
+}
+
+impl Placement {
+    /// Returns a movable placement at `(x, y)`
+    pub fn new(x: f64, y: f64) -> Self {
+        Self {
+            x,
+            y,
+            ..Default::default()
+        }
+    }
+}
+
+/// The placement of the circuit nodes of a netlist
+pub type PlacementMap = AnnotationMap<Placement>;
+
+/// Options for [Netlist::to_bookshelf]
+#[derive(Debug, Clone, Default)]
+pub struct BookshelfOptions {
+    /// The width and height of each cell, by cell name. Cells that are not listed and terminals are 1 by 1.
+    pub sizes: HashMap<String, (f64, f64)>,
+    /// The positions of the terminals of the top-level outputs, by port name. The others are at the origin.
+    pub outputs: HashMap<Identifier, (f64, f64)>,
+}
+
+/// The files of a design in the Bookshelf format
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bookshelf {
+    /// The `.nodes` file, with the size of every cell and terminal
+    pub nodes: String,
+    /// The `.nets` file, with the pins of every net
+    pub nets: String,
+    /// The `.pl` file, with the position of every cell and terminal
+    pub pl: String,
+}
+
+impl Bookshelf {
+    /// Writes the files to `dir` as `name.nodes`, `name.nets` and `name.pl`, along with a `name.aux` file listing them
+    pub fn save(&self, dir: &Path, name: &str) -> Result<(), Error> {
+        let aux = format!("RowBasedPlacement : {name}.nodes {name}.nets {name}.pl\n");
+        std::fs::write(dir.join(format!("{name}.aux")), aux)?;
+        std::fs::write(dir.join(format!("{name}.nodes")), &self.nodes)?;
+        std::fs::write(dir.join(format!("{name}.nets")), &self.nets)?;
+        std::fs::write(dir.join(format!("{name}.pl")), &self.pl)?;
+        Ok(())
+    }
+}
+
+/// Returns the name of a node in Bookshelf, where names cannot contain whitespace
+fn bookshelf_name(name: &Identifier) -> String {
+    let name = match name.get_bit_index() {
+        Some(index) => format!("{}[{index}]", name.get_name()),
+        None => name.get_name().to_string(),
+    };
+    name.replace(char::is_whitespace, "_")
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Returns the Bookshelf name of a principal input or instance
+    fn node_name(node: &NetRef<I>) -> String {
+        match node.get_instance_name() {
+            Some(name) => bookshelf_name(&name),
+            None => bookshelf_name(&node.get_identifier()),
+        }
+    }
+
+    /// Returns the handle of each principal input and instance, by Bookshelf name.
+    /// Returns [Error::NonuniqueInsts] if two nodes have the same name, like the escaped `\x[0] ` and the bit `x[0]`.
+    fn node_ids(&self) -> Result<HashMap<String, ObjectId>, Error> {
+        let mut ids = HashMap::new();
+        for node in self.objects() {
+            let name = Self::node_name(&node);
+            if ids.insert(name.clone(), node.get_id()).is_some() {
+                return Err(Error::NonuniqueInsts(vec![name.as_str().into()]));
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Returns the design in the Bookshelf format, with the positions of `placement`.
+    /// Principal inputs and top-level outputs are fixed terminals, and nodes without a placement are at the origin.
+    /// Returns [Error::NonuniqueNets] if an output port has the name of a node,
+    /// and [Error::NonuniqueInsts] if two nodes have the same name.
+    pub fn to_bookshelf(
+        &self,
+        placement: &PlacementMap,
+        options: &BookshelfOptions,
+    ) -> Result<Bookshelf, Error> {
+        let nodes: Vec<NetRef<I>> = self.objects().collect();
+        let names = self.node_ids()?;
+        let outputs = self.outputs();
+        if let Some((_, port)) = outputs
+            .iter()
+            .find(|(_, port)| names.contains_key(&bookshelf_name(port.get_identifier())))
+        {
+            return Err(Error::NonuniqueNets(vec![port.clone()]));
+        }
+        let terminals = nodes.iter().filter(|n| n.is_an_input()).count() + outputs.len();
+
+        let mut files = Bookshelf::default();
+        let w = &mut files.nodes;
+        writeln!(w, "UCLA nodes 1.0\n").unwrap();
+        writeln!(w, "NumNodes : {}", nodes.len() + outputs.len()).unwrap();
+        writeln!(w, "NumTerminals : {terminals}").unwrap();
+        for node in &nodes {
+            let name = Self::node_name(node);
+            match node.get_instance_type() {
+                Some(cell) => {
+                    let (width, height) = options
+                        .sizes
+                        .get(cell.get_name().get_name())
+                        .copied()
+                        .unwrap_or((1.0, 1.0));
+                    writeln!(w, "{name} {width} {height}").unwrap();
+                }
+                None => writeln!(w, "{name} 1 1 terminal").unwrap(),
+            }
+        }
+        for (_, port) in &outputs {
+            writeln!(w, "{} 1 1 terminal", bookshelf_name(port.get_identifier())).unwrap();
+        }
+
+        // The pins of every net with a load, driver first
+        let mut nets: Vec<(String, Vec<(String, char)>)> = Vec::new();
+        for node in &nodes {
+            for net in node.outputs() {
+                let mut pins = vec![(Self::node_name(node), 'O')];
+                pins.extend(
+                    self.get_uses(&net)
+                        .into_iter()
+                        .map(|port| (Self::node_name(&port.netref), 'I')),
+                );
+                pins.extend(
+                    outputs
+                        .iter()
+                        .filter(|(driver, _)| *driver == net)
+                        .map(|(_, port)| (bookshelf_name(port.get_identifier()), 'I')),
+                );
+                if pins.len() > 1 {
+                    nets.push((bookshelf_name(&net.get_identifier()), pins));
+                }
+            }
+        }
+        let w = &mut files.nets;
+        writeln!(w, "UCLA nets 1.0\n").unwrap();
+        writeln!(w, "NumNets : {}", nets.len()).unwrap();
+        let pins: usize = nets.iter().map(|(_, pins)| pins.len()).sum();
+        writeln!(w, "NumPins : {pins}").unwrap();
+        for (name, pins) in nets {
+            writeln!(w, "NetDegree : {} {name}", pins.len()).unwrap();
+            for (node, direction) in pins {
+                writeln!(w, "\t{node} {direction} : 0 0").unwrap();
+            }
+        }
+
+        let w = &mut files.pl;
+        writeln!(w, "UCLA pl 1.0\n").unwrap();
+        for node in &nodes {
+            let name = Self::node_name(node);
+            let (x, y, fixed) = match placement.get(&node.get_id()) {
+                Some(p) => (p.x, p.y, p.fixed || node.is_an_input()),
+                None => (0.0, 0.0, node.is_an_input()),
+            };
+            let fixed = if fixed { " /FIXED" } else { "" };
+            writeln!(w, "{name} {x} {y} : N{fixed}").unwrap();
+        }
+        for (_, port) in &outputs {
+            let (x, y) = options
+                .outputs
+                .get(port.get_identifier())
+                .copied()
+                .unwrap_or_default();
+            let name = bookshelf_name(port.get_identifier());
+            writeln!(w, "{name} {x} {y} : N /FIXED").unwrap();
+        }
+        Ok(files)
+    }
+
+    /// Reads the positions of the principal inputs and instances from the `.pl` file of a Bookshelf design,
+    /// such as the result of a placer. Output terminals and unknown nodes are skipped.
+    /// Returns [Error::ParseError] for a line that is not `name x y : orientation`, optionally followed by `/FIXED`,
+    /// and [Error::NonuniqueInsts] if two nodes of the netlist have the same name.
+    pub fn read_bookshelf_pl(&self, pl: &str) -> Result<PlacementMap, Error> {
+        let ids = self.node_ids()?;
+        let mut placement = PlacementMap::new();
+        for line in pl.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() || line.starts_with("UCLA") {
+                continue;
+            }
+            let bad = || Error::ParseError(format!("Invalid Bookshelf placement `{line}`"));
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (name, x, y, rest) = match fields.as_slice() {
+                [name, x, y, ":", rest @ ..] => (*name, *x, *y, rest),
+                [name, x, y] => (*name, *x, *y, &[][..]),
+                _ => return Err(bad()),
+            };
+            let x: f64 = x.parse().map_err(|_| bad())?;
+            let y: f64 = y.parse().map_err(|_| bad())?;
+            let fixed = match rest {
+                [] | [_] => false,
+                [_, fixed] if fixed.starts_with("/FIXED") => true,
+                _ => return Err(bad()),
+            };
+            if let Some(id) = ids.get(name) {
+                placement.insert(
+                    *id,
+                    Placement {
+                        x,
+                        y,
+                        site: None,
+                        fixed,
+                    },
+                );
+            }
+        }
+        Ok(placement)
+    }
+}
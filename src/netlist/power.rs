@@ -0,0 +1,146 @@
+/*!
+
+  Dynamic and leakage power estimation from switching activity.
+
+*/
+
+use super::{
+    DrivenNet, Netlist,
+    annotation::{AnnotationMap, NetId},
+    sim::{Simulator, random_word},
+};
+use crate::{
+    circuit::{Evaluate, Instantiable},
+    error::Error,
+};
+use std::collections::BTreeMap;
+
+/// The switching activity of the nets of a netlist, as the expected number of transitions per cycle
+pub trait Activity<I: Instantiable> {
+    /// Returns the toggle rate of `net`
+    fn toggle_rate(&self, net: &DrivenNet<I>) -> f64;
+}
+
+/// Every net toggles at the same default rate
+impl<I: Instantiable> Activity<I> for f64 {
+    fn toggle_rate(&self, _net: &DrivenNet<I>) -> f64 {
+        *self
+    }
+}
+
+/// Toggle rates per net, like the ones measured by [simulated_activity]. Missing nets never toggle.
+impl<I: Instantiable> Activity<I> for AnnotationMap<f64, NetId> {
+    fn toggle_rate(&self, net: &DrivenNet<I>) -> f64 {
+        self.get(&net.get_id()).copied().unwrap_or_default()
+    }
+}
+
+impl<I, F> Activity<I> for F
+where
+    I: Instantiable,
+    F: Fn(&DrivenNet<I>) -> f64,
+{
+    fn toggle_rate(&self, net: &DrivenNet<I>) -> f64 {
+        self(net)
+    }
+}
+
+/// The power characteristics of a cell, in the units of the cell library
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CellPower {
+    /// The energy dissipated by each transition of an output of the cell, including its load
+    pub energy: f64,
+    /// The static power of the cell
+    pub leakage: f64,
+}
+
+/// The estimated power of a netlist, in the units of its [CellPower] model.
+/// Dynamic power is given per cycle, so it is scaled by the clock frequency to get a rate.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PowerReport {
+    /// The dynamic power of all the cells
+    pub dynamic: f64,
+    /// The leakage power of all the cells
+    pub leakage: f64,
+    /// The dynamic and leakage power of each cell type
+    pub cells: BTreeMap<String, f64>,
+}
+
+impl PowerReport {
+    /// Returns the sum of dynamic and leakage power
+    pub fn total(&self) -> f64 {
+        self.dynamic + self.leakage
+    }
+}
+
+impl std::fmt::Display for PowerReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "dynamic: {}", self.dynamic)?;
+        writeln!(f, "leakage: {}", self.leakage)?;
+        for (cell, power) in &self.cells {
+            writeln!(f, "  {cell}: {power}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Estimates the power of `netlist`, where `activity` gives the toggle rate of each net and `model` the power characteristics of each cell.
+/// The energy of a transition is charged to the cell driving the net, so principal inputs are free.
+pub fn estimate<I: Instantiable>(
+    netlist: &Netlist<I>,
+    activity: &impl Activity<I>,
+    model: impl Fn(&I) -> CellPower,
+) -> PowerReport {
+    let mut report = PowerReport::default();
+    for obj in netlist.objects() {
+        let Some(inst) = obj.get_instance_type() else {
+            continue;
+        };
+        let power = model(&inst);
+        let toggles: f64 = obj.outputs().map(|o| activity.toggle_rate(&o)).sum();
+        let dynamic = power.energy * toggles;
+        report.dynamic += dynamic;
+        report.leakage += power.leakage;
+        *report.cells.entry(inst.get_name().to_string()).or_default() += dynamic + power.leakage;
+    }
+    report
+}
+
+/// Measures the toggle rate of every net over a sequence of `n_patterns` pseudo-random patterns, drawn as in [Netlist::signatures].
+/// Each free variable toggles between consecutive patterns with probability one half, and so does every net that follows it.
+/// Returns an error if the netlist has combinational cycles.
+pub fn simulated_activity<I: Evaluate>(
+    netlist: &Netlist<I>,
+    n_patterns: usize,
+    seed: u64,
+) -> Result<AnnotationMap<f64, NetId>, Error> {
+    let mut sim = Simulator::new(netlist)?;
+    let nets: Vec<DrivenNet<I>> = netlist
+        .objects()
+        .flat_map(|o| o.outputs().collect::<Vec<_>>())
+        .collect();
+    let mut toggles = vec![0u64; nets.len()];
+    let mut last: Vec<Option<bool>> = vec![None; nets.len()];
+
+    for word in 0..n_patterns.div_ceil(64) {
+        sim.run(|n| random_word(&n.as_net(), seed, word));
+        let lanes = (n_patterns - word * 64).min(64);
+        let mask = u64::MAX >> (64 - lanes);
+        for (i, net) in nets.iter().enumerate() {
+            let value = sim.get_word(net) & mask;
+            // Transitions between neighbouring lanes, then across the word boundary
+            toggles[i] += u64::from(((value ^ (value >> 1)) & (mask >> 1)).count_ones());
+            if last[i].is_some_and(|l| l != (value & 1 == 1)) {
+                toggles[i] += 1;
+            }
+            last[i] = Some((value >> (lanes - 1)) & 1 == 1);
+        }
+    }
+
+    let transitions = n_patterns.saturating_sub(1).max(1) as f64;
+    Ok(nets
+        .iter()
+        .zip(toggles)
+        .map(|(net, t)| (net.get_id(), t as f64 / transitions))
+        .collect())
+}
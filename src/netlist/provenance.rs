@@ -0,0 +1,199 @@
+/*!
+
+  Provenance of circuit nodes and nets, to trace them back to the source they were created from.
+
+*/
+
+use super::{NetRef, Netlist};
+use crate::{
+    attribute::{AttributeKey, AttributeValue, Parameter},
+    circuit::{Instantiable, Net},
+};
+use std::collections::HashMap;
+
+/// Where a circuit node or net came from.
+/// Fields that are unknown are left as `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Provenance {
+    /// The source file of the original construct
+    pub file: Option<String>,
+    /// The line of the original construct in the source file
+    pub line: Option<usize>,
+    /// The name of the original construct in the RTL
+    pub rtl_name: Option<String>,
+    /// The pass that created the node or net
+    pub pass: Option<String>,
+}
+
+impl Provenance {
+    /// Creates a provenance pointing to `line` of `file`
+    pub fn new(file: impl Into<String>, line: usize) -> Self {
+        Self {
+            file: Some(file.into()),
+            line: Some(line),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the provenance with the name of the original construct in the RTL
+    pub fn with_rtl_name(mut self, rtl_name: impl Into<String>) -> Self {
+        self.rtl_name = Some(rtl_name.into());
+        self
+    }
+
+    /// Returns the provenance with the pass that created the node or net
+    pub fn with_pass(mut self, pass: impl Into<String>) -> Self {
+        self.pass = Some(pass.into());
+        self
+    }
+
+    /// Returns `true` if nothing is known about the origin
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fills in the fields that are unknown with the ones of `other`
+    pub fn inherit(&mut self, other: &Provenance) {
+        self.file = self.file.take().or_else(|| other.file.clone());
+        self.line = self.line.or(other.line);
+        self.rtl_name = self.rtl_name.take().or_else(|| other.rtl_name.clone());
+        self.pass = self.pass.take().or_else(|| other.pass.clone());
+    }
+
+    /// Returns the source location as `file:line`
+    fn location(&self) -> Option<String> {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => Some(format!("{file}:{line}")),
+            (Some(file), None) => Some(file.clone()),
+            (None, Some(line)) => Some(format!("line {line}")),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the provenance as Verilog attributes.
+    /// The location is stored under `src`, following the convention of Yosys.
+    fn attributes(&self) -> impl Iterator<Item = (AttributeKey, AttributeValue)> {
+        [
+            ("src", self.location()),
+            ("rtl_name", self.rtl_name.clone()),
+            ("pass", self.pass.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(k, v)| Some((k.to_string(), Some(Parameter::String(v?)))))
+    }
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self
+            .location()
+            .into_iter()
+            .chain(self.rtl_name.iter().map(|n| format!("rtl {n}")))
+            .chain(self.pass.iter().map(|p| format!("pass {p}")))
+            .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// How provenance is emitted in Verilog
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProvenanceStyle {
+    /// Provenance is not emitted
+    #[default]
+    Omit,
+    /// Provenance is emitted as a comment before the declaration
+    Comment,
+    /// Provenance is emitted as attributes `src`, `rtl_name` and `pass`
+    Attribute,
+}
+
+impl ProvenanceStyle {
+    /// Emits `provenance` in this style, either as a comment or by adding it to `attributes`.
+    /// Attributes that are already set take precedence.
+    pub(crate) fn annotate(
+        self,
+        f: &mut std::fmt::Formatter<'_>,
+        indent: &str,
+        provenance: Option<&Provenance>,
+        attributes: &mut HashMap<AttributeKey, AttributeValue>,
+    ) -> std::fmt::Result {
+        let Some(provenance) = provenance.filter(|p| !p.is_empty()) else {
+            return Ok(());
+        };
+        match self {
+            ProvenanceStyle::Omit => Ok(()),
+            ProvenanceStyle::Comment => writeln!(f, "{indent}// {provenance}"),
+            ProvenanceStyle::Attribute => {
+                for (k, v) in provenance.attributes() {
+                    attributes.entry(k).or_insert(v);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<I> NetRef<I>
+where
+    I: Instantiable,
+{
+    /// Returns the provenance of this circuit node
+    pub fn get_provenance(&self) -> Option<Provenance> {
+        self.netref.borrow().provenance.clone()
+    }
+
+    /// Sets the provenance of this circuit node, returning the previous one
+    pub fn set_provenance(&self, provenance: Provenance) -> Option<Provenance> {
+        self.netref.borrow_mut().provenance.replace(provenance)
+    }
+
+    /// Clears the provenance of this circuit node, returning the previous one
+    pub fn clear_provenance(&self) -> Option<Provenance> {
+        self.netref.borrow_mut().provenance.take()
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Sets the pass that is editing the netlist.
+    /// Circuit nodes inserted while a pass is set record it in their provenance.
+    pub fn set_current_pass(&self, pass: Option<String>) {
+        *self.current_pass.borrow_mut() = pass;
+    }
+
+    /// Returns the pass that is editing the netlist, if any
+    pub fn get_current_pass(&self) -> Option<String> {
+        self.current_pass.borrow().clone()
+    }
+
+    /// Returns the provenance of `net`
+    pub fn get_net_provenance(&self, net: &Net) -> Option<Provenance> {
+        self.net_provenance
+            .borrow()
+            .get(net.get_identifier())
+            .cloned()
+    }
+
+    /// Sets the provenance of `net`, returning the previous one
+    pub fn set_net_provenance(&self, net: &Net, provenance: Provenance) -> Option<Provenance> {
+        self.net_provenance
+            .borrow_mut()
+            .insert(net.get_identifier().clone(), provenance)
+    }
+
+    /// Clears the provenance of `net`, returning the previous one
+    pub fn clear_net_provenance(&self, net: &Net) -> Option<Provenance> {
+        self.net_provenance
+            .borrow_mut()
+            .remove(net.get_identifier())
+    }
+
+    /// Returns the provenance of a newly inserted circuit node
+    pub(crate) fn new_provenance(&self) -> Option<Provenance> {
+        self.get_current_pass()
+            .map(|pass| Provenance::default().with_pass(pass))
+    }
+}
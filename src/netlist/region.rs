@@ -0,0 +1,129 @@
+/*!
+
+  Placement regions of instances, for handing floorplanning intent to physical tools.
+
+  An instance is assigned to a region, like a Vivado pblock, by the [PBLOCK] attribute. The attribute travels with the
+  instance through edits and passes that keep attributes, is emitted in Verilog as `(* pblock = "name" *)`, and can be
+  written as XDC `create_pblock` and `add_cells_to_pblock` constraints with [Netlist::write_pblocks].
+
+*/
+
+use super::{NetRef, Netlist};
+use crate::{
+    attribute::Parameter,
+    circuit::{Identifier, Instantiable},
+    error::Error,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Write,
+};
+
+/// The attribute that assigns an instance to a placement region, with the name of the region as a string value
+pub const PBLOCK: &str = "pblock";
+
+/// Options for [Netlist::write_pblocks]
+#[derive(Debug, Clone, Default)]
+pub struct PblockOptions {
+    /// The site ranges of each region, like `SLICE_X0Y0:SLICE_X15Y49` or `CLOCKREGION_X0Y0`, added with `resize_pblock`.
+    /// Regions without ranges are only created, for the floorplan to place them.
+    pub ranges: HashMap<String, Vec<String>>,
+}
+
+impl<I> NetRef<I>
+where
+    I: Instantiable,
+{
+    /// Assigns the instance to the region `name`, returning the region it was in before
+    pub fn set_region(&self, name: &str) -> Option<String> {
+        self.insert_attribute(PBLOCK.to_string(), name)
+            .and_then(region_name)
+    }
+
+    /// Returns the region that the instance is assigned to
+    pub fn get_region(&self) -> Option<String> {
+        self.get_attribute(&PBLOCK.to_string())
+            .and_then(region_name)
+    }
+
+    /// Removes the instance from its region, returning the region it was in
+    pub fn clear_region(&self) -> Option<String> {
+        self.clear_attribute(&PBLOCK.to_string())
+            .and_then(region_name)
+    }
+}
+
+/// Returns the name of an instance as Vivado knows it, without the escape of escaped identifiers
+fn xdc_name(name: &Identifier) -> String {
+    match name.get_bit_index() {
+        Some(index) => format!("{}[{index}]", name.get_name()),
+        None => name.get_name().to_string(),
+    }
+}
+
+/// Returns the name of a region from the value of a [PBLOCK] attribute
+fn region_name(value: Option<Parameter>) -> Option<String> {
+    match value? {
+        Parameter::String(name) => Some(name),
+        _ => None,
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Returns the instances of each region, by region name and in netlist order
+    pub fn regions(&self) -> BTreeMap<String, Vec<NetRef<I>>> {
+        let mut regions: BTreeMap<String, Vec<NetRef<I>>> = BTreeMap::new();
+        for inst in self.instances() {
+            if let Some(region) = inst.get_region() {
+                regions.entry(region).or_default().push(inst);
+            }
+        }
+        regions
+    }
+
+    /// Returns the instances of the region `name`, in netlist order
+    pub fn region_members(&self, name: &str) -> Vec<NetRef<I>> {
+        self.instances()
+            .filter(|inst| inst.get_region().as_deref() == Some(name))
+            .collect()
+    }
+
+    /// Writes the regions to `writer` as XDC constraints: a `create_pblock` command for each region, by name,
+    /// followed by the `resize_pblock` commands of its ranges in `options` and an `add_cells_to_pblock` command
+    /// with its instances. Regions of `options` without instances are created empty.
+    pub fn write_pblocks(
+        &self,
+        writer: &mut impl Write,
+        options: &PblockOptions,
+    ) -> Result<(), Error> {
+        let mut regions = self.regions();
+        for name in options.ranges.keys() {
+            regions.entry(name.clone()).or_default();
+        }
+        for (name, members) in regions {
+            writeln!(writer, "create_pblock {{{name}}}")?;
+            for range in options.ranges.get(&name).into_iter().flatten() {
+                writeln!(
+                    writer,
+                    "resize_pblock [get_pblocks {{{name}}}] -add {{{range}}}"
+                )?;
+            }
+            if members.is_empty() {
+                continue;
+            }
+            let cells: Vec<String> = members
+                .iter()
+                .map(|inst| format!("{{{}}}", xdc_name(&inst.get_instance_name().unwrap())))
+                .collect();
+            writeln!(
+                writer,
+                "add_cells_to_pblock [get_pblocks {{{name}}}] [get_cells [list {}]]",
+                cells.join(" ")
+            )?;
+        }
+        Ok(())
+    }
+}
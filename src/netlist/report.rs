@@ -0,0 +1,120 @@
+/*!
+
+  Reports of the edits made by a pass, and dry runs of passes.
+
+  [Netlist::run_pass] compares the circuit nodes by their stable [super::ObjectId] before and after a pass, so any pass
+  can be reported or tried out without applying it, like [Netlist::clean] or [super::opt::propagate_constants].
+
+*/
+
+use super::{NetRef, Netlist, Operand};
+use crate::{
+    attribute::Parameter,
+    circuit::{Identifier, Instantiable, Net},
+    error::Error,
+    util::{debug_span, trace_event},
+};
+use std::{collections::HashMap, rc::Rc};
+
+/// The circuit nodes that a pass added, removed or modified, by the name of the instance or input
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PassReport {
+    /// The names of the circuit nodes that the pass added, in netlist order
+    pub added: Vec<Identifier>,
+    /// The names of the circuit nodes that the pass removed, in netlist order before the pass
+    pub removed: Vec<Identifier>,
+    /// The names of the circuit nodes whose cell, parameters, nets or drivers the pass changed, in netlist order
+    pub modified: Vec<Identifier>,
+}
+
+impl PassReport {
+    /// Returns `true` if the pass left the netlist as it was
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// What a circuit node is, as far as a report is concerned
+#[derive(PartialEq)]
+struct Snapshot {
+    name: Identifier,
+    cell: Option<(Identifier, Vec<(Identifier, Parameter)>)>,
+    nets: Vec<Net>,
+    /// The stable identifiers and output positions of the drivers of each input
+    drivers: Vec<Option<(usize, usize)>>,
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Returns the snapshot of each circuit node, by stable identifier, in netlist order
+    fn snapshots(&self) -> Vec<(usize, Snapshot)> {
+        let objects = self.objects.borrow();
+        let id = |operand: &Operand| match operand {
+            Operand::DirectIndex(i) => (objects[*i].borrow().id.0, 0),
+            Operand::CellIndex(i, j) => (objects[*i].borrow().id.0, *j),
+        };
+        objects
+            .iter()
+            .map(|obj| {
+                let node = NetRef::wrap(obj.clone());
+                let obj = obj.borrow();
+                let snapshot = Snapshot {
+                    name: node
+                        .get_instance_name()
+                        .unwrap_or_else(|| node.get_identifier()),
+                    cell: node
+                        .get_instance_type()
+                        .map(|inst| (inst.get_name().clone(), inst.parameters().collect())),
+                    nets: node.nets().collect(),
+                    drivers: obj.operands.iter().map(|o| o.as_ref().map(id)).collect(),
+                };
+                (obj.id.0, snapshot)
+            })
+            .collect()
+    }
+
+    /// Runs `pass` and reports the circuit nodes it added, removed or modified. With `dry_run`, the pass runs on a copy
+    /// of the netlist, so the netlist and its observers are left as they are and the report lists what the pass would do.
+    /// Returns the result of the pass with its report, or the first error of the pass.
+    pub fn run_pass<R>(
+        self: &Rc<Self>,
+        dry_run: bool,
+        pass: impl FnOnce(&Rc<Self>) -> Result<R, Error>,
+    ) -> Result<(R, PassReport), Error> {
+        debug_span!("run_pass", netlist = %self.get_name(), dry_run);
+        let before = self.snapshots();
+        let target = if dry_run {
+            self.duplicate()
+        } else {
+            self.clone()
+        };
+        let result = pass(&target)?;
+        let after = target.snapshots();
+
+        let mut report = PassReport::default();
+        let mut remaining: HashMap<usize, &Snapshot> =
+            after.iter().map(|(id, snapshot)| (*id, snapshot)).collect();
+        for (id, old) in &before {
+            match remaining.remove(id) {
+                None => report.removed.push(old.name.clone()),
+                Some(new) if new != old => report.modified.push(new.name.clone()),
+                Some(_) => (),
+            }
+        }
+        report.added = after
+            .iter()
+            .filter(|(id, _)| remaining.contains_key(id))
+            .map(|(_, snapshot)| snapshot.name.clone())
+            .collect();
+        trace_event!(
+            debug,
+            added = report.added.len(),
+            removed = report.removed.len(),
+            modified = report.modified.len(),
+            "pass reported"
+        );
+        Ok((result, report))
+    }
+}
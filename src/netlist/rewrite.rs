@@ -0,0 +1,800 @@
+/*!
+
+  Rewriting of 4-input cones with the smallest known implementation of their NPN class.
+
+*/
+
+use super::{DrivenNet, Gate, NetRef, Netlist, WeakIndex, opt::move_loads, sim::is_source};
+use crate::{
+    circuit::{Evaluate, Identifier, Instantiable},
+    error::Error,
+    format_id,
+    graph::TopoOrder,
+    logic::Logic,
+    util::{debug_span, trace_event},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+/// The smallest implementation of each NPN class of 4-input functions, keyed by the canonical truth table of the class.
+/// The formulas are in prefix notation over the inputs `a` to `d` and the constants `0` and `1`, with `!` for `INV`,
+/// `&`, `|`, `^`, `N`, `R` and `X` for the two-input `AND`, `OR`, `XOR`, `NAND`, `NOR` and `XNOR` gates,
+/// and `?` for a `MUX` whose select is its first operand. They were found by an exhaustive search over formulas
+/// of increasing gate count, where every gate costs one.
+const CLASSES: [(u16, &str); 222] = [
+    (0x0000, "0"),
+    (0x0001, "Ra|b|cd"),
+    (0x0003, "Rb|cd"),
+    (0x0006, "^a?c?dbaa"),
+    (0x0007, "Rc|d&ab"),
+    (0x000f, "Rcd"),
+    (0x0016, "^a?d?bc?cbaa"),
+    (0x0017, "RdX?abc?b1c"),
+    (0x0018, "Rd|^abXac"),
+    (0x0019, "Rd?ab?bac"),
+    (0x001b, "Rd?abc"),
+    (0x001e, "^c?d|abc"),
+    (0x001f, "Rd&c|ab"),
+    (0x003c, "^b?dcb"),
+    (0x003d, "Rd?b?cabc"),
+    (0x003f, "Rd&bc"),
+    (0x0069, "^a?dXbca"),
+    (0x006b, "^a?d?bRacca"),
+    (0x006f, "Rd&cXab"),
+    (0x007e, "Rd&XabXac"),
+    (0x007f, "Rd&a&bc"),
+    (0x00ff, "!d"),
+    (0x0116, "^|a?bcd|d?cba"),
+    (0x0117, "^a|Xa|bc?d^bca"),
+    (0x0118, "^c?a?bdc|c?dbc"),
+    (0x0119, "R?abc^a?dbc"),
+    (0x011a, "^c?a?bdc?dac"),
+    (0x011b, "R&cd?ab|cd"),
+    (0x011e, "Xc^d|Rab&cd"),
+    (0x011f, "X?cda?a?bdc0"),
+    (0x012c, "^b?a?cdb?dcb"),
+    (0x012d, "^c|Rab?dbc"),
+    (0x012f, "!?b?acd|cd"),
+    (0x013c, "^b?dc|bRac"),
+    (0x013d, "!?b?cad|cd"),
+    (0x013e, "^d|^bc?bad"),
+    (0x013f, "N|bd|c?bad"),
+    (0x0168, "^|ad?b?cad|cd"),
+    (0x0169, "Xa^b?dc?a?bcab"),
+    (0x016a, "^a?b&d?cda?dca"),
+    (0x016b, "^?abd?c1?bad"),
+    (0x016e, "^d|^ab?cad"),
+    (0x016f, "!?a?bcd|d&bc"),
+    (0x017e, "^d|^ab?ac?cad"),
+    (0x017f, "^d|?abd!?cdb"),
+    (0x0180, "R?abd!?cdb"),
+    (0x0181, "R^ab?ac?cad"),
+    (0x0182, "RXad?bcNac"),
+    (0x0183, "R^bc?abd"),
+    (0x0186, "Xa^?cda?b1?acd"),
+    (0x0187, "^c|Rab?d^abc"),
+    (0x0189, "R^ab?acd"),
+    (0x018b, "R?abd?bcd"),
+    (0x018f, "R?acd?bcd"),
+    (0x0196, "^a?d^bc|aRbc"),
+    (0x0197, "Xa^?dba?c|abd"),
+    (0x0198, "^d?a?bcd|bd"),
+    (0x0199, "Xa?dbRaRbc"),
+    (0x019a, "^a^d&Nad?bcd"),
+    (0x019b, "^?a1d|b?cad"),
+    (0x019e, "Xa^d?c?b1a?dba"),
+    (0x019f, "Xa?d?cabRaRbc"),
+    (0x01a8, "^&ad?b?cdaa"),
+    (0x01a9, "Xa&Nad|bc"),
+    (0x01aa, "?daRa|bc"),
+    (0x01ab, "!?a|bcd"),
+    (0x01ac, "&Nad?c^bda"),
+    (0x01ad, "!?c?bad?acd"),
+    (0x01ae, "^d|a?cbd"),
+    (0x01af, "R&bd?acd"),
+    (0x01bc, "^d|?bca^b?dca"),
+    (0x01bd, "!?b?cad|d?acd"),
+    (0x01be, "^d|a?bc?cbd"),
+    (0x01bf, "!?a^?bca?dcbd"),
+    (0x01e8, "RRa?bdc?b?cadd"),
+    (0x01e9, "RRaXbc?b?cadd"),
+    (0x01ea, "!?a|^bcXbdd"),
+    (0x01eb, "!?a?bc?cbdd"),
+    (0x01ee, "!?a?b?d1cdd"),
+    (0x01ef, "!?a?bcdd"),
+    (0x01fe, "^d|a|bc"),
+    (0x033c, "^b?cd?dcb"),
+    (0x033d, "!?b?c?dabd|cd"),
+    (0x033f, "^?bcd?c1d"),
+    (0x0356, "XRad|bc"),
+    (0x0357, "|RadRbc"),
+    (0x0358, "?c?bd?dacRad"),
+    (0x0359, "^?cbd?a1d"),
+    (0x035a, "^c?da?bdc"),
+    (0x035b, "^c|Rbc?dac"),
+    (0x035e, "!?c?bRadd|ad"),
+    (0x035f, "?cNbdRad"),
+    (0x0368, "^b?c|d?abc?dab"),
+    (0x0369, "Xb^c?da&bc"),
+    (0x036a, "^?dab?cdb"),
+    (0x036b, "^b|Rbc?dXacb"),
+    (0x036c, "^b?cd?dab"),
+    (0x036d, "^b?c|dRab?dab"),
+    (0x036e, "?d?abNbcRbc"),
+    (0x036f, "^b?c?b1d?dab"),
+    (0x037c, "^b?cd?d?bcab"),
+    (0x037d, "?dNaXbcRbc"),
+    (0x037e, "^d|^bcXb?a1d"),
+    (0x03c0, "R^bcXbd"),
+    (0x03c1, "R^bc?b?dabd"),
+    (0x03c3, "!?bc?cbd"),
+    (0x03c5, "!?c?dab?bcd"),
+    (0x03c6, "^b?c|ad&bd"),
+    (0x03c7, "!?bc|d?cad"),
+    (0x03cf, "!?bcd"),
+    (0x03d4, "^?bcd|d?abc"),
+    (0x03d5, "?dNaNbcRbc"),
+    (0x03d6, "^b?c|ad?bRadd"),
+    (0x03d7, "Xb?d?abc?bc0"),
+    (0x03d8, "?d?acbRbc"),
+    (0x03d9, "?d?a?b1cbRbc"),
+    (0x03db, "!?a?cbd?bcd"),
+    (0x03dc, "!?b?d?c1acd"),
+    (0x03dd, "!?b?dacd"),
+    (0x03de, "!?bXc|add"),
+    (0x03fc, "^d|bc"),
+    (0x0660, "RXabXcd"),
+    (0x0661, "^a?c?d?b1ab?dba"),
+    (0x0662, "RXab?cRadd"),
+    (0x0663, "^b?c?d1a?dab"),
+    (0x0666, "^a?cb?dba"),
+    (0x0667, "^a?c|bRad?dba"),
+    (0x0669, "^a?cXbd?dba"),
+    (0x066b, "^a?c?bRadd?dba"),
+    (0x066f, "^?cab?dXacb"),
+    (0x0672, "^a?d?acb?cba"),
+    (0x0673, "^a?d?a?b1cb?cba"),
+    (0x0676, "^a?cb?d?acba"),
+    (0x0678, "^a?dXc?a1b?cba"),
+    (0x0679, "Xa^d?cb&a?bad"),
+    (0x067a, "^a?c&bd?d?acba"),
+    (0x067b, "^?b1c?ad?dbc"),
+    (0x067e, "^?cad?bc?abd"),
+    (0x0690, "&^cd^a^bc"),
+    (0x0691, "^a?dNb?abc?cba"),
+    (0x0693, "^b?dNac?cab"),
+    (0x0696, "^a?cbXd?dba"),
+    (0x0697, "!?a?bdc?bc?cad"),
+    (0x069f, "!?a?bdc?bcd"),
+    (0x06b0, "^a?c?dab?aRbdd"),
+    (0x06b1, "Xa^d?d?abc?cba"),
+    (0x06b2, "^?cad?bc|ad"),
+    (0x06b3, "!?a?bd?dbc?cbd"),
+    (0x06b4, "^a?d^c|ab?cba"),
+    (0x06b5, "!?a?bdc?c?dabd"),
+    (0x06b6, "^a?cb?aRbdd"),
+    (0x06b7, "!?a?bdc?cbd"),
+    (0x06b9, "Xa^d|?db0?cba"),
+    (0x06bd, "X?cab?bd?da0"),
+    (0x06f0, "?dc^a?cba"),
+    (0x06f1, "Xd?c?abNbd0"),
+    (0x06f2, "!?c?aNbdbd"),
+    (0x06f6, "!?cXabd"),
+    (0x06f9, "Xa^d?cba"),
+    (0x0776, "R&ab?cRa|bdd"),
+    (0x0778, "^c?ad?bd?dac"),
+    (0x0779, "^?b1?acd?c?dabd"),
+    (0x077a, "^c?adX&bc?dcb"),
+    (0x077e, "^c|^a?cba?ad?dbc"),
+    (0x07b0, "&^cdXa?bac"),
+    (0x07b1, "!?a?dbc?c?dabd"),
+    (0x07b4, "^c?bdNaNcd"),
+    (0x07b5, "^?a1c&?bdc?acd"),
+    (0x07b6, "!?aXc|bd?cbd"),
+    (0x07bc, "!?bXcd?acd"),
+    (0x07e0, "^&cd|?bdc?adc"),
+    (0x07e1, "^c&?a1d?b1?adc"),
+    (0x07e2, "?d?bacRc&ab"),
+    (0x07e3, "!?aXc?b1d?cbd"),
+    (0x07e6, "^?cad|b?adc"),
+    (0x07e9, "Xa^?cba?a?bcdd"),
+    (0x07f0, "?dcRc&ab"),
+    (0x07f1, "!?c^?ab0?dabd"),
+    (0x07f2, "!?aXcd?cbd"),
+    (0x07f8, "^d|c&ab"),
+    (0x0ff0, "^cd"),
+    (0x1668, "^?ab?bcd?c?dbad"),
+    (0x1669, "Xa^b?cdNdNab"),
+    (0x166a, "^a?b&cd?cd?dba"),
+    (0x166b, "^?ab?bcd?c?d1ad"),
+    (0x166e, "^?cad?ab?bcd"),
+    (0x167e, "^?abc?b?ac?cad?dac"),
+    (0x1681, "^&ad?b?cRadd?cda"),
+    (0x1683, "Xb^?adb?bc?cad"),
+    (0x1686, "^a?cb^?ad0?bad"),
+    (0x1687, "Xc&Xab?adNcd"),
+    (0x1689, "^?aRcdd?cb?bda"),
+    (0x168b, "X?adb?bcNd|ac"),
+    (0x168e, "!?a?bNcdc?bcd"),
+    (0x1696, "^a?bcNcNad"),
+    (0x1697, "!?a?b?cdac?bc?cad"),
+    (0x1698, "^?abd|?acb?dbc"),
+    (0x1699, "Xa^b&dNcNab"),
+    (0x169a, "^a?bc&d?cba"),
+    (0x169b, "!?a^b?cda?bcd"),
+    (0x169e, "!?aXbc?bcd"),
+    (0x16a9, "Xa^d?bc|aNcd"),
+    (0x16ac, "^&ad?cb|a?bda"),
+    (0x16ad, "Xc^d?ba?adc"),
+    (0x16bc, "^b?ac^d?bc0"),
+    (0x16e9, "Xa^d?bc?cba"),
+    (0x177e, "^a^?abc^?dca?bdc"),
+    (0x178e, "^?adc?baXcd"),
+    (0x1796, "^a|^bc&dXab"),
+    (0x1798, "^?acb&?cdb?a1d"),
+    (0x179a, "!?aXc?cdb?bcd"),
+    (0x17ac, "^?cba&d?bda"),
+    (0x17e8, "Xd^?abc?b1c"),
+    (0x18e7, "^d|^abXac"),
+    (0x19e1, "^|ab?cNa&bdd"),
+    (0x19e3, "Xb^d?a|cd&bc"),
+    (0x19e6, "^d?ab?bac"),
+    (0x1bd8, "^c?b?cda?cad"),
+    (0x1be4, "^d?abc"),
+    (0x1ee1, "Xc^d|ab"),
+    (0x3cc3, "Xb^cd"),
+    (0x6996, "^a^b^cd"),
+];
+
+/// The truth tables of the four inputs over 64 patterns, where input `i` is bit `i` of the pattern index
+const PROJECTIONS: [u64; 4] = [
+    0xAAAA_AAAA_AAAA_AAAA,
+    0xCCCC_CCCC_CCCC_CCCC,
+    0xF0F0_F0F0_F0F0_F0F0,
+    0xFF00_FF00_FF00_FF00,
+];
+
+/// The largest number of cuts that are kept for a circuit node
+const MAX_CUTS: usize = 12;
+
+/// A permutation and complementation of the inputs and output of a 4-input function.
+/// The functions of an NPN class are the ones that transformations map onto each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NpnTransform {
+    /// Input `i` of the transformed function feeds input `perm[i]` of the original function
+    pub perm: [u8; 4],
+    /// Bit `i` is set when input `i` of the transformed function is complemented on its way to the original function
+    pub negated_inputs: u8,
+    /// Whether the output of the original function is complemented
+    pub negated_output: bool,
+}
+
+impl NpnTransform {
+    /// The transformation that leaves every function as it is
+    pub const IDENTITY: Self = Self {
+        perm: [0, 1, 2, 3],
+        negated_inputs: 0,
+        negated_output: false,
+    };
+
+    /// Returns the truth table of the function `table` under the transformation.
+    /// Input `i` is bit `i` of the index into a table, as in [crate::circuit::Evaluate::eval_words].
+    pub fn apply(&self, table: u16) -> u16 {
+        (0..16).fold(0, |acc, m: u16| {
+            let source = (0..4).fold(0, |source, i| {
+                let bit = ((m >> i) & 1) ^ u16::from((self.negated_inputs >> i) & 1);
+                source | (bit << self.perm[i])
+            });
+            let bit = ((table >> source) & 1) ^ u16::from(self.negated_output);
+            acc | (bit << m)
+        })
+    }
+}
+
+/// Returns the 24 permutations of four inputs
+fn permutations() -> impl Iterator<Item = [u8; 4]> {
+    (0..64u8)
+        .map(|i| [i & 3, (i >> 2) & 3, i >> 4])
+        .filter(|[a, b, c]| a != b && a != c && b != c)
+        .map(|[a, b, c]| [a, b, c, 6 - a - b - c])
+}
+
+/// Returns the canonical truth table of the NPN class of `table`, which is the smallest table that a transformation maps it to,
+/// along with such a transformation. All 768 transformations are tried, so the callers should cache the results.
+pub fn npn_canonical(table: u16) -> (u16, NpnTransform) {
+    let mut best = (table, NpnTransform::IDENTITY);
+    for perm in permutations() {
+        for negated_inputs in 0..16 {
+            for negated_output in [false, true] {
+                let transform = NpnTransform {
+                    perm,
+                    negated_inputs,
+                    negated_output,
+                };
+                let canonical = transform.apply(table);
+                if canonical < best.0 {
+                    best = (canonical, transform);
+                }
+            }
+        }
+    }
+    best
+}
+
+/// Returns the smallest known implementation of the NPN class whose canonical truth table is `canonical`,
+/// in the prefix notation described by the database, or [None] if `canonical` is not canonical. See [npn_canonical].
+pub fn class_formula(canonical: u16) -> Option<&'static str> {
+    CLASSES
+        .binary_search_by_key(&canonical, |(table, _)| *table)
+        .ok()
+        .map(|i| CLASSES[i].1)
+}
+
+/// A parsed formula of the class database
+#[derive(Debug)]
+enum Formula {
+    Const(bool),
+    Var(usize),
+    Not(Box<Formula>),
+    /// A two-input gate, by name
+    Gate(&'static str, Box<Formula>, Box<Formula>),
+    /// A `MUX` of its select, the operand for a low select and the operand for a high select
+    Mux(Box<Formula>, Box<Formula>, Box<Formula>),
+}
+
+/// Returns the name of the gate that computes the complement of the two-input gate `name`
+fn complement(name: &'static str) -> &'static str {
+    match name {
+        "AND" => "NAND",
+        "NAND" => "AND",
+        "OR" => "NOR",
+        "NOR" => "OR",
+        "XOR" => "XNOR",
+        _ => "XOR",
+    }
+}
+
+impl Formula {
+    /// Parses the formula at the start of `text`, returning it with the rest of the text
+    fn parse(text: &[u8]) -> (Self, &[u8]) {
+        let (head, rest) = text.split_first().expect("Truncated formula");
+        match head {
+            b'0' | b'1' => (Formula::Const(*head == b'1'), rest),
+            b'a'..=b'd' => (Formula::Var((head - b'a') as usize), rest),
+            b'!' => {
+                let (a, rest) = Self::parse(rest);
+                (Formula::Not(Box::new(a)), rest)
+            }
+            b'?' => {
+                let (s, rest) = Self::parse(rest);
+                let (a, rest) = Self::parse(rest);
+                let (b, rest) = Self::parse(rest);
+                (Formula::Mux(Box::new(s), Box::new(a), Box::new(b)), rest)
+            }
+            _ => {
+                let name = match head {
+                    b'&' => "AND",
+                    b'|' => "OR",
+                    b'^' => "XOR",
+                    b'N' => "NAND",
+                    b'R' => "NOR",
+                    b'X' => "XNOR",
+                    _ => panic!("Unknown operator {} in formula", *head as char),
+                };
+                let (a, rest) = Self::parse(rest);
+                let (b, rest) = Self::parse(rest);
+                (Formula::Gate(name, Box::new(a), Box::new(b)), rest)
+            }
+        }
+    }
+
+    /// Returns the inputs that the formula reads, as a bit set
+    fn support(&self) -> u8 {
+        match self {
+            Formula::Const(_) => 0,
+            Formula::Var(i) => 1 << i,
+            Formula::Not(a) => a.support(),
+            Formula::Gate(_, a, b) => a.support() | b.support(),
+            Formula::Mux(s, a, b) => s.support() | a.support() | b.support(),
+        }
+    }
+
+    /// Returns the number of gates that [Formula::build] inserts, without the inverters of the inputs.
+    /// The output is complemented when `invert` is set, and the inputs that need an inverter set their bit in `inverted`.
+    fn cost(&self, invert: bool, negated: u8, inverted: &mut u8) -> usize {
+        match self {
+            Formula::Const(_) => 0,
+            Formula::Var(i) => {
+                if invert ^ ((negated >> i) & 1 == 1) {
+                    *inverted |= 1 << i;
+                }
+                0
+            }
+            Formula::Not(a) => a.cost(!invert, negated, inverted),
+            Formula::Gate(_, a, b) => {
+                1 + a.cost(false, negated, inverted) + b.cost(false, negated, inverted)
+            }
+            Formula::Mux(s, a, b) => {
+                1 + usize::from(invert)
+                    + s.cost(false, negated, inverted)
+                    + a.cost(false, negated, inverted)
+                    + b.cost(false, negated, inverted)
+            }
+        }
+    }
+
+    /// Inserts the gates of the formula over the inputs of `builder`, complementing the output when `invert` is set.
+    /// Complements are pushed into the gates and the inputs, so that only a `MUX` needs an inverter on its output.
+    fn build<I>(&self, builder: &mut Builder<'_, I>, invert: bool) -> Result<DrivenNet<I>, Error>
+    where
+        I: Instantiable + From<Gate>,
+    {
+        match self {
+            Formula::Const(value) => builder
+                .netlist
+                .constant_driver(Logic::from_bool(*value ^ invert)),
+            Formula::Var(i) => builder.input(*i, invert),
+            Formula::Not(a) => a.build(builder, !invert),
+            Formula::Gate(name, a, b) => {
+                let name = if invert { complement(name) } else { name };
+                let inputs = [a.build(builder, false)?, b.build(builder, false)?];
+                builder.gate(name, &["A", "B"], &inputs)
+            }
+            Formula::Mux(s, a, b) => {
+                let inputs = [
+                    s.build(builder, false)?,
+                    a.build(builder, false)?,
+                    b.build(builder, false)?,
+                ];
+                let mux = builder.gate("MUX", &["S", "A", "B"], &inputs)?;
+                if invert {
+                    builder.gate("INV", &["A"], &[mux])
+                } else {
+                    Ok(mux)
+                }
+            }
+        }
+    }
+}
+
+/// Inserts the gates of a formula, whose input `i` is `inputs[i]`, complemented when bit `i` of `negated` is set.
+/// The inputs that the formula does not read may be missing.
+struct Builder<'a, I: Instantiable> {
+    netlist: &'a Rc<Netlist<I>>,
+    inputs: Vec<Option<DrivenNet<I>>>,
+    negated: u8,
+    /// The inverters of the inputs that were already inserted
+    inverted: Vec<Option<DrivenNet<I>>>,
+}
+
+impl<I> Builder<'_, I>
+where
+    I: Instantiable + From<Gate>,
+{
+    fn gate(
+        &mut self,
+        name: &str,
+        ports: &[&str],
+        inputs: &[DrivenNet<I>],
+    ) -> Result<DrivenNet<I>, Error> {
+        let ports = ports.iter().map(|p| Identifier::from(*p)).collect();
+        let gate = Gate::new_logical(name.into(), ports, "Y".into());
+        let inst_name = format_id!("rewrite_{}", self.netlist.next_id.get());
+        Ok(self
+            .netlist
+            .insert_gate(gate.into(), inst_name, inputs)?
+            .get_output(0))
+    }
+
+    /// Returns input `i` of the formula, complemented when `invert` is set
+    fn input(&mut self, i: usize, invert: bool) -> Result<DrivenNet<I>, Error> {
+        let input = self.inputs[i]
+            .clone()
+            .expect("Formula reads a missing input");
+        if invert == ((self.negated >> i) & 1 == 1) {
+            return Ok(input);
+        }
+        if let Some(net) = &self.inverted[i] {
+            return Ok(net.clone());
+        }
+        let net = self.gate("INV", &["A"], &[input])?;
+        self.inverted[i] = Some(net.clone());
+        Ok(net)
+    }
+}
+
+/// A cut of a circuit node, as the sorted object indices and output positions of its leaves
+type Cut = Vec<(usize, usize)>;
+
+/// A cheaper implementation of the cone of a circuit node
+struct Candidate {
+    /// The leaves of the cone
+    cut: Cut,
+    /// The truth table of the cone over its leaves
+    table: u16,
+    /// The nodes that are left without loads once the node is replaced, including the node itself
+    freed: Vec<usize>,
+    /// The number of cells that are saved
+    gain: usize,
+}
+
+/// The cut enumeration and class lookups of a [rewrite] pass
+struct Rewriter<'a, I: Instantiable> {
+    netlist: &'a Rc<Netlist<I>>,
+    /// The cuts of the circuit nodes that were visited, without their trivial cuts
+    cuts: HashMap<usize, Vec<Cut>>,
+    /// The class formula and the transformation of the truth tables that were looked up
+    classes: HashMap<u16, (Rc<Formula>, NpnTransform)>,
+}
+
+impl<I> Rewriter<'_, I>
+where
+    I: Evaluate + From<Gate>,
+{
+    fn node(&self, index: usize) -> NetRef<I> {
+        NetRef::wrap(self.netlist.index_weak(&index))
+    }
+
+    /// Returns `true` if the node at `index` may be inside a cone, which is the case for combinational cells
+    /// with a single output that is not tri-state, and whose inputs are all connected
+    fn is_expandable(&self, index: usize) -> bool {
+        let node = self.node(index);
+        !is_source(&node)
+            && node.nets().count() == 1
+            && node.is_fully_connected()
+            && node
+                .get_instance_type()
+                .is_some_and(|i| !i.is_tristate_output(0))
+    }
+
+    /// Returns `true` if the node at `index` is a constant cell
+    fn is_constant(&self, index: usize) -> bool {
+        self.node(index)
+            .get_instance_type()
+            .is_some_and(|i| i.get_constant().is_some())
+    }
+
+    /// Returns `true` if the node at `index` drives a top-level output
+    fn is_output(&self, index: usize) -> bool {
+        self.netlist
+            .outputs
+            .borrow()
+            .iter()
+            .any(|(o, _)| o.as_ref().is_some_and(|o| o.root() == index))
+    }
+
+    /// Returns the drivers of the inputs of the node at `index`, as object indices and output positions
+    fn fanin(&self, index: usize) -> Vec<(usize, usize)> {
+        self.netlist
+            .index_weak(&index)
+            .borrow()
+            .operands
+            .iter()
+            .flatten()
+            .map(|o| (o.root(), o.secondary()))
+            .collect()
+    }
+
+    /// Returns the cuts of up to four leaves of the expandable node at `index`, smallest first.
+    /// They are merged from the cuts of its drivers, of which only [MAX_CUTS] are kept.
+    fn cuts_of(&mut self, index: usize) -> Vec<Cut> {
+        if let Some(cuts) = self.cuts.get(&index) {
+            return cuts.clone();
+        }
+        let mut cuts: Vec<Cut> = vec![Vec::new()];
+        for leaf in self.fanin(index) {
+            let mut choices = vec![vec![leaf]];
+            if leaf.1 == 0 && self.is_expandable(leaf.0) {
+                choices.extend(self.cuts_of(leaf.0));
+            }
+            let mut merged: Vec<Cut> = Vec::new();
+            for cut in &cuts {
+                for choice in &choices {
+                    let mut union = cut.clone();
+                    union.extend(choice);
+                    union.sort_unstable();
+                    union.dedup();
+                    if union.len() <= 4 && !merged.contains(&union) {
+                        merged.push(union);
+                    }
+                }
+            }
+            merged.sort_by_key(Vec::len);
+            merged.truncate(MAX_CUTS);
+            cuts = merged;
+        }
+        self.cuts.insert(index, cuts.clone());
+        cuts
+    }
+
+    /// Simulates the node at `index` with the leaves of its cone already in `values`.
+    /// The nodes of the cone are pushed to `cone` in topological order.
+    fn eval(
+        &self,
+        index: usize,
+        values: &mut HashMap<(usize, usize), u64>,
+        cone: &mut Vec<usize>,
+    ) -> Option<u64> {
+        if let Some(value) = values.get(&(index, 0)) {
+            return Some(*value);
+        }
+        if !self.is_expandable(index) {
+            return None;
+        }
+        let mut inputs = Vec::new();
+        for (root, pos) in self.fanin(index) {
+            let value = match values.get(&(root, pos)) {
+                Some(value) => *value,
+                None if pos == 0 => self.eval(root, values, cone)?,
+                None => return None,
+            };
+            inputs.push(value);
+        }
+        let value = self.node(index).get_instance_type()?.eval_words(&inputs)[0];
+        values.insert((index, 0), value);
+        cone.push(index);
+        Some(value)
+    }
+
+    /// Returns the formula of the class of `table` and the transformation of `table` into the class
+    fn class(&mut self, table: u16) -> (Rc<Formula>, NpnTransform) {
+        self.classes
+            .entry(table)
+            .or_insert_with(|| {
+                let (canonical, transform) = npn_canonical(table);
+                let formula = class_formula(canonical).expect("Missing NPN class");
+                (Rc::new(Formula::parse(formula.as_bytes()).0), transform)
+            })
+            .clone()
+    }
+
+    /// Returns the number of cells of the implementation of `table` over `n` leaves, or [None] if it reads a missing leaf
+    fn cost(&mut self, table: u16, n: usize) -> Option<usize> {
+        let (formula, transform) = self.class(table);
+        let support = formula.support();
+        if (0..4).any(|i| (support >> i) & 1 == 1 && usize::from(transform.perm[i]) >= n) {
+            return None;
+        }
+        let mut inverted = 0;
+        let gates = formula.cost(
+            transform.negated_output,
+            transform.negated_inputs,
+            &mut inverted,
+        );
+        Some(gates + inverted.count_ones() as usize)
+    }
+
+    /// Returns the cut of the node at `index` whose implementation saves the most cells, if any saves one
+    fn best_candidate(&mut self, index: usize) -> Option<Candidate> {
+        let mut best: Option<Candidate> = None;
+        for cut in self.cuts_of(index) {
+            let mut values: HashMap<(usize, usize), u64> =
+                cut.iter().copied().zip(PROJECTIONS).collect();
+            let mut cone = Vec::new();
+            let Some(value) = self.eval(index, &mut values, &mut cone) else {
+                continue;
+            };
+            let table = value as u16;
+            let freed = self.freed(&cone);
+            if freed.iter().any(|i| {
+                self.netlist
+                    .is_protected(&self.netlist.index_weak(i).borrow())
+            }) {
+                continue;
+            }
+            let Some(cost) = self.cost(table, cut.len()) else {
+                continue;
+            };
+            if cost < freed.len() && best.as_ref().is_none_or(|b| freed.len() - cost > b.gain) {
+                best = Some(Candidate {
+                    cut,
+                    table,
+                    gain: freed.len() - cost,
+                    freed,
+                });
+            }
+        }
+        best
+    }
+
+    /// Returns the nodes of `cone` that only its root reads, directly or through other such nodes.
+    /// These are freed when the root is replaced. `cone` is in topological order and ends with the root.
+    /// Constant cells are shared by the implementations, so they are never freed and are left for [Netlist::clean].
+    fn freed(&self, cone: &[usize]) -> Vec<usize> {
+        let Some((root, rest)) = cone.split_last() else {
+            return Vec::new();
+        };
+        let mut freed = vec![*root];
+        for node in rest.iter().rev() {
+            let constant = self
+                .node(*node)
+                .get_instance_type()
+                .is_some_and(|i| i.get_constant().is_some());
+            let readers = self.netlist.readers_of_object(*node);
+            if !constant && !self.is_output(*node) && readers.iter().all(|(r, _)| freed.contains(r))
+            {
+                freed.push(*node);
+            }
+        }
+        freed
+    }
+
+    /// Inserts the implementation of a candidate, returning its output
+    fn build(&mut self, candidate: &Candidate) -> Result<DrivenNet<I>, Error> {
+        let (formula, transform) = self.class(candidate.table);
+        let inputs = transform
+            .perm
+            .iter()
+            .map(|p| {
+                candidate
+                    .cut
+                    .get(usize::from(*p))
+                    .map(|(root, pos)| self.node(*root).get_output(*pos))
+            })
+            .collect();
+        let mut builder = Builder {
+            netlist: self.netlist,
+            inputs,
+            negated: transform.negated_inputs,
+            inverted: vec![None; 4],
+        };
+        formula.build(&mut builder, transform.negated_output)
+    }
+}
+
+/// Replaces the 4-input cones of the netlist with the smallest known implementation of their NPN class,
+/// like the `rewrite` command of ABC. Each cell counts as one, and the implementations are built from the
+/// `AND`, `OR`, `XOR`, `NAND`, `NOR`, `XNOR`, `INV` and `MUX` gates of [Gate].
+///
+/// The circuit nodes are visited in topological order. For each of a bounded number of cuts with up to four leaves,
+/// the cone above the cut is simulated for its truth table, which is looked up in the class database with [npn_canonical].
+/// The loads and top-level outputs of the node are moved onto the implementation that saves the most cells,
+/// counting the cells that only the cone reads, and those cells are removed.
+/// Sequential cells, black boxes, multi-output and tri-state cells are never part of a cone. Nodes protected by a
+/// [crate::attribute::DONT_TOUCH] attribute, or with protected loads, are not replaced, and cones that would free
+/// a protected node are skipped. Returns the number of replaced cones, or an error if the netlist has combinational cycles.
+pub fn rewrite<I>(netlist: &Rc<Netlist<I>>) -> Result<usize, Error>
+where
+    I: Evaluate + From<Gate>,
+{
+    debug_span!("rewrite", netlist = %netlist.get_name());
+    let order: Vec<usize> = netlist
+        .get_analysis::<TopoOrder<I>>()?
+        .iter()
+        .map(|n| n.netref.borrow().get_index())
+        .collect();
+    let mut rewriter = Rewriter {
+        netlist,
+        cuts: HashMap::new(),
+        classes: HashMap::new(),
+    };
+    let mut dead = HashSet::new();
+    let mut rewrites = 0;
+    for index in order {
+        if dead.contains(&index) || !rewriter.is_expandable(index) || rewriter.is_constant(index) {
+            continue;
+        }
+        let readers = netlist.readers_of_object(index);
+        if (readers.is_empty() && !rewriter.is_output(index))
+            || netlist.check_dont_touch(index, &readers).is_err()
+        {
+            continue;
+        }
+        let Some(candidate) = rewriter.best_candidate(index) else {
+            continue;
+        };
+        let to = rewriter.build(&candidate)?;
+        let from = rewriter.node(index).get_output(0);
+        move_loads(netlist, &from, &to, &netlist.get_uses(&from));
+        dead.extend(candidate.freed);
+        for (reader, _) in readers {
+            rewriter.cuts.remove(&reader);
+        }
+        rewrites += 1;
+    }
+    netlist.remove_objects(&dead)?;
+    trace_event!(debug, rewrites, removed = dead.len(), "cones rewritten");
+    Ok(rewrites)
+}
@@ -0,0 +1,434 @@
+/*!
+
+  Conjunctive normal forms of netlists and a small SAT solver, to prove properties of combinational logic.
+
+  A [Cnf] is a [NetworkBuilder], so [Netlist::to_cnf] and any other network construction produce the Tseitin
+  encoding of the logic: a variable for every input and AND node, and three clauses per AND node. [Cnf::to_dimacs]
+  exports the clauses for external solvers, and [Cnf::solve] runs a conflict-driven clause learning solver that is
+  meant for the miters of equivalence and redundancy checks, rather than for hard instances.
+
+*/
+
+use super::{Netlist, network::NetworkBuilder};
+use crate::{
+    circuit::{Evaluate, Identifier},
+    error::Error,
+};
+use std::fmt::Write;
+
+/// A literal: a variable of a [Cnf] or its complement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Lit(u32);
+
+impl Lit {
+    /// Returns the literal of variable `var`, complemented if `negated`
+    pub fn new(var: usize, negated: bool) -> Self {
+        Self((var as u32) << 1 | negated as u32)
+    }
+
+    /// Returns the variable of the literal
+    pub fn var(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    /// Returns `true` if the literal is the complement of its variable
+    pub fn is_negated(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl std::ops::Not for Lit {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(self.0 ^ 1)
+    }
+}
+
+/// Literals print in the DIMACS format, where variable `v` is `v + 1` and complements are negative
+impl std::fmt::Display for Lit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_negated() {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.var() + 1)
+    }
+}
+
+/// The result of [Cnf::solve]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SatResult {
+    /// The clauses are satisfiable, with the value of every variable in a satisfying assignment
+    Sat(Vec<bool>),
+    /// The clauses are unsatisfiable
+    Unsat,
+    /// The solver gave up after its conflict limit
+    Unknown,
+}
+
+/// A formula in conjunctive normal form, with the variables of the inputs and outputs of the network it encodes
+#[derive(Debug, Clone, Default)]
+pub struct Cnf {
+    vars: usize,
+    clauses: Vec<Vec<Lit>>,
+    inputs: Vec<(Identifier, Lit)>,
+    outputs: Vec<(Identifier, Lit)>,
+    /// The variable that is constrained to be true, for constants
+    truth: Option<Lit>,
+}
+
+impl Cnf {
+    /// Returns an empty formula
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the literal of a fresh variable
+    pub fn new_var(&mut self) -> Lit {
+        self.vars += 1;
+        Lit::new(self.vars - 1, false)
+    }
+
+    /// Adds the clause that at least one of `lits` is true
+    pub fn add_clause(&mut self, lits: &[Lit]) {
+        self.clauses.push(lits.to_vec());
+    }
+
+    /// Returns the number of variables
+    pub fn num_vars(&self) -> usize {
+        self.vars
+    }
+
+    /// Returns the clauses
+    pub fn clauses(&self) -> &[Vec<Lit>] {
+        &self.clauses
+    }
+
+    /// Returns the literals of the primary inputs, by name
+    pub fn inputs(&self) -> &[(Identifier, Lit)] {
+        &self.inputs
+    }
+
+    /// Returns the literals of the primary outputs, by name
+    pub fn outputs(&self) -> &[(Identifier, Lit)] {
+        &self.outputs
+    }
+
+    /// Returns the formula in the DIMACS format, with a comment line for every input and output
+    pub fn to_dimacs(&self) -> String {
+        let mut dimacs = String::new();
+        for (name, lit) in &self.inputs {
+            writeln!(dimacs, "c input {name} {lit}").unwrap();
+        }
+        for (name, lit) in &self.outputs {
+            writeln!(dimacs, "c output {name} {lit}").unwrap();
+        }
+        writeln!(dimacs, "p cnf {} {}", self.vars, self.clauses.len()).unwrap();
+        for clause in &self.clauses {
+            for lit in clause {
+                write!(dimacs, "{lit} ").unwrap();
+            }
+            writeln!(dimacs, "0").unwrap();
+        }
+        dimacs
+    }
+
+    /// Decides whether the clauses are satisfiable with every literal of `assumptions` true.
+    /// Returns [SatResult::Unknown] once the solver has seen `conflicts` conflicts, if given.
+    pub fn solve(&self, assumptions: &[Lit], conflicts: Option<usize>) -> SatResult {
+        let mut solver = Solver::new(self.vars);
+        let units = assumptions.iter().map(std::slice::from_ref);
+        for clause in self.clauses.iter().map(Vec::as_slice).chain(units) {
+            if !solver.add_clause(clause) {
+                return SatResult::Unsat;
+            }
+        }
+        solver.solve(conflicts)
+    }
+}
+
+impl NetworkBuilder for Cnf {
+    type Signal = Lit;
+
+    fn constant(&mut self, value: bool) -> Lit {
+        let truth = match self.truth {
+            Some(truth) => truth,
+            None => {
+                let truth = self.new_var();
+                self.add_clause(&[truth]);
+                self.truth = Some(truth);
+                truth
+            }
+        };
+        if value { truth } else { !truth }
+    }
+
+    fn create_pi(&mut self, name: &Identifier) -> Lit {
+        let lit = self.new_var();
+        self.inputs.push((name.clone(), lit));
+        lit
+    }
+
+    fn create_po(&mut self, signal: Lit, name: &Identifier) {
+        self.outputs.push((name.clone(), signal));
+    }
+
+    fn create_not(&mut self, a: &Lit) -> Lit {
+        !*a
+    }
+
+    fn create_and(&mut self, a: &Lit, b: &Lit) -> Lit {
+        let c = self.new_var();
+        self.add_clause(&[!c, *a]);
+        self.add_clause(&[!c, *b]);
+        self.add_clause(&[c, !*a, !*b]);
+        c
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Evaluate,
+{
+    /// Returns the Tseitin encoding of the netlist, see [Netlist::to_network] for the netlists that can be encoded
+    pub fn to_cnf(&self) -> Result<Cnf, Error> {
+        let mut cnf = Cnf::new();
+        self.to_network(&mut cnf)?;
+        Ok(cnf)
+    }
+}
+
+/// A conflict-driven clause learning solver with two watched literals per clause
+struct Solver {
+    /// The clauses of two literals or more, whose first two literals are watched
+    clauses: Vec<Vec<Lit>>,
+    /// The clauses that watch each literal, by literal index
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    /// The clause that implied each variable, whose first literal is the implied one
+    reasons: Vec<Option<usize>>,
+    activity: Vec<f64>,
+    increment: f64,
+    trail: Vec<Lit>,
+    /// The position in the trail of each decision
+    decisions: Vec<usize>,
+    /// The position in the trail of the next literal to propagate
+    head: usize,
+}
+
+impl Solver {
+    fn new(vars: usize) -> Self {
+        Self {
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); 2 * vars],
+            values: vec![None; vars],
+            levels: vec![0; vars],
+            reasons: vec![None; vars],
+            activity: vec![0.0; vars],
+            increment: 1.0,
+            trail: Vec::new(),
+            decisions: Vec::new(),
+            head: 0,
+        }
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.var()].map(|v| v != lit.is_negated())
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        self.values[lit.var()] = Some(!lit.is_negated());
+        self.levels[lit.var()] = self.decisions.len();
+        self.reasons[lit.var()] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Adds a clause before solving, and returns `false` if the clauses are already unsatisfiable
+    fn add_clause(&mut self, clause: &[Lit]) -> bool {
+        let mut clause = clause.to_vec();
+        clause.sort_unstable();
+        clause.dedup();
+        if clause.windows(2).any(|w| w[0] == !w[1]) {
+            return true;
+        }
+        clause.retain(|l| self.value(*l) != Some(false));
+        if clause.iter().any(|l| self.value(*l) == Some(true)) {
+            return true;
+        }
+        match clause.len() {
+            0 => false,
+            1 => {
+                self.assign(clause[0], None);
+                self.propagate().is_none()
+            }
+            _ => {
+                self.attach(clause);
+                true
+            }
+        }
+    }
+
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+        self.watches[clause[0].index()].push(index);
+        self.watches[clause[1].index()].push(index);
+        self.clauses.push(clause);
+        index
+    }
+
+    /// Propagates the assignments of the trail, and returns the clause that is left false, if any
+    fn propagate(&mut self) -> Option<usize> {
+        while self.head < self.trail.len() {
+            let falsified = !self.trail[self.head];
+            self.head += 1;
+            let watching = std::mem::take(&mut self.watches[falsified.index()]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (i, &c) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watching[i..]);
+                    break;
+                }
+                if self.clauses[c][0] == falsified {
+                    self.clauses[c].swap(0, 1);
+                }
+                let first = self.clauses[c][0];
+                if self.value(first) == Some(true) {
+                    kept.push(c);
+                    continue;
+                }
+                let replacement = (2..self.clauses[c].len())
+                    .find(|&k| self.value(self.clauses[c][k]) != Some(false));
+                if let Some(k) = replacement {
+                    self.clauses[c].swap(1, k);
+                    self.watches[self.clauses[c][1].index()].push(c);
+                    continue;
+                }
+                kept.push(c);
+                match self.value(first) {
+                    None => self.assign(first, Some(c)),
+                    _ => conflict = Some(c),
+                }
+            }
+            self.watches[falsified.index()] = kept;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Returns the first-UIP clause learnt from the `conflict`, with the asserting literal first and a literal
+    /// of the backtracking level second
+    fn analyze(&mut self, conflict: usize) -> Vec<Lit> {
+        let level = self.decisions.len();
+        let mut seen = vec![false; self.values.len()];
+        let mut learnt = vec![Lit(0)];
+        let mut pending = 0;
+        let mut position = self.trail.len();
+        let mut clause = conflict;
+        let mut implied: Option<Lit> = None;
+        loop {
+            let skip = usize::from(implied.is_some());
+            for k in skip..self.clauses[clause].len() {
+                let lit = self.clauses[clause][k];
+                let var = lit.var();
+                if seen[var] || self.levels[var] == 0 {
+                    continue;
+                }
+                seen[var] = true;
+                self.bump(var);
+                if self.levels[var] == level {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+            loop {
+                position -= 1;
+                if seen[self.trail[position].var()] {
+                    break;
+                }
+            }
+            let lit = self.trail[position];
+            seen[lit.var()] = false;
+            pending -= 1;
+            if pending == 0 {
+                learnt[0] = !lit;
+                break;
+            }
+            implied = Some(lit);
+            clause = self.reasons[lit.var()].expect("Implied literals have a reason");
+        }
+        if let Some(k) = (1..learnt.len()).max_by_key(|&k| self.levels[learnt[k].var()]) {
+            learnt.swap(1, k);
+        }
+        self.increment /= 0.95;
+        learnt
+    }
+
+    fn bump(&mut self, var: usize) {
+        self.activity[var] += self.increment;
+        if self.activity[var] > 1e100 {
+            self.activity.iter_mut().for_each(|a| *a *= 1e-100);
+            self.increment *= 1e-100;
+        }
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.decisions.len() <= level {
+            return;
+        }
+        for lit in self.trail.drain(self.decisions[level]..) {
+            self.values[lit.var()] = None;
+        }
+        self.decisions.truncate(level);
+        self.head = self.trail.len();
+    }
+
+    fn solve(&mut self, limit: Option<usize>) -> SatResult {
+        if self.propagate().is_some() {
+            return SatResult::Unsat;
+        }
+        let mut conflicts = 0;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.decisions.is_empty() {
+                    return SatResult::Unsat;
+                }
+                conflicts += 1;
+                if limit.is_some_and(|l| conflicts > l) {
+                    return SatResult::Unknown;
+                }
+                let learnt = self.analyze(conflict);
+                let level = learnt.get(1).map_or(0, |l| self.levels[l.var()]);
+                self.backtrack(level);
+                if learnt.len() == 1 {
+                    self.assign(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let clause = self.attach(learnt);
+                    self.assign(asserting, Some(clause));
+                }
+                continue;
+            }
+            let next = (0..self.values.len())
+                .filter(|&v| self.values[v].is_none())
+                .max_by(|&a, &b| {
+                    self.activity[a]
+                        .total_cmp(&self.activity[b])
+                        .then(b.cmp(&a))
+                });
+            let Some(var) = next else {
+                return SatResult::Sat(self.values.iter().map(|v| v == &Some(true)).collect());
+            };
+            self.decisions.push(self.trail.len());
+            self.assign(Lit::new(var, true), None);
+        }
+    }
+}
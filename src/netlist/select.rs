@@ -0,0 +1,569 @@
+/*!
+
+  Named selections of circuit nodes and nets, which scope passes to a region of the netlist.
+
+*/
+
+use super::{
+    DrivenNet, NetRef, Netlist,
+    annotation::{NetId, ObjectId},
+    memory::vec_bytes,
+};
+use crate::{
+    attribute::AttributeKey,
+    circuit::{Identifier, Instantiable},
+    error::Error,
+    graph::FanOutTable,
+    util::glob_match,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    ops::Bound,
+};
+
+/// Returns the name of `id` to match patterns against, with the index of bit-slices but without escaping
+pub(super) fn plain_name(id: &Identifier) -> String {
+    match id.get_bit_index() {
+        Some(index) => format!("{}[{}]", id.get_name(), index),
+        None => id.get_name().to_string(),
+    }
+}
+
+/// A pattern that names of instances and nets are matched against by [Netlist::query] and [Netlist::find_nets_matching]
+pub trait NamePattern {
+    /// Returns a prefix of every name that matches, so that only those names are visited
+    fn prefix(&self) -> &str;
+
+    /// Returns `true` if `name` matches the pattern
+    fn is_match(&self, name: &str) -> bool;
+}
+
+/// Strings are glob patterns, where `*` matches any sequence of characters and `?` matches a single character
+impl NamePattern for str {
+    fn prefix(&self) -> &str {
+        &self[..self.find(['*', '?']).unwrap_or(self.len())]
+    }
+
+    fn is_match(&self, name: &str) -> bool {
+        glob_match(self, name)
+    }
+}
+
+/// Regular expressions match a name if they match any part of it, so they must be anchored with `^` and `$`
+/// to match whole names. Every name in the index is visited.
+#[cfg(feature = "regex")]
+impl NamePattern for regex::Regex {
+    fn prefix(&self) -> &str {
+        ""
+    }
+
+    fn is_match(&self, name: &str) -> bool {
+        regex::Regex::is_match(self, name)
+    }
+}
+
+/// Returns the values of the names in `map` that match `pattern`.
+/// Only the names that start with the prefix of the pattern are visited.
+fn matching<'a, T, P: NamePattern + ?Sized>(
+    map: &'a BTreeMap<String, Vec<T>>,
+    pattern: &'a P,
+) -> impl Iterator<Item = &'a T> {
+    let prefix = pattern.prefix();
+    map.range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+        .take_while(move |(name, _)| name.starts_with(prefix))
+        .filter(move |(name, _)| pattern.is_match(name))
+        .flat_map(|(_, values)| values)
+}
+
+/// An index of the names of the instances and nets in a netlist, which is updated in place as circuit nodes are
+/// inserted, renamed and removed
+#[derive(Debug, Default)]
+pub(super) struct NameIndex {
+    /// The instances by name
+    instances: BTreeMap<String, Vec<ObjectId>>,
+    /// The nets by name
+    nets: BTreeMap<String, Vec<NetId>>,
+    /// The instance and net names each circuit node is indexed under
+    names: HashMap<ObjectId, (Option<String>, Vec<String>)>,
+    /// The circuit nodes that were inserted, removed or may have been renamed since they were indexed
+    stale: HashSet<ObjectId>,
+}
+
+/// Removes `value` from the entry of `name` in `map`, and the entry if it is left empty
+fn remove_entry<T: PartialEq>(map: &mut BTreeMap<String, Vec<T>>, name: &str, value: &T) {
+    if let Some(values) = map.get_mut(name) {
+        values.retain(|v| v != value);
+        if values.is_empty() {
+            map.remove(name);
+        }
+    }
+}
+
+impl NameIndex {
+    /// Returns the bytes used by the index
+    pub(super) fn footprint(&self) -> usize {
+        fn entries<T>(map: &BTreeMap<String, Vec<T>>) -> usize {
+            map.iter()
+                .map(|(k, v)| size_of::<(String, Vec<T>)>() + k.capacity() + vec_bytes(v))
+                .sum()
+        }
+        entries(&self.instances)
+            + entries(&self.nets)
+            + self
+                .names
+                .values()
+                .map(|(i, n)| {
+                    size_of::<(ObjectId, Option<String>, Vec<String>)>()
+                        + i.as_ref().map_or(0, String::capacity)
+                        + vec_bytes(n)
+                        + n.iter().map(String::capacity).sum::<usize>()
+                })
+                .sum::<usize>()
+            + self.stale.capacity() * size_of::<ObjectId>()
+    }
+
+    /// Indexes the names in `netlist`
+    fn build<I>(netlist: &Netlist<I>) -> Self
+    where
+        I: Instantiable,
+    {
+        let mut index = Self::default();
+        for obj in netlist.objects() {
+            index.insert(&obj);
+        }
+        index
+    }
+
+    /// Indexes the names of the circuit node `obj`
+    fn insert<I>(&mut self, obj: &NetRef<I>)
+    where
+        I: Instantiable,
+    {
+        let id = obj.get_id();
+        let instance = obj.get_instance_name().map(|name| plain_name(&name));
+        if let Some(name) = &instance {
+            self.instances.entry(name.clone()).or_default().push(id);
+        }
+        let mut nets = Vec::new();
+        for net in obj.outputs() {
+            let name = plain_name(net.as_net().get_identifier());
+            self.nets
+                .entry(name.clone())
+                .or_default()
+                .push(net.get_id());
+            nets.push(name);
+        }
+        self.names.insert(id, (instance, nets));
+    }
+
+    /// Removes the names of the circuit node `id` from the index
+    fn remove(&mut self, id: ObjectId) {
+        let Some((instance, nets)) = self.names.remove(&id) else {
+            return;
+        };
+        if let Some(name) = instance {
+            remove_entry(&mut self.instances, &name, &id);
+        }
+        for (output, name) in nets.iter().enumerate() {
+            remove_entry(&mut self.nets, name, &NetId { object: id, output });
+        }
+    }
+
+    /// Reindexes the stale circuit nodes that are still in `netlist`, and forgets the others
+    fn refresh<I>(&mut self, netlist: &Netlist<I>)
+    where
+        I: Instantiable,
+    {
+        for id in std::mem::take(&mut self.stale) {
+            self.remove(id);
+            if let Some(obj) = netlist.find_object(id) {
+                self.insert(&obj);
+            }
+        }
+    }
+}
+
+/// A named set of circuit nodes and nets.
+/// Members are kept by their stable identifiers, so a selection stays valid across edits,
+/// and the members that are removed from the netlist are skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    /// The name of the selection
+    name: String,
+    /// The selected circuit nodes
+    objects: BTreeSet<ObjectId>,
+    /// The selected nets
+    nets: BTreeSet<NetId>,
+}
+
+impl Selection {
+    /// Creates an empty selection
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Selects every circuit node and net of the netlist
+    pub fn all<I>(name: impl Into<String>, netlist: &Netlist<I>) -> Self
+    where
+        I: Instantiable,
+    {
+        let mut selection = Self::new(name);
+        for obj in netlist.objects() {
+            selection.insert_node(&obj);
+        }
+        selection
+    }
+
+    /// Selects the instances whose cell type matches the glob `pattern`
+    pub fn cell_type<I>(name: impl Into<String>, netlist: &Netlist<I>, pattern: &str) -> Self
+    where
+        I: Instantiable,
+    {
+        let mut selection = Self::new(name);
+        for obj in netlist.objects() {
+            if obj
+                .get_instance_type()
+                .is_some_and(|i| glob_match(pattern, &plain_name(i.get_name())))
+            {
+                selection.insert_object(&obj);
+            }
+        }
+        selection
+    }
+
+    /// Selects the instances whose name matches the glob `pattern`
+    pub fn instance_name<I>(name: impl Into<String>, netlist: &Netlist<I>, pattern: &str) -> Self
+    where
+        I: Instantiable,
+    {
+        let mut selection = Self::new(name);
+        for obj in netlist.query(pattern) {
+            selection.insert_object(&obj);
+        }
+        selection
+    }
+
+    /// Selects the nets whose name matches the glob `pattern`
+    pub fn net_name<I>(name: impl Into<String>, netlist: &Netlist<I>, pattern: &str) -> Self
+    where
+        I: Instantiable,
+    {
+        let mut selection = Self::new(name);
+        for net in netlist.find_nets_matching(pattern) {
+            selection.insert_net(&net);
+        }
+        selection
+    }
+
+    /// Selects the circuit nodes and nets that have an attribute with the key `k`
+    pub fn attribute<I>(name: impl Into<String>, netlist: &Netlist<I>, k: &AttributeKey) -> Self
+    where
+        I: Instantiable,
+    {
+        let mut selection = Self::new(name);
+        for obj in netlist.objects() {
+            if obj.has_attribute(k) {
+                selection.insert_object(&obj);
+            }
+            for net in obj.outputs() {
+                if netlist.net_has_attribute(&net.as_net(), k) {
+                    selection.insert_net(&net);
+                }
+            }
+        }
+        selection
+    }
+
+    /// Selects the transitive fan-in of `root`, along with `root` itself.
+    /// The cone ends at principal inputs and sequential cells, which are included.
+    pub fn fan_in_cone<I>(name: impl Into<String>, root: &DrivenNet<I>) -> Self
+    where
+        I: Instantiable,
+    {
+        let mut selection = Self::new(name);
+        selection.insert_net(root);
+        let root = root.clone().unwrap();
+        let mut stack = vec![root.clone()];
+        while let Some(node) = stack.pop() {
+            if !selection.objects.insert(node.get_id()) {
+                continue;
+            }
+            if node.is_an_input() || (node != root && node.get_instance_type().unwrap().is_seq()) {
+                continue;
+            }
+            for driver in node.inputs().filter_map(|i| i.get_driver()) {
+                selection.insert_net(&driver);
+                stack.push(driver.unwrap());
+            }
+        }
+        selection
+    }
+
+    /// Selects the transitive fan-out of `root`, along with `root` itself and the nets driven in the cone.
+    /// The cone ends at sequential cells, which are included.
+    pub fn fan_out_cone<I>(
+        name: impl Into<String>,
+        netlist: &Netlist<I>,
+        root: &DrivenNet<I>,
+    ) -> Result<Self, Error>
+    where
+        I: Instantiable,
+    {
+        let fan_out = netlist.get_analysis::<FanOutTable<I>>()?;
+        let mut selection = Self::new(name);
+        selection.insert_net(root);
+        selection.objects.insert(root.clone().unwrap().get_id());
+        let mut stack: Vec<NetRef<I>> = fan_out.get_net_users(&root.as_net()).collect();
+        while let Some(node) = stack.pop() {
+            if !selection.objects.insert(node.get_id()) {
+                continue;
+            }
+            for net in node.outputs() {
+                selection.insert_net(&net);
+            }
+            if !node.get_instance_type().unwrap().is_seq() {
+                stack.extend(fan_out.get_node_users(&node));
+            }
+        }
+        Ok(selection)
+    }
+
+    /// Returns the name of the selection
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Renames the selection
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    /// Adds a circuit node to the selection, returning `false` if it was already selected
+    pub fn insert_object<I>(&mut self, node: &NetRef<I>) -> bool
+    where
+        I: Instantiable,
+    {
+        self.objects.insert(node.get_id())
+    }
+
+    /// Adds a circuit node and the nets it drives to the selection
+    pub fn insert_node<I>(&mut self, node: &NetRef<I>)
+    where
+        I: Instantiable,
+    {
+        self.insert_object(node);
+        for net in node.outputs() {
+            self.insert_net(&net);
+        }
+    }
+
+    /// Adds a net to the selection, returning `false` if it was already selected
+    pub fn insert_net<I>(&mut self, net: &DrivenNet<I>) -> bool
+    where
+        I: Instantiable,
+    {
+        self.nets.insert(net.get_id())
+    }
+
+    /// Returns `true` if the circuit node is selected
+    pub fn contains_object<I>(&self, node: &NetRef<I>) -> bool
+    where
+        I: Instantiable,
+    {
+        self.objects.contains(&node.get_id())
+    }
+
+    /// Returns `true` if the net is selected
+    pub fn contains_net<I>(&self, net: &DrivenNet<I>) -> bool
+    where
+        I: Instantiable,
+    {
+        self.nets.contains(&net.get_id())
+    }
+
+    /// Returns the number of selected circuit nodes and nets
+    pub fn len(&self) -> usize {
+        self.objects.len() + self.nets.len()
+    }
+
+    /// Returns `true` if nothing is selected
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty() && self.nets.is_empty()
+    }
+
+    /// Returns the selection of the members of either selection, named after `self`
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            name: self.name.clone(),
+            objects: &self.objects | &other.objects,
+            nets: &self.nets | &other.nets,
+        }
+    }
+
+    /// Returns the selection of the members of both selections, named after `self`
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self {
+            name: self.name.clone(),
+            objects: &self.objects & &other.objects,
+            nets: &self.nets & &other.nets,
+        }
+    }
+
+    /// Returns the selection of the members of `self` that are not in `other`, named after `self`
+    pub fn difference(&self, other: &Self) -> Self {
+        Self {
+            name: self.name.clone(),
+            objects: &self.objects - &other.objects,
+            nets: &self.nets - &other.nets,
+        }
+    }
+
+    /// Returns the identifiers of the selected circuit nodes
+    pub fn object_ids(&self) -> impl Iterator<Item = ObjectId> {
+        self.objects.iter().copied()
+    }
+
+    /// Returns the identifiers of the selected nets
+    pub fn net_ids(&self) -> impl Iterator<Item = NetId> {
+        self.nets.iter().copied()
+    }
+
+    /// Returns the selected circuit nodes that are still in `netlist`
+    pub fn objects<'a, I>(&'a self, netlist: &'a Netlist<I>) -> impl Iterator<Item = NetRef<I>>
+    where
+        I: Instantiable,
+    {
+        self.object_ids().filter_map(|id| netlist.find_object(id))
+    }
+
+    /// Returns the selected nets that are still in `netlist`
+    pub fn nets<'a, I>(&'a self, netlist: &'a Netlist<I>) -> impl Iterator<Item = DrivenNet<I>>
+    where
+        I: Instantiable,
+    {
+        self.net_ids().filter_map(|id| netlist.find_net_by_id(id))
+    }
+}
+
+/// A summary of the contents of a netlist, or of a selection in it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of principal inputs
+    pub inputs: usize,
+    /// The number of instances
+    pub instances: usize,
+    /// The number of sequential instances
+    pub sequential: usize,
+    /// The number of nets
+    pub nets: usize,
+    /// The number of instances of each cell type
+    pub cells: BTreeMap<String, usize>,
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "inputs: {}", self.inputs)?;
+        writeln!(
+            f,
+            "instances: {} ({} sequential)",
+            self.instances, self.sequential
+        )?;
+        writeln!(f, "nets: {}", self.nets)?;
+        for (cell, count) in &self.cells {
+            writeln!(f, "  {cell}: {count}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Drops the name index, so that it is rebuilt by the next query, after edits that rename many nets at once
+    pub(super) fn invalidate_names(&self) {
+        self.name_index.borrow_mut().take();
+    }
+
+    /// Marks the names of the circuit node `id` as stale, after it is inserted or removed or before it may be renamed,
+    /// so that the next query reindexes it alone
+    pub(super) fn stale_names(&self, id: ObjectId) {
+        if let Some(index) = self.name_index.borrow_mut().as_mut() {
+            index.stale.insert(id);
+        }
+    }
+
+    /// Calls `f` on the name index, which is built by the first query and then kept up to date
+    fn with_name_index<R>(&self, f: impl FnOnce(&NameIndex) -> R) -> R {
+        let mut index = self
+            .name_index
+            .take()
+            .unwrap_or_else(|| NameIndex::build(self));
+        index.refresh(self);
+        let result = f(&index);
+        *self.name_index.borrow_mut() = Some(index);
+        result
+    }
+
+    /// Returns the instances whose name matches `pattern`, in the order they were inserted.
+    /// A string is a glob, where `*` matches any sequence of characters and `?` matches a single character,
+    /// and a [regex::Regex] is a regular expression with the `regex` feature, see [NamePattern].
+    /// Names are looked up in an index, which is built by the first query and updated as names change.
+    pub fn query<P: NamePattern + ?Sized>(&self, pattern: &P) -> Vec<NetRef<I>> {
+        let mut ids: Vec<ObjectId> =
+            self.with_name_index(|index| matching(&index.instances, pattern).copied().collect());
+        ids.sort();
+        ids.into_iter()
+            .filter_map(|id| self.find_object(id))
+            .collect()
+    }
+
+    /// Returns the nets whose name matches `pattern`, a glob or a regular expression like for [Netlist::query],
+    /// in the order they were inserted. Bit-slices are matched by their name and index, like `x[3]`.
+    pub fn find_nets_matching<P: NamePattern + ?Sized>(&self, pattern: &P) -> Vec<DrivenNet<I>> {
+        let mut ids: Vec<NetId> =
+            self.with_name_index(|index| matching(&index.nets, pattern).copied().collect());
+        ids.sort();
+        ids.into_iter()
+            .filter_map(|id| self.find_net_by_id(id))
+            .collect()
+    }
+
+    /// Greedily removes the unused circuit nodes in `selection`, until it stops changing.
+    /// Returns `true` if the netlist was changed.
+    pub fn clean_in(&self, selection: &Selection) -> Result<bool, Error> {
+        let mut changed = false;
+        while self.clean_once_where(|obj| selection.contains_object(obj))? {
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    /// Summarizes the circuit nodes and nets in `selection`
+    pub fn stats_of(&self, selection: &Selection) -> Stats {
+        let mut stats = Stats {
+            nets: selection.nets(self).count(),
+            ..Default::default()
+        };
+        for obj in selection.objects(self) {
+            let Some(inst) = obj.get_instance_type() else {
+                stats.inputs += 1;
+                continue;
+            };
+            stats.instances += 1;
+            if inst.is_seq() {
+                stats.sequential += 1;
+            }
+            *stats.cells.entry(plain_name(inst.get_name())).or_default() += 1;
+        }
+        stats
+    }
+
+    /// Summarizes the whole netlist
+    pub fn stats(&self) -> Stats {
+        self.stats_of(&Selection::all("all", self))
+    }
+}
@@ -0,0 +1,69 @@
+/*!
+
+  Utilities that edit the sequential structure of a netlist, like inserting pipeline stages.
+
+*/
+
+use super::{DrivenNet, Netlist, opt::move_loads};
+use crate::{
+    circuit::{Identifier, Instantiable},
+    error::Error,
+    format_id,
+    util::{debug_span, trace_event},
+};
+use std::{collections::HashSet, rc::Rc};
+
+/// Inserts `n_stages` registers on each net of `cut`, so that its loads read it `n_stages` cycles later.
+/// `ff_factory` inserts a register named `name` whose data input is driven by `d` and returns its output, so that it
+/// may connect the clock, enable and reset as the library needs. The registers of a net `x` are named `x_pipe0`,
+/// `x_pipe1`, ... from the driver onwards.
+/// The loads and top-level outputs of each net are moved onto its last stage, except the loads that are protected by
+/// a [crate::attribute::DONT_TOUCH] attribute. Returns the last stage of each net of the cut, in order.
+/// Returns [Error::NonuniqueNets] if a net is in the cut twice, before any edit, or the first error of `ff_factory`,
+/// leaving the stages inserted so far in place.
+pub fn pipeline<I>(
+    netlist: &Rc<Netlist<I>>,
+    cut: impl IntoIterator<Item = DrivenNet<I>>,
+    n_stages: usize,
+    ff_factory: impl Fn(&Rc<Netlist<I>>, DrivenNet<I>, Identifier) -> Result<DrivenNet<I>, Error>,
+) -> Result<Vec<DrivenNet<I>>, Error>
+where
+    I: Instantiable,
+{
+    debug_span!("pipeline", netlist = %netlist.get_name(), n_stages);
+    let cut: Vec<DrivenNet<I>> = cut.into_iter().collect();
+    let mut seen = HashSet::new();
+    let repeated: Vec<_> = cut
+        .iter()
+        .filter(|net| !seen.insert((*net).clone()))
+        .map(|net| net.as_net().clone())
+        .collect();
+    if !repeated.is_empty() {
+        return Err(Error::NonuniqueNets(repeated));
+    }
+    if n_stages == 0 {
+        return Ok(cut);
+    }
+
+    let mut result = Vec::with_capacity(cut.len());
+    for net in cut {
+        let loads: Vec<_> = netlist
+            .get_uses(&net)
+            .into_iter()
+            .filter(|load| !netlist.is_protected(&load.netref.netref.borrow()))
+            .collect();
+        let id = net.get_identifier();
+        let mut stage = net.clone();
+        for n in 0..n_stages {
+            stage = ff_factory(netlist, stage, &id + &format_id!("pipe{}", n))?;
+        }
+        move_loads(netlist, &net, &stage, &loads);
+        result.push(stage);
+    }
+    trace_event!(
+        debug,
+        stages = result.len() * n_stages,
+        "registers inserted"
+    );
+    Ok(result)
+}
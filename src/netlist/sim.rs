@@ -0,0 +1,429 @@
+/*!
+
+  Bit-parallel and four-state simulation of netlists.
+  With the `cosim` feature, `cosim` compares the simulation against external Verilog simulators.
+
+*/
+
+#[cfg(feature = "parallel")]
+use super::levels::Levels;
+use super::{DrivenNet, NetRef, Netlist};
+use crate::{
+    attribute::Parameter,
+    circuit::{Evaluate, Identifier, Instantiable, Net},
+    error::Error,
+    graph::TopoOrder,
+    logic::{Logic, LogicVec},
+    util::Rng,
+};
+use bitvec::vec::BitVec;
+use std::collections::HashMap;
+
+#[cfg(feature = "cosim")]
+mod cosim;
+#[cfg(feature = "cosim")]
+pub use cosim::{Backend, CosimOptions, CosimReport, cosim, cosim_with};
+
+/// Returns `true` if the outputs of `node` are free variables of the simulation
+pub(super) fn is_source<I: Instantiable>(node: &NetRef<I>) -> bool {
+    match node.get_instance_type() {
+        Some(inst) => inst.is_seq() || inst.is_blackbox() || inst.is_latch(),
+        None => true,
+    }
+}
+
+/// Returns the initial value of a register from its `INIT` parameter, if it is a known bit
+pub(super) fn init_value<I: Instantiable>(cell: &I) -> Option<bool> {
+    match cell.get_parameter(&"INIT".into())? {
+        Parameter::Logic(Logic::True) | Parameter::Integer(1) => Some(true),
+        Parameter::Logic(Logic::False) | Parameter::Integer(0) => Some(false),
+        _ => None,
+    }
+}
+
+/// A word forced onto a net or an input pin during simulation, like a stuck-at fault
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Force {
+    /// The output at a position of the circuit node at an index
+    Net(usize, usize, u64),
+    /// The input at a position of the circuit node at an index
+    Pin(usize, usize, u64),
+}
+
+/// A two-state simulator which evaluates 64 patterns at a time.
+/// The principal inputs and the outputs of sequential cells and black boxes are the free variables of the simulation.
+/// Like an [crate::graph::Analysis], the simulator becomes stale when the netlist is modified.
+/// Tri-state buses are not resolved: each reader sees the word of its own driver. Use [LogicSimulator] for those.
+pub struct Simulator<'a, I: Evaluate> {
+    /// A reference to the underlying netlist
+    _netlist: &'a Netlist<I>,
+    /// The circuit nodes in evaluation order
+    order: Vec<NetRef<I>>,
+    /// The last simulated word of each output, indexed by object
+    values: Vec<Vec<u64>>,
+}
+
+impl<'a, I> Simulator<'a, I>
+where
+    I: Evaluate,
+{
+    /// Prepares a simulator for `netlist`. Returns an error if the netlist has combinational cycles.
+    pub fn new(netlist: &'a Netlist<I>) -> Result<Self, Error> {
+        let order = netlist.get_analysis::<TopoOrder<I>>()?.get_order().to_vec();
+        let values = netlist
+            .objects()
+            .map(|o| vec![0; o.outputs().count()])
+            .collect();
+        Ok(Self {
+            _netlist: netlist,
+            order,
+            values,
+        })
+    }
+
+    /// Returns an iterator to the free variables of the simulation.
+    pub fn sources(&self) -> impl Iterator<Item = DrivenNet<I>> {
+        self.order
+            .iter()
+            .filter(|n| is_source(n))
+            .flat_map(|n| n.outputs())
+    }
+
+    /// Simulates the next 64 patterns, where `source` supplies the word of each free variable.
+    /// Unconnected input ports read as zero.
+    pub fn run(&mut self, source: impl FnMut(&DrivenNet<I>) -> u64) {
+        self.run_forced(source, None);
+    }
+
+    /// Simulates the next 64 patterns like [Simulator::run], with `force` overriding the word of a net or input pin
+    pub(super) fn run_forced(
+        &mut self,
+        mut source: impl FnMut(&DrivenNet<I>) -> u64,
+        force: Option<Force>,
+    ) {
+        for node in self.order.iter() {
+            let index = node.netref.borrow().get_index();
+            if is_source(node) {
+                for output in node.outputs() {
+                    self.values[index][output.pos] = source(&output);
+                }
+            } else {
+                let mut inputs: Vec<u64> = node
+                    .netref
+                    .borrow()
+                    .operands
+                    .iter()
+                    .map(|operand| match operand {
+                        Some(op) => self.values[op.root()][op.secondary()],
+                        None => 0,
+                    })
+                    .collect();
+                if let Some(Force::Pin(i, pos, word)) = force
+                    && i == index
+                {
+                    inputs[pos] = word;
+                }
+                let outputs = node.get_instance_type().unwrap().eval_words(&inputs);
+                for (value, word) in self.values[index].iter_mut().zip(outputs) {
+                    *value = word;
+                }
+            }
+            if let Some(Force::Net(i, pos, word)) = force
+                && i == index
+            {
+                self.values[index][pos] = word;
+            }
+        }
+    }
+
+    /// Returns the last simulated word of `net`.
+    pub fn get_word(&self, net: &DrivenNet<I>) -> u64 {
+        self.values[net.netref.netref.borrow().get_index()][net.pos]
+    }
+}
+
+/// A four-state simulator which evaluates one pattern at a time and resolves tri-state buses.
+/// Readers of a bus see the value of all its drivers combined by the [crate::logic::Resolution] of the net,
+/// and keeper buses hold their value from the previous run.
+/// Like an [crate::graph::Analysis], the simulator becomes stale when the netlist is modified.
+pub struct LogicSimulator<'a, I: Evaluate> {
+    /// A reference to the underlying netlist
+    netlist: &'a Netlist<I>,
+    /// The circuit nodes in evaluation order
+    order: Vec<NetRef<I>>,
+    /// The output nets of each object
+    nets: Vec<Vec<Net>>,
+    /// The last simulated value of each driver, indexed by object
+    values: Vec<Vec<Logic>>,
+    /// The drivers of each net with more than one, as object and output indices
+    buses: HashMap<Identifier, Vec<(usize, usize)>>,
+    /// The last resolved value of each bus
+    resolved: HashMap<Identifier, Logic>,
+}
+
+impl<'a, I> LogicSimulator<'a, I>
+where
+    I: Evaluate,
+{
+    /// Prepares a simulator for `netlist`. Returns an error if the netlist has combinational cycles.
+    pub fn new(netlist: &'a Netlist<I>) -> Result<Self, Error> {
+        let order = netlist.get_analysis::<TopoOrder<I>>()?.get_order().to_vec();
+        let nets: Vec<Vec<Net>> = netlist.objects().map(|o| o.nets().collect()).collect();
+        let values = nets.iter().map(|n| vec![Logic::X; n.len()]).collect();
+        let mut buses: HashMap<Identifier, Vec<(usize, usize)>> = HashMap::new();
+        for (index, outputs) in nets.iter().enumerate() {
+            for (pos, net) in outputs.iter().enumerate() {
+                buses
+                    .entry(net.get_identifier().clone())
+                    .or_default()
+                    .push((index, pos));
+            }
+        }
+        buses.retain(|_, drivers| drivers.len() > 1);
+        let resolved = buses.keys().map(|id| (id.clone(), Logic::Z)).collect();
+        Ok(Self {
+            netlist,
+            order,
+            nets,
+            values,
+            buses,
+            resolved,
+        })
+    }
+
+    /// Returns an iterator to the free variables of the simulation.
+    pub fn sources(&self) -> impl Iterator<Item = DrivenNet<I>> {
+        self.order
+            .iter()
+            .filter(|n| is_source(n))
+            .flat_map(|n| n.outputs())
+    }
+
+    /// Resolves the bus `id` from the current values of its drivers
+    fn resolve(&self, id: &Identifier) -> Logic {
+        let drivers = &self.buses[id];
+        let (i, p) = drivers[0];
+        self.netlist.get_resolution(&self.nets[i][p]).resolve(
+            drivers.iter().map(|(i, p)| self.values[*i][*p]),
+            self.resolved[id],
+        )
+    }
+
+    /// Returns the value read from output `pos` of object `index`
+    fn read(&self, index: usize, pos: usize) -> Logic {
+        let id = self.nets[index][pos].get_identifier();
+        match self.buses.contains_key(id) {
+            true => self.resolve(id),
+            false => self.values[index][pos],
+        }
+    }
+
+    /// Simulates the next pattern, where `source` supplies the value of each free variable.
+    /// Unconnected input ports read as unknown.
+    pub fn run(&mut self, mut source: impl FnMut(&DrivenNet<I>) -> Logic) {
+        for node in self.order.iter() {
+            let index = node.netref.borrow().get_index();
+            if is_source(node) {
+                for output in node.outputs() {
+                    self.values[index][output.pos] = source(&output);
+                }
+                continue;
+            }
+
+            let inputs: Vec<Logic> = node
+                .netref
+                .borrow()
+                .operands
+                .iter()
+                .map(|operand| match operand {
+                    Some(op) => self.read(op.root(), op.secondary()),
+                    None => Logic::X,
+                })
+                .collect();
+            let outputs = node.get_instance_type().unwrap().eval(&inputs);
+            for (value, v) in self.values[index].iter_mut().zip(outputs) {
+                *value = v;
+            }
+        }
+
+        let resolved: Vec<(Identifier, Logic)> = self
+            .buses
+            .keys()
+            .map(|id| (id.clone(), self.resolve(id)))
+            .collect();
+        self.resolved.extend(resolved);
+    }
+
+    /// Returns the last simulated value of `net`, resolving it if it is a bus.
+    pub fn get_value(&self, net: &DrivenNet<I>) -> Logic {
+        let id = net.as_net().get_identifier().clone();
+        match self.resolved.get(&id) {
+            Some(v) => *v,
+            None => self.values[net.netref.netref.borrow().get_index()][net.pos],
+        }
+    }
+
+    /// Returns the last simulated values of `nets` as a vector, where the first net is the least significant bit.
+    pub fn get_vec<'b>(&self, nets: impl IntoIterator<Item = &'b DrivenNet<I>>) -> LogicVec
+    where
+        I: 'b,
+    {
+        nets.into_iter().map(|n| self.get_value(n)).collect()
+    }
+
+    /// Returns the buses that had drivers contending with opposing values in the last run.
+    pub fn conflicts(&self) -> Vec<Net> {
+        self.buses
+            .iter()
+            .filter(|(_, drivers)| {
+                let values = drivers.iter().map(|(i, p)| self.values[*i][*p]);
+                let mut driven = values.filter(|v| *v != Logic::Z);
+                driven
+                    .next()
+                    .is_some_and(|first| driven.any(|v| v != first || v == Logic::X))
+            })
+            .map(|(_, drivers)| self.nets[drivers[0].0][drivers[0].1].clone())
+            .collect()
+    }
+}
+
+/// Returns the `word`-th word of pseudo-random patterns for the free variable `net`.
+/// Patterns only depend on the name of the net, so netlists that share names are stimulated identically.
+pub fn random_word(net: &Net, seed: u64, word: usize) -> u64 {
+    // FNV-1a is stable across platforms and runs, unlike the std hasher
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in net.get_identifier().to_string().bytes() {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    let word = (word as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    Rng::new(hash ^ seed.rotate_left(32) ^ word).next_u64()
+}
+
+impl<I> Netlist<I>
+where
+    I: Evaluate,
+{
+    /// Computes the simulation signature of every net under `n_patterns` pseudo-random patterns.
+    /// Bit `i` of a signature is the value of the net under pattern `i`, and the patterns of each
+    /// free variable are drawn with [random_word], so the result is reproducible for a given `seed`.
+    /// Nets with different signatures are certainly not equivalent, while nets with equal signatures
+    /// are probably equivalent. Returns an error if the netlist has combinational cycles.
+    pub fn signatures(
+        &self,
+        n_patterns: usize,
+        seed: u64,
+    ) -> Result<HashMap<DrivenNet<I>, BitVec>, Error> {
+        let mut sim = Simulator::new(self)?;
+        let nets: Vec<DrivenNet<I>> = self
+            .objects()
+            .flat_map(|o| o.outputs().collect::<Vec<_>>())
+            .collect();
+        let mut signatures: Vec<BitVec> = nets
+            .iter()
+            .map(|_| BitVec::with_capacity(n_patterns))
+            .collect();
+
+        for word in 0..n_patterns.div_ceil(64) {
+            sim.run(|n| random_word(&n.as_net(), seed, word));
+            let lanes = (n_patterns - word * 64).min(64);
+            for (net, signature) in nets.iter().zip(signatures.iter_mut()) {
+                let value = sim.get_word(net);
+                signature.extend((0..lanes).map(|lane| (value >> lane) & 1 == 1));
+            }
+        }
+
+        Ok(nets.into_iter().zip(signatures).collect())
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<I> Netlist<I>
+where
+    I: Evaluate + Send + Sync,
+{
+    /// Computes the same signatures as [Netlist::signatures], but evaluates the circuit nodes of each [Levels] level in parallel.
+    /// The cells are copied out of the netlist first, so the simulation itself does not touch the netlist.
+    /// Returns an error if the netlist has combinational cycles.
+    pub fn par_signatures(
+        &self,
+        n_patterns: usize,
+        seed: u64,
+    ) -> Result<HashMap<DrivenNet<I>, BitVec>, Error> {
+        let schedule = self.get_analysis::<Levels<I>>()?.get_schedule().clone();
+        let n_words = n_patterns.div_ceil(64);
+        let cells: Vec<Option<I>> = self
+            .objects()
+            .map(|o| o.get_instance_type().map(|i| i.clone()))
+            .collect();
+        let nets: Vec<Vec<Net>> = self.objects().map(|o| o.nets().collect()).collect();
+
+        // The words of each output of each object
+        let values = schedule.map_levels(Vec::new(), |index, values: &[Vec<Vec<u64>>]| {
+            if schedule.sources[index] {
+                return nets[index]
+                    .iter()
+                    .map(|net| (0..n_words).map(|w| random_word(net, seed, w)).collect())
+                    .collect();
+            }
+            let cell = cells[index].as_ref().unwrap();
+            let mut outputs = vec![Vec::with_capacity(n_words); nets[index].len()];
+            let drivers: Vec<Option<&Vec<u64>>> = schedule.operands[index]
+                .iter()
+                .map(|op| op.map(|(root, pos)| &values[root][pos]))
+                .collect();
+            for word in 0..n_words {
+                let inputs: Vec<u64> = drivers
+                    .iter()
+                    .map(|driver| driver.map_or(0, |words| words[word]))
+                    .collect();
+                for (output, value) in outputs.iter_mut().zip(cell.eval_words(&inputs)) {
+                    output.push(value);
+                }
+            }
+            outputs
+        });
+
+        let mut signatures = HashMap::new();
+        for (obj, words) in self.objects().zip(values) {
+            for (net, words) in obj.outputs().zip(words) {
+                let mut signature = BitVec::with_capacity(n_patterns);
+                for (word, value) in words.into_iter().enumerate() {
+                    let lanes = (n_patterns - word * 64).min(64);
+                    signature.extend((0..lanes).map(|lane| (value >> lane) & 1 == 1));
+                }
+                signatures.insert(net, signature);
+            }
+        }
+        Ok(signatures)
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Evaluate,
+{
+    /// Checks for contention on the tri-state buses of the netlist under `n_patterns` pseudo-random patterns,
+    /// drawn as in [Netlist::signatures]. Structurally, [Netlist::verify] allows tri-statable cells to share a net,
+    /// but this rejects buses where two drivers are simultaneously enabled with different values.
+    /// Returns an error listing the conflicting nets, or if the netlist has combinational cycles.
+    pub fn verify_buses(&self, n_patterns: usize, seed: u64) -> Result<(), Error> {
+        let mut sim = LogicSimulator::new(self)?;
+        let mut conflicts: Vec<Net> = Vec::new();
+        for pattern in 0..n_patterns {
+            let (word, lane) = (pattern / 64, pattern % 64);
+            sim.run(|n| Logic::from_bool((random_word(&n.as_net(), seed, word) >> lane) & 1 == 1));
+            for net in sim.conflicts() {
+                if !conflicts.contains(&net) {
+                    conflicts.push(net);
+                }
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::BusConflict(conflicts))
+        }
+    }
+}
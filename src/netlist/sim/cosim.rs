@@ -0,0 +1,189 @@
+/*!
+
+  Co-simulation with external Verilog simulators, to cross-check the simulator and the Verilog emission.
+
+*/
+
+use crate::{
+    circuit::Evaluate,
+    error::Error,
+    logic::Logic,
+    netlist::{
+        Netlist,
+        stimulus::{Mismatch, Stimulus},
+    },
+};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// An external Verilog simulator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Icarus Verilog, run with `iverilog` and `vvp`
+    Icarus,
+    /// Verilator, run with `verilator --binary`
+    Verilator,
+}
+
+/// Options for [cosim_with]
+#[derive(Debug, Clone, Default)]
+pub struct CosimOptions {
+    /// The Verilog files with the models of the cells, which are compiled along with the netlist
+    pub libraries: Vec<PathBuf>,
+    /// The directory of the generated files, which is kept. By default, a fresh temporary directory is removed afterwards.
+    pub dir: Option<PathBuf>,
+}
+
+/// The mismatches found by the simulator and by an external simulator under the same [Stimulus]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CosimReport {
+    /// The mismatches of [Stimulus::mismatches]
+    pub internal: Vec<Mismatch>,
+    /// The mismatches reported by the testbench in the external simulator
+    pub external: Vec<Mismatch>,
+    /// The output of the external simulator
+    pub log: String,
+}
+
+impl CosimReport {
+    /// Returns `true` if both simulators report the same mismatches, or none
+    pub fn agrees(&self) -> bool {
+        self.internal == self.external
+    }
+
+    /// Returns `true` if both simulators see every expected value
+    pub fn passes(&self) -> bool {
+        self.internal.is_empty() && self.external.is_empty()
+    }
+}
+
+/// Simulates `netlist` under `vectors` both with the [super::LogicSimulator] and with an external simulator, see [cosim_with]
+pub fn cosim<I>(
+    netlist: &Netlist<I>,
+    vectors: &Stimulus,
+    backend: Backend,
+) -> Result<CosimReport, Error>
+where
+    I: Evaluate,
+{
+    cosim_with(netlist, vectors, backend, &CosimOptions::default())
+}
+
+/// Simulates `netlist` under `vectors` both with the [super::LogicSimulator] and with an external simulator:
+/// writes the netlist and its [Netlist::write_testbench] to a directory, compiles them with the cell libraries of
+/// `options`, runs the testbench, and reads the mismatches it displays back.
+/// Returns [Error::IoError] if the simulator cannot be run or fails, or the errors of [Stimulus::mismatches].
+pub fn cosim_with<I>(
+    netlist: &Netlist<I>,
+    vectors: &Stimulus,
+    backend: Backend,
+    options: &CosimOptions,
+) -> Result<CosimReport, Error>
+where
+    I: Evaluate,
+{
+    let internal = vectors.mismatches(netlist)?;
+    let dir = match &options.dir {
+        Some(dir) => dir.clone(),
+        None => {
+            static RUNS: AtomicUsize = AtomicUsize::new(0);
+            let run = RUNS.fetch_add(1, Ordering::Relaxed);
+            std::env::temp_dir().join(format!("safety-net-cosim-{}-{run}", std::process::id()))
+        }
+    };
+    std::fs::create_dir_all(&dir)?;
+    let result = run(netlist, vectors, backend, options, &dir);
+    if options.dir.is_none() {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    let log = result?;
+    Ok(CosimReport {
+        internal,
+        external: parse_mismatches(&log),
+        log,
+    })
+}
+
+/// Writes the netlist and testbench to `dir`, and returns the output of the testbench
+fn run<I>(
+    netlist: &Netlist<I>,
+    vectors: &Stimulus,
+    backend: Backend,
+    options: &CosimOptions,
+    dir: &Path,
+) -> Result<String, Error>
+where
+    I: Evaluate,
+{
+    let design = dir.join("design.v");
+    std::fs::write(&design, netlist.to_string())?;
+    let testbench = dir.join("testbench.v");
+    let mut file = std::fs::File::create(&testbench)?;
+    netlist.write_testbench(&mut file, vectors)?;
+    drop(file);
+    let top = format!("{}_tb", netlist.get_name());
+    let sources: Vec<&Path> = [testbench.as_path(), design.as_path()]
+        .into_iter()
+        .chain(options.libraries.iter().map(|p| p.as_path()))
+        .collect();
+
+    match backend {
+        Backend::Icarus => {
+            let compiled = dir.join("testbench.vvp");
+            execute(
+                Command::new("iverilog")
+                    .args(["-g2012", "-s", &top, "-o"])
+                    .arg(&compiled)
+                    .args(&sources),
+            )?;
+            execute(Command::new("vvp").arg("-n").arg(&compiled))
+        }
+        Backend::Verilator => {
+            let build = dir.join("obj_dir");
+            execute(
+                Command::new("verilator")
+                    .args(["--binary", "-Wno-fatal", "--top-module", &top, "--Mdir"])
+                    .arg(&build)
+                    .args(&sources),
+            )?;
+            execute(&mut Command::new(build.join(format!("V{top}"))))
+        }
+    }
+}
+
+/// Runs `command` and returns its standard output, or an error with its standard error if it fails
+fn execute(command: &mut Command) -> Result<String, Error> {
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(Error::IoError(std::io::Error::other(format!(
+            "{:?} failed: {}",
+            command.get_program(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads the mismatches displayed by a testbench of [Netlist::write_testbench], like
+/// `At cycle 3, expected y = 1'b1, got 0`
+fn parse_mismatches(log: &str) -> Vec<Mismatch> {
+    log.lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("At cycle ")?;
+            let (cycle, rest) = rest.split_once(", expected ")?;
+            let (net, rest) = rest.rsplit_once(" = ")?;
+            let (expected, actual) = rest.split_once(", got ")?;
+            Some(Mismatch {
+                cycle: cycle.parse().ok()?,
+                net: net.into(),
+                expected: expected.parse().ok()?,
+                actual: format!("1'b{}", actual.trim().to_lowercase())
+                    .parse()
+                    .unwrap_or(Logic::X),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,479 @@
+/*!
+
+  A compact binary snapshot of a netlist (`.snet`), which loads much faster than JSON or Verilog.
+
+  A snapshot is a 16-byte header followed by a body, which is compressed with zstd when flag `1` of the header is set.
+  All integers are little-endian `u32`s, except in the header, and every section of the body is aligned to 4 bytes,
+  so that an uncompressed snapshot can be read in place from a memory map.
+
+  | Section | Layout |
+  |---------|--------|
+  | Header  | `SNET`, version `u16`, flags `u16`, length of the uncompressed body `u64` |
+  | Counts  | the number of strings, cells, objects, pins and outputs |
+  | Strings | `strings + 1` offsets into the bytes that follow, padded to 4 bytes |
+  | Cells   | the string of each distinct cell type, serialized as JSON |
+  | Objects | 6 words each: kind (0 for an input, 1 for an instance), instance name, cell, first pin, number of inputs and of outputs |
+  | Pins    | 4 words each: the object and output driving an input pin, or the name and data type of an output net |
+  | Outputs | 4 words each: the object and output driving the port, and the name and data type of the port |
+  | Rest    | the string of the name of the netlist, and the string of its attributes, provenance, port order, net resolutions and aliases as JSON |
+
+  Strings are referred to by index, and missing references are `u32::MAX`.
+  A direct reference to an object, as for an input, has `u32::MAX` as its output.
+
+*/
+
+use super::{Netlist, ObjectId, Operand, Operands, OwnedObject, Provenance, sort_key};
+use crate::{
+    attribute::{AttributeKey, AttributeValue},
+    circuit::{DataType, Identifier, Instantiable, Net, Object},
+    error::Error,
+    logic::Resolution,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+/// The first bytes of a snapshot
+const MAGIC: &[u8; 4] = b"SNET";
+/// The version of the format
+const VERSION: u16 = 1;
+/// The header flag for a body compressed with zstd
+const COMPRESSED: u16 = 1;
+/// A missing reference
+const NONE: u32 = u32::MAX;
+
+/// The data of a netlist that is rare enough to be stored as JSON.
+/// Maps are sorted by key, so that the same netlist is always written to the same bytes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Rest {
+    /// The attributes of each object that has some
+    object_attributes: Vec<(u32, BTreeMap<AttributeKey, AttributeValue>)>,
+    /// The provenance of each object that has one
+    object_provenance: Vec<(u32, Provenance)>,
+    port_order: Vec<Identifier>,
+    resolutions: Vec<(Identifier, Resolution)>,
+    attributes: BTreeMap<AttributeKey, AttributeValue>,
+    net_attributes: Vec<(Identifier, BTreeMap<AttributeKey, AttributeValue>)>,
+    net_provenance: Vec<(Identifier, Provenance)>,
+    /// The aliases of nets and the operands they name
+    #[serde(default)]
+    aliases: Vec<(String, Net)>,
+}
+
+fn data_type_code(data_type: DataType) -> u32 {
+    match data_type {
+        DataType::TwoState => 0,
+        DataType::ThreeState => 1,
+        DataType::FourState => 2,
+    }
+}
+
+fn data_type(code: u32) -> Result<DataType, Error> {
+    match code {
+        0 => Ok(DataType::TwoState),
+        1 => Ok(DataType::ThreeState),
+        2 => Ok(DataType::FourState),
+        _ => Err(malformed("data type")),
+    }
+}
+
+fn malformed(what: &str) -> Error {
+    Error::ParseError(format!("Malformed snapshot: invalid {what}"))
+}
+
+#[cfg(feature = "zstd")]
+fn compress(body: &[u8], level: i32) -> Result<Vec<u8>, Error> {
+    Ok(zstd::bulk::compress(body, level)?)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn compress(_body: &[u8], _level: i32) -> Result<Vec<u8>, Error> {
+    Err(Error::Unsupported(
+        "Compressing a snapshot requires the zstd feature".to_string(),
+    ))
+}
+
+/// Decompresses at most `len + 1` bytes, which grow with the data rather than being allocated from the header
+#[cfg(feature = "zstd")]
+fn decompress(payload: &[u8], len: u64) -> Result<Vec<u8>, Error> {
+    use std::io::Read;
+    let mut body = Vec::new();
+    zstd::stream::read::Decoder::new(payload)?
+        .take(len.saturating_add(1))
+        .read_to_end(&mut body)?;
+    Ok(body)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress(_payload: &[u8], _len: u64) -> Result<Vec<u8>, Error> {
+    Err(Error::Unsupported(
+        "Loading a compressed snapshot requires the zstd feature".to_string(),
+    ))
+}
+
+/// Encodes the body of a snapshot
+#[derive(Default)]
+struct Writer {
+    strings: Vec<String>,
+    string_index: HashMap<String, u32>,
+    cells: Vec<u32>,
+    cell_index: HashMap<u32, u32>,
+    objects: Vec<[u32; 6]>,
+    pins: Vec<[u32; 4]>,
+    outputs: Vec<[u32; 4]>,
+}
+
+impl Writer {
+    fn string(&mut self, s: &str) -> u32 {
+        if let Some(index) = self.string_index.get(s) {
+            return *index;
+        }
+        let index = self.strings.len() as u32;
+        self.strings.push(s.to_string());
+        self.string_index.insert(s.to_string(), index);
+        index
+    }
+
+    fn identifier(&mut self, id: &Identifier) -> u32 {
+        self.string(&id.as_verilog())
+    }
+
+    fn cell<I: Instantiable + Serialize>(&mut self, cell: &I) -> Result<u32, Error> {
+        let json = serde_json::to_string(cell).map_err(|e| Error::ParseError(e.to_string()))?;
+        let string = self.string(&json);
+        let next = self.cells.len() as u32;
+        let index = *self.cell_index.entry(string).or_insert(next);
+        if index == next {
+            self.cells.push(string);
+        }
+        Ok(index)
+    }
+
+    fn operand(operand: Option<&Operand>) -> [u32; 2] {
+        match operand {
+            None => [NONE, NONE],
+            Some(Operand::DirectIndex(i)) => [*i as u32, NONE],
+            Some(Operand::CellIndex(i, j)) => [*i as u32, *j as u32],
+        }
+    }
+
+    fn finish(self, name: u32, rest: u32) -> Vec<u8> {
+        let mut words = vec![
+            self.strings.len() as u32,
+            self.cells.len() as u32,
+            self.objects.len() as u32,
+            self.pins.len() as u32,
+            self.outputs.len() as u32,
+        ];
+        let mut offset = 0;
+        words.push(offset);
+        for s in &self.strings {
+            offset += s.len() as u32;
+            words.push(offset);
+        }
+        let mut body: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        for s in &self.strings {
+            body.extend_from_slice(s.as_bytes());
+        }
+        body.resize(body.len().next_multiple_of(4), 0);
+        let records = self
+            .cells
+            .iter()
+            .chain(self.objects.iter().flatten())
+            .chain(self.pins.iter().flatten())
+            .chain(self.outputs.iter().flatten())
+            .chain([&name, &rest]);
+        body.extend(records.flat_map(|w| w.to_le_bytes()));
+        body
+    }
+}
+
+/// Decodes the body of a snapshot
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| malformed("length"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn word(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn words<const N: usize>(&mut self, count: u32) -> Result<Vec<[u32; N]>, Error> {
+        if (count as usize).saturating_mul(4 * N) > self.bytes.len() - self.pos {
+            return Err(malformed("count"));
+        }
+        (0..count)
+            .map(|_| {
+                let mut record = [0; N];
+                for w in record.iter_mut() {
+                    *w = self.word()?;
+                }
+                Ok(record)
+            })
+            .collect()
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable + Serialize,
+{
+    /// Returns the netlist as a `.snet` snapshot, which is compressed with zstd at `level` when given.
+    /// Levels are those of zstd, where `0` is its default, and compression requires the `zstd` feature.
+    pub fn to_snet(&self, level: Option<i32>) -> Result<Vec<u8>, Error> {
+        let mut writer = Writer::default();
+        let mut rest = Rest::default();
+        for (index, oref) in self.objects.borrow().iter().enumerate() {
+            let owned = oref.borrow();
+            let first_pin = writer.pins.len() as u32;
+            let record = match &owned.object {
+                Object::Input(net) => {
+                    let name = writer.identifier(net.get_identifier());
+                    let ty = data_type_code(*net.get_type());
+                    writer.pins.push([NONE, NONE, name, ty]);
+                    [0, NONE, NONE, first_pin, 0, 1]
+                }
+                Object::Instance(nets, name, cell) => {
+                    let name = writer.identifier(name);
+                    let cell = writer.cell(cell)?;
+                    for operand in &owned.operands {
+                        let [object, output] = Writer::operand(operand.as_ref());
+                        writer.pins.push([object, output, NONE, NONE]);
+                    }
+                    for net in nets {
+                        let id = writer.identifier(net.get_identifier());
+                        writer
+                            .pins
+                            .push([NONE, NONE, id, data_type_code(*net.get_type())]);
+                    }
+                    let inputs = owned.operands.len() as u32;
+                    [1, name, cell, first_pin, inputs, nets.len() as u32]
+                }
+            };
+            writer.objects.push(record);
+            if !owned.attributes.is_empty() {
+                rest.object_attributes
+                    .push((index as u32, owned.attributes.clone().into_iter().collect()));
+            }
+            if let Some(provenance) = &owned.provenance {
+                rest.object_provenance
+                    .push((index as u32, provenance.clone()));
+            }
+        }
+        for (operand, net) in self.outputs.borrow().iter() {
+            let [object, output] = Writer::operand(operand.as_ref());
+            let name = writer.identifier(net.get_identifier());
+            let ty = data_type_code(*net.get_type());
+            writer.outputs.push([object, output, name, ty]);
+        }
+        rest.port_order = self.port_order.borrow().clone();
+        rest.resolutions = self.resolutions.borrow().clone().into_iter().collect();
+        rest.resolutions
+            .sort_by(|(a, _), (b, _)| sort_key(a).cmp(&sort_key(b)));
+        rest.attributes = self.attributes.borrow().clone().into_iter().collect();
+        rest.net_attributes = self
+            .net_attributes
+            .borrow()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone().into_iter().collect()))
+            .collect();
+        rest.net_attributes
+            .sort_by(|(a, _), (b, _)| sort_key(a).cmp(&sort_key(b)));
+        rest.net_provenance = self.net_provenance.borrow().clone().into_iter().collect();
+        rest.net_provenance
+            .sort_by(|(a, _), (b, _)| sort_key(a).cmp(&sort_key(b)));
+        rest.aliases = self
+            .aliases
+            .borrow()
+            .iter()
+            .map(|(o, n)| (o.to_string(), n.clone()))
+            .collect();
+        let rest = serde_json::to_string(&rest).map_err(|e| Error::ParseError(e.to_string()))?;
+        let name = writer.string(&self.get_name());
+        let rest = writer.string(&rest);
+        let body = writer.finish(name, rest);
+
+        let mut bytes = Vec::with_capacity(16 + body.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        let flags = if level.is_some() { COMPRESSED } else { 0 };
+        bytes.extend_from_slice(&flags.to_le_bytes());
+        bytes.extend_from_slice(&(body.len() as u64).to_le_bytes());
+        match level {
+            Some(level) => bytes.extend_from_slice(&compress(&body, level)?),
+            None => bytes.extend_from_slice(&body),
+        }
+        Ok(bytes)
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable + Serialize + DeserializeOwned,
+{
+    /// Loads a netlist from a `.snet` snapshot made by [Netlist::to_snet], like the contents of a file or a memory map.
+    /// Object identifiers are renumbered, as with the other serialization formats.
+    /// Returns [Error::ParseError] if the snapshot is malformed.
+    pub fn from_snet(bytes: &[u8]) -> Result<Rc<Self>, Error> {
+        if bytes.len() < 16 || &bytes[..4] != MAGIC {
+            return Err(malformed("header"));
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != VERSION {
+            return Err(Error::ParseError(format!(
+                "Unsupported snapshot version {version}"
+            )));
+        }
+        let flags = u16::from_le_bytes([bytes[6], bytes[7]]);
+        let len = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        let decompressed;
+        let body = if flags & COMPRESSED == 0 {
+            &bytes[16..]
+        } else {
+            decompressed = decompress(&bytes[16..], len)?;
+            &decompressed[..]
+        };
+        if body.len() as u64 != len {
+            return Err(malformed("length"));
+        }
+
+        let mut reader = Reader {
+            bytes: body,
+            pos: 0,
+        };
+        let [n_strings, n_cells, n_objects, n_pins, n_outputs] = reader.words::<5>(1)?[0];
+        let offsets: Vec<u32> = reader
+            .words::<1>(n_strings.checked_add(1).ok_or_else(|| malformed("count"))?)?
+            .into_iter()
+            .map(|[w]| w)
+            .collect();
+        let text = reader.bytes(*offsets.last().unwrap() as usize)?;
+        reader.bytes(reader.pos.next_multiple_of(4) - reader.pos)?;
+        let string = |index: u32| -> Result<&str, Error> {
+            let index = index as usize;
+            let (start, end) = match offsets.get(index..index + 2) {
+                Some([start, end]) if start <= end => (*start as usize, *end as usize),
+                _ => return Err(malformed("string")),
+            };
+            std::str::from_utf8(text.get(start..end).ok_or_else(|| malformed("string"))?)
+                .map_err(|_| malformed("string"))
+        };
+        let identifier = |index: u32| string(index).map(|s| Identifier::new(s.to_string()));
+        let net = |name: u32, ty: u32| Ok(Net::new(identifier(name)?, data_type(ty)?));
+
+        let cells = reader
+            .words::<1>(n_cells)?
+            .into_iter()
+            .map(|[s]| {
+                serde_json::from_str::<I>(string(s)?).map_err(|e| Error::ParseError(e.to_string()))
+            })
+            .collect::<Result<Vec<I>, Error>>()?;
+        let objects = reader.words::<6>(n_objects)?;
+        // A direct reference is to an input or a single-output instance, and an output is of an instance
+        let operand = |object: u32, output: u32| -> Result<Option<Operand>, Error> {
+            if object == NONE {
+                return Ok(None);
+            }
+            let [kind, .., outputs] = objects
+                .get(object as usize)
+                .ok_or_else(|| malformed("operand"))?;
+            match (*kind, output) {
+                (0, NONE) => Ok(Some(Operand::DirectIndex(object as usize))),
+                (1, NONE) if *outputs == 1 => Ok(Some(Operand::DirectIndex(object as usize))),
+                (1, j) if j < *outputs => Ok(Some(Operand::CellIndex(object as usize, j as usize))),
+                _ => Err(malformed("operand")),
+            }
+        };
+        let pins = reader.words::<4>(n_pins)?;
+        let outputs = reader.words::<4>(n_outputs)?;
+        let [name, rest] = reader.words::<2>(1)?[0];
+        let mut rest: Rest =
+            serde_json::from_str(string(rest)?).map_err(|e| Error::ParseError(e.to_string()))?;
+        let netlist = Netlist::new(string(name)?.to_string());
+
+        let mut attributes: HashMap<u32, _> = rest.object_attributes.drain(..).collect();
+        let mut provenance: HashMap<u32, _> = rest.object_provenance.drain(..).collect();
+        let mut owned_objects = Vec::with_capacity(objects.len());
+        for (index, &[kind, name, cell, first_pin, inputs, outputs]) in objects.iter().enumerate() {
+            let first = first_pin as usize;
+            let pins = pins
+                .get(first..first + inputs as usize + outputs as usize)
+                .ok_or_else(|| malformed("pins"))?;
+            let (input_pins, output_pins) = pins.split_at(inputs as usize);
+            let nets = output_pins
+                .iter()
+                .map(|[_, _, name, ty]| net(*name, *ty))
+                .collect::<Result<Vec<Net>, Error>>()?;
+            let object = match kind {
+                0 => Object::Input(nets.into_iter().next().ok_or_else(|| malformed("input"))?),
+                1 => {
+                    let cell = cells.get(cell as usize).ok_or_else(|| malformed("cell"))?;
+                    Object::Instance(nets, identifier(name)?, cell.clone())
+                }
+                _ => return Err(malformed("object")),
+            };
+            let operands = input_pins
+                .iter()
+                .map(|[object, output, _, _]| operand(*object, *output))
+                .collect::<Result<Operands, Error>>()?;
+            owned_objects.push(Rc::new(RefCell::new(OwnedObject {
+                object,
+                owner: Rc::downgrade(&netlist),
+                operands,
+                attributes: attributes
+                    .remove(&(index as u32))
+                    .map(|a| a.into_iter().collect())
+                    .unwrap_or_default(),
+                provenance: provenance.remove(&(index as u32)),
+                payloads: Default::default(),
+                mark: 0,
+                index,
+                id: ObjectId(index),
+            })));
+        }
+        let outputs = outputs
+            .into_iter()
+            .map(|[object, output, name, ty]| Ok((operand(object, output)?, net(name, ty)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        netlist.next_id.set(owned_objects.len());
+        *netlist.objects.borrow_mut() = owned_objects;
+        *netlist.outputs.borrow_mut() = outputs;
+        *netlist.port_order.borrow_mut() = rest.port_order;
+        *netlist.resolutions.borrow_mut() = rest.resolutions.into_iter().collect();
+        *netlist.attributes.borrow_mut() = rest.attributes.into_iter().collect();
+        *netlist.net_attributes.borrow_mut() = rest
+            .net_attributes
+            .into_iter()
+            .map(|(k, v)| (k, v.into_iter().collect()))
+            .collect();
+        *netlist.net_provenance.borrow_mut() = rest.net_provenance.into_iter().collect();
+        *netlist.aliases.borrow_mut() = rest
+            .aliases
+            .into_iter()
+            .map(|(o, n)| {
+                let operand = o
+                    .parse::<Operand>()
+                    .map_err(|e| Error::ParseError(e.to_string()))?;
+                Ok((operand, n))
+            })
+            .collect::<Result<_, Error>>()?;
+        netlist.rebuild_uses();
+        Ok(netlist)
+    }
+}
@@ -0,0 +1,364 @@
+/*!
+
+  Functional tests of netlists as waveforms of input values and expected output values, cycle by cycle.
+
+  A [Stimulus] drives the principal inputs of a netlist with a value per clock cycle, simulates it with a
+  [LogicSimulator], and compares the nets it expects against their expected values, like
+  `Stimulus::clock("clk").signal("a", [0, 1, 1, 0]).expect("y", [0, 0, 1, 1])`.
+  [Netlist::write_testbench] writes the same stimulus as a self-checking Verilog testbench, to cross-check the
+  simulator against external simulators like Icarus Verilog or Verilator.
+
+*/
+
+use super::{
+    Declarations, DrivenNet, NetRef, Netlist, PortDirection, sim::LogicSimulator, sim::init_value,
+};
+use crate::{
+    circuit::{Evaluate, Identifier, Instantiable, Net},
+    error::Error,
+    logic::Logic,
+};
+use std::{collections::HashMap, io::Write};
+
+/// The values of principal inputs and the expected values of nets over clock cycles.
+/// Every cycle ends with a rising edge of the clock, which moves all the registers to their next state:
+/// the [Evaluate] function of their inputs. The clock input itself is held low, and registers start from
+/// the value of their `INIT` parameter, or unknown.
+#[derive(Debug, Clone, Default)]
+pub struct Stimulus {
+    /// The clock input, if there is one
+    clock: Option<Identifier>,
+    /// The value of each input on each cycle. An input keeps its last value once its values run out.
+    signals: Vec<(Identifier, Vec<Logic>)>,
+    /// The expected value of each net on each cycle
+    expects: Vec<(Identifier, Vec<Logic>)>,
+}
+
+/// A net that differs from its expected value on a cycle of a [Stimulus]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The cycle, from zero
+    pub cycle: usize,
+    /// The output port or net
+    pub net: Identifier,
+    /// The expected value
+    pub expected: Logic,
+    /// The simulated value
+    pub actual: Logic,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "At cycle {}, expected {} = {}, got {}",
+            self.cycle, self.net, self.expected, self.actual
+        )
+    }
+}
+
+/// Converts `values` into logic values
+fn to_logic<T: Into<Logic>>(values: impl IntoIterator<Item = T>) -> Vec<Logic> {
+    values.into_iter().map(Into::into).collect()
+}
+
+impl Stimulus {
+    /// Returns an empty stimulus of a netlist without a clock input
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns an empty stimulus of a netlist clocked by the input `clock`
+    pub fn clock(clock: impl Into<Identifier>) -> Self {
+        Self {
+            clock: Some(clock.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Drives the principal input `name` with `values`, one per cycle, like `[0, 1, 1, 0]`
+    pub fn signal<T: Into<Logic>>(
+        mut self,
+        name: impl Into<Identifier>,
+        values: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.signals.push((name.into(), to_logic(values)));
+        self
+    }
+
+    /// Expects the output port or net `name` to have `values`, one per cycle, before the clock edge that ends it
+    pub fn expect<T: Into<Logic>>(
+        mut self,
+        name: impl Into<Identifier>,
+        values: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.expects.push((name.into(), to_logic(values)));
+        self
+    }
+
+    /// Returns the number of simulated cycles: the length of the longest waveform
+    pub fn cycles(&self) -> usize {
+        self.signals
+            .iter()
+            .chain(&self.expects)
+            .map(|(_, v)| v.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Simulates `netlist` under the stimulus and checks the expected values.
+    /// Returns [Error::NonequivalentOutputs] for the first net that differs, in an [Error::Context] with its cycle,
+    /// or the errors of [Stimulus::mismatches].
+    pub fn check<I>(&self, netlist: &Netlist<I>) -> Result<(), Error>
+    where
+        I: Evaluate,
+    {
+        match self.mismatches(netlist)?.into_iter().next() {
+            None => Ok(()),
+            Some(m) => Err(
+                Error::NonequivalentOutputs(vec![Net::new_logic(m.net.clone())])
+                    .context(m.to_string(), []),
+            ),
+        }
+    }
+
+    /// Simulates `netlist` under the stimulus and returns every expected value that differs, by cycle.
+    /// Returns [Error::PortNotFound] for inputs and [Error::NetNotFound] for nets that do not exist,
+    /// [Error::Unsupported] for latches and multi-output sequential cells, or an error if the netlist has combinational cycles.
+    pub fn mismatches<I>(&self, netlist: &Netlist<I>) -> Result<Vec<Mismatch>, Error>
+    where
+        I: Evaluate,
+    {
+        let inputs: HashMap<Identifier, DrivenNet<I>> =
+            netlist.inputs().map(|i| (i.get_identifier(), i)).collect();
+        let mut drive: HashMap<DrivenNet<I>, &[Logic]> = HashMap::new();
+        for (name, values) in &self.signals {
+            let input = inputs.get(name).ok_or(Error::PortNotFound(name.clone()))?;
+            drive.insert(input.clone(), values);
+        }
+        if let Some(clock) = &self.clock {
+            let input = inputs
+                .get(clock)
+                .ok_or(Error::PortNotFound(clock.clone()))?;
+            drive.insert(input.clone(), &[Logic::False]);
+        }
+        let outputs = netlist.outputs();
+        let mut expects: Vec<(DrivenNet<I>, &Identifier, &[Logic])> = Vec::new();
+        for (name, values) in &self.expects {
+            let net = outputs
+                .iter()
+                .find(|(_, port)| port.get_identifier() == name)
+                .map(|(driver, _)| driver.clone())
+                .or_else(|| netlist.find_net(&Net::new_logic(name.clone())))
+                .ok_or_else(|| Error::NetNotFound(Net::new_logic(name.clone())))?;
+            expects.push((net, name, values));
+        }
+
+        let mut registers: Vec<(NetRef<I>, Logic)> = Vec::new();
+        for node in netlist.instances() {
+            let cell = node.get_instance_type().unwrap().clone();
+            let name = node.get_instance_name().expect("Instance has a name");
+            if cell.is_latch() {
+                return Err(Error::Unsupported(format!("latch {name}")));
+            }
+            if !cell.is_seq() {
+                continue;
+            }
+            if node.is_multi_output() {
+                return Err(Error::Unsupported(format!(
+                    "multi-output sequential cell {name}"
+                )));
+            }
+            let state = init_value(&cell).map_or(Logic::X, Logic::from);
+            registers.push((node, state));
+        }
+
+        let mut sim = LogicSimulator::new(netlist)?;
+        let mut mismatches = Vec::new();
+        for cycle in 0..self.cycles() {
+            let states: HashMap<DrivenNet<I>, Logic> = registers
+                .iter()
+                .map(|(node, state)| (node.get_output(0), *state))
+                .collect();
+            sim.run(|net| {
+                if let Some(state) = states.get(net) {
+                    return *state;
+                }
+                match drive.get(net) {
+                    Some(values) => values
+                        .get(cycle)
+                        .or(values.last())
+                        .copied()
+                        .unwrap_or(Logic::X),
+                    None => Logic::X,
+                }
+            });
+            for (net, name, values) in &expects {
+                let Some(expected) = values.get(cycle) else {
+                    continue;
+                };
+                let actual = sim.get_value(net);
+                if actual != *expected {
+                    mismatches.push(Mismatch {
+                        cycle,
+                        net: (*name).clone(),
+                        expected: *expected,
+                        actual,
+                    });
+                }
+            }
+            for (node, state) in registers.iter_mut() {
+                let inputs: Vec<Logic> = node
+                    .inputs()
+                    .map(|i| i.get_driver().map_or(Logic::X, |d| sim.get_value(&d)))
+                    .collect();
+                *state = node.get_instance_type().unwrap().eval(&inputs)[0];
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Simulates `netlist` under the stimulus, see [Stimulus::check].
+    ///
+    /// # Panics
+    ///
+    /// Panics with the first cycle and net that differ from the expected values.
+    #[track_caller]
+    pub fn assert<I>(&self, netlist: &Netlist<I>)
+    where
+        I: Evaluate,
+    {
+        if let Err(e) = self.check(netlist) {
+            panic!("Netlist {} fails its stimulus: {e}", netlist.get_name());
+        }
+    }
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Writes a self-checking Verilog testbench of the module of the netlist to `writer`, which applies the inputs of
+    /// `vectors` cycle by cycle and compares the expected nets with `!==`, like [Stimulus::check].
+    /// Each cycle lasts 10 time units: the inputs change at its start, the nets are compared after 5 units, and then
+    /// the clock rises. Expected nets that are not output ports are read inside the instance `dut`.
+    /// The testbench displays each mismatch, then `PASS` or `FAIL` before it finishes.
+    /// Returns [Error::PortNotFound] for inputs and [Error::NetNotFound] for nets that do not exist.
+    pub fn write_testbench(
+        &self,
+        writer: &mut impl Write,
+        vectors: &Stimulus,
+    ) -> Result<(), Error> {
+        let ports = self.get_ports();
+        let inputs: HashMap<&Identifier, &Net> = ports
+            .iter()
+            .filter(|(dir, _)| *dir == PortDirection::Input)
+            .map(|(_, net)| (net.get_identifier(), net))
+            .collect();
+        for name in vectors.signals.iter().map(|(n, _)| n).chain(&vectors.clock) {
+            if !inputs.contains_key(name) {
+                return Err(Error::PortNotFound(name.clone()));
+            }
+        }
+        let mut expects: Vec<(String, &Identifier, &[Logic])> = Vec::new();
+        for (name, values) in &vectors.expects {
+            let is_output = ports
+                .iter()
+                .any(|(dir, net)| *dir == PortDirection::Output && net.get_identifier() == name);
+            let net = Net::new_logic(name.clone());
+            let signal = if is_output {
+                name.emit_name()
+            } else if self.find_net(&net).is_some() {
+                format!("dut.{}", name.emit_name())
+            } else {
+                return Err(Error::NetNotFound(net));
+            };
+            expects.push((signal, name, values));
+        }
+
+        let module = self.get_name().to_string();
+        writeln!(writer, "`timescale 1ns / 1ps")?;
+        writeln!(writer)?;
+        writeln!(writer, "module {module}_tb;")?;
+        let mut decls = Declarations::default();
+        for (_, net) in &ports {
+            decls.add_net(net);
+        }
+        for (dir, net) in &ports {
+            if let Some(name) = decls.declare(net) {
+                let kind = match dir {
+                    PortDirection::Input => "reg",
+                    PortDirection::Output => "wire",
+                };
+                writeln!(writer, "  {kind} {name};")?;
+            }
+        }
+        writeln!(writer, "  integer errors = 0;")?;
+        writeln!(writer)?;
+        let mut connections: Vec<String> = ports
+            .iter()
+            .map(|(_, net)| Declarations::port_name(net))
+            .collect();
+        connections.dedup();
+        writeln!(writer, "  {module} dut (")?;
+        for (i, port) in connections.iter().enumerate() {
+            let sep = if i + 1 == connections.len() { "" } else { "," };
+            writeln!(writer, "    .{port}({port}){sep}")?;
+        }
+        writeln!(writer, "  );")?;
+        writeln!(writer)?;
+
+        writeln!(writer, "  initial begin")?;
+        for (_, net) in ports.iter().filter(|(dir, _)| *dir == PortDirection::Input) {
+            let value = match vectors.clock.as_ref() {
+                Some(clock) if clock == net.get_identifier() => Logic::False,
+                _ => Logic::X,
+            };
+            writeln!(
+                writer,
+                "    {} = {value};",
+                net.get_identifier().emit_name()
+            )?;
+        }
+        for cycle in 0..vectors.cycles() {
+            writeln!(writer, "    // Cycle {cycle}")?;
+            for (name, values) in &vectors.signals {
+                if let Some(value) = values.get(cycle) {
+                    writeln!(writer, "    {} = {value};", name.emit_name())?;
+                }
+            }
+            writeln!(writer, "    #5;")?;
+            for (signal, name, values) in &expects {
+                let Some(expected) = values.get(cycle) else {
+                    continue;
+                };
+                writeln!(writer, "    if ({signal} !== {expected}) begin")?;
+                writeln!(
+                    writer,
+                    "      $display(\"At cycle {cycle}, expected {} = {expected}, got %b\", {signal});",
+                    name.to_string().replace('\\', "\\\\")
+                )?;
+                writeln!(writer, "      errors = errors + 1;")?;
+                writeln!(writer, "    end")?;
+            }
+            match &vectors.clock {
+                Some(clock) => {
+                    let clock = clock.emit_name();
+                    writeln!(writer, "    {clock} = 1'b1;")?;
+                    writeln!(writer, "    #5;")?;
+                    writeln!(writer, "    {clock} = 1'b0;")?;
+                }
+                None => writeln!(writer, "    #5;")?,
+            }
+        }
+        writeln!(writer, "    if (errors == 0) $display(\"PASS\");")?;
+        writeln!(
+            writer,
+            "    else $display(\"FAIL: %0d mismatches\", errors);"
+        )?;
+        writeln!(writer, "    $finish;")?;
+        writeln!(writer, "  end")?;
+        writeln!(writer, "endmodule")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+/*!
+
+  Structural hashing of instances as they are inserted, so that generators do not build duplicate logic.
+
+  While [Netlist::set_strash] is on, the netlist keeps a table of its combinational instances by their cell type,
+  parameters and input nets, and [Netlist::insert_gate] returns the instance of the table with the same key instead of
+  inserting a duplicate. The name of the duplicate is dropped. Sequential, black-box and tri-state cells are never
+  shared, nor are the instances marked [DONT_TOUCH] or [SPARE]. The table is checked against the netlist on every hit,
+  so instances that were removed, reconnected or retyped since they were inserted are not returned, and it is rebuilt
+  when objects are removed or reordered.
+
+*/
+
+use super::{NetRef, Netlist, Operand, annotation::ObjectId};
+use crate::{
+    attribute::{DONT_TOUCH, SPARE},
+    circuit::Instantiable,
+};
+use std::collections::HashMap;
+
+/// What makes two instances interchangeable: their cell type and parameters, and the nets of their input ports
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct StrashKey {
+    cell: String,
+    parameters: Vec<(String, String)>,
+    ports: Vec<String>,
+    operands: Vec<Option<Operand>>,
+}
+
+/// The structural hashing table of a netlist, by key and with the first instance inserted with that key
+#[derive(Debug, Clone, Default)]
+pub(super) struct StrashTable {
+    entries: HashMap<StrashKey, ObjectId>,
+}
+
+/// Returns the key of an instance of `inst_type` with input nets `operands`, or [None] if it must not be shared
+fn strash_key<I: Instantiable>(inst_type: &I, operands: &[Option<Operand>]) -> Option<StrashKey> {
+    let outputs = inst_type.get_output_ports().into_iter().count();
+    if inst_type.is_seq()
+        || inst_type.is_blackbox()
+        || inst_type.is_driverless()
+        || (0..outputs).any(|i| inst_type.is_tristate_output(i))
+    {
+        return None;
+    }
+    let mut parameters: Vec<(String, String)> = inst_type
+        .parameters()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    parameters.sort();
+    let ports = inst_type
+        .get_input_ports()
+        .into_iter()
+        .chain(inst_type.get_output_ports())
+        .map(|p| p.get_identifier().to_string())
+        .collect();
+    Some(StrashKey {
+        cell: inst_type.get_name().to_string(),
+        parameters,
+        ports,
+        operands: operands.to_vec(),
+    })
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Turns structural hashing of [Netlist::insert_gate] on or off. It is off by default.
+    /// Turning it on builds the table from the instances already in the netlist, without merging the existing duplicates.
+    pub fn set_strash(&self, enable: bool) {
+        if !enable {
+            *self.strash.borrow_mut() = None;
+            return;
+        }
+        let mut table = StrashTable::default();
+        for inst in self.instances() {
+            if let Some(key) = Self::node_strash_key(&inst) {
+                table.entries.entry(key).or_insert(inst.get_id());
+            }
+        }
+        *self.strash.borrow_mut() = Some(table);
+    }
+
+    /// Rebuilds the table after the objects have been renumbered, since its keys refer to nets by object index
+    pub(super) fn strash_rebuild(&self) {
+        if self.is_strash_enabled() {
+            self.set_strash(true);
+        }
+    }
+
+    /// Returns `true` if [Netlist::insert_gate] shares structurally equal instances
+    pub fn is_strash_enabled(&self) -> bool {
+        self.strash.borrow().is_some()
+    }
+
+    /// Returns the key of an instance in the netlist, or [None] if it must not be shared
+    fn node_strash_key(node: &NetRef<I>) -> Option<StrashKey> {
+        if node.has_attribute(&DONT_TOUCH.to_string()) || node.has_attribute(&SPARE.to_string()) {
+            return None;
+        }
+        let obj = node.netref.borrow();
+        strash_key(obj.get().get_instance_type()?, &obj.operands)
+    }
+
+    /// Returns the instance that an instance of `inst_type` with input nets `operands` would duplicate, if any
+    pub(super) fn strash_lookup(
+        &self,
+        inst_type: &I,
+        operands: &[Option<Operand>],
+    ) -> Option<NetRef<I>> {
+        let table = self.strash.borrow();
+        let entries = &table.as_ref()?.entries;
+        let key = strash_key(inst_type, operands)?;
+        let id = *entries.get(&key)?;
+        drop(table);
+        self.find_object(id)
+            .filter(|node| Self::node_strash_key(node).as_ref() == Some(&key))
+    }
+
+    /// Records a newly inserted instance in the table, unless the table already has a live instance with its key
+    pub(super) fn strash_record(&self, node: &NetRef<I>) {
+        if !self.is_strash_enabled() {
+            return;
+        }
+        let Some(key) = Self::node_strash_key(node) else {
+            return;
+        };
+        let existing = self
+            .strash
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .entries
+            .get(&key)
+            .copied();
+        let live = existing
+            .and_then(|id| self.find_object(id))
+            .is_some_and(|n| Self::node_strash_key(&n).as_ref() == Some(&key));
+        if !live {
+            self.strash
+                .borrow_mut()
+                .as_mut()
+                .unwrap()
+                .entries
+                .insert(key, node.get_id());
+        }
+    }
+}
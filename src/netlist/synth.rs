@@ -0,0 +1,147 @@
+/*!
+
+  Synthesis of truth tables into lookup tables and gates.
+
+*/
+
+use super::{DrivenNet, Gate, Netlist};
+use crate::{
+    circuit::{Identifier, Instantiable},
+    error::Error,
+    format_id,
+    logic::Logic,
+};
+use bitvec::vec::BitVec;
+use std::{collections::HashMap, rc::Rc};
+
+/// Inserts the cells of a truth table, named with a prefix and a count
+struct Synthesis<'a, I: Instantiable, F> {
+    netlist: &'a Rc<Netlist<I>>,
+    prefix: Identifier,
+    count: usize,
+    lut: F,
+    /// The nets of the functions that were already built
+    built: HashMap<(BitVec, Vec<DrivenNet<I>>), DrivenNet<I>>,
+}
+
+impl<I, F> Synthesis<'_, I, F>
+where
+    I: Instantiable + From<Gate>,
+    F: Fn(&BitVec) -> Option<I>,
+{
+    fn next_name(&mut self) -> Identifier {
+        let name = format_id!("{}_{}", self.prefix, self.count);
+        self.count += 1;
+        name
+    }
+
+    fn insert(&mut self, cell: I, inputs: &[DrivenNet<I>]) -> Result<DrivenNet<I>, Error> {
+        let inst_name = self.next_name();
+        Ok(self
+            .netlist
+            .insert_gate(cell, inst_name, inputs)?
+            .get_output(0))
+    }
+
+    fn gate(
+        &mut self,
+        name: &str,
+        ports: &[&str],
+        inputs: &[DrivenNet<I>],
+    ) -> Result<DrivenNet<I>, Error> {
+        let ports = ports.iter().map(|p| Identifier::from(*p)).collect();
+        let gate = Gate::new_logical(name.into(), ports, "Y".into());
+        self.insert(gate.into(), inputs)
+    }
+
+    fn build(&mut self, table: BitVec, inputs: Vec<DrivenNet<I>>) -> Result<DrivenNet<I>, Error> {
+        let (table, inputs) = support(table, inputs);
+        let key = (table, inputs);
+        if let Some(net) = self.built.get(&key) {
+            return Ok(net.clone());
+        }
+        let (table, inputs) = &key;
+        let net = if table.not_any() || table.all() {
+            let inst_name = self.next_name();
+            self.netlist
+                .insert_constant(Logic::from_bool(table.all()), inst_name)?
+        } else if inputs.len() == 1 && table[1] {
+            inputs[0].clone()
+        } else if inputs.len() == 1 {
+            self.gate("INV", &["A"], inputs)?
+        } else if let Some(cell) = (self.lut)(table) {
+            self.insert(cell, inputs)?
+        } else {
+            // Shannon expansion on the last input, whose cofactors are the halves of the table
+            let (rest, x) = inputs.split_at(inputs.len() - 1);
+            let half = table.len() / 2;
+            let f0 = self.build(table[..half].to_bitvec(), rest.to_vec())?;
+            let f1 = self.build(table[half..].to_bitvec(), rest.to_vec())?;
+            self.gate("MUX", &["S", "A", "B"], &[x[0].clone(), f0, f1])?
+        };
+        self.built.insert(key, net.clone());
+        Ok(net)
+    }
+}
+
+/// Removes the inputs that the function of `table` does not depend on
+fn support<I: Instantiable>(
+    mut table: BitVec,
+    mut inputs: Vec<DrivenNet<I>>,
+) -> (BitVec, Vec<DrivenNet<I>>) {
+    let mut i = 0;
+    while i < inputs.len() {
+        let (f0, f1): (BitVec, BitVec) = {
+            let mut f0 = BitVec::with_capacity(table.len() / 2);
+            let mut f1 = BitVec::with_capacity(table.len() / 2);
+            for (index, bit) in table.iter().enumerate() {
+                if (index >> i) & 1 == 0 {
+                    f0.push(*bit);
+                } else {
+                    f1.push(*bit);
+                }
+            }
+            (f0, f1)
+        };
+        if f0 == f1 {
+            table = f0;
+            inputs.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    (table, inputs)
+}
+
+/// Builds the function given by the truth table `bits` over `inputs`, returning the net of its value.
+/// Input `i` is bit `i` of the index into `bits`, as in the `INIT` parameter of a LUT.
+///
+/// The inputs that the function does not depend on are dropped first. Then the function is a single cell from `lut`,
+/// which returns [None] for the tables it cannot implement, like those of more than `K` inputs for K-LUTs.
+/// Otherwise, it is expanded with `MUX` gates on its last input, down to functions that fit a LUT, constants,
+/// inputs or their inversions with `INV` gates. Pass `|_| None` to build only gates.
+/// The new instances are named `prefix` suffixed with a count, like `prefix_0`.
+///
+/// Returns [Error::ArgumentMismatch] if `bits` does not have an entry for every combination of `inputs`.
+pub fn from_truth_table<I>(
+    netlist: &Rc<Netlist<I>>,
+    bits: &BitVec,
+    inputs: &[DrivenNet<I>],
+    prefix: Identifier,
+    lut: impl Fn(&BitVec) -> Option<I>,
+) -> Result<DrivenNet<I>, Error>
+where
+    I: Instantiable + From<Gate>,
+{
+    if bits.len() != 1 << inputs.len() {
+        return Err(Error::ArgumentMismatch(1 << inputs.len(), bits.len()));
+    }
+    let mut synthesis = Synthesis {
+        netlist,
+        prefix,
+        count: 0,
+        lut,
+        built: HashMap::new(),
+    };
+    synthesis.build(bits.clone(), inputs.to_vec())
+}
@@ -0,0 +1,754 @@
+/*!
+
+  Utilities for testing netlists and the passes that transform them.
+
+*/
+
+use super::{DrivenNet, Netlist};
+use crate::{
+    circuit::{Evaluate, Identifier, Instantiable, Net},
+    error::{Diagnostics, Error},
+    format_id,
+    graph::TopoOrder,
+    util::Rng,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    rc::Rc,
+};
+
+/// Configures the shape of the netlists created by [random_netlist]
+#[derive(Debug, Clone)]
+pub struct RandomConfig<I: Instantiable> {
+    /// The name of the generated module
+    pub name: String,
+    /// The cell set to sample from, each paired with a relative weight.
+    /// The weights of the combinational cells determine the fanin distribution of the result.
+    /// Sequential cells (see [Instantiable::is_seq]) are only used to build register ranks.
+    pub cells: Vec<(I, usize)>,
+    /// The number of principal inputs
+    pub inputs: usize,
+    /// The number of combinational instances
+    pub instances: usize,
+    /// The maximum number of module outputs
+    pub outputs: usize,
+    /// When set, fanin is only drawn from the `n` most recently created nets.
+    /// Small windows produce deep, narrow circuits.
+    pub locality: Option<usize>,
+    /// The number of register ranks every input-to-output path must cross
+    pub seq_depth: usize,
+    /// The number of sequential cells in each register rank
+    pub rank_width: usize,
+}
+
+impl<I> RandomConfig<I>
+where
+    I: Instantiable,
+{
+    /// Creates a purely combinational configuration over `cells` with uniform weights
+    pub fn new(cells: Vec<I>) -> Self {
+        Self {
+            name: "random".to_string(),
+            cells: cells.into_iter().map(|c| (c, 1)).collect(),
+            inputs: 8,
+            instances: 32,
+            outputs: 4,
+            locality: None,
+            seq_depth: 0,
+            rank_width: 4,
+        }
+    }
+}
+
+/// Picks a cell from the weighted cell set
+fn pick_cell<'a, I: Instantiable>(cells: &[&'a (I, usize)], rng: &mut Rng) -> &'a I {
+    let total: usize = cells.iter().map(|(_, w)| *w).sum();
+    let mut choice = rng.below(total);
+    for (cell, weight) in cells {
+        if choice < *weight {
+            return cell;
+        }
+        choice -= weight;
+    }
+    unreachable!("Weighted choice out of range")
+}
+
+/// Picks a driver for a new cell, honoring the locality window
+fn pick_driver<I: Instantiable>(
+    pool: &[DrivenNet<I>],
+    locality: Option<usize>,
+    rng: &mut Rng,
+) -> DrivenNet<I> {
+    let window = locality.unwrap_or(pool.len()).clamp(1, pool.len());
+    pool[pool.len() - window + rng.below(window)].clone()
+}
+
+/// Generates a random, well-formed netlist from the cell set in `config`.
+/// The same `seed` and `config` always produce the same netlist.
+///
+/// The combinational instances are split evenly across `seq_depth + 1` stages.
+/// The first stage draws fanin from the principal inputs, and every later stage only from
+/// the register rank before it and its own nets. Every input-to-output path therefore crosses
+/// all `seq_depth` ranks, and the combinational logic is always a DAG.
+/// Logic that does not reach an output is left in place; call [Netlist::clean] to remove it.
+pub fn random_netlist<I>(seed: u64, config: &RandomConfig<I>) -> Result<Rc<Netlist<I>>, Error>
+where
+    I: Instantiable,
+{
+    let comb: Vec<_> = config
+        .cells
+        .iter()
+        .filter(|(c, w)| !c.is_seq() && *w > 0)
+        .collect();
+    let seq: Vec<_> = config
+        .cells
+        .iter()
+        .filter(|(c, w)| c.is_seq() && *w > 0)
+        .collect();
+
+    if config.inputs == 0 {
+        return Err(Error::InstantiableError(
+            "A random netlist needs at least one input".to_string(),
+        ));
+    }
+    if comb.is_empty() && config.instances > 0 {
+        return Err(Error::InstantiableError(
+            "No combinational cells to sample from".to_string(),
+        ));
+    }
+    if seq.is_empty() && config.seq_depth > 0 {
+        return Err(Error::InstantiableError(
+            "No sequential cells to build register ranks from".to_string(),
+        ));
+    }
+    if config.rank_width == 0 && config.seq_depth > 0 {
+        return Err(Error::InstantiableError(
+            "Register ranks need at least one sequential cell".to_string(),
+        ));
+    }
+
+    let mut rng = Rng::new(seed);
+    let netlist = Netlist::new(config.name.clone());
+    let mut pool: Vec<DrivenNet<I>> = (0..config.inputs)
+        .map(|i| netlist.insert_input(Net::new_logic(format_id!("x{i}"))))
+        .collect();
+
+    let stages = config.seq_depth + 1;
+    let mut used = std::collections::HashSet::new();
+    let mut n_gates = 0;
+    for stage in 0..stages {
+        let n = config.instances / stages + usize::from(stage < config.instances % stages);
+        for _ in 0..n {
+            let cell = pick_cell(&comb, &mut rng);
+            let arity = cell.get_input_ports().into_iter().count();
+            let operands: Vec<_> = (0..arity)
+                .map(|_| pick_driver(&pool, config.locality, &mut rng))
+                .collect();
+            used.extend(operands.iter().cloned());
+            let inst = netlist.insert_gate(cell.clone(), format_id!("g{n_gates}"), &operands)?;
+            n_gates += 1;
+            pool.extend(inst.outputs());
+        }
+
+        if stage + 1 < stages {
+            let mut next_pool = Vec::new();
+            for r in 0..config.rank_width {
+                let cell = pick_cell(&seq, &mut rng);
+                let arity = cell.get_input_ports().into_iter().count();
+                let operands: Vec<_> = (0..arity)
+                    .map(|_| pick_driver(&pool, config.locality, &mut rng))
+                    .collect();
+                used.extend(operands.iter().cloned());
+                let inst =
+                    netlist.insert_gate(cell.clone(), format_id!("r{stage}_{r}"), &operands)?;
+                next_pool.extend(inst.outputs());
+            }
+            pool = next_pool;
+        }
+    }
+
+    // Prefer exposing the nets that would otherwise be dead
+    let mut sinks: Vec<_> = pool
+        .iter()
+        .filter(|n| !n.is_an_input() && !used.contains(*n))
+        .cloned()
+        .collect();
+    let mut others: Vec<_> = pool
+        .iter()
+        .filter(|n| n.is_an_input() || used.contains(*n))
+        .cloned()
+        .collect();
+    for i in (1..sinks.len()).rev() {
+        sinks.swap(i, rng.below(i + 1));
+    }
+    for i in (1..others.len()).rev() {
+        others.swap(i, rng.below(i + 1));
+    }
+
+    for (i, net) in sinks
+        .into_iter()
+        .chain(others)
+        .take(config.outputs)
+        .enumerate()
+    {
+        net.expose_with_name(format_id!("y{i}"));
+    }
+
+    Ok(netlist)
+}
+
+/// Checks that every input port of every instance is connected to a driver.
+pub fn check_connected<I>(netlist: &Netlist<I>) -> Result<(), Error>
+where
+    I: Instantiable,
+{
+    netlist.inputs_connected()
+}
+
+/// Checks that all nets and instances are uniquely named.
+pub fn check_unique_names<I>(netlist: &Netlist<I>) -> Result<(), Error>
+where
+    I: Instantiable,
+{
+    let mut diagnostics = Diagnostics::new();
+    netlist.diagnose_names(&mut diagnostics);
+    diagnostics.into_result().map_err(Error::into_root)
+}
+
+/// Checks that every instance drives a net that is read by another instance or is a module output.
+/// Unused principal inputs are allowed, since they are part of the interface of the module,
+/// and so are unused outputs of a cell whose other outputs are in use.
+pub fn check_no_dangling<I>(netlist: &Netlist<I>) -> Result<(), Error>
+where
+    I: Instantiable,
+{
+    let mut dangling = Vec::new();
+    for object in netlist.objects().filter(|o| !o.is_an_input()) {
+        let outputs: Vec<DrivenNet<I>> = object.outputs().collect();
+        if outputs
+            .iter()
+            .all(|n| !n.is_top_level_output() && netlist.get_uses(n).is_empty())
+        {
+            dangling.extend(outputs.iter().map(|n| n.as_net().clone()));
+        }
+    }
+    if dangling.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::DanglingNets(dangling))
+    }
+}
+
+/// Checks that the netlist has no combinational cycles.
+pub fn check_acyclic<I>(netlist: &Netlist<I>) -> Result<(), Error>
+where
+    I: Instantiable,
+{
+    netlist.get_analysis::<TopoOrder<I>>().map(|_| ())
+}
+
+/// Checks that `a` and `b` compute the same function by comparing the signatures of their outputs
+/// (see [Netlist::signatures]). Outputs are matched by port name, and free variables are stimulated
+/// by name, so that registers with the same output net are treated as the same state.
+/// Passing this check does not prove equivalence, but failing it proves non-equivalence.
+pub fn check_equivalent<I>(
+    a: &Netlist<I>,
+    b: &Netlist<I>,
+    n_patterns: usize,
+    seed: u64,
+) -> Result<(), Error>
+where
+    I: Evaluate,
+{
+    let a_outputs: HashMap<Net, DrivenNet<I>> =
+        a.outputs().into_iter().map(|(d, n)| (n, d)).collect();
+    let b_outputs: HashMap<Net, DrivenNet<I>> =
+        b.outputs().into_iter().map(|(d, n)| (n, d)).collect();
+
+    let mut mismatches: Vec<Net> = a_outputs
+        .keys()
+        .filter(|n| !b_outputs.contains_key(*n))
+        .chain(b_outputs.keys().filter(|n| !a_outputs.contains_key(*n)))
+        .cloned()
+        .collect();
+
+    let a_sigs = a.signatures(n_patterns, seed)?;
+    let b_sigs = b.signatures(n_patterns, seed)?;
+    for (name, a_net) in a_outputs.iter() {
+        if let Some(b_net) = b_outputs.get(name)
+            && a_sigs[a_net] != b_sigs[b_net]
+        {
+            mismatches.push(name.clone());
+        }
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::NonequivalentOutputs(mismatches))
+    }
+}
+
+/// Panics if `netlist` has unconnected input ports, dangling nets, duplicate names, combinational cycles, or stale use lists.
+#[track_caller]
+pub fn assert_invariants<I>(netlist: &Netlist<I>)
+where
+    I: Instantiable,
+{
+    for check in [
+        check_connected,
+        check_no_dangling,
+        check_unique_names,
+        check_acyclic,
+    ] {
+        if let Err(e) = check(netlist) {
+            panic!("Netlist {} breaks an invariant: {e}", netlist.get_name());
+        }
+    }
+    assert!(
+        netlist.uses_consistent(),
+        "Netlist {} has stale use lists",
+        netlist.get_name()
+    );
+}
+
+/// Panics if `a` and `b` are distinguished by `n_patterns` random input patterns.
+/// See [check_equivalent].
+#[track_caller]
+pub fn assert_equivalent<I>(a: &Netlist<I>, b: &Netlist<I>, n_patterns: usize)
+where
+    I: Evaluate,
+{
+    if let Err(e) = check_equivalent(a, b, n_patterns, 0) {
+        panic!("Netlists {} and {} differ: {e}", a.get_name(), b.get_name());
+    }
+}
+
+/// A token of structural Verilog
+type Token = String;
+
+/// Splits structural Verilog into tokens, without comments and compiler directives.
+/// Attribute brackets are the tokens `(*` and `*)`.
+fn tokenize(source: &str) -> Result<Vec<Token>, Error> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        match (c, chars.get(i + 1)) {
+            ('/', Some('/')) | ('`', _) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            ('/', Some('*')) => {
+                i += 2;
+                while i < chars.len() && !(chars[i - 1] == '*' && chars[i] == '/') {
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(Error::ParseError("Unterminated comment".to_string()));
+                }
+                i += 1;
+                continue;
+            }
+            ('(', Some('*')) if chars.get(i + 2) != Some(&')') => i += 2,
+            ('*', Some(')')) => i += 2,
+            ('\\', _) => {
+                while i < chars.len() && !chars[i].is_whitespace() {
+                    i += 1;
+                }
+            }
+            ('"', _) => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                if i >= chars.len() {
+                    return Err(Error::ParseError("Unterminated string".to_string()));
+                }
+                i += 1;
+            }
+            (c, _) if c.is_alphanumeric() || matches!(c, '_' | '$' | '\'') => {
+                let number = c.is_ascii_digit() || c == '\'';
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric()
+                        || matches!(chars[i], '_' | '$' | '\'')
+                        || (number && chars[i] == '.'))
+                {
+                    i += 1;
+                }
+            }
+            _ => i += 1,
+        }
+        tokens.push(chars[start..i].iter().collect());
+    }
+    Ok(tokens)
+}
+
+/// A declared net of a module, merged from its port and net declarations
+#[derive(Debug, Default, PartialEq)]
+struct NetDecl {
+    direction: Option<String>,
+    kind: Option<String>,
+    range: Option<String>,
+    attributes: Vec<String>,
+}
+
+impl std::fmt::Display for NetDecl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for attribute in &self.attributes {
+            write!(f, "(* {attribute} *) ")?;
+        }
+        if let Some(direction) = &self.direction {
+            write!(f, "{direction} ")?;
+        }
+        write!(f, "{}", self.kind.as_deref().unwrap_or("wire"))?;
+        if let Some(range) = &self.range {
+            write!(f, " [{range}]")?;
+        }
+        Ok(())
+    }
+}
+
+/// An instance of a module, with its parameters and connections by name
+#[derive(Debug, PartialEq)]
+struct InstDecl {
+    cell: String,
+    parameters: BTreeMap<String, String>,
+    connections: BTreeMap<String, String>,
+    attributes: Vec<String>,
+}
+
+impl std::fmt::Display for InstDecl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for attribute in &self.attributes {
+            write!(f, "(* {attribute} *) ")?;
+        }
+        write!(f, "{}", self.cell)?;
+        if !self.parameters.is_empty() {
+            let parameters: Vec<String> = self
+                .parameters
+                .iter()
+                .map(|(k, v)| format!(".{k}({v})"))
+                .collect();
+            write!(f, " #({})", parameters.join(", "))?;
+        }
+        let connections: Vec<String> = self
+            .connections
+            .iter()
+            .map(|(k, v)| format!(".{k}({v})"))
+            .collect();
+        write!(f, " ({})", connections.join(", "))
+    }
+}
+
+/// A module of structural Verilog, where the order of the declarations, instances and assignments does not matter
+#[derive(Debug, Default, PartialEq)]
+struct ModuleDecl {
+    ports: Vec<String>,
+    nets: BTreeMap<String, NetDecl>,
+    instances: BTreeMap<String, InstDecl>,
+    assigns: BTreeMap<String, String>,
+    attributes: Vec<String>,
+}
+
+/// A cursor over the tokens of structural Verilog
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+/// The keywords that declare nets
+const NET_KINDS: &[&str] = &[
+    "wire", "reg", "tri", "wand", "wor", "triand", "trior", "tri0", "tri1", "supply0", "supply1",
+];
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|t| t.as_str())
+    }
+
+    fn next(&mut self) -> Result<Token, Error> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| Error::ParseError("Unexpected end of Verilog".to_string()))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), Error> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(Error::ParseError(format!(
+                "Expected `{expected}`, got `{token}`"
+            ))),
+        }
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// Returns the tokens up to the first of `ends` outside of brackets, joined by spaces, without consuming the end
+    fn until(&mut self, ends: &[&str]) -> Result<String, Error> {
+        let mut depth = 0;
+        let mut tokens = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(Error::ParseError("Unexpected end of Verilog".to_string())),
+                Some(t) if depth == 0 && ends.contains(&t) => return Ok(tokens.join(" ")),
+                Some("(" | "[" | "{") => depth += 1,
+                Some(")" | "]" | "}") => depth -= 1,
+                _ => (),
+            }
+            tokens.push(self.next()?);
+        }
+    }
+
+    /// Parses the attributes `(* ... *)` at the cursor, if any
+    fn attributes(&mut self) -> Result<Vec<String>, Error> {
+        let mut attributes = Vec::new();
+        while self.eat("(*") {
+            loop {
+                let attribute = self.until(&[",", "*)"])?;
+                attributes.push(attribute);
+                if self.next()? == "*)" {
+                    break;
+                }
+            }
+        }
+        Ok(attributes)
+    }
+
+    /// Parses the optional net kind and range, then the names of a declaration
+    fn declaration(
+        &mut self,
+        module: &mut ModuleDecl,
+        direction: Option<String>,
+        attributes: Vec<String>,
+        ends: &[&str],
+    ) -> Result<Vec<String>, Error> {
+        let kind = self
+            .peek()
+            .filter(|t| NET_KINDS.contains(t))
+            .map(|t| t.to_string());
+        if kind.is_some() {
+            self.pos += 1;
+        }
+        let range = if self.eat("[") {
+            let range = self.until(&["]"])?;
+            self.expect("]")?;
+            Some(range)
+        } else {
+            None
+        };
+        let mut names = Vec::new();
+        loop {
+            let name = self.next()?;
+            let net = module.nets.entry(name.clone()).or_default();
+            net.direction = direction.clone().or(net.direction.take());
+            net.kind = kind.clone().or(net.kind.take());
+            net.range = range.clone().or(net.range.take());
+            net.attributes.extend(attributes.iter().cloned());
+            net.attributes.sort();
+            net.attributes.dedup();
+            names.push(name);
+            // In a port list, the next port may have a declaration of its own
+            if ends.contains(&",") || !self.eat(",") {
+                break;
+            }
+        }
+        Ok(names)
+    }
+
+    /// Parses `(.name(expr), ...)`, leaving out the unconnected names
+    fn named_list(&mut self) -> Result<BTreeMap<String, String>, Error> {
+        let mut list = BTreeMap::new();
+        self.expect("(")?;
+        while !self.eat(")") {
+            self.eat(",");
+            self.expect(".")?;
+            let name = self.next()?;
+            self.expect("(")?;
+            let value = self.until(&[")"])?;
+            self.expect(")")?;
+            if !value.is_empty() {
+                list.insert(name, value);
+            }
+        }
+        Ok(list)
+    }
+
+    /// Parses a module, after the `module` keyword
+    fn module(&mut self, attributes: Vec<String>) -> Result<(String, ModuleDecl), Error> {
+        let name = self.next()?;
+        let mut module = ModuleDecl {
+            attributes,
+            ..Default::default()
+        };
+        if self.eat("(") {
+            while !self.eat(")") {
+                self.eat(",");
+                let attributes = self.attributes()?;
+                match self.peek() {
+                    Some(d @ ("input" | "output" | "inout")) => {
+                        let direction = Some(d.to_string());
+                        self.pos += 1;
+                        let names = self.declaration(&mut module, direction, attributes, &[","])?;
+                        module.ports.extend(names);
+                    }
+                    _ => module.ports.push(self.next()?),
+                }
+            }
+        }
+        self.expect(";")?;
+        loop {
+            let attributes = self.attributes()?;
+            let token = self.next()?;
+            match token.as_str() {
+                "endmodule" => {
+                    // Ports are wires unless declared otherwise
+                    for net in module.nets.values_mut() {
+                        net.kind.get_or_insert_with(|| "wire".to_string());
+                    }
+                    return Ok((name, module));
+                }
+                "input" | "output" | "inout" => {
+                    self.declaration(&mut module, Some(token), attributes, &[";"])?;
+                    self.expect(";")?;
+                }
+                t if NET_KINDS.contains(&t) => {
+                    self.pos -= 1;
+                    self.declaration(&mut module, None, attributes, &[";"])?;
+                    self.expect(";")?;
+                }
+                "assign" => loop {
+                    let lhs = self.until(&["="])?;
+                    self.expect("=")?;
+                    let rhs = self.until(&[",", ";"])?;
+                    module.assigns.insert(lhs, rhs);
+                    if self.next()? == ";" {
+                        break;
+                    }
+                },
+                cell => {
+                    let parameters = if self.eat("#") {
+                        self.named_list()?
+                    } else {
+                        BTreeMap::new()
+                    };
+                    let name = self.next()?;
+                    let connections = self.named_list()?;
+                    self.expect(";")?;
+                    let mut attributes = attributes;
+                    attributes.sort();
+                    let inst = InstDecl {
+                        cell: cell.to_string(),
+                        parameters,
+                        connections,
+                        attributes,
+                    };
+                    if module.instances.insert(name.clone(), inst).is_some() {
+                        return Err(Error::NonuniqueInsts(vec![Identifier::new(name)]));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parses the modules of structural Verilog, as emitted by [Netlist::to_verilog]
+fn parse_structure(source: &str) -> Result<BTreeMap<String, ModuleDecl>, Error> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+    };
+    let mut modules = BTreeMap::new();
+    while parser.peek().is_some() {
+        let mut attributes = parser.attributes()?;
+        attributes.sort();
+        parser.expect("module")?;
+        let (name, module) = parser.module(attributes)?;
+        if modules.insert(name.clone(), module).is_some() {
+            return Err(Error::DuplicateModule(Identifier::new(name)));
+        }
+    }
+    Ok(modules)
+}
+
+/// Returns a description of the first difference between two maps, with the key and both sides
+fn first_difference<V: PartialEq>(
+    what: &str,
+    left: &BTreeMap<String, V>,
+    right: &BTreeMap<String, V>,
+    show: impl Fn(&V) -> String,
+) -> Option<String> {
+    let keys: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+    keys.into_iter().find_map(|key| {
+        let (l, r) = (left.get(key), right.get(key));
+        if l == r {
+            return None;
+        }
+        let side = |v: Option<&V>| v.map_or("(missing)".to_string(), &show);
+        Some(format!(
+            "{what} `{key}` differs\n  left:  {}\n  right: {}",
+            side(l),
+            side(r)
+        ))
+    })
+}
+
+/// Compares two Verilog netlists structurally, as emitted by [Netlist::to_verilog]: the order of the declarations,
+/// instances, assignments, port connections and attributes does not matter, but the order of the module ports does.
+/// Returns a description of the first mismatching module, port list, net, instance or assignment, or [None] if they match.
+/// Returns an error if either side is not structural Verilog.
+pub fn verilog_diff(left: &str, right: &str) -> Result<Option<String>, Error> {
+    let (left, right) = (parse_structure(left)?, parse_structure(right)?);
+    let names = |m: &BTreeMap<String, ModuleDecl>| m.keys().cloned().collect::<Vec<_>>();
+    if names(&left) != names(&right) {
+        return Ok(Some(format!(
+            "modules differ\n  left:  {:?}\n  right: {:?}",
+            names(&left),
+            names(&right)
+        )));
+    }
+    for (name, l) in left.iter() {
+        let r = &right[name];
+        let diff = if l.ports != r.ports {
+            Some(format!(
+                "ports differ\n  left:  {:?}\n  right: {:?}",
+                l.ports, r.ports
+            ))
+        } else if l.attributes != r.attributes {
+            Some(format!(
+                "attributes differ\n  left:  {:?}\n  right: {:?}",
+                l.attributes, r.attributes
+            ))
+        } else {
+            first_difference("net", &l.nets, &r.nets, |n| n.to_string())
+                .or_else(|| {
+                    first_difference("instance", &l.instances, &r.instances, |i| i.to_string())
+                })
+                .or_else(|| {
+                    first_difference("assignment to", &l.assigns, &r.assigns, |a| a.clone())
+                })
+        };
+        if let Some(diff) = diff {
+            return Ok(Some(format!("in module `{name}`, {diff}")));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,1098 @@
+/*!
+
+  Static timing analysis and critical path reports.
+
+*/
+
+use super::{
+    DrivenNet, InputPort, NetRef, Netlist,
+    annotation::{AnnotationMap, NetId, ObjectId},
+    delay::DelayModel,
+    levels::{Levels, Schedule},
+    observer::ObserverId,
+    sim::is_source,
+};
+use crate::{
+    circuit::{Instantiable, Net},
+    constraints::{Constraints, Target},
+    error::Error,
+    graph::TopoOrder,
+};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::rc::Rc;
+
+/// The end of a timing path, where a signal is captured
+#[derive(Debug, Clone)]
+pub enum Endpoint<I: Instantiable> {
+    /// A top-level output
+    Output(Net),
+    /// An input of a sequential cell, latch or black box
+    Pin(InputPort<I>),
+}
+
+impl<I> std::fmt::Display for Endpoint<I>
+where
+    I: Instantiable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Endpoint::Output(net) => net.get_identifier().fmt(f),
+            Endpoint::Pin(port) => {
+                let node = port.clone().unwrap();
+                match node.get_instance_name() {
+                    Some(name) => write!(f, "{name}/{port}"),
+                    None => port.fmt(f),
+                }
+            }
+        }
+    }
+}
+
+impl<I> Endpoint<I>
+where
+    I: Instantiable,
+{
+    /// Returns `true` if the constraint `target` refers to the endpoint
+    fn matches(&self, target: &Target) -> bool {
+        match self {
+            Endpoint::Output(net) => target.matches_port(&net.get_identifier().to_string()),
+            Endpoint::Pin(port) => {
+                let node = port.clone().unwrap();
+                let cell = node.get_instance_name().unwrap().to_string();
+                target.matches_cell(&cell)
+                    || target.matches_pin(&cell, &port.get_port().get_identifier().to_string())
+            }
+        }
+    }
+}
+
+/// Returns `true` if the constraint `target` refers to the startpoint `node`
+fn startpoint_matches<I: Instantiable>(node: &NetRef<I>, target: &Target) -> bool {
+    match node.get_instance_name() {
+        Some(cell) => {
+            let cell = cell.to_string();
+            target.matches_cell(&cell)
+                || node
+                    .outputs()
+                    .any(|o| target.matches_pin(&cell, &o.get_port().get_identifier().to_string()))
+        }
+        None => target.matches_port(&node.get_identifier().to_string()),
+    }
+}
+
+/// A stage of a timing path: a net and the delay of the cell that drives it
+#[derive(Debug, Clone)]
+pub struct Stage<I: Instantiable> {
+    /// The net at the end of the stage
+    pub net: DrivenNet<I>,
+    /// The delay of the driving cell, which is zero for principal inputs
+    pub delay: f64,
+    /// The transition time of the net
+    pub slew: f64,
+    /// The arrival time of the net
+    pub arrival: f64,
+}
+
+/// A timing path from a startpoint to an [Endpoint], through the latest arriving input of each cell
+#[derive(Debug, Clone)]
+pub struct TimingPath<I: Instantiable> {
+    /// Where the path is captured
+    pub endpoint: Endpoint<I>,
+    /// The required time minus the arrival time at the endpoint
+    pub slack: f64,
+    /// The stages of the path, starting with the startpoint
+    pub stages: Vec<Stage<I>>,
+}
+
+impl<I> std::fmt::Display for TimingPath<I>
+where
+    I: Instantiable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "endpoint: {}", self.endpoint)?;
+        writeln!(f, "slack: {}", self.slack)?;
+        for stage in &self.stages {
+            let cell = match stage.net.get_instance_type() {
+                Some(inst) => inst.get_name().to_string(),
+                None => "input".to_string(),
+            };
+            writeln!(
+                f,
+                "  {:>10.3} {:>+10.3}  {} ({cell})",
+                stage.arrival, stage.delay, stage.net
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The paths timed by a [View]: those launched by the startpoints that are not `masked`, which avoid the `killed` nodes,
+/// and which go through one of the `through` nodes if there are any
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+struct ViewKey {
+    masked: BTreeSet<usize>,
+    killed: BTreeSet<usize>,
+    through: Option<BTreeSet<usize>>,
+}
+
+/// The arrival times of some paths of a netlist, as selected by the timing exceptions of an endpoint.
+/// Paths are tracked separately before [0] and after [1] they go through one of the `through` nodes of their [ViewKey].
+struct View {
+    /// The arrival time of each circuit node, indexed by object
+    arrival: [Vec<f64>; 2],
+    /// The input with the latest arrival time of each combinational node, indexed by object,
+    /// and whether the path through it had already gone through a `through` node
+    critical: [Vec<Option<(usize, bool)>>; 2],
+    /// Whether the timed paths are the ones after a `through` node
+    through: bool,
+}
+
+/// The timing of an endpoint
+struct EndpointTiming<I: Instantiable> {
+    /// The endpoint
+    endpoint: Endpoint<I>,
+    /// The net that drives the endpoint
+    driver: DrivenNet<I>,
+    /// The views with the timed paths to the endpoint and their required times, which are none if it is unconstrained
+    checks: Vec<(usize, f64)>,
+}
+
+/// A false path or multicycle path, with the startpoints and the nodes it goes through as object indices
+struct Exception<'a> {
+    /// The number of clock periods of the paths, or [None] for a false path
+    multiplier: Option<usize>,
+    /// The startpoints, or [None] for any startpoint
+    from: Option<BTreeSet<usize>>,
+    /// The nodes the paths go through, or [None] if the exception has no `-through` list
+    through: Option<BTreeSet<usize>>,
+    /// The endpoints
+    to: &'a [Target],
+}
+
+/// Returns `true` if the constraint `target` refers to `node`, one of its pins or one of the nets it drives
+fn through_matches<I: Instantiable>(node: &NetRef<I>, target: &Target) -> bool {
+    if node
+        .nets()
+        .any(|n| target.matches_net(&n.get_identifier().to_string()))
+    {
+        return true;
+    }
+    match node.get_instance_name() {
+        Some(cell) => {
+            let cell = cell.to_string();
+            let pin = |p: &Net| target.matches_pin(&cell, &p.get_identifier().to_string());
+            target.matches_cell(&cell)
+                || node.inputs().any(|i| pin(&i.get_port()))
+                || node.outputs().any(|o| pin(&o.get_port()))
+        }
+        None => target.matches_port(&node.get_identifier().to_string()),
+    }
+}
+
+/// A static timing analysis of a netlist under the shortest clock period of its [Constraints].
+/// The principal inputs launch after their input delay, and the sequential cells and black boxes launch after their own delay.
+/// Top-level outputs are required by the clock period minus their output delay, and the inputs of sequential cells and black boxes
+/// by the clock period. Latches are timed like flip-flops, without borrowing time across the transparent phase. Paths from clock ports are not timed.
+/// False paths are left out of the slack of their endpoints, and multicycle paths are required that many clock periods later.
+/// The `-through` objects of an exception are cells, pins or nets, and a path goes through the exception if it goes through any of them.
+/// When several multicycle paths apply to a path, the largest multiplier wins. The slack of a net keeps the tightest required time
+/// of the endpoints it reaches.
+/// Like an [crate::graph::Analysis], the timing becomes stale when the netlist is modified.
+pub struct Sta<'a, I: Instantiable> {
+    /// A reference to the underlying netlist
+    netlist: &'a Netlist<I>,
+    /// The clock period
+    period: f64,
+    /// The circuit nodes in topological order
+    order: Vec<NetRef<I>>,
+    /// The levels of the circuit nodes, along which arrival times are propagated
+    schedule: Schedule,
+    /// The delay of each circuit node, indexed by object
+    delays: Vec<f64>,
+    /// The transition time of the outputs of each circuit node, indexed by object
+    slews: Vec<f64>,
+    /// The launch time of each source, indexed by object
+    launch: Vec<f64>,
+    /// The arrival times of all paths first, then of the paths selected by the timing exceptions of some endpoints
+    views: Vec<View>,
+    /// The required time of each output, indexed by object
+    required: Vec<Vec<f64>>,
+    /// The endpoints, in the order of the top-level outputs, then of the cells
+    endpoints: Vec<EndpointTiming<I>>,
+}
+
+impl<'a, I> Sta<'a, I>
+where
+    I: Instantiable,
+{
+    /// Times `netlist` for a clock `period`, where `delay` models the delay of the cells, like `|_| 1.0` or [super::delay::LinearDelay].
+    /// Returns an error if the netlist has combinational cycles.
+    pub fn new(
+        netlist: &'a Netlist<I>,
+        period: f64,
+        delay: impl DelayModel<I>,
+    ) -> Result<Self, Error> {
+        Self::with_constraints(netlist, &Constraints::with_period(period), delay)
+    }
+
+    /// Times `netlist` under `constraints`, where `delay` models the delay of the cells.
+    /// The transition time at the inputs of a cell is the slowest of its drivers, and is zero at the startpoints.
+    /// Returns an error if the netlist has combinational cycles.
+    pub fn with_constraints(
+        netlist: &'a Netlist<I>,
+        constraints: &Constraints,
+        delay: impl DelayModel<I>,
+    ) -> Result<Self, Error> {
+        let order = netlist.get_analysis::<TopoOrder<I>>()?.get_order().to_vec();
+        let schedule = netlist.get_analysis::<Levels<I>>()?.get_schedule().clone();
+        let period = constraints.get_period();
+        let n = netlist.objects().count();
+        let mut delays = vec![0.0; n];
+        let mut slews = vec![0.0; n];
+        for node in order.iter() {
+            let Some(inst) = node.get_instance_type() else {
+                continue;
+            };
+            let input_slew = if is_source(node) {
+                0.0
+            } else {
+                node.drivers()
+                    .flatten()
+                    .map(|d| slews[d.netref.borrow().get_index()])
+                    .fold(0.0, f64::max)
+            };
+            let load = node
+                .outputs()
+                .map(|o| delay.load(netlist.fanout(&o)))
+                .fold(0.0, f64::max);
+            let index = node.netref.borrow().get_index();
+            delays[index] = delay.delay(&inst, input_slew, load);
+            slews[index] = delay.slew(&inst, input_slew, load);
+        }
+        let mut launch = Vec::new();
+        for obj in netlist.objects() {
+            launch.push(if obj.is_an_input() {
+                let name = obj.get_identifier().to_string();
+                if constraints.is_clock_port(&name) {
+                    f64::NEG_INFINITY
+                } else {
+                    constraints.input_delay(&name).unwrap_or(0.0)
+                }
+            } else {
+                0.0
+            });
+        }
+
+        let mut sta = Self {
+            netlist,
+            period,
+            order,
+            schedule,
+            delays,
+            slews,
+            launch,
+            views: Vec::new(),
+            required: Vec::new(),
+            endpoints: Vec::new(),
+        };
+        sta.views.push(sta.propagate_arrival(&ViewKey::default()));
+        sta.constrain_endpoints(constraints);
+        sta.propagate_required();
+        Ok(sta)
+    }
+
+    /// Computes the arrival times of the paths selected by `key` level by level
+    fn propagate_arrival(&self, key: &ViewKey) -> View {
+        let (schedule, delays, launch) = (&self.schedule, &self.delays, &self.launch);
+        let none = (f64::NEG_INFINITY, None);
+        let latest = schedule.map_levels([none; 2], |index, arrival| {
+            if key.masked.contains(&index) || key.killed.contains(&index) {
+                return [none; 2];
+            }
+            // The paths that reach a `through` node have gone through it
+            let through = key.through.as_ref().is_some_and(|t| t.contains(&index));
+            let mut latest = [none; 2];
+            if schedule.sources[index] {
+                latest[usize::from(through)] = (launch[index], None);
+            } else {
+                for (pos, operand) in schedule.operands[index].iter().enumerate() {
+                    if let Some((root, _)) = operand {
+                        for (after, (value, _)) in [false, true].into_iter().zip(arrival[*root]) {
+                            let best = &mut latest[usize::from(through || after)];
+                            if best.1.is_none() || value > best.0 {
+                                *best = (value, Some((pos, after)));
+                            }
+                        }
+                    }
+                }
+                // A cell without connected inputs launches like a constant
+                if latest.iter().all(|(_, c)| c.is_none()) {
+                    latest[usize::from(through)] = (0.0, None);
+                }
+            }
+            latest.map(|(value, critical)| (value + delays[index], critical))
+        });
+        let [before, after] = [0, 1].map(|state| {
+            let arrival = latest.iter().map(|l| l[state].0).collect();
+            let critical = latest.iter().map(|l| l[state].1).collect();
+            (arrival, critical)
+        });
+        View {
+            arrival: [before.0, after.0],
+            critical: [before.1, after.1],
+            through: key.through.is_some(),
+        }
+    }
+
+    /// Returns the arrival time of `net` in `view`
+    fn view_arrival(&self, view: usize, net: &DrivenNet<I>) -> f64 {
+        let view = &self.views[view];
+        view.arrival[usize::from(view.through)][net.netref.netref.borrow().get_index()]
+    }
+
+    /// Returns the view of the paths selected by `key`, computing it on first use
+    fn view(&mut self, views: &mut HashMap<ViewKey, usize>, key: ViewKey) -> usize {
+        if let Some(view) = views.get(&key) {
+            return *view;
+        }
+        let view = self.propagate_arrival(&key);
+        self.views.push(view);
+        views.insert(key, self.views.len() - 1);
+        self.views.len() - 1
+    }
+
+    /// Finds the endpoints and applies the output delays, false paths and multicycle paths of `constraints`.
+    /// The startpoints of an endpoint are grouped by the exceptions that apply to them, and each group is timed in its own
+    /// views: one for the paths that avoid the `-through` nodes of the exceptions, and one for the paths through the
+    /// nodes of the multicycle paths.
+    fn constrain_endpoints(&mut self, constraints: &Constraints) {
+        let mut endpoints: Vec<(Endpoint<I>, DrivenNet<I>)> = self
+            .netlist
+            .outputs()
+            .into_iter()
+            .map(|(driver, net)| (Endpoint::Output(net), driver))
+            .collect();
+        for node in self.order.iter().filter(|n| is_source(n)) {
+            for port in node.inputs() {
+                if let Some(driver) = port.get_driver() {
+                    endpoints.push((Endpoint::Pin(port), driver));
+                }
+            }
+        }
+
+        let clocks: Vec<&str> = constraints.clocks.iter().map(|c| c.name.as_str()).collect();
+        let is_clock = |t: &Target| clocks.iter().any(|c| t.matches_clock(c));
+        let startpoints: Vec<(usize, NetRef<I>)> = self
+            .order
+            .iter()
+            .filter(|n| is_source(n))
+            .map(|n| (n.netref.borrow().get_index(), n.clone()))
+            .collect();
+        let nodes: Vec<NetRef<I>> = self.netlist.objects().collect();
+        let exception = |multiplier, from: &[Target], through: &[Target], to| {
+            let from = (!from.is_empty() && !from.iter().any(is_clock)).then(|| {
+                startpoints
+                    .iter()
+                    .filter(|(_, s)| from.iter().any(|t| startpoint_matches(s, t)))
+                    .map(|(i, _)| *i)
+                    .collect()
+            });
+            let through = (!through.is_empty()).then(|| {
+                (0..nodes.len())
+                    .filter(|i| through.iter().any(|t| through_matches(&nodes[*i], t)))
+                    .collect()
+            });
+            Exception {
+                multiplier,
+                from,
+                through,
+                to,
+            }
+        };
+        let exceptions: Vec<Exception> = constraints
+            .false_paths
+            .iter()
+            .map(|p| exception(None, &p.from, &p.through, &p.to[..]))
+            .chain(
+                constraints
+                    .multicycle_paths
+                    .iter()
+                    .map(|p| exception(Some(p.multiplier), &p.from, &p.through, &p.to[..])),
+            )
+            .collect();
+        let mut views: HashMap<ViewKey, usize> = HashMap::new();
+        views.insert(ViewKey::default(), 0);
+
+        for (endpoint, driver) in endpoints {
+            let required = match &endpoint {
+                Endpoint::Output(net) => {
+                    let name = net.get_identifier().to_string();
+                    self.period - constraints.output_delay(&name).unwrap_or(0.0)
+                }
+                Endpoint::Pin(_) => self.period,
+            };
+            let period = self.period;
+            let with_multiplier = |m: usize| required + (m as f64 - 1.0) * period;
+            let applicable: Vec<&Exception> = exceptions
+                .iter()
+                .filter(|e| {
+                    e.to.is_empty() || e.to.iter().any(|t| is_clock(t) || endpoint.matches(t))
+                })
+                .collect();
+            if applicable.is_empty() {
+                self.endpoints.push(EndpointTiming {
+                    endpoint,
+                    driver,
+                    checks: vec![(0, required)],
+                });
+                continue;
+            }
+
+            // The startpoints, by the exceptions that apply to them
+            let mut groups: BTreeMap<Vec<usize>, BTreeSet<usize>> = BTreeMap::new();
+            for (index, _) in startpoints.iter() {
+                let armed = (0..applicable.len())
+                    .filter(|e| {
+                        applicable[*e]
+                            .from
+                            .as_ref()
+                            .is_none_or(|f| f.contains(index))
+                    })
+                    .collect();
+                groups.entry(armed).or_default().insert(*index);
+            }
+            let mut checks = Vec::new();
+            for (armed, group) in groups {
+                let armed: Vec<&Exception> = armed.into_iter().map(|e| applicable[e]).collect();
+                let (false_paths, multicycles): (Vec<&Exception>, Vec<&Exception>) =
+                    armed.into_iter().partition(|e| e.multiplier.is_none());
+                if false_paths.iter().any(|e| e.through.is_none()) {
+                    continue;
+                }
+                let masked: BTreeSet<usize> = startpoints
+                    .iter()
+                    .map(|(i, _)| *i)
+                    .filter(|i| !group.contains(i))
+                    .collect();
+                let mut killed: BTreeSet<usize> = false_paths
+                    .iter()
+                    .flat_map(|e| e.through.iter().flatten().copied())
+                    .collect();
+                let multiplier = multicycles
+                    .iter()
+                    .filter(|e| e.through.is_none())
+                    .filter_map(|e| e.multiplier)
+                    .fold(1, usize::max);
+                let through: Vec<&&Exception> =
+                    multicycles.iter().filter(|e| e.through.is_some()).collect();
+                if !through.is_empty() {
+                    let nodes: BTreeSet<usize> = through
+                        .iter()
+                        .flat_map(|e| e.through.iter().flatten().copied())
+                        .collect();
+                    let through_multiplier = through
+                        .iter()
+                        .filter_map(|e| e.multiplier)
+                        .fold(1, usize::max);
+                    let key = ViewKey {
+                        masked: masked.clone(),
+                        killed: killed.clone(),
+                        through: Some(nodes.clone()),
+                    };
+                    checks.push((
+                        self.view(&mut views, key),
+                        with_multiplier(through_multiplier),
+                    ));
+                    killed.extend(nodes);
+                }
+                let key = ViewKey {
+                    masked,
+                    killed,
+                    through: None,
+                };
+                checks.push((self.view(&mut views, key), with_multiplier(multiplier)));
+            }
+            self.endpoints.push(EndpointTiming {
+                endpoint,
+                driver,
+                checks,
+            });
+        }
+    }
+
+    /// Computes required times in reverse topological order, once the endpoints are all known
+    fn propagate_required(&mut self) {
+        self.required = self
+            .netlist
+            .objects()
+            .map(|o| vec![f64::INFINITY; o.outputs().count()])
+            .collect();
+        for endpoint in self.endpoints.iter() {
+            let index = endpoint.driver.netref.netref.borrow().get_index();
+            let value = &mut self.required[index][endpoint.driver.pos];
+            *value = endpoint.checks.iter().fold(*value, |a, (_, b)| a.min(*b));
+        }
+        for node in self.order.iter().rev().filter(|n| !is_source(n)) {
+            let index = node.netref.borrow().get_index();
+            let required = self.required[index]
+                .iter()
+                .fold(f64::INFINITY, |a, b| a.min(*b))
+                - self.delays[index];
+            for op in node.netref.borrow().operands.iter().flatten() {
+                let value = &mut self.required[op.root()][op.secondary()];
+                *value = value.min(required);
+            }
+        }
+    }
+
+    /// Returns the clock period
+    pub fn get_period(&self) -> f64 {
+        self.period
+    }
+
+    /// Returns the latest arrival time of `net`, which is negative infinity if no timed path reaches it
+    pub fn arrival(&self, net: &DrivenNet<I>) -> f64 {
+        self.view_arrival(0, net)
+    }
+
+    /// Returns the earliest required time of `net`, which is infinite if it reaches no endpoint
+    pub fn required(&self, net: &DrivenNet<I>) -> f64 {
+        self.required[net.netref.netref.borrow().get_index()][net.pos]
+    }
+
+    /// Returns the slack of `net`
+    pub fn slack(&self, net: &DrivenNet<I>) -> f64 {
+        self.required(net) - self.arrival(net)
+    }
+
+    /// Returns the slack of an endpoint, with the view of its worst check
+    fn endpoint_slack(&self, endpoint: &EndpointTiming<I>) -> (f64, usize) {
+        endpoint
+            .checks
+            .iter()
+            .map(|(view, required)| (required - self.view_arrival(*view, &endpoint.driver), *view))
+            .fold((f64::INFINITY, 0), |a, b| if b.0 < a.0 { b } else { a })
+    }
+
+    /// Returns the worst slack of any endpoint, which is infinite if there are none
+    pub fn worst_slack(&self) -> f64 {
+        self.endpoints
+            .iter()
+            .map(|e| self.endpoint_slack(e).0)
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Traces the latest arriving path back from `net`
+    fn trace(&self, net: DrivenNet<I>, view: &View) -> Vec<Stage<I>> {
+        let mut stages = Vec::new();
+        let mut next = Some((net, usize::from(view.through)));
+        while let Some((net, state)) = next {
+            let index = net.netref.netref.borrow().get_index();
+            next = if is_source(&net.netref) {
+                None
+            } else {
+                view.critical[state][index].and_then(|(pos, after)| {
+                    let driver = net.netref.get_input(pos).get_driver()?;
+                    Some((driver, usize::from(after)))
+                })
+            };
+            stages.push(Stage {
+                delay: self.delays[index],
+                slew: self.slews[index],
+                arrival: view.arrival[state][index],
+                net,
+            });
+        }
+        stages.reverse();
+        stages
+    }
+
+    /// Reports the `n` endpoints with the worst slack, with the latest arriving path to each of them.
+    /// Unconstrained endpoints are left out, and endpoints with equal slack are reported in the order of the top-level outputs, then of the cells.
+    pub fn report_paths(&self, n: usize) -> Vec<TimingPath<I>> {
+        let mut endpoints: Vec<((f64, usize), &EndpointTiming<I>)> = self
+            .endpoints
+            .iter()
+            .map(|e| (self.endpoint_slack(e), e))
+            .filter(|((slack, _), _)| slack.is_finite())
+            .collect();
+        endpoints.sort_by(|a, b| a.0.0.total_cmp(&b.0.0));
+        endpoints
+            .into_iter()
+            .take(n)
+            .map(|((slack, view), e)| TimingPath {
+                endpoint: e.endpoint.clone(),
+                slack,
+                stages: self.trace(e.driver.clone(), &self.views[view]),
+            })
+            .collect()
+    }
+
+    /// Returns a DOT graph of the netlist, with the cells and connections along `paths` highlighted in red.
+    /// Top-level outputs are drawn as boxes.
+    pub fn to_dot(&self, paths: &[TimingPath<I>]) -> String {
+        let mut nodes: HashSet<NetId> = HashSet::new();
+        let mut edges: HashSet<(NetId, String)> = HashSet::new();
+        for path in paths {
+            for (i, stage) in path.stages.iter().enumerate() {
+                nodes.insert(stage.net.get_id());
+                let target = match path.stages.get(i + 1) {
+                    Some(next) => next.net.netref.get_id().to_string(),
+                    None => match &path.endpoint {
+                        Endpoint::Output(net) => format!("out:{}", net.get_identifier()),
+                        Endpoint::Pin(port) => port.clone().unwrap().get_id().to_string(),
+                    },
+                };
+                edges.insert((stage.net.get_id(), target));
+            }
+        }
+        let on_path = |net: &DrivenNet<I>| nodes.contains(&net.get_id());
+        const RED: &str = ", color=red, penwidth=2";
+
+        let mut dot = String::new();
+        writeln!(dot, "digraph \"{}\" {{", self.netlist.get_name()).unwrap();
+        for obj in self.netlist.objects() {
+            let label = match obj.get_instance_type() {
+                Some(inst) => format!("{}\\n{}", obj.get_instance_name().unwrap(), inst.get_name()),
+                None => obj.get_identifier().to_string(),
+            };
+            let highlight = if obj.outputs().any(|o| on_path(&o)) {
+                RED
+            } else {
+                ""
+            };
+            writeln!(
+                dot,
+                "  \"{}\" [label=\"{label}\"{highlight}];",
+                obj.get_id()
+            )
+            .unwrap();
+        }
+        for c in self.netlist.connections() {
+            let target = c.target().unwrap().get_id().to_string();
+            let highlight = if edges.contains(&(c.src().get_id(), target.clone())) {
+                RED
+            } else {
+                ""
+            };
+            writeln!(
+                dot,
+                "  \"{}\" -> \"{target}\" [label=\"{}\"{highlight}];",
+                c.src().netref.get_id(),
+                c.net().get_identifier()
+            )
+            .unwrap();
+        }
+        for (driver, net) in self.netlist.outputs() {
+            let target = format!("out:{}", net.get_identifier());
+            let highlight = if edges.contains(&(driver.get_id(), target.clone())) {
+                RED
+            } else {
+                ""
+            };
+            writeln!(
+                dot,
+                "  \"{target}\" [label=\"{}\", shape=box];",
+                net.get_identifier()
+            )
+            .unwrap();
+            writeln!(
+                dot,
+                "  \"{}\" -> \"{target}\" [label=\"{}\"{highlight}];",
+                driver.netref.get_id(),
+                driver.as_net().get_identifier()
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// The edits seen by the observers of an [IncrementalSta] since its last update
+#[derive(Default)]
+struct Dirty {
+    /// The circuit nodes whose drivers may have changed, and so their arrival times
+    arrival: HashSet<ObjectId>,
+    /// The circuit nodes whose loads may have changed, and so their required times
+    required: HashSet<ObjectId>,
+    /// The circuit nodes that were removed
+    removed: Vec<ObjectId>,
+}
+
+/// A static timing analysis that follows the edits to a netlist, like [Sta] without false paths.
+/// The observers of the netlist record which circuit nodes an edit touches, and [IncrementalSta::update] only propagates
+/// arrival times through their fan-out cones and required times through their fan-in cones.
+/// Edits that do not reconnect or insert nodes, like a change of cell in place, are not observed and must be reported
+/// with [IncrementalSta::invalidate].
+pub struct IncrementalSta<I: Instantiable> {
+    /// The netlist being timed
+    netlist: Rc<Netlist<I>>,
+    /// The constraints, without false paths
+    constraints: Constraints,
+    /// The delay model of the cells
+    model: Box<dyn DelayModel<I>>,
+    /// The delay of each circuit node
+    delays: AnnotationMap<f64>,
+    /// The transition time of the outputs of each circuit node
+    slews: AnnotationMap<f64>,
+    /// The arrival time of the outputs of each circuit node
+    arrival: AnnotationMap<f64>,
+    /// The required time of each net
+    required: AnnotationMap<f64, NetId>,
+    /// The drivers of each circuit node as of the last update, whose required times change when it is reconnected
+    drivers: AnnotationMap<Vec<ObjectId>>,
+    /// The drivers of the top-level outputs and their required times, as of the last update
+    outputs: Vec<(NetId, f64)>,
+    /// The edits since the last update
+    dirty: Rc<RefCell<Dirty>>,
+    /// The observers registered on the netlist
+    observers: Vec<ObserverId>,
+}
+
+impl<I> IncrementalSta<I>
+where
+    I: Instantiable,
+{
+    /// Times `netlist` under `constraints`, where `delay` models the delay of the cells like in [Sta::with_constraints],
+    /// and keeps the timing in sync with its edits until dropped.
+    /// Returns [Error::Unsupported] if `constraints` has timing exceptions, or an error if the netlist has combinational cycles.
+    pub fn new(
+        netlist: &Rc<Netlist<I>>,
+        constraints: &Constraints,
+        delay: impl DelayModel<I> + 'static,
+    ) -> Result<Self, Error> {
+        if !constraints.false_paths.is_empty() || !constraints.multicycle_paths.is_empty() {
+            return Err(Error::Unsupported(
+                "timing exceptions in incremental timing".to_string(),
+            ));
+        }
+        let dirty: Rc<RefCell<Dirty>> = Rc::default();
+        {
+            let mut dirty = dirty.borrow_mut();
+            for node in netlist.objects() {
+                dirty.arrival.insert(node.get_id());
+                dirty.required.insert(node.get_id());
+            }
+        }
+        let on_insert = dirty.clone();
+        let on_reconnect = dirty.clone();
+        let on_remove = dirty.clone();
+        let observers = vec![
+            netlist.on_insert(move |node| {
+                let mut dirty = on_insert.borrow_mut();
+                dirty.arrival.insert(node.get_id());
+                dirty.required.insert(node.get_id());
+                for driver in node.drivers().flatten() {
+                    dirty.required.insert(driver.get_id());
+                }
+            }),
+            netlist.on_reconnect(move |port| {
+                let mut dirty = on_reconnect.borrow_mut();
+                dirty.arrival.insert(port.netref.get_id());
+                if let Some(driver) = port.get_driver() {
+                    dirty.required.insert(driver.netref.get_id());
+                }
+            }),
+            netlist.on_remove(move |id| on_remove.borrow_mut().removed.push(id)),
+        ];
+        let mut sta = Self {
+            netlist: netlist.clone(),
+            constraints: constraints.clone(),
+            model: Box::new(delay),
+            delays: AnnotationMap::new(),
+            slews: AnnotationMap::new(),
+            arrival: AnnotationMap::new(),
+            required: AnnotationMap::new(),
+            drivers: AnnotationMap::new(),
+            outputs: Vec::new(),
+            dirty,
+            observers,
+        };
+        sta.update()?;
+        Ok(sta)
+    }
+
+    /// Marks `node` as edited, like after a change of its cell, so that the next update retimes it
+    pub fn invalidate(&self, node: &NetRef<I>) {
+        let mut dirty = self.dirty.borrow_mut();
+        dirty.arrival.insert(node.get_id());
+        dirty.required.insert(node.get_id());
+        for driver in node.drivers().flatten() {
+            dirty.required.insert(driver.get_id());
+        }
+    }
+
+    /// Returns the delay of the cell of `node` as of its last retiming, which is zero for principal inputs
+    fn delay_of(&self, node: &NetRef<I>) -> f64 {
+        self.delays.get(&node.get_id()).copied().unwrap_or_default()
+    }
+
+    /// Updates the delay and output transition time of `node` from its drivers and loads.
+    /// Returns `true` if its delay changed.
+    fn retime(&mut self, node: &NetRef<I>) -> bool {
+        let Some(inst) = node.get_instance_type() else {
+            return false;
+        };
+        let input_slew = if is_source(node) {
+            0.0
+        } else {
+            node.drivers()
+                .flatten()
+                .map(|d| self.slews.get(&d.get_id()).copied().unwrap_or_default())
+                .fold(0.0, f64::max)
+        };
+        let load = node
+            .outputs()
+            .map(|o| self.model.load(self.netlist.fanout(&o)))
+            .fold(0.0, f64::max);
+        let id = node.get_id();
+        self.slews
+            .insert(id, self.model.slew(&inst, input_slew, load));
+        let delay = self.model.delay(&inst, input_slew, load);
+        self.delays.insert(id, delay) != Some(delay)
+    }
+
+    /// Returns the arrival time of `node` from the arrival times of its drivers
+    fn arrival_of(&self, node: &NetRef<I>) -> f64 {
+        let latest = if node.is_an_input() {
+            let name = node.get_identifier().to_string();
+            if self.constraints.is_clock_port(&name) {
+                f64::NEG_INFINITY
+            } else {
+                self.constraints.input_delay(&name).unwrap_or(0.0)
+            }
+        } else if is_source(node) {
+            0.0
+        } else {
+            // A cell without connected inputs launches like a constant
+            node.drivers()
+                .flatten()
+                .map(|d| {
+                    self.arrival
+                        .get(&d.get_id())
+                        .copied()
+                        .unwrap_or(f64::NEG_INFINITY)
+                })
+                .reduce(f64::max)
+                .unwrap_or(0.0)
+        };
+        latest + self.delay_of(node)
+    }
+
+    /// Returns the required time of `net` from the endpoints and loads it drives
+    fn required_of(&self, net: &DrivenNet<I>) -> f64 {
+        let id = net.get_id();
+        let mut required = self
+            .outputs
+            .iter()
+            .filter(|(o, _)| *o == id)
+            .map(|(_, r)| *r)
+            .fold(f64::INFINITY, f64::min);
+        for load in self.netlist.get_uses(net) {
+            let load = load.unwrap();
+            let value = if is_source(&load) {
+                self.constraints.get_period()
+            } else {
+                load.outputs()
+                    .map(|o| {
+                        self.required
+                            .get(&o.get_id())
+                            .copied()
+                            .unwrap_or(f64::INFINITY)
+                    })
+                    .fold(f64::INFINITY, f64::min)
+                    - self.delay_of(&load)
+            };
+            required = required.min(value);
+        }
+        required
+    }
+
+    /// Brings the timing up to date with the edits since the last update.
+    /// Returns an error with the nets of the edited nodes left on combinational loops.
+    pub fn update(&mut self) -> Result<(), Error> {
+        let dirty = self.dirty.take();
+        let mut arrival_seeds = Vec::new();
+        let mut required_seeds: HashSet<ObjectId> = dirty.required;
+        for id in dirty.removed {
+            if let Some(drivers) = self.drivers.remove(&id) {
+                required_seeds.extend(drivers);
+            }
+            self.arrival.remove(&id);
+            self.delays.remove(&id);
+            self.slews.remove(&id);
+        }
+        self.required.retain_live(&self.netlist);
+        for id in dirty.arrival {
+            let Some(node) = self.netlist.find_object(id) else {
+                continue;
+            };
+            let drivers: Vec<ObjectId> = node.drivers().flatten().map(|d| d.get_id()).collect();
+            if let Some(old) = self.drivers.insert(id, drivers.clone()) {
+                required_seeds.extend(old);
+            }
+            required_seeds.extend(drivers);
+            arrival_seeds.push(node);
+        }
+        let outputs: Vec<(NetId, f64)> = self
+            .netlist
+            .outputs()
+            .into_iter()
+            .map(|(driver, net)| {
+                let name = net.get_identifier().to_string();
+                let required = self.constraints.get_period()
+                    - self.constraints.output_delay(&name).unwrap_or(0.0);
+                (driver.get_id(), required)
+            })
+            .collect();
+        if outputs != self.outputs {
+            required_seeds.extend(self.outputs.iter().chain(&outputs).map(|(d, _)| d.object));
+            self.outputs = outputs;
+        }
+        let mut required_seeds: Vec<NetRef<I>> = required_seeds
+            .into_iter()
+            .filter_map(|id| self.netlist.find_object(id))
+            .collect();
+
+        // Arrival times flow from drivers to loads, along with the transition times and the delays of the nodes
+        // whose load changed. Required times flow the other way, from the nodes whose loads or delay changed.
+        arrival_seeds.extend(required_seeds.iter().cloned());
+        for node in self.cone_order(arrival_seeds, true)? {
+            if self.retime(&node) {
+                required_seeds.push(node.clone());
+            }
+            let arrival = self.arrival_of(&node);
+            self.arrival.insert(node.get_id(), arrival);
+        }
+        for node in self.cone_order(required_seeds, false)? {
+            for net in node.outputs() {
+                let required = self.required_of(&net);
+                self.required.insert(net.get_id(), required);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the cone of `seeds` in the order times are propagated, from drivers to loads if `forward`.
+    /// Times only flow through combinational nodes, as the sources launch and capture on the clock.
+    fn cone_order(&self, seeds: Vec<NetRef<I>>, forward: bool) -> Result<Vec<NetRef<I>>, Error> {
+        // Whether time flows from `from` into `to`
+        let flows = |from: &NetRef<I>, to: &NetRef<I>| {
+            if forward {
+                !is_source(to)
+            } else {
+                !is_source(from)
+            }
+        };
+        let next = |node: &NetRef<I>| -> Vec<NetRef<I>> {
+            let nodes: Vec<NetRef<I>> = if forward {
+                node.outputs()
+                    .flat_map(|o| self.netlist.get_uses(&o))
+                    .map(|i| i.unwrap())
+                    .collect()
+            } else {
+                node.drivers().flatten().collect()
+            };
+            nodes.into_iter().filter(|n| flows(node, n)).collect()
+        };
+
+        let mut cone: HashSet<NetRef<I>> = seeds.iter().cloned().collect();
+        let mut stack = seeds;
+        while let Some(node) = stack.pop() {
+            for n in next(&node) {
+                if cone.insert(n.clone()) {
+                    stack.push(n);
+                }
+            }
+        }
+
+        let mut degree: HashMap<NetRef<I>, usize> = cone.iter().map(|n| (n.clone(), 0)).collect();
+        for node in cone.iter() {
+            for n in next(node) {
+                *degree.get_mut(&n).unwrap() += 1;
+            }
+        }
+        let mut ready: Vec<NetRef<I>> = degree
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(n, _)| n.clone())
+            .collect();
+        let mut order = Vec::with_capacity(cone.len());
+        while let Some(node) = ready.pop() {
+            for n in next(&node) {
+                let d = degree.get_mut(&n).unwrap();
+                *d -= 1;
+                if *d == 0 {
+                    ready.push(n);
+                }
+            }
+            order.push(node);
+        }
+        if order.len() < cone.len() {
+            let nets = degree
+                .iter()
+                .filter(|(_, d)| **d > 0)
+                .flat_map(|(n, _)| n.nets())
+                .collect();
+            return Err(Error::CycleDetected(nets));
+        }
+        Ok(order)
+    }
+
+    /// Returns the latest arrival time of `net` as of the last update, which is negative infinity if no timed path reaches it
+    pub fn arrival(&self, net: &DrivenNet<I>) -> f64 {
+        self.arrival
+            .get(&net.netref.get_id())
+            .copied()
+            .unwrap_or(f64::NEG_INFINITY)
+    }
+
+    /// Returns the earliest required time of `net` as of the last update, which is infinite if it reaches no endpoint
+    pub fn required(&self, net: &DrivenNet<I>) -> f64 {
+        self.required
+            .get(&net.get_id())
+            .copied()
+            .unwrap_or(f64::INFINITY)
+    }
+
+    /// Returns the slack of `net` as of the last update
+    pub fn slack(&self, net: &DrivenNet<I>) -> f64 {
+        self.required(net) - self.arrival(net)
+    }
+
+    /// Returns the worst slack of any endpoint as of the last update, which is infinite if there are none
+    pub fn worst_slack(&self) -> f64 {
+        let outputs = self.outputs.iter().map(|(driver, required)| {
+            required
+                - self
+                    .arrival
+                    .get(&driver.object)
+                    .copied()
+                    .unwrap_or(f64::NEG_INFINITY)
+        });
+        let pins = self
+            .netlist
+            .objects()
+            .filter(|n| is_source(n) && !n.is_an_input())
+            .flat_map(|n| n.drivers().flatten().collect::<Vec<_>>())
+            .map(|d| {
+                let arrival = self.arrival.get(&d.get_id()).copied();
+                self.constraints.get_period() - arrival.unwrap_or(f64::NEG_INFINITY)
+            });
+        outputs.chain(pins).fold(f64::INFINITY, f64::min)
+    }
+}
+
+impl<I> Drop for IncrementalSta<I>
+where
+    I: Instantiable,
+{
+    fn drop(&mut self) {
+        for id in self.observers.drain(..) {
+            self.netlist.remove_observer(id);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+/*!
+
+  Bounded sequential equivalence checking by simulation of unrolled netlists.
+
+  Combinational equivalence (see [crate::netlist::testing::check_equivalent]) compares netlists register by register,
+  so it cannot validate edits that move or recode registers, like retiming or clock gating. [seq_equiv] instead
+  runs both netlists side by side for a number of clock cycles from corresponding initial states, and compares their outputs
+  on every cycle.
+
+*/
+
+use super::{
+    DrivenNet, NetRef, Netlist,
+    sim::{Simulator, init_value, random_word},
+};
+use crate::{
+    circuit::{Evaluate, Identifier, Net},
+    error::Error,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Options for [seq_equiv_with]
+#[derive(Debug, Clone)]
+pub struct SeqEquivOptions {
+    /// The registers of the first netlist that correspond to registers of the second, by instance name.
+    /// Registers that are not listed correspond to the register of the same name, if there is one.
+    pub correspondence: HashMap<Identifier, Identifier>,
+    /// The number of random input sequences simulated, rounded up to a multiple of 64
+    pub patterns: usize,
+    /// The seed of the random inputs and initial states
+    pub seed: u64,
+}
+
+impl Default for SeqEquivOptions {
+    fn default() -> Self {
+        Self {
+            correspondence: HashMap::new(),
+            patterns: 256,
+            seed: 0,
+        }
+    }
+}
+
+/// The registers of one of the netlists under comparison and their current states
+struct Machine<'a, I: Evaluate> {
+    sim: Simulator<'a, I>,
+    /// The registers, with the word of their current state
+    registers: Vec<(NetRef<I>, u64)>,
+    /// The position of each register by the net it drives
+    positions: HashMap<DrivenNet<I>, usize>,
+}
+
+impl<'a, I> Machine<'a, I>
+where
+    I: Evaluate,
+{
+    /// Prepares the simulation of `netlist`, where `initial` is the starting state of the registers without an `INIT` value
+    fn new(netlist: &'a Netlist<I>, initial: impl Fn(&Identifier) -> u64) -> Result<Self, Error> {
+        let mut registers = Vec::new();
+        let mut positions = HashMap::new();
+        for node in netlist.objects() {
+            let Some(cell) = node.get_instance_type().map(|c| c.clone()) else {
+                continue;
+            };
+            let name = node.get_instance_name().expect("Instance has a name");
+            if cell.is_latch() {
+                return Err(Error::Unsupported(format!("latch {name}")));
+            }
+            if !cell.is_seq() {
+                continue;
+            }
+            if node.is_multi_output() {
+                return Err(Error::Unsupported(format!(
+                    "multi-output sequential cell {name}"
+                )));
+            }
+            let state = match init_value(&cell) {
+                Some(true) => u64::MAX,
+                Some(false) => 0,
+                None => initial(&name),
+            };
+            positions.insert(node.get_output(0), registers.len());
+            registers.push((node, state));
+        }
+        Ok(Self {
+            sim: Simulator::new(netlist)?,
+            registers,
+            positions,
+        })
+    }
+
+    /// Simulates the current cycle, where the inputs and black boxes take the random words of `word`
+    fn run(&mut self, seed: u64, word: usize) {
+        let (registers, positions) = (&self.registers, &self.positions);
+        self.sim.run(|net| match positions.get(net) {
+            Some(pos) => registers[*pos].1,
+            None => random_word(&net.as_net(), seed, word),
+        });
+    }
+
+    /// Moves every register to its next state, computed from the words of the current cycle
+    fn step(&mut self) {
+        for (node, state) in self.registers.iter_mut() {
+            let inputs: Vec<u64> = node
+                .inputs()
+                .map(|i| i.get_driver().map_or(0, |d| self.sim.get_word(&d)))
+                .collect();
+            *state = node.get_instance_type().unwrap().eval_words(&inputs)[0];
+        }
+    }
+}
+
+/// Checks that `a` and `b` produce the same outputs for `bound` clock cycles, see [seq_equiv_with]
+pub fn seq_equiv<I>(a: &Netlist<I>, b: &Netlist<I>, bound: usize) -> Result<(), Error>
+where
+    I: Evaluate,
+{
+    seq_equiv_with(a, b, bound, &SeqEquivOptions::default())
+}
+
+/// Checks that `a` and `b` produce the same outputs for `bound` clock cycles under random input sequences.
+/// Inputs and outputs are matched by port name, and all the registers are clocked together: the next state of a sequential
+/// cell is its [Evaluate] function of its inputs. Registers start from the value of their `INIT` parameter when it is
+/// `0` or `1`. Otherwise corresponding registers start from the same random state, and the others start from `0`.
+/// Like [crate::netlist::testing::check_equivalent], passing does not prove equivalence, but failing proves non-equivalence.
+/// Returns [Error::NonequivalentOutputs] for outputs missing from either netlist or that differ, in an [Error::Context]
+/// with the first cycle they differ on. Returns [Error::Unsupported] for latches and multi-output sequential cells,
+/// or an error if a netlist has combinational cycles.
+pub fn seq_equiv_with<I>(
+    a: &Netlist<I>,
+    b: &Netlist<I>,
+    bound: usize,
+    options: &SeqEquivOptions,
+) -> Result<(), Error>
+where
+    I: Evaluate,
+{
+    let (a_outputs, b_outputs) = (a.outputs(), b.outputs());
+    let missing: Vec<_> = a_outputs
+        .iter()
+        .filter(|(_, n)| !b_outputs.iter().any(|(_, m)| m == n))
+        .chain(
+            b_outputs
+                .iter()
+                .filter(|(_, n)| !a_outputs.iter().any(|(_, m)| m == n)),
+        )
+        .map(|(_, n)| n.clone())
+        .collect();
+    if !missing.is_empty() {
+        return Err(Error::NonequivalentOutputs(missing));
+    }
+    let pairs: Vec<(DrivenNet<I>, DrivenNet<I>, _)> = a_outputs
+        .into_iter()
+        .map(|(a_net, port)| {
+            let b_net = b_outputs
+                .iter()
+                .find(|(_, n)| *n == port)
+                .unwrap()
+                .0
+                .clone();
+            (a_net, b_net, port)
+        })
+        .collect();
+
+    // Corresponding registers start from the random state of the name of the register of `b`
+    let initial = |name: &Identifier, others: &HashSet<Identifier>| {
+        if others.contains(name) {
+            random_word(&Net::new_logic(name.clone()), options.seed, usize::MAX)
+        } else {
+            0
+        }
+    };
+    let counterpart = |name: Identifier| options.correspondence.get(&name).cloned().unwrap_or(name);
+    let a_names: HashSet<Identifier> = a
+        .objects()
+        .filter_map(|o| o.get_instance_name())
+        .map(counterpart)
+        .collect();
+    let b_names: HashSet<Identifier> = b.objects().filter_map(|o| o.get_instance_name()).collect();
+    let a_initial = |name: &Identifier| initial(&counterpart(name.clone()), &b_names);
+    let b_initial = |name: &Identifier| initial(name, &a_names);
+
+    let n_words = options.patterns.div_ceil(64);
+    for pattern in 0..n_words {
+        let mut a_machine = Machine::new(a, a_initial)?;
+        let mut b_machine = Machine::new(b, b_initial)?;
+        for cycle in 0..bound {
+            let word = pattern * bound + cycle;
+            a_machine.run(options.seed, word);
+            b_machine.run(options.seed, word);
+            let differ: Vec<_> = pairs
+                .iter()
+                .filter(|(a_net, b_net, _)| {
+                    a_machine.sim.get_word(a_net) != b_machine.sim.get_word(b_net)
+                })
+                .map(|(_, _, port)| port.clone())
+                .collect();
+            if !differ.is_empty() {
+                return Err(
+                    Error::NonequivalentOutputs(differ).context(format!("At cycle {cycle}"), [])
+                );
+            }
+            a_machine.step();
+            b_machine.step();
+        }
+    }
+    Ok(())
+}
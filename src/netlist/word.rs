@@ -0,0 +1,501 @@
+/*!
+
+  Word-level datapath cells, like adders and multiplexers over buses, and their lowering to gates.
+
+*/
+
+use super::{DrivenNet, Gate, Netlist};
+use crate::{
+    attribute::Parameter,
+    circuit::{Evaluate, Identifier, Instantiable, Net},
+    error::Error,
+    format_id,
+    logic::Logic,
+};
+use std::rc::Rc;
+
+/// The operation computed by a [WordCell]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WordOp {
+    /// `Y = A + B`, truncated to the width of the operands
+    Add,
+    /// `Y = A - B`, truncated to the width of the operands
+    Sub,
+    /// `Y = A * B`, truncated to the width of the operands
+    Mul,
+    /// `Y = A == B`
+    Eq,
+    /// `Y = A < B`, comparing the operands as unsigned numbers
+    Lt,
+    /// `Y = S ? B : A`
+    Mux,
+}
+
+impl WordOp {
+    /// Returns the name of the operation, which prefixes the name of the cell
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WordOp::Add => "ADD",
+            WordOp::Sub => "SUB",
+            WordOp::Mul => "MUL",
+            WordOp::Eq => "EQ",
+            WordOp::Lt => "LT",
+            WordOp::Mux => "MUX",
+        }
+    }
+
+    /// Returns `true` if the operation is a comparison with a single output bit
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, WordOp::Eq | WordOp::Lt)
+    }
+}
+
+/// Returns the ports `name[0]`..`name[width - 1]`
+fn bus_ports(name: &str, width: usize) -> Vec<Net> {
+    (0..width)
+        .map(|i| Net::new_logic(format_id!("{name}[{i}]")))
+        .collect()
+}
+
+/// A word-level cell over buses of equal width, named by its operation and width (e.g. `ADD8`).
+/// The input buses are `A` and `B`, preceded by the select bit `S` for [WordOp::Mux].
+/// The output is the bus `Y`, or the single bit `Y` for comparisons.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WordCell {
+    /// The operation of the cell
+    op: WordOp,
+    /// The width of the operands
+    width: usize,
+    /// The name of the cell
+    name: Identifier,
+    /// Input ports, order matters
+    inputs: Vec<Net>,
+    /// Output ports, order matters
+    outputs: Vec<Net>,
+}
+
+impl WordCell {
+    /// Creates a word-level cell computing `op` over operands of `width` bits
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    pub fn new(op: WordOp, width: usize) -> Self {
+        if width == 0 {
+            panic!("Attempted to create a word-level cell of zero width");
+        }
+        let mut inputs = Vec::with_capacity(2 * width + 1);
+        if op == WordOp::Mux {
+            inputs.push(Net::new_logic("S".into()));
+        }
+        inputs.extend(bus_ports("A", width));
+        inputs.extend(bus_ports("B", width));
+        let outputs = if op.is_comparison() {
+            vec![Net::new_logic("Y".into())]
+        } else {
+            bus_ports("Y", width)
+        };
+        Self {
+            op,
+            width,
+            name: format_id!("{}{width}", op.as_str()),
+            inputs,
+            outputs,
+        }
+    }
+
+    /// Returns the operation of the cell
+    pub fn get_op(&self) -> WordOp {
+        self.op
+    }
+
+    /// Returns the width of the operands
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+}
+
+impl Instantiable for WordCell {
+    fn get_name(&self) -> &Identifier {
+        &self.name
+    }
+
+    fn get_input_ports(&self) -> impl IntoIterator<Item = &Net> {
+        &self.inputs
+    }
+
+    fn get_output_ports(&self) -> impl IntoIterator<Item = &Net> {
+        &self.outputs
+    }
+
+    fn has_parameter(&self, _id: &Identifier) -> bool {
+        false
+    }
+
+    fn get_parameter(&self, _id: &Identifier) -> Option<Parameter> {
+        None
+    }
+
+    fn set_parameter(&mut self, _id: &Identifier, _val: Parameter) -> Option<Parameter> {
+        None
+    }
+
+    fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)> {
+        std::iter::empty()
+    }
+
+    fn from_constant(_val: Logic) -> Option<Self> {
+        None
+    }
+
+    fn get_constant(&self) -> Option<Logic> {
+        None
+    }
+
+    fn is_seq(&self) -> bool {
+        false
+    }
+}
+
+/// Returns the sum of `a`, `b` and `carry`, truncated to the width of `a`, and the carry out
+fn add_bits(a: &[bool], b: &[bool], mut carry: bool) -> (Vec<bool>, bool) {
+    let sum = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| {
+            let s = a ^ b ^ carry;
+            carry = (a & b) | (carry & (a ^ b));
+            s
+        })
+        .collect();
+    (sum, carry)
+}
+
+/// The outputs are unknown if any input is unknown
+impl Evaluate for WordCell {
+    fn eval(&self, inputs: &[Logic]) -> Vec<Logic> {
+        let bits: Option<Vec<bool>> = inputs
+            .iter()
+            .map(|l| match l {
+                Logic::True => Some(true),
+                Logic::False => Some(false),
+                _ => None,
+            })
+            .collect();
+        let Some(bits) = bits else {
+            return vec![Logic::X; self.outputs.len()];
+        };
+
+        let w = self.width;
+        let out = match self.op {
+            WordOp::Add => add_bits(&bits[..w], &bits[w..], false).0,
+            WordOp::Sub => {
+                let not_b: Vec<bool> = bits[w..].iter().map(|b| !b).collect();
+                add_bits(&bits[..w], &not_b, true).0
+            }
+            WordOp::Mul => {
+                let (a, b) = bits.split_at(w);
+                let mut acc = vec![false; w];
+                for (i, _) in b.iter().enumerate().filter(|(_, b)| **b) {
+                    let mut shifted = vec![false; i];
+                    shifted.extend_from_slice(&a[..w - i]);
+                    acc = add_bits(&acc, &shifted, false).0;
+                }
+                acc
+            }
+            WordOp::Eq => vec![bits[..w] == bits[w..]],
+            WordOp::Lt => {
+                let not_b: Vec<bool> = bits[w..].iter().map(|b| !b).collect();
+                vec![!add_bits(&bits[..w], &not_b, true).1]
+            }
+            WordOp::Mux => {
+                if bits[0] {
+                    bits[w + 1..].to_vec()
+                } else {
+                    bits[1..w + 1].to_vec()
+                }
+            }
+        };
+        out.into_iter().map(Logic::from_bool).collect()
+    }
+}
+
+/// A cell of a word-level netlist: either a gate or a word-level cell
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Cell {
+    /// A primitive gate
+    Gate(Gate),
+    /// A word-level cell
+    Word(WordCell),
+}
+
+impl Cell {
+    /// Creates a word-level cell computing `op` over operands of `width` bits
+    pub fn word(op: WordOp, width: usize) -> Self {
+        Cell::Word(WordCell::new(op, width))
+    }
+}
+
+impl From<Gate> for Cell {
+    fn from(gate: Gate) -> Self {
+        Cell::Gate(gate)
+    }
+}
+
+impl From<WordCell> for Cell {
+    fn from(cell: WordCell) -> Self {
+        Cell::Word(cell)
+    }
+}
+
+impl Instantiable for Cell {
+    fn get_name(&self) -> &Identifier {
+        match self {
+            Cell::Gate(g) => g.get_name(),
+            Cell::Word(w) => w.get_name(),
+        }
+    }
+
+    fn get_input_ports(&self) -> impl IntoIterator<Item = &Net> {
+        match self {
+            Cell::Gate(g) => g.get_input_ports().into_iter().collect::<Vec<_>>(),
+            Cell::Word(w) => w.get_input_ports().into_iter().collect::<Vec<_>>(),
+        }
+    }
+
+    fn get_output_ports(&self) -> impl IntoIterator<Item = &Net> {
+        match self {
+            Cell::Gate(g) => g.get_output_ports().into_iter().collect::<Vec<_>>(),
+            Cell::Word(w) => w.get_output_ports().into_iter().collect::<Vec<_>>(),
+        }
+    }
+
+    fn has_parameter(&self, _id: &Identifier) -> bool {
+        false
+    }
+
+    fn get_parameter(&self, _id: &Identifier) -> Option<Parameter> {
+        None
+    }
+
+    fn set_parameter(&mut self, _id: &Identifier, _val: Parameter) -> Option<Parameter> {
+        None
+    }
+
+    fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)> {
+        std::iter::empty()
+    }
+
+    fn from_constant(val: Logic) -> Option<Self> {
+        Gate::from_constant(val).map(Cell::Gate)
+    }
+
+    fn get_constant(&self) -> Option<Logic> {
+        match self {
+            Cell::Gate(g) => g.get_constant(),
+            Cell::Word(_) => None,
+        }
+    }
+
+    fn fold(&self, inputs: &[Option<Logic>]) -> Option<Logic> {
+        match self {
+            Cell::Gate(g) => g.fold(inputs),
+            Cell::Word(_) => None,
+        }
+    }
+
+    fn is_seq(&self) -> bool {
+        false
+    }
+}
+
+impl Evaluate for Cell {
+    fn eval(&self, inputs: &[Logic]) -> Vec<Logic> {
+        match self {
+            Cell::Gate(g) => g.eval(inputs),
+            Cell::Word(w) => w.eval(inputs),
+        }
+    }
+
+    fn eval_words(&self, inputs: &[u64]) -> Vec<u64> {
+        match self {
+            Cell::Gate(g) => g.eval_words(inputs),
+            Cell::Word(w) => w.eval_words(inputs),
+        }
+    }
+}
+
+/// A netlist of gates and word-level cells
+pub type WordNetlist = Netlist<Cell>;
+
+/// Inserts the gates of a lowered word-level cell, named after the cell
+struct Lowering<'a> {
+    netlist: &'a Rc<WordNetlist>,
+    inst_name: Identifier,
+    count: usize,
+}
+
+impl Lowering<'_> {
+    /// Inserts a gate of the bundled gate library computing `name` over `inputs`
+    fn gate(&mut self, name: &str, inputs: &[DrivenNet<Cell>]) -> Result<DrivenNet<Cell>, Error> {
+        let ports: &[&str] = if name == "MUX" {
+            &["S", "A", "B"]
+        } else {
+            &["A", "B"][..inputs.len()]
+        };
+        let ports = ports.iter().map(|p| Identifier::from(*p)).collect();
+        let gate = Gate::new_logical(name.into(), ports, "Y".into());
+        let inst_name = &self.inst_name + &format_id!("g{}", self.count);
+        self.count += 1;
+        Ok(self
+            .netlist
+            .insert_gate(gate.into(), inst_name, inputs)?
+            .get_output(0))
+    }
+
+    /// Inserts a constant driver
+    fn constant(&mut self, value: Logic) -> Result<DrivenNet<Cell>, Error> {
+        let inst_name = &self.inst_name + &format_id!("g{}", self.count);
+        self.count += 1;
+        self.netlist.insert_constant(value, inst_name)
+    }
+
+    /// Builds a ripple-carry adder, returning the sum and the carry out
+    fn add(
+        &mut self,
+        a: &[DrivenNet<Cell>],
+        b: &[DrivenNet<Cell>],
+        mut carry: DrivenNet<Cell>,
+    ) -> Result<(Vec<DrivenNet<Cell>>, DrivenNet<Cell>), Error> {
+        let mut sum = Vec::with_capacity(a.len());
+        for (a, b) in a.iter().zip(b) {
+            let half = self.gate("XOR", &[a.clone(), b.clone()])?;
+            sum.push(self.gate("XOR", &[half.clone(), carry.clone()])?);
+            let generate = self.gate("AND", &[a.clone(), b.clone()])?;
+            let propagate = self.gate("AND", &[half, carry])?;
+            carry = self.gate("OR", &[generate, propagate])?;
+        }
+        Ok((sum, carry))
+    }
+
+    /// Builds `a + !b + 1`, returning the difference and the carry out
+    fn sub(
+        &mut self,
+        a: &[DrivenNet<Cell>],
+        b: &[DrivenNet<Cell>],
+    ) -> Result<(Vec<DrivenNet<Cell>>, DrivenNet<Cell>), Error> {
+        let not_b = b
+            .iter()
+            .map(|b| self.gate("INV", std::slice::from_ref(b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let one = self.constant(Logic::True)?;
+        self.add(a, &not_b, one)
+    }
+
+    /// Builds the gates computing `cell` over `inputs`, returning the nets of its outputs
+    fn lower(
+        &mut self,
+        cell: &WordCell,
+        inputs: &[DrivenNet<Cell>],
+    ) -> Result<Vec<DrivenNet<Cell>>, Error> {
+        let w = cell.width;
+        let (a, b) = inputs.split_at(inputs.len() - w);
+        let a = &a[a.len() - w..];
+        match cell.op {
+            WordOp::Add => {
+                let zero = self.constant(Logic::False)?;
+                Ok(self.add(a, b, zero)?.0)
+            }
+            WordOp::Sub => Ok(self.sub(a, b)?.0),
+            WordOp::Mul => {
+                let zero = self.constant(Logic::False)?;
+                let mut acc = vec![zero.clone(); w];
+                for (i, b) in b.iter().enumerate() {
+                    let mut row = vec![zero.clone(); i];
+                    for a in &a[..w - i] {
+                        row.push(self.gate("AND", &[a.clone(), b.clone()])?);
+                    }
+                    acc = self.add(&acc, &row, zero.clone())?.0;
+                }
+                Ok(acc)
+            }
+            WordOp::Eq => {
+                let same = a
+                    .iter()
+                    .zip(b)
+                    .map(|(a, b)| self.gate("XNOR", &[a.clone(), b.clone()]))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut eq = same[0].clone();
+                for s in &same[1..] {
+                    eq = self.gate("AND", &[eq, s.clone()])?;
+                }
+                Ok(vec![eq])
+            }
+            WordOp::Lt => {
+                let carry = self.sub(a, b)?.1;
+                Ok(vec![self.gate("INV", &[carry])?])
+            }
+            WordOp::Mux => {
+                let s = &inputs[0];
+                a.iter()
+                    .zip(b)
+                    .map(|(a, b)| self.gate("MUX", &[s.clone(), a.clone(), b.clone()]))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Lowers every word-level cell of `netlist` to gates of the bundled gate library (see [Gate]).
+/// The lowered gates drive the nets of the word-level cells under the same names, and the word-level cells are cleaned away.
+/// Returns an error if a word-level cell has an unconnected input.
+pub fn lower(netlist: &Rc<WordNetlist>) -> Result<(), Error> {
+    let words: Vec<_> = netlist
+        .objects()
+        .filter(|o| matches!(o.get_instance_type().as_deref(), Some(Cell::Word(_))))
+        .map(|o| o.get_id())
+        .collect();
+
+    for id in words {
+        let node = netlist
+            .find_object(id)
+            .expect("Word-level cell was removed");
+        let Some(Cell::Word(cell)) = node.get_instance_type().as_deref().cloned() else {
+            unreachable!("Object is not a word-level cell");
+        };
+        let inst_name = node.get_instance_name().unwrap();
+        let inputs = node
+            .inputs()
+            .map(|i| {
+                i.get_driver().ok_or_else(|| {
+                    Error::UnconnectedInputs(vec![(inst_name.clone(), i.get_port())])
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut lowering = Lowering {
+            netlist,
+            inst_name: inst_name.clone(),
+            count: 0,
+        };
+        let lowered = lowering.lower(&cell, &inputs)?;
+        drop(inputs);
+        drop(node);
+
+        // Hand the names of the word-level nets over to the gates
+        for (i, new) in lowered.into_iter().enumerate() {
+            let old = netlist.find_object(id).unwrap().get_output(i);
+            let name = old.get_identifier();
+            old.as_net_mut()
+                .set_identifier(&name + &Identifier::from("word"));
+            new.as_net_mut().set_identifier(name);
+            netlist.replace_net_uses(old, &new)?;
+        }
+    }
+
+    netlist.clean()?;
+    Ok(())
+}
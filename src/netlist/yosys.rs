@@ -0,0 +1,569 @@
+/*!
+
+  Exchange of netlists with [Yosys](https://yosyshq.net/yosys/) through its JSON format, so that passes written with
+  this crate can run as a step of a Yosys flow.
+
+  [Netlist::to_yosys_json] writes the top module of a design as `write_json` does, and [Netlist::from_yosys_json]
+  reads the top module of a `write_json` file back as a netlist of [Gate]s. A pass runs inside a flow as a shell
+  command between the two, see [yosys_commands] and [run_yosys_step], like the `snet yosys` command.
+
+*/
+
+use super::{DrivenNet, Gate, GateNetlist, Netlist};
+use crate::{
+    attribute::{Attribute, Parameter},
+    circuit::{Identifier, Instantiable, Net},
+    error::Error,
+    logic::{Logic, LogicVec},
+};
+use bitvec::vec::BitVec;
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{MapAccess, Visitor},
+    ser::SerializeMap,
+};
+use serde_json::Value;
+use std::{collections::HashMap, marker::PhantomData, path::Path, rc::Rc};
+
+/// The entries of a JSON object in file order, which Yosys uses for the order of ports
+#[derive(Debug, Clone)]
+struct Ordered<T>(Vec<(String, T)>);
+
+impl<T> Default for Ordered<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T> Ordered<T> {
+    /// Returns the value of `key`
+    fn get(&self, key: &str) -> Option<&T> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Returns the value of `key`, inserting `value` first if there is none
+    fn entry(&mut self, key: String, value: impl FnOnce() -> T) -> &mut T {
+        let pos = match self.0.iter().position(|(k, _)| *k == key) {
+            Some(pos) => pos,
+            None => {
+                self.0.push((key, value()));
+                self.0.len() - 1
+            }
+        };
+        &mut self.0[pos].1
+    }
+}
+
+impl<T: Serialize> Serialize for Ordered<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (k, v) in &self.0 {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Ordered<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct OrderedVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for OrderedVisitor<T> {
+            type Value = Ordered<T>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Ordered(entries))
+            }
+        }
+
+        deserializer.deserialize_map(OrderedVisitor(PhantomData))
+    }
+}
+
+/// A bit of a Yosys signal: a net number, or a constant `"0"`, `"1"`, `"x"` or `"z"`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Bit {
+    Net(usize),
+    Constant(String),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Design {
+    #[serde(default)]
+    creator: String,
+    modules: Ordered<Module>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Module {
+    #[serde(default)]
+    attributes: Ordered<Value>,
+    #[serde(default)]
+    ports: Ordered<Port>,
+    #[serde(default)]
+    cells: Ordered<Cell>,
+    #[serde(default)]
+    netnames: Ordered<Netname>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Port {
+    direction: String,
+    bits: Vec<Bit>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Cell {
+    #[serde(default)]
+    hide_name: u8,
+    #[serde(rename = "type")]
+    cell_type: String,
+    #[serde(default)]
+    parameters: Ordered<Value>,
+    #[serde(default)]
+    attributes: Ordered<Value>,
+    #[serde(default)]
+    port_directions: Ordered<String>,
+    connections: Ordered<Vec<Bit>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Netname {
+    #[serde(default)]
+    hide_name: u8,
+    bits: Vec<Bit>,
+    #[serde(default)]
+    attributes: Ordered<Value>,
+}
+
+/// Returns the name of an identifier in Yosys, without the escape of escaped identifiers
+fn yosys_name(name: &Identifier) -> String {
+    match name.get_bit_index() {
+        Some(index) => format!("{}[{index}]", name.get_name()),
+        None => name.get_name().to_string(),
+    }
+}
+
+/// Returns the character of a logic value in a Yosys constant
+fn logic_char(value: Logic) -> char {
+    match value {
+        Logic::False => '0',
+        Logic::True => '1',
+        Logic::X => 'x',
+        Logic::Z => 'z',
+    }
+}
+
+/// Returns a parameter as Yosys writes it: integers as 32 bits, or 64 when they do not fit, and bit vectors most
+/// significant bit first. Strings made only of bits get a trailing space, which Yosys strips when reading them.
+fn to_yosys_value(value: &Parameter) -> Value {
+    let text = match value {
+        Parameter::Integer(i) => match i32::try_from(*i) {
+            Ok(i) => format!("{:032b}", i as u32),
+            Err(_) => format!("{:064b}", *i as u64),
+        },
+        Parameter::Real(r) => format!("{r:?}"),
+        Parameter::BitVec(bv) => bv
+            .iter()
+            .rev()
+            .map(|b| if *b { '1' } else { '0' })
+            .collect(),
+        Parameter::Logic(l) => logic_char(*l).to_string(),
+        Parameter::LogicVec(lv) => {
+            let bits: Vec<char> = lv.iter().map(logic_char).collect();
+            bits.into_iter().rev().collect()
+        }
+        Parameter::String(s) if is_constant(s) => format!("{s} "),
+        Parameter::String(s) => s.clone(),
+    };
+    Value::String(text)
+}
+
+/// Returns `true` if `text` reads as a Yosys constant, made only of the characters `01xz`
+fn is_constant(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| matches!(c, '0' | '1' | 'x' | 'z'))
+}
+
+/// Reads a parameter or attribute value of Yosys: constants of known bits are integers up to 64 bits, signed at 32 bits,
+/// and bit vectors beyond, constants with unknown bits are logic vectors, and anything else is a string
+fn from_yosys_value(value: &Value) -> Parameter {
+    match value {
+        Value::String(s) if is_constant(s) && !s.contains(['x', 'z']) => {
+            let value = u64::from_str_radix(&s[s.len().saturating_sub(64)..], 2).unwrap();
+            // Yosys writes integers as 32-bit signed constants
+            if s.len() == 32 {
+                Parameter::Integer(i64::from(value as u32 as i32))
+            } else if s.len() <= 64 {
+                Parameter::Integer(value as i64)
+            } else {
+                Parameter::BitVec(s.chars().rev().map(|c| c == '1').collect::<BitVec>())
+            }
+        }
+        Value::String(s) if is_constant(s) => Parameter::LogicVec(
+            s.chars()
+                .rev()
+                .map(|c| match c {
+                    '0' => Logic::False,
+                    '1' => Logic::True,
+                    'x' => Logic::X,
+                    _ => Logic::Z,
+                })
+                .collect::<LogicVec>(),
+        ),
+        Value::String(s) => match s.strip_suffix(' ') {
+            Some(constant) if is_constant(constant) => Parameter::String(constant.to_string()),
+            _ => Parameter::String(s.clone()),
+        },
+        Value::Number(n) if n.is_i64() => Parameter::Integer(n.as_i64().unwrap()),
+        Value::Number(n) => Parameter::Real(n.as_f64().unwrap_or_default()),
+        other => Parameter::String(other.to_string()),
+    }
+}
+
+/// Returns attributes as Yosys values sorted by key, where attributes without a value are the integer 1
+fn to_yosys_attributes(attributes: impl Iterator<Item = Attribute>) -> Ordered<Value> {
+    let mut attributes: Vec<(String, Value)> = attributes
+        .map(|a| {
+            let value = a.value().clone().unwrap_or(Parameter::Integer(1));
+            (a.key().clone(), to_yosys_value(&value))
+        })
+        .collect();
+    // Attributes are kept in hash maps, so they are sorted for a stable output
+    attributes.sort_by(|a, b| a.0.cmp(&b.0));
+    Ordered(attributes)
+}
+
+/// Groups `bits` named by identifiers into Yosys signals, where the bits `x[i]` of a bus `x` are in ascending order
+fn group_bits(bits: impl IntoIterator<Item = (Identifier, Bit)>) -> Ordered<Vec<Bit>> {
+    let mut groups: Ordered<Vec<(usize, Bit)>> = Ordered::default();
+    for (name, bit) in bits {
+        let key = match name.get_bit_index() {
+            Some(_) => name.get_name().to_string(),
+            None => yosys_name(&name),
+        };
+        groups
+            .entry(key, Vec::new)
+            .push((name.get_bit_index().unwrap_or(0), bit));
+    }
+    Ordered(
+        groups
+            .0
+            .into_iter()
+            .map(|(name, mut bits)| {
+                bits.sort_by_key(|(index, _)| *index);
+                (name, bits.into_iter().map(|(_, bit)| bit).collect())
+            })
+            .collect(),
+    )
+}
+
+impl<I> Netlist<I>
+where
+    I: Instantiable,
+{
+    /// Returns the netlist as a Yosys JSON design with a single module, the top, as `write_json` writes it.
+    /// Constant cells become the constant bits `"0"`, `"1"`, `"x"` and `"z"`, and bus ports and ports of cells are
+    /// grouped by name. Returns [Error::NonuniqueNets] if an output port has the name of an input.
+    pub fn to_yosys_json(&self) -> Result<String, Error> {
+        let mut bits: HashMap<DrivenNet<I>, Bit> = HashMap::new();
+        let mut next = 2;
+        for node in self.objects() {
+            let constant = node.get_instance_type().and_then(|i| i.get_constant());
+            for net in node.outputs() {
+                let bit = match constant {
+                    Some(value) => Bit::Constant(logic_char(value).to_string()),
+                    None => {
+                        next += 1;
+                        Bit::Net(next - 1)
+                    }
+                };
+                bits.insert(net, bit);
+            }
+        }
+        let driver_bit = |driver: Option<DrivenNet<I>>| match driver {
+            Some(driver) => bits[&driver].clone(),
+            None => Bit::Constant("x".to_string()),
+        };
+
+        let mut module = Module {
+            attributes: to_yosys_attributes(self.attributes()),
+            ..Default::default()
+        };
+        module
+            .attributes
+            .0
+            .push(("top".to_string(), to_yosys_value(&Parameter::Integer(1))));
+        let inputs = group_bits(
+            self.inputs()
+                .map(|i| (i.get_identifier(), bits[&i].clone())),
+        );
+        let outputs = self.outputs();
+        if let Some((_, port)) = outputs
+            .iter()
+            .find(|(_, port)| inputs.get(port.get_identifier().get_name()).is_some())
+        {
+            return Err(Error::NonuniqueNets(vec![port.clone()]));
+        }
+        let outputs = group_bits(
+            outputs
+                .into_iter()
+                .map(|(driver, port)| (port.take_identifier(), bits[&driver].clone())),
+        );
+        for (direction, ports) in [("input", &inputs), ("output", &outputs)] {
+            for (name, bits) in &ports.0 {
+                let port = Port {
+                    direction: direction.to_string(),
+                    bits: bits.clone(),
+                };
+                module.ports.0.push((name.clone(), port));
+            }
+        }
+
+        for inst in self.instances() {
+            let inst_type = inst.get_instance_type().unwrap();
+            if inst_type.get_constant().is_some() {
+                continue;
+            }
+            let inputs = inst_type
+                .get_input_ports()
+                .into_iter()
+                .enumerate()
+                .map(|(k, port)| {
+                    let driver = inst.get_input(k).get_driver();
+                    (port.get_identifier().clone(), driver_bit(driver))
+                });
+            let inputs = group_bits(inputs);
+            let outputs = group_bits(
+                inst.outputs()
+                    .map(|net| (net.get_port().take_identifier(), bits[&net].clone())),
+            );
+            let mut port_directions = Ordered::default();
+            let mut connections = Ordered::default();
+            for (direction, ports) in [("input", inputs), ("output", outputs)] {
+                for (name, bits) in ports.0 {
+                    port_directions
+                        .0
+                        .push((name.clone(), direction.to_string()));
+                    connections.0.push((name, bits));
+                }
+            }
+            let cell = Cell {
+                hide_name: 0,
+                cell_type: yosys_name(inst_type.get_name()),
+                parameters: Ordered(
+                    inst_type
+                        .parameters()
+                        .map(|(k, v)| (yosys_name(&k), to_yosys_value(&v)))
+                        .collect(),
+                ),
+                attributes: to_yosys_attributes(inst.attributes()),
+                port_directions,
+                connections,
+            };
+            let name = yosys_name(&inst.get_instance_name().unwrap());
+            module.cells.0.push((name, cell));
+        }
+
+        for (name, bits) in inputs.0.into_iter().chain(outputs.0) {
+            let netname = Netname {
+                hide_name: 0,
+                bits,
+                attributes: Ordered::default(),
+            };
+            module.netnames.0.push((name, netname));
+        }
+        for node in self.instances() {
+            for net in node.outputs() {
+                if let Bit::Net(_) = bits[&net] {
+                    let name = yosys_name(&net.get_identifier());
+                    module.netnames.entry(name, || Netname {
+                        hide_name: 0,
+                        bits: vec![bits[&net].clone()],
+                        attributes: to_yosys_attributes(self.net_attributes(&net.as_net())),
+                    });
+                }
+            }
+        }
+
+        let design = Design {
+            creator: format!("safety-net {}", env!("CARGO_PKG_VERSION")),
+            modules: Ordered(vec![(self.get_name().clone(), module)]),
+        };
+        serde_json::to_string_pretty(&design).map_err(|e| Error::ParseError(e.to_string()))
+    }
+}
+
+impl Netlist<Gate> {
+    /// Reads the top module of a Yosys JSON design as a netlist of [Gate]s with the cell types of the module, like the
+    /// output of `write_json`. The top module has the `top` attribute, or is the only module. The ports of cells come
+    /// from their `port_directions`, with a port `A[i]` for each bit of a bus `A`, and the attributes of the module and
+    /// its cells are kept. Undriven and `"x"` bits are left unconnected.
+    /// Returns [Error::ParseError] for malformed designs, and [Error::Unsupported] for cells with parameters, which
+    /// gates cannot hold, and for inout ports.
+    pub fn from_yosys_json(text: &str) -> Result<Rc<Self>, Error> {
+        let design: Design =
+            serde_json::from_str(text).map_err(|e| Error::ParseError(e.to_string()))?;
+        let is_top = |m: &Module| {
+            m.attributes
+                .get("top")
+                .is_some_and(|v| from_yosys_value(v) != Parameter::Integer(0))
+        };
+        let (name, module) = match design.modules.0.iter().find(|(_, m)| is_top(m)) {
+            Some(top) => top,
+            None => match &design.modules.0[..] {
+                [top] => top,
+                [] => return Err(Error::ParseError("No module in the design".to_string())),
+                _ => {
+                    return Err(Error::Unsupported(
+                        "Several modules without a top".to_string(),
+                    ));
+                }
+            },
+        };
+
+        let netlist = Netlist::new(name.clone());
+        for (k, v) in &module.attributes.0 {
+            if k != "top" {
+                netlist.insert_attribute(k.clone(), from_yosys_value(v));
+            }
+        }
+        let bit_name = |name: &str, width: usize, i: usize| {
+            if width > 1 {
+                Identifier::new(format!("{name}[{i}]"))
+            } else {
+                Identifier::new(name.to_string())
+            }
+        };
+        let mut drivers: HashMap<usize, DrivenNet<Gate>> = HashMap::new();
+        let mut define = |bit: &Bit, net: DrivenNet<Gate>| match bit {
+            Bit::Net(id) if drivers.insert(*id, net).is_some() => {
+                Err(Error::ParseError(format!("Bit {id} has several drivers")))
+            }
+            Bit::Net(_) => Ok(()),
+            Bit::Constant(c) => Err(Error::ParseError(format!("Constant `{c}` is driven"))),
+        };
+
+        for (port, Port { direction, bits }) in &module.ports.0 {
+            match direction.as_str() {
+                "input" => {
+                    for (i, bit) in bits.iter().enumerate() {
+                        let net =
+                            netlist.insert_input(Net::new_logic(bit_name(port, bits.len(), i)));
+                        define(bit, net)?;
+                    }
+                }
+                "output" => (),
+                _ => return Err(Error::Unsupported(format!("{direction} port {port}"))),
+            }
+        }
+
+        let mut loads = Vec::new();
+        for (inst_name, cell) in &module.cells.0 {
+            if !cell.parameters.0.is_empty() {
+                return Err(Error::Unsupported(format!(
+                    "Parameters of cell {inst_name} of type {}",
+                    cell.cell_type
+                )));
+            }
+            let cell_type = Identifier::new(cell.cell_type.clone());
+            if cell_type.is_sliced() {
+                return Err(Error::ParseError(format!(
+                    "Invalid cell type `{}`",
+                    cell.cell_type
+                )));
+            }
+            let (mut inputs, mut outputs) = (Vec::new(), Vec::new());
+            for (port, direction) in &cell.port_directions.0 {
+                let bits = cell.connections.get(port).ok_or_else(|| {
+                    Error::ParseError(format!("Port {port} of cell {inst_name} is not connected"))
+                })?;
+                let ports = match direction.as_str() {
+                    "input" => &mut inputs,
+                    "output" => &mut outputs,
+                    _ => {
+                        return Err(Error::Unsupported(format!(
+                            "{direction} port {port} of cell {inst_name}"
+                        )));
+                    }
+                };
+                for (i, bit) in bits.iter().enumerate() {
+                    ports.push((bit_name(port, bits.len(), i), bit));
+                }
+            }
+            let gate = Gate::new_logical_multi(
+                cell_type,
+                inputs.iter().map(|(port, _)| port.clone()).collect(),
+                outputs.iter().map(|(port, _)| port.clone()).collect(),
+            );
+            let inst = netlist.insert_gate_disconnected(gate, Identifier::new(inst_name.clone()));
+            for (k, (_, bit)) in outputs.into_iter().enumerate() {
+                define(bit, inst.get_output(k))?;
+            }
+            for (k, v) in &cell.attributes.0 {
+                inst.insert_attribute(k.clone(), from_yosys_value(v));
+            }
+            for (k, (_, bit)) in inputs.into_iter().enumerate() {
+                loads.push((inst.get_input(k), bit));
+            }
+        }
+
+        let driver = |bit: &Bit| -> Result<Option<DrivenNet<Gate>>, Error> {
+            match bit {
+                Bit::Net(id) => Ok(drivers.get(id).cloned()),
+                Bit::Constant(c) if c == "0" => netlist.constant_driver(Logic::False).map(Some),
+                Bit::Constant(c) if c == "1" => netlist.constant_driver(Logic::True).map(Some),
+                Bit::Constant(c) if c == "x" || c == "z" => Ok(None),
+                Bit::Constant(c) => Err(Error::ParseError(format!("Invalid bit `{c}`"))),
+            }
+        };
+        for (input, bit) in loads {
+            if let Some(driver) = driver(bit)? {
+                driver.connect(input);
+            }
+        }
+        for (port, Port { direction, bits }) in &module.ports.0 {
+            if direction != "output" {
+                continue;
+            }
+            for (i, bit) in bits.iter().enumerate() {
+                let name = bit_name(port, bits.len(), i);
+                match driver(bit)? {
+                    Some(driver) => {
+                        netlist.expose_net_with_name(driver, name);
+                    }
+                    None => netlist.insert_output(name)?,
+                }
+            }
+        }
+        Ok(netlist)
+    }
+}
+
+/// Returns the Yosys commands that run the shell command `command` as a step of a flow: the design is written to
+/// `file` with `write_json`, `command` is run with `file` as its argument, and the design is replaced by the module
+/// that `command` writes back to `file`. For example, `yosys_commands("snet yosys clean", "top.json")`.
+pub fn yosys_commands(command: &str, file: &str) -> String {
+    format!("write_json {file}; !{command} {file} {file}; design -reset; read_json {file}")
+}
+
+/// Runs `pass` as a step of a Yosys flow: reads the top module of the Yosys JSON file `input`, runs `pass` on it,
+/// and writes the result to the Yosys JSON file `output`, see [yosys_commands]
+pub fn run_yosys_step(
+    input: &Path,
+    output: &Path,
+    pass: impl FnOnce(&Rc<GateNetlist>) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let netlist = Netlist::from_yosys_json(&std::fs::read_to_string(input)?)?;
+    pass(&netlist)?;
+    std::fs::write(output, netlist.to_yosys_json()?)?;
+    Ok(())
+}
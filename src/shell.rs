@@ -0,0 +1,247 @@
+/*!
+
+  An interactive shell for inspecting and editing a netlist of [Gate]s, behind the `shell` feature.
+
+  The `safety-net-shell` binary reads commands from a script given as its argument, or from standard input.
+  Each line is a command and its arguments separated by whitespace, and `#` starts a comment.
+  Netlists are loaded and saved by file extension: `.json`, `.snet` and `.aag` (ASCII AIGER), and `.v` for saving only.
+  Type `help` for the list of commands.
+
+*/
+
+use crate::{
+    error::Error,
+    netlist::{
+        DrivenNet, Gate, GateNetlist, Netlist, VerilogOptions,
+        network::LogicNetwork,
+        select::Selection,
+        serde::{netlist_deserialize, netlist_serialize_copy},
+    },
+};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    rc::Rc,
+};
+
+/// The commands of the shell, with their arguments and what they do
+const COMMANDS: [(&str, &str, &str); 12] = [
+    ("help", "", "list the commands"),
+    ("new", "<name>", "start an empty netlist"),
+    ("load", "<file>", "load a .json, .snet or .aag netlist"),
+    ("save", "<file>", "save as .json, .snet, .aag or .v"),
+    ("stats", "[pattern]", "summarize the netlist or instances"),
+    ("query", "<pattern>", "list the instances matching a glob"),
+    ("nets", "<pattern>", "list the nets matching a glob"),
+    ("replace", "<net> <with>", "replace the uses of a net"),
+    ("clean", "", "remove the unused circuit nodes"),
+    ("verify", "", "check the netlist"),
+    ("write_verilog", "[file]", "print or write the Verilog"),
+    ("quit", "", "leave the shell"),
+];
+
+/// Whether the shell keeps reading commands after one was executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Read the next command
+    Continue,
+    /// Leave the shell
+    Quit,
+}
+
+/// The state of a shell session: the netlist being worked on
+pub struct Shell {
+    netlist: Rc<GateNetlist>,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the extension of `path`, for choosing its format
+fn extension(path: &str) -> &str {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+}
+
+/// Returns the single net of `netlist` named `name`
+fn find_net(netlist: &GateNetlist, name: &str) -> Result<DrivenNet<Gate>, Error> {
+    let mut nets = netlist.find_nets_matching(name);
+    match nets.len() {
+        1 => Ok(nets.pop().unwrap()),
+        0 => Err(Error::Unsupported(format!("No net named `{name}`"))),
+        n => Err(Error::Unsupported(format!("{n} nets match `{name}`"))),
+    }
+}
+
+/// Loads the netlist in the file at `path`, choosing the format by its extension
+pub fn load_netlist(path: &str) -> Result<Rc<GateNetlist>, Error> {
+    match extension(path) {
+        "json" => netlist_deserialize(BufReader::new(File::open(path)?))
+            .map_err(|e| Error::ParseError(e.to_string())),
+        "snet" => Netlist::from_snet(&std::fs::read(path)?),
+        "aag" => {
+            let name = Path::new(path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("top");
+            LogicNetwork::from_aag(&std::fs::read_to_string(path)?)?.to_netlist(name)
+        }
+        ext => Err(Error::Unsupported(format!("Loading `.{ext}` files"))),
+    }
+}
+
+/// Saves `netlist` to the file at `path`, choosing the format by its extension
+pub fn save_netlist(netlist: &GateNetlist, path: &str) -> Result<(), Error> {
+    let contents = match extension(path) {
+        "json" => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            netlist_serialize_copy(netlist, &mut writer)?;
+            writer.flush()?;
+            return Ok(());
+        }
+        "snet" => netlist.to_snet(None)?,
+        "aag" => netlist.to_logic_network()?.to_aag().into_bytes(),
+        "v" => netlist.to_verilog(&VerilogOptions::default()).into_bytes(),
+        ext => return Err(Error::Unsupported(format!("Saving `.{ext}` files"))),
+    };
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+impl Shell {
+    /// Starts a session with an empty netlist named `top`
+    pub fn new() -> Self {
+        Self {
+            netlist: Netlist::new("top".to_string()),
+        }
+    }
+
+    /// Returns the netlist being worked on
+    pub fn netlist(&self) -> &Rc<GateNetlist> {
+        &self.netlist
+    }
+
+    /// Replaces the netlist being worked on
+    pub fn set_netlist(&mut self, netlist: Rc<GateNetlist>) {
+        self.netlist = netlist;
+    }
+
+    /// Executes the command `line`, writing its output to `out`
+    pub fn execute(&mut self, line: &str, out: &mut impl Write) -> Result<Flow, Error> {
+        let line = line.split('#').next().unwrap_or_default();
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = words.split_first() else {
+            return Ok(Flow::Continue);
+        };
+        let Some((_, usage, _)) = COMMANDS.iter().find(|(c, _, _)| *c == command) else {
+            return Err(Error::ParseError(format!(
+                "Unknown command `{command}`, try `help`"
+            )));
+        };
+        let required = usage.matches('<').count();
+        let allowed = required + usage.matches('[').count();
+        if args.len() < required || args.len() > allowed {
+            return Err(Error::ArgumentMismatch(required, args.len()));
+        }
+
+        match command {
+            "help" => {
+                for (command, usage, description) in COMMANDS {
+                    let synopsis = format!("{command} {usage}");
+                    writeln!(out, "{synopsis:<24} {description}")?;
+                }
+            }
+            "new" => self.netlist = Netlist::new(args[0].to_string()),
+            "load" => {
+                self.netlist = load_netlist(args[0])?;
+                let stats = self.netlist.stats();
+                writeln!(
+                    out,
+                    "loaded `{}` with {} inputs and {} instances",
+                    self.netlist.get_name(),
+                    stats.inputs,
+                    stats.instances
+                )?;
+            }
+            "save" => save_netlist(&self.netlist, args[0])?,
+            "stats" => {
+                let stats = match args.first() {
+                    Some(pattern) => self.netlist.stats_of(&Selection::instance_name(
+                        *pattern,
+                        &self.netlist,
+                        pattern,
+                    )),
+                    None => self.netlist.stats(),
+                };
+                write!(out, "{stats}")?;
+            }
+            "query" => {
+                for inst in self.netlist.query(args[0]) {
+                    let name = inst.get_instance_name().unwrap();
+                    let cell = inst.get_instance_type().unwrap().get_gate_name().clone();
+                    writeln!(out, "{name} {cell}")?;
+                }
+            }
+            "nets" => {
+                for net in self.netlist.find_nets_matching(args[0]) {
+                    writeln!(out, "{}", net.get_identifier())?;
+                }
+            }
+            "replace" => {
+                let of = find_net(&self.netlist, args[0])?;
+                let with = find_net(&self.netlist, args[1])?;
+                self.netlist.replace_net_uses(of, &with)?;
+            }
+            "clean" => {
+                let before = self.netlist.objects().count();
+                self.netlist.clean()?;
+                let removed = before - self.netlist.objects().count();
+                writeln!(out, "removed {removed} circuit nodes")?;
+            }
+            "verify" => {
+                self.netlist.verify()?;
+                writeln!(out, "ok")?;
+            }
+            "write_verilog" => match args.first() {
+                Some(path) => {
+                    std::fs::write(path, self.netlist.to_verilog(&VerilogOptions::default()))?
+                }
+                None => write!(out, "{}", self.netlist)?,
+            },
+            "quit" => return Ok(Flow::Quit),
+            _ => unreachable!("Every command is handled"),
+        }
+        Ok(Flow::Continue)
+    }
+
+    /// Executes the commands read from `input` until it ends or a `quit`, writing their output and errors to `out`.
+    /// When given, the `prompt` is written before each command.
+    pub fn run(
+        &mut self,
+        input: impl BufRead,
+        mut out: impl Write,
+        prompt: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut lines = input.lines();
+        loop {
+            if let Some(prompt) = prompt {
+                write!(out, "{prompt}")?;
+                out.flush()?;
+            }
+            let Some(line) = lines.next() else {
+                return Ok(());
+            };
+            match self.execute(&line?, &mut out) {
+                Ok(Flow::Continue) => (),
+                Ok(Flow::Quit) => return Ok(()),
+                Err(e) => writeln!(out, "error: {e}")?,
+            }
+        }
+    }
+}
@@ -33,3 +33,121 @@ macro_rules! assert_verilog_eq {
         }
     };
 }
+
+/// Compare Verilog netlists structurally, regardless of the order of the wires, instances and port connections.
+/// Panics with the first mismatching net or instance, see [crate::netlist::testing::verilog_diff].
+#[macro_export]
+macro_rules! assert_netlist_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left_val, right_val) => {
+                let left_val: &str = ::std::convert::AsRef::as_ref(left_val);
+                let right_val: &str = ::std::convert::AsRef::as_ref(right_val);
+                match $crate::netlist::testing::verilog_diff(left_val, right_val) {
+                    Ok(None) => (),
+                    Ok(Some(diff)) => panic!("Netlists differ: {diff}"),
+                    Err(e) => panic!("Netlists do not parse: {e}"),
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left_val, right_val) => {
+                let left_val: &str = ::std::convert::AsRef::as_ref(left_val);
+                let right_val: &str = ::std::convert::AsRef::as_ref(right_val);
+                match $crate::netlist::testing::verilog_diff(left_val, right_val) {
+                    Ok(None) => (),
+                    Ok(Some(diff)) => panic!("Netlists differ: {diff}: {}", std::format_args!($($arg)+)),
+                    Err(e) => panic!("Netlists do not parse: {e}: {}", std::format_args!($($arg)+)),
+                }
+            }
+        }
+    };
+}
+
+/// A small, seedable pseudo-random number generator (SplitMix64).
+/// It is not cryptographically secure, but it is fast and reproducible across platforms.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a new generator from a seed
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Returns the next 64 random bits
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a random number in the range `[0, n)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            panic!("Cannot sample from an empty range");
+        }
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Returns `true` if `text` matches the glob `pattern`,
+/// where `*` matches any sequence of characters and `?` matches a single character.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // The position of the last `*` and the text position it was tried at
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bt)) => {
+                    backtrack = Some((bp, bt + 1));
+                    p = bp + 1;
+                    t = bt + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Enters a debug-level `tracing` span until the end of the enclosing scope, when the `tracing` feature is enabled.
+/// Passes open one on entry, so that subscribers can time them.
+macro_rules! debug_span {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = ::tracing::debug_span!($($arg)*).entered();
+    };
+}
+
+/// Emits a `tracing` event at `level` when the `tracing` feature is enabled.
+/// The fields are not evaluated otherwise, so they must not have side effects.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        ::tracing::$level!($($arg)*);
+    };
+}
+
+pub(crate) use {debug_span, trace_event};
@@ -0,0 +1,272 @@
+/*!
+
+  A [wasm-bindgen](https://docs.rs/wasm-bindgen) wrapper for using netlists of [Gate]s from JavaScript, behind the `wasm` feature.
+
+  Build it for the browser with `wasm-pack build --target web --features wasm`, or with
+  `cargo build --target wasm32-unknown-unknown --features wasm` and `wasm-bindgen`.
+  The core of the crate makes no filesystem or threading assumptions, so it builds for `wasm32-unknown-unknown` on its own.
+  Of the other features, `parallel` needs threads and `zstd` needs a C toolchain for the target.
+
+  Netlists are read from and written to strings and byte arrays, and the circuit nodes are referred to by their stable
+  identifiers (see [ObjectId]). The methods that can fail throw their error message as a string.
+
+*/
+
+use crate::{
+    circuit::Identifier,
+    netlist::{
+        DrivenNet, Gate, GateNetlist, NetRef, Netlist, VerifyOptions, VerilogOptions,
+        annotation::{NetId, ObjectId},
+        network::LogicNetwork,
+        rewrite::rewrite,
+        serde::{netlist_deserialize, netlist_serialize_copy},
+    },
+};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// A net, as the stable identifier of its driver and its output position on the driver
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasmNet {
+    /// The circuit node driving the net
+    pub object: usize,
+    /// The output position of the net on its driver
+    pub output: usize,
+}
+
+#[wasm_bindgen]
+impl WasmNet {
+    /// Creates a reference to output `output` of the circuit node `object`
+    #[wasm_bindgen(constructor)]
+    pub fn new(object: usize, output: usize) -> Self {
+        Self { object, output }
+    }
+}
+
+impl From<NetId> for WasmNet {
+    fn from(id: NetId) -> Self {
+        Self::new(id.object.0, id.output)
+    }
+}
+
+/// A netlist of [Gate]s
+#[wasm_bindgen]
+pub struct WasmNetlist {
+    netlist: Rc<GateNetlist>,
+}
+
+impl WasmNetlist {
+    /// Returns the netlist being wrapped
+    pub fn netlist(&self) -> &Rc<GateNetlist> {
+        &self.netlist
+    }
+
+    /// Returns the circuit node with identifier `object`
+    fn object(&self, object: usize) -> Result<NetRef<Gate>, String> {
+        self.netlist
+            .find_object(ObjectId(object))
+            .ok_or_else(|| format!("No circuit node #{object}"))
+    }
+
+    /// Returns the net `net`
+    fn net(&self, net: &WasmNet) -> Result<DrivenNet<Gate>, String> {
+        self.netlist
+            .find_net_by_id(NetId {
+                object: ObjectId(net.object),
+                output: net.output,
+            })
+            .ok_or_else(|| format!("No output {} of circuit node #{}", net.output, net.object))
+    }
+}
+
+impl From<Rc<GateNetlist>> for WasmNetlist {
+    fn from(netlist: Rc<GateNetlist>) -> Self {
+        Self { netlist }
+    }
+}
+
+#[wasm_bindgen]
+impl WasmNetlist {
+    /// Creates an empty netlist named `name`
+    #[wasm_bindgen(constructor)]
+    pub fn new(name: &str) -> Self {
+        Netlist::new(name.to_string()).into()
+    }
+
+    /// Parses a netlist serialized as JSON, like the output of [WasmNetlist::to_json]
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(text: &str) -> Result<WasmNetlist, String> {
+        netlist_deserialize(text.as_bytes())
+            .map(Self::from)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Loads a netlist from a `.snet` snapshot, see [Netlist::from_snet]
+    #[wasm_bindgen(js_name = fromSnet)]
+    pub fn from_snet(bytes: &[u8]) -> Result<WasmNetlist, String> {
+        Netlist::from_snet(bytes)
+            .map(Self::from)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Parses an ASCII AIGER file into a netlist of `AND` and `INV` gates named `name`, see [LogicNetwork::from_aag]
+    #[wasm_bindgen(js_name = fromAiger)]
+    pub fn from_aiger(text: &str, name: &str) -> Result<WasmNetlist, String> {
+        LogicNetwork::from_aag(text)
+            .and_then(|network| network.to_netlist(name))
+            .map(Self::from)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Returns the name of the netlist
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.netlist.get_name().clone()
+    }
+
+    /// Inserts an input named `name` and returns its net
+    #[wasm_bindgen(js_name = insertInput)]
+    pub fn insert_input(&self, name: &str) -> WasmNet {
+        self.netlist.insert_input(name.into()).get_id().into()
+    }
+
+    /// Inserts an instance `inst_name` of the gate `cell`, with input ports `ports` and output `Y`.
+    /// The inputs are left unconnected, and the identifier of the circuit node is returned.
+    /// Fails if `cell` is a bit slice like `X[1]`.
+    #[wasm_bindgen(js_name = insertGate)]
+    pub fn insert_gate(
+        &self,
+        cell: &str,
+        inst_name: &str,
+        ports: Vec<String>,
+    ) -> Result<usize, String> {
+        let cell = Identifier::from(cell);
+        if cell.is_sliced() {
+            return Err(format!("The cell name {cell} cannot be a bit slice"));
+        }
+        let ports = ports.iter().map(|p| Identifier::from(p.as_str())).collect();
+        let gate = Gate::new_logical(cell, ports, "Y".into());
+        Ok(self
+            .netlist
+            .insert_gate_disconnected(gate, inst_name.into())
+            .get_id()
+            .0)
+    }
+
+    /// Connects input `input` of the circuit node `object` to `driver`, replacing its previous driver
+    pub fn connect(&self, driver: &WasmNet, object: usize, input: usize) -> Result<(), String> {
+        let driver = self.net(driver)?;
+        let node = self.object(object)?;
+        if input >= node.get_num_input_ports() {
+            return Err(format!("Circuit node #{object} has no input {input}"));
+        }
+        node.get_input(input).reconnect(driver);
+        Ok(())
+    }
+
+    /// Drives the top-level output `name` with `net`
+    pub fn expose(&self, net: &WasmNet, name: &str) -> Result<(), String> {
+        let net = self.net(net)?;
+        self.netlist.expose_net_with_name(net, name.into());
+        Ok(())
+    }
+
+    /// Returns the identifiers of the circuit nodes, in netlist order
+    pub fn objects(&self) -> Vec<usize> {
+        self.netlist.objects().map(|o| o.get_id().0).collect()
+    }
+
+    /// Returns the instance name of the circuit node `object`, or the name of its net for an input
+    #[wasm_bindgen(js_name = objectName)]
+    pub fn object_name(&self, object: usize) -> Result<String, String> {
+        let node = self.object(object)?;
+        Ok(match node.get_instance_name() {
+            Some(name) => name.to_string(),
+            None => node.get_identifier().to_string(),
+        })
+    }
+
+    /// Returns the name of the gate of the circuit node `object`, which is undefined for an input
+    #[wasm_bindgen(js_name = cellName)]
+    pub fn cell_name(&self, object: usize) -> Result<Option<String>, String> {
+        let node = self.object(object)?;
+        Ok(node
+            .get_instance_type()
+            .map(|g| g.get_gate_name().to_string()))
+    }
+
+    /// Returns the name of `net`
+    #[wasm_bindgen(js_name = netName)]
+    pub fn net_name(&self, net: &WasmNet) -> Result<String, String> {
+        Ok(self.net(net)?.get_identifier().to_string())
+    }
+
+    /// Returns the number of input ports of the circuit node `object`
+    #[wasm_bindgen(js_name = numInputs)]
+    pub fn num_inputs(&self, object: usize) -> Result<usize, String> {
+        Ok(self.object(object)?.get_num_input_ports())
+    }
+
+    /// Returns the net driving input `input` of the circuit node `object`, which is undefined when unconnected
+    pub fn driver(&self, object: usize, input: usize) -> Result<Option<WasmNet>, String> {
+        let node = self.object(object)?;
+        if input >= node.get_num_input_ports() {
+            return Err(format!("Circuit node #{object} has no input {input}"));
+        }
+        Ok(node
+            .get_input(input)
+            .get_driver()
+            .map(|d| d.get_id().into()))
+    }
+
+    /// Removes the circuit nodes that do not reach a top-level output, returning whether any were removed
+    pub fn clean(&self) -> Result<bool, String> {
+        self.netlist.clean().map_err(|e| e.to_string())
+    }
+
+    /// Rewrites cuts of the netlist to smaller equivalents, returning the number of rewrites, see [rewrite]
+    pub fn rewrite(&self) -> Result<usize, String> {
+        rewrite(&self.netlist).map_err(|e| e.to_string())
+    }
+
+    /// Checks that the netlist has outputs, unique names and no unconnected inputs, see [Netlist::verify_with]
+    pub fn verify(&self) -> Result<(), String> {
+        let options = VerifyOptions {
+            deny_unconnected_inputs: true,
+            ..VerifyOptions::default()
+        };
+        self.netlist
+            .verify_with(&options)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Returns the netlist as Verilog with the default [VerilogOptions]
+    #[wasm_bindgen(js_name = toVerilog)]
+    pub fn to_verilog(&self) -> String {
+        self.netlist.to_verilog(&VerilogOptions::default())
+    }
+
+    /// Returns the netlist serialized as JSON
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, String> {
+        let mut json = Vec::new();
+        netlist_serialize_copy(&self.netlist, &mut json).map_err(|e| e.to_string())?;
+        String::from_utf8(json).map_err(|e| e.to_string())
+    }
+
+    /// Returns the netlist as an uncompressed `.snet` snapshot, see [Netlist::to_snet]
+    #[wasm_bindgen(js_name = toSnet)]
+    pub fn to_snet(&self) -> Result<Vec<u8>, String> {
+        self.netlist.to_snet(None).map_err(|e| e.to_string())
+    }
+
+    /// Returns the netlist as an ASCII AIGER file, see [LogicNetwork::to_aag]
+    #[wasm_bindgen(js_name = toAiger)]
+    pub fn to_aiger(&self) -> Result<String, String> {
+        self.netlist
+            .to_logic_network()
+            .map(|network| network.to_aag())
+            .map_err(|e| e.to_string())
+    }
+}
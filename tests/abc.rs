@@ -0,0 +1,88 @@
+#![cfg(all(feature = "abc", unix))]
+
+use safety_net::{
+    error::Error,
+    netlist::{
+        GateNetlist, Netlist,
+        abc::{AbcOptions, abc, abc_with},
+        testing::{assert_equivalent, assert_invariants},
+    },
+};
+use std::{os::unix::fs::PermissionsExt, path::PathBuf, process::Command, rc::Rc};
+
+fn full_adder() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("adder".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let cin = netlist.insert_input("cin".into());
+    let p = netlist.xor(&a, &b).unwrap();
+    netlist
+        .xor(&p, &cin)
+        .unwrap()
+        .expose_with_name("sum".into());
+    netlist
+        .mux(&p, &a, &cin)
+        .unwrap()
+        .expose_with_name("cout".into());
+    netlist
+}
+
+/// Writes a stand-in for ABC that copies the input AIG to the output unchanged
+fn fake_abc(dir: &std::path::Path) -> PathBuf {
+    let path = dir.join("abc");
+    std::fs::write(
+        &path,
+        "#!/bin/sh\n\
+         in=$(echo \"$2\" | sed 's/^read_aiger \\([^;]*\\);.*/\\1/')\n\
+         out=$(echo \"$2\" | sed 's/.*write_aiger -s //')\n\
+         cp \"$in\" \"$out\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[test]
+fn round_trip_keeps_the_interface() {
+    let dir = std::env::temp_dir().join(format!("safety-net-abc-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let options = AbcOptions {
+        binary: Some(fake_abc(&dir)),
+        dir: Some(dir.clone()),
+    };
+    let netlist = full_adder();
+    let result = abc_with(&netlist, "dc2;", &options).unwrap();
+    assert_invariants(&result);
+    assert_eq!(result.get_name().as_str(), "adder");
+    let inputs: Vec<String> = result.inputs().map(|i| i.to_string()).collect();
+    assert_eq!(inputs, ["a", "b", "cin"]);
+    let outputs: Vec<String> = result
+        .outputs()
+        .iter()
+        .map(|(_, port)| port.get_identifier().to_string())
+        .collect();
+    assert_eq!(outputs, ["sum", "cout"]);
+    assert_equivalent(&netlist, &result, 64);
+    assert!(dir.join("input.aig").exists() && dir.join("output.aig").exists());
+
+    let options = AbcOptions {
+        binary: Some(dir.join("missing")),
+        dir: None,
+    };
+    assert!(matches!(
+        abc_with(&netlist, "dc2", &options),
+        Err(Error::IoError(_))
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn abc_optimizes() {
+    if Command::new("abc").args(["-q", "quit"]).output().is_err() {
+        return;
+    }
+    let netlist = full_adder();
+    let result = abc(&netlist, "balance; rewrite; refactor").unwrap();
+    assert_invariants(&result);
+    assert_equivalent(&netlist, &result, 64);
+}
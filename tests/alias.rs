@@ -0,0 +1,166 @@
+use safety_net::{
+    assert_verilog_eq,
+    error::Error,
+    logic::Logic,
+    netlist::{
+        Gate, GateNetlist, Netlist, RemovePolicy, VerilogOptions, testing::assert_invariants,
+    },
+};
+use std::rc::Rc;
+
+fn and_gate() -> Gate {
+    Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+fn inv_gate() -> Gate {
+    Gate::new_logical("INV".into(), vec!["A".into()], "Y".into())
+}
+
+/// `y = a & b`, where the AND gate drives the alias `t`
+fn get_alias_example() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("alias".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let and = netlist
+        .insert_gate(and_gate(), "inst_0".into(), &[a, b])
+        .unwrap();
+    netlist
+        .insert_alias(&and.get_output(0), "t".into())
+        .unwrap();
+    and.expose_with_name("y".into());
+    netlist
+}
+
+#[test]
+fn aliases_are_emitted() {
+    let netlist = get_alias_example();
+    assert_invariants(&netlist);
+    assert_eq!(netlist.aliases().len(), 1);
+    assert_verilog_eq!(
+        netlist.to_string(),
+        "module alias (
+           a,
+           b,
+           y
+         );
+           input a;
+           wire a;
+           input b;
+           wire b;
+           output y;
+           wire y;
+           wire inst_0_Y;
+           wire t;
+           AND inst_0 (
+             .A(a),
+             .B(b),
+             .Y(inst_0_Y)
+           );
+           assign t = inst_0_Y;
+           assign y = inst_0_Y;
+         endmodule\n"
+    );
+
+    // Collapsed aliases leave only the port assignment
+    let options = VerilogOptions {
+        aliases: false,
+        ..VerilogOptions::default()
+    };
+    let collapsed = netlist.to_verilog(&options);
+    assert!(!collapsed.contains(" t;") && !collapsed.contains("assign t"));
+    assert_eq!(netlist.collapse_aliases(), 1);
+    assert_eq!(netlist.to_string(), collapsed);
+}
+
+#[test]
+fn aliases_are_not_ports() {
+    let netlist = get_alias_example();
+    assert_eq!(netlist.get_output_ports().len(), 1);
+    let and = netlist.resolve_alias(&"t".into()).unwrap();
+    assert_eq!(and.get_identifier(), "inst_0_Y".into());
+
+    // Names are unique among nets, ports and aliases
+    for name in ["t", "y", "a", "inst_0_Y"] {
+        assert!(matches!(
+            netlist.insert_alias(&and, name.into()),
+            Err(Error::NonuniqueNets(_))
+        ));
+    }
+    assert!(matches!(
+        netlist.remove_alias(&"u".into()),
+        Err(Error::NetNotFound(_))
+    ));
+    assert_eq!(netlist.remove_alias(&"t".into()).unwrap(), and);
+    assert!(netlist.aliases().is_empty());
+}
+
+#[test]
+fn aliases_follow_edits() {
+    let netlist = get_alias_example();
+    let and = netlist.resolve_alias(&"t".into()).unwrap();
+    let inv = netlist
+        .insert_gate(inv_gate(), "inst_1".into(), std::slice::from_ref(&and))
+        .unwrap();
+    netlist
+        .insert_alias(&inv.get_output(0), "u".into())
+        .unwrap();
+    let unused = netlist
+        .insert_gate(inv_gate(), "inst_2".into(), std::slice::from_ref(&and))
+        .unwrap();
+    netlist
+        .insert_alias(&unused.get_output(0), "v".into())
+        .unwrap();
+    inv.expose_with_name("z".into());
+
+    // The alias moves with the uses of the AND gate, and an alias does not keep its driver from being cleaned
+    let zero = netlist
+        .insert_constant(Logic::False, "zero".into())
+        .unwrap();
+    netlist.replace_net_uses(and, &zero).unwrap();
+    netlist.clean().unwrap();
+    assert_invariants(&netlist);
+    let aliases = |netlist: &GateNetlist| -> Vec<(String, String)> {
+        netlist
+            .aliases()
+            .into_iter()
+            .map(|(net, alias)| (net.get_identifier().to_string(), alias.to_string()))
+            .collect()
+    };
+    assert_eq!(
+        aliases(&netlist),
+        [
+            ("zero_Y".to_string(), "t".to_string()),
+            ("inst_1_Y".to_string(), "u".to_string())
+        ]
+    );
+    assert!(netlist.to_string().contains("assign t = 1'b0;"));
+
+    // The alias of a removed instance names its replacement
+    let inv = netlist.resolve_alias(&"u".into()).unwrap().unwrap();
+    netlist
+        .remove_instance(inv, RemovePolicy::StitchThrough)
+        .unwrap();
+    assert_eq!(
+        aliases(&netlist),
+        [
+            ("zero_Y".to_string(), "t".to_string()),
+            ("zero_Y".to_string(), "u".to_string())
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn aliases_are_serialized() {
+    use safety_net::netlist::serde::{netlist_deserialize, netlist_serialize};
+
+    let netlist = get_alias_example();
+    let expected = netlist.to_string();
+    let copy = GateNetlist::from_snet(&netlist.to_snet(None).unwrap()).unwrap();
+    assert_eq!(copy.to_string(), expected);
+
+    let mut json = Vec::new();
+    netlist_serialize(Rc::try_unwrap(netlist).ok().unwrap(), &mut json).unwrap();
+    let copy: Rc<GateNetlist> = netlist_deserialize(json.as_slice()).unwrap();
+    assert_eq!(copy.to_string(), expected);
+}
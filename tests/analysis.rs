@@ -1,4 +1,4 @@
-use safety_net::attribute::dont_touch_filter;
+use safety_net::attribute::{attribute_filter, dont_touch_filter};
 use safety_net::circuit::Net;
 use safety_net::format_id;
 use safety_net::graph::FanOutTable;
@@ -7,6 +7,7 @@ use safety_net::netlist::Gate;
 use safety_net::netlist::GateNetlist;
 use safety_net::netlist::Netlist;
 use safety_net::netlist::iter::DFSIterator;
+use safety_net::netlist::levels::Levels;
 use std::rc::Rc;
 
 fn and_gate() -> Gate {
@@ -126,6 +127,41 @@ fn test_attr_filter() {
     assert_eq!(filter.keys().len(), 1)
 }
 
+#[test]
+fn test_attribute_queries() {
+    let netlist = get_simple_example();
+    let inst_0 = netlist.last().unwrap();
+    let a = netlist.first().unwrap();
+    assert!(
+        netlist
+            .objects_with_attribute(&"keep".into())
+            .next()
+            .is_none()
+    );
+
+    inst_0.insert_attribute("keep".into(), true);
+    assert!(inst_0.has_attribute(&"keep".into()));
+    assert_eq!(
+        inst_0.get_attribute(&"keep".into()),
+        Some(Some(true.into()))
+    );
+    assert_eq!(inst_0.attributes().count(), 1);
+
+    // Attributes on nets select their drivers
+    netlist.set_net_attribute(&a.as_net(), "dont_touch".into());
+    let selected: Vec<_> = netlist
+        .objects_with_attribute(&"dont_touch".into())
+        .collect();
+    assert_eq!(selected, vec![a.clone()]);
+
+    let filter = attribute_filter(&*netlist, ["keep".into(), "dont_touch".into()]);
+    assert_eq!(filter.len(), 2);
+    assert!(filter.has_key(&inst_0, &"keep".into()));
+    assert!(!filter.has_key(&inst_0, &"dont_touch".into()));
+    assert!(filter.iter().any(|n| *n == a));
+    assert!(dont_touch_filter(&*netlist).has(&a));
+}
+
 #[cfg(feature = "graph")]
 #[test]
 fn test_petgraph() {
@@ -155,6 +191,28 @@ fn test_comb_depth() {
     assert_eq!(depth_info.get_max_depth(), 1);
 }
 
+#[test]
+fn test_levels() {
+    let netlist = get_simple_example();
+    let levels = netlist.get_analysis::<Levels<_>>().unwrap();
+    let a = netlist.first().unwrap();
+    let and = netlist.last().unwrap();
+    assert_eq!(levels.get_level(&a), 0);
+    assert_eq!(levels.get_level(&and), 1);
+    assert_eq!(levels.get_max_level(), 1);
+    assert_eq!(levels.get_levels()[0].len(), 2);
+    assert_eq!(levels.get_levels()[1], vec![and]);
+
+    // Gates are combinational, so the loop through the register is a cycle
+    let netlist = divider_netlist();
+    assert!(netlist.get_analysis::<Levels<_>>().is_err());
+
+    let empty = GateNetlist::new("empty".to_string());
+    let levels = empty.get_analysis::<Levels<_>>().unwrap();
+    assert!(levels.get_levels().is_empty());
+    assert_eq!(levels.get_max_level(), 0);
+}
+
 #[test]
 fn test_fanout_table() {
     let netlist = get_simple_example();
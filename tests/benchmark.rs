@@ -0,0 +1,92 @@
+#![cfg(feature = "benchmarks")]
+
+use safety_net::{
+    error::Error,
+    netlist::benchmark::{Benchmark, BenchmarkCache, BenchmarkFormat, sha256},
+};
+use std::{cell::Cell, rc::Rc};
+
+/// `y = a & b` in ASCII AIGER
+const AND: &str = "aag 3 2 0 1 1\n2\n4\n6\n6 2 4\ni0 a\ni1 b\no0 y\n";
+
+fn and_benchmark() -> Benchmark {
+    Benchmark {
+        name: "and".to_string(),
+        url: "https://example.com/and.aag".to_string(),
+        sha256: sha256(AND.as_bytes()),
+        format: BenchmarkFormat::Aag,
+    }
+}
+
+/// A cache in a fresh directory that counts its downloads
+fn counting_cache(test: &str) -> (BenchmarkCache, Rc<Cell<usize>>) {
+    let dir = std::env::temp_dir().join(format!("safety-net-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let count = Rc::new(Cell::new(0));
+    let fetched = count.clone();
+    let cache = BenchmarkCache::new(dir).with_fetch(move |url| {
+        assert_eq!(url, "https://example.com/and.aag");
+        fetched.set(fetched.get() + 1);
+        Ok(AND.as_bytes().to_vec())
+    });
+    (cache, count)
+}
+
+#[test]
+fn sha256_digests() {
+    assert_eq!(
+        sha256(b""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        sha256(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(
+        sha256(&[b'a'; 1000]),
+        "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+    );
+}
+
+#[test]
+fn benchmarks_are_cached() {
+    let (cache, count) = counting_cache("cached");
+    let benchmark = and_benchmark();
+    let netlist = cache.load(&benchmark).unwrap();
+    assert_eq!(netlist.get_name().as_str(), "and");
+    assert_eq!(netlist.instances().count(), 1);
+    assert_eq!(count.get(), 1);
+    assert!(cache.path(&benchmark).ends_with("and.aag"));
+
+    cache.load(&benchmark).unwrap();
+    assert_eq!(count.get(), 1);
+
+    // A corrupted file is downloaded again
+    std::fs::write(cache.path(&benchmark), "aag 0 0 0 0 0\n").unwrap();
+    cache.load(&benchmark).unwrap();
+    assert_eq!(count.get(), 2);
+    std::fs::remove_dir_all(cache.dir()).unwrap();
+}
+
+#[test]
+fn checksums_are_verified() {
+    let (cache, _) = counting_cache("checksum");
+    let benchmark = Benchmark {
+        sha256: sha256(b"something else"),
+        ..and_benchmark()
+    };
+    assert!(matches!(
+        cache.load(&benchmark),
+        Err(Error::ChecksumMismatch { .. })
+    ));
+    assert!(!cache.path(&benchmark).exists());
+
+    // Formats without a parser are only cached
+    let benchmark = Benchmark {
+        format: BenchmarkFormat::Bench,
+        ..and_benchmark()
+    };
+    assert!(matches!(cache.load(&benchmark), Err(Error::Unsupported(_))));
+    assert!(cache.path(&benchmark).exists());
+    std::fs::remove_dir_all(cache.dir()).unwrap();
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "derive")]
+use safety_net::{
+    attribute::Parameter,
+    circuit::{Evaluate, Identifier, Instantiable, Net},
+    derive::Instantiable,
+    graph::TopoOrder,
+    logic::Logic,
+    netlist::{
+        Gate, Netlist, VerilogOptions,
+        blackbox::BlackBox,
+        sim::{LogicSimulator, Simulator},
+    },
+};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Instantiable)]
+enum Cell {
+    #[instantiable(constant)]
+    Gate(Gate),
+    BlackBox(BlackBox),
+}
+
+impl Evaluate for Cell {
+    fn eval(&self, inputs: &[Logic]) -> Vec<Logic> {
+        match self {
+            Cell::Gate(g) => g.eval(inputs),
+            Cell::BlackBox(b) => b.eval(inputs),
+        }
+    }
+}
+
+fn and_gate() -> Cell {
+    Cell::Gate(Gate::new_logical(
+        "AND".into(),
+        vec!["A".into(), "B".into()],
+        "Y".into(),
+    ))
+}
+
+fn ip_block() -> Cell {
+    Cell::BlackBox(BlackBox::new(
+        "IP".into(),
+        vec!["D[0]".into(), "D[1]".into(), "EN".into()],
+        vec!["Q".into()],
+    ))
+}
+
+/// The black box output feeds back into its own input through an AND gate
+fn get_loop_example() -> Rc<Netlist<Cell>> {
+    let netlist = Netlist::new("wrapper".to_string());
+    let a = netlist.insert_input("a".into());
+    let en = netlist.insert_input("en".into());
+    let ip = netlist.insert_gate_disconnected(ip_block(), "u_ip".into());
+    let and = netlist
+        .insert_gate(and_gate(), "inst_0".into(), &[a.clone(), ip.get_output(0)])
+        .unwrap();
+    ip.find_input(&"D[0]".into()).unwrap().connect(a);
+    ip.find_input(&"D[1]".into())
+        .unwrap()
+        .connect(and.get_output(0));
+    ip.find_input(&"EN".into()).unwrap().connect(en);
+    and.expose_with_name("y".into());
+    netlist
+}
+
+#[test]
+fn blackbox_outputs_are_free() {
+    let netlist = get_loop_example();
+    assert!(netlist.verify().is_ok());
+    // Black boxes break combinational loops like registers
+    assert!(netlist.get_analysis::<TopoOrder<_>>().is_ok());
+
+    let q = Net::new_logic("u_ip_Q".into());
+    let sim = LogicSimulator::new(&netlist).unwrap();
+    let sources: Vec<Net> = sim.sources().map(|n| n.as_net().clone()).collect();
+    assert!(sources.contains(&q));
+    assert_eq!(sources.len(), 3);
+
+    let mut sim = Simulator::new(&netlist).unwrap();
+    sim.run(|n| if *n.as_net() == q { 0b1100 } else { 0b1010 });
+    let y = netlist.find_net(&"inst_0_Y".into()).unwrap();
+    assert_eq!(sim.get_word(&y), 0b1000);
+}
+
+#[test]
+fn blackbox_stubs() {
+    let netlist = get_loop_example();
+    assert!(!netlist.to_string().contains("(* blackbox *)"));
+
+    let verilog = netlist.to_verilog(&VerilogOptions {
+        blackbox_stubs: true,
+        ..Default::default()
+    });
+    let stub = verilog.split("endmodule\n").nth(1).unwrap();
+    assert_eq!(
+        stub,
+        "\n(* blackbox *)\nmodule IP (\n  D,\n  EN,\n  Q\n);\n  input [1:0] D;\n  input EN;\n  output Q;\n"
+    );
+    assert_eq!(verilog.matches("(* blackbox *)").count(), 1);
+
+    let verilog = netlist.to_verilog(&VerilogOptions {
+        blackbox_stubs: true,
+        ansi_ports: true,
+        ..Default::default()
+    });
+    assert!(verilog.contains(
+        "module IP (\n  input wire [1:0] D,\n  input wire EN,\n  output wire Q\n);\nendmodule\n"
+    ));
+}
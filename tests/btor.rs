@@ -0,0 +1,111 @@
+#![cfg(feature = "derive")]
+use safety_net::{
+    circuit::Identifier,
+    error::Error,
+    logic::Logic,
+    netlist::{Gate, Netlist, btor::Btor2Options},
+};
+use std::rc::Rc;
+
+mod common;
+use common::{Cell, Dff};
+
+/// A register that toggles while `en` is high, with its value as the output `q`
+fn toggle() -> Rc<Netlist<Cell>> {
+    let netlist = Netlist::new("toggle".to_string());
+    let en = netlist.insert_input("en".into());
+    let clk = netlist.insert_input("clk".into());
+    let dff = Dff {
+        init: Logic::False,
+        d: "D".into(),
+        c: "C".into(),
+        q: "Q".into(),
+    };
+    let ff = netlist.insert_gate_disconnected(Cell::Dff(dff), "ff".into());
+    let xor = Gate::new_logical("XOR".into(), vec!["A".into(), "B".into()], "Y".into());
+    let next = netlist
+        .insert_gate(Cell::Gate(xor), "u0".into(), &[ff.get_output(0), en])
+        .unwrap();
+    ff.get_input(0).connect(next.get_output(0));
+    ff.get_input(1).connect(clk);
+    ff.get_output(0).expose_with_name("q".into());
+    netlist
+}
+
+fn btor2(netlist: &Netlist<Cell>, bad: &[&str]) -> Result<String, Error> {
+    let options = Btor2Options {
+        bad: bad.iter().map(|b| Identifier::from(*b)).collect(),
+    };
+    let mut out = Vec::new();
+    netlist.to_btor2(&mut out, &options)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn registers_are_states() {
+    let netlist = toggle();
+    let expected = "\
+1 sort bitvec 1
+2 input 1 en
+3 input 1 clk
+4 state 1 ff
+5 xor 1 2 4
+6 zero 1
+7 init 1 4 6
+8 next 1 4 5
+9 output 4 q
+";
+    assert_eq!(btor2(&netlist, &[]).unwrap(), expected);
+
+    // The register never reaching 1 is a safety property
+    let model = btor2(&netlist, &["q"]).unwrap();
+    assert!(model.ends_with("9 bad 4 q\n"));
+    assert!(matches!(
+        btor2(&netlist, &["y"]),
+        Err(Error::PortNotFound(_))
+    ));
+}
+
+#[test]
+fn combinational_cells() {
+    let netlist = Netlist::new("mux".to_string());
+    let inputs: Vec<_> = ["s", "a", "b"]
+        .iter()
+        .map(|n| netlist.insert_input((*n).into()))
+        .collect();
+    let mux = Gate::new_logical(
+        "MUX".into(),
+        vec!["S".into(), "A".into(), "B".into()],
+        "Y".into(),
+    );
+    let nand = Gate::new_logical("NAND".into(), vec!["A".into(), "B".into()], "Y".into());
+    let y = netlist
+        .insert_gate(Cell::Gate(mux), "u0".into(), &inputs)
+        .unwrap();
+    netlist
+        .insert_gate(
+            Cell::Gate(nand),
+            "u1".into(),
+            &[y.get_output(0), inputs[0].clone()],
+        )
+        .unwrap()
+        .expose_with_name("y".into());
+    let model = btor2(&netlist, &[]).unwrap();
+    assert!(model.contains(" ite 1 "));
+    assert!(model.lines().last().unwrap().ends_with("output 8 y"));
+    assert!(model.lines().all(|l| !l.contains("state")));
+
+    let unconnected = netlist.insert_gate_disconnected(
+        Cell::Gate(Gate::new_logical(
+            "INV".into(),
+            vec!["A".into()],
+            "Y".into(),
+        )),
+        "u2".into(),
+    );
+    unconnected.expose_with_name("z".into());
+    assert!(matches!(
+        btor2(&netlist, &[]),
+        Err(Error::UnconnectedInputs(_))
+    ));
+}
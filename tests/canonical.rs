@@ -0,0 +1,162 @@
+use safety_net::{
+    assert_verilog_eq,
+    attribute::Parameter,
+    netlist::{
+        Gate, GateNetlist, Netlist, canonical::CanonicalOptions, testing::assert_invariants,
+    },
+};
+use std::rc::Rc;
+
+fn gate(name: &str, inputs: &[&str]) -> Gate {
+    Gate::new_logical(
+        name.into(),
+        inputs.iter().map(|i| (*i).into()).collect(),
+        "Y".into(),
+    )
+}
+
+/// `y = (~a & b) | a` and `z = ~b`, built in one order or the other, with generated names that depend on the order
+fn get_example(reversed: bool) -> Rc<GateNetlist> {
+    let netlist = Netlist::new("canonical".to_string());
+    let (a, b) = if reversed {
+        let b = netlist.insert_input("b".into());
+        (netlist.insert_input("a".into()), b)
+    } else {
+        (
+            netlist.insert_input("a".into()),
+            netlist.insert_input("b".into()),
+        )
+    };
+    let names = if reversed { [9, 1, 4] } else { [3, 7, 5] };
+    let insert = |cell: Gate, n: usize, inputs: &[_]| {
+        netlist
+            .insert_gate(cell, format!("rewrite_{n}").into(), inputs)
+            .unwrap()
+    };
+    let z = || insert(gate("INV", &["A"]), names[2], std::slice::from_ref(&b));
+    if reversed {
+        z().expose_with_name("z".into());
+    }
+    let inv = insert(gate("INV", &["A"]), names[0], std::slice::from_ref(&a));
+    let and = insert(
+        gate("AND", &["A", "B"]),
+        names[1],
+        &[inv.get_output(0), b.clone()],
+    );
+    and.insert_attribute("keep".to_string(), Parameter::Integer(1));
+    netlist
+        .insert_gate(
+            gate("OR", &["A", "B"]),
+            "out".into(),
+            &[and.get_output(0), a.clone()],
+        )
+        .unwrap()
+        .expose_with_name("y".into());
+    if !reversed {
+        z().expose_with_name("z".into());
+    }
+    netlist
+}
+
+#[test]
+fn canonical_forms_match() {
+    let options = CanonicalOptions {
+        anonymous: vec!["rewrite_*".to_string()],
+    };
+    let [first, second] = [false, true].map(|reversed| {
+        let netlist = get_example(reversed);
+        assert_eq!(netlist.canonicalize(&options).unwrap(), 6);
+        assert_invariants(&netlist);
+        netlist
+    });
+    assert_ne!(
+        get_example(false).to_string(),
+        get_example(true).to_string()
+    );
+    assert_eq!(first.to_string(), second.to_string());
+    // The anonymous instances are numbered from the outputs by name, and drivers come before their loads
+    assert_verilog_eq!(
+        first.to_string(),
+        "module canonical (
+           a,
+           b,
+           y,
+           z
+         );
+           input a;
+           wire a;
+           input b;
+           wire b;
+           output y;
+           wire y;
+           output z;
+           wire z;
+           wire _0__Y;
+           wire _1__Y;
+           wire out_Y;
+           wire _2__Y;
+           INV _0_ (
+             .A(a),
+             .Y(_0__Y)
+           );
+           (* keep = 1 *)
+           AND _1_ (
+             .A(_0__Y),
+             .B(b),
+             .Y(_1__Y)
+           );
+           OR out (
+             .A(_1__Y),
+             .B(a),
+             .Y(out_Y)
+           );
+           INV _2_ (
+             .A(b),
+             .Y(_2__Y)
+           );
+           assign y = out_Y;
+           assign z = _2__Y;
+         endmodule\n"
+    );
+    #[cfg(feature = "serde")]
+    assert_eq!(first.to_snet(None).unwrap(), second.to_snet(None).unwrap());
+
+    // Canonicalizing again changes nothing
+    let expected = first.to_string();
+    assert_eq!(first.canonicalize(&options).unwrap(), 0);
+    assert_eq!(first.to_string(), expected);
+}
+
+#[test]
+fn ids_are_found_after_canonicalize() {
+    let netlist = get_example(true);
+    let ids: Vec<_> = netlist.objects().map(|o| o.get_id()).collect();
+    netlist
+        .canonicalize(&CanonicalOptions {
+            anonymous: vec!["rewrite_*".to_string()],
+        })
+        .unwrap();
+    let find_all = |netlist: &GateNetlist| {
+        for obj in netlist.objects() {
+            assert_eq!(netlist.find_object(obj.get_id()), Some(obj.clone()));
+            for net in obj.outputs() {
+                assert_eq!(netlist.find_net_by_id(net.get_id()), Some(net));
+            }
+        }
+    };
+    find_all(&netlist);
+    assert_eq!(netlist.objects().count(), ids.len());
+    assert!(ids.iter().all(|id| netlist.find_object(*id).is_some()));
+
+    // Objects inserted or removed afterwards are found too
+    let a = netlist.inputs().next().unwrap();
+    let inv = netlist
+        .insert_gate(gate("INV", &["A"]), "extra".into(), &[a])
+        .unwrap();
+    find_all(&netlist);
+    let id = inv.get_id();
+    drop(inv);
+    netlist.clean().unwrap();
+    assert!(netlist.find_object(id).is_none());
+    find_all(&netlist);
+}
@@ -0,0 +1,302 @@
+#![cfg(feature = "capi")]
+
+use safety_net::capi::*;
+use std::ffi::{CStr, CString, c_char};
+
+/// Returns the string `s` returned by the library, freeing it
+fn take(s: *mut c_char) -> String {
+    assert!(!s.is_null());
+    let string = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+    unsafe { sn_string_free(s) };
+    string
+}
+
+fn last_error() -> String {
+    unsafe { CStr::from_ptr(sn_last_error()) }
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn build_and_emit() {
+    let name = CString::new("top").unwrap();
+    let netlist = unsafe { sn_netlist_new(name.as_ptr()) };
+    assert!(!netlist.is_null());
+
+    let mut a = SnNet {
+        object: 0,
+        output: 0,
+    };
+    let mut b = a;
+    let (na, nb) = (CString::new("a").unwrap(), CString::new("b").unwrap());
+    assert_eq!(
+        unsafe { sn_insert_input(netlist, na.as_ptr(), &mut a) },
+        SN_OK
+    );
+    assert_eq!(
+        unsafe { sn_insert_input(netlist, nb.as_ptr(), &mut b) },
+        SN_OK
+    );
+
+    let (cell, inst) = (CString::new("AND").unwrap(), CString::new("u0").unwrap());
+    let ports = [CString::new("A").unwrap(), CString::new("B").unwrap()];
+    let port_ptrs: Vec<*const c_char> = ports.iter().map(|p| p.as_ptr()).collect();
+    let mut and = 0;
+    let status = unsafe {
+        sn_insert_gate(
+            netlist,
+            cell.as_ptr(),
+            inst.as_ptr(),
+            port_ptrs.as_ptr(),
+            port_ptrs.len(),
+            &mut and,
+        )
+    };
+    assert_eq!(status, SN_OK);
+
+    // The gate is unconnected until both inputs are driven
+    assert_eq!(unsafe { sn_verify(netlist) }, SN_ERROR);
+    assert!(!last_error().is_empty());
+    assert_eq!(unsafe { sn_connect(netlist, a, and, 0) }, SN_OK);
+    assert_eq!(unsafe { sn_connect(netlist, b, and, 1) }, SN_OK);
+    assert_eq!(unsafe { sn_connect(netlist, b, and, 2) }, SN_ERROR);
+    assert!(last_error().contains("no input 2"));
+    let y = CString::new("y").unwrap();
+    let out = SnNet {
+        object: and,
+        output: 0,
+    };
+    assert_eq!(unsafe { sn_expose(netlist, out, y.as_ptr()) }, SN_OK);
+    assert_eq!(unsafe { sn_verify(netlist) }, SN_OK);
+
+    // Walk the netlist back
+    let count = unsafe { sn_objects(netlist, std::ptr::null_mut(), 0) };
+    assert_eq!(count, 3);
+    let mut objects = vec![0; count];
+    assert_eq!(
+        unsafe { sn_objects(netlist, objects.as_mut_ptr(), count) },
+        3
+    );
+    assert_eq!(objects[2], and);
+    assert_eq!(take(unsafe { sn_object_name(netlist, and) }), "u0");
+    assert_eq!(take(unsafe { sn_object_name(netlist, a.object) }), "a");
+    assert_eq!(take(unsafe { sn_cell_name(netlist, and) }), "AND");
+    assert!(unsafe { sn_cell_name(netlist, a.object) }.is_null());
+    let mut inputs = 0;
+    assert_eq!(unsafe { sn_num_inputs(netlist, and, &mut inputs) }, SN_OK);
+    assert_eq!(inputs, 2);
+    let mut driver = out;
+    assert_eq!(
+        unsafe { sn_get_driver(netlist, and, 1, &mut driver) },
+        SN_OK
+    );
+    assert_eq!(driver, b);
+    assert_eq!(take(unsafe { sn_net_name(netlist, driver) }), "b");
+    assert_eq!(unsafe { sn_num_inputs(netlist, 99, &mut inputs) }, SN_ERROR);
+    assert!(last_error().contains("#99"));
+
+    assert_eq!(unsafe { sn_clean(netlist) }, SN_OK);
+    let verilog = take(unsafe { sn_to_verilog(netlist) });
+    assert!(verilog.contains("module top"));
+    assert!(verilog.contains("AND"));
+    unsafe { sn_netlist_free(netlist) };
+}
+
+#[test]
+fn null_arguments() {
+    assert!(unsafe { sn_netlist_new(std::ptr::null()) }.is_null());
+    assert!(last_error().contains("null"));
+    let mut net = SnNet {
+        object: 0,
+        output: 0,
+    };
+    assert_eq!(
+        unsafe { sn_insert_input(std::ptr::null_mut(), std::ptr::null(), &mut net) },
+        SN_ERROR
+    );
+    assert_eq!(
+        unsafe { sn_objects(std::ptr::null(), std::ptr::null_mut(), 0) },
+        0
+    );
+    unsafe { sn_netlist_free(std::ptr::null_mut()) };
+    unsafe { sn_string_free(std::ptr::null_mut()) };
+}
+
+#[test]
+fn sliced_cell_name() {
+    let name = CString::new("top").unwrap();
+    let netlist = unsafe { sn_netlist_new(name.as_ptr()) };
+    let (cell, inst) = (CString::new("X[1]").unwrap(), CString::new("u0").unwrap());
+    let port = CString::new("A").unwrap();
+    let mut object = 0;
+    let status = unsafe {
+        sn_insert_gate(
+            netlist,
+            cell.as_ptr(),
+            inst.as_ptr(),
+            &port.as_ptr(),
+            1,
+            &mut object,
+        )
+    };
+    assert_eq!(status, SN_ERROR);
+    assert!(last_error().contains("X[1]"));
+    assert_eq!(unsafe { sn_objects(netlist, std::ptr::null_mut(), 0) }, 0);
+    unsafe { sn_netlist_free(netlist) };
+}
+
+/// Returns the C type of the Rust type `ty`
+fn c_type(ty: &str) -> String {
+    if let Some(inner) = ty.strip_prefix("*const ") {
+        let inner = c_type(inner);
+        return match inner.ends_with('*') {
+            true => format!("{inner}const *"),
+            false => format!("const {inner} *"),
+        };
+    }
+    if let Some(inner) = ty.strip_prefix("*mut ") {
+        let inner = c_type(inner);
+        return match inner.ends_with('*') {
+            true => format!("{inner}*"),
+            false => format!("{inner} *"),
+        };
+    }
+    match ty {
+        "c_char" => "char",
+        "i32" => "int32_t",
+        "u64" => "uint64_t",
+        "usize" => "size_t",
+        _ => ty,
+    }
+    .to_string()
+}
+
+/// Returns `ty name` in C, without a space after a pointer
+fn c_declaration(ty: &str, name: &str) -> String {
+    let ty = c_type(ty);
+    match ty.ends_with('*') {
+        true => format!("{ty}{name}"),
+        false => format!("{ty} {name}"),
+    }
+}
+
+/// Generates the C header from the declarations of the `capi` module
+fn generate_header(source: &str) -> String {
+    let mut header = String::from(
+        "/* Generated from src/capi.rs by tests/capi.rs, do not edit. */\n\n\
+         #ifndef SAFETY_NET_H\n#define SAFETY_NET_H\n\n\
+         #include <stddef.h>\n#include <stdint.h>\n\n\
+         #ifdef __cplusplus\nextern \"C\" {\n#endif\n\n",
+    );
+    let mut docs: Vec<String> = Vec::new();
+    let mut lines = source.lines();
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        if let Some(doc) = line.strip_prefix("///") {
+            docs.push(doc.trim().replace(['[', ']'], ""));
+            continue;
+        }
+        let comment = || {
+            let mut c = String::from("/**\n");
+            for d in &docs {
+                c.push_str(&format!(" *{}{d}\n", if d.is_empty() { "" } else { " " }));
+            }
+            c.push_str(" */\n");
+            c
+        };
+        if let Some(decl) = line.strip_prefix("pub const ") {
+            let (name, rest) = decl.split_once(':').unwrap();
+            let value = rest.split_once('=').unwrap().1.trim_end_matches(';').trim();
+            header.push_str(&comment());
+            let value = match value.starts_with('-') {
+                true => format!("({value})"),
+                false => value.to_string(),
+            };
+            header.push_str(&format!("#define {name} {value}\n\n"));
+        } else if let Some(decl) = line.strip_prefix("pub struct ") {
+            let name = decl.split(['(', ' ', '{']).next().unwrap();
+            header.push_str(&comment());
+            if decl.ends_with('{') {
+                header.push_str(&format!("typedef struct {name} {{\n"));
+                let mut field_docs = Vec::new();
+                for field in lines.by_ref().map(str::trim) {
+                    if field == "}" {
+                        break;
+                    }
+                    if let Some(doc) = field.strip_prefix("///") {
+                        field_docs.push(doc.trim().to_string());
+                    } else if let Some((name, ty)) =
+                        field.strip_prefix("pub ").and_then(|f| f.split_once(": "))
+                    {
+                        for d in field_docs.drain(..) {
+                            header.push_str(&format!("    /* {d} */\n"));
+                        }
+                        let decl = c_declaration(ty.trim_end_matches(','), name);
+                        header.push_str(&format!("    {decl};\n"));
+                    }
+                }
+                header.push_str(&format!("}} {name};\n\n"));
+            } else {
+                header.push_str(&format!("typedef struct {name} {name};\n\n"));
+            }
+        } else if let Some(decl) = line.strip_prefix("pub type ") {
+            let (name, ty) = decl.trim_end_matches(';').split_once(" = ").unwrap();
+            header.push_str(&comment());
+            header.push_str(&format!("typedef {};\n\n", c_declaration(ty, name)));
+        } else if line.starts_with("pub extern \"C\" fn")
+            || line.starts_with("pub unsafe extern \"C\" fn")
+        {
+            let mut signature = line.to_string();
+            while !signature.ends_with('{') {
+                signature.push_str(lines.next().unwrap().trim());
+            }
+            let signature = signature.split_once(" fn ").unwrap().1;
+            let (name, rest) = signature.split_once('(').unwrap();
+            let (args, ret) = rest.rsplit_once(')').unwrap();
+            let ret = ret
+                .trim_end_matches('{')
+                .trim()
+                .strip_prefix("-> ")
+                .unwrap_or("void");
+            let args: Vec<String> = args
+                .split(',')
+                .map(str::trim)
+                .filter(|a| !a.is_empty())
+                .map(|a| {
+                    let (name, ty) = a.split_once(": ").unwrap();
+                    c_declaration(ty, name)
+                })
+                .collect();
+            let args = if args.is_empty() {
+                "void".to_string()
+            } else {
+                args.join(", ")
+            };
+            header.push_str(&comment());
+            header.push_str(&format!("{}({args});\n\n", c_declaration(ret, name)));
+        }
+        if !line.starts_with("#[") {
+            docs.clear();
+        }
+    }
+    header.push_str("#ifdef __cplusplus\n}\n#endif\n\n#endif /* SAFETY_NET_H */\n");
+    header
+}
+
+#[test]
+fn header_is_current() {
+    let root = env!("CARGO_MANIFEST_DIR");
+    let source = std::fs::read_to_string(format!("{root}/src/capi.rs")).unwrap();
+    let header = generate_header(&source);
+    let path = format!("{root}/include/safety_net.h");
+    if std::env::var("SAFETY_NET_HEADER").is_ok_and(|v| v == "overwrite") {
+        std::fs::create_dir_all(format!("{root}/include")).unwrap();
+        std::fs::write(&path, &header).unwrap();
+    }
+    let committed = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == header,
+        "include/safety_net.h is stale, regenerate it with SAFETY_NET_HEADER=overwrite"
+    );
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "cli")]
+
+use safety_net::{
+    netlist::{Gate, GateNetlist, Netlist},
+    shell::save_netlist,
+};
+use std::{path::PathBuf, process::Command, rc::Rc};
+
+/// A multiplexer of `a` and `b` selected by `s`, with an unused inverter
+fn mux() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("mux".to_string());
+    let s = netlist.insert_input("s".into());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    netlist.not(&a).unwrap();
+    netlist
+        .mux(&s, &a, &b)
+        .unwrap()
+        .expose_with_name("y".into());
+    netlist
+}
+
+/// A scratch directory for the files of `test`
+fn scratch(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("safety-net-cli-{test}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Runs `snet` with `args`, returning its exit code and standard output
+fn snet(args: &[&PathBuf], command: &str) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_snet"))
+        .arg(command)
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn convert_clean_and_diff() {
+    let dir = scratch("convert");
+    let json = dir.join("mux.json");
+    save_netlist(&mux(), json.to_str().unwrap()).unwrap();
+
+    let (code, stats) = snet(&[&json], "stats");
+    assert_eq!(code, 0);
+    assert!(stats.contains("instances: 2 (0 sequential)"));
+
+    let (snet_file, aag) = (dir.join("mux.snet"), dir.join("mux.aag"));
+    assert_eq!(snet(&[&json, &snet_file], "convert").0, 0);
+    assert_eq!(snet(&[&snet_file, &aag], "convert").0, 0);
+    let (code, out) = snet(&[&json, &aag], "diff");
+    assert_eq!(code, 1);
+    assert!(out.contains("MUX: 1 -> 0"));
+    assert!(out.contains("outputs agree"));
+
+    let cleaned = dir.join("clean.json");
+    assert_eq!(snet(&[&json, &cleaned], "clean").0, 0);
+    let (code, out) = snet(&[&json, &cleaned], "diff");
+    assert_eq!(code, 1);
+    assert!(out.starts_with("INV: 1 -> 0\n"));
+    assert_eq!(snet(&[&cleaned, &snet_file], "diff").0, 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn lint_and_dot() {
+    let dir = scratch("lint");
+    let json = dir.join("mux.json");
+    save_netlist(&mux(), json.to_str().unwrap()).unwrap();
+    let (code, out) = snet(&[&json], "lint");
+    assert_eq!(code, 0);
+    assert_eq!(out, "warning: 1 circuit nodes do not reach an output\n");
+
+    let broken = Netlist::new("broken".to_string());
+    let inv = broken.insert_gate_disconnected(
+        Gate::new_logical("INV".into(), vec!["A".into()], "Y".into()),
+        "u0".into(),
+    );
+    inv.get_output(0).expose_with_name("y".into());
+    let json = dir.join("broken.json");
+    save_netlist(&broken, json.to_str().unwrap()).unwrap();
+    let (code, out) = snet(&[&json], "lint");
+    assert_eq!(code, 1);
+    assert!(out.starts_with(
+        "error[unconnected-inputs]: 1 instances have unconnected inputs: Unconnected input ports"
+    ));
+
+    let (code, dot) = snet(&[&json], "dot");
+    assert_eq!(code, 0);
+    assert!(dot.starts_with("digraph {"));
+    assert!(dot.contains("INV(u0)"));
+
+    assert_eq!(snet(&[], "frobnicate").0, 2);
+    assert_eq!(snet(&[&dir.join("missing.json")], "stats").0, 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn yosys_step() {
+    let dir = scratch("yosys");
+    let json = dir.join("mux.json");
+    std::fs::write(&json, mux().to_yosys_json().unwrap()).unwrap();
+    let out = dir.join("out.json");
+    let pass = PathBuf::from("clean,constants");
+    assert_eq!(snet(&[&pass, &json, &out], "yosys").0, 0);
+    let cleaned = Netlist::from_yosys_json(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    assert_eq!(cleaned.instances().count(), 1);
+
+    let pass = PathBuf::from("frobnicate");
+    assert_eq!(snet(&[&pass, &json, &out], "yosys").0, 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,40 @@
+use safety_net::{
+    attribute::Parameter,
+    circuit::{Evaluate, Identifier, Instantiable, Net},
+    derive::Instantiable,
+    logic::Logic,
+    netlist::Gate,
+};
+
+/// A flip-flop capturing `D` on the clock `C`, starting from `INIT`
+#[derive(Debug, Clone, Instantiable)]
+#[cell_name = "FDRE"]
+#[instantiable(seq)]
+pub struct Dff {
+    #[parameter(name = "INIT")]
+    pub init: Logic,
+    #[input_port]
+    pub d: Net,
+    #[input_port]
+    pub c: Net,
+    #[output_port]
+    pub q: Net,
+}
+
+/// A gate or a flip-flop
+#[derive(Debug, Clone, Instantiable)]
+pub enum Cell {
+    #[instantiable(constant)]
+    Gate(Gate),
+    Dff(Dff),
+}
+
+/// Registers capture `D` on the next clock edge
+impl Evaluate for Cell {
+    fn eval(&self, inputs: &[Logic]) -> Vec<Logic> {
+        match self {
+            Cell::Gate(g) => g.eval(inputs),
+            Cell::Dff(_) => vec![inputs[0]],
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use safety_net::constraints::{Constraints, Target};
+
+const SDC: &str = r#"
+# A clock on port clk
+create_clock -period 10.0 -name sys_clk -waveform {0 5} [get_ports clk]
+set_input_delay -clock sys_clk 2 [get_ports {a b}]; set_input_delay -clock sys_clk -min 0.5 [get_ports a]
+set_output_delay -clock [get_clocks sys_clk] \
+    -max 3 [get_ports y*]
+set_false_path -from [get_ports rst] -to [get_pins r0/D]
+set_multicycle_path 2 -setup -from [get_cells r0] -through [get_nets n*] -to [get_pins r1/D]
+set_multicycle_path 1 -hold -from [get_cells r0]
+set_property IOSTANDARD LVCMOS33 [get_ports clk]
+"#;
+
+#[test]
+fn test_parse_constraints() {
+    let constraints: Constraints = SDC.parse().unwrap();
+    assert_eq!(constraints.clocks.len(), 1);
+    let clock = &constraints.clocks[0];
+    assert_eq!(clock.name, "sys_clk");
+    assert_eq!(clock.period, 10.0);
+    assert_eq!(clock.waveform, (0.0, 5.0));
+    assert_eq!(clock.sources, [Target::Port("clk".to_string())]);
+    assert_eq!(constraints.get_period(), 10.0);
+    assert!(constraints.is_clock_port("clk"));
+
+    // The minimum delay is only for hold analysis
+    assert_eq!(constraints.input_delays.len(), 1);
+    assert_eq!(constraints.input_delay("b"), Some(2.0));
+    assert_eq!(constraints.input_delay("c"), None);
+    assert_eq!(
+        constraints.output_delays[0].clock.as_deref(),
+        Some("sys_clk")
+    );
+    assert_eq!(constraints.output_delay("y0"), Some(3.0));
+
+    let path = &constraints.false_paths[0];
+    assert_eq!(path.from, [Target::Port("rst".to_string())]);
+    assert!(path.to[0].matches_pin("r0", "D"));
+    assert!(!path.to[0].matches_cell("r0"));
+    assert_eq!(path.to[0].to_string(), "[get_pins {r0/D}]");
+
+    // Hold multipliers are skipped
+    assert_eq!(constraints.multicycle_paths.len(), 1);
+    let path = &constraints.multicycle_paths[0];
+    assert_eq!(path.multiplier, 2);
+    assert_eq!(path.from, [Target::Cell("r0".to_string())]);
+    assert!(path.through[0].matches_net("n0"));
+    assert!(path.to[0].matches_pin("r1", "D"));
+    assert_eq!(constraints.ignored, ["set_property"]);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(
+        "create_clock [get_ports clk]"
+            .parse::<Constraints>()
+            .is_err()
+    );
+    assert!("create_clock -period 10".parse::<Constraints>().is_err());
+    assert!(
+        "set_input_delay -clock clk [get_ports a]"
+            .parse::<Constraints>()
+            .is_err()
+    );
+    assert!(
+        "set_false_path -from [get_ports a"
+            .parse::<Constraints>()
+            .is_err()
+    );
+    assert!(
+        "set_false_path -to [get_foo a]"
+            .parse::<Constraints>()
+            .is_err()
+    );
+    assert!("set_multicycle_path -to y".parse::<Constraints>().is_err());
+    assert!(
+        "set_multicycle_path 0 -to y"
+            .parse::<Constraints>()
+            .is_err()
+    );
+    assert_eq!(
+        "create_clock -period 4 clk"
+            .parse::<Constraints>()
+            .unwrap()
+            .clocks[0]
+            .name,
+        "clk"
+    );
+}
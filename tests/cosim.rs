@@ -0,0 +1,84 @@
+#![cfg(all(feature = "cosim", feature = "derive"))]
+
+use safety_net::{
+    logic::Logic,
+    netlist::{
+        Gate, Netlist,
+        sim::{Backend, CosimOptions, cosim_with},
+        stimulus::Stimulus,
+    },
+};
+use std::{process::Command, rc::Rc};
+
+mod common;
+use common::{Cell, Dff};
+
+/// The Verilog models of the cells
+const LIBRARY: &str = "module AND (input A, input B, output Y);
+  assign Y = A & B;
+endmodule
+
+module FDRE #(parameter INIT = 1'b0) (input D, input C, output reg Q);
+  initial Q = INIT;
+  always @(posedge C) Q <= D;
+endmodule
+";
+
+/// The output `q` is `a & b` delayed by one cycle
+fn and_register() -> Rc<Netlist<Cell>> {
+    let netlist = Netlist::new("and_register".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let clk = netlist.insert_input("clk".into());
+    let and = Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into());
+    let and = netlist
+        .insert_gate(Cell::Gate(and), "g0".into(), &[a, b])
+        .unwrap();
+    let dff = Dff {
+        init: Logic::False,
+        d: "D".into(),
+        c: "C".into(),
+        q: "Q".into(),
+    };
+    netlist
+        .insert_gate(Cell::Dff(dff), "ff".into(), &[and.get_output(0), clk])
+        .unwrap()
+        .expose_with_name("q".into());
+    netlist
+}
+
+/// Returns `true` if `program` can be run
+fn installed(program: &str) -> bool {
+    Command::new(program).arg("-V").output().is_ok()
+}
+
+#[test]
+fn icarus_agrees() {
+    if !installed("iverilog") {
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("safety-net-cosim-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let library = dir.join("cells.v");
+    std::fs::write(&library, LIBRARY).unwrap();
+    let options = CosimOptions {
+        libraries: vec![library],
+        dir: None,
+    };
+    let netlist = and_register();
+    let stimulus = Stimulus::clock("clk")
+        .signal("a", [0, 1, 1, 0])
+        .signal("b", [1, 1, 0, 1])
+        .expect("g0_Y", [0, 1, 0, 0])
+        .expect("q", [0, 0, 1, 0]);
+    let report = cosim_with(&netlist, &stimulus, Backend::Icarus, &options).unwrap();
+    assert!(report.passes(), "{}", report.log);
+
+    // Both simulators see the same wrong expectation
+    let stimulus = stimulus.expect("g0_Y", [0, 0]);
+    let report = cosim_with(&netlist, &stimulus, Backend::Icarus, &options).unwrap();
+    assert!(!report.passes());
+    assert!(report.agrees(), "{}", report.log);
+    assert_eq!(report.external.len(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,61 @@
+use safety_net::netlist::Gate;
+use safety_net::netlist::GateNetlist;
+use safety_net::netlist::Netlist;
+use safety_net::netlist::cost::{gate_equivalents, lut_count, unit};
+use safety_net::netlist::select::Selection;
+use std::rc::Rc;
+
+fn gate(name: &str, inputs: &[&str]) -> Gate {
+    Gate::new_logical(
+        name.into(),
+        inputs.iter().map(|i| (*i).into()).collect(),
+        "Y".into(),
+    )
+}
+
+/// `y = LUT4(~(a & b), a, b, c)`
+fn get_example() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("example".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let c = netlist.insert_input("c".into());
+    let nand = netlist
+        .insert_gate(
+            gate("NAND", &["A", "B"]),
+            "inst_0".into(),
+            &[a.clone(), b.clone()],
+        )
+        .unwrap();
+    let inv = netlist
+        .insert_gate(gate("INV", &["A"]), "inst_1".into(), &[c])
+        .unwrap();
+    netlist
+        .insert_gate(
+            gate("LUT4", &["I0", "I1", "I2", "I3"]),
+            "inst_2".into(),
+            &[nand.get_output(0), inv.get_output(0), a, b],
+        )
+        .unwrap()
+        .expose_with_name("y".into());
+    netlist
+}
+
+#[test]
+fn test_cost_models() {
+    let netlist = get_example();
+    assert_eq!(netlist.cost(&unit).total, 3.0);
+    assert_eq!(netlist.cost(&lut_count).total, 1.0);
+
+    let area = netlist.cost(&gate_equivalents);
+    assert_eq!(area.total, 2.5);
+    assert_eq!(area.cells["NAND"], 1.0);
+    assert_eq!(area.cells["INV"], 0.5);
+    assert_eq!(
+        area.to_string(),
+        "total: 2.5\n  INV: 0.5\n  LUT4: 1\n  NAND: 1\n"
+    );
+
+    let luts = Selection::cell_type("luts", &netlist, "LUT*");
+    assert_eq!(netlist.cost_of(&luts, &unit).cells.len(), 1);
+    assert_eq!(netlist.cost_of(&luts, &|_: &Gate| 6.0).total, 6.0);
+}
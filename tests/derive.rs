@@ -0,0 +1,452 @@
+#![cfg(feature = "derive")]
+use bitvec::{bitvec, order::Lsb0, vec::BitVec};
+use safety_net::{
+    attribute::Parameter,
+    circuit::{Evaluate, Identifier, Instantiable, Net},
+    derive::{Evaluate, Instantiable},
+    format_id,
+    logic::Logic,
+    netlist::{Gate, Netlist},
+};
+
+#[derive(Debug, Clone, Instantiable, Evaluate)]
+#[cell_name = "LUT{k}"]
+#[evaluate(table = "INIT")]
+struct Lut {
+    #[cell_name]
+    name: Identifier,
+    k: usize,
+    #[parameter(name = "INIT")]
+    init: BitVec,
+    #[input_port]
+    inputs: Vec<Net>,
+    #[output_port]
+    output: Net,
+}
+
+impl Lut {
+    fn new(k: usize, init: BitVec) -> Self {
+        Self {
+            name: Self::cell_name(&k),
+            k,
+            init,
+            inputs: (0..k).map(|i| Net::new_logic(format_id!("I{i}"))).collect(),
+            output: Net::new_logic("O".into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Instantiable)]
+#[instantiable(seq)]
+struct FlipFlop {
+    #[cell_name]
+    name: Identifier,
+    #[parameter]
+    width: i64,
+    #[input_port]
+    d: Net,
+    #[input_port]
+    clk: Net,
+    #[output_port]
+    q: Net,
+}
+
+#[derive(Debug, Clone, Instantiable)]
+#[cell_name = "LDCE"]
+#[instantiable(latch)]
+struct Latch {
+    #[input_port]
+    d: Net,
+    #[input_port]
+    g: Net,
+    #[output_port]
+    q: Net,
+}
+
+#[derive(Debug, Clone, Instantiable)]
+#[cell_name = "REG{width}"]
+#[instantiable(seq)]
+struct Register {
+    #[cell_name]
+    name: Identifier,
+    #[parameter]
+    width: i64,
+    #[input_port]
+    d: Net,
+    #[output_port]
+    q: Net,
+}
+
+#[derive(Debug, Clone, Instantiable)]
+enum Cell {
+    #[instantiable(constant)]
+    Gate(Gate),
+    Lut(Lut),
+    FlipFlop(FlipFlop),
+    Latch(Latch),
+}
+
+#[test]
+fn test_derive_struct() {
+    let mut lut = Lut::new(2, bitvec![0, 1, 1, 0]);
+    assert_eq!(*lut.get_name(), "LUT2".into());
+    assert_eq!(*Lut::new(3, BitVec::new()).get_name(), "LUT3".into());
+    let inputs: Vec<&Net> = lut.get_input_ports().into_iter().collect();
+    assert_eq!(inputs, [&Net::new_logic("I0".into()), &"I1".into()]);
+    assert_eq!(lut.get_output_ports().into_iter().count(), 1);
+    assert!(!lut.is_seq());
+    assert!(lut.get_constant().is_none());
+
+    let init: Identifier = "INIT".into();
+    assert!(lut.has_parameter(&init));
+    assert!(!lut.has_parameter(&"k".into()));
+    assert_eq!(
+        lut.get_parameter(&init),
+        Some(Parameter::BitVec(bitvec![0, 1, 1, 0]))
+    );
+    let old = lut.set_parameter(&init, Parameter::BitVec(bitvec![1, 0, 0, 1]));
+    assert_eq!(old, Some(Parameter::BitVec(bitvec![0, 1, 1, 0])));
+    assert_eq!(lut.init, bitvec![1, 0, 0, 1]);
+    assert_eq!(lut.parameters().count(), 1);
+
+    let mut ff = FlipFlop {
+        name: "FDRE".into(),
+        width: 1,
+        d: "D".into(),
+        clk: "C".into(),
+        q: "Q".into(),
+    };
+    assert!(ff.is_seq());
+    assert_eq!(ff.get_input_ports().into_iter().count(), 2);
+    assert_eq!(ff.set_parameter(&"width".into(), 2.into()), Some(1.into()));
+    assert_eq!(
+        ff.get_parameter(&"width".into()),
+        Some(Parameter::Integer(2))
+    );
+}
+
+#[test]
+fn test_derive_formatted_name() {
+    assert_eq!(Lut::cell_name(&4), "LUT4".into());
+
+    let mut reg = Register {
+        name: Register::cell_name(&1),
+        width: 1,
+        d: "D".into(),
+        q: "Q".into(),
+    };
+    assert_eq!(*reg.get_name(), "REG1".into());
+    reg.set_parameter(&"width".into(), 8.into());
+    assert_eq!(*reg.get_name(), "REG8".into());
+}
+
+#[test]
+#[should_panic(expected = "Invalid parameter type for INIT")]
+fn test_derive_struct_wrong_type() {
+    let mut lut = Lut::new(1, bitvec![0, 1]);
+    lut.set_parameter(&"INIT".into(), Parameter::Logic(Logic::True));
+}
+
+#[test]
+fn test_derived_cells_in_netlist() {
+    let netlist = Netlist::new("derived".to_string());
+    let a = netlist.insert_input("a".into());
+    let clk = netlist.insert_input("clk".into());
+    let lut = netlist
+        .insert_gate(
+            Cell::Lut(Lut::new(2, bitvec![0, 0, 0, 1])),
+            "lut".into(),
+            &[a.clone(), a],
+        )
+        .unwrap();
+    let ff = FlipFlop {
+        name: "FDRE".into(),
+        width: 1,
+        d: "D".into(),
+        clk: "C".into(),
+        q: "Q".into(),
+    };
+    netlist
+        .insert_gate(Cell::FlipFlop(ff), "ff".into(), &[lut.get_output(0), clk])
+        .unwrap()
+        .expose_with_name("y".into());
+    assert!(netlist.verify().is_ok());
+    let verilog = netlist.to_string();
+    assert!(verilog.contains("LUT2 #("));
+    assert!(verilog.contains(".INIT(4'h8)"));
+    assert!(verilog.contains("FDRE #("));
+}
+
+#[test]
+fn test_derived_latch_breaks_loops() {
+    use safety_net::netlist::{VerifyOptions, testing::check_acyclic};
+    let latch = Latch {
+        d: "D".into(),
+        g: "G".into(),
+        q: "Q".into(),
+    };
+    assert!(latch.is_latch() && !latch.is_seq());
+    assert!(Cell::Latch(latch.clone()).is_latch());
+    assert!(!Cell::Lut(Lut::new(1, bitvec![0, 1])).is_latch());
+
+    // A latch feeding back through an inverter is not a combinational loop
+    let netlist = Netlist::new("latch".to_string());
+    let en = netlist.insert_input("en".into());
+    let q = netlist.insert_gate_disconnected(Cell::Latch(latch), "l0".into());
+    let inv = Gate::new_logical("INV".into(), vec!["A".into()], "Y".into());
+    let y = netlist
+        .insert_gate(Cell::Gate(inv), "u0".into(), &[q.get_output(0)])
+        .unwrap();
+    q.get_input(0).connect(y.get_output(0));
+    q.get_input(1).connect(en);
+    y.expose_with_name("y".into());
+    assert!(check_acyclic(&netlist).is_ok());
+    assert!(netlist.verify_with(&VerifyOptions::signoff()).is_ok());
+}
+
+#[derive(Debug, Clone, Instantiable)]
+enum Either<L, R>
+where
+    L: Instantiable,
+    R: Instantiable,
+{
+    #[instantiable(constant)]
+    Left(L),
+    Right(R),
+}
+
+/// A cell with a lifetime and a generic parameter type
+#[derive(Debug, Clone, Instantiable)]
+#[cell_name = "CFG"]
+struct Config<'a, P: Clone + Into<Parameter> + TryFrom<Parameter>> {
+    #[parameter(name = "MODE")]
+    mode: P,
+    #[output_port]
+    outputs: &'a [Net],
+}
+
+#[test]
+fn test_derive_generics() {
+    let gate = Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into());
+    let left: Either<Gate, Lut> = Either::Left(gate);
+    assert_eq!(*left.get_name(), "AND".into());
+    let right: Either<Gate, Lut> = Either::Right(Lut::new(2, bitvec![0, 1, 1, 0]));
+    assert_eq!(right.parameters().count(), 1);
+    let vdd = Either::<Gate, Lut>::from_constant(Logic::True).unwrap();
+    assert_eq!(vdd.get_constant(), Some(Logic::True));
+
+    let outputs = [Net::new_logic("O0".into()), Net::new_logic("O1".into())];
+    let config = Config {
+        mode: "SYNC".to_string(),
+        outputs: &outputs,
+    };
+    assert_eq!(*config.get_name(), "CFG".into());
+    assert_eq!(config.get_output_ports().into_iter().count(), 2);
+    assert_eq!(
+        config.get_parameter(&"MODE".into()),
+        Some(Parameter::String("SYNC".to_string()))
+    );
+}
+
+#[derive(Debug, Clone, Instantiable)]
+#[instantiable(accessors)]
+enum Library {
+    #[instantiable(constant)]
+    Gate(Gate),
+    Lut(Lut),
+    FlipFlop(FlipFlop),
+}
+
+#[test]
+fn test_derive_accessors() {
+    let mut cell: Library = Lut::new(2, bitvec![0, 1, 1, 0]).into();
+    assert!(cell.as_gate().is_none());
+    assert_eq!(cell.as_lut().unwrap().k, 2);
+    cell.as_lut_mut().unwrap().init = bitvec![0, 0, 0, 1];
+    assert!(cell.as_flip_flop().is_none());
+    let cell = cell.into_gate().unwrap_err();
+    assert_eq!(cell.into_lut().unwrap().init, bitvec![0, 0, 0, 1]);
+
+    // The accessors work on the cells of a netlist
+    let netlist = Netlist::<Library>::new("accessors".to_string());
+    let a = netlist.insert_input("a".into());
+    let lut = netlist
+        .insert_gate(
+            Lut::new(2, bitvec![0, 1, 1, 0]).into(),
+            "lut".into(),
+            &[a.clone(), a],
+        )
+        .unwrap();
+    let k = lut.get_instance_type().unwrap().as_lut().map(|l| l.k);
+    assert_eq!(k, Some(2));
+
+    // Nodes are selected by variant, kind and function
+    let ff = FlipFlop {
+        name: "FDRE".into(),
+        width: 1,
+        d: Net::new_logic("D".into()),
+        clk: Net::new_logic("C".into()),
+        q: Net::new_logic("Q".into()),
+    };
+    let one = netlist.insert_constant(Logic::True, "one".into()).unwrap();
+    netlist
+        .insert_gate(ff.into(), "ff".into(), &[lut.get_output(0), one])
+        .unwrap();
+    assert_eq!(netlist.instances().count(), 3);
+    assert_eq!(netlist.instances_of::<Lut>().collect::<Vec<_>>(), [lut]);
+    assert_eq!(netlist.instances_of::<FlipFlop>().count(), 1);
+    assert_eq!(netlist.instances_of::<Gate>().count(), 1);
+    assert_eq!(netlist.instances_of::<Library>().count(), 3);
+    let seq: Vec<_> = netlist.seq_cells().collect();
+    assert_eq!(seq.len(), 1);
+    assert_eq!(seq[0].get_instance_name(), Some("ff".into()));
+    let constants: Vec<_> = netlist.constants().collect();
+    assert_eq!(constants.len(), 1);
+    assert_eq!(constants[0].get_instance_name(), Some("one".into()));
+}
+
+#[test]
+fn test_derive_port_iterators() {
+    let cell = Cell::Lut(Lut::new(3, bitvec![0; 8]));
+    let inputs = cell.get_input_ports().into_iter();
+    assert_eq!(inputs.size_hint(), (3, Some(3)));
+    let names: Vec<String> = inputs.map(|n| n.get_identifier().to_string()).collect();
+    assert_eq!(names, ["I0", "I1", "I2"]);
+
+    let cell = Cell::FlipFlop(FlipFlop {
+        name: "FDRE".into(),
+        width: 1,
+        d: Net::new_logic("D".into()),
+        clk: Net::new_logic("C".into()),
+        q: Net::new_logic("Q".into()),
+    });
+    assert_eq!(cell.get_input_ports().into_iter().count(), 2);
+    assert_eq!(cell.get_output_ports().into_iter().count(), 1);
+    let params: Vec<Identifier> = cell.parameters().map(|(id, _)| id).collect();
+    assert_eq!(params, [Identifier::from("width")]);
+}
+
+/// A cell that ties its output to high impedance
+#[derive(Debug, Clone)]
+struct TieZ(Gate);
+
+impl Instantiable for TieZ {
+    fn get_name(&self) -> &Identifier {
+        self.0.get_name()
+    }
+
+    fn get_input_ports(&self) -> impl IntoIterator<Item = &Net> {
+        self.0.get_input_ports()
+    }
+
+    fn get_output_ports(&self) -> impl IntoIterator<Item = &Net> {
+        self.0.get_output_ports()
+    }
+
+    fn has_parameter(&self, _id: &Identifier) -> bool {
+        false
+    }
+
+    fn get_parameter(&self, _id: &Identifier) -> Option<Parameter> {
+        None
+    }
+
+    fn set_parameter(&mut self, _id: &Identifier, _val: Parameter) -> Option<Parameter> {
+        None
+    }
+
+    fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)> {
+        std::iter::empty()
+    }
+
+    fn from_constant(val: Logic) -> Option<Self> {
+        (val == Logic::Z).then(|| TieZ(Gate::new_logical("TIEZ".into(), vec![], "Y".into())))
+    }
+
+    fn get_constant(&self) -> Option<Logic> {
+        Some(Logic::Z)
+    }
+
+    fn is_seq(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Instantiable)]
+enum Tristate {
+    #[instantiable(constant)]
+    Gate(Gate),
+    #[instantiable(constant(Z))]
+    TieZ(TieZ),
+}
+
+#[test]
+fn test_derive_constant_values() {
+    assert!(matches!(
+        Tristate::from_constant(Logic::True),
+        Some(Tristate::Gate(_))
+    ));
+    assert!(matches!(
+        Tristate::from_constant(Logic::Z),
+        Some(Tristate::TieZ(_))
+    ));
+    assert!(Tristate::from_constant(Logic::X).is_none());
+
+    let netlist = Netlist::<Tristate>::new("tristate".to_string());
+    netlist
+        .insert_constant(Logic::Z, "z".into())
+        .unwrap()
+        .expose_with_name("y".into());
+    assert!(netlist.to_string().contains("assign y = 1'bz;"));
+    assert!(netlist.insert_constant(Logic::X, "x".into()).is_err());
+}
+
+#[derive(Debug, Clone, Instantiable, Evaluate)]
+enum Combinational {
+    #[instantiable(constant)]
+    Gate(Gate),
+    Lut(Lut),
+}
+
+#[test]
+fn test_derive_evaluate() {
+    // A 2-input multiplexer with `I2` as the select
+    let mux = Lut::new(3, bitvec![0, 1, 0, 1, 0, 0, 1, 1]);
+    let eval = |inputs: [Logic; 3]| mux.eval(&inputs);
+    assert_eq!(
+        eval([Logic::True, Logic::False, Logic::False]),
+        [Logic::True]
+    );
+    assert_eq!(
+        eval([Logic::True, Logic::False, Logic::True]),
+        [Logic::False]
+    );
+    assert_eq!(eval([Logic::True, Logic::True, Logic::X]), [Logic::True]);
+    assert_eq!(eval([Logic::True, Logic::False, Logic::X]), [Logic::X]);
+    assert_eq!(
+        mux.eval_words(&[0b1111_0000, 0b1100_1100, 0b1010_1010]),
+        [0b1101_1000]
+    );
+
+    let cell = Combinational::Lut(mux);
+    assert_eq!(cell.eval_words(&[u64::MAX, 0, 0]), [u64::MAX]);
+    let and = Combinational::Gate(Gate::new_logical(
+        "AND".into(),
+        vec!["A".into(), "B".into()],
+        "Y".into(),
+    ));
+    assert_eq!(and.eval(&[Logic::True, Logic::False]), [Logic::False]);
+}
+
+#[test]
+fn test_derive_fold() {
+    let and = Cell::Gate(Gate::new_logical(
+        "AND".into(),
+        vec!["A".into(), "B".into()],
+        "Y".into(),
+    ));
+    assert_eq!(and.fold(&[Some(Logic::False), None]), Some(Logic::False));
+    assert_eq!(and.fold(&[Some(Logic::True), None]), None);
+    let lut = Cell::Lut(Lut::new(2, bitvec![0, 0, 0, 1]));
+    assert_eq!(lut.fold(&[Some(Logic::False), Some(Logic::False)]), None);
+}
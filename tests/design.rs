@@ -0,0 +1,392 @@
+#![cfg(feature = "derive")]
+use safety_net::{
+    attribute::Parameter,
+    circuit::{Evaluate, HierPath, Identifier, Instantiable, Net},
+    derive::Instantiable,
+    error::Error,
+    format_id,
+    logic::Logic,
+    netlist::{Gate, Netlist, VerilogOptions, blackbox::BlackBox, design::Design},
+};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Instantiable)]
+enum Cell {
+    #[instantiable(constant)]
+    Gate(Gate),
+    BlackBox(BlackBox),
+}
+
+impl Evaluate for Cell {
+    fn eval(&self, inputs: &[Logic]) -> Vec<Logic> {
+        match self {
+            Cell::Gate(g) => g.eval(inputs),
+            Cell::BlackBox(b) => b.eval(inputs),
+        }
+    }
+}
+
+/// `y = a & b`
+fn get_and() -> Rc<Netlist<Cell>> {
+    let netlist = Netlist::new("and2".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    netlist
+        .insert_gate(
+            Cell::Gate(Gate::new_logical(
+                "AND".into(),
+                vec!["A".into(), "B".into()],
+                "Y".into(),
+            )),
+            "inst_0".into(),
+            &[a, b],
+        )
+        .unwrap()
+        .expose_with_name("y".into());
+    netlist
+}
+
+/// `y = (a & b) & c`, with two instances of `and2` and an IP block on the side
+fn get_top(and2: &Netlist<Cell>) -> Rc<Netlist<Cell>> {
+    let netlist = Netlist::new("top".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let c = netlist.insert_input("c".into());
+    let cell = Cell::BlackBox(and2.to_blackbox());
+    let u0 = netlist
+        .insert_gate(cell.clone(), "u0".into(), &[a, b])
+        .unwrap();
+    let u1 = netlist
+        .insert_gate(cell, "u1".into(), &[u0.get_output(0), c.clone()])
+        .unwrap();
+    u1.expose_with_name("y".into());
+    let ip = BlackBox::new("IP".into(), vec!["D".into()], vec!["Q".into()]);
+    netlist
+        .insert_gate(Cell::BlackBox(ip), "u_ip".into(), &[c])
+        .unwrap()
+        .expose_with_name("q".into());
+    netlist
+}
+
+fn get_design() -> Design<Cell> {
+    let and2 = get_and();
+    let top = get_top(&and2);
+    let mut design = Design::new();
+    design.add(top).unwrap();
+    design.add(and2).unwrap();
+    design
+}
+
+#[test]
+fn test_design_hierarchy() {
+    let design = get_design();
+    assert!(design.verify().is_ok());
+    assert_eq!(*design.get_top().unwrap().get_name(), "top");
+    let top = design.get("top").unwrap();
+    let children: Vec<String> = design
+        .children(top)
+        .iter()
+        .map(|m| m.get_name().clone())
+        .collect();
+    assert_eq!(children, ["and2"]);
+    let u0 = top.find_net(&"u0_y".into()).unwrap().unwrap();
+    assert!(Rc::ptr_eq(
+        design.resolve(&u0).unwrap(),
+        design.get("and2").unwrap()
+    ));
+    let order: Vec<String> = design
+        .order()
+        .unwrap()
+        .iter()
+        .map(|m| m.get_name().clone())
+        .collect();
+    assert_eq!(order, ["and2", "top"]);
+}
+
+#[test]
+fn test_design_top() {
+    let mut design = get_design();
+    assert!(matches!(
+        design.set_top("missing"),
+        Err(Error::ModuleNotFound(_))
+    ));
+    design.set_top("and2").unwrap();
+    assert_eq!(*design.get_top().unwrap().get_name(), "and2");
+
+    // Two modules that are never instantiated
+    let mut design = Design::new();
+    design.add(get_and()).unwrap();
+    let other = get_and();
+    other.set_name("and3".to_string());
+    design.add(other).unwrap();
+    assert!(design.get_top().is_none());
+}
+
+#[test]
+fn test_design_errors() {
+    let mut design = get_design();
+    assert!(matches!(
+        design.add(get_and()),
+        Err(Error::DuplicateModule(_))
+    ));
+
+    // The instance has no port `c`
+    let and2 = get_and();
+    let top = get_top(&and2);
+    let and3 = BlackBox::new(
+        "and2".into(),
+        vec!["a".into(), "c".into()],
+        vec!["y".into()],
+    );
+    let a = top.find_net(&"a".into()).unwrap();
+    top.insert_gate(Cell::BlackBox(and3), "u2".into(), &[a.clone(), a])
+        .unwrap();
+    let mut design = Design::new();
+    design.add(top).unwrap();
+    design.add(and2).unwrap();
+    assert!(matches!(design.verify(), Err(Error::PortNotFound(id)) if id == "c".into()));
+
+    // A module that instantiates itself
+    let and2 = get_and();
+    let a = and2.find_net(&"a".into()).unwrap();
+    let cell = Cell::BlackBox(and2.to_blackbox());
+    and2.insert_gate(cell, "u0".into(), &[a.clone(), a])
+        .unwrap();
+    let mut design = Design::new();
+    design.add(and2).unwrap();
+    assert!(matches!(design.order(), Err(Error::RecursiveModules(m)) if m.len() == 1));
+}
+
+/// Renames the module that a black box instantiates
+fn rename(cell: &Cell, name: &Identifier) -> Cell {
+    match cell {
+        Cell::BlackBox(b) => {
+            let mut b = b.clone();
+            b.set_name(name.clone());
+            Cell::BlackBox(b)
+        }
+        c => c.clone(),
+    }
+}
+
+#[test]
+fn test_design_uniquify() {
+    let and2 = get_and();
+    let top = get_top(&and2);
+    let mut cell = and2.to_blackbox();
+    cell.set_parameter(&"WIDTH".into(), Parameter::Integer(2));
+    let a = top.find_net(&"a".into()).unwrap();
+    top.insert_gate(Cell::BlackBox(cell), "u2".into(), &[a.clone(), a])
+        .unwrap()
+        .expose_with_name("z".into());
+    let mut design = Design::new();
+    design.add(top).unwrap();
+    design.add(and2).unwrap();
+
+    assert_eq!(design.uniquify(rename).unwrap(), 1);
+    assert!(design.verify().is_ok());
+    assert_eq!(design.modules().count(), 3);
+    let copy = design.get("and2_1").unwrap();
+    assert_eq!(
+        copy.to_string(),
+        get_and().to_string().replace("and2", "and2_1")
+    );
+    let verilog = design.to_string();
+    assert!(verilog.contains("  and2_1 #(\n    .WIDTH(2)\n  ) u2 ("));
+    assert!(verilog.contains("  and2 u0 ("));
+    assert!(verilog.contains("  and2 u1 ("));
+
+    // Every module is instantiated with a single set of parameters
+    assert_eq!(design.uniquify(rename).unwrap(), 0);
+}
+
+#[test]
+fn test_design_flatten() {
+    let design = get_design();
+    let (flat, paths) = design.flatten('/').unwrap();
+    assert!(flat.verify().is_ok());
+    assert_eq!(*flat.get_name(), "top");
+    let verilog = flat.to_string();
+    assert!(verilog.contains("  AND \\u0/inst_0  ("));
+    assert!(verilog.contains("  AND \\u1/inst_0  ("));
+    assert!(verilog.contains("  IP u_ip ("));
+    assert!(!verilog.contains("and2"));
+
+    let path = |s: &str| s.parse::<HierPath>().unwrap();
+    let id: Identifier = "u1/inst_0".into();
+    assert_eq!(paths[&id], path("top.u1.inst_0"));
+    assert_eq!(flat.get_hier_path(&id, '/').unwrap(), paths[&id]);
+    assert_eq!(paths[&"u0/inst_0_Y".into()], path("top.u0.inst_0_Y"));
+    assert_eq!(paths[&"u_ip".into()], path("top.u_ip"));
+
+    // The second AND gate is driven by the first through the ports of both instances
+    let and = flat.find_net(&"u1/inst_0_Y".into()).unwrap().unwrap();
+    let driver = and.get_driver(0).unwrap();
+    assert_eq!(driver.get_instance_name().unwrap(), "u0/inst_0".into());
+    assert!(flat.find_net(&"u1_y".into()).is_none());
+
+    // An output of a submodule that is wired to its input
+    let wire = Netlist::<Cell>::new("wire".to_string());
+    wire.insert_input("a".into()).expose_with_name("y".into());
+    let top = Netlist::new("top".to_string());
+    let a = top.insert_input("a".into());
+    top.insert_gate(Cell::BlackBox(wire.to_blackbox()), "u0".into(), &[a])
+        .unwrap()
+        .expose_with_name("y".into());
+    let mut design = Design::new();
+    design.add(top).unwrap();
+    design.add(wire).unwrap();
+    let (flat, _) = design.flatten('/').unwrap();
+    assert!(flat.to_string().contains("assign y = a;"));
+
+    let mut design = Design::new();
+    design.add(get_and()).unwrap();
+    design.add(get_and()).unwrap_err();
+    let other = get_and();
+    other.set_name("and3".to_string());
+    design.add(other).unwrap();
+    assert!(matches!(design.flatten('/'), Err(Error::NoTopModule)));
+}
+
+#[test]
+fn test_design_verilog() {
+    let design = get_design();
+    let verilog = design.to_string();
+    let and2 = verilog.find("module and2").unwrap();
+    let top = verilog.find("module top").unwrap();
+    assert!(and2 < top);
+    assert_eq!(verilog.matches("endmodule").count(), 2);
+    assert!(verilog.contains("  and2 u0 ("));
+    assert!(!verilog.contains("(* blackbox *)"));
+
+    let options = VerilogOptions {
+        blackbox_stubs: true,
+        ..Default::default()
+    };
+    let verilog = design.to_verilog(&options);
+    assert_eq!(verilog.matches("(* blackbox *)").count(), 1);
+    assert!(verilog.contains("module IP"));
+    assert_eq!(verilog.matches("module and2").count(), 1);
+    assert!(verilog.ends_with("endmodule\n"));
+}
+
+/// `y` is the conjunction of the `WIDTH` bits of `a`
+fn and_tree(parameters: &[(Identifier, Parameter)]) -> Result<Rc<Netlist<Cell>>, Error> {
+    let width = match parameters.iter().find(|(k, _)| *k == "WIDTH".into()) {
+        Some((_, Parameter::Integer(w))) => *w as usize,
+        Some((k, _)) => return Err(Error::InstantiableError(format!("Invalid {k}"))),
+        None => 2,
+    };
+    let netlist = Netlist::new("and_tree".to_string());
+    let mut y = netlist.insert_input(Net::new_logic(format_id!("a[0]")));
+    for i in 1..width {
+        let a = netlist.insert_input(Net::new_logic(format_id!("a[{i}]")));
+        let and = Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into());
+        y = netlist
+            .insert_gate(Cell::Gate(and), format_id!("inst_{i}"), &[y, a])?
+            .get_output(0);
+    }
+    y.expose_with_name("y".into());
+    Ok(netlist)
+}
+
+/// An instance of `and_tree` with `width` inputs, where `WIDTH` is set to `param`
+fn and_tree_cell(width: usize, param: i64) -> Cell {
+    let inputs = (0..width).map(|i| format_id!("a[{i}]")).collect();
+    let mut cell = BlackBox::new("and_tree".into(), inputs, vec!["y".into()]);
+    cell.set_parameter(&"WIDTH".into(), Parameter::Integer(param));
+    Cell::BlackBox(cell)
+}
+
+/// Renames the module that a black box instantiates, without its parameters
+fn specialize(cell: &Cell, name: &Identifier) -> Cell {
+    match rename(cell, name) {
+        Cell::BlackBox(mut b) => {
+            b.clear_parameters();
+            Cell::BlackBox(b)
+        }
+        c => c,
+    }
+}
+
+#[test]
+fn test_design_elaborate() {
+    let top = Netlist::new("top".to_string());
+    let a: Vec<_> = (0..4)
+        .map(|i| top.insert_input(Net::new_logic(format_id!("a[{i}]"))))
+        .collect();
+    for (name, width) in [("u0", 4), ("u1", 4), ("u2", 2)] {
+        top.insert_gate(and_tree_cell(width, width as i64), name.into(), &a[..width])
+            .unwrap()
+            .expose_with_name(format_id!("{name}_y"));
+    }
+    let mut design = Design::new();
+    design.add(top).unwrap();
+    design.add_generator("and_tree", and_tree).unwrap();
+    assert!(matches!(
+        design.add_generator("top", and_tree),
+        Err(Error::DuplicateModule(_))
+    ));
+
+    assert_eq!(design.elaborate(specialize).unwrap(), 2);
+    assert!(design.verify().is_ok());
+    assert_eq!(
+        design.get("and_tree_1").unwrap().get_input_ports().count(),
+        4
+    );
+    assert_eq!(
+        design.get("and_tree_2").unwrap().get_input_ports().count(),
+        2
+    );
+    let verilog = design.to_string();
+    assert!(verilog.contains("  and_tree_1 u0 ("));
+    assert!(verilog.contains("  and_tree_1 u1 ("));
+    assert!(verilog.contains("  and_tree_2 u2 ("));
+    assert!(!verilog.contains("WIDTH"));
+    let (flat, _) = design.flatten('/').unwrap();
+    assert_eq!(flat.objects().filter(|n| !n.is_an_input()).count(), 7);
+
+    // Every instance is already elaborated
+    assert_eq!(design.elaborate(specialize).unwrap(), 0);
+}
+
+#[test]
+fn test_design_elaborate_errors() {
+    let top = Netlist::new("top".to_string());
+    let a = top.insert_input("a".into());
+    top.insert_gate(and_tree_cell(2, 3), "u0".into(), &[a.clone(), a])
+        .unwrap()
+        .expose_with_name("y".into());
+    let mut design = Design::new();
+    design.add(top).unwrap();
+    design.add_generator("and_tree", and_tree).unwrap();
+    assert!(
+        matches!(design.elaborate(specialize), Err(Error::PortNotFound(id)) if id == "a[2]".into())
+    );
+
+    // The generator fails, and the stub of the black box declares the parameters of the instance
+    let top = Netlist::<Cell>::new("top".to_string());
+    let a = top.insert_input("a".into());
+    let cell = BlackBox::new("and_tree".into(), vec!["a[0]".into()], vec!["y".into()]);
+    let mut cell = Cell::BlackBox(cell);
+    cell.set_parameter(&"WIDTH".into(), Parameter::String("all".to_string()));
+    top.insert_gate(cell, "u0".into(), &[a])
+        .unwrap()
+        .expose_with_name("y".into());
+    let mut design = Design::new();
+    design.add(top).unwrap();
+    design.add_generator("and_tree", and_tree).unwrap();
+    assert!(matches!(
+        design.elaborate(specialize),
+        Err(Error::InstantiableError(_))
+    ));
+    let options = VerilogOptions {
+        blackbox_stubs: true,
+        ..Default::default()
+    };
+    assert!(
+        design
+            .to_verilog(&options)
+            .contains("  output y;\n  parameter WIDTH = \"all\";\nendmodule")
+    );
+}
@@ -0,0 +1,361 @@
+#![cfg(feature = "derive")]
+use safety_net::{
+    attribute::Parameter,
+    circuit::{Evaluate, Identifier, Instantiable, Net},
+    derive::Instantiable,
+    error::Error,
+    logic::Logic,
+    netlist::{
+        DrivenNet, Gate, GateNetlist, NetRef, Netlist,
+        dft::{
+            FaultSite, ScanCell, ScanOptions, ScanOrder, Scoap, StuckAt, fault_list,
+            fault_simulate, insert_scan_chains, testability,
+        },
+    },
+};
+use std::rc::Rc;
+
+/// A flip-flop with any inputs and a single output `Q`
+#[derive(Debug, Clone)]
+struct Dff {
+    name: Identifier,
+    inputs: Vec<Net>,
+    output: Net,
+}
+
+impl Dff {
+    fn new(name: &str, inputs: &[&str]) -> Self {
+        Self {
+            name: name.into(),
+            inputs: inputs.iter().map(|i| Net::new_logic((*i).into())).collect(),
+            output: Net::new_logic("Q".into()),
+        }
+    }
+}
+
+impl Instantiable for Dff {
+    fn get_name(&self) -> &Identifier {
+        &self.name
+    }
+
+    fn get_input_ports(&self) -> impl IntoIterator<Item = &Net> {
+        &self.inputs
+    }
+
+    fn get_output_ports(&self) -> impl IntoIterator<Item = &Net> {
+        std::slice::from_ref(&self.output)
+    }
+
+    fn has_parameter(&self, _id: &Identifier) -> bool {
+        false
+    }
+
+    fn get_parameter(&self, _id: &Identifier) -> Option<Parameter> {
+        None
+    }
+
+    fn set_parameter(&mut self, _id: &Identifier, _val: Parameter) -> Option<Parameter> {
+        None
+    }
+
+    fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)> {
+        std::iter::empty()
+    }
+
+    fn from_constant(_val: Logic) -> Option<Self> {
+        None
+    }
+
+    fn get_constant(&self) -> Option<Logic> {
+        None
+    }
+
+    fn is_seq(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, Instantiable)]
+enum Cell {
+    #[instantiable(constant)]
+    Gate(Gate),
+    Dff(Dff),
+}
+
+/// Registers capture `D` on the next clock edge
+impl Evaluate for Cell {
+    fn eval(&self, inputs: &[Logic]) -> Vec<Logic> {
+        match self {
+            Cell::Gate(g) => g.eval(inputs),
+            Cell::Dff(_) => vec![inputs[0]],
+        }
+    }
+}
+
+fn dff() -> Cell {
+    Cell::Dff(Dff::new("DFF", &["D", "C"]))
+}
+
+fn scan_dff(cell: &Cell) -> Option<ScanCell<Cell>> {
+    match cell {
+        Cell::Dff(ff) if ff.name == "DFF".into() => Some(ScanCell {
+            cell: Cell::Dff(Dff::new("SDFF", &["D", "SI", "SE", "C"])),
+            scan_in: "SI".into(),
+            scan_enable: "SE".into(),
+            scan_out: "Q".into(),
+        }),
+        _ => None,
+    }
+}
+
+/// `a` is registered by `r0`, and-ed with `a` and registered again by `r1`
+fn get_pipeline() -> Rc<Netlist<Cell>> {
+    let netlist = Netlist::new("pipeline".to_string());
+    let a = netlist.insert_input("a".into());
+    let clk = netlist.insert_input("clk".into());
+    let r0 = netlist
+        .insert_gate(dff(), "r0".into(), &[a.clone(), clk.clone()])
+        .unwrap();
+    let and = netlist
+        .insert_gate(
+            Cell::Gate(Gate::new_logical(
+                "AND".into(),
+                vec!["A".into(), "B".into()],
+                "Y".into(),
+            )),
+            "inst_0".into(),
+            &[r0.get_output(0), a],
+        )
+        .unwrap();
+    netlist
+        .insert_gate(dff(), "r1".into(), &[and.get_output(0), clk])
+        .unwrap()
+        .expose_with_name("y".into());
+    netlist
+}
+
+fn names(chains: &[Vec<NetRef<Cell>>]) -> Vec<Vec<String>> {
+    chains
+        .iter()
+        .map(|c| {
+            c.iter()
+                .map(|n| n.get_instance_name().unwrap().to_string())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_scan_chain() {
+    let netlist = get_pipeline();
+    let chains = insert_scan_chains(&netlist, &ScanOptions::default(), scan_dff).unwrap();
+    assert_eq!(names(&chains), [["r0", "r1"]]);
+    drop(chains);
+    assert!(netlist.verify().is_ok());
+
+    let verilog = netlist.to_string();
+    assert!(verilog.contains("input scan_en;"));
+    assert!(verilog.contains("input scan_in;"));
+    assert!(verilog.contains("output scan_out;"));
+    assert!(verilog.contains(
+        "  SDFF r0 (\n    .D(a),\n    .SI(scan_in),\n    .SE(scan_en),\n    .C(clk),\n    .Q(r0_Q)\n  );"
+    ));
+    assert!(verilog.contains(".SI(r0_Q)"));
+    assert!(verilog.contains("assign scan_out = r1_Q;"));
+    assert!(!verilog.contains(" DFF r"));
+
+    // Scan cells are not converted again
+    let chains = insert_scan_chains(&netlist, &ScanOptions::default(), scan_dff).unwrap();
+    assert!(chains.is_empty());
+}
+
+#[test]
+fn test_scan_order() {
+    let scan = |order, chains| {
+        let netlist = get_pipeline();
+        let options = ScanOptions {
+            order,
+            chains,
+            ..Default::default()
+        };
+        let chains = insert_scan_chains(&netlist, &options, scan_dff).unwrap();
+        assert!(netlist.verify().is_ok());
+        names(&chains)
+    };
+    assert_eq!(scan(ScanOrder::Insertion, 1), [["r0", "r1"]]);
+    assert_eq!(scan(ScanOrder::Connectivity, 1), [["r1", "r0"]]);
+    assert_eq!(scan(ScanOrder::Name, 2), [["r0"], ["r1"]]);
+
+    let netlist = get_pipeline();
+    let options = ScanOptions {
+        chains: 2,
+        ..Default::default()
+    };
+    drop(insert_scan_chains(&netlist, &options, scan_dff).unwrap());
+    let verilog = netlist.to_string();
+    assert!(verilog.contains("input scan_in1;"));
+    assert!(verilog.contains("assign scan_out0 = r0_Q;"));
+}
+
+#[test]
+fn test_scan_errors() {
+    let netlist = get_pipeline();
+    let before = netlist.to_string();
+
+    let options = ScanOptions {
+        scan_enable: "clk".to_string(),
+        ..Default::default()
+    };
+    assert!(matches!(
+        insert_scan_chains(&netlist, &options, scan_dff),
+        Err(Error::NonuniqueNets(_))
+    ));
+
+    let missing = |cell: &Cell| {
+        scan_dff(cell).map(|mut scan| {
+            scan.scan_enable = "TE".into();
+            scan
+        })
+    };
+    assert!(matches!(
+        insert_scan_chains(&netlist, &ScanOptions::default(), missing),
+        Err(Error::PortNotFound(_))
+    ));
+    assert_eq!(netlist.to_string(), before);
+}
+
+/// `z = (a & b) | c`
+fn get_and_or() -> Rc<GateNetlist> {
+    let netlist = GateNetlist::new("and_or".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let c = netlist.insert_input("c".into());
+    let and = netlist
+        .insert_gate(
+            Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into()),
+            "inst_0".into(),
+            &[a, b],
+        )
+        .unwrap();
+    netlist
+        .insert_gate(
+            Gate::new_logical("OR".into(), vec!["A".into(), "B".into()], "Y".into()),
+            "inst_1".into(),
+            &[and.get_output(0), c],
+        )
+        .unwrap()
+        .expose_with_name("z".into());
+    netlist
+}
+
+#[test]
+fn test_scoap() {
+    let netlist = get_and_or();
+    let scoap = testability(&netlist).unwrap();
+    let get = |name: &str| {
+        *scoap
+            .get(&netlist.find_net(&name.into()).unwrap().get_id())
+            .unwrap()
+    };
+    let measures = |cc0, cc1, co| Scoap { cc0, cc1, co };
+    assert_eq!(get("a"), measures(1, 1, 4));
+    assert_eq!(get("c"), measures(1, 1, 3));
+    assert_eq!(get("inst_0_Y"), measures(2, 3, 2));
+    assert_eq!(get("inst_1_Y"), measures(4, 2, 0));
+    assert_eq!(get("inst_0_Y").to_string(), "(2, 3) 2");
+
+    // Flip-flops are scanned
+    let netlist = get_pipeline();
+    let scoap = testability(&netlist).unwrap();
+    let get = |name: &str| {
+        *scoap
+            .get(&netlist.find_net(&name.into()).unwrap().get_id())
+            .unwrap()
+    };
+    assert_eq!(get("r0_Q"), measures(1, 1, 2));
+    assert_eq!(get("inst_0_Y"), measures(2, 3, 0));
+}
+
+#[test]
+fn test_fault_list() {
+    let netlist = get_and_or();
+    let faults = fault_list(&netlist);
+    assert_eq!(faults.len(), 10);
+    assert!(faults.iter().all(|f| matches!(f.site, FaultSite::Net(_))));
+
+    // The AND gate now drives two loads
+    let and = netlist.find_net(&"inst_0_Y".into()).unwrap();
+    and.clone().expose_with_name("y".into());
+    let faults = fault_list(&netlist);
+    assert_eq!(faults.len(), 12);
+    let or = netlist.find_net(&"inst_1_Y".into()).unwrap().unwrap();
+    let branch = StuckAt {
+        site: FaultSite::Pin(or.get_id(), 0),
+        value: true,
+    };
+    assert_eq!(faults[11], branch);
+    assert_eq!(branch.to_string(), format!("{}/0 SA1", or.get_id()));
+}
+
+/// Every combination of `a`, `b` and `c`, or only the first `n`
+fn exhaustive<I: Instantiable>(net: &DrivenNet<I>, n: usize) -> u64 {
+    let word = match net.get_identifier().to_string().as_str() {
+        "a" => 0b1111_0000,
+        "b" => 0b1100_1100,
+        _ => 0b1010_1010,
+    };
+    word & !(u64::MAX << n)
+}
+
+#[test]
+fn test_fault_simulation() {
+    let netlist = get_and_or();
+    let faults = fault_list(&netlist);
+    let coverage = fault_simulate(&netlist, &faults, 8, |n, _| exhaustive(n, 8)).unwrap();
+    assert!(coverage.undetected.is_empty());
+    assert_eq!(coverage.coverage(), 1.0);
+
+    // All zeros only detects the stuck-at-1 faults of `c`, the AND gate and the OR gate
+    let coverage = fault_simulate(&netlist, &faults, 1, |n, _| exhaustive(n, 1)).unwrap();
+    assert_eq!(coverage.detected.len(), 3);
+    assert!(coverage.detected.iter().all(|f| f.value));
+    assert_eq!(
+        coverage.to_string(),
+        "faults: 10\ndetected: 3\ncoverage: 30.00%\n"
+    );
+
+    // The branches of the AND gate are observed separately
+    netlist
+        .find_net(&"inst_0_Y".into())
+        .unwrap()
+        .expose_with_name("y".into());
+    let faults = fault_list(&netlist);
+    let coverage = fault_simulate(&netlist, &faults, 8, |n, _| exhaustive(n, 8)).unwrap();
+    assert_eq!(coverage.detected.len(), 12);
+}
+
+#[test]
+fn test_fault_simulation_scanned() {
+    let netlist = get_pipeline();
+    let faults = fault_list(&netlist);
+    let coverage = fault_simulate(&netlist, &faults, 8, |n, _| exhaustive(n, 8)).unwrap();
+    assert_eq!(coverage.coverage(), 1.0);
+
+    // The branch of `a` into `r0` only reaches the scanned input of `r0`
+    let r0 = netlist.find_net(&"r0_Q".into()).unwrap().unwrap();
+    let branch = StuckAt {
+        site: FaultSite::Pin(r0.get_id(), 0),
+        value: false,
+    };
+    assert!(faults.contains(&branch));
+    let coverage = fault_simulate(&netlist, &[branch], 8, |n, _| exhaustive(n, 8)).unwrap();
+    assert_eq!(coverage.detected, [branch]);
+
+    // Faults on pins that do not exist are never detected
+    let stale = StuckAt {
+        site: FaultSite::Pin(r0.get_id(), 9),
+        value: true,
+    };
+    let coverage = fault_simulate(&netlist, &[stale], 8, |n, _| exhaustive(n, 8)).unwrap();
+    assert_eq!(coverage.undetected, [stale]);
+}
@@ -0,0 +1,123 @@
+use safety_net::circuit::Instantiable;
+use safety_net::error::Error;
+use safety_net::logic::Logic;
+use safety_net::netlist::opt::propagate_constants;
+use safety_net::netlist::{Gate, GateNetlist, NetRef, Netlist};
+use std::rc::Rc;
+
+fn gate(name: &str, inputs: &[&str]) -> Gate {
+    let ports = inputs.iter().map(|p| (*p).into()).collect();
+    Gate::new_logical(name.into(), ports, "Y".into())
+}
+
+/// `y = (a & b) | 0` next to the spare `s0`, and `z = b | 0` next to the spares `s1` and `s2`,
+/// each region with its own tie-off cell
+fn design() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("eco".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let tie0 = netlist
+        .insert_constant(Logic::False, "tie0".into())
+        .unwrap();
+    let tie1 = netlist
+        .insert_constant(Logic::False, "tie1".into())
+        .unwrap();
+    let g0 = netlist
+        .insert_gate(gate("AND", &["A", "B"]), "g0".into(), &[a, b.clone()])
+        .unwrap();
+    netlist
+        .insert_gate(
+            gate("OR", &["A", "B"]),
+            "g1".into(),
+            &[g0.get_output(0), tie0.clone()],
+        )
+        .unwrap()
+        .expose_with_name("y".into());
+    netlist
+        .insert_gate(gate("OR", &["A", "B"]), "g2".into(), &[b, tie1.clone()])
+        .unwrap()
+        .expose_with_name("z".into());
+    for (name, cell, tie) in [
+        ("s0", gate("AND", &["A", "B"]), &tie0),
+        ("s1", gate("INV", &["A"]), &tie1),
+        ("s2", gate("AND", &["A", "B"]), &tie1),
+    ] {
+        let ties = vec![tie.clone(); cell.get_input_ports().into_iter().count()];
+        let spare = netlist.insert_gate(cell, name.into(), &ties).unwrap();
+        netlist.mark_spare(&spare);
+    }
+    netlist
+}
+
+fn find(netlist: &GateNetlist, name: &str) -> NetRef<Gate> {
+    netlist
+        .objects()
+        .find(|o| o.get_instance_name().is_some_and(|n| n.to_string() == name))
+        .unwrap()
+}
+
+fn names(spares: &[(NetRef<Gate>, usize)]) -> Vec<(String, usize)> {
+    spares
+        .iter()
+        .map(|(s, d)| (s.get_instance_name().unwrap().to_string(), *d))
+        .collect()
+}
+
+#[test]
+fn spares_are_kept() {
+    let netlist = design();
+    assert_eq!(netlist.spares().count(), 3);
+    assert!(!netlist.clean().unwrap());
+    assert_eq!(propagate_constants(&netlist).unwrap(), 0);
+    assert_eq!(netlist.spares().count(), 3);
+    assert!(netlist.verify().is_ok());
+}
+
+#[test]
+fn nearest_spares() {
+    let netlist = design();
+    let g0 = find(&netlist, "g0");
+    let all = netlist.nearest_spares(&g0, usize::MAX, |_| true);
+    assert_eq!(
+        names(&all),
+        [
+            ("s0".to_string(), 3),
+            ("s1".to_string(), 4),
+            ("s2".to_string(), 4)
+        ]
+    );
+    let ands = netlist.nearest_spares(&g0, 3, |g| g.get_gate_name().to_string() == "AND");
+    assert_eq!(names(&ands), [("s0".to_string(), 3)]);
+}
+
+#[test]
+fn use_spare() {
+    let netlist = design();
+    let g0 = find(&netlist, "g0");
+    let (spare, _) = netlist
+        .nearest_spares(&g0, usize::MAX, |g| {
+            g.get_input_ports().into_iter().count() == 1
+        })
+        .remove(0);
+    let a = netlist.inputs().next().unwrap();
+
+    assert!(matches!(
+        netlist.use_spare(&spare, &[(&"B".into(), a.clone())]),
+        Err(Error::PortNotFound(_))
+    ));
+    assert!(netlist.is_spare(&spare));
+    assert!(matches!(
+        netlist.use_spare(&g0, &[(&"A".into(), a.clone())]),
+        Err(Error::InstantiableError(_))
+    ));
+
+    // The spare inverter computes `!a`, and its tie-off is left without loads
+    netlist
+        .use_spare(&spare, &[(&"A".into(), a.clone())])
+        .unwrap();
+    assert!(!netlist.is_spare(&spare));
+    assert_eq!(spare.get_driver(0).unwrap(), a.unwrap());
+    spare.get_output(0).expose_with_name("w".into());
+    assert!(netlist.verify().is_ok());
+    assert_eq!(netlist.spares().count(), 2);
+}
@@ -1,7 +1,18 @@
 use safety_net::assert_verilog_eq;
+use safety_net::attribute::DONT_TOUCH;
+use safety_net::error::Error;
+use safety_net::logic::Logic;
+use safety_net::netlist::CleanOptions;
+use safety_net::netlist::DrivenNet;
 use safety_net::netlist::Gate;
 use safety_net::netlist::GateNetlist;
 use safety_net::netlist::Netlist;
+use safety_net::netlist::Pin;
+use safety_net::netlist::RemovePolicy;
+use safety_net::netlist::VerifyOptions;
+use safety_net::netlist::annotation::{AnnotationMap, NetId};
+use safety_net::netlist::testing::{assert_invariants, check_no_dangling};
+use std::cell::RefCell;
 use std::rc::Rc;
 
 fn and_gate() -> Gate {
@@ -52,6 +63,201 @@ fn test_clean() {
     assert!(!netlist.clean().unwrap());
 }
 
+#[test]
+fn test_clean_with() {
+    let netlist = get_simple_example();
+    let inputs: Vec<_> = netlist.inputs().collect();
+    // A chain of two unused gates, a kept gate and a gate driving a debug net
+    let inst_1 = netlist
+        .insert_gate(and_gate(), "inst_1".into(), &inputs)
+        .unwrap();
+    netlist
+        .insert_gate(
+            or_gate(),
+            "inst_2".into(),
+            &[inst_1.get_output(0), inputs[0].clone()],
+        )
+        .unwrap();
+    netlist
+        .insert_gate(and_gate(), "inst_3".into(), &inputs)
+        .unwrap()
+        .set_attribute("keep".into());
+    netlist
+        .insert_gate(or_gate(), "dbg_0".into(), &inputs)
+        .unwrap();
+    drop((inputs, inst_1));
+
+    let options = CleanOptions {
+        keep_attributes: vec!["keep".into()],
+        keep_nets: vec!["dbg_*".into()],
+        dry_run: true,
+    };
+    let report = netlist.clean_with(&options).unwrap();
+    assert_eq!(report.removed, ["inst_1".into(), "inst_2".into()]);
+    assert_eq!(netlist.objects().count(), 7);
+
+    let options = CleanOptions {
+        dry_run: false,
+        ..options
+    };
+    assert_eq!(netlist.clean_with(&options).unwrap(), report);
+    assert_eq!(netlist.objects().count(), 5);
+    assert!(matches!(
+        check_no_dangling(&netlist),
+        Err(Error::DanglingNets(nets)) if nets.len() == 2
+    ));
+    assert!(netlist.clean_with(&options).unwrap().removed.is_empty());
+
+    // Without the policy, the rest goes too
+    let report = netlist.clean_with(&CleanOptions::default()).unwrap();
+    assert_eq!(report.removed, ["inst_3".into(), "dbg_0".into()]);
+    assert!(!netlist.clean().unwrap());
+    assert_invariants(&netlist);
+}
+
+#[test]
+fn test_dont_touch() {
+    let netlist = get_simple_example();
+    let inputs: Vec<_> = netlist.inputs().collect();
+    netlist
+        .insert_gate(and_gate(), "inst_1".into(), &inputs)
+        .unwrap()
+        .set_attribute(DONT_TOUCH.into());
+    let or = netlist
+        .insert_gate(or_gate(), "inst_2".into(), &inputs)
+        .unwrap()
+        .expose_with_name("z".into());
+    drop(inputs);
+
+    // Protected nodes survive cleaning
+    assert!(!netlist.clean().unwrap());
+    assert_eq!(netlist.objects().count(), 5);
+
+    // Reconnecting the input of a protected node is rejected
+    let a = netlist.inputs().next().unwrap();
+    assert!(matches!(
+        netlist.replace_net_uses(a, &or.get_output(0)),
+        Err(Error::DontTouch(_))
+    ));
+
+    // So is disconnecting a node driving a protected net
+    let inst_0 = netlist.find_net(&"inst_0_Y".into()).unwrap();
+    netlist.set_net_attribute(&inst_0.as_net(), DONT_TOUCH.into());
+    assert!(matches!(
+        inst_0.unwrap().delete_uses(),
+        Err(Error::DontTouch(_))
+    ));
+
+    // Unless enforcement is turned off
+    netlist.enforce_dont_touch(false);
+    assert!(netlist.clean().unwrap());
+    assert_eq!(netlist.objects().count(), 4);
+}
+
+#[test]
+fn test_stable_ids() {
+    let netlist = get_simple_example();
+    let inputs: Vec<_> = netlist.inputs().collect();
+    let dead = netlist
+        .insert_gate(or_gate(), "inst_1".into(), &inputs)
+        .unwrap();
+    let dup = netlist
+        .insert_gate(two_out_gate(), "inst_2".into(), &inputs[..1])
+        .unwrap();
+    dup.get_output(1).expose_with_name("z".into());
+
+    let mut placement: AnnotationMap<(usize, usize)> = AnnotationMap::new();
+    let mut slack: AnnotationMap<f64, NetId> = AnnotationMap::new();
+    for (i, obj) in netlist.objects().enumerate() {
+        placement.insert(obj.get_id(), (i, 0));
+    }
+    slack.insert(dup.get_output(1).get_id(), 0.5);
+    let (dead_id, dup_id) = (dead.get_id(), dup.get_id());
+    assert_ne!(dead_id, dup_id);
+    drop((inputs, dead, dup));
+
+    // Cleaning shifts positions, but not identifiers
+    assert!(netlist.clean().unwrap());
+    assert!(netlist.find_object(dead_id).is_none());
+    let dup = netlist.find_object(dup_id).unwrap();
+    assert_eq!(dup.get_instance_name(), Some("inst_2".into()));
+    assert_eq!(placement.get(&dup_id), Some(&(4, 0)));
+    let z = netlist.find_net_by_id(slack.iter().next().unwrap().0.to_owned());
+    assert_eq!(z, Some(dup.get_output(1)));
+    assert!(
+        netlist
+            .find_net_by_id(NetId {
+                object: dup_id,
+                output: 2
+            })
+            .is_none()
+    );
+
+    assert_eq!(placement.len(), 5);
+    placement.retain_live(&netlist);
+    assert_eq!(placement.len(), 4);
+    assert!(!placement.contains_key(&dead_id));
+
+    // New objects never reuse identifiers
+    let a = netlist.inputs().next().unwrap();
+    let new = netlist
+        .insert_gate(and_gate(), "inst_3".into(), &[a.clone(), a])
+        .unwrap();
+    assert!(new.get_id() > dup_id);
+}
+
+#[test]
+fn test_observers() {
+    let netlist = get_simple_example();
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let log = events.clone();
+    netlist.on_insert(move |n| {
+        log.borrow_mut()
+            .push(format!("insert {}", n.get_identifier()))
+    });
+    let log = events.clone();
+    let remove = netlist.on_remove(move |id| log.borrow_mut().push(format!("remove {id}")));
+    let log = events.clone();
+    netlist.on_reconnect(move |p| {
+        let driver = p.get_driver().map(|d| d.get_identifier().to_string());
+        log.borrow_mut()
+            .push(format!("reconnect {} {driver:?}", p.get_port()))
+    });
+
+    let inputs: Vec<_> = netlist.inputs().collect();
+    let or = netlist
+        .insert_gate(or_gate(), "inst_1".into(), &inputs)
+        .unwrap();
+    let or_id = or.get_id();
+    or.get_input(1).connect(inputs[0].clone());
+    or.get_input(0).disconnect();
+    drop((inputs, or));
+    assert!(netlist.clean().unwrap());
+
+    // Replacing the uses of `a` reconnects the AND gate
+    let inv = Gate::new_logical("INV".into(), vec!["I".into()], "O".into());
+    let a = netlist.inputs().next().unwrap();
+    let inverted = netlist
+        .insert_gate(inv, "inst_2".into(), std::slice::from_ref(&a))
+        .unwrap();
+    netlist.replace_net_uses(a, &inverted.into()).unwrap();
+
+    assert!(netlist.remove_observer(remove));
+    assert!(!netlist.remove_observer(remove));
+    assert_eq!(
+        *events.borrow(),
+        vec![
+            "insert inst_1_Y".to_string(),
+            "reconnect B Some(\"a\")".to_string(),
+            "reconnect A None".to_string(),
+            format!("remove {or_id}"),
+            "insert inst_2_O".to_string(),
+            "reconnect A Some(\"inst_2_O\")".to_string(),
+            "reconnect I Some(\"inst_2_O\")".to_string(),
+        ]
+    );
+}
+
 #[test]
 fn test_replace() {
     let netlist = get_simple_example();
@@ -321,3 +527,308 @@ fn test_replace_multiple_multiple() {
           endmodule"
     );
 }
+
+#[test]
+fn test_output_ports() {
+    let netlist = Netlist::new("example".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+
+    netlist.insert_output("y".into()).unwrap();
+    assert!(matches!(
+        netlist.insert_output("a".into()),
+        Err(Error::NonuniqueNets(_))
+    ));
+    assert!(netlist.get_output_driver(&"y".into()).is_none());
+    assert!(netlist.outputs().is_empty());
+
+    let and_out = netlist
+        .insert_gate(and_gate(), "inst_0".into(), &[a.clone(), b])
+        .unwrap()
+        .get_output(0);
+    netlist.drive_output(&"y".into(), and_out.clone()).unwrap();
+    and_out.clone().expose_with_name("z".into());
+    assert!(matches!(
+        netlist.drive_output(&"w".into(), and_out.clone()),
+        Err(Error::PortNotFound(_))
+    ));
+    assert_eq!(
+        netlist.get_output_driver(&"y".into()),
+        Some(and_out.clone())
+    );
+    assert_eq!(netlist.outputs().len(), 2);
+    assert!(netlist.verify().is_ok());
+
+    // Both ports follow the driver when it is replaced
+    let or_out = netlist
+        .insert_gate(or_gate(), "inst_1".into(), &[a.clone(), a])
+        .unwrap()
+        .get_output(0);
+    netlist.replace_net_uses(and_out, &or_out).unwrap();
+    assert_eq!(netlist.get_output_driver(&"z".into()), Some(or_out.clone()));
+
+    assert_eq!(
+        netlist.disconnect_output(&"y".into()).unwrap(),
+        Some(or_out)
+    );
+    assert!(netlist.remove_output(&"z".into()).is_ok());
+    assert!(netlist.remove_output(&"z".into()).is_err());
+    assert_verilog_eq!(
+        netlist.to_string(),
+        "module example (
+          a,
+          b,
+          y
+        );
+          input a;
+          wire a;
+          input b;
+          wire b;
+          output y;
+          wire y;
+          wire inst_0_Y;
+          wire inst_1_Y;
+          AND inst_0 (
+            .A(a),
+            .B(b),
+            .Y(inst_0_Y)
+          );
+          OR inst_1 (
+            .A(a),
+            .B(a),
+            .Y(inst_1_Y)
+          );
+        endmodule"
+    );
+}
+
+#[test]
+fn test_insert_gate_named() {
+    let netlist = get_simple_example();
+    let a = netlist.find_net(&"a".into()).unwrap();
+    let b = netlist.find_net(&"b".into()).unwrap();
+    let mux = Gate::new_logical(
+        "MUX".into(),
+        vec!["S".into(), "A".into(), "B".into()],
+        "Y".into(),
+    );
+
+    // Connections are matched by name, not by position
+    let inst = netlist
+        .insert_gate_named(
+            mux.clone(),
+            "inst_1".into(),
+            &[
+                (&"B".into(), a.clone().into()),
+                (&"S".into(), b.clone().into()),
+                (&"A".into(), Pin::Unconnected),
+            ],
+        )
+        .unwrap();
+    assert_eq!(inst.get_input(0).get_driver(), Some(b.clone()));
+    assert!(inst.get_input(1).get_driver().is_none());
+    assert_eq!(inst.get_input(2).get_driver(), Some(a.clone()));
+
+    let missing = netlist.insert_gate_named(
+        mux.clone(),
+        "inst_2".into(),
+        &[(&"S".into(), a.clone().into())],
+    );
+    assert!(matches!(missing, Err(Error::UnconnectedInputs(ports)) if ports.len() == 2));
+    let extra = netlist.insert_gate_named(
+        mux.clone(),
+        "inst_2".into(),
+        &[(&"C".into(), a.clone().into())],
+    );
+    assert!(matches!(extra, Err(Error::PortNotFound(_))));
+    let twice = netlist.insert_gate_named(
+        mux,
+        "inst_2".into(),
+        &[(&"S".into(), a.clone().into()), (&"S".into(), b.into())],
+    );
+    assert!(matches!(twice, Err(Error::DuplicateConnection(_))));
+    assert_eq!(netlist.objects().count(), 4);
+}
+
+#[test]
+fn test_deferred_hookup() {
+    let netlist = get_simple_example();
+    let a = netlist.find_net(&"a".into()).unwrap();
+    let inst = netlist
+        .insert_gate_disconnected(and_gate(), "inst_1".into())
+        .expose_with_name("z".into());
+    assert!(!inst.is_fully_connected());
+    assert_eq!(inst.unconnected_inputs().count(), 2);
+
+    // Dangling pins are only flagged on request
+    let strict = VerifyOptions {
+        deny_unconnected_inputs: true,
+        ..VerifyOptions::default()
+    };
+    assert!(netlist.verify().is_ok());
+    assert!(matches!(
+        netlist.verify_with(&strict),
+        Err(Error::UnconnectedInputs(ports)) if ports.len() == 2
+    ));
+
+    for input in inst.unconnected_inputs().collect::<Vec<_>>() {
+        input.connect(a.clone());
+    }
+    assert!(inst.is_fully_connected());
+    assert_eq!(inst.unconnected_inputs().count(), 0);
+    assert!(netlist.verify_with(&strict).is_ok());
+}
+
+#[test]
+fn test_reconnect_pin() {
+    let netlist = get_simple_example();
+    let a = netlist.find_net(&"a".into()).unwrap();
+    let b = netlist.find_net(&"b".into()).unwrap();
+    let inst_1 = netlist
+        .insert_gate(or_gate(), "inst_1".into(), &[a.clone(), a.clone()])
+        .unwrap()
+        .expose_with_name("z".into());
+
+    // Only the rewired pin changes driver
+    let pin = inst_1.find_input(&"B".into()).unwrap();
+    assert_eq!(pin.reconnect(b.clone()), Some(a.clone()));
+    assert_eq!(inst_1.get_input(0).get_driver(), Some(a.clone()));
+    assert_eq!(pin.get_driver(), Some(b.clone()));
+    let inst_0 = netlist.find_net(&"inst_0_Y".into()).unwrap().unwrap();
+    assert_eq!(inst_0.get_input(0).get_driver(), Some(a.clone()));
+
+    assert_eq!(pin.disconnect(), Some(b.clone()));
+    assert_eq!(pin.reconnect(a.clone()), None);
+    assert_eq!(pin.get_driver(), Some(a));
+    assert!(netlist.verify().is_ok());
+}
+
+#[test]
+fn test_remove_instance() {
+    let netlist = get_simple_example();
+    let a = netlist.find_net(&"a".into()).unwrap();
+    let buf = Gate::new_logical("BUF".into(), vec!["A".into()], "Y".into());
+    let buf = netlist
+        .insert_gate(buf, "inst_1".into(), std::slice::from_ref(&a))
+        .unwrap();
+    let or = netlist
+        .insert_gate(or_gate(), "inst_2".into(), &[buf.get_output(0), a.clone()])
+        .unwrap()
+        .expose_with_name("z".into());
+
+    // The buffer is still in use
+    assert!(matches!(
+        netlist.remove_instance(buf.clone(), RemovePolicy::Reject),
+        Err(Error::DanglingReference(_))
+    ));
+    drop(buf);
+    let buf = netlist.find_net(&"inst_1_Y".into()).unwrap().unwrap();
+    netlist
+        .remove_instance(buf, RemovePolicy::StitchThrough)
+        .unwrap();
+    assert_eq!(or.get_input(0).get_driver(), Some(a.clone()));
+    assert_eq!(netlist.objects().count(), 4);
+    assert!(netlist.verify().is_ok());
+
+    // Stitching needs a single input
+    drop(or);
+    let or = || netlist.find_net(&"inst_2_Y".into()).unwrap().unwrap();
+    assert!(matches!(
+        netlist.remove_instance(or(), RemovePolicy::StitchThrough),
+        Err(Error::ArgumentMismatch(1, 2))
+    ));
+
+    // The output port z is tied to ground instead
+    netlist
+        .remove_instance(or(), RemovePolicy::Tie(Logic::False))
+        .unwrap();
+    assert!(netlist.verify().is_ok());
+    assert!(netlist.to_string().contains("assign z = 1'b0;"));
+    assert!(netlist.find_net(&"inst_2_Y".into()).is_none());
+}
+
+#[test]
+fn test_remove_instance_without_uses() {
+    let netlist = get_simple_example();
+    let a = netlist.find_net(&"a".into()).unwrap();
+    let buf = Gate::new_logical("BUF".into(), vec!["A".into()], "Y".into());
+    let buf = netlist
+        .insert_gate(buf, "inst_1".into(), std::slice::from_ref(&a))
+        .unwrap();
+
+    // An unused instance is removed without tying anything
+    let count = netlist.objects().count();
+    netlist
+        .remove_instance(buf, RemovePolicy::Tie(Logic::True))
+        .unwrap();
+    assert_eq!(netlist.objects().count(), count - 1);
+    assert!(!netlist.to_string().contains("1'b1"));
+
+    // A buffer that drives its own input cannot be stitched through
+    let buf = Gate::new_logical("BUF".into(), vec!["A".into()], "Y".into());
+    let buf = netlist
+        .insert_gate(buf, "inst_2".into(), std::slice::from_ref(&a))
+        .unwrap();
+    buf.get_input(0).reconnect(buf.get_output(0));
+    let buf = buf.expose_with_name("z".into());
+    assert!(matches!(
+        netlist.remove_instance(buf, RemovePolicy::StitchThrough),
+        Err(Error::CycleDetected(_))
+    ));
+    assert!(netlist.find_net(&"inst_2_Y".into()).is_some());
+}
+
+#[test]
+fn test_use_lists() {
+    let netlist = get_simple_example();
+    let a = netlist.find_net(&"a".into()).unwrap();
+    let b = netlist.find_net(&"b".into()).unwrap();
+    let names = |net: &DrivenNet<Gate>| {
+        netlist
+            .get_uses(net)
+            .into_iter()
+            .map(|p| format!("{}/{}", p.clone().unwrap().get_instance_name().unwrap(), p))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&a), ["inst_0/A"]);
+
+    // Insertion and reconnection update the readers
+    let dup = netlist
+        .insert_gate(two_out_gate(), "dup".into(), std::slice::from_ref(&a))
+        .unwrap();
+    let or = netlist
+        .insert_gate(or_gate(), "inst_1".into(), &[dup.get_output(1), a.clone()])
+        .unwrap()
+        .expose_with_name("z".into());
+    assert_eq!(names(&a), ["inst_0/A", "dup/I", "inst_1/B"]);
+    assert_eq!(names(&dup.get_output(1)), ["inst_1/A"]);
+    assert!(names(&dup.get_output(0)).is_empty());
+    or.get_input(1).reconnect(b.clone());
+    assert_eq!(names(&b), ["inst_0/B", "inst_1/B"]);
+    or.get_input(1).disconnect();
+    assert_eq!(names(&b), ["inst_0/B"]);
+    or.get_input(1).connect(dup.get_output(0));
+    assert_invariants(&netlist);
+
+    // Replacing moves the readers onto the new driver
+    drop(dup);
+    let dup = |i| {
+        netlist
+            .find_net(&format!("dup_O{i}").as_str().into())
+            .unwrap()
+    };
+    netlist.replace_net_uses(dup(1), &dup(0)).unwrap();
+    assert_eq!(names(&dup(0)), ["inst_1/A", "inst_1/B"]);
+    assert!(names(&dup(1)).is_empty());
+    assert_invariants(&netlist);
+
+    // Deleting and cleaning up leave no stale readers
+    let dup = dup(0).unwrap();
+    netlist.delete_net_uses(dup).unwrap();
+    assert!(or.get_input(0).get_driver().is_none());
+    or.get_input(0).connect(a.clone());
+    or.get_input(1).connect(b.clone());
+    netlist.clean().unwrap();
+    assert_eq!(names(&a), ["inst_0/A", "inst_1/A"]);
+    assert_invariants(&netlist);
+}
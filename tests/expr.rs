@@ -0,0 +1,76 @@
+use safety_net::circuit::Identifier;
+use safety_net::error::Error;
+use safety_net::logic::Logic;
+use safety_net::netlist::GateNetlist;
+use safety_net::netlist::expr::Expr;
+use safety_net::netlist::sim::Simulator;
+
+/// The value of `a`, `b` and `c` in each of the 8 patterns
+fn pattern(name: &Identifier) -> u64 {
+    match name.to_string().as_str() {
+        "a" => 0b1111_0000,
+        "b" => 0b1100_1100,
+        _ => 0b1010_1010,
+    }
+}
+
+#[test]
+fn test_expr() {
+    let (a, b, c) = (Expr::var("a"), Expr::var("b"), Expr::var("c"));
+    let expr = Expr::and(a.clone(), !b.clone()) | Expr::mux(c.clone(), a.clone(), b.clone() ^ c);
+    assert_eq!(expr.to_string(), "((a & ~b) | (c ? a : (b ^ c)))");
+    assert_eq!(expr.vars(), [&"a".into(), &"b".into(), &"c".into()]);
+
+    let value = |name: &Identifier| match name.to_string().as_str() {
+        "a" => Logic::True,
+        "b" => Logic::False,
+        _ => Logic::X,
+    };
+    assert_eq!(expr.eval(&value), Logic::True);
+    assert_eq!((b & a).eval(&value), Logic::False);
+    assert_eq!(
+        Expr::mux(Expr::var("c"), Expr::constant(true), Expr::var("a")).eval(&value),
+        Logic::True
+    );
+}
+
+#[test]
+fn test_insert_expr() {
+    let netlist = GateNetlist::new("expr".to_string());
+    for name in ["a", "b", "c"] {
+        netlist.insert_input(name.into());
+    }
+    let (a, b, c) = (Expr::var("a"), Expr::var("b"), Expr::var("c"));
+    // `!b` is shared by both operands of the OR gate
+    let expr = (a.clone() & !b.clone()) | Expr::mux(c.clone(), !b, a ^ c);
+    netlist
+        .insert_expr(&expr, "dec".into())
+        .unwrap()
+        .expose_with_name("y".into());
+    assert!(netlist.verify().is_ok());
+    assert_eq!(netlist.objects().count(), 3 + 5);
+
+    let verilog = netlist.to_string();
+    assert!(verilog.contains("  INV dec_0 (\n    .A(b),\n    .Y(dec_0_Y)\n  );"));
+    assert!(verilog.contains("MUX dec_3 (\n    .S(c),\n    .A(dec_2_Y),\n    .B(dec_0_Y),"));
+
+    let mut sim = Simulator::new(&netlist).unwrap();
+    sim.run(|n| pattern(&n.get_identifier()));
+    let (a, b, c) = (0b1111_0000u64, 0b1100_1100u64, 0b1010_1010u64);
+    let expected = (a & !b) | (c & !b) | (!c & (a ^ c));
+    assert_eq!(
+        sim.get_word(&netlist.outputs()[0].0) & 0xff,
+        expected & 0xff
+    );
+
+    // Constants are drivers of their own
+    let one = netlist
+        .insert_expr(&Expr::constant(true), "one".into())
+        .unwrap();
+    assert_eq!(one.get_identifier(), "one_0_Y".into());
+
+    assert!(matches!(
+        netlist.insert_expr(&Expr::var("d"), "missing".into()),
+        Err(Error::NetNotFound(_))
+    ));
+}
@@ -0,0 +1,125 @@
+use safety_net::{
+    circuit::Net,
+    netlist::{Gate, GateNetlist, Netlist},
+};
+use std::rc::Rc;
+
+fn gate(name: &str, inputs: &[&str]) -> Gate {
+    Gate::new_logical(
+        name.into(),
+        inputs.iter().map(|p| (*p).into()).collect(),
+        "Y".into(),
+    )
+}
+
+/// `y = (a & b) | !c`, with the names of `names` and the gates inserted in `order`
+fn circuit(names: [&str; 6], reversed: bool) -> Rc<GateNetlist> {
+    let netlist = Netlist::new(names[0].to_string());
+    let inputs: Vec<_> = names[1..4]
+        .iter()
+        .map(|n| netlist.insert_input(Net::new_logic((*n).into())))
+        .collect();
+    let (and, inv) = if reversed {
+        let inv = netlist.insert_gate(gate("INV", &["A"]), names[5].into(), &[inputs[2].clone()]);
+        let and = netlist.insert_gate(
+            gate("AND", &["A", "B"]),
+            names[4].into(),
+            &[inputs[0].clone(), inputs[1].clone()],
+        );
+        (and.unwrap(), inv.unwrap())
+    } else {
+        let and = netlist.insert_gate(
+            gate("AND", &["A", "B"]),
+            names[4].into(),
+            &[inputs[0].clone(), inputs[1].clone()],
+        );
+        let inv = netlist.insert_gate(gate("INV", &["A"]), names[5].into(), &[inputs[2].clone()]);
+        (and.unwrap(), inv.unwrap())
+    };
+    netlist
+        .insert_gate(
+            gate("OR", &["A", "B"]),
+            "or".into(),
+            &[and.get_output(0), inv.get_output(0)],
+        )
+        .unwrap()
+        .expose_with_name("y".into());
+    netlist
+}
+
+#[test]
+fn names_and_order_do_not_matter() {
+    let netlist = circuit(["top", "a", "b", "c", "g0", "g1"], false);
+    let fingerprint = netlist.fingerprint();
+    assert_eq!(fingerprint.to_string().len(), 16);
+    assert_eq!(netlist.fingerprint(), fingerprint);
+    let renamed = circuit(["other", "x", "y", "z", "u0", "u1"], true);
+    assert_eq!(renamed.fingerprint(), fingerprint);
+    netlist
+        .instances()
+        .next()
+        .unwrap()
+        .insert_attribute("keep".to_string(), "yes");
+    assert_eq!(netlist.fingerprint(), fingerprint);
+}
+
+#[test]
+fn structure_matters() {
+    let netlist = circuit(["top", "a", "b", "c", "g0", "g1"], false);
+    let fingerprint = netlist.fingerprint();
+
+    // Another cell type
+    let changed = circuit(["top", "a", "b", "c", "g0", "g1"], false);
+    let or = changed.instances().last().unwrap();
+    *or.get_instance_type_mut().unwrap() = gate("XOR", &["A", "B"]);
+    assert_ne!(changed.fingerprint(), fingerprint);
+
+    // The same cells on swapped ports of the OR gate
+    let swapped = circuit(["top", "a", "b", "c", "g0", "g1"], false);
+    let or = swapped.instances().last().unwrap();
+    let (a, b) = (or.get_input(0), or.get_input(1));
+    let (da, db) = (a.disconnect().unwrap(), b.disconnect().unwrap());
+    db.connect(a);
+    da.connect(b);
+    assert_ne!(swapped.fingerprint(), fingerprint);
+
+    // An unused gate
+    let extra = circuit(["top", "a", "b", "c", "g0", "g1"], false);
+    let a = extra.inputs().next().unwrap();
+    extra
+        .insert_gate(gate("INV", &["A"]), "spare".into(), &[a])
+        .unwrap();
+    assert_ne!(extra.fingerprint(), fingerprint);
+    extra.clean().unwrap();
+    assert_eq!(extra.fingerprint(), fingerprint);
+}
+
+/// A ring of `n` inverters, exposing the output of the first
+fn ring(netlist: &Rc<GateNetlist>, n: usize, prefix: &str) {
+    let invs: Vec<_> = (0..n)
+        .map(|i| {
+            netlist.insert_gate_disconnected(gate("INV", &["A"]), format!("{prefix}{i}").into())
+        })
+        .collect();
+    for i in 0..n {
+        invs[i]
+            .get_output(0)
+            .connect(invs[(i + 1) % n].get_input(0));
+    }
+}
+
+#[test]
+fn refinement_cannot_split_regular_structures() {
+    // The documented collision: every inverter sees the same neighborhood in both netlists
+    let one = Netlist::new("one".to_string());
+    ring(&one, 8, "a");
+    let two = Netlist::new("two".to_string());
+    ring(&two, 4, "a");
+    ring(&two, 4, "b");
+    assert_eq!(one.fingerprint(), two.fingerprint());
+
+    let three = Netlist::new("three".to_string());
+    ring(&three, 4, "a");
+    ring(&three, 3, "b");
+    assert_ne!(one.fingerprint(), three.fingerprint());
+}
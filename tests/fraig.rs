@@ -0,0 +1,181 @@
+use safety_net::{
+    attribute::DONT_TOUCH,
+    netlist::{
+        DrivenNet, Gate, GateNetlist, Netlist,
+        opt::{FraigOptions, fraig, fraig_with},
+        testing::check_equivalent,
+    },
+};
+use std::rc::Rc;
+
+/// The inputs named `x0`, `x1`, ...
+fn inputs(netlist: &Rc<GateNetlist>, n: usize) -> Vec<DrivenNet<Gate>> {
+    (0..n)
+        .map(|i| netlist.insert_input(format!("x{i}").as_str().into()))
+        .collect()
+}
+
+/// `!(x0 & x1)` as a NAND and by De Morgan's law, and `x0 ^ x1` as an XOR and as a sum of products
+fn de_morgan() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("de_morgan".to_string());
+    let x = inputs(&netlist, 2);
+    let and = netlist.and(&x[0], &x[1]).unwrap();
+    let nand = netlist.not(&and).unwrap();
+    let (n0, n1) = (netlist.not(&x[0]).unwrap(), netlist.not(&x[1]).unwrap());
+    let or = netlist.or(&n0, &n1).unwrap();
+    netlist.expose_net_with_name(nand, "y0".into());
+    netlist.expose_net_with_name(or, "y1".into());
+
+    let xor = netlist.xor(&x[0], &x[1]).unwrap();
+    let p0 = netlist.and(&x[0], &n1).unwrap();
+    let p1 = netlist.and(&n0, &x[1]).unwrap();
+    let sop = netlist.or(&p0, &p1).unwrap();
+    netlist.expose_net_with_name(xor, "y2".into());
+    netlist.expose_net_with_name(sop, "y3".into());
+    netlist
+}
+
+/// The parity of `n` inputs, as a chain and as a balanced tree of XORs
+fn parity(n: usize) -> Rc<GateNetlist> {
+    let netlist = Netlist::new("parity".to_string());
+    let x = inputs(&netlist, n);
+    let mut chain = x[0].clone();
+    for input in &x[1..] {
+        chain = netlist.xor(&chain, input).unwrap();
+    }
+    let mut level = x.clone();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => netlist.xor(a, b).unwrap(),
+                [a] => a.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    netlist.expose_net_with_name(chain, "chain".into());
+    netlist.expose_net_with_name(level[0].clone(), "tree".into());
+    netlist
+}
+
+/// Returns `true` if all the top-level outputs are driven by the same net
+fn shared_outputs(netlist: &GateNetlist, names: &[&str]) -> bool {
+    let drivers: Vec<_> = netlist
+        .outputs()
+        .into_iter()
+        .filter(|(_, net)| names.contains(&net.get_identifier().to_string().as_str()))
+        .map(|(d, _)| d)
+        .collect();
+    drivers.len() == names.len() && drivers.iter().all(|d| d == &drivers[0])
+}
+
+#[test]
+fn merges_functionally_equivalent_nets() {
+    let netlist = de_morgan();
+    assert_eq!(fraig(&netlist).unwrap(), 2);
+    assert!(shared_outputs(&netlist, &["y0", "y1"]));
+    assert!(shared_outputs(&netlist, &["y2", "y3"]));
+    netlist.clean().unwrap();
+    assert_eq!(netlist.instances().count(), 3);
+    check_equivalent(&netlist, &de_morgan(), 256, 1).unwrap();
+}
+
+#[test]
+fn proves_with_sat() {
+    let options = FraigOptions {
+        exhaustive_inputs: 0,
+        ..FraigOptions::default()
+    };
+    let netlist = de_morgan();
+    assert_eq!(fraig_with(&netlist, &options).unwrap(), 2);
+    assert!(shared_outputs(&netlist, &["y0", "y1"]));
+
+    // Sixteen inputs are beyond the default exhaustive checks
+    let netlist = parity(16);
+    assert!(fraig(&netlist).unwrap() >= 1);
+    assert!(shared_outputs(&netlist, &["chain", "tree"]));
+    netlist.clean().unwrap();
+    check_equivalent(&netlist, &parity(16), 1024, 1).unwrap();
+}
+
+#[test]
+fn keeps_different_nets_with_equal_signatures() {
+    // A single pattern puts many different nets in the same group, which the proofs must tell apart
+    for exhaustive_inputs in [0, 12] {
+        let options = FraigOptions {
+            patterns: 1,
+            exhaustive_inputs,
+            ..FraigOptions::default()
+        };
+        let netlist = de_morgan();
+        assert_eq!(fraig_with(&netlist, &options).unwrap(), 2);
+        check_equivalent(&netlist, &de_morgan(), 256, 2).unwrap();
+
+        let netlist = parity(6);
+        fraig_with(&netlist, &options).unwrap();
+        check_equivalent(&netlist, &parity(6), 256, 2).unwrap();
+    }
+}
+
+#[test]
+fn leaves_protected_cells() {
+    let netlist = de_morgan();
+    for inst in netlist.instances() {
+        if inst
+            .get_instance_type()
+            .unwrap()
+            .get_gate_name()
+            .to_string()
+            == "OR"
+        {
+            inst.set_attribute(DONT_TOUCH.to_string());
+        }
+    }
+    assert_eq!(fraig(&netlist).unwrap(), 0);
+}
+
+#[test]
+fn leaves_protected_loads() {
+    let netlist = de_morgan();
+    let or = netlist.outputs()[1].0.clone();
+    let x0 = netlist.find_net(&"x0".into()).unwrap();
+    let keep = netlist.and(&or, &x0).unwrap();
+    keep.clone().unwrap().set_attribute(DONT_TOUCH.to_string());
+    netlist.expose_net_with_name(keep.clone(), "y4".into());
+
+    // The OR is merged with the NAND, but still drives the protected AND
+    assert_eq!(fraig(&netlist).unwrap(), 2);
+    assert!(shared_outputs(&netlist, &["y0", "y1"]));
+    assert_eq!(keep.unwrap().get_input(0).get_driver().unwrap(), or);
+    netlist.clean().unwrap();
+    assert_eq!(netlist.instances().count(), 7);
+}
+
+#[test]
+fn unknown_cells_are_free_variables() {
+    let netlist = Netlist::new("unknown".to_string());
+    let x = inputs(&netlist, 2);
+    let cell = |name: &str| Gate::new_logical(name.into(), vec!["A".into()], "Y".into());
+    let foo = netlist
+        .insert_gate(cell("FOO"), "foo".into(), &[x[0].clone()])
+        .unwrap()
+        .get_output(0);
+    let bar = netlist
+        .insert_gate(cell("BAR"), "bar".into(), &[x[0].clone()])
+        .unwrap()
+        .get_output(0);
+    let and = netlist.and(&foo, &x[1]).unwrap();
+    let nand = netlist.not(&and).unwrap();
+    let (nfoo, n1) = (netlist.not(&foo).unwrap(), netlist.not(&x[1]).unwrap());
+    let or = netlist.or(&nfoo, &n1).unwrap();
+    netlist.expose_net_with_name(nand, "y0".into());
+    netlist.expose_net_with_name(or, "y1".into());
+    netlist.expose_net_with_name(foo, "y2".into());
+    netlist.expose_net_with_name(bar, "y3".into());
+
+    // The unknown cells simulate alike, but are not merged
+    assert_eq!(fraig(&netlist).unwrap(), 1);
+    assert!(shared_outputs(&netlist, &["y0", "y1"]));
+    assert!(!shared_outputs(&netlist, &["y2", "y3"]));
+}
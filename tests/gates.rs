@@ -0,0 +1,85 @@
+use safety_net::error::Error;
+use safety_net::netlist::GateNetlist;
+use safety_net::netlist::sim::Simulator;
+use std::rc::Rc;
+
+/// Returns the top-level outputs for every combination of `a`, `b` and `c`
+fn simulate(netlist: &Rc<GateNetlist>) -> Vec<u64> {
+    let mut sim = Simulator::new(netlist).unwrap();
+    sim.run(|n| match n.get_identifier().to_string().as_str() {
+        "a" => 0b1111_0000,
+        "b" => 0b1100_1100,
+        _ => 0b1010_1010,
+    });
+    netlist
+        .outputs()
+        .iter()
+        .map(|(o, _)| sim.get_word(o) & 0xff)
+        .collect()
+}
+
+#[test]
+fn test_gate_ops() {
+    let netlist = GateNetlist::new("ops".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let c = netlist.insert_input("c".into());
+
+    let not_b = netlist.not(&b).unwrap();
+    let and = netlist.and(&a, &not_b).unwrap();
+    netlist.or(&and, &c).unwrap().expose_with_name("y".into());
+    netlist.xor(&a, &b).unwrap().expose_with_name("x".into());
+    netlist
+        .mux(&c, &a, &b)
+        .unwrap()
+        .expose_with_name("m".into());
+    netlist.nand(&a, &b).unwrap().expose_with_name("n".into());
+    assert!(netlist.verify().is_ok());
+
+    let verilog = netlist.to_string();
+    assert!(verilog.contains("  INV inv_3 (\n    .A(b),\n    .Y(inv_3_Y)\n  );"));
+    assert!(verilog.contains("AND and_4 ("));
+    assert!(verilog.contains("MUX mux_7 (\n    .S(c),"));
+
+    let (a, b, c) = (0b1111_0000, 0b1100_1100, 0b1010_1010);
+    assert_eq!(
+        simulate(&netlist),
+        [
+            (a & !b | c) & 0xff,
+            a ^ b,
+            (c & b) | (!c & a) & 0xff,
+            !(a & b) & 0xff
+        ]
+    );
+}
+
+#[test]
+fn test_gate_reductions() {
+    let netlist = GateNetlist::new("reductions".to_string());
+    let inputs: Vec<_> = ["a", "b", "c"]
+        .iter()
+        .map(|i| netlist.insert_input((*i).into()))
+        .collect();
+    netlist
+        .and_all(&inputs)
+        .unwrap()
+        .expose_with_name("all".into());
+    netlist
+        .or_all(&inputs)
+        .unwrap()
+        .expose_with_name("any".into());
+    netlist
+        .xor_all(&inputs)
+        .unwrap()
+        .expose_with_name("parity".into());
+    assert_eq!(netlist.and_all(&inputs[..1]).unwrap(), inputs[0]);
+    assert!(matches!(
+        netlist.or_all(&[]),
+        Err(Error::ArgumentMismatch(1, 0))
+    ));
+    assert!(netlist.verify().is_ok());
+    assert_eq!(netlist.objects().count(), 9);
+
+    let (a, b, c) = (0b1111_0000, 0b1100_1100, 0b1010_1010);
+    assert_eq!(simulate(&netlist), [a & b & c, a | b | c, a ^ b ^ c]);
+}
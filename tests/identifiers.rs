@@ -1,4 +1,8 @@
-use safety_net::circuit::Identifier;
+use safety_net::{
+    circuit::{HierPath, Identifier},
+    error::Error,
+    netlist::GateNetlist,
+};
 
 #[test]
 fn concat_simple() {
@@ -13,7 +17,7 @@ fn concat_simple_w_sliced() {
     let id0 = Identifier::new("id0".to_string());
     let id1 = Identifier::new("id[1]".to_string());
     let id2 = id0 + id1;
-    assert_eq!(Identifier::new("\\id0_id_1".to_string()), id2);
+    assert_eq!(Identifier::new("\\id0_id[1]".to_string()), id2);
     assert!(id2.is_escaped());
 }
 
@@ -35,3 +39,111 @@ fn aig_id() {
     assert_eq!(Identifier::new("\\1_inv".to_string()), id2);
     assert!(id2.is_escaped());
 }
+
+#[test]
+fn escaped_terminator() {
+    let id = Identifier::new("\\foo[3] ".to_string());
+    assert!(id.is_escaped());
+    assert!(!id.is_sliced());
+    assert_eq!(id.get_name(), "foo[3]");
+    assert_eq!(id.as_verilog(), "\\foo[3] ");
+    assert_ne!(id, Identifier::new("foo[3]".to_string()));
+}
+
+#[test]
+fn non_simple_names() {
+    for name in [
+        "a.b", "a[b]", "a[03]", "a[1][2]", "x-y", "wire", "module", "1a[0]", "id$",
+    ] {
+        let id = Identifier::new(name.to_string());
+        assert!(id.is_escaped(), "{name}");
+        assert_eq!(id.get_name(), name);
+        assert_eq!(id.as_verilog(), format!("\\{name} "));
+    }
+    for name in ["a", "_a1", "wires", "a_b$"] {
+        assert!(Identifier::from_verilog(name).is_ok(), "{name}");
+    }
+    assert_eq!(Identifier::new("a_b".to_string()).as_verilog(), "a_b");
+}
+
+#[test]
+fn verilog_round_trip() {
+    for name in [
+        "data",
+        "data[12]",
+        "\\data ",
+        "\\a.b[3] ",
+        "\\wire ",
+        "a+b",
+        "sum[0]_c",
+    ] {
+        let id = Identifier::new(name.to_string());
+        assert_eq!(Identifier::new(id.as_verilog()), id, "{name}");
+        assert_eq!(
+            Identifier::from_verilog(&id.as_verilog()).unwrap(),
+            id,
+            "{name}"
+        );
+    }
+    assert_eq!(
+        Identifier::from_verilog(" bus [7] ").unwrap(),
+        Identifier::new("bus[7]".to_string())
+    );
+    assert_eq!(
+        Identifier::from_verilog("\\foo[3]").unwrap().get_name(),
+        "foo[3]"
+    );
+    for bad in ["", "\\", "\\a b", "1a", "wire", "a b", "a[x]", "$display"] {
+        assert!(
+            matches!(Identifier::from_verilog(bad), Err(Error::ParseError(_))),
+            "{bad}"
+        );
+    }
+}
+
+#[test]
+fn hier_paths() {
+    let flat = Identifier::new("u_core/u_alu/sum[3]".to_string());
+    assert!(flat.is_escaped());
+    let path = HierPath::split(&flat, '/').unwrap();
+    assert_eq!(path.len(), 3);
+    assert_eq!(path.leaf(), Some(&Identifier::new("sum[3]".to_string())));
+    assert!(path.leaf().unwrap().is_sliced());
+    assert_eq!(path.join('/'), Some(flat));
+    assert_eq!(path.to_string(), "u_core.u_alu.sum[3]");
+    assert_eq!(
+        path.join('.').unwrap().as_verilog(),
+        "\\u_core.u_alu.sum[3] "
+    );
+
+    let parent = path.parent().unwrap();
+    assert!(path.starts_with(&parent));
+    assert_eq!(
+        path.strip_prefix(&parent).unwrap().components(),
+        &[Identifier::new("sum[3]".to_string())]
+    );
+    assert!(HierPath::split(&"a//b".into(), '/').is_err());
+    assert_eq!(HierPath::default().join('/'), None);
+}
+
+#[test]
+fn hier_path_parsing() {
+    let path: HierPath = "top.\\a.b .q".parse().unwrap();
+    assert_eq!(
+        path.components(),
+        &[
+            Identifier::new("top".to_string()),
+            Identifier::new("\\a.b".to_string()),
+            Identifier::new("q".to_string())
+        ]
+    );
+    assert_eq!(path.to_string(), "top.\\a.b .q");
+    assert_eq!(path.to_string().parse::<HierPath>().unwrap(), path);
+    for bad in ["", "top.", ".q", "top..q", "top q"] {
+        assert!(bad.parse::<HierPath>().is_err(), "{bad}");
+    }
+
+    let netlist = GateNetlist::new("top".to_string());
+    let path = netlist.get_hier_path(&"u_core/g3".into(), '/').unwrap();
+    assert_eq!(path.to_string(), "top.u_core.g3");
+}
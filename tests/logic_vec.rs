@@ -0,0 +1,129 @@
+use bitvec::{bitvec, order::Lsb0, vec::BitVec};
+use safety_net::{
+    error::Error,
+    logic::{Logic, LogicVec},
+    netlist::{GateNetlist, sim::LogicSimulator},
+};
+
+fn lv(s: &str) -> LogicVec {
+    s.parse().unwrap()
+}
+
+#[test]
+fn parse_literals() {
+    let v = lv("8'b1x0z_0101");
+    assert_eq!(v.len(), 8);
+    assert_eq!(v.get(0), Some(Logic::True));
+    assert_eq!(v.get(4), Some(Logic::Z));
+    assert_eq!(v.get(6), Some(Logic::X));
+    assert_eq!(v.get(8), None);
+    assert_eq!(v.to_string(), "8'b1x0z0101");
+    assert_eq!(lv(&v.to_string()), v);
+
+    assert_eq!(lv("'hF").len(), 32);
+    assert_eq!(lv("'hF").to_u64(), Some(15));
+    assert_eq!(lv("4'hFF").to_u64(), Some(15));
+    assert_eq!(lv("16'd300").to_u64(), Some(300));
+    assert_eq!(lv("12").to_u64(), Some(12));
+    assert_eq!(lv("6'o7x").to_string(), "6'b111xxx");
+    // Literals are extended with an unknown most significant digit
+    assert_eq!(lv("4'bz"), LogicVec::filled(4, Logic::Z));
+    assert_eq!(lv("4'dx"), LogicVec::filled(4, Logic::X));
+    assert_eq!(lv("4'b?1").to_string(), "4'bzzz1");
+
+    for bad in ["", "8'q1", "0'b1", "4'b2", "8'hG", "x"] {
+        assert!(matches!(bad.parse::<LogicVec>(), Err(Error::ParseError(_))));
+    }
+}
+
+#[test]
+fn slicing() {
+    let v = lv("8'b1x0z_0101");
+    assert_eq!(v.slice(4..), lv("4'b1x0z"));
+    assert_eq!(v.slice(..=1), lv("2'b01"));
+    assert_eq!(v.slice(4..).concat(&v.slice(..4)), lv("8'b0101_1x0z"));
+
+    let mut w = v.clone();
+    w.set(6, Logic::False);
+    w.resize(10, Logic::True);
+    assert_eq!(w, lv("10'b11_100z_0101"));
+    assert_eq!(w.iter().filter(|b| *b == Logic::True).count(), 5);
+}
+
+#[test]
+fn bitvec_conversions() {
+    let bv: BitVec = bitvec![usize, Lsb0; 1, 0, 1, 1];
+    let v = LogicVec::from(bv.clone());
+    assert_eq!(v, lv("4'b1101"));
+    assert!(v.is_known());
+    assert_eq!(v.to_bitvec(), Some(bv));
+    assert_eq!(lv("4'b1x01").to_bitvec(), None);
+    assert_eq!(lv("4'b1x01").to_u64(), None);
+    assert_eq!(LogicVec::from_u64(0xA5, 8), lv("8'hA5"));
+}
+
+#[test]
+fn operators() {
+    let a = lv("4'b10xz");
+    let b = lv("4'b1100");
+    assert_eq!(&a & &b, lv("4'b1000"));
+    assert_eq!(&a | &b, lv("4'b11xx"));
+    assert_eq!(&a ^ &b, lv("4'b01xx"));
+    assert_eq!(!&a, lv("4'b01xx"));
+    // The narrower operand is zero-extended
+    assert_eq!(lv("2'b11") | lv("4'b1000"), lv("4'b1011"));
+
+    assert_eq!(lv("8'd200") + lv("8'd100"), lv("8'd44"));
+    assert_eq!(lv("8'd3") - lv("8'd5"), lv("8'd254"));
+    assert_eq!(lv("8'd13") * lv("8'd11"), lv("8'd143"));
+    assert_eq!(lv("4'd3") * lv("8'd100"), lv("8'd44"));
+    assert_eq!(lv("4'b000x") + lv("4'd1"), LogicVec::filled(4, Logic::X));
+}
+
+#[test]
+fn constant_bus() {
+    let netlist = GateNetlist::new("constant_bus".to_string());
+    let value = lv("4'b10x1");
+    let bits = netlist.insert_constant_vec(&value, "c".into());
+    // The gate library has no constant for unknown values
+    assert!(bits.is_err());
+
+    let value = lv("4'b1001");
+    let bits = netlist.insert_constant_vec(&value, "c".into()).unwrap();
+    assert_eq!(bits.len(), 4);
+    let mut sim = LogicSimulator::new(&netlist).unwrap();
+    sim.run(|_| Logic::X);
+    assert_eq!(sim.get_vec(&bits), value);
+}
+
+#[test]
+fn hex_literals() {
+    assert_eq!(format!("{:X}", lv("8'hA5")), "8'hA5");
+    assert_eq!(format!("{:X}", lv("6'b100101")), "6'h25");
+    assert_eq!(format!("{:X}", lv("8'bxxxx_zzzz")), "8'hxz");
+    assert_eq!(format!("{:X}", lv("4'b10x1")), "4'b10x1");
+    for s in ["8'hA5", "6'h25", "8'hxz", "12'h0F0"] {
+        assert_eq!(lv(&format!("{:X}", lv(s))), lv(s));
+    }
+}
+
+#[test]
+fn tied_bus() {
+    let netlist = GateNetlist::new("tied_bus".to_string());
+    let value = lv("8'hA5");
+    let bits = netlist.tie_bus("y", &value).unwrap();
+    assert_eq!(bits.len(), 8);
+    // One constant cell per value
+    assert_eq!(netlist.objects().count(), 2);
+    netlist.tie_bus("z", &lv("2'b11")).unwrap();
+    assert_eq!(netlist.objects().count(), 2);
+    assert!(netlist.tie_bus("w", &lv("1'bx")).is_err());
+
+    let verilog = netlist.to_string();
+    assert!(verilog.contains("  assign y = 8'hA5;\n  assign z = 2'h3;\n"));
+    assert!(!verilog.contains("assign y[0]"));
+
+    let mut sim = LogicSimulator::new(&netlist).unwrap();
+    sim.run(|_| Logic::X);
+    assert_eq!(sim.get_vec(&bits), value);
+}
@@ -0,0 +1,78 @@
+use safety_net::{
+    attribute::Parameter,
+    netlist::{Gate, GateNetlist, INLINE_PINS, Netlist},
+};
+use std::rc::Rc;
+
+fn and_gate() -> Gate {
+    Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+fn and_chain(n: usize) -> Rc<GateNetlist> {
+    let netlist = Netlist::new("chain".to_string());
+    let mut y = netlist.insert_input("a".into());
+    for i in 0..n {
+        let b = netlist.insert_input(format!("b{i}").as_str().into());
+        y = netlist
+            .insert_gate(and_gate(), format!("inst_{i}").into(), &[y, b])
+            .unwrap()
+            .get_output(0);
+    }
+    y.expose_with_name("y".into());
+    netlist
+}
+
+#[test]
+fn footprint_grows_with_the_netlist() {
+    let small = and_chain(4).memory_footprint();
+    let large = and_chain(64).memory_footprint();
+    assert_eq!(small.rc_allocations, 9);
+    assert_eq!(large.rc_allocations, 129);
+    assert!(small.objects > 0 && small.nets > 0 && small.names > 0);
+    assert!(large.objects > small.objects);
+    assert!(large.names > small.names);
+    assert_eq!(
+        large.total(),
+        large.objects + large.nets + large.names + large.attributes + large.indices
+    );
+    assert!(large.to_string().contains("rc allocations: 129"));
+}
+
+#[test]
+fn footprint_of_attributes_and_indices() {
+    let netlist = and_chain(4);
+    let before = netlist.memory_footprint();
+    assert_eq!(before.attributes, 0);
+
+    netlist
+        .last()
+        .unwrap()
+        .insert_attribute("mode".to_string(), Parameter::String("fast".to_string()));
+    let after = netlist.memory_footprint();
+    assert!(after.attributes > 0);
+    assert_eq!(after.objects, before.objects);
+
+    // The name index is counted once a query builds it
+    assert_eq!(netlist.find_nets_matching("b*").len(), 4);
+    assert!(netlist.memory_footprint().indices > after.indices);
+}
+
+#[test]
+fn footprint_of_wide_cells() {
+    let netlist = and_chain(8);
+    assert_eq!(netlist.memory_footprint().spilled_pins, 0);
+
+    let inputs: Vec<_> = netlist.inputs().collect();
+    let ports = (0..inputs.len())
+        .map(|i| format!("A{i}").as_str().into())
+        .collect();
+    let wide = Gate::new_logical("ANDN".into(), ports, "Y".into());
+    assert!(inputs.len() > INLINE_PINS);
+    netlist
+        .insert_gate(wide, "wide".into(), &inputs)
+        .unwrap()
+        .expose_with_name("w".into());
+    let fp = netlist.memory_footprint();
+    assert_eq!(fp.spilled_pins, 1);
+    assert!(fp.to_string().contains("spilled pin lists: 1"));
+}
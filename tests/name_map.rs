@@ -0,0 +1,116 @@
+use safety_net::netlist::{Gate, GateNetlist, Netlist, RemovePolicy};
+use std::rc::Rc;
+
+fn and_gate() -> Gate {
+    Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+fn buf_gate() -> Gate {
+    Gate::new_logical("BUF".into(), vec!["A".into()], "Y".into())
+}
+
+/// `y = buf(a & b)`, with a duplicate `c = a & b` that is not read
+fn get_example() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("name_map".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let and = netlist
+        .insert_gate(and_gate(), "and_0".into(), &[a.clone(), b.clone()])
+        .unwrap();
+    netlist
+        .insert_gate(and_gate(), "and_1".into(), &[a, b])
+        .unwrap();
+    netlist
+        .insert_gate(buf_gate(), "buf_0".into(), &[and.get_output(0)])
+        .unwrap()
+        .expose_with_name("y".into());
+    netlist
+}
+
+#[test]
+fn names_are_not_recorded_by_default() {
+    let netlist = get_example();
+    assert!(netlist.name_map().is_none());
+    netlist.record_names();
+    assert!(netlist.name_map().is_some());
+    netlist.clear_names();
+    assert!(netlist.name_map().is_none());
+}
+
+#[test]
+fn renamed_nodes_keep_their_original_name() {
+    let netlist = get_example();
+    netlist.record_names();
+    let map = netlist.name_map().unwrap();
+    assert_eq!(map.instances.len(), 3);
+    assert_eq!(map.nets.len(), 5);
+    assert_eq!(map.instance(&"and_0".into()), vec![&"and_0".into()]);
+
+    let and = netlist.find_net(&"and_0_Y".into()).unwrap().unwrap();
+    and.set_instance_name("g0".into());
+    and.set_identifier("n0".into());
+    let map = netlist.name_map().unwrap();
+    assert_eq!(map.instance(&"and_0".into()), vec![&"g0".into()]);
+    assert_eq!(map.net(&"and_0_Y".into()), vec![&"n0".into()]);
+    assert_eq!(map.instance(&"buf_0".into()), vec![&"buf_0".into()]);
+    assert_eq!(map.net(&"a".into()), vec![&"a".into()]);
+}
+
+#[test]
+fn merged_nodes_take_the_original_names() {
+    let netlist = get_example();
+    netlist.record_names();
+    let and_0 = netlist.find_net(&"and_0_Y".into()).unwrap();
+    let and_1 = netlist.find_net(&"and_1_Y".into()).unwrap();
+    netlist.replace_net_uses(and_0, &and_1).unwrap();
+    assert!(netlist.clean().unwrap());
+
+    let map = netlist.name_map().unwrap();
+    assert_eq!(map.instance(&"and_0".into()), vec![&"and_1".into()]);
+    assert_eq!(map.net(&"and_0_Y".into()), vec![&"and_1_Y".into()]);
+
+    // A buffer that is stitched through merges into its driver
+    let buf = netlist.find_net(&"buf_0_Y".into()).unwrap().unwrap();
+    netlist
+        .remove_instance(buf, RemovePolicy::StitchThrough)
+        .unwrap();
+    let map = netlist.name_map().unwrap();
+    assert_eq!(map.instance(&"buf_0".into()), vec![&"and_1".into()]);
+    assert_eq!(map.net(&"buf_0_Y".into()), vec![&"and_1_Y".into()]);
+}
+
+#[test]
+fn removed_nodes_map_to_nothing() {
+    let netlist = get_example();
+    netlist.record_names();
+    assert!(netlist.clean().unwrap());
+    let map = netlist.name_map().unwrap();
+    assert!(map.instance(&"and_1".into()).is_empty());
+    assert!(map.instances.contains(&("and_1".into(), None)));
+    assert!(map.nets.contains(&("and_1_Y".into(), None)));
+
+    // New nodes have no original name
+    let a = netlist.find_net(&"a".into()).unwrap();
+    netlist
+        .insert_gate(buf_gate(), "buf_1".into(), &[a])
+        .unwrap();
+    assert_eq!(netlist.name_map().unwrap(), map);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn name_maps_are_saved_as_json() {
+    use safety_net::netlist::name_map::NameMap;
+    let netlist = get_example();
+    netlist.record_names();
+    netlist
+        .find_net(&"buf_0_Y".into())
+        .unwrap()
+        .unwrap()
+        .set_instance_name("out".into());
+    let map = netlist.name_map().unwrap();
+    let json = serde_json::to_string(&map).unwrap();
+    let read: NameMap = serde_json::from_str(&json).unwrap();
+    assert_eq!(read, map);
+    assert_eq!(read.instance(&"buf_0".into()), vec![&"out".into()]);
+}
@@ -0,0 +1,315 @@
+use safety_net::{
+    circuit::Identifier,
+    error::Error,
+    netlist::{
+        Gate, GateNetlist, Netlist,
+        network::{LogicNetwork, NetworkBuilder, NetworkNode, Signal},
+        sim::Simulator,
+        testing::{RandomConfig, assert_equivalent, assert_invariants, random_netlist},
+    },
+};
+use std::rc::Rc;
+
+const PATTERNS: [u64; 3] = [0xAAAA_AAAA, 0xCCCC_CCCC, 0xF0F0_F0F0];
+
+fn gate(name: &str, ports: &[&str]) -> Gate {
+    Gate::new_logical(
+        name.into(),
+        ports.iter().map(|p| (*p).into()).collect(),
+        "Y".into(),
+    )
+}
+
+/// A full adder of `a`, `b` and `cin`, with the carry from a `MUX`
+fn full_adder() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("adder".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let cin = netlist.insert_input("cin".into());
+    let p = netlist.xor(&a, &b).unwrap();
+    netlist
+        .xor(&p, &cin)
+        .unwrap()
+        .expose_with_name("sum".into());
+    netlist
+        .mux(&p, &a, &cin)
+        .unwrap()
+        .expose_with_name("cout".into());
+    netlist
+}
+
+/// Returns the words of the outputs of `netlist` under [PATTERNS], by input name
+fn simulate(netlist: &Rc<GateNetlist>) -> Vec<u64> {
+    let mut sim = Simulator::new(netlist).unwrap();
+    sim.run(|n| match n.get_identifier().get_name() {
+        "a" => PATTERNS[0],
+        "b" => PATTERNS[1],
+        _ => PATTERNS[2],
+    });
+    netlist
+        .outputs()
+        .iter()
+        .map(|(o, _)| sim.get_word(o) & 0xFFFF_FFFF)
+        .collect()
+}
+
+#[test]
+fn export_full_adder() {
+    let netlist = full_adder();
+    let network = netlist.to_logic_network().unwrap();
+    assert!(network.is_aig());
+    assert_eq!(network.inputs().count(), 3);
+    let names: Vec<&str> = network
+        .outputs()
+        .iter()
+        .map(|(n, _)| n.get_name())
+        .collect();
+    assert_eq!(names, ["sum", "cout"]);
+    // Two XORs of three ANDs each, and a MUX of three ANDs between the carries for a low and a high `cin`
+    assert_eq!(network.num_gates(), 11);
+    let words: Vec<u64> = network
+        .simulate(&PATTERNS)
+        .unwrap()
+        .into_iter()
+        .map(|w| w & 0xFFFF_FFFF)
+        .collect();
+    assert_eq!(words, simulate(&netlist));
+    assert!(matches!(
+        network.simulate(&[0]),
+        Err(Error::ArgumentMismatch(3, 1))
+    ));
+}
+
+#[test]
+fn round_trip_random() {
+    let cells = vec![
+        gate("AND", &["A", "B"]),
+        gate("NOR", &["A", "B"]),
+        gate("XNOR", &["A", "B", "C"]),
+        gate("INV", &["A"]),
+        gate("MUX", &["S", "A", "B"]),
+    ];
+    let mut config = RandomConfig::new(cells);
+    config.instances = 100;
+    config.outputs = 8;
+    for seed in 0..8 {
+        let netlist = random_netlist(seed, &config).unwrap();
+        let network = netlist.to_logic_network().unwrap();
+        let imported: Rc<GateNetlist> = network.to_netlist("random").unwrap();
+        // The network keeps the dead logic of the random netlist
+        imported.clean().unwrap();
+        assert_invariants(&imported);
+        assert!(imported.verify().is_ok());
+        assert_equivalent(&netlist, &imported, 256);
+    }
+}
+
+#[test]
+fn structural_hashing() {
+    let mut network = LogicNetwork::new();
+    let a = network.create_pi(&"a".into());
+    let b = network.create_pi(&"b".into());
+    let ab = network.create_and(&a, &b);
+    assert_eq!(network.create_and(&b, &a), ab);
+    assert_eq!(network.create_and(&a, &!a), Signal::FALSE);
+    assert_eq!(network.create_and(&a, &Signal::TRUE), a);
+    assert_eq!(network.create_maj(&a, &b, &!a), b);
+    assert_eq!(network.create_maj(&a, &b, &Signal::FALSE), ab);
+    assert_eq!(network.num_gates(), 1);
+    assert_eq!(network.nodes()[ab.node()], NetworkNode::And(a, b));
+    assert_eq!(Signal::from_literal(ab.literal()), ab);
+    assert!((!ab).is_complemented());
+}
+
+#[test]
+fn majority_networks() {
+    let mut mig = LogicNetwork::new();
+    let inputs: Vec<Signal> = ["a", "b", "cin"]
+        .iter()
+        .map(|n| mig.create_pi(&(*n).into()))
+        .collect();
+    let carry = mig.create_maj(&inputs[0], &inputs[1], &inputs[2]);
+    mig.create_po(carry, &"cout".into());
+    mig.create_po(!carry, &"ncout".into());
+    assert!(!mig.is_aig());
+    let words = mig.simulate(&PATTERNS).unwrap();
+    assert_eq!(words[0] & 0xFF, 0xE8);
+    assert_eq!(words[1], !words[0]);
+
+    let aig = mig.to_aig();
+    assert!(aig.is_aig());
+    assert_eq!(aig.simulate(&PATTERNS).unwrap(), words);
+
+    let netlist: Rc<GateNetlist> = mig.to_netlist("mig").unwrap();
+    assert_invariants(&netlist);
+    let mut sim = Simulator::new(&netlist).unwrap();
+    let mut patterns = PATTERNS.iter();
+    sim.run(|_| *patterns.next().unwrap());
+    let outputs = netlist.outputs();
+    assert_eq!(sim.get_word(&outputs[0].0), words[0]);
+    assert_eq!(sim.get_word(&outputs[1].0), words[1]);
+}
+
+#[test]
+fn aiger_round_trip() {
+    let network = full_adder().to_logic_network().unwrap();
+    let text = network.to_aag();
+    assert!(text.starts_with("aag 14 3 0 2 11\n"));
+    assert!(text.contains("i2 cin\n") && text.contains("o1 cout\n"));
+    let read = LogicNetwork::from_aag(&text).unwrap();
+    assert_eq!(read.num_gates(), 11);
+    assert_eq!(read.outputs()[0].0, "sum".into());
+    assert_eq!(
+        read.simulate(&PATTERNS).unwrap(),
+        network.simulate(&PATTERNS).unwrap()
+    );
+}
+
+#[test]
+fn aiger_parsing() {
+    // The AND gates may come in any order, and the symbols are optional
+    let network =
+        LogicNetwork::from_aag("aag 4 2 0 1 2\n2\n4\n9\n8 6 2\n6 2 4\ni0 x\nc\nfree text\n")
+            .unwrap();
+    let names: Vec<Identifier> = network.inputs().map(|(_, n)| n.clone()).collect();
+    assert_eq!(names, vec!["x".into(), "i1".into()]);
+    assert_eq!(network.outputs()[0].0, "o0".into());
+    // The output is the NAND of `x` and `i1`
+    assert_eq!(
+        network.simulate(&[0b1100, 0b1010]).unwrap()[0] & 0xF,
+        0b0111
+    );
+
+    assert!(matches!(
+        LogicNetwork::from_aag("aag 1 0 1 0 0\n2 3\n"),
+        Err(Error::Unsupported(_))
+    ));
+    assert!(matches!(
+        LogicNetwork::from_aag("aig 0 0 0 0 0\n"),
+        Err(Error::ParseError(_))
+    ));
+    assert!(matches!(
+        LogicNetwork::from_aag("aag 2 1 0 1 1\n2\n4\n4 4 2\n"),
+        Err(Error::ParseError(_))
+    ));
+    // An empty symbol, an odd AND gate, and AND gates that redefine an input or each other
+    for text in [
+        "aag 1 1 0 0 0\n2\ni0 \n",
+        "aag 1 1 0 0 0\n2\ni0 \\\n",
+        "aag 2 1 0 1 1\n2\n5\n5 2 2\n",
+        "aag 2 1 0 1 1\n2\n2\n2 1 1\n",
+        "aag 2 1 0 1 2\n2\n4\n4 2 2\n4 3 3\n",
+    ] {
+        assert!(matches!(
+            LogicNetwork::from_aag(text),
+            Err(Error::ParseError(_))
+        ));
+    }
+}
+
+#[test]
+fn binary_aiger_round_trip() {
+    let network = full_adder().to_logic_network().unwrap();
+    let bytes = network.to_aig_bytes();
+    assert!(bytes.starts_with(b"aig 14 3 0 2 11\n"));
+    let read = LogicNetwork::from_aig_bytes(&bytes).unwrap();
+    assert_eq!(read.num_gates(), 11);
+    assert_eq!(read.outputs()[1].0, "cout".into());
+    assert_eq!(
+        read.simulate(&PATTERNS).unwrap(),
+        network.simulate(&PATTERNS).unwrap()
+    );
+    // Both formats describe the same graph
+    assert_eq!(
+        LogicNetwork::from_aag(&network.to_aag()).unwrap().to_aag(),
+        read.to_aag()
+    );
+
+    // The NAND of two inputs, with the deltas 2 and 2 of the AND gate 6 = 4 & 2
+    let nand = LogicNetwork::from_aig_bytes(b"aig 3 2 0 1 1\n7\n\x02\x02").unwrap();
+    assert_eq!(nand.simulate(&[0b1100, 0b1010]).unwrap()[0] & 0xF, 0b0111);
+    assert!(matches!(
+        LogicNetwork::from_aig_bytes(b"aig 3 2 0 1 1\n7\n\x82"),
+        Err(Error::ParseError(_))
+    ));
+    assert!(matches!(
+        LogicNetwork::from_aig_bytes(b"aig 3 2 0 1 1\n7\n\x07\x00"),
+        Err(Error::ParseError(_))
+    ));
+
+    // The counts of the header are not trusted to allocate, nor to number the AND gates
+    for bytes in [
+        &b"aig 0 0 0 0 2147483647\n"[..],
+        b"aig 0 0 0 4000000000 0\n",
+        b"aig 2 2147483647 0 0 1\n\x02\x02",
+    ] {
+        assert!(matches!(
+            LogicNetwork::from_aig_bytes(bytes),
+            Err(Error::ParseError(_))
+        ));
+    }
+}
+
+/// Computes the simulation words of the signals as the network is built
+#[derive(Default)]
+struct WordBuilder {
+    inputs: usize,
+    outputs: Vec<(Identifier, u64)>,
+}
+
+impl NetworkBuilder for WordBuilder {
+    type Signal = u64;
+
+    fn constant(&mut self, value: bool) -> u64 {
+        if value { u64::MAX } else { 0 }
+    }
+
+    fn create_pi(&mut self, _name: &Identifier) -> u64 {
+        self.inputs += 1;
+        PATTERNS[self.inputs - 1]
+    }
+
+    fn create_po(&mut self, signal: u64, name: &Identifier) {
+        self.outputs.push((name.clone(), signal & 0xFFFF_FFFF));
+    }
+
+    fn create_not(&mut self, a: &u64) -> u64 {
+        !a
+    }
+
+    fn create_and(&mut self, a: &u64, b: &u64) -> u64 {
+        a & b
+    }
+}
+
+#[test]
+fn custom_builder() {
+    let netlist = full_adder();
+    let mut builder = WordBuilder::default();
+    netlist.to_network(&mut builder).unwrap();
+    let words: Vec<u64> = builder.outputs.iter().map(|(_, w)| *w).collect();
+    assert_eq!(words, simulate(&netlist));
+
+    // Replaying a network gives the same words
+    let mut replayed = WordBuilder::default();
+    netlist.to_logic_network().unwrap().replay(&mut replayed);
+    assert_eq!(replayed.outputs, builder.outputs);
+}
+
+#[test]
+fn wide_cells_are_unsupported() {
+    let netlist = Netlist::new("wide".to_string());
+    let inputs: Vec<_> = (0..7)
+        .map(|i| netlist.insert_input(format!("i{i}").as_str().into()))
+        .collect();
+    let ports: Vec<String> = (0..7).map(|i| format!("A{i}")).collect();
+    let ports: Vec<&str> = ports.iter().map(String::as_str).collect();
+    netlist
+        .insert_gate(gate("AND", &ports), "wide".into(), &inputs)
+        .unwrap()
+        .expose_with_name("y".into());
+    assert!(matches!(
+        netlist.to_logic_network(),
+        Err(Error::Unsupported(_))
+    ));
+}
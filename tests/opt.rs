@@ -0,0 +1,313 @@
+use safety_net::attribute::DONT_TOUCH;
+use safety_net::graph::FanOutTable;
+use safety_net::logic::Logic;
+use safety_net::netlist::Gate;
+use safety_net::netlist::GateNetlist;
+use safety_net::netlist::Netlist;
+use safety_net::netlist::opt::{limit_fanout, propagate_constants, push_inverters};
+use safety_net::netlist::sim::Simulator;
+use std::rc::Rc;
+
+fn and_gate() -> Gate {
+    Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+fn inv_gate() -> Gate {
+    Gate::new_logical("INV".into(), vec!["A".into()], "Y".into())
+}
+
+fn buf_gate() -> Gate {
+    Gate::new_logical("BUF".into(), vec!["A".into()], "Y".into())
+}
+
+/// Inputs `a` and `b` both drive each of `n` AND gates
+fn get_fanout_example(n: usize) -> Rc<GateNetlist> {
+    let netlist = Netlist::new("fanout".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    for i in 0..n {
+        let and = netlist
+            .insert_gate(
+                and_gate(),
+                format!("inst_{i}").into(),
+                &[a.clone(), b.clone()],
+            )
+            .unwrap();
+        and.expose_with_name(format!("y{i}").into());
+    }
+    netlist
+}
+
+fn max_fanout(netlist: &Rc<GateNetlist>) -> usize {
+    let fanout = netlist.get_analysis::<FanOutTable<_>>().unwrap();
+    netlist
+        .objects()
+        .flat_map(|o| o.outputs().collect::<Vec<_>>())
+        .map(|o| fanout.get_net_users(&o.as_net()).count())
+        .max()
+        .unwrap()
+}
+
+/// Returns the top-level outputs for every combination of `a` and `b`
+fn simulate(netlist: &Rc<GateNetlist>) -> Vec<u64> {
+    let mut sim = Simulator::new(netlist).unwrap();
+    sim.run(|n| {
+        if n.get_identifier() == "a".into() {
+            0b1100
+        } else {
+            0b1010
+        }
+    });
+    netlist
+        .outputs()
+        .iter()
+        .map(|(o, _)| sim.get_word(o) & 0b1111)
+        .collect()
+}
+
+#[test]
+fn test_limit_fanout() {
+    let netlist = get_fanout_example(10);
+    assert_eq!(max_fanout(&netlist), 10);
+    let before = simulate(&netlist);
+
+    // The 10 loads of each input need 4 buffers of up to 3 loads, which need 2 more buffers
+    assert_eq!(limit_fanout(&netlist, 3, buf_gate).unwrap(), 12);
+    assert!(netlist.verify().is_ok());
+    assert_eq!(max_fanout(&netlist), 3);
+    assert_eq!(limit_fanout(&netlist, 3, buf_gate).unwrap(), 0);
+
+    assert_eq!(simulate(&netlist), before);
+}
+
+#[test]
+fn test_limit_fanout_protected() {
+    let netlist = get_fanout_example(4);
+    for i in 0..2 {
+        let inst = netlist
+            .find_net(&format!("inst_{i}_Y").as_str().into())
+            .unwrap()
+            .unwrap();
+        inst.set_attribute(DONT_TOUCH.to_string());
+    }
+    // Two loads of each input stay on the driver, so the others share one buffer
+    assert_eq!(limit_fanout(&netlist, 3, buf_gate).unwrap(), 2);
+    assert!(netlist.verify().is_ok());
+    assert_eq!(max_fanout(&netlist), 3);
+
+    assert!(limit_fanout(&netlist, 1, buf_gate).is_err());
+    assert!(limit_fanout(&netlist, 3, and_gate).is_err());
+}
+
+#[test]
+fn test_double_inversion() {
+    let netlist = Netlist::new("double_inversion".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let inv_0 = netlist
+        .insert_gate(inv_gate(), "inv_0".into(), &[a])
+        .unwrap();
+    let inv_1 = netlist
+        .insert_gate(inv_gate(), "inv_1".into(), &[inv_0.get_output(0)])
+        .unwrap();
+    netlist
+        .insert_gate(and_gate(), "inst_0".into(), &[inv_1.get_output(0), b])
+        .unwrap()
+        .expose_with_name("y".into());
+    inv_1.expose_with_name("z".into());
+    drop(inv_0);
+    let before = simulate(&netlist);
+
+    assert_eq!(push_inverters(&netlist).unwrap(), 1);
+    assert!(netlist.verify().is_ok());
+    assert_eq!(netlist.objects().count(), 3);
+    assert_eq!(simulate(&netlist), before);
+    assert!(netlist.to_string().contains("assign z = a;"));
+    assert_eq!(push_inverters(&netlist).unwrap(), 0);
+}
+
+#[test]
+fn test_absorb_inverters() {
+    let netlist = Netlist::new("absorb_inverters".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let and = netlist
+        .insert_gate(and_gate(), "inst_0".into(), &[a, b])
+        .unwrap();
+    for i in 0..2 {
+        netlist
+            .insert_gate(inv_gate(), format!("inv_{i}").into(), &[and.get_output(0)])
+            .unwrap()
+            .expose_with_name(format!("y{i}").into());
+    }
+    drop(and);
+    let before = simulate(&netlist);
+
+    assert_eq!(push_inverters(&netlist).unwrap(), 1);
+    assert!(netlist.verify().is_ok());
+    assert_eq!(netlist.objects().count(), 3);
+    assert_eq!(simulate(&netlist), before);
+    assert!(netlist.to_string().contains("NAND inst_0 ("));
+}
+
+#[test]
+fn test_de_morgan() {
+    let netlist = Netlist::new("de_morgan".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let inv_a = netlist
+        .insert_gate(inv_gate(), "inv_0".into(), &[a])
+        .unwrap();
+    let inv_b = netlist
+        .insert_gate(inv_gate(), "inv_1".into(), &[b])
+        .unwrap();
+    netlist
+        .insert_gate(
+            and_gate(),
+            "inst_0".into(),
+            &[inv_a.get_output(0), inv_b.get_output(0)],
+        )
+        .unwrap()
+        .expose_with_name("y".into());
+    let before = simulate(&netlist);
+
+    // The inverter of `b` has another load, so the AND gate stays
+    inv_b.expose_with_name("z".into());
+    drop(inv_a);
+    assert_eq!(push_inverters(&netlist).unwrap(), 0);
+
+    netlist.remove_output(&"z".into()).unwrap();
+    assert_eq!(push_inverters(&netlist).unwrap(), 1);
+    assert!(netlist.verify().is_ok());
+    assert_eq!(netlist.objects().count(), 3);
+    assert_eq!(simulate(&netlist), before[..1]);
+    assert!(netlist.to_string().contains("NOR inst_0 ("));
+}
+
+#[test]
+fn test_push_inverters_protected() {
+    let netlist = Netlist::new("protected".to_string());
+    let a = netlist.insert_input("a".into());
+    let inv_0 = netlist
+        .insert_gate(inv_gate(), "inv_0".into(), &[a])
+        .unwrap();
+    let inv_1 = netlist
+        .insert_gate(inv_gate(), "inv_1".into(), &[inv_0.get_output(0)])
+        .unwrap();
+    inv_1.expose_with_name("y".into());
+    inv_0.set_attribute(DONT_TOUCH.to_string());
+    drop(inv_0);
+    assert_eq!(push_inverters(&netlist).unwrap(), 0);
+    assert_eq!(netlist.objects().count(), 3);
+}
+
+#[test]
+fn test_push_inverters_protected_load() {
+    let netlist = Netlist::new("protected".to_string());
+    let a = netlist.insert_input("a".into());
+    let i0 = netlist
+        .insert_gate(inv_gate(), "i0".into(), std::slice::from_ref(&a))
+        .unwrap();
+    let i1 = netlist
+        .insert_gate(inv_gate(), "i1".into(), &[i0.get_output(0)])
+        .unwrap();
+    let keep = netlist
+        .insert_gate(and_gate(), "keep".into(), &[i1.get_output(0), a])
+        .unwrap();
+    keep.set_attribute(DONT_TOUCH.to_string());
+    keep.clone().expose_with_name("y".into());
+    drop((i0, i1));
+
+    // The inverters cancel out, but the protected gate keeps reading `i1`
+    assert_eq!(push_inverters(&netlist).unwrap(), 0);
+    assert_eq!(
+        keep.get_input(0).get_driver().unwrap().get_identifier(),
+        "i1_Y".into()
+    );
+    assert_eq!(netlist.objects().count(), 4);
+}
+
+#[test]
+fn test_propagate_constants() {
+    let netlist = GateNetlist::new("constants".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let zero = netlist
+        .insert_constant(Logic::False, "zero".into())
+        .unwrap();
+    let and = netlist
+        .insert_gate(and_gate(), "inst_0".into(), &[a.clone(), zero])
+        .unwrap();
+    let inv = netlist
+        .insert_gate(inv_gate(), "inst_1".into(), &[and.get_output(0)])
+        .unwrap();
+    let or = netlist
+        .insert_gate(
+            Gate::new_logical("OR".into(), vec!["A".into(), "B".into()], "Y".into()),
+            "inst_2".into(),
+            &[and.get_output(0), b],
+        )
+        .unwrap();
+    or.expose_with_name("y".into());
+    inv.expose_with_name("z".into());
+    let before = simulate(&netlist);
+
+    // A dry run reports the edits without making them
+    let verilog = netlist.to_string();
+    let (folded, report) = netlist.run_pass(true, propagate_constants).unwrap();
+    assert_eq!(folded, 2);
+    assert_eq!(netlist.to_string(), verilog);
+    assert_eq!(report.added, ["tie_1".into()]);
+    assert_eq!(report.removed, ["inst_0".into(), "inst_1".into()]);
+    assert_eq!(report.modified, ["inst_2".into()]);
+
+    // The AND gate is zero and the inverter is one, but the OR gate depends on `b`
+    assert_eq!(
+        netlist.run_pass(false, propagate_constants).unwrap(),
+        (2, report)
+    );
+    assert!(netlist.verify().is_ok());
+    assert_eq!(simulate(&netlist), before);
+    let verilog = netlist.to_string();
+    assert!(!verilog.contains("AND inst_0"));
+    assert!(verilog.contains("    .A(1'b0),"));
+    assert!(verilog.contains("assign z = 1'b1;"));
+    assert_eq!(propagate_constants(&netlist).unwrap(), 0);
+}
+
+#[test]
+fn test_propagate_constants_protected() {
+    let netlist = GateNetlist::new("constants".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let zero = netlist
+        .insert_constant(Logic::False, "zero".into())
+        .unwrap();
+    let g0 = netlist
+        .insert_gate(and_gate(), "g0".into(), &[a.clone(), zero])
+        .unwrap();
+    let keep = netlist
+        .insert_gate(and_gate(), "keep".into(), &[g0.get_output(0), a])
+        .unwrap();
+    keep.set_attribute(DONT_TOUCH.to_string());
+    keep.clone().expose_with_name("y".into());
+    netlist
+        .insert_gate(
+            Gate::new_logical("OR".into(), vec!["A".into(), "B".into()], "Y".into()),
+            "or".into(),
+            &[g0.get_output(0), b],
+        )
+        .unwrap()
+        .expose_with_name("z".into());
+    let before = simulate(&netlist);
+
+    // The OR gate reads the constant, while the protected AND gate keeps reading `g0`
+    assert_eq!(propagate_constants(&netlist).unwrap(), 0);
+    assert_eq!(
+        keep.get_input(0).get_driver().unwrap().get_identifier(),
+        "g0_Y".into()
+    );
+    assert!(netlist.to_string().contains("    .A(1'b0),"));
+    assert!(netlist.verify().is_ok());
+    assert_eq!(simulate(&netlist), before);
+}
@@ -5,7 +5,7 @@ use safety_net::{
     circuit::{Identifier, Instantiable, Net},
     format_id,
     logic::Logic,
-    netlist::Netlist,
+    netlist::{Netlist, VerilogOptions},
 };
 
 #[derive(Debug, Clone)]
@@ -149,7 +149,7 @@ fn param_verilog() {
            wire y;
            wire inst_0_O;
            LUT2 #(
-             .INIT(4'b1000)
+             .INIT(4'h8)
            ) inst_0 (
              .I0(a),
              .I1(b),
@@ -163,5 +163,169 @@ fn param_verilog() {
 #[test]
 fn param_bv() {
     let param = Parameter::bitvec(3, 14);
-    assert_eq!(param.to_string(), "3'b110");
+    assert_eq!(param.to_string(), "3'h6");
+}
+
+/// A cell with an arbitrary list of parameters
+#[derive(Debug, Clone)]
+struct Primitive {
+    id: Identifier,
+    params: Vec<(Identifier, Parameter)>,
+    defaults: Vec<(Identifier, Parameter)>,
+    inputs: Vec<Net>,
+    output: Net,
+}
+
+impl Instantiable for Primitive {
+    fn get_name(&self) -> &Identifier {
+        &self.id
+    }
+
+    fn get_input_ports(&self) -> impl IntoIterator<Item = &Net> {
+        &self.inputs
+    }
+
+    fn get_output_ports(&self) -> impl IntoIterator<Item = &Net> {
+        std::slice::from_ref(&self.output)
+    }
+
+    fn has_parameter(&self, id: &Identifier) -> bool {
+        self.params.iter().any(|(k, _)| k == id)
+    }
+
+    fn get_parameter(&self, id: &Identifier) -> Option<Parameter> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == id)
+            .map(|(_, v)| v.clone())
+    }
+
+    fn set_parameter(&mut self, id: &Identifier, val: Parameter) -> Option<Parameter> {
+        let (_, v) = self.params.iter_mut().find(|(k, _)| k == id)?;
+        Some(std::mem::replace(v, val))
+    }
+
+    fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)> {
+        self.params.clone().into_iter()
+    }
+
+    fn get_default_parameter(&self, id: &Identifier) -> Option<Parameter> {
+        self.defaults
+            .iter()
+            .find(|(k, _)| k == id)
+            .map(|(_, v)| v.clone())
+    }
+
+    fn from_constant(_val: Logic) -> Option<Self> {
+        None
+    }
+
+    fn get_constant(&self) -> Option<Logic> {
+        None
+    }
+
+    fn is_seq(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn param_literals_verilog() {
+    let netlist = Netlist::new("example".to_string());
+    let a = netlist.insert_input("a".into());
+
+    let params = [
+        ("INIT", "32'hDEADBEEF"),
+        ("MODE", "\"SYNC\""),
+        ("DEPTH", "-16"),
+        ("PERIOD", "2.5"),
+    ];
+    let cell = Primitive {
+        id: "PRIM".into(),
+        params: params
+            .iter()
+            .map(|(k, v)| ((*k).into(), v.parse::<Parameter>().unwrap()))
+            .collect(),
+        defaults: vec![],
+        inputs: vec![Net::new_logic("I".into())],
+        output: Net::new_logic("O".into()),
+    };
+    let instance = netlist.insert_gate(cell, "inst_0".into(), &[a]).unwrap();
+    assert_eq!(
+        instance
+            .get_instance_type()
+            .unwrap()
+            .get_parameter(&"MODE".into()),
+        Some(Parameter::string("SYNC"))
+    );
+    instance.expose_with_name("y".into());
+
+    assert_verilog_eq!(
+        netlist.to_string(),
+        "module example (
+           a,
+           y
+         );
+           input a;
+           wire a;
+           output y;
+           wire y;
+           wire inst_0_O;
+           PRIM #(
+             .INIT(32'hDEADBEEF),
+             .MODE(\"SYNC\"),
+             .DEPTH(-16),
+             .PERIOD(2.5)
+           ) inst_0 (
+             .I(a),
+             .O(inst_0_O)
+           );
+           assign y = inst_0_O;
+         endmodule\n"
+    );
+}
+
+#[test]
+fn param_omit_defaults() {
+    let netlist = Netlist::new("example".to_string());
+    let a = netlist.insert_input("a".into());
+    let defaults = vec![
+        ("INIT".into(), Parameter::bitvec(16, 0)),
+        ("MODE".into(), Parameter::string("ASYNC")),
+    ];
+    let cell = Primitive {
+        id: "PRIM".into(),
+        params: vec![
+            ("INIT".into(), Parameter::bitvec(16, 0xAAAA)),
+            ("MODE".into(), Parameter::string("ASYNC")),
+        ],
+        defaults,
+        inputs: vec![Net::new_logic("I".into())],
+        output: Net::new_logic("O".into()),
+    };
+    let instance = netlist.insert_gate(cell, "inst_0".into(), &[a]).unwrap();
+    instance.clone().expose_with_name("y".into());
+
+    // Defaults are emitted unless asked otherwise
+    let verilog = netlist.to_string();
+    assert!(verilog.contains(".INIT(16'hAAAA),"));
+    assert!(verilog.contains(".MODE(\"ASYNC\")"));
+    assert_eq!(netlist.to_verilog(&VerilogOptions::default()), verilog);
+
+    let options = VerilogOptions {
+        omit_default_parameters: true,
+        ..Default::default()
+    };
+    let verilog = netlist.to_verilog(&options);
+    assert!(verilog.contains(".INIT(16'hAAAA)\n"));
+    assert!(!verilog.contains("MODE"));
+
+    // Without any overrides, the parameter list is dropped entirely
+    instance
+        .get_instance_type_mut()
+        .unwrap()
+        .set_parameter(&"INIT".into(), Parameter::bitvec(16, 0));
+    let verilog = netlist.to_verilog(&options);
+    assert!(verilog.contains("PRIM inst_0 ("));
+    assert!(!verilog.contains("#("));
 }
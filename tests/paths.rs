@@ -0,0 +1,154 @@
+#![cfg(feature = "derive")]
+use safety_net::{
+    error::Error,
+    graph::{PathOptions, between, longest_path, reachable, sccs, shortest_path},
+    logic::Logic,
+    netlist::{Gate, NetRef, Netlist},
+};
+use std::rc::Rc;
+
+mod common;
+use common::{Cell, Dff};
+
+/// `and1 = (a & !a) & !ff`, where `ff` registers `and1`
+fn get_loop_example() -> (Rc<Netlist<Cell>>, Vec<NetRef<Cell>>) {
+    let netlist = Netlist::new("paths".to_string());
+    let a = netlist.insert_input("a".into());
+    let clk = netlist.insert_input("clk".into());
+    let and = || {
+        Cell::Gate(Gate::new_logical(
+            "AND".into(),
+            vec!["A".into(), "B".into()],
+            "Y".into(),
+        ))
+    };
+    let inv = || {
+        Cell::Gate(Gate::new_logical(
+            "INV".into(),
+            vec!["A".into()],
+            "Y".into(),
+        ))
+    };
+    let inv0 = netlist
+        .insert_gate(inv(), "inv0".into(), std::slice::from_ref(&a))
+        .unwrap();
+    let and0 = netlist
+        .insert_gate(and(), "and0".into(), &[a.clone(), inv0.get_output(0)])
+        .unwrap();
+    let dff = Dff {
+        init: Logic::False,
+        d: "D".into(),
+        c: "C".into(),
+        q: "Q".into(),
+    };
+    let ff = netlist
+        .insert_gate(Cell::Dff(dff), "ff".into(), &[a.clone(), clk])
+        .unwrap();
+    let inv1 = netlist
+        .insert_gate(inv(), "inv1".into(), &[ff.get_output(0)])
+        .unwrap();
+    let and1 = netlist
+        .insert_gate(
+            and(),
+            "and1".into(),
+            &[and0.get_output(0), inv1.get_output(0)],
+        )
+        .unwrap();
+    ff.get_input(0).reconnect(and1.get_output(0));
+    and1.clone().expose_with_name("y".into());
+    assert!(netlist.verify().is_ok());
+    let nodes = vec![a.unwrap(), inv0, and0, ff, inv1, and1];
+    (netlist, nodes)
+}
+
+fn names(nodes: &[NetRef<Cell>]) -> Vec<String> {
+    nodes
+        .iter()
+        .map(|n| {
+            n.get_instance_name()
+                .unwrap_or_else(|| n.get_identifier())
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn reachable_nodes() {
+    let (netlist, nodes) = get_loop_example();
+    let [a, _, _, ff, _, _] = nodes.as_slice() else {
+        unreachable!()
+    };
+    let comb = PathOptions {
+        combinational_only: true,
+    };
+    assert_eq!(
+        names(&reachable(&netlist, a, &comb)),
+        ["inv0", "and0", "and1", "ff"]
+    );
+    assert_eq!(
+        names(&reachable(&netlist, a, &PathOptions::default())),
+        ["inv0", "and0", "and1", "ff", "inv1"]
+    );
+    // A register reaches itself through its feedback loop
+    assert_eq!(
+        names(&reachable(&netlist, ff, &comb)),
+        ["inv1", "and1", "ff"]
+    );
+}
+
+#[test]
+fn paths_between_nodes() {
+    let (netlist, nodes) = get_loop_example();
+    let [a, _, _, ff, _, and1] = nodes.as_slice() else {
+        unreachable!()
+    };
+    let comb = PathOptions {
+        combinational_only: true,
+    };
+    assert_eq!(
+        names(&between(&netlist, a, ff, &comb)),
+        ["a", "inv0", "and0", "ff", "and1"]
+    );
+    assert!(between(&netlist, ff, a, &comb).is_empty());
+
+    assert_eq!(
+        names(&shortest_path(&netlist, a, and1, &comb).unwrap()),
+        ["a", "and0", "and1"]
+    );
+    assert_eq!(
+        names(&longest_path(&netlist, a, and1, &comb).unwrap().unwrap()),
+        ["a", "inv0", "and0", "and1"]
+    );
+    assert!(shortest_path(&netlist, and1, a, &comb).is_none());
+    assert!(longest_path(&netlist, and1, a, &comb).unwrap().is_none());
+
+    // Through the register, the paths to `and1` loop
+    assert!(matches!(
+        longest_path(&netlist, a, and1, &PathOptions::default()),
+        Err(Error::CycleDetected(_))
+    ));
+}
+
+#[test]
+fn feedback_loops() {
+    let (netlist, _) = get_loop_example();
+    let components: Vec<Vec<String>> = sccs(&netlist, &PathOptions::default())
+        .iter()
+        .map(|c| names(c))
+        .collect();
+    assert_eq!(components.len(), 5);
+    let feedback = components.iter().position(|c| c.len() > 1).unwrap();
+    assert_eq!(components[feedback], ["ff", "inv1", "and1"]);
+    // The loop comes after the logic that drives it
+    let and0 = components.iter().position(|c| c[0] == "and0").unwrap();
+    let inv0 = components.iter().position(|c| c[0] == "inv0").unwrap();
+    assert!(inv0 < and0 && and0 < feedback);
+
+    // The register breaks the loop
+    let comb = PathOptions {
+        combinational_only: true,
+    };
+    let components = sccs(&netlist, &comb);
+    assert_eq!(components.len(), netlist.objects().count());
+    assert!(components.iter().all(|c| c.len() == 1));
+}
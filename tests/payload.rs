@@ -0,0 +1,171 @@
+use safety_net::{
+    logic::Logic,
+    netlist::{DrivenNet, Gate, GateNetlist, NetRef, Netlist, RemovePolicy},
+};
+use std::rc::Rc;
+
+fn gate(name: &str) -> Gate {
+    Gate::new_logical(name.into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+/// A full adder, where `x0 = a ^ b` and the inputs fan out to both the sum and the carry
+fn full_adder() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("payload".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let cin = netlist.insert_input("cin".into());
+    let x0 = netlist
+        .insert_gate(gate("XOR"), "x0".into(), &[a.clone(), b.clone()])
+        .unwrap();
+    netlist
+        .insert_gate(gate("XOR"), "sum".into(), &[x0.get_output(0), cin.clone()])
+        .unwrap()
+        .expose_with_name("s".into());
+    let c0 = netlist
+        .insert_gate(gate("AND"), "c0".into(), &[a, b])
+        .unwrap();
+    let c1 = netlist
+        .insert_gate(gate("AND"), "c1".into(), &[x0.get_output(0), cin])
+        .unwrap();
+    netlist
+        .insert_gate(
+            gate("OR"),
+            "cout".into(),
+            &[c0.get_output(0), c1.get_output(0)],
+        )
+        .unwrap()
+        .expose_with_name("co".into());
+    netlist
+}
+
+/// Returns the net named `name`
+fn net(netlist: &GateNetlist, name: &str) -> DrivenNet<Gate> {
+    netlist.find_net(&name.into()).unwrap()
+}
+
+/// Returns the instance named `name`
+fn inst(netlist: &GateNetlist, name: &str) -> NetRef<Gate> {
+    net(netlist, &format!("{name}_Y")).unwrap()
+}
+
+#[derive(Debug, PartialEq)]
+struct Placement {
+    x: i32,
+    y: i32,
+}
+
+#[test]
+fn object_payloads() {
+    let netlist = full_adder();
+    let inst = inst(&netlist, "x0");
+    assert!(inst.payload::<Placement>().is_none());
+    assert!(inst.set_payload(Placement { x: 1, y: 2 }).is_none());
+    assert_eq!(
+        *inst.payload::<Placement>().unwrap(),
+        Placement { x: 1, y: 2 }
+    );
+    inst.payload_mut::<Placement>().unwrap().x = 3;
+    assert_eq!(inst.payload::<Placement>().unwrap().x, 3);
+
+    // Payloads are typed, and a payload of another type stays in place
+    assert!(inst.payload::<u32>().is_none());
+    assert!(inst.take_payload::<u32>().is_none());
+    let old = inst.set_payload(7u32).unwrap();
+    assert_eq!(
+        *old.downcast::<Placement>().unwrap(),
+        Placement { x: 3, y: 2 }
+    );
+    assert_eq!(inst.take_payload::<u32>(), Some(7));
+    assert!(inst.payload::<u32>().is_none());
+}
+
+#[test]
+fn net_payloads() {
+    let netlist = full_adder();
+    let net = net(&netlist, "x0_Y");
+    net.set_payload("critical");
+    assert_eq!(*net.payload::<&str>().unwrap(), "critical");
+    // The node and its nets have separate slots
+    assert!(net.clone().unwrap().payload::<&str>().is_none());
+    *net.payload_mut::<&str>().unwrap() = "relaxed";
+    assert_eq!(net.take_payload::<&str>(), Some("relaxed"));
+
+    // Payloads go away with their node
+    let inst = net.unwrap();
+    inst.set_payload(1usize);
+    netlist
+        .remove_instance(inst, RemovePolicy::Tie(Logic::False))
+        .unwrap();
+    assert!(netlist.objects().all(|o| o.payload::<usize>().is_none()));
+}
+
+#[test]
+fn payloads_stay_with_their_nodes() {
+    let netlist = full_adder();
+
+    // The sum cone is placed, the carry cells are numbered, and the carry nets are labeled
+    inst(&netlist, "x0").set_payload(Placement { x: 0, y: 0 });
+    inst(&netlist, "sum").set_payload(Placement { x: 1, y: 0 });
+    let carry = ["c0", "c1", "cout"];
+    for (i, name) in carry.iter().enumerate() {
+        inst(&netlist, name).set_payload(i);
+        net(&netlist, &format!("{name}_Y")).set_payload(name.to_string());
+    }
+    net(&netlist, "a").set_payload(0.5f64);
+
+    // Removing a node in the middle moves the later nodes, but not their payloads
+    netlist
+        .remove_instance(inst(&netlist, "c0"), RemovePolicy::Tie(Logic::False))
+        .unwrap();
+    assert_eq!(
+        *inst(&netlist, "sum").payload::<Placement>().unwrap(),
+        Placement { x: 1, y: 0 }
+    );
+    assert!(inst(&netlist, "sum").payload::<usize>().is_none());
+    for (i, name) in carry.iter().enumerate().skip(1) {
+        assert_eq!(*inst(&netlist, name).payload::<usize>().unwrap(), i);
+        assert_eq!(
+            *net(&netlist, &format!("{name}_Y"))
+                .payload::<String>()
+                .unwrap(),
+            *name
+        );
+    }
+    assert!(net(&netlist, "b").payload::<f64>().is_none());
+    assert_eq!(*net(&netlist, "a").payload::<f64>().unwrap(), 0.5);
+    assert_eq!(
+        netlist
+            .objects()
+            .filter(|o| o.payload::<usize>().is_some())
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn traversal_marks() {
+    let netlist = full_adder();
+    let co = netlist.outputs()[1].0.clone().unwrap();
+
+    // A depth-first search through the drivers visits the shared `a`, `b` and `x0` once
+    netlist.new_traversal();
+    let mut stack = vec![co.clone()];
+    let mut visited = Vec::new();
+    while let Some(node) = stack.pop() {
+        if node.mark() {
+            visited.push(node.to_string());
+            stack.extend(node.drivers().flatten());
+        }
+    }
+    assert_eq!(visited.len(), netlist.objects().count() - 1);
+    assert!(!inst(&netlist, "sum").is_marked());
+    assert!(inst(&netlist, "x0").is_marked());
+    assert!(!co.mark());
+    co.unmark();
+    assert!(!co.is_marked());
+
+    // A new traversal clears every mark
+    netlist.new_traversal();
+    assert!(netlist.objects().all(|o| !o.is_marked()));
+    assert!(co.mark());
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "derive")]
+use safety_net::{
+    circuit::Identifier,
+    error::Error,
+    logic::Logic,
+    netlist::{DrivenNet, Gate, Netlist, seq::pipeline, verify::seq_equiv},
+};
+use std::rc::Rc;
+
+mod common;
+use common::{Cell, Dff};
+
+/// Inserts a register named `name` that captures `d` on the clock `clk`
+fn dff(
+    netlist: &Rc<Netlist<Cell>>,
+    d: DrivenNet<Cell>,
+    clk: &DrivenNet<Cell>,
+    name: Identifier,
+) -> Result<DrivenNet<Cell>, Error> {
+    let dff = Dff {
+        init: Logic::False,
+        d: "D".into(),
+        c: "C".into(),
+        q: "Q".into(),
+    };
+    Ok(netlist
+        .insert_gate(Cell::Dff(dff), name, &[d, clk.clone()])?
+        .get_output(0))
+}
+
+/// The outputs `y0`..`y3` are `a[i] ^ b`, with `stages` registers inserted by hand after each XOR
+fn xor_bus(stages: usize) -> (Rc<Netlist<Cell>>, DrivenNet<Cell>, Vec<DrivenNet<Cell>>) {
+    let netlist = Netlist::new("xor_bus".to_string());
+    let a = netlist.insert_input_escaped_logic_bus("a".to_string(), 4);
+    let b = netlist.insert_input("b".into());
+    let clk = netlist.insert_input("clk".into());
+    let mut cut = Vec::new();
+    for (i, a) in a.into_iter().enumerate() {
+        let xor = Gate::new_logical("XOR".into(), vec!["A".into(), "B".into()], "Y".into());
+        let mut y = netlist
+            .insert_gate(Cell::Gate(xor), format!("x{i}").into(), &[a, b.clone()])
+            .unwrap()
+            .get_output(0);
+        cut.push(y.clone());
+        for n in 0..stages {
+            y = dff(&netlist, y, &clk, format!("x{i}_Y_pipe{n}").into()).unwrap();
+        }
+        y.expose_with_name(format!("y{i}").into());
+    }
+    (netlist, clk, cut)
+}
+
+#[test]
+fn stages_on_a_cut() {
+    let (netlist, clk, cut) = xor_bus(0);
+    let stages = pipeline(&netlist, cut.clone(), 2, |netlist, d, name| {
+        dff(netlist, d, &clk, name)
+    })
+    .unwrap();
+    assert!(netlist.verify().is_ok());
+    assert_eq!(netlist.seq_cells().count(), 8);
+    assert_eq!(
+        stages[3]
+            .clone()
+            .unwrap()
+            .get_instance_name()
+            .unwrap()
+            .to_string(),
+        "x3_Y_pipe1"
+    );
+    for (net, (driver, _)) in stages.iter().zip(netlist.outputs()) {
+        assert_eq!(*net, driver);
+    }
+    // The XORs only drive the first stage
+    assert!(
+        cut.iter()
+            .all(|net| netlist.get_uses(net).len() == 1 && !net.is_top_level_output())
+    );
+
+    let (reference, _, _) = xor_bus(2);
+    assert!(seq_equiv(&reference, &netlist, 8).is_ok());
+}
+
+#[test]
+fn repeated_nets() {
+    let (netlist, clk, cut) = xor_bus(0);
+    let twice = [cut[0].clone(), cut[0].clone()];
+    assert!(matches!(
+        pipeline(&netlist, twice, 1, |netlist, d, name| dff(
+            netlist, d, &clk, name
+        )),
+        Err(Error::NonuniqueNets(_))
+    ));
+    assert_eq!(
+        pipeline(&netlist, cut.clone(), 0, |_, _, _| unreachable!()).unwrap(),
+        cut
+    );
+    assert_eq!(netlist.seq_cells().count(), 0);
+}
@@ -0,0 +1,272 @@
+use safety_net::{
+    error::Error,
+    netlist::{
+        DrivenNet, Gate, GateNetlist, NetRef, Netlist,
+        placement::{BookshelfOptions, Placement, PlacementMap},
+    },
+};
+use std::{collections::HashMap, rc::Rc};
+
+fn gate(name: &str) -> Gate {
+    Gate::new_logical(name.into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+/// A ripple-carry adder of `bits` full adders, so that with ten or more bits the names of the
+/// cells, like `s1` and `s10`, are prefixes of each other and sort differently as text than in the netlist
+fn ripple(bits: usize) -> Rc<GateNetlist> {
+    let netlist = Netlist::new("placement".to_string());
+    let a = netlist.insert_input_escaped_logic_bus("a".to_string(), bits);
+    let b = netlist.insert_input_escaped_logic_bus("b".to_string(), bits);
+    let mut carry = netlist.insert_input("cin".into());
+    let insert = |cell: &str, name: String, inputs: &[DrivenNet<Gate>]| {
+        netlist
+            .insert_gate(gate(cell), name.as_str().into(), inputs)
+            .unwrap()
+            .get_output(0)
+    };
+    for (i, (a, b)) in a.into_iter().zip(b).enumerate() {
+        let x = insert("XOR", format!("x{i}"), &[a.clone(), b.clone()]);
+        let s = insert("XOR", format!("s{i}"), &[x.clone(), carry.clone()]);
+        let g = insert("AND", format!("g{i}"), &[a, b]);
+        let t = insert("AND", format!("t{i}"), &[x, carry]);
+        carry = insert("OR", format!("c{i}"), &[g, t]);
+        netlist.expose_net_with_name(s, format!("y{i}").as_str().into());
+    }
+    netlist.expose_net_with_name(carry, "cout".into());
+    netlist
+}
+
+/// Returns the node named `name`
+fn node(netlist: &GateNetlist, name: &str) -> NetRef<Gate> {
+    netlist
+        .objects()
+        .find(|n| match n.get_instance_name() {
+            Some(inst) => inst == name.into(),
+            None => n.get_identifier().get_name() == name,
+        })
+        .unwrap()
+}
+
+/// Returns the lines of a Bookshelf file after its header
+fn body(file: &str) -> Vec<&str> {
+    file.lines()
+        .skip(1)
+        .filter(|l| !l.is_empty() && !l.starts_with("Num"))
+        .collect()
+}
+
+#[test]
+fn bookshelf_export() {
+    let netlist = ripple(1);
+    let mut placement = PlacementMap::new();
+    placement.insert(node(&netlist, "a[0]").get_id(), Placement::new(0.0, 5.0));
+    placement.insert(
+        node(&netlist, "x0").get_id(),
+        Placement {
+            x: 2.0,
+            y: 3.5,
+            site: Some("SLICE_X1Y3".to_string()),
+            fixed: true,
+        },
+    );
+    let options = BookshelfOptions {
+        sizes: HashMap::from([("XOR".to_string(), (2.0, 1.0))]),
+        outputs: HashMap::from([("cout".into(), (10.0, 0.0))]),
+    };
+    let files = netlist.to_bookshelf(&placement, &options).unwrap();
+    assert_eq!(
+        files.nodes,
+        "UCLA nodes 1.0\n\n\
+         NumNodes : 10\n\
+         NumTerminals : 5\n\
+         a[0] 1 1 terminal\n\
+         b[0] 1 1 terminal\n\
+         cin 1 1 terminal\n\
+         x0 2 1\n\
+         s0 2 1\n\
+         g0 1 1\n\
+         t0 1 1\n\
+         c0 1 1\n\
+         y0 1 1 terminal\n\
+         cout 1 1 terminal\n"
+    );
+    assert_eq!(
+        files.nets,
+        "UCLA nets 1.0\n\n\
+         NumNets : 8\n\
+         NumPins : 20\n\
+         NetDegree : 3 a[0]\n\ta[0] O : 0 0\n\tx0 I : 0 0\n\tg0 I : 0 0\n\
+         NetDegree : 3 b[0]\n\tb[0] O : 0 0\n\tx0 I : 0 0\n\tg0 I : 0 0\n\
+         NetDegree : 3 cin\n\tcin O : 0 0\n\ts0 I : 0 0\n\tt0 I : 0 0\n\
+         NetDegree : 3 x0_Y\n\tx0 O : 0 0\n\ts0 I : 0 0\n\tt0 I : 0 0\n\
+         NetDegree : 2 s0_Y\n\ts0 O : 0 0\n\ty0 I : 0 0\n\
+         NetDegree : 2 g0_Y\n\tg0 O : 0 0\n\tc0 I : 0 0\n\
+         NetDegree : 2 t0_Y\n\tt0 O : 0 0\n\tc0 I : 0 0\n\
+         NetDegree : 2 c0_Y\n\tc0 O : 0 0\n\tcout I : 0 0\n"
+    );
+    assert_eq!(
+        files.pl,
+        "UCLA pl 1.0\n\n\
+         a[0] 0 5 : N /FIXED\n\
+         b[0] 0 0 : N /FIXED\n\
+         cin 0 0 : N /FIXED\n\
+         x0 2 3.5 : N /FIXED\n\
+         s0 0 0 : N\n\
+         g0 0 0 : N\n\
+         t0 0 0 : N\n\
+         c0 0 0 : N\n\
+         y0 0 0 : N /FIXED\n\
+         cout 10 0 : N /FIXED\n"
+    );
+}
+
+#[test]
+fn bookshelf_follows_netlist_order() {
+    let netlist = ripple(12);
+    let files = netlist
+        .to_bookshelf(&PlacementMap::new(), &BookshelfOptions::default())
+        .unwrap();
+
+    // The nodes and positions are listed in netlist order, then the output terminals
+    let nodes: Vec<&str> = body(&files.nodes)
+        .iter()
+        .map(|l| l.split_whitespace().next().unwrap())
+        .collect();
+    let pl: Vec<&str> = body(&files.pl)
+        .iter()
+        .map(|l| l.split_whitespace().next().unwrap())
+        .collect();
+    assert_eq!(nodes, pl);
+    assert_eq!(nodes.len(), netlist.objects().count() + 13);
+    assert!(
+        files
+            .nodes
+            .contains(&format!("NumNodes : {}\n", nodes.len()))
+    );
+    let position = |name| nodes.iter().position(|n| *n == name).unwrap();
+    assert!(position("s2") < position("s10"));
+    assert!(position("c9") < position("x10"));
+    assert_eq!(position("cout"), nodes.len() - 1);
+
+    // Every net starts with its driver, and each cell drives one net and loads two
+    let mut degrees = Vec::new();
+    let mut pins: HashMap<&str, (usize, usize)> = HashMap::new();
+    for line in body(&files.nets) {
+        if let Some(degree) = line.strip_prefix("NetDegree : ") {
+            degrees.push((
+                degree.split(' ').next().unwrap().parse::<usize>().unwrap(),
+                0,
+            ));
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (degree, seen) = degrees.last_mut().unwrap();
+        assert_eq!(fields[1] == "O", *seen == 0, "{line}");
+        *seen += 1;
+        assert!(*seen <= *degree);
+        let entry = pins.entry(fields[0]).or_default();
+        match fields[1] {
+            "O" => entry.0 += 1,
+            _ => entry.1 += 1,
+        }
+    }
+    assert!(degrees.iter().all(|(degree, seen)| degree == seen));
+    let total: usize = degrees.iter().map(|(degree, _)| degree).sum();
+    assert!(
+        files
+            .nets
+            .contains(&format!("NumNets : {}\n", degrees.len()))
+    );
+    assert!(files.nets.contains(&format!("NumPins : {total}\n")));
+    for inst in netlist.instances() {
+        let name = inst.get_instance_name().unwrap().to_string();
+        assert_eq!(pins[name.as_str()].0, 1, "{name}");
+        assert_eq!(pins[name.as_str()].1, 2, "{name}");
+    }
+    assert_eq!(pins["a[11]"], (1, 0));
+    assert_eq!(pins["cout"], (0, 1));
+}
+
+#[test]
+fn bookshelf_placement_is_read_back() {
+    let netlist = ripple(12);
+
+    // Every node is on its own spot of a grid, with the inputs fixed on the left
+    let mut placement = PlacementMap::new();
+    for (i, node) in netlist.objects().enumerate() {
+        let spot = Placement {
+            fixed: node.is_an_input(),
+            ..Placement::new((i % 8) as f64 + 0.5, (i / 8) as f64)
+        };
+        placement.insert(node.get_id(), spot);
+    }
+    let files = netlist
+        .to_bookshelf(&placement, &BookshelfOptions::default())
+        .unwrap();
+    let again = netlist.read_bookshelf_pl(&files.pl).unwrap();
+    assert_eq!(again.len(), netlist.objects().count());
+    for node in netlist.objects() {
+        assert_eq!(again.get(&node.get_id()), placement.get(&node.get_id()));
+    }
+
+    // Names that are prefixes of each other are kept apart
+    let pl = "UCLA pl 1.0\n\
+              # placed by a placer\n\n\
+              a[1] 0 5 : N /FIXED\n\
+              s1 4 2 : N\n\
+              s10 6.5 2 : FS\n\
+              s11 7 3\n\
+              y1 10 0 : N /FIXED\n";
+    let placement = netlist.read_bookshelf_pl(pl).unwrap();
+    assert_eq!(placement.len(), 4);
+    assert_eq!(
+        placement.get(&node(&netlist, "a[1]").get_id()),
+        Some(&Placement {
+            fixed: true,
+            ..Placement::new(0.0, 5.0)
+        })
+    );
+    assert_eq!(
+        placement.get(&node(&netlist, "s1").get_id()),
+        Some(&Placement::new(4.0, 2.0))
+    );
+    assert_eq!(
+        placement.get(&node(&netlist, "s10").get_id()),
+        Some(&Placement::new(6.5, 2.0))
+    );
+    assert_eq!(
+        placement.get(&node(&netlist, "s11").get_id()),
+        Some(&Placement::new(7.0, 3.0))
+    );
+
+    assert!(netlist.read_bookshelf_pl("s0 4 : N").is_err());
+    assert!(netlist.read_bookshelf_pl("s0 x 2 : N").is_err());
+}
+
+#[test]
+fn bookshelf_names_overlap() {
+    // The escaped input `\x[0] ` and the instance `x[0]` are both `x[0]` in Bookshelf
+    let netlist = ripple(1);
+    let x = netlist.insert_input_escaped_logic_bus("x".to_string(), 1);
+    netlist
+        .insert_gate(gate("AND"), "x[0]".into(), &[x[0].clone(), x[0].clone()])
+        .unwrap()
+        .expose_with_name("z".into());
+    let options = BookshelfOptions::default();
+    assert!(matches!(
+        netlist.to_bookshelf(&PlacementMap::new(), &options),
+        Err(Error::NonuniqueInsts(_))
+    ));
+    assert!(matches!(
+        netlist.read_bookshelf_pl("x[0] 1 1 : N"),
+        Err(Error::NonuniqueInsts(_))
+    ));
+
+    // An output port with the name of a node is rejected too
+    let netlist = ripple(1);
+    netlist.expose_net_with_name(netlist.find_net(&"c0_Y".into()).unwrap(), "s0".into());
+    assert!(matches!(
+        netlist.to_bookshelf(&PlacementMap::new(), &options),
+        Err(Error::NonuniqueNets(_))
+    ));
+}
@@ -0,0 +1,91 @@
+use safety_net::netlist::Gate;
+use safety_net::netlist::GateNetlist;
+use safety_net::netlist::Netlist;
+use safety_net::netlist::power::{CellPower, estimate, simulated_activity};
+use std::rc::Rc;
+
+fn and_gate() -> Gate {
+    Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+fn inv_gate() -> Gate {
+    Gate::new_logical("INV".into(), vec!["A".into()], "Y".into())
+}
+
+/// `y = ~(a & b)`
+fn get_nand() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("nand".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let and = netlist
+        .insert_gate(and_gate(), "inst_0".into(), &[a, b])
+        .unwrap();
+    netlist
+        .insert_gate(inv_gate(), "inst_1".into(), &[and.get_output(0)])
+        .unwrap()
+        .expose_with_name("y".into());
+    netlist
+}
+
+fn model(gate: &Gate) -> CellPower {
+    match gate.get_gate_name().to_string().as_str() {
+        "AND" => CellPower {
+            energy: 2.0,
+            leakage: 0.5,
+        },
+        _ => CellPower {
+            energy: 1.0,
+            leakage: 0.25,
+        },
+    }
+}
+
+#[test]
+fn test_default_activity() {
+    let netlist = get_nand();
+    let report = estimate(&netlist, &0.5, model);
+    assert_eq!(report.dynamic, 1.5);
+    assert_eq!(report.leakage, 0.75);
+    assert_eq!(report.total(), 2.25);
+    assert_eq!(report.cells["AND"], 1.5);
+    assert_eq!(
+        report.to_string(),
+        "dynamic: 1.5\nleakage: 0.75\n  AND: 1.5\n  INV: 0.75\n"
+    );
+
+    // Only the inverter toggles
+    let activity = |net: &_| {
+        if netlist.outputs()[0].0 == *net {
+            1.0
+        } else {
+            0.0
+        }
+    };
+    assert_eq!(estimate(&netlist, &activity, model).dynamic, 1.0);
+}
+
+#[test]
+fn test_simulated_activity() {
+    let netlist = get_nand();
+    let activity = simulated_activity(&netlist, 1000, 0).unwrap();
+    let rate = |name: &str| {
+        *activity
+            .get(&netlist.find_net(&name.into()).unwrap().get_id())
+            .unwrap()
+    };
+    assert!((rate("a") - 0.5).abs() < 0.1);
+    // An AND gate of independent inputs is high a quarter of the time, and toggles 3/8 of the time
+    assert!((rate("inst_0_Y") - 0.375).abs() < 0.1);
+    assert_eq!(rate("inst_0_Y"), rate("inst_1_Y"));
+
+    // Dead logic costs leakage, and its nets were not simulated
+    let before = estimate(&netlist, &activity, model);
+    let a = netlist.find_net(&"a".into()).unwrap();
+    netlist
+        .insert_gate(inv_gate(), "inst_2".into(), &[a])
+        .unwrap();
+    let after = estimate(&netlist, &activity, model);
+    assert_eq!(after.total() - before.total(), 0.25);
+    assert!(netlist.clean().unwrap());
+    assert_eq!(estimate(&netlist, &activity, model), before);
+}
@@ -0,0 +1,89 @@
+use safety_net::netlist::{
+    Gate, GateNetlist, Netlist, VerilogOptions,
+    provenance::{Provenance, ProvenanceStyle},
+};
+use std::rc::Rc;
+
+fn and_gate() -> Gate {
+    Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+fn or_gate() -> Gate {
+    Gate::new_logical("OR".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+fn get_simple_example() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("example".to_string());
+
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+
+    let instance = netlist
+        .insert_gate(and_gate(), "inst_0".into(), &[a, b])
+        .unwrap();
+    instance.set_provenance(Provenance::new("top.v", 12).with_rtl_name("y_and"));
+    netlist.set_net_provenance(&instance.get_net(0), Provenance::new("top.v", 10));
+
+    instance.expose_with_name("y".into());
+
+    netlist
+}
+
+#[test]
+fn provenance_through_edits() {
+    let netlist = get_simple_example();
+    let inputs: Vec<_> = netlist.inputs().collect();
+
+    netlist.set_current_pass(Some("remap".to_string()));
+    let or = netlist
+        .insert_gate(or_gate(), "inst_1".into(), &inputs)
+        .unwrap();
+    netlist.set_current_pass(None);
+    assert_eq!(
+        or.get_provenance(),
+        Some(Provenance::default().with_pass("remap"))
+    );
+    drop(inputs);
+
+    let and = netlist.find_net(&"inst_0_Y".into()).unwrap();
+    netlist.replace_net_uses(and, &or.get_output(0)).unwrap();
+    assert_eq!(
+        or.get_provenance(),
+        Some(
+            Provenance::new("top.v", 12)
+                .with_rtl_name("y_and")
+                .with_pass("remap")
+        )
+    );
+    assert_eq!(
+        netlist.get_net_provenance(&or.get_net(0)),
+        Some(Provenance::new("top.v", 10))
+    );
+    assert!(netlist.clean().unwrap());
+
+    assert!(or.clear_provenance().is_some());
+    assert!(or.get_provenance().is_none());
+}
+
+#[test]
+fn provenance_emission() {
+    let netlist = get_simple_example();
+    assert!(!netlist.to_string().contains("top.v"));
+
+    let verilog = netlist.to_verilog(&VerilogOptions {
+        provenance: ProvenanceStyle::Comment,
+        ..Default::default()
+    });
+    assert!(verilog.contains("  // top.v:12, rtl y_and\n  AND inst_0 ("));
+    assert!(verilog.contains("  // top.v:10\n  wire inst_0_Y;"));
+
+    let verilog = netlist.to_verilog(&VerilogOptions {
+        provenance: ProvenanceStyle::Attribute,
+        ..Default::default()
+    });
+    assert!(
+        verilog
+            .contains("  (* rtl_name = \"y_and\" *)\n  (* src = \"top.v:12\" *)\n  AND inst_0 (")
+    );
+    assert!(verilog.contains("  (* src = \"top.v:10\" *)\n  wire inst_0_Y;"));
+}
@@ -0,0 +1,109 @@
+use safety_net::{
+    attribute::DONT_TOUCH,
+    netlist::{
+        DrivenNet, Gate, GateNetlist, Netlist,
+        opt::{RedundancyOptions, remove_redundancies, remove_redundancies_with},
+        testing::check_equivalent,
+    },
+};
+use std::rc::Rc;
+
+/// The inputs `a`, `b` and `c`
+fn inputs(netlist: &Rc<GateNetlist>) -> [DrivenNet<Gate>; 3] {
+    ["a", "b", "c"].map(|name| netlist.insert_input(name.into()))
+}
+
+/// `a & b | !a & c | b & c`, where the consensus term `b & c` is redundant
+fn consensus() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("consensus".to_string());
+    let [a, b, c] = inputs(&netlist);
+    let p = netlist.and(&a, &b).unwrap();
+    let na = netlist.not(&a).unwrap();
+    let q = netlist.and(&na, &c).unwrap();
+    let r = netlist.and(&b, &c).unwrap();
+    let pq = netlist.or(&p, &q).unwrap();
+    let y = netlist.or(&pq, &r).unwrap();
+    netlist.expose_net_with_name(y, "y".into());
+    netlist
+}
+
+/// `a | a & b`, whose AND gate is redundant
+fn absorption() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("absorption".to_string());
+    let [a, b, _] = inputs(&netlist);
+    let ab = netlist.and(&a, &b).unwrap();
+    let y = netlist.or(&a, &ab).unwrap();
+    netlist.expose_net_with_name(y, "y".into());
+    netlist
+}
+
+#[test]
+fn removes_consensus_term() {
+    let netlist = consensus();
+    assert!(remove_redundancies(&netlist).unwrap() >= 1);
+    netlist.clean().unwrap();
+    check_equivalent(&netlist, &consensus(), 256, 1).unwrap();
+    // The consensus AND gate is gone, and its OR gate reads a constant
+    let ands = netlist
+        .instances()
+        .filter(|i| i.get_instance_type().unwrap().get_gate_name().to_string() == "AND")
+        .count();
+    assert_eq!(ands, 2);
+}
+
+#[test]
+fn removes_absorbed_gate() {
+    let netlist = absorption();
+    assert!(remove_redundancies(&netlist).unwrap() >= 1);
+    netlist.clean().unwrap();
+    check_equivalent(&netlist, &absorption(), 256, 1).unwrap();
+    assert!(
+        netlist
+            .instances()
+            .all(|i| i.get_instance_type().unwrap().get_gate_name().to_string() != "AND")
+    );
+}
+
+#[test]
+fn keeps_irredundant_logic() {
+    let netlist = Netlist::new("irredundant".to_string());
+    let [a, b, c] = inputs(&netlist);
+    let ab = netlist.xor(&a, &b).unwrap();
+    let y = netlist.and(&ab, &c).unwrap();
+    netlist.expose_net_with_name(y, "y".into());
+    // A single pattern leaves most faults undetected, which SAT then proves detectable
+    let options = RedundancyOptions {
+        patterns: 1,
+        ..RedundancyOptions::default()
+    };
+    assert_eq!(remove_redundancies_with(&netlist, &options).unwrap(), 0);
+    assert_eq!(netlist.instances().count(), 2);
+}
+
+#[test]
+fn leaves_protected_cells() {
+    let netlist = absorption();
+    for inst in netlist.instances() {
+        inst.set_attribute(DONT_TOUCH.to_string());
+    }
+    assert_eq!(remove_redundancies(&netlist).unwrap(), 0);
+}
+
+#[test]
+fn leaves_protected_loads() {
+    let netlist = absorption();
+    let or = netlist.outputs()[0].0.clone().unwrap();
+    or.set_attribute(DONT_TOUCH.to_string());
+    // The inputs of the redundant AND gate are tied off, but it still drives the protected OR gate
+    assert_eq!(remove_redundancies(&netlist).unwrap(), 2);
+    let driver = or.get_input(1).get_driver().unwrap().unwrap();
+    assert_eq!(
+        driver
+            .get_instance_type()
+            .unwrap()
+            .get_gate_name()
+            .to_string(),
+        "AND"
+    );
+    check_equivalent(&netlist, &absorption(), 256, 1).unwrap();
+}
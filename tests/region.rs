@@ -0,0 +1,152 @@
+use safety_net::netlist::{
+    DrivenNet, Gate, GateNetlist, NetRef, Netlist,
+    region::{PBLOCK, PblockOptions},
+};
+use std::{collections::HashMap, rc::Rc};
+
+fn gate(name: &str) -> Gate {
+    Gate::new_logical(name.into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+/// A 2-bit adder with bit-sliced instance names, whose carry cone `co` and sum cone `s[1]`
+/// overlap in the propagate `p[1]` and the generate `g[0]`, while `s[0]` has a cone of its own
+fn adder() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("region".to_string());
+    let input = |name: &str| netlist.insert_input(name.into());
+    let (a, b): (Vec<DrivenNet<Gate>>, Vec<DrivenNet<Gate>>) = (0..2)
+        .map(|i| (input(&format!("a{i}")), input(&format!("b{i}"))))
+        .unzip();
+    let insert = |cell: &str, name: &str, inputs: &[DrivenNet<Gate>]| {
+        netlist
+            .insert_gate(gate(cell), name.into(), inputs)
+            .unwrap()
+            .get_output(0)
+    };
+    let p0 = insert("XOR", "p[0]", &[a[0].clone(), b[0].clone()]);
+    let g0 = insert("AND", "g[0]", &[a[0].clone(), b[0].clone()]);
+    let p1 = insert("XOR", "p[1]", &[a[1].clone(), b[1].clone()]);
+    let g1 = insert("AND", "g[1]", &[a[1].clone(), b[1].clone()]);
+    let s1 = insert("XOR", "s1", &[p1.clone(), g0.clone()]);
+    let t1 = insert("AND", "t1", &[p1, g0]);
+    let co = insert("OR", "co", &[g1, t1]);
+    netlist.expose_net_with_name(p0, "s[0]".into());
+    netlist.expose_net_with_name(s1, "s[1]".into());
+    netlist.expose_net_with_name(co, "co".into());
+    netlist
+}
+
+/// Returns the instance named `name`
+fn inst(netlist: &GateNetlist, name: &str) -> NetRef<Gate> {
+    netlist
+        .instances()
+        .find(|i| i.get_instance_name() == Some(name.into()))
+        .unwrap()
+}
+
+/// Returns the instances in the fanin cone of the output `index`
+fn cone(netlist: &GateNetlist, index: usize) -> Vec<NetRef<Gate>> {
+    let mut stack = vec![netlist.outputs()[index].0.clone().unwrap()];
+    let mut cone = Vec::new();
+    while let Some(node) = stack.pop() {
+        if !node.is_an_input() && !cone.contains(&node) {
+            stack.extend(node.drivers().flatten());
+            cone.push(node);
+        }
+    }
+    cone
+}
+
+/// Returns the instance names of `members`
+fn names(members: &[NetRef<Gate>]) -> Vec<String> {
+    members
+        .iter()
+        .map(|i| i.get_instance_name().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn instances_join_regions() {
+    let netlist = adder();
+    assert!(netlist.regions().is_empty());
+    let p0 = inst(&netlist, "p[0]");
+    let t1 = inst(&netlist, "t1");
+    assert_eq!(p0.set_region("pb_core"), None);
+    assert_eq!(t1.set_region("pb_io"), None);
+    assert_eq!(p0.get_region().as_deref(), Some("pb_core"));
+    assert_eq!(t1.set_region("pb_core").as_deref(), Some("pb_io"));
+
+    let regions = netlist.regions();
+    assert_eq!(regions.len(), 1);
+    assert_eq!(regions["pb_core"], vec![p0.clone(), t1.clone()]);
+    assert_eq!(netlist.region_members("pb_core").len(), 2);
+    assert!(netlist.region_members("pb_io").is_empty());
+
+    // Regions are attributes, which are emitted in Verilog
+    assert_eq!(
+        p0.get_attribute(&PBLOCK.to_string()),
+        Some(Some("pb_core".into()))
+    );
+    assert!(netlist.to_string().contains("(* pblock = \"pb_core\" *)"));
+
+    assert_eq!(t1.clear_region().as_deref(), Some("pb_core"));
+    assert_eq!(netlist.region_members("pb_core"), vec![p0]);
+}
+
+#[test]
+fn overlapping_cones() {
+    let netlist = adder();
+    for inst in cone(&netlist, 2) {
+        assert_eq!(inst.set_region("pb_carry"), None);
+    }
+    assert_eq!(
+        names(&netlist.region_members("pb_carry")),
+        ["g[0]", "p[1]", "g[1]", "t1", "co"]
+    );
+
+    // An instance is in one region at a time, so the shared logic moves to the sum
+    let moved: Vec<Option<String>> = cone(&netlist, 1)
+        .iter()
+        .map(|inst| inst.set_region("pb_sum"))
+        .collect();
+    assert_eq!(moved.iter().flatten().count(), 2);
+    assert!(moved.iter().flatten().all(|r| r == "pb_carry"));
+    inst(&netlist, "p[0]").set_region("pb_sum");
+
+    let regions = netlist.regions();
+    assert_eq!(regions.keys().collect::<Vec<_>>(), ["pb_carry", "pb_sum"]);
+    assert_eq!(names(&regions["pb_carry"]), ["g[1]", "t1", "co"]);
+    assert_eq!(names(&regions["pb_sum"]), ["p[0]", "g[0]", "p[1]", "s1"]);
+    assert_eq!(regions.values().map(Vec::len).sum::<usize>(), 7);
+}
+
+#[test]
+fn regions_are_written_as_xdc() {
+    let netlist = adder();
+    for inst in cone(&netlist, 1) {
+        inst.set_region("pb_b");
+    }
+    for name in ["g[1]", "co", "t1"] {
+        inst(&netlist, name).set_region("pb_a");
+    }
+    let options = PblockOptions {
+        ranges: HashMap::from([
+            (
+                "pb_a".to_string(),
+                vec!["SLICE_X0Y0:SLICE_X9Y9".to_string()],
+            ),
+            ("pb_c".to_string(), vec!["CLOCKREGION_X1Y1".to_string()]),
+        ]),
+    };
+    let mut xdc = Vec::new();
+    netlist.write_pblocks(&mut xdc, &options).unwrap();
+    assert_eq!(
+        String::from_utf8(xdc).unwrap(),
+        "create_pblock {pb_a}\n\
+         resize_pblock [get_pblocks {pb_a}] -add {SLICE_X0Y0:SLICE_X9Y9}\n\
+         add_cells_to_pblock [get_pblocks {pb_a}] [get_cells [list {g[1]} {t1} {co}]]\n\
+         create_pblock {pb_b}\n\
+         add_cells_to_pblock [get_pblocks {pb_b}] [get_cells [list {g[0]} {p[1]} {s1}]]\n\
+         create_pblock {pb_c}\n\
+         resize_pblock [get_pblocks {pb_c}] -add {CLOCKREGION_X1Y1}\n"
+    );
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "derive")]
+use safety_net::{
+    attribute::{DONT_TOUCH, Parameter},
+    circuit::{Instantiable, Net},
+    logic::Logic,
+    netlist::{Gate, Netlist, opt::replicate_registers, verify::seq_equiv},
+};
+use std::rc::Rc;
+
+mod common;
+use common::{Cell, Dff};
+
+/// A register `ff` with its value as the output `q`, that also drives `n` inverters with outputs `y0`, `y1`, ...
+fn high_fanout(n: usize) -> Rc<Netlist<Cell>> {
+    let netlist = Netlist::new("high_fanout".to_string());
+    let d = netlist.insert_input("d".into());
+    let clk = netlist.insert_input("clk".into());
+    let dff = Dff {
+        init: Logic::True,
+        d: "D".into(),
+        c: "C".into(),
+        q: "Q".into(),
+    };
+    let ff = netlist
+        .insert_gate(Cell::Dff(dff), "ff".into(), &[d, clk])
+        .unwrap();
+    for i in 0..n {
+        let inv = Gate::new_logical("INV".into(), vec!["A".into()], "Y".into());
+        netlist
+            .insert_gate(
+                Cell::Gate(inv),
+                format!("inv{i}").into(),
+                &[ff.get_output(0)],
+            )
+            .unwrap()
+            .expose_with_name(format!("y{i}").into());
+    }
+    ff.expose_with_name("q".into());
+    netlist
+}
+
+#[test]
+fn registers_are_replicated() {
+    let netlist = high_fanout(7);
+    // The output and 2 inverters stay on `ff`, and the other 5 are split between 2 copies
+    assert_eq!(replicate_registers(&netlist, 3).unwrap(), 2);
+    assert!(netlist.verify().is_ok());
+    assert_eq!(replicate_registers(&netlist, 3).unwrap(), 0);
+
+    let registers: Vec<_> = netlist.seq_cells().collect();
+    let names: Vec<_> = registers
+        .iter()
+        .map(|r| r.get_instance_name().unwrap().to_string())
+        .collect();
+    assert_eq!(names, ["ff", "ff_rep0", "ff_rep1"]);
+    let loads: Vec<_> = registers
+        .iter()
+        .map(|r| netlist.get_uses(&r.get_output(0)).len())
+        .collect();
+    assert_eq!(loads, [2, 3, 2]);
+    for register in &registers {
+        let cell = register.get_instance_type().unwrap().clone();
+        assert_eq!(
+            cell.get_parameter(&"INIT".into()),
+            Some(Parameter::Logic(Logic::True))
+        );
+        assert_eq!(
+            register.get_driver_net(1),
+            Some(Net::new_logic("clk".into()))
+        );
+    }
+
+    assert!(seq_equiv(&high_fanout(7), &netlist, 8).is_ok());
+}
+
+#[test]
+fn copies_keep_the_original_name() {
+    let netlist = high_fanout(7);
+    netlist.record_names();
+    replicate_registers(&netlist, 3).unwrap();
+    let map = netlist.name_map().unwrap();
+    assert_eq!(
+        map.instance(&"ff".into()),
+        vec![&"ff".into(), &"ff_rep0".into(), &"ff_rep1".into()]
+    );
+    assert_eq!(
+        map.net(&"ff_Q".into()),
+        vec![&"ff_Q".into(), &"ff_rep0_Q".into(), &"ff_rep1_Q".into()]
+    );
+}
+
+#[test]
+fn protected_registers_are_kept() {
+    let netlist = high_fanout(7);
+    assert!(replicate_registers(&netlist, 0).is_err());
+    netlist
+        .seq_cells()
+        .next()
+        .unwrap()
+        .set_attribute(DONT_TOUCH.to_string());
+    assert_eq!(replicate_registers(&netlist, 3).unwrap(), 0);
+    assert_eq!(netlist.seq_cells().count(), 1);
+}
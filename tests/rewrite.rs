@@ -0,0 +1,162 @@
+use bitvec::vec::BitVec;
+use safety_net::{
+    attribute::DONT_TOUCH,
+    netlist::{
+        DrivenNet, Gate, GateNetlist, Netlist,
+        rewrite::{NpnTransform, class_formula, npn_canonical, rewrite},
+        sim::Simulator,
+        synth::from_truth_table,
+        testing::{RandomConfig, assert_equivalent, assert_invariants, random_netlist},
+    },
+};
+use std::rc::Rc;
+
+fn gate(name: &str, ports: &[&str]) -> Gate {
+    Gate::new_logical(
+        name.into(),
+        ports.iter().map(|p| (*p).into()).collect(),
+        "Y".into(),
+    )
+}
+
+fn instances(netlist: &GateNetlist) -> usize {
+    netlist.objects().filter(|o| !o.is_an_input()).count()
+}
+
+/// Returns the truth table of the first output over inputs `a` to `d`
+fn truth_table(netlist: &Rc<GateNetlist>) -> u16 {
+    let mut sim = Simulator::new(netlist).unwrap();
+    sim.run(|n| match n.get_identifier().get_name() {
+        "a" => 0xAAAA,
+        "b" => 0xCCCC,
+        "c" => 0xF0F0,
+        _ => 0xFF00,
+    });
+    sim.get_word(&netlist.outputs()[0].0) as u16
+}
+
+/// Builds `table` over four inputs as a tree of `MUX` gates
+fn mux_tree(table: u16) -> Rc<GateNetlist> {
+    let netlist = Netlist::new("table".to_string());
+    let inputs: Vec<DrivenNet<Gate>> = ["a", "b", "c", "d"]
+        .iter()
+        .map(|i| netlist.insert_input((*i).into()))
+        .collect();
+    let bits: BitVec = (0..16).map(|m| (table >> m) & 1 == 1).collect();
+    from_truth_table(&netlist, &bits, &inputs, "f".into(), |_| None)
+        .unwrap()
+        .expose_with_name("y".into());
+    netlist
+}
+
+#[test]
+fn canonical_forms() {
+    for table in (0..=u16::MAX).step_by(251) {
+        let (canonical, transform) = npn_canonical(table);
+        assert_eq!(transform.apply(table), canonical);
+        assert!(canonical <= table);
+        assert_eq!(npn_canonical(canonical).0, canonical);
+        assert!(class_formula(canonical).is_some());
+    }
+    // The functions of a class share a canonical form
+    let and = 0x8888;
+    let nor = 0x1111;
+    assert_eq!(npn_canonical(and).0, npn_canonical(nor).0);
+    assert_ne!(npn_canonical(and).0, npn_canonical(0x6666).0);
+    assert_eq!(NpnTransform::IDENTITY.apply(0x1234), 0x1234);
+    assert_eq!(class_formula(0x6996), Some("^a^b^cd"));
+    assert_eq!(class_formula(0x1234), None);
+}
+
+#[test]
+fn rewrite_truth_tables() {
+    for table in (0..=u16::MAX).step_by(509) {
+        let netlist = mux_tree(table);
+        let before = instances(&netlist);
+        rewrite(&netlist).unwrap();
+        assert!(instances(&netlist) <= before);
+        // Leaves that the new cone does not read are left for clean
+        netlist.clean().unwrap();
+        assert_invariants(&netlist);
+        assert_eq!(truth_table(&netlist), table, "table {table:#06x}");
+    }
+}
+
+#[test]
+fn rewrite_xor_of_nands() {
+    let netlist = Netlist::new("xor".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let ab = netlist.nand(&a, &b).unwrap();
+    let x = netlist.nand(&a, &ab).unwrap();
+    let y = netlist.nand(&b, &ab).unwrap();
+    netlist.nand(&x, &y).unwrap().expose_with_name("y".into());
+
+    assert_eq!(rewrite(&netlist).unwrap(), 1);
+    assert_invariants(&netlist);
+    assert_eq!(instances(&netlist), 1);
+    assert_eq!(truth_table(&netlist), 0x6666);
+    assert_eq!(rewrite(&netlist).unwrap(), 0);
+}
+
+#[test]
+fn rewrite_shared_logic() {
+    // The AND of `a` and `b` has another load, so it stays and the cone saves less
+    let netlist = Netlist::new("shared".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let ab = netlist.and(&a, &b).unwrap();
+    let nb = netlist.not(&b).unwrap();
+    let anb = netlist.and(&a, &nb).unwrap();
+    netlist.or(&ab, &anb).unwrap().expose_with_name("y".into());
+    ab.expose_with_name("z".into());
+
+    assert_eq!(rewrite(&netlist).unwrap(), 1);
+    assert_invariants(&netlist);
+    // `y` is `a`, and only the AND of `z` is left
+    assert_eq!(instances(&netlist), 1);
+    assert_eq!(truth_table(&netlist), 0xAAAA);
+}
+
+#[test]
+fn rewrite_dont_touch() {
+    let netlist = Netlist::new("xor".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let ab = netlist.nand(&a, &b).unwrap();
+    let x = netlist.nand(&a, &ab).unwrap();
+    let y = netlist.nand(&b, &ab).unwrap();
+    netlist.nand(&x, &y).unwrap().expose_with_name("y".into());
+    ab.unwrap().set_attribute(DONT_TOUCH.to_string());
+
+    // Every cone that saves a cell frees the protected NAND
+    assert_eq!(rewrite(&netlist).unwrap(), 0);
+    assert_eq!(instances(&netlist), 4);
+}
+
+#[test]
+fn rewrite_random() {
+    let cells = vec![
+        gate("AND", &["A", "B"]),
+        gate("OR", &["A", "B"]),
+        gate("NAND", &["A", "B"]),
+        gate("XOR", &["A", "B"]),
+        gate("INV", &["A"]),
+        gate("MUX", &["S", "A", "B"]),
+    ];
+    let mut config = RandomConfig::new(cells);
+    config.instances = 200;
+    config.outputs = 16;
+    config.locality = Some(12);
+    for seed in 0..8 {
+        let netlist = random_netlist(seed, &config).unwrap();
+        netlist.clean().unwrap();
+        let reference = random_netlist(seed, &config).unwrap();
+        let before = instances(&netlist);
+        let rewrites = rewrite(&netlist).unwrap();
+        assert!(rewrites == 0 || instances(&netlist) < before);
+        netlist.clean().unwrap();
+        assert_invariants(&netlist);
+        assert_equivalent(&netlist, &reference, 1024);
+    }
+}
@@ -0,0 +1,132 @@
+use safety_net::netlist::{
+    Netlist,
+    sat::{Cnf, Lit, SatResult},
+};
+
+/// Returns `true` if `values` satisfies every clause of `cnf`
+fn satisfies(cnf: &Cnf, values: &[bool]) -> bool {
+    cnf.clauses()
+        .iter()
+        .all(|c| c.iter().any(|l| values[l.var()] != l.is_negated()))
+}
+
+/// The clauses that `pigeons` pigeons sit in `holes` holes, at most one per hole
+fn pigeonhole(pigeons: usize, holes: usize) -> Cnf {
+    let mut cnf = Cnf::new();
+    let sits: Vec<Vec<Lit>> = (0..pigeons)
+        .map(|_| (0..holes).map(|_| cnf.new_var()).collect())
+        .collect();
+    for pigeon in &sits {
+        cnf.add_clause(pigeon);
+    }
+    for hole in 0..holes {
+        for (p, first) in sits.iter().enumerate() {
+            for second in &sits[p + 1..] {
+                cnf.add_clause(&[!first[hole], !second[hole]]);
+            }
+        }
+    }
+    cnf
+}
+
+#[test]
+fn pigeonholes() {
+    let cnf = pigeonhole(4, 4);
+    match cnf.solve(&[], None) {
+        SatResult::Sat(values) => assert!(satisfies(&cnf, &values)),
+        result => panic!("expected a model, got {result:?}"),
+    }
+    assert_eq!(pigeonhole(5, 4).solve(&[], None), SatResult::Unsat);
+    // The proof needs more than a single conflict
+    assert_eq!(pigeonhole(5, 4).solve(&[], Some(1)), SatResult::Unknown);
+}
+
+#[test]
+fn random_formulas_match_brute_force() {
+    let mut state: u64 = 1;
+    let mut next = |n: u64| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) % n
+    };
+    let vars = 8;
+    for _ in 0..200 {
+        let mut cnf = Cnf::new();
+        for _ in 0..vars {
+            cnf.new_var();
+        }
+        for _ in 0..34 {
+            let clause: Vec<Lit> = (0..3)
+                .map(|_| Lit::new(next(vars) as usize, next(2) == 1))
+                .collect();
+            cnf.add_clause(&clause);
+        }
+        let assumption = Lit::new(next(vars) as usize, next(2) == 1);
+        let brute = (0..1u32 << vars).any(|m| {
+            let values: Vec<bool> = (0..vars).map(|v| (m >> v) & 1 == 1).collect();
+            satisfies(&cnf, &values) && values[assumption.var()] != assumption.is_negated()
+        });
+        match cnf.solve(&[assumption], None) {
+            SatResult::Sat(values) => {
+                assert!(brute);
+                assert!(satisfies(&cnf, &values));
+                assert_eq!(values[assumption.var()], !assumption.is_negated());
+            }
+            SatResult::Unsat => assert!(!brute),
+            SatResult::Unknown => panic!("no conflict limit"),
+        }
+    }
+}
+
+#[test]
+fn empty_clause_is_unsat() {
+    let mut cnf = Cnf::new();
+    cnf.add_clause(&[]);
+    assert_eq!(cnf.solve(&[], None), SatResult::Unsat);
+
+    let mut cnf = Cnf::new();
+    let a = cnf.new_var();
+    cnf.add_clause(&[a]);
+    cnf.add_clause(&[!a]);
+    assert_eq!(cnf.solve(&[], None), SatResult::Unsat);
+}
+
+#[test]
+fn netlist_to_cnf() {
+    let netlist = Netlist::new("top".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let x = netlist.xor(&a, &b).unwrap();
+    netlist.expose_net_with_name(x, "y".into());
+
+    let cnf = netlist.to_cnf().unwrap();
+    assert_eq!(
+        cnf.inputs()
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>(),
+        ["a", "b"]
+    );
+    let (name, y) = cnf.outputs()[0].clone();
+    assert_eq!(name.to_string(), "y");
+    let (a, b) = (cnf.inputs()[0].1, cnf.inputs()[1].1);
+
+    // The output is one exactly when the inputs differ
+    assert_eq!(cnf.solve(&[y, a, b], None), SatResult::Unsat);
+    assert_eq!(cnf.solve(&[y, !a, !b], None), SatResult::Unsat);
+    assert!(matches!(cnf.solve(&[y, a, !b], None), SatResult::Sat(_)));
+    assert!(matches!(cnf.solve(&[!y, a, a], None), SatResult::Sat(_)));
+
+    let dimacs = cnf.to_dimacs();
+    assert!(dimacs.starts_with("c input a 1\nc input b 2\n"));
+    assert!(dimacs.contains(&format!(
+        "p cnf {} {}\n",
+        cnf.num_vars(),
+        cnf.clauses().len()
+    )));
+    assert_eq!(
+        dimacs.lines().filter(|l| l.ends_with(" 0")).count(),
+        cnf.clauses().len()
+    );
+}
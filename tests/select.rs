@@ -0,0 +1,230 @@
+use safety_net::attribute::DONT_TOUCH;
+use safety_net::netlist::Gate;
+use safety_net::netlist::GateNetlist;
+use safety_net::netlist::NetRef;
+use safety_net::netlist::Netlist;
+use safety_net::netlist::select::Selection;
+use std::rc::Rc;
+
+fn and_gate() -> Gate {
+    Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+fn or_gate() -> Gate {
+    Gate::new_logical("OR".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+fn inv_gate() -> Gate {
+    Gate::new_logical("INV".into(), vec!["A".into()], "Y".into())
+}
+
+/// `y = ~(a & b) | a`, with an unused AND and OR gate
+fn get_example() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("example".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let and = netlist
+        .insert_gate(and_gate(), "inst_0".into(), &[a.clone(), b.clone()])
+        .unwrap();
+    let inv = netlist
+        .insert_gate(inv_gate(), "inst_1".into(), &[and.get_output(0)])
+        .unwrap();
+    netlist
+        .insert_gate(or_gate(), "inst_2".into(), &[inv.get_output(0), a.clone()])
+        .unwrap()
+        .expose_with_name("y".into());
+    netlist
+        .insert_gate(and_gate(), "unused_0".into(), &[a.clone(), b.clone()])
+        .unwrap();
+    netlist
+        .insert_gate(or_gate(), "unused_1".into(), &[a, b])
+        .unwrap();
+    netlist
+}
+
+fn names(netlist: &GateNetlist, selection: &Selection) -> Vec<String> {
+    let mut names: Vec<String> = selection
+        .objects(netlist)
+        .map(|o| match o.get_instance_name() {
+            Some(name) => name.to_string(),
+            None => o.get_identifier().to_string(),
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_queries() {
+    let netlist = get_example();
+
+    let ands = Selection::cell_type("ands", &netlist, "AND");
+    assert_eq!(ands.get_name(), "ands");
+    assert_eq!(names(&netlist, &ands), ["inst_0", "unused_0"]);
+    let insts = Selection::instance_name("insts", &netlist, "inst_?");
+    assert_eq!(names(&netlist, &insts), ["inst_0", "inst_1", "inst_2"]);
+    assert_eq!(names(&netlist, &ands.intersection(&insts)), ["inst_0"]);
+    assert_eq!(ands.union(&insts).len(), 4);
+    assert_eq!(names(&netlist, &ands.difference(&insts)), ["unused_0"]);
+
+    let nets = Selection::net_name("nets", &netlist, "unused_*");
+    assert_eq!(nets.nets(&netlist).count(), 2);
+    assert!(names(&netlist, &nets).is_empty());
+
+    let inst_1 = netlist.find_net(&"inst_1_Y".into()).unwrap();
+    inst_1
+        .clone()
+        .unwrap()
+        .set_attribute(DONT_TOUCH.to_string());
+    let protected = Selection::attribute("protected", &netlist, &DONT_TOUCH.to_string());
+    assert_eq!(names(&netlist, &protected), ["inst_1"]);
+    assert!(protected.contains_object(&inst_1.clone().unwrap()));
+    assert!(!protected.contains_net(&inst_1));
+}
+
+#[test]
+fn test_cones() {
+    let netlist = get_example();
+    let inv = netlist.find_net(&"inst_1_Y".into()).unwrap();
+    let fan_in = Selection::fan_in_cone("fan_in", &inv);
+    assert_eq!(names(&netlist, &fan_in), ["a", "b", "inst_0", "inst_1"]);
+    assert!(fan_in.contains_net(&inv));
+
+    let b = netlist.find_net(&"b".into()).unwrap();
+    let fan_out = Selection::fan_out_cone("fan_out", &netlist, &b).unwrap();
+    assert_eq!(
+        names(&netlist, &fan_out),
+        ["b", "inst_0", "inst_1", "inst_2", "unused_0", "unused_1"]
+    );
+}
+
+#[test]
+fn test_scoped_passes() {
+    let netlist = get_example();
+    let stats = netlist.stats();
+    assert_eq!(stats.inputs, 2);
+    assert_eq!(stats.instances, 5);
+    assert_eq!(stats.nets, 7);
+    assert_eq!(stats.cells["AND"], 2);
+    assert_eq!(
+        stats.to_string(),
+        "inputs: 2\ninstances: 5 (0 sequential)\nnets: 7\n  AND: 2\n  INV: 1\n  OR: 2\n"
+    );
+
+    let ands = Selection::cell_type("ands", &netlist, "AND");
+    let stats = netlist.stats_of(&ands);
+    assert_eq!(stats.instances, 2);
+    assert_eq!(stats.nets, 0);
+
+    assert!(netlist.clean_in(&ands).unwrap());
+    assert!(!netlist.clean_in(&ands).unwrap());
+    assert_eq!(netlist.stats_of(&ands).instances, 1);
+    assert_eq!(netlist.stats().cells["OR"], 2);
+    assert!(netlist.clean().unwrap());
+    assert_eq!(netlist.stats().instances, 3);
+}
+
+#[test]
+fn test_name_queries() {
+    let netlist = get_example();
+    let names = |nodes: Vec<NetRef<Gate>>| -> Vec<String> {
+        nodes
+            .iter()
+            .map(|n| n.get_instance_name().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(
+        names(netlist.query("inst_*")),
+        ["inst_0", "inst_1", "inst_2"]
+    );
+    assert_eq!(names(netlist.query("*_0")), ["inst_0", "unused_0"]);
+    assert!(netlist.query("inst").is_empty());
+
+    let nets: Vec<String> = netlist
+        .find_nets_matching("inst_?_Y")
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+    assert_eq!(nets, ["inst_0_Y", "inst_1_Y", "inst_2_Y"]);
+
+    // The index follows renames, insertions and removals
+    netlist.query("inst_1")[0].set_instance_name("inv".into());
+    assert_eq!(names(netlist.query("inst_*")), ["inst_0", "inst_2"]);
+    assert_eq!(names(netlist.query("inv")), ["inv"]);
+    netlist.find_nets_matching("inst_1_Y")[0]
+        .as_net_mut()
+        .set_identifier("n".into());
+    assert_eq!(netlist.find_nets_matching("n").len(), 1);
+    assert!(netlist.find_nets_matching("inst_1_Y").is_empty());
+
+    let d = netlist.insert_input("d[3]".into());
+    netlist
+        .insert_gate(inv_gate(), "inst_3".into(), &[d])
+        .unwrap();
+    assert_eq!(netlist.find_nets_matching("d[?]").len(), 1);
+    assert_eq!(netlist.query("inst_*").len(), 3);
+    netlist.clean().unwrap();
+    assert_eq!(names(netlist.query("*")), ["inst_0", "inv", "inst_2"]);
+}
+
+#[test]
+fn test_name_index_follows_interleaved_edits() {
+    let netlist = get_example();
+    let a = netlist.inputs().next().unwrap();
+    // The patterns are all a literal prefix followed by `*` or `?`
+    let scan = |pattern: &str| -> Vec<String> {
+        let (prefix, wildcard) = pattern.split_at(pattern.len() - 1);
+        netlist
+            .objects()
+            .filter_map(|o| o.get_instance_name())
+            .map(|n| n.to_string())
+            .filter(|n| n.starts_with(prefix) && (wildcard == "*" || n.len() == pattern.len()))
+            .collect()
+    };
+    let indexed = |pattern: &str| -> Vec<String> {
+        netlist
+            .query(pattern)
+            .iter()
+            .map(|n| n.get_instance_name().unwrap().to_string())
+            .collect()
+    };
+    // Every edit is followed by a query, which reindexes the edited nodes alone
+    for i in 0..20 {
+        let inv = netlist
+            .insert_gate(
+                inv_gate(),
+                format!("gen_{i}").as_str().into(),
+                std::slice::from_ref(&a),
+            )
+            .unwrap();
+        assert_eq!(indexed("gen_*"), scan("gen_*"));
+        if i % 3 == 0 {
+            inv.set_instance_name(format!("renamed_{i}").as_str().into());
+            assert_eq!(indexed("renamed_*"), scan("renamed_*"));
+            assert_eq!(indexed("gen_*"), scan("gen_*"));
+        }
+    }
+    netlist.clean().unwrap();
+    for pattern in ["gen_*", "renamed_*", "*", "inst_?"] {
+        assert_eq!(indexed(pattern), scan(pattern));
+    }
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_regex_queries() {
+    use regex::Regex;
+    let netlist = get_example();
+    let names: Vec<String> = netlist
+        .query(&Regex::new("^(inst|unused)_[02]$").unwrap())
+        .iter()
+        .map(|n| n.get_instance_name().unwrap().to_string())
+        .collect();
+    assert_eq!(names, ["inst_0", "inst_2", "unused_0"]);
+    let nets: Vec<String> = netlist
+        .find_nets_matching(&Regex::new(r"^inst_\d_Y$").unwrap())
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+    assert_eq!(nets, ["inst_0_Y", "inst_1_Y", "inst_2_Y"]);
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "derive")]
+use safety_net::{
+    circuit::Identifier,
+    error::Error,
+    logic::Logic,
+    netlist::{
+        DrivenNet, Gate, Netlist,
+        verify::{SeqEquivOptions, seq_equiv, seq_equiv_with},
+    },
+};
+use std::{collections::HashMap, rc::Rc};
+
+mod common;
+use common::{Cell, Dff};
+
+fn gate(name: &str, inputs: usize) -> Cell {
+    let ports = ["A", "B"][..inputs].iter().map(|p| (*p).into()).collect();
+    Cell::Gate(Gate::new_logical(name.into(), ports, "Y".into()))
+}
+
+/// Inserts a register named `name` that toggles while `en` is high, returning its output
+fn toggle(
+    netlist: &Rc<Netlist<Cell>>,
+    name: &str,
+    init: Logic,
+    en: &DrivenNet<Cell>,
+    clk: &DrivenNet<Cell>,
+) -> DrivenNet<Cell> {
+    let dff = Dff {
+        init,
+        d: "D".into(),
+        c: "C".into(),
+        q: "Q".into(),
+    };
+    let ff = netlist.insert_gate_disconnected(Cell::Dff(dff), name.into());
+    let next = netlist
+        .insert_gate(
+            gate("XOR", 2),
+            format!("{name}_next").into(),
+            &[ff.get_output(0), en.clone()],
+        )
+        .unwrap();
+    ff.get_input(0).connect(next.get_output(0));
+    ff.get_input(1).connect(clk.clone());
+    ff.get_output(0)
+}
+
+/// Toggle registers named `names` that drive the outputs `q0`, `q1`, ..., with an optional inversion of the state
+fn counters(names: &[&str], init: Logic, inverted: bool) -> Rc<Netlist<Cell>> {
+    let netlist = Netlist::new("counters".to_string());
+    let en = netlist.insert_input("en".into());
+    let clk = netlist.insert_input("clk".into());
+    for (i, name) in names.iter().enumerate() {
+        let mut q = toggle(&netlist, name, init, &en, &clk);
+        if inverted {
+            q = netlist
+                .insert_gate(gate("INV", 1), format!("{name}_inv").into(), &[q])
+                .unwrap()
+                .get_output(0);
+        }
+        q.expose_with_name(format!("q{i}").into());
+    }
+    netlist
+}
+
+#[test]
+fn recoded_state_is_equivalent() {
+    let a = counters(&["ff"], Logic::False, false);
+    // The register holds the complement of the output, and starts from 1
+    let b = counters(&["ff_n"], Logic::True, true);
+    assert!(seq_equiv(&a, &b, 16).is_ok());
+
+    // Starting from the wrong state differs on the first cycle
+    let c = counters(&["ff"], Logic::True, false);
+    let err = seq_equiv(&a, &c, 16).unwrap_err();
+    assert_eq!(err.to_string(), "At cycle 0");
+    assert!(matches!(err.root(), Error::NonequivalentOutputs(o) if o.len() == 1));
+
+    // A missing output is reported before simulating
+    let d = counters(&["ff", "ff2"], Logic::False, false);
+    assert!(matches!(
+        seq_equiv(&a, &d, 1),
+        Err(Error::NonequivalentOutputs(o)) if o.len() == 1
+    ));
+}
+
+#[test]
+fn correspondence_of_uninitialized_registers() {
+    let a = counters(&["r0", "r1"], Logic::X, false);
+    let b = counters(&["s0", "s1"], Logic::X, false);
+    let options = SeqEquivOptions {
+        correspondence: HashMap::from([
+            (Identifier::from("r0"), Identifier::from("s0")),
+            (Identifier::from("r1"), Identifier::from("s1")),
+        ]),
+        ..SeqEquivOptions::default()
+    };
+    assert!(seq_equiv_with(&a, &b, 8, &options).is_ok());
+
+    // Matched by name, the registers start from different random states
+    let swapped = counters(&["r1", "r0"], Logic::X, false);
+    assert!(seq_equiv(&a, &swapped, 8).is_err());
+    let options = SeqEquivOptions {
+        correspondence: HashMap::from([
+            (Identifier::from("r0"), Identifier::from("r1")),
+            (Identifier::from("r1"), Identifier::from("r0")),
+        ]),
+        ..SeqEquivOptions::default()
+    };
+    assert!(seq_equiv_with(&a, &swapped, 8, &options).is_ok());
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "shell")]
+
+use safety_net::{
+    error::Error,
+    netlist::{GateNetlist, Netlist},
+    shell::{Flow, Shell},
+};
+use std::rc::Rc;
+
+/// A buffer of `a` that drives `y`, next to an unused inverter
+fn buffered() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("buffered".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    netlist.not(&b).unwrap();
+    let buf = netlist.not(&netlist.not(&a).unwrap()).unwrap();
+    buf.expose_with_name("y".into());
+    netlist
+}
+
+/// Runs `script` in `shell` and returns its output
+fn run(shell: &mut Shell, script: &str) -> String {
+    let mut out = Vec::new();
+    shell.run(script.as_bytes(), &mut out, None).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn inspect_and_edit() {
+    let mut shell = Shell::new();
+    shell.set_netlist(buffered());
+    let out = run(&mut shell, "stats\nquery inv_*\n");
+    assert!(out.starts_with("inputs: 2\ninstances: 3 (0 sequential)\n"));
+    assert!(out.contains("  INV: 3\n"));
+    assert_eq!(out.matches(" INV\n").count(), 3);
+
+    let out = run(&mut shell, "clean # drops the unused inverter\nstats\n");
+    assert!(out.starts_with("removed 1 circuit nodes\n"));
+    assert!(out.contains("instances: 2"));
+
+    // Bypass the double inversion
+    let nets = run(&mut shell, "nets *");
+    let driver = nets.lines().last().unwrap().to_string();
+    let out = run(&mut shell, &format!("replace {driver} a\nverify\nclean\n"));
+    assert_eq!(out, "ok\nremoved 2 circuit nodes\n");
+    let verilog = run(&mut shell, "write_verilog");
+    assert!(verilog.contains("assign y = a;"));
+}
+
+#[test]
+fn errors_keep_the_session() {
+    let mut shell = Shell::new();
+    let out = run(
+        &mut shell,
+        "frobnicate\nquery\nreplace x y\nnew adder\nquit\nnew never\n",
+    );
+    let errors: Vec<&str> = out.lines().collect();
+    assert_eq!(errors.len(), 3);
+    assert!(errors.iter().all(|e| e.starts_with("error: ")));
+    assert!(errors[0].contains("frobnicate"));
+    assert_eq!(shell.netlist().get_name().as_str(), "adder");
+
+    let mut out = Vec::new();
+    assert!(matches!(
+        shell.execute("load x.txt", &mut out),
+        Err(Error::Unsupported(_))
+    ));
+    assert!(matches!(
+        shell.execute("stats a b", &mut out),
+        Err(Error::ArgumentMismatch(0, 2))
+    ));
+    assert_eq!(shell.execute("", &mut out).unwrap(), Flow::Continue);
+    assert_eq!(shell.execute("quit", &mut out).unwrap(), Flow::Quit);
+    assert!(out.is_empty());
+
+    let help = run(&mut shell, "help");
+    assert_eq!(help.lines().count(), 12);
+}
+
+#[test]
+fn load_and_save() {
+    let dir = std::env::temp_dir().join(format!("safety-net-shell-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut shell = Shell::new();
+    shell.set_netlist(buffered());
+    for ext in ["json", "snet", "aag", "v"] {
+        let path = dir.join(format!("buffered.{ext}"));
+        run(&mut shell, &format!("save {}", path.display()));
+        assert!(path.exists());
+    }
+    let expected = shell.netlist().to_string();
+
+    let mut loaded = Shell::new();
+    for ext in ["json", "snet"] {
+        let path = dir.join(format!("buffered.{ext}"));
+        let out = run(&mut loaded, &format!("load {}", path.display()));
+        assert_eq!(out, "loaded `buffered` with 2 inputs and 3 instances\n");
+        assert_eq!(loaded.netlist().to_string(), expected);
+    }
+    let out = run(
+        &mut loaded,
+        &format!("load {}", dir.join("buffered.aag").display()),
+    );
+    assert_eq!(out, "loaded `buffered` with 2 inputs and 0 instances\n");
+    let out = run(
+        &mut loaded,
+        &format!("load {}", dir.join("buffered.v").display()),
+    );
+    assert!(out.starts_with("error: Unsupported"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
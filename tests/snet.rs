@@ -0,0 +1,127 @@
+#![cfg(feature = "serde")]
+use safety_net::{
+    attribute::Parameter,
+    circuit::Net,
+    error::Error,
+    logic::Logic,
+    netlist::{Gate, GateNetlist, Netlist},
+};
+use std::rc::Rc;
+
+fn and_gate() -> Gate {
+    Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+/// A chain of AND gates over a bus, with a constant, attributes and escaped names
+fn get_chain(n: usize) -> Rc<GateNetlist> {
+    let netlist = GateNetlist::new("chain".to_string());
+    let bus = netlist.insert_input_escaped_logic_bus("d".to_string(), n);
+    let one = netlist.insert_constant(Logic::True, "tie".into()).unwrap();
+    let mut y = netlist.insert_input(Net::new_logic("\\en$ ".into()));
+    for (i, d) in bus.into_iter().enumerate() {
+        let inputs = if i == 0 { [d, one.clone()] } else { [d, y] };
+        y = netlist
+            .insert_gate(and_gate(), format!("g{i}").into(), &inputs)
+            .unwrap()
+            .get_output(0);
+    }
+    y.clone()
+        .unwrap()
+        .insert_attribute("keep".to_string(), Parameter::Integer(1));
+    y.expose_with_name("y".into());
+    netlist.insert_output("z".into()).unwrap();
+    netlist
+        .set_port_order(["y".into(), "\\en$ ".into()])
+        .unwrap();
+    netlist
+}
+
+#[test]
+fn test_snet_roundtrip() {
+    let netlist = get_chain(4);
+    let bytes = netlist.to_snet(None).unwrap();
+    assert_eq!(&bytes[..4], b"SNET");
+    let loaded: Rc<GateNetlist> = Netlist::from_snet(&bytes).unwrap();
+    assert!(loaded.verify().is_ok());
+    assert_eq!(loaded.to_string(), netlist.to_string());
+    assert_eq!(loaded.objects().count(), netlist.objects().count());
+
+    // The cell type is stored once
+    let mut json = Vec::new();
+    get_chain(64)
+        .reclaim()
+        .unwrap()
+        .serialize(&mut json)
+        .unwrap();
+    let bytes = get_chain(64).to_snet(None).unwrap();
+    assert!(bytes.len() * 4 < json.len());
+
+    // Snapshots are deterministic
+    assert_eq!(bytes, get_chain(64).to_snet(None).unwrap());
+}
+
+#[test]
+fn test_snet_malformed() {
+    let bytes = get_chain(2).to_snet(None).unwrap();
+    let load = |bytes: &[u8]| Netlist::<Gate>::from_snet(bytes).map(|_| ());
+    assert!(matches!(load(&bytes[..10]), Err(Error::ParseError(_))));
+    assert!(matches!(
+        load(&bytes[..bytes.len() - 4]),
+        Err(Error::ParseError(_))
+    ));
+    let mut version = bytes.clone();
+    version[4] = 9;
+    assert!(matches!(load(&version), Err(Error::ParseError(_))));
+
+    // The first string offset of a snapshot is zero, and the count of strings comes first
+    let mut count = bytes.clone();
+    count[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(load(&count), Err(Error::ParseError(_))));
+
+    // The first gate reads the input `d[0]` directly: an input has no numbered outputs, and the constant one output
+    let direct = [0, u32::MAX, u32::MAX, u32::MAX]
+        .map(u32::to_le_bytes)
+        .concat();
+    let pin = bytes.windows(16).position(|w| w == direct).unwrap();
+    let mut output = bytes.clone();
+    output[pin + 4..pin + 8].copy_from_slice(&0u32.to_le_bytes());
+    assert!(matches!(load(&output), Err(Error::ParseError(_))));
+    output[pin..pin + 4].copy_from_slice(&2u32.to_le_bytes());
+    output[pin + 4..pin + 8].copy_from_slice(&1u32.to_le_bytes());
+    assert!(matches!(load(&output), Err(Error::ParseError(_))));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_snet_compressed() {
+    let netlist = get_chain(64);
+    let bytes = netlist.to_snet(None).unwrap();
+    let compressed = netlist.to_snet(Some(0)).unwrap();
+    assert!(compressed.len() < bytes.len());
+    let loaded = Netlist::<Gate>::from_snet(&compressed).unwrap();
+    assert_eq!(loaded.to_string(), netlist.to_string());
+
+    // The length in the header is not trusted to allocate the body
+    let mut len = compressed.clone();
+    len[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(matches!(
+        Netlist::<Gate>::from_snet(&len),
+        Err(Error::ParseError(_))
+    ));
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn test_snet_compressed() {
+    let netlist = get_chain(2);
+    assert!(matches!(
+        netlist.to_snet(Some(0)),
+        Err(Error::Unsupported(_))
+    ));
+    let mut bytes = netlist.to_snet(None).unwrap();
+    bytes[6] = 1;
+    assert!(matches!(
+        Netlist::<Gate>::from_snet(&bytes),
+        Err(Error::Unsupported(_))
+    ));
+}
@@ -0,0 +1,139 @@
+#![cfg(feature = "derive")]
+
+use safety_net::{
+    attribute::Parameter,
+    circuit::Instantiable,
+    error::Error,
+    logic::Logic,
+    netlist::{Gate, Netlist, stimulus::Stimulus},
+};
+use std::rc::Rc;
+
+mod common;
+use common::{Cell, Dff};
+
+/// The output `q` is `a & b` delayed by one cycle, where `g0_Y` is the internal net `a & b`
+fn and_register() -> Rc<Netlist<Cell>> {
+    let netlist = Netlist::new("and_register".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let clk = netlist.insert_input("clk".into());
+    let and = Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into());
+    let and = netlist
+        .insert_gate(Cell::Gate(and), "g0".into(), &[a, b])
+        .unwrap();
+    let dff = Dff {
+        init: Logic::False,
+        d: "D".into(),
+        c: "C".into(),
+        q: "Q".into(),
+    };
+    netlist
+        .insert_gate(Cell::Dff(dff), "ff".into(), &[and.get_output(0), clk])
+        .unwrap()
+        .expose_with_name("q".into());
+    netlist
+}
+
+#[test]
+fn stimulus_passes() {
+    let netlist = and_register();
+    Stimulus::clock("clk")
+        .signal("a", [0, 1, 1, 0])
+        .signal("b", [1, 1, 0, 1])
+        .expect("g0_Y", [0, 1, 0, 0])
+        .expect("q", [0, 0, 1, 0])
+        .assert(&netlist);
+
+    // Inputs keep their last value, and undriven inputs are unknown
+    Stimulus::clock("clk")
+        .signal("a", [1])
+        .signal("b", [0, 1, 1])
+        .expect("q", [0, 0, 1])
+        .assert(&netlist);
+    Stimulus::new()
+        .signal("a", [1])
+        .expect("g0_Y", [Logic::X])
+        .assert(&netlist);
+}
+
+#[test]
+fn stimulus_reports_the_first_mismatch() {
+    let netlist = and_register();
+    let err = Stimulus::clock("clk")
+        .signal("a", [1, 1, 1])
+        .signal("b", [1, 0, 1])
+        .expect("g0_Y", [1, 0, 1])
+        .expect("q", [0, 1, 1])
+        .check(&netlist)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "At cycle 2, expected q = 1'b1, got 1'b0");
+    assert_eq!(err.code(), "nonequivalent-outputs");
+
+    let stimulus = Stimulus::new().signal("c", [0]);
+    assert!(matches!(
+        stimulus.check(&netlist),
+        Err(Error::PortNotFound(_))
+    ));
+    let stimulus = Stimulus::new().expect("z", [0]);
+    assert!(matches!(
+        stimulus.check(&netlist),
+        Err(Error::NetNotFound(_))
+    ));
+}
+
+#[test]
+#[should_panic(expected = "At cycle 0, expected g0_Y = 1'b1, got 1'b0")]
+fn stimulus_assert_panics() {
+    Stimulus::new()
+        .signal("a", [0])
+        .signal("b", [1])
+        .expect("g0_Y", [1])
+        .assert(&and_register());
+}
+
+#[test]
+fn registers_start_from_init() {
+    let netlist = and_register();
+    let ff = netlist.seq_cells().next().unwrap();
+    let mut cell = ff.get_instance_type_mut().unwrap();
+    cell.set_parameter(&"INIT".into(), Parameter::Logic(Logic::True));
+    drop(cell);
+    Stimulus::clock("clk").expect("q", [1]).assert(&netlist);
+}
+
+#[test]
+fn testbench_is_written() {
+    let netlist = and_register();
+    let stimulus = Stimulus::clock("clk")
+        .signal("a", [0, 1])
+        .signal("b", [1])
+        .expect("g0_Y", [0, 1])
+        .expect("q", [0]);
+    let mut tb = Vec::new();
+    netlist.write_testbench(&mut tb, &stimulus).unwrap();
+    let tb = String::from_utf8(tb).unwrap();
+    assert!(tb.starts_with("`timescale 1ns / 1ps\n\nmodule and_register_tb;\n"));
+    assert!(tb.contains("  reg a;\n  reg b;\n  reg clk;\n  wire q;\n"));
+    assert!(tb.contains(
+        "  and_register dut (\n    .a(a),\n    .b(b),\n    .clk(clk),\n    .q(q)\n  );\n"
+    ));
+    assert!(tb.contains(
+        "    // Cycle 1\n    a = 1'b1;\n    #5;\n    if (dut.g0_Y !== 1'b1) begin\n      \
+         $display(\"At cycle 1, expected g0_Y = 1'b1, got %b\", dut.g0_Y);\n"
+    ));
+    assert_eq!(tb.matches("if (q !== ").count(), 1);
+    assert_eq!(tb.matches("clk = 1'b1;").count(), 2);
+    assert!(tb.ends_with("    $finish;\n  end\nendmodule\n"));
+
+    let stimulus = Stimulus::clock("clock");
+    assert!(matches!(
+        netlist.write_testbench(&mut Vec::new(), &stimulus),
+        Err(Error::PortNotFound(_))
+    ));
+    let stimulus = Stimulus::new().expect("z", [1]);
+    assert!(matches!(
+        netlist.write_testbench(&mut Vec::new(), &stimulus),
+        Err(Error::NetNotFound(_))
+    ));
+}
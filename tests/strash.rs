@@ -0,0 +1,105 @@
+use safety_net::{
+    attribute::DONT_TOUCH,
+    netlist::{DrivenNet, Gate, GateNetlist, Netlist, RemovePolicy},
+};
+use std::rc::Rc;
+
+/// The inputs `a` and `b`
+fn inputs(netlist: &Rc<GateNetlist>) -> [DrivenNet<Gate>; 2] {
+    [
+        netlist.insert_input("a".into()),
+        netlist.insert_input("b".into()),
+    ]
+}
+
+#[test]
+fn shares_equal_gates() {
+    let netlist = Netlist::new("top".to_string());
+    assert!(!netlist.is_strash_enabled());
+    netlist.set_strash(true);
+    assert!(netlist.is_strash_enabled());
+    let [a, b] = inputs(&netlist);
+    let x = netlist.and(&a, &b).unwrap();
+    assert_eq!(netlist.and(&a, &b).unwrap(), x);
+    assert_eq!(netlist.instances().count(), 1);
+
+    // Other cells and other input orders are different gates
+    let y = netlist.or(&a, &b).unwrap();
+    let z = netlist.and(&b, &a).unwrap();
+    assert!(y != x && z != x);
+    assert_eq!(netlist.instances().count(), 3);
+
+    // The sharing reaches through the logic built on shared gates
+    let w = netlist.xor(&x, &y).unwrap();
+    let x2 = netlist.and(&a, &b).unwrap();
+    let y2 = netlist.or(&a, &b).unwrap();
+    assert_eq!(netlist.xor(&x2, &y2).unwrap(), w);
+    assert_eq!(netlist.instances().count(), 4);
+
+    netlist.set_strash(false);
+    assert_ne!(netlist.and(&a, &b).unwrap(), x);
+    assert_eq!(netlist.instances().count(), 5);
+}
+
+#[test]
+fn existing_gates_are_shared_but_not_merged() {
+    let netlist = Netlist::new("top".to_string());
+    let [a, b] = inputs(&netlist);
+    let first = netlist.and(&a, &b).unwrap();
+    let second = netlist.and(&a, &b).unwrap();
+    assert_ne!(first, second);
+
+    netlist.set_strash(true);
+    assert_eq!(netlist.instances().count(), 2);
+    assert_eq!(netlist.and(&a, &b).unwrap(), first);
+    assert_eq!(netlist.instances().count(), 2);
+}
+
+#[test]
+fn stale_and_protected_gates_are_not_shared() {
+    let netlist = Netlist::new("top".to_string());
+    netlist.set_strash(true);
+    let [a, b] = inputs(&netlist);
+
+    // A removed gate
+    let x = netlist.and(&a, &b).unwrap();
+    netlist
+        .remove_instance(x.unwrap(), RemovePolicy::Reject)
+        .unwrap();
+    let x = netlist.and(&a, &b).unwrap();
+    assert_eq!(netlist.instances().count(), 1);
+
+    // A reconnected gate
+    let inst = x.clone().unwrap();
+    b.connect(inst.get_input(0));
+    let y = netlist.and(&a, &b).unwrap();
+    assert_ne!(y, x);
+    assert_eq!(netlist.and(&a, &b).unwrap(), y);
+
+    // A protected gate
+    let z = netlist.or(&a, &b).unwrap();
+    z.clone().unwrap().set_attribute(DONT_TOUCH.to_string());
+    assert_ne!(netlist.or(&a, &b).unwrap(), z);
+}
+
+#[test]
+fn gates_are_shared_after_clean() {
+    let netlist = Netlist::new("top".to_string());
+    netlist.set_strash(true);
+    let [a, b] = inputs(&netlist);
+
+    // The unused gate comes first, so cleaning it renumbers the others
+    netlist.or(&a, &b).unwrap();
+    let x = netlist.and(&a, &b).unwrap();
+    let y = netlist.xor(&x, &b).unwrap();
+    netlist.expose_net_with_name(y.clone(), "y".into());
+    netlist.clean().unwrap();
+    assert_eq!(netlist.instances().count(), 2);
+    assert_eq!(netlist.and(&a, &b).unwrap(), x);
+    assert_eq!(netlist.xor(&x, &b).unwrap(), y);
+    assert_eq!(netlist.instances().count(), 2);
+
+    netlist.canonicalize(&Default::default()).unwrap();
+    assert_eq!(netlist.xor(&x, &b).unwrap(), y);
+    assert_eq!(netlist.instances().count(), 2);
+}
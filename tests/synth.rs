@@ -0,0 +1,161 @@
+#![cfg(feature = "derive")]
+use bitvec::{bitvec, order::Lsb0, vec::BitVec};
+use safety_net::{
+    attribute::Parameter,
+    circuit::{Evaluate, Identifier, Instantiable, Net},
+    derive::{Evaluate, Instantiable},
+    error::Error,
+    format_id,
+    logic::Logic,
+    netlist::{DrivenNet, Gate, Netlist, sim::Simulator, synth::from_truth_table},
+};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Instantiable, Evaluate)]
+#[cell_name = "LUT{k}"]
+#[evaluate(table = "INIT")]
+struct Lut {
+    #[cell_name]
+    name: Identifier,
+    k: usize,
+    #[parameter(name = "INIT")]
+    init: BitVec,
+    #[input_port]
+    inputs: Vec<Net>,
+    #[output_port]
+    output: Net,
+}
+
+#[derive(Debug, Clone, Instantiable, Evaluate)]
+#[instantiable(accessors)]
+enum Cell {
+    #[instantiable(constant)]
+    Gate(Gate),
+    Lut(Lut),
+}
+
+/// K-LUTs for the tables of at most `k` inputs
+fn lut(k: usize) -> impl Fn(&BitVec) -> Option<Cell> {
+    move |table| {
+        let n = table.len().trailing_zeros() as usize;
+        (n <= k).then(|| {
+            Cell::Lut(Lut {
+                name: Lut::cell_name(&n),
+                k: n,
+                init: table.clone(),
+                inputs: (0..n).map(|i| Net::new_logic(format_id!("I{i}"))).collect(),
+                output: Net::new_logic("O".into()),
+            })
+        })
+    }
+}
+
+/// Builds `bits` over inputs `a`, `b` and `c`, and checks it for every combination of them
+fn check<I>(netlist: &Rc<Netlist<I>>, bits: &BitVec, lut: impl Fn(&BitVec) -> Option<I>)
+where
+    I: Evaluate + From<Gate>,
+{
+    let inputs: Vec<DrivenNet<I>> = ["a", "b", "c"]
+        .iter()
+        .map(|i| netlist.insert_input((*i).into()))
+        .collect();
+    from_truth_table(netlist, bits, &inputs, "f".into(), lut)
+        .unwrap()
+        .expose_with_name("y".into());
+    assert!(netlist.verify().is_ok());
+
+    let mut sim = Simulator::new(netlist).unwrap();
+    sim.run(|n| match n.get_identifier().to_string().as_str() {
+        "a" => 0b1010_1010,
+        "b" => 0b1100_1100,
+        _ => 0b1111_0000,
+    });
+    let y = sim.get_word(&netlist.outputs()[0].0);
+    for (lane, bit) in bits.iter().enumerate() {
+        assert_eq!((y >> lane) & 1 == 1, *bit, "pattern {lane}");
+    }
+}
+
+#[test]
+fn test_truth_table_gates() {
+    // The majority of three inputs
+    let majority = bitvec![0, 0, 0, 1, 0, 1, 1, 1];
+    let netlist = Netlist::<Gate>::new("majority".to_string());
+    check(&netlist, &majority, |_| None);
+    let verilog = netlist.to_string();
+    assert!(verilog.contains("MUX f_"));
+    assert!(!verilog.contains("LUT"));
+
+    // `c ? !a : b` needs an inverter
+    let netlist = Netlist::<Gate>::new("inverted".to_string());
+    check(&netlist, &bitvec![0, 0, 1, 1, 1, 0, 1, 0], |_| None);
+    assert!(netlist.to_string().contains("INV f_"));
+}
+
+#[test]
+fn test_truth_table_luts() {
+    let majority = bitvec![0, 0, 0, 1, 0, 1, 1, 1];
+    let netlist = Netlist::<Cell>::new("majority".to_string());
+    check(&netlist, &majority, lut(3));
+    assert_eq!(netlist.objects().count(), 3 + 1);
+    assert!(netlist.to_string().contains(".INIT(8'hE8)"));
+
+    // Two 2-LUTs and a MUX, the second LUT being an OR gate
+    let netlist = Netlist::<Cell>::new("majority".to_string());
+    check(&netlist, &majority, lut(2));
+    assert_eq!(netlist.objects().count(), 3 + 3);
+    assert_eq!(
+        netlist
+            .objects()
+            .filter(|o| o.get_instance_type().is_some_and(|i| i.as_lut().is_some()))
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_truth_table_support() {
+    let netlist = Netlist::<Cell>::new("support".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+
+    // `b` alone, and its inversion
+    let y = from_truth_table(
+        &netlist,
+        &bitvec![0, 0, 1, 1],
+        &[a.clone(), b.clone()],
+        "f".into(),
+        lut(2),
+    )
+    .unwrap();
+    assert_eq!(y, b);
+    let y = from_truth_table(
+        &netlist,
+        &bitvec![1, 1, 0, 0],
+        &[a.clone(), b.clone()],
+        "g".into(),
+        lut(2),
+    )
+    .unwrap();
+    assert_eq!(y.get_identifier(), "g_0_Y".into());
+    assert!(y.get_instance_type().unwrap().as_gate().is_some());
+
+    // Constants
+    let y = from_truth_table(
+        &netlist,
+        &bitvec![1, 1, 1, 1],
+        &[a.clone(), b.clone()],
+        "h".into(),
+        lut(2),
+    )
+    .unwrap();
+    assert_eq!(
+        y.get_instance_type().unwrap().get_constant(),
+        Some(Logic::True)
+    );
+
+    assert!(matches!(
+        from_truth_table(&netlist, &bitvec![0, 1], &[a, b], "i".into(), lut(2)),
+        Err(Error::ArgumentMismatch(4, 2))
+    ));
+}
@@ -0,0 +1,346 @@
+use safety_net::{
+    assert_netlist_eq,
+    attribute::Parameter,
+    circuit::{Identifier, Instantiable, Net},
+    error::Error,
+    logic::Logic,
+    netlist::{
+        Gate, GateNetlist, NetRef, Netlist, VerilogOptions,
+        annotation::ObjectId,
+        testing::{
+            RandomConfig, assert_equivalent, assert_invariants, check_acyclic, check_connected,
+            check_equivalent, check_no_dangling, random_netlist, verilog_diff,
+        },
+    },
+};
+use std::{collections::HashMap, rc::Rc};
+
+/// A gate that is sequential when named "REG"
+#[derive(Debug, Clone)]
+struct Cell(Gate);
+
+impl Instantiable for Cell {
+    fn get_name(&self) -> &Identifier {
+        self.0.get_name()
+    }
+
+    fn get_input_ports(&self) -> impl IntoIterator<Item = &Net> {
+        self.0.get_input_ports()
+    }
+
+    fn get_output_ports(&self) -> impl IntoIterator<Item = &Net> {
+        self.0.get_output_ports()
+    }
+
+    fn has_parameter(&self, id: &Identifier) -> bool {
+        self.0.has_parameter(id)
+    }
+
+    fn get_parameter(&self, id: &Identifier) -> Option<Parameter> {
+        self.0.get_parameter(id)
+    }
+
+    fn set_parameter(&mut self, id: &Identifier, val: Parameter) -> Option<Parameter> {
+        self.0.set_parameter(id, val)
+    }
+
+    fn parameters(&self) -> impl Iterator<Item = (Identifier, Parameter)> {
+        self.0.parameters()
+    }
+
+    fn from_constant(val: Logic) -> Option<Self> {
+        Gate::from_constant(val).map(Cell)
+    }
+
+    fn get_constant(&self) -> Option<Logic> {
+        self.0.get_constant()
+    }
+
+    fn is_seq(&self) -> bool {
+        self.0.get_name().get_name() == "REG"
+    }
+}
+
+fn and_gate() -> Gate {
+    Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+fn inv_gate() -> Gate {
+    Gate::new_logical("INV".into(), vec!["I".into()], "O".into())
+}
+
+fn mux_gate() -> Gate {
+    Gate::new_logical(
+        "MUX".into(),
+        vec!["S".into(), "A".into(), "B".into()],
+        "Y".into(),
+    )
+}
+
+/// A description of the structure that doesn't depend on output ordering
+fn structure(netlist: &GateNetlist) -> Vec<String> {
+    netlist
+        .objects()
+        .map(|o| {
+            let drivers: Vec<_> = o.driver_nets().flatten().map(|n| n.to_string()).collect();
+            format!("{o} <- {}", drivers.join(","))
+        })
+        .collect()
+}
+
+#[test]
+fn random_is_reproducible() {
+    let config = RandomConfig::new(vec![and_gate(), inv_gate(), mux_gate()]);
+    let a = random_netlist(7, &config).unwrap();
+    let b = random_netlist(7, &config).unwrap();
+    let c = random_netlist(8, &config).unwrap();
+    assert_eq!(structure(&a), structure(&b));
+    assert_ne!(structure(&a), structure(&c));
+}
+
+#[test]
+fn random_is_well_formed() {
+    let mut config = RandomConfig::new(vec![and_gate(), inv_gate(), mux_gate()]);
+    config.instances = 100;
+    config.locality = Some(10);
+    for seed in 0..16 {
+        let netlist = random_netlist(seed, &config).unwrap();
+        assert!(netlist.verify().is_ok());
+        assert_eq!(netlist.inputs().count(), config.inputs);
+        assert_eq!(netlist.outputs().len(), config.outputs);
+        assert_eq!(netlist.objects().count(), config.inputs + config.instances);
+        assert!(netlist.objects().all(|o| o.is_fully_connected()));
+    }
+}
+
+#[test]
+fn random_weights() {
+    let mut config = RandomConfig::new(vec![and_gate(), inv_gate()]);
+    config.cells[1].1 = 0;
+    let netlist = random_netlist(3, &config).unwrap();
+    assert!(
+        netlist
+            .objects()
+            .filter_map(|o| o.get_instance_type().map(|i| i.get_gate_name().to_string()))
+            .all(|n| n == "AND")
+    );
+}
+
+#[test]
+fn random_sequential() {
+    let reg = Cell(Gate::new_logical(
+        "REG".into(),
+        vec!["D".into()],
+        "Q".into(),
+    ));
+    let mut config = RandomConfig::new(vec![Cell(and_gate()), Cell(inv_gate())]);
+    config.seq_depth = 2;
+    assert!(random_netlist(0, &config).is_err());
+
+    config.cells.push((reg, 1));
+    let netlist = random_netlist(0, &config).unwrap();
+    assert!(netlist.verify().is_ok());
+    let regs = netlist
+        .objects()
+        .filter(|o| o.get_instance_type().is_some_and(|i| i.is_seq()))
+        .count();
+    assert_eq!(regs, config.seq_depth * config.rank_width);
+
+    // Count the fewest registers on any path from an input
+    fn ranks(node: &NetRef<Cell>, memo: &mut HashMap<ObjectId, usize>) -> usize {
+        if node.is_an_input() {
+            return 0;
+        }
+        if let Some(r) = memo.get(&node.get_id()) {
+            return *r;
+        }
+        let is_seq = node.get_instance_type().unwrap().is_seq();
+        let r = node
+            .drivers()
+            .map(|d| ranks(&d.unwrap(), memo))
+            .min()
+            .unwrap()
+            + usize::from(is_seq);
+        memo.insert(node.get_id(), r);
+        r
+    }
+
+    let mut memo = HashMap::new();
+    for (driver, _) in netlist.outputs() {
+        assert!(ranks(&driver.unwrap(), &mut memo) >= config.seq_depth);
+    }
+
+    config.rank_width = 0;
+    assert!(random_netlist(0, &config).is_err());
+}
+
+fn nand_gate() -> Gate {
+    Gate::new_logical("NAND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+fn and_netlist() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("and".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let and = netlist
+        .insert_gate(and_gate(), "g0".into(), &[a, b])
+        .unwrap();
+    and.expose_with_name("y".into());
+    netlist
+}
+
+fn nand_inv_netlist() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("nand_inv".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let nand = netlist
+        .insert_gate(nand_gate(), "g0".into(), &[a, b])
+        .unwrap();
+    let inv = netlist
+        .insert_gate(inv_gate(), "g1".into(), &[nand.into()])
+        .unwrap();
+    inv.expose_with_name("y".into());
+    netlist
+}
+
+#[test]
+fn equivalence() {
+    let a = and_netlist();
+    let b = nand_inv_netlist();
+    assert_invariants(&a);
+    assert_invariants(&b);
+    assert_equivalent(&a, &b, 100);
+
+    // Break the second netlist
+    let inv = b.last().unwrap();
+    let nand = inv.get_driver(0).unwrap();
+    nand.get_instance_type_mut()
+        .unwrap()
+        .set_gate_name("OR".into());
+    assert!(check_equivalent(&a, &b, 100, 1).is_err());
+}
+
+#[test]
+fn clean_preserves_function() {
+    let mut config = RandomConfig::new(vec![and_gate(), inv_gate(), mux_gate(), nand_gate()]);
+    config.instances = 64;
+    for seed in 0..8 {
+        let golden = random_netlist(seed, &config).unwrap();
+        let cleaned = random_netlist(seed, &config).unwrap();
+        cleaned.clean().unwrap();
+        assert_invariants(&cleaned);
+        assert_equivalent(&golden, &cleaned, 256);
+    }
+}
+
+#[test]
+fn broken_invariants() {
+    let netlist = and_netlist();
+    let gate = netlist.last().unwrap();
+    gate.get_input(1).disconnect();
+    assert!(check_connected(&netlist).is_err());
+    assert!(check_acyclic(&netlist).is_ok());
+    gate.get_input(1).connect(gate.clone().into());
+    assert!(check_connected(&netlist).is_ok());
+    assert!(check_acyclic(&netlist).is_err());
+
+    let netlist = and_netlist();
+    assert!(check_no_dangling(&netlist).is_ok());
+    let a = netlist.inputs().next().unwrap();
+    netlist.insert_gate(inv_gate(), "g1".into(), &[a]).unwrap();
+    assert!(matches!(
+        check_no_dangling(&netlist),
+        Err(Error::DanglingNets(nets)) if nets.len() == 1
+    ));
+}
+
+#[test]
+fn signatures() {
+    let netlist = and_netlist();
+    let a = netlist.inputs().next().unwrap();
+    let b = netlist.inputs().nth(1).unwrap();
+    let nand = netlist
+        .insert_gate(nand_gate(), "g1".into(), &[a.clone(), b])
+        .unwrap();
+    let inv = netlist
+        .insert_gate(inv_gate(), "g2".into(), &[nand.clone().into()])
+        .unwrap();
+
+    let sigs = netlist.signatures(100, 3).unwrap();
+    assert_eq!(sigs.len(), netlist.objects().count());
+    assert!(sigs.values().all(|s| s.len() == 100));
+
+    let and = netlist.outputs().first().unwrap().0.clone();
+    assert_eq!(sigs[&and], sigs[&inv.into()]);
+    assert_eq!(sigs[&and], !sigs[&nand.into()].clone());
+    assert_ne!(sigs[&and], sigs[&a]);
+
+    // Signatures are reproducible
+    assert_eq!(sigs, netlist.signatures(100, 3).unwrap());
+    assert_ne!(sigs[&a], netlist.signatures(100, 4).unwrap()[&a]);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn par_signatures() {
+    let mut config = RandomConfig::new(vec![and_gate(), inv_gate(), mux_gate(), nand_gate()]);
+    config.instances = 256;
+    for seed in 0..4 {
+        let netlist = random_netlist(seed, &config).unwrap();
+        assert_eq!(
+            netlist.par_signatures(200, seed).unwrap(),
+            netlist.signatures(200, seed).unwrap()
+        );
+    }
+}
+
+#[test]
+fn structural_verilog() {
+    let mut config = RandomConfig::new(vec![and_gate(), inv_gate(), mux_gate(), nand_gate()]);
+    config.instances = 16;
+    let netlist = random_netlist(5, &config).unwrap();
+    netlist
+        .last()
+        .unwrap()
+        .insert_attribute("keep".to_string(), Parameter::Integer(1));
+    let golden = netlist.to_string();
+
+    // The same netlist in another layout
+    let options = VerilogOptions {
+        ansi_ports: true,
+        sorted: true,
+        line_width: Some(200),
+        header: Some("golden".to_string()),
+        ..VerilogOptions::default()
+    };
+    let reordered = netlist.to_verilog(&options);
+    assert_ne!(golden, reordered);
+    assert_netlist_eq!(golden, reordered);
+    assert_netlist_eq!(golden.as_str(), &reordered, "seed {}", 5);
+
+    // A swapped connection is reported with its instance
+    let inv = netlist.last().unwrap();
+    let driver = inv.get_driver(0).unwrap();
+    let other = netlist.inputs().next().unwrap();
+    inv.get_input(0).reconnect(other);
+    let diff = verilog_diff(&golden, &netlist.to_string())
+        .unwrap()
+        .unwrap();
+    let name = inv.get_instance_name().unwrap();
+    assert!(diff.starts_with(&format!("in module `random`, instance `{name}` differs")));
+    assert!(diff.contains(&format!(".I({})", driver.get_identifier())));
+
+    assert!(verilog_diff("module m (a);", &golden).is_err());
+}
+
+#[test]
+#[should_panic(expected = "net `b` differs")]
+fn structural_verilog_mismatch() {
+    let left = "module m (a, b); input a; input b; endmodule";
+    let right = "module m (a, b); input a; output b; endmodule";
+    assert_eq!(
+        verilog_diff(left, right).unwrap().unwrap(),
+        "in module `m`, net `b` differs\n  left:  input wire\n  right: output wire"
+    );
+    assert_netlist_eq!(left, right);
+}
@@ -0,0 +1,286 @@
+use safety_net::constraints::{Constraints, FalsePath, Target};
+use safety_net::netlist::Gate;
+use safety_net::netlist::GateNetlist;
+use safety_net::netlist::Netlist;
+use safety_net::netlist::delay::{CellTiming, DelayModel, LinearDelay, UnitDelay, WireLoad};
+use safety_net::netlist::timing::{IncrementalSta, Sta};
+use std::rc::Rc;
+
+fn gate(name: &str, inputs: &[&str]) -> Gate {
+    Gate::new_logical(
+        name.into(),
+        inputs.iter().map(|i| (*i).into()).collect(),
+        "Y".into(),
+    )
+}
+
+/// An AND gate costs 2 and an inverter 1
+fn delay(gate: &Gate) -> f64 {
+    match gate.get_gate_name().to_string().as_str() {
+        "AND" => 2.0,
+        _ => 1.0,
+    }
+}
+
+/// `y = ~(~a & b)` and `z = b`
+fn get_example() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("example".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    let inv_0 = netlist
+        .insert_gate(gate("INV", &["A"]), "inst_0".into(), &[a])
+        .unwrap();
+    let and = netlist
+        .insert_gate(
+            gate("AND", &["A", "B"]),
+            "inst_1".into(),
+            &[inv_0.get_output(0), b.clone()],
+        )
+        .unwrap();
+    netlist
+        .insert_gate(gate("INV", &["A"]), "inst_2".into(), &[and.get_output(0)])
+        .unwrap()
+        .expose_with_name("y".into());
+    b.expose_with_name("z".into());
+    netlist
+}
+
+#[test]
+fn test_sta() {
+    let netlist = get_example();
+    let sta = Sta::new(&netlist, 5.0, delay).unwrap();
+    let net = |name: &str| netlist.find_net(&name.into()).unwrap();
+    assert_eq!(sta.get_period(), 5.0);
+    assert_eq!(sta.arrival(&net("inst_1_Y")), 3.0);
+    assert_eq!(sta.arrival(&net("inst_2_Y")), 4.0);
+    assert_eq!(sta.required(&net("inst_1_Y")), 4.0);
+    assert_eq!(sta.slack(&net("a")), 1.0);
+    // `b` is required by `z` at 5, but by the AND gate at 2
+    assert_eq!(sta.required(&net("b")), 2.0);
+    assert_eq!(sta.worst_slack(), 1.0);
+}
+
+#[test]
+fn test_report_paths() {
+    let netlist = get_example();
+    let sta = Sta::new(&netlist, 5.0, delay).unwrap();
+    let paths = sta.report_paths(5);
+    assert_eq!(paths.len(), 2);
+    assert_eq!(paths[0].endpoint.to_string(), "y");
+    assert_eq!(paths[0].slack, 1.0);
+    let nets: Vec<String> = paths[0].stages.iter().map(|s| s.net.to_string()).collect();
+    assert_eq!(nets, ["a", "inst_0_Y", "inst_1_Y", "inst_2_Y"]);
+    let delays: Vec<f64> = paths[0].stages.iter().map(|s| s.delay).collect();
+    assert_eq!(delays, [0.0, 1.0, 2.0, 1.0]);
+    assert_eq!(paths[1].endpoint.to_string(), "z");
+    assert_eq!(paths[1].stages.len(), 1);
+    assert!(
+        paths[0]
+            .to_string()
+            .contains("      3.000     +2.000  inst_1_Y (AND)\n")
+    );
+
+    let dot = sta.to_dot(&paths[..1]);
+    assert!(dot.starts_with("digraph \"example\" {"));
+    let highlighted = dot.lines().filter(|l| l.contains("color=red")).count();
+    // Four nodes and four connections, including the one to `y`
+    assert_eq!(highlighted, 8);
+    assert!(dot.contains("[label=\"y\", shape=box];"));
+}
+
+#[test]
+fn test_constraints() {
+    let netlist = get_example();
+    let constraints: Constraints = "create_clock -period 6 -name clk\n\
+        set_input_delay -clock clk 1 [get_ports a]\n\
+        set_output_delay -clock clk 2 [get_ports y]"
+        .parse()
+        .unwrap();
+    let sta = Sta::with_constraints(&netlist, &constraints, delay).unwrap();
+    let net = |name: &str| netlist.find_net(&name.into()).unwrap();
+    assert_eq!(sta.arrival(&net("inst_2_Y")), 5.0);
+    assert_eq!(sta.required(&net("inst_2_Y")), 4.0);
+    assert_eq!(sta.worst_slack(), -1.0);
+
+    // Without `a`, the worst path to `y` starts at `b`
+    let constraints = Constraints {
+        false_paths: vec![FalsePath {
+            from: vec![Target::Port("a".to_string())],
+            ..Default::default()
+        }],
+        ..constraints
+    };
+    let sta = Sta::with_constraints(&netlist, &constraints, delay).unwrap();
+    let paths = sta.report_paths(5);
+    assert_eq!(paths[0].slack, 1.0);
+    assert_eq!(paths[0].stages[0].net.to_string(), "b");
+    assert_eq!(sta.arrival(&net("inst_2_Y")), 5.0);
+
+    // Paths to `y` are not timed at all, and neither are the paths from clock ports
+    let constraints: Constraints = "create_clock -period 6 [get_ports b]\nset_false_path -to y"
+        .parse()
+        .unwrap();
+    let sta = Sta::with_constraints(&netlist, &constraints, delay).unwrap();
+    assert!(sta.report_paths(5).is_empty());
+    assert_eq!(sta.worst_slack(), f64::INFINITY);
+}
+
+#[test]
+fn test_timing_exceptions() {
+    let netlist = get_example();
+    let sta = |sdc: &str| {
+        let constraints: Constraints = sdc.parse().unwrap();
+        Sta::with_constraints(&netlist, &constraints, delay).unwrap()
+    };
+    let start = |sta: &Sta<Gate>| sta.report_paths(1)[0].stages[0].net.to_string();
+    assert_eq!(sta("create_clock -period 3 -name clk").worst_slack(), -1.0);
+
+    // Paths through the first inverter are false, so the worst path to `y` starts at `b`
+    let through = sta("create_clock -period 3 -name clk
+set_false_path -through [get_cells inst_0]");
+    assert_eq!(through.worst_slack(), 0.0);
+    assert_eq!(start(&through), "b");
+
+    // The paths from `a` get two clock periods, but not the ones from `b`
+    let from = sta("create_clock -period 3 -name clk
+set_multicycle_path 2 -from [get_ports a] -to y");
+    assert_eq!(from.worst_slack(), 0.0);
+    assert_eq!(start(&from), "b");
+
+    // Only the paths through the net of the first inverter get two clock periods
+    let through = sta("create_clock -period 2 -name clk
+        set_multicycle_path 2 -through [get_nets inst_0_Y]
+        set_false_path -from [get_ports b]");
+    assert_eq!(through.worst_slack(), 0.0);
+    let paths = through.report_paths(5);
+    assert_eq!(paths.len(), 1);
+    let nets: Vec<String> = paths[0].stages.iter().map(|s| s.net.to_string()).collect();
+    assert_eq!(nets, ["a", "inst_0_Y", "inst_1_Y", "inst_2_Y"]);
+    assert_eq!(paths[0].stages[3].arrival, 4.0);
+    let through = sta("create_clock -period 2 -name clk
+        set_multicycle_path 2 -through [get_nets inst_0_Y]");
+    assert_eq!(through.worst_slack(), -1.0);
+    assert_eq!(start(&through), "b");
+}
+
+/// Checks that the incremental timing agrees with a full analysis of every net
+fn assert_same_timing(
+    netlist: &GateNetlist,
+    constraints: &Constraints,
+    sta: &IncrementalSta<Gate>,
+    model: impl DelayModel<Gate>,
+) {
+    let full = Sta::with_constraints(netlist, constraints, model).unwrap();
+    for node in netlist.objects() {
+        for net in node.outputs() {
+            assert_eq!(sta.arrival(&net), full.arrival(&net), "arrival of {net}");
+            assert_eq!(sta.required(&net), full.required(&net), "required of {net}");
+        }
+    }
+    assert_eq!(sta.worst_slack(), full.worst_slack());
+}
+
+#[test]
+fn test_incremental_sta() {
+    let netlist = get_example();
+    let constraints: Constraints = "create_clock -period 6 -name clk\n\
+        set_input_delay -clock clk 1 [get_ports a]\n\
+        set_output_delay -clock clk 2 [get_ports y]"
+        .parse()
+        .unwrap();
+    let mut sta = IncrementalSta::new(&netlist, &constraints, delay).unwrap();
+    assert_same_timing(&netlist, &constraints, &sta, delay);
+    let net = |name: &str| netlist.find_net(&name.into()).unwrap();
+
+    // A faster driver for the final inverter
+    let and = netlist
+        .insert_gate(
+            gate("AND", &["A", "B"]),
+            "inst_3".into(),
+            &[net("a"), net("b")],
+        )
+        .unwrap();
+    netlist
+        .replace_net_uses(net("inst_1_Y"), &and.get_output(0))
+        .unwrap();
+    sta.update().unwrap();
+    assert_same_timing(&netlist, &constraints, &sta, delay);
+    assert_eq!(sta.arrival(&net("inst_2_Y")), 4.0);
+
+    // Removing the unused cells leaves the timing as it is
+    netlist.clean().unwrap();
+    sta.update().unwrap();
+    assert_same_timing(&netlist, &constraints, &sta, delay);
+
+    // A new load of `b` makes it more critical
+    let inv = netlist
+        .insert_gate(gate("INV", &["A"]), "inst_4".into(), &[net("inst_2_Y")])
+        .unwrap();
+    inv.get_input(0).reconnect(net("b"));
+    inv.expose_with_name("w".into());
+    sta.update().unwrap();
+    assert_same_timing(&netlist, &constraints, &sta, delay);
+
+    let false_paths: Constraints = "set_false_path -to y".parse().unwrap();
+    assert!(IncrementalSta::new(&netlist, &false_paths, delay).is_err());
+    let multicycle: Constraints = "set_multicycle_path 2 -to y".parse().unwrap();
+    assert!(IncrementalSta::new(&netlist, &multicycle, delay).is_err());
+}
+
+#[test]
+fn test_delay_models() {
+    let wire_load = WireLoad {
+        capacitance: vec![1.0, 1.5],
+        slope: 0.5,
+    };
+    let caps: Vec<f64> = (0..5).map(|n| wire_load.capacitance(n)).collect();
+    assert_eq!(caps, [0.0, 1.0, 1.5, 2.0, 2.5]);
+
+    let netlist = get_example();
+    let net = |name: &str| netlist.find_net(&name.into()).unwrap();
+    let sta = Sta::new(&netlist, 5.0, UnitDelay).unwrap();
+    assert_eq!(sta.arrival(&net("inst_2_Y")), 3.0);
+
+    // Each net has a load of 2, so an inverter takes 2 and the AND gate 4 plus half the slew of 1 from the inverter
+    let model = LinearDelay {
+        cells: [
+            (
+                "AND".to_string(),
+                CellTiming {
+                    intrinsic: 2.0,
+                    resistance: 1.0,
+                },
+            ),
+            (
+                "INV".to_string(),
+                CellTiming {
+                    intrinsic: 1.0,
+                    resistance: 0.5,
+                },
+            ),
+        ]
+        .into(),
+        pin_capacitance: 1.0,
+        wire_load,
+        ..Default::default()
+    };
+    let sta = Sta::new(&netlist, 10.0, model.clone()).unwrap();
+    assert_eq!(sta.arrival(&net("inst_0_Y")), 2.0);
+    assert_eq!(sta.arrival(&net("inst_1_Y")), 6.5);
+    assert_eq!(sta.arrival(&net("inst_2_Y")), 9.5);
+    let path = &sta.report_paths(1)[0];
+    let slews: Vec<f64> = path.stages.iter().map(|s| s.slew).collect();
+    assert_eq!(slews, [0.0, 1.0, 2.0, 1.0]);
+
+    // Another load on the AND gate slows it down, along with the inverter it drives
+    let constraints = Constraints::with_period(10.0);
+    let mut incremental = IncrementalSta::new(&netlist, &constraints, model.clone()).unwrap();
+    netlist
+        .insert_gate(gate("INV", &["A"]), "inst_3".into(), &[net("inst_1_Y")])
+        .unwrap()
+        .expose_with_name("w".into());
+    incremental.update().unwrap();
+    assert_same_timing(&netlist, &constraints, &incremental, model);
+    assert_eq!(incremental.arrival(&net("inst_1_Y")), 8.0);
+    assert_eq!(incremental.arrival(&net("inst_2_Y")), 11.75);
+}
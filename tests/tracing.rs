@@ -0,0 +1,134 @@
+#![cfg(feature = "tracing")]
+
+use safety_net::netlist::{GateNetlist, Netlist, VerifyOptions, rewrite::rewrite};
+use std::{
+    fmt::Debug,
+    rc::Rc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+use tracing::{
+    Event, Metadata, Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+};
+
+/// A subscriber that records the names of the spans and the messages and fields of the events
+#[derive(Default, Clone)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<String>>>,
+    events: Arc<Mutex<Vec<String>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+/// Formats the fields of an event as `message key=value ...`
+#[derive(Default)]
+struct Fields(String);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{value:?}"));
+        } else {
+            self.0.push_str(&format!(" {}={value:?}", field.name()));
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.spans
+            .lock()
+            .unwrap()
+            .push(span.metadata().name().to_string());
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        self.events.lock().unwrap().push(fields.0);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+/// Runs `f` with a [Recorder] installed, returning the recorded span names and events
+fn record(f: impl FnOnce()) -> (Vec<String>, Vec<String>) {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), f);
+    let spans = recorder.spans.lock().unwrap().clone();
+    let events = recorder.events.lock().unwrap().clone();
+    (spans, events)
+}
+
+/// A buffer of `a` that drives `y`, next to an unused inverter
+fn buffered() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("buffered".to_string());
+    let a = netlist.insert_input("a".into());
+    let b = netlist.insert_input("b".into());
+    netlist.not(&b).unwrap();
+    let buf = netlist.not(&netlist.not(&a).unwrap()).unwrap();
+    buf.expose_with_name("y".into());
+    netlist
+}
+
+#[test]
+fn edits_emit_events() {
+    let (spans, events) = record(|| {
+        buffered();
+    });
+    assert!(spans.is_empty());
+    assert_eq!(events.iter().filter(|e| e.starts_with("insert")).count(), 5);
+
+    let netlist = buffered();
+    let (spans, events) = record(|| {
+        let mut inputs = netlist.inputs();
+        let (a, b) = (inputs.next().unwrap(), inputs.next().unwrap());
+        netlist.replace_net_uses(a, &b).unwrap();
+    });
+    assert_eq!(spans, ["replace_net_uses"]);
+    assert!(events.iter().any(|e| e.starts_with("reconnect")));
+}
+
+#[test]
+fn passes_emit_spans_and_counts() {
+    let netlist = buffered();
+    let (spans, events) = record(|| {
+        netlist.clean().unwrap();
+        netlist
+            .verify_with(&VerifyOptions {
+                deny_unconnected_inputs: true,
+                ..VerifyOptions::default()
+            })
+            .unwrap();
+    });
+    // Building the analyses for cleaning verifies the netlist too
+    assert_eq!(spans.first().unwrap(), "clean");
+    assert_eq!(spans.last().unwrap(), "verify");
+    assert!(events.contains(&"clean round removed=1".to_string()));
+    assert!(events.iter().any(|e| e.starts_with("remove node=")));
+    assert!(
+        events
+            .iter()
+            .any(|e| e.starts_with("removing a circuit node without uses"))
+    );
+
+    let (spans, events) = record(|| {
+        rewrite(&netlist).unwrap();
+    });
+    assert_eq!(spans, ["rewrite"]);
+    assert!(events.iter().any(|e| e.starts_with("cones rewritten")));
+}
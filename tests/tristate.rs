@@ -0,0 +1,171 @@
+use safety_net::{
+    circuit::{Identifier, Net},
+    error::Error,
+    logic::{Logic, Resolution},
+    netlist::{DrivenNet, Gate, GateNetlist, sim::LogicSimulator},
+};
+use std::rc::Rc;
+
+fn tbuf_gate() -> Gate {
+    Gate::new_tristate("TBUF".into(), vec!["A".into(), "EN".into()], "Y".into())
+}
+
+fn and_gate() -> Gate {
+    Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+/// Two tri-state buffers driving `bus`, which is read by an AND gate
+fn shared_bus() -> (Rc<GateNetlist>, Vec<DrivenNet<Gate>>, DrivenNet<Gate>) {
+    let netlist = GateNetlist::new("shared_bus".to_string());
+    let inputs: Vec<_> = ["a", "en_a", "b", "en_b"]
+        .into_iter()
+        .map(|n| netlist.insert_input(n.into()))
+        .collect();
+    for (i, ops) in inputs.chunks(2).enumerate() {
+        let inst = netlist
+            .insert_gate(tbuf_gate(), format!("t{i}").into(), ops)
+            .unwrap();
+        inst.get_output(0).as_net_mut().set_identifier("bus".into());
+    }
+    let bus = netlist.find_drivers(&"bus".into())[0].clone();
+    let y = netlist
+        .insert_gate(
+            and_gate(),
+            "inst_0".into(),
+            &[bus.clone(), inputs[0].clone()],
+        )
+        .unwrap()
+        .get_output(0);
+    y.clone().expose_with_name("y".into());
+    (netlist, inputs, y)
+}
+
+fn run(sim: &mut LogicSimulator<Gate>, inputs: &[DrivenNet<Gate>], values: [Logic; 4]) {
+    sim.run(|n| values[inputs.iter().position(|i| i == n).unwrap()]);
+}
+
+#[test]
+fn resolution() {
+    assert_eq!(Logic::Z.resolve(Logic::True), Logic::True);
+    assert_eq!(Logic::False.resolve(Logic::Z), Logic::False);
+    assert_eq!(Logic::True.resolve(Logic::False), Logic::X);
+    assert_eq!(Logic::Z.resolve(Logic::Z), Logic::Z);
+
+    let undriven = [Logic::Z, Logic::Z];
+    assert_eq!(Resolution::Tri.resolve(undriven, Logic::True), Logic::Z);
+    assert_eq!(
+        Resolution::TriReg.resolve(undriven, Logic::True),
+        Logic::True
+    );
+    assert_eq!(
+        Resolution::Tri1.resolve(undriven, Logic::False),
+        Logic::True
+    );
+    assert_eq!(
+        Resolution::Tri0.resolve(undriven, Logic::True),
+        Logic::False
+    );
+    assert_eq!(
+        Resolution::Tri1.resolve([Logic::Z, Logic::False], Logic::X),
+        Logic::False
+    );
+}
+
+#[test]
+fn tristate_drivers_share_nets() {
+    let (netlist, _, _) = shared_bus();
+    assert!(netlist.verify().is_ok());
+    assert_eq!(netlist.find_drivers(&"bus".into()).len(), 2);
+
+    // A non-tristate driver on the bus is a true conflict
+    let inputs: Vec<_> = netlist.get_input_ports().collect();
+    let a = netlist.find_net(&inputs[0]).unwrap();
+    let inst = netlist
+        .insert_gate(and_gate(), "inst_1".into(), &[a.clone(), a])
+        .unwrap();
+    inst.get_output(0).as_net_mut().set_identifier("bus".into());
+    assert!(matches!(netlist.verify(), Err(Error::NonuniqueNets(_))));
+}
+
+#[test]
+fn simulate_bus() {
+    let (netlist, inputs, y) = shared_bus();
+    let bus = netlist.find_drivers(&"bus".into())[0].clone();
+    let mut sim = LogicSimulator::new(&netlist).unwrap();
+    let (t, f) = (Logic::True, Logic::False);
+
+    run(&mut sim, &inputs, [t, t, f, f]);
+    assert_eq!(sim.get_value(&bus), t);
+    assert_eq!(sim.get_value(&y), t);
+    assert!(sim.conflicts().is_empty());
+
+    run(&mut sim, &inputs, [t, f, f, t]);
+    assert_eq!(sim.get_value(&bus), f);
+    assert_eq!(sim.get_value(&y), f);
+
+    run(&mut sim, &inputs, [t, f, f, f]);
+    assert_eq!(sim.get_value(&bus), Logic::Z);
+    assert_eq!(sim.get_value(&y), Logic::X);
+
+    run(&mut sim, &inputs, [t, t, f, t]);
+    assert_eq!(sim.get_value(&bus), Logic::X);
+    assert_eq!(sim.conflicts(), vec![bus.as_net().clone()]);
+}
+
+#[test]
+fn bus_keeper() {
+    let (netlist, inputs, y) = shared_bus();
+    let bus = netlist.find_drivers(&"bus".into())[0].clone();
+    netlist.set_resolution(&bus.as_net(), Resolution::TriReg);
+    let mut sim = LogicSimulator::new(&netlist).unwrap();
+    let (t, f) = (Logic::True, Logic::False);
+
+    run(&mut sim, &inputs, [t, t, f, f]);
+    run(&mut sim, &inputs, [t, f, f, f]);
+    assert_eq!(sim.get_value(&bus), t);
+    assert_eq!(sim.get_value(&y), t);
+
+    netlist.set_resolution(&bus.as_net(), Resolution::Tri0);
+    let mut sim = LogicSimulator::new(&netlist).unwrap();
+    run(&mut sim, &inputs, [t, f, f, f]);
+    assert_eq!(sim.get_value(&bus), f);
+}
+
+#[test]
+fn verify_buses() {
+    let (netlist, _, _) = shared_bus();
+    // Both buffers are enabled with opposing values for some pattern
+    assert!(matches!(
+        netlist.verify_buses(64, 0),
+        Err(Error::BusConflict(nets)) if nets[0].get_identifier() == &Identifier::from("bus")
+    ));
+
+    // Enable the buffers with opposing polarity
+    let (netlist, inputs, _) = shared_bus();
+    let inv = netlist
+        .insert_gate(
+            Gate::new_logical("INV".into(), vec!["I".into()], "O".into()),
+            "inst_1".into(),
+            &[inputs[1].clone()],
+        )
+        .unwrap();
+    let t1 = netlist.find_drivers(&"bus".into())[1].clone().unwrap();
+    t1.get_input(1).connect(inv.get_output(0));
+    assert!(netlist.verify().is_ok());
+    assert!(netlist.verify_buses(64, 0).is_ok());
+}
+
+#[test]
+fn emit_net_type() {
+    let (netlist, _, _) = shared_bus();
+    let bus: Net = netlist.find_drivers(&"bus".into())[0]
+        .clone()
+        .as_net()
+        .clone();
+    assert!(netlist.to_string().contains("wire bus;"));
+    netlist.set_resolution(&bus, Resolution::Tri1);
+    assert_eq!(netlist.get_resolution(&bus), Resolution::Tri1);
+    assert!(netlist.to_string().contains("tri1 bus;"));
+    netlist.set_resolution(&bus, Resolution::Tri);
+    assert!(netlist.to_string().contains("wire bus;"));
+}
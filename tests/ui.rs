@@ -0,0 +1,8 @@
+#![cfg(feature = "derive")]
+
+/// The derive reports malformed inputs with errors that point at the offending code
+#[test]
+fn test_derive_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
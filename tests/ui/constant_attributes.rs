@@ -0,0 +1,16 @@
+use safety_net::{derive::Instantiable, netlist::Gate};
+
+#[derive(Instantiable)]
+#[instantiable(getters)]
+enum Cell {
+    #[instantiable(constant)]
+    Gate(Gate),
+    #[instantiable(constant(False))]
+    Ground(Gate),
+    #[instantiable(constant(High))]
+    Pull(Gate),
+    #[instantiable(seq)]
+    Register(Gate),
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use safety_net::derive::Instantiable;
+
+#[derive(Instantiable)]
+enum Cell {}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use safety_net::derive::Instantiable;
+
+#[derive(Instantiable)]
+union Cell {
+    a: u32,
+    b: f32,
+}
+
+fn main() {}
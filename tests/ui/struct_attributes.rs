@@ -0,0 +1,25 @@
+use safety_net::{circuit::Net, derive::Instantiable};
+
+#[derive(Instantiable)]
+#[instantiable(combinational)]
+#[cell_name = "LUT{size}"]
+struct Lut {
+    k: usize,
+    #[parameter(label = "INIT")]
+    init: u64,
+    #[input_port]
+    inputs: Vec<Net>,
+    #[output_port]
+    output: Net,
+}
+
+#[derive(Instantiable)]
+struct Unnamed {
+    #[output_port]
+    output: Net,
+}
+
+#[derive(Instantiable)]
+struct Tuple(Net);
+
+fn main() {}
@@ -0,0 +1,11 @@
+use safety_net::{derive::Instantiable, netlist::Gate};
+
+#[derive(Instantiable)]
+enum Cell {
+    Gate(Gate),
+    Empty,
+    Pair(Gate, Gate),
+    Named { gate: Gate },
+}
+
+fn main() {}
@@ -104,3 +104,87 @@ fn test_empty_netlist() {
     netlist.delete_net_uses(a.unwrap()).unwrap();
     assert!(netlist.outputs().is_empty());
 }
+
+#[test]
+fn diagnose_reports_every_problem() {
+    use safety_net::{error::Error, netlist::VerifyOptions};
+    let netlist = GateNetlist::new("diagnose".to_string());
+    let a = netlist.insert_input("a".into());
+    netlist.insert_input("a".into());
+    let inv = Gate::new_logical("INV".into(), vec!["A".into()], "Y".into());
+    let u0 = netlist.insert_gate_disconnected(inv.clone(), "u0".into());
+    let u1 = netlist.insert_gate(inv, "u0".into(), &[a]).unwrap();
+
+    let options = VerifyOptions {
+        deny_unconnected_inputs: true,
+        ..VerifyOptions::default()
+    };
+    let diagnostics = netlist.diagnose(&options);
+    let codes: Vec<&str> = diagnostics.iter().map(Error::code).collect();
+    assert_eq!(
+        codes,
+        [
+            "no-outputs",
+            "nonunique-nets",
+            "nonunique-nets",
+            "nonunique-insts",
+            "unconnected-inputs"
+        ]
+    );
+    let errors: Vec<Error> = diagnostics.into_iter().collect();
+    assert!(errors[0].objects().is_empty());
+    assert_eq!(errors[1].objects().len(), 1);
+    // The outputs of the instances are named after them
+    assert_eq!(errors[2].objects(), [u1.get_id()]);
+    assert_eq!(errors[3].objects(), [u0.get_id(), u1.get_id()]);
+    assert!(matches!(errors[3].root(), Error::NonuniqueInsts(_)));
+    assert_eq!(errors[4].objects(), [u0.get_id()]);
+    assert!(std::error::Error::source(&errors[4]).is_some());
+
+    // Verification fails on the first problem, without the context
+    assert!(matches!(
+        netlist.verify_with(&options),
+        Err(Error::NoOutputs)
+    ));
+    u1.expose_with_name("y".into());
+    assert!(matches!(
+        netlist.verify_with(&options),
+        Err(Error::NonuniqueNets(_))
+    ));
+    let report = netlist.diagnose(&options).to_string();
+    assert_eq!(report.lines().count(), 4);
+    assert!(report.starts_with("error[nonunique-nets]: In "));
+}
+
+#[test]
+fn verify_levels() {
+    use safety_net::{error::Error, netlist::VerifyOptions};
+    let netlist = GateNetlist::new("levels".to_string());
+    assert!(netlist.verify().is_err());
+    assert!(netlist.verify_with(&VerifyOptions::construction()).is_ok());
+
+    // A transparent latch closing a loop through an AND
+    let a = netlist.insert_input("a".into());
+    let latch = Gate::new_logical("LATCH".into(), vec!["D".into()], "Q".into());
+    let and = Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into());
+    let q = netlist.insert_gate_disconnected(latch, "u0".into());
+    let y = netlist
+        .insert_gate(and, "u1".into(), &[a, q.get_output(0)])
+        .unwrap();
+    q.get_input(0).connect(y.get_output(0));
+    y.expose_with_name("y".into());
+    netlist.insert_output("z".into()).unwrap();
+    assert!(netlist.verify().is_ok());
+
+    let mut signoff = VerifyOptions::signoff();
+    let codes: Vec<&str> = netlist.diagnose(&signoff).iter().map(Error::code).collect();
+    assert_eq!(codes, ["undriven-outputs", "cycle-detected"]);
+    assert!(matches!(
+        netlist.verify_with(&signoff),
+        Err(Error::UndrivenOutputs(ports)) if ports.len() == 1
+    ));
+
+    signoff.cycle_breakers.push("LATCH".into());
+    signoff.deny_undriven_outputs = false;
+    assert!(netlist.verify_with(&signoff).is_ok());
+}
@@ -1,6 +1,10 @@
 use safety_net::{
-    assert_verilog_eq, logic,
-    netlist::{Gate, GateNetlist, Netlist},
+    assert_verilog_eq,
+    attribute::Parameter,
+    circuit::Identifier,
+    error::Error,
+    logic,
+    netlist::{ConstantStyle, Gate, GateNetlist, Netlist, VerilogOptions},
 };
 use std::rc::Rc;
 
@@ -156,6 +160,71 @@ fn simple_gate_attribute() {
     );
 }
 
+#[test]
+fn typed_attributes() {
+    let netlist = get_simple_example();
+    netlist.insert_attribute("keep_hierarchy".to_string(), "yes");
+    let gate = netlist.last().unwrap();
+    gate.insert_attribute("dont_touch".to_string(), true);
+    gate.insert_attribute("loc".to_string(), "SLICE_X0Y0");
+    gate.insert_attribute("init".to_string(), Parameter::bitvec(8, 0xA5));
+    let a = netlist.first().unwrap().as_net().clone();
+    netlist.insert_net_attribute(&a, "max_fanout".to_string(), 4i64);
+    netlist.set_net_attribute(&gate.as_net(), "keep".to_string());
+    assert_eq!(
+        netlist
+            .attributes()
+            .next()
+            .unwrap()
+            .value()
+            .as_ref()
+            .unwrap(),
+        &Parameter::string("yes")
+    );
+    assert_eq!(netlist.net_attributes(&a).count(), 1);
+    assert_verilog_eq!(
+        netlist.to_string(),
+        "(* keep_hierarchy = \"yes\" *)
+         module example (
+           a,
+           b,
+           y
+         );
+           input a;
+           (* max_fanout = 4 *)
+           wire a;
+           input b;
+           wire b;
+           output y;
+           wire y;
+           (* keep *)
+           wire inst_0_Y;
+           (* dont_touch = 1 *)
+           (* init = 8'hA5 *)
+           (* loc = \"SLICE_X0Y0\" *)
+           AND inst_0 (
+             .A(a),
+             .B(b),
+             .Y(inst_0_Y)
+           );
+           assign y = inst_0_Y;
+         endmodule\n"
+    );
+
+    assert!(
+        netlist
+            .clear_net_attribute(&a, &"max_fanout".to_string())
+            .is_some()
+    );
+    assert_eq!(netlist.net_attributes(&a).count(), 0);
+    assert!(
+        netlist
+            .clear_attribute(&"keep_hierarchy".to_string())
+            .is_some()
+    );
+    assert!(netlist.to_string().starts_with("module example"));
+}
+
 #[test]
 fn constant_output() {
     let netlist: Rc<GateNetlist> = Netlist::new("top".to_string());
@@ -201,3 +270,280 @@ fn constant_driver() {
            assign y = inst_0_Y;\n"
     );
 }
+
+#[test]
+fn constant_styles() {
+    let netlist: Rc<GateNetlist> = Netlist::new("top".to_string());
+    let a = netlist.insert_input("a".into());
+    let vdd = netlist
+        .insert_constant(logic::Logic::True, "tie".into())
+        .unwrap();
+    netlist
+        .insert_gate(and_gate(), "inst_0".into(), &[a, vdd.clone()])
+        .unwrap()
+        .expose_with_name("y".into());
+    vdd.expose_with_name("z".into());
+    let emit = |constants| {
+        netlist.to_verilog(&VerilogOptions {
+            constants,
+            port_wires: false,
+            line_width: Some(80),
+            ..Default::default()
+        })
+    };
+    let body = |verilog: String| -> Vec<String> {
+        verilog
+            .lines()
+            .skip_while(|l| !l.starts_with("  output z;"))
+            .skip(1)
+            .map(str::to_string)
+            .collect()
+    };
+
+    assert_eq!(
+        body(emit(ConstantStyle::Literal)),
+        [
+            "  wire inst_0_Y;",
+            "  AND inst_0 (.A(a), .B(1'b1), .Y(inst_0_Y));",
+            "  assign y = inst_0_Y;",
+            "  assign z = 1'b1;",
+            "endmodule"
+        ]
+    );
+    assert_eq!(
+        body(emit(ConstantStyle::TieCells)),
+        [
+            "  wire tie_Y;",
+            "  wire inst_0_Y;",
+            "  VDD tie (.Y(tie_Y));",
+            "  AND inst_0 (.A(a), .B(tie_Y), .Y(inst_0_Y));",
+            "  assign y = inst_0_Y;",
+            "  assign z = tie_Y;",
+            "endmodule"
+        ]
+    );
+    assert_eq!(
+        body(emit(ConstantStyle::Assign)),
+        [
+            "  wire tie_Y;",
+            "  wire inst_0_Y;",
+            "  AND inst_0 (.A(a), .B(tie_Y), .Y(inst_0_Y));",
+            "  assign tie_Y = 1'b1;",
+            "  assign y = inst_0_Y;",
+            "  assign z = tie_Y;",
+            "endmodule"
+        ]
+    );
+}
+
+#[test]
+fn port_order() {
+    let netlist = GateNetlist::new("ports".to_string());
+    let d1 = netlist.insert_input("d[1]".into());
+    let clk = netlist.insert_input("clk".into());
+    let d0 = netlist.insert_input("d[0]".into());
+    let and = netlist
+        .insert_gate(and_gate(), "inst_0".into(), &[d0, d1])
+        .unwrap();
+    and.expose_with_name("y".into());
+    assert!(clk.is_an_input());
+
+    netlist.group_ports(|n| n.get_identifier().get_name() == "clk");
+    let names: Vec<String> = netlist
+        .get_ports()
+        .into_iter()
+        .map(|(_, n)| n.get_identifier().to_string())
+        .collect();
+    assert_eq!(names[..3], ["clk", "d[0]", "d[1]"]);
+
+    assert!(
+        netlist
+            .set_port_order(["y", "d[1]", "clk"].map(Identifier::from))
+            .is_ok()
+    );
+    assert!(matches!(
+        netlist.set_port_order(["inst_0_Y".into()]),
+        Err(Error::PortNotFound(_))
+    ));
+    assert_verilog_eq!(
+        netlist.to_verilog(&VerilogOptions {
+            ansi_ports: true,
+            ..Default::default()
+        }),
+        "module ports (
+           output wire y,
+           input wire [1:0] d,
+           input wire clk
+         );
+           wire inst_0_Y;
+           AND inst_0 (
+             .A(d[0]),
+             .B(d[1]),
+             .Y(inst_0_Y)
+           );
+           assign y = inst_0_Y;
+         endmodule\n"
+    );
+}
+
+#[test]
+fn writer_options() {
+    let netlist = get_simple_example();
+    let gate = netlist.last().unwrap();
+    gate.insert_attribute("keep".to_string(), Parameter::Integer(1));
+    let options = VerilogOptions {
+        indent: 4,
+        port_wires: false,
+        line_width: Some(60),
+        attributes: false,
+        header: Some("Netlist of the example\nDo not edit".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        netlist.to_verilog(&options),
+        "// Netlist of the example
+// Do not edit
+module example (
+    a,
+    b,
+    y
+);
+    input a;
+    input b;
+    output y;
+    wire inst_0_Y;
+    AND inst_0 (.A(a), .B(b), .Y(inst_0_Y));
+    assign y = inst_0_Y;
+endmodule\n"
+    );
+
+    // Instances that do not fit on a line keep a connection per line
+    let options = VerilogOptions {
+        line_width: Some(20),
+        timestamp: true,
+        ..Default::default()
+    };
+    let verilog = netlist.to_verilog(&options);
+    assert!(verilog.starts_with("// Generated on "));
+    assert!(verilog.contains("  wire a;\n"));
+    assert!(verilog.contains("  (* keep = 1 *)\n  AND inst_0 (\n    .A(a),\n"));
+}
+
+/// `y = a & b` and `z = a | b`, built in either order
+fn get_and_or(reversed: bool) -> Rc<GateNetlist> {
+    let netlist = GateNetlist::new("and_or".to_string());
+    let (a, b) = if reversed {
+        let b = netlist.insert_input("b".into());
+        (netlist.insert_input("a".into()), b)
+    } else {
+        let a = netlist.insert_input("a".into());
+        (a, netlist.insert_input("b".into()))
+    };
+    let or = Gate::new_logical("OR".into(), vec!["A".into(), "B".into()], "Y".into());
+    let mut gates = vec![(and_gate(), "u0", "y"), (or, "u1", "z")];
+    if reversed {
+        gates.reverse();
+    }
+    for (gate, name, output) in gates {
+        netlist
+            .insert_gate(gate, name.into(), &[a.clone(), b.clone()])
+            .unwrap()
+            .expose_with_name(output.into());
+    }
+    netlist
+}
+
+#[test]
+fn sorted_emission() {
+    let options = VerilogOptions {
+        sorted: true,
+        ..Default::default()
+    };
+    let (first, second) = (get_and_or(false), get_and_or(true));
+    assert_ne!(first.to_string(), second.to_string());
+    assert_eq!(first.to_verilog(&options), second.to_verilog(&options));
+    assert_verilog_eq!(
+        second.to_verilog(&options),
+        "module and_or (
+           a,
+           b,
+           y,
+           z
+         );
+           input a;
+           wire a;
+           input b;
+           wire b;
+           output y;
+           wire y;
+           output z;
+           wire z;
+           wire u0_Y;
+           wire u1_Y;
+           AND u0 (
+             .A(a),
+             .B(b),
+             .Y(u0_Y)
+           );
+           OR u1 (
+             .A(a),
+             .B(b),
+             .Y(u1_Y)
+           );
+           assign y = u0_Y;
+           assign z = u1_Y;
+         endmodule\n"
+    );
+
+    // Ports with an explicit order still come first
+    second.set_port_order(["z".into()]).unwrap();
+    assert!(
+        second
+            .to_verilog(&options)
+            .starts_with("module and_or (\n  z,\n  a,\n")
+    );
+}
+
+#[test]
+fn bus_conversion() {
+    let netlist = GateNetlist::new("buses".to_string());
+    let a = netlist.insert_input_escaped_logic_bus("a".to_string(), 2);
+    netlist
+        .insert_gate(and_gate(), "inst_0".into(), &a)
+        .unwrap()
+        .expose_with_name("y".into());
+
+    assert_eq!(netlist.reaggregate("b*").unwrap(), 0);
+    assert_eq!(netlist.reaggregate("a").unwrap(), 2);
+    assert!(a[1].get_identifier().is_sliced());
+    assert_verilog_eq!(
+        netlist.to_string(),
+        "module buses (
+           a,
+           y
+         );
+           input [1:0] a;
+           wire [1:0] a;
+           output y;
+           wire y;
+           wire inst_0_Y;
+           AND inst_0 (
+             .A(a[0]),
+             .B(a[1]),
+             .Y(inst_0_Y)
+           );
+           assign y = inst_0_Y;
+         endmodule\n"
+    );
+
+    assert_eq!(netlist.bitblast().unwrap(), 2);
+    assert_eq!(a[0].get_identifier(), Identifier::new("\\a[0]".to_string()));
+    assert!(netlist.to_string().contains("input \\a[1] ;"));
+
+    // The bus bit is already taken
+    netlist.insert_input("a[1]".into());
+    assert!(matches!(
+        netlist.reaggregate("*"),
+        Err(Error::NonuniqueNets(_))
+    ));
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "wasm")]
+
+use safety_net::wasm::{WasmNet, WasmNetlist};
+
+/// An XOR of `a` and `b` from four NANDs
+fn xor() -> WasmNetlist {
+    let netlist = WasmNetlist::new("xor");
+    let a = netlist.insert_input("a");
+    let b = netlist.insert_input("b");
+    let ports = || vec!["A".to_string(), "B".to_string()];
+    let nand = |name: &str, x: &WasmNet, y: &WasmNet| {
+        let id = netlist.insert_gate("NAND", name, ports()).unwrap();
+        netlist.connect(x, id, 0).unwrap();
+        netlist.connect(y, id, 1).unwrap();
+        WasmNet::new(id, 0)
+    };
+    let ab = nand("u0", &a, &b);
+    let x = nand("u1", &a, &ab);
+    let y = nand("u2", &b, &ab);
+    let out = nand("u3", &x, &y);
+    netlist.expose(&out, "y").unwrap();
+    netlist
+}
+
+#[test]
+fn edit_and_query() {
+    let netlist = xor();
+    assert_eq!(netlist.name(), "xor");
+    assert!(netlist.verify().is_ok());
+    let objects = netlist.objects();
+    assert_eq!(objects.len(), 6);
+    assert_eq!(netlist.object_name(objects[0]).unwrap(), "a");
+    assert_eq!(netlist.cell_name(objects[0]).unwrap(), None);
+    assert_eq!(netlist.object_name(objects[2]).unwrap(), "u0");
+    assert_eq!(netlist.cell_name(objects[2]).unwrap().unwrap(), "NAND");
+    assert_eq!(netlist.num_inputs(objects[2]).unwrap(), 2);
+    let driver = netlist.driver(objects[2], 1).unwrap().unwrap();
+    assert_eq!(driver, WasmNet::new(objects[1], 0));
+    assert_eq!(netlist.net_name(&driver).unwrap(), "b");
+
+    assert!(netlist.driver(objects[2], 2).is_err());
+    assert!(netlist.object_name(99).is_err());
+    assert!(
+        netlist
+            .connect(&WasmNet::new(99, 0), objects[2], 0)
+            .is_err()
+    );
+
+    // The NANDs are an XOR
+    assert_eq!(netlist.rewrite().unwrap(), 1);
+    assert!(!netlist.clean().unwrap());
+    assert_eq!(netlist.objects().len(), 3);
+}
+
+#[test]
+fn unconnected_gate() {
+    let netlist = WasmNetlist::new("top");
+    let a = netlist.insert_input("a");
+    let inv = netlist
+        .insert_gate("INV", "u0", vec!["A".to_string()])
+        .unwrap();
+    netlist.expose(&WasmNet::new(inv, 0), "y").unwrap();
+    assert_eq!(netlist.driver(inv, 0).unwrap(), None);
+    assert!(netlist.verify().is_err());
+    netlist.connect(&a, inv, 0).unwrap();
+    assert!(netlist.verify().is_ok());
+}
+
+#[test]
+fn sliced_cell_name() {
+    let netlist = WasmNetlist::new("top");
+    let err = netlist
+        .insert_gate("INV[1]", "u0", vec!["A".to_string()])
+        .unwrap_err();
+    assert!(err.contains("bit slice"));
+    assert!(netlist.objects().is_empty());
+}
+
+#[test]
+fn formats() {
+    let netlist = xor();
+    let verilog = netlist.to_verilog();
+    assert!(verilog.contains("module xor"));
+    assert!(verilog.contains("NAND"));
+
+    let json = netlist.to_json().unwrap();
+    let parsed = WasmNetlist::from_json(&json).unwrap();
+    assert_eq!(parsed.to_verilog(), verilog);
+    assert!(WasmNetlist::from_json("{").is_err());
+
+    let snapshot = netlist.to_snet().unwrap();
+    assert_eq!(
+        WasmNetlist::from_snet(&snapshot).unwrap().to_verilog(),
+        verilog
+    );
+    assert!(WasmNetlist::from_snet(&snapshot[..4]).is_err());
+
+    let aiger = netlist.to_aiger().unwrap();
+    assert!(aiger.starts_with("aag "));
+    let imported = WasmNetlist::from_aiger(&aiger, "imported").unwrap();
+    assert!(imported.verify().is_ok());
+    assert_eq!(imported.name(), "imported");
+    assert!(WasmNetlist::from_aiger("aig 0 0 0 0 0\n", "bad").is_err());
+}
@@ -0,0 +1,115 @@
+#![cfg(feature = "word")]
+use safety_net::{
+    circuit::Net,
+    format_id,
+    logic::Logic,
+    netlist::{
+        DrivenNet,
+        sim::LogicSimulator,
+        word::{Cell, WordNetlist, WordOp, lower},
+    },
+};
+use std::rc::Rc;
+
+const WIDTH: usize = 3;
+
+/// A netlist computing `op` over the buses `a` and `b`, and the select bit `s` for muxes
+fn word_netlist(op: WordOp) -> (Rc<WordNetlist>, Vec<DrivenNet<Cell>>) {
+    let netlist = WordNetlist::new(format!("word_{}", op.as_str().to_lowercase()));
+    let mut inputs = Vec::new();
+    if op == WordOp::Mux {
+        inputs.push(netlist.insert_input("s".into()));
+    }
+    for bus in ["a", "b"] {
+        for i in 0..WIDTH {
+            inputs.push(netlist.insert_input(Net::new_logic(format_id!("{bus}[{i}]"))));
+        }
+    }
+    let inst = netlist
+        .insert_gate(Cell::word(op, WIDTH), "inst_0".into(), &inputs)
+        .unwrap();
+    for (i, y) in inst.outputs().enumerate() {
+        y.expose_with_name(format_id!("y[{i}]"));
+    }
+    (netlist, inputs)
+}
+
+/// Simulates every input pattern, returning the outputs as numbers
+fn simulate(netlist: &WordNetlist, inputs: &[DrivenNet<Cell>]) -> Vec<u64> {
+    let outputs: Vec<_> = netlist.outputs().into_iter().map(|(d, _)| d).collect();
+    let mut sim = LogicSimulator::new(netlist).unwrap();
+    (0..1u64 << inputs.len())
+        .map(|pattern| {
+            sim.run(|n| {
+                let i = inputs.iter().position(|i| i == n).unwrap();
+                Logic::from_bool((pattern >> i) & 1 == 1)
+            });
+            outputs
+                .iter()
+                .enumerate()
+                .map(|(i, y)| u64::from(sim.get_value(y).unwrap()) << i)
+                .sum()
+        })
+        .collect()
+}
+
+/// Computes `op` for an input pattern
+fn expected(op: WordOp, pattern: u64) -> u64 {
+    let mask = (1 << WIDTH) - 1;
+    let (s, pattern) = match op {
+        WordOp::Mux => (pattern & 1, pattern >> 1),
+        _ => (0, pattern),
+    };
+    let (a, b) = (pattern & mask, (pattern >> WIDTH) & mask);
+    match op {
+        WordOp::Add => (a + b) & mask,
+        WordOp::Sub => a.wrapping_sub(b) & mask,
+        WordOp::Mul => (a * b) & mask,
+        WordOp::Eq => u64::from(a == b),
+        WordOp::Lt => u64::from(a < b),
+        WordOp::Mux if s == 1 => b,
+        WordOp::Mux => a,
+    }
+}
+
+#[test]
+fn word_cells() {
+    for op in [
+        WordOp::Add,
+        WordOp::Sub,
+        WordOp::Mul,
+        WordOp::Eq,
+        WordOp::Lt,
+        WordOp::Mux,
+    ] {
+        let (netlist, inputs) = word_netlist(op);
+        let results = simulate(&netlist, &inputs);
+        for (pattern, result) in results.iter().enumerate() {
+            assert_eq!(*result, expected(op, pattern as u64), "{op:?} of {pattern}");
+        }
+
+        lower(&netlist).unwrap();
+        assert!(netlist.verify().is_ok());
+        assert!(
+            netlist
+                .objects()
+                .all(|o| !matches!(o.get_instance_type().as_deref(), Some(Cell::Word(_))))
+        );
+        assert_eq!(simulate(&netlist, &inputs), results, "{op:?}");
+    }
+}
+
+#[test]
+fn word_verilog() {
+    let (netlist, _) = word_netlist(WordOp::Add);
+    let verilog = netlist.to_string();
+    assert!(verilog.contains("input [2:0] a;"));
+    assert!(verilog.contains("ADD3 inst_0 ("));
+    assert!(verilog.contains(".A({a[2], a[1], a[0]}),"));
+    assert!(verilog.contains(".Y({\\inst_0_Y[2] , \\inst_0_Y[1] , \\inst_0_Y[0] })"));
+
+    lower(&netlist).unwrap();
+    let verilog = netlist.to_string();
+    assert!(!verilog.contains("ADD3"));
+    assert!(verilog.contains("assign y[2] = \\inst_0_Y[2] ;"));
+}
@@ -0,0 +1,182 @@
+#![cfg(feature = "yosys")]
+
+use safety_net::{
+    attribute::Parameter,
+    circuit::{Instantiable, Net},
+    error::Error,
+    logic::Logic,
+    netlist::{
+        Gate, GateNetlist, Netlist,
+        testing::{
+            assert_equivalent, check_acyclic, check_connected, check_no_dangling,
+            check_unique_names,
+        },
+        yosys::{run_yosys_step, yosys_commands},
+    },
+};
+use serde_json::Value;
+use std::rc::Rc;
+
+fn and_gate() -> Gate {
+    Gate::new_logical("AND".into(), vec!["A".into(), "B".into()], "Y".into())
+}
+
+/// The AND of a bus `d` and an enable, with a constant, attributes and an unused inverter
+fn masked() -> Rc<GateNetlist> {
+    let netlist = Netlist::new("masked".to_string());
+    let en = netlist.insert_input("en".into());
+    let d: Vec<_> = (0..2)
+        .map(|i| netlist.insert_input(Net::new_logic(format!("d[{i}]").into())))
+        .collect();
+    let one = netlist.insert_constant(Logic::True, "tie".into()).unwrap();
+    let g0 = netlist
+        .insert_gate(and_gate(), "g0".into(), &[d[0].clone(), en.clone()])
+        .unwrap();
+    g0.insert_attribute("src".to_string(), "top.v:3");
+    g0.insert_attribute("keep".to_string(), Parameter::Integer(1));
+    g0.expose_with_name("q[0]".into());
+    netlist
+        .insert_gate(and_gate(), "g1".into(), &[d[1].clone(), one])
+        .unwrap()
+        .expose_with_name("q[1]".into());
+    netlist.not(&en).unwrap();
+    netlist
+}
+
+#[test]
+fn json_round_trip() {
+    let netlist = masked();
+    let text = netlist.to_yosys_json().unwrap();
+    let json: Value = serde_json::from_str(&text).unwrap();
+    let module = &json["modules"]["masked"];
+    assert_eq!(module["ports"]["d"]["bits"].as_array().unwrap().len(), 2);
+    assert_eq!(module["ports"]["q"]["direction"], "output");
+    // The constant is a bit, not a cell
+    assert_eq!(module["cells"]["g1"]["connections"]["B"][0], "1");
+    assert!(module["cells"].get("tie").is_none());
+    assert_eq!(
+        module["cells"]["g0"]["attributes"]["keep"],
+        "00000000000000000000000000000001"
+    );
+
+    let read = Netlist::from_yosys_json(&text).unwrap();
+    for check in [check_connected, check_unique_names, check_acyclic] {
+        assert!(check(&read).is_ok());
+    }
+    // The unused inverter survives the round trip
+    assert!(matches!(
+        check_no_dangling(&read),
+        Err(Error::DanglingNets(nets)) if nets.len() == 1
+    ));
+    assert_eq!(read.get_name().as_str(), "masked");
+    let inputs: Vec<String> = read.inputs().map(|i| i.to_string()).collect();
+    assert_eq!(inputs, ["en", "d[0]", "d[1]"]);
+    let g0 = read.query("g0").pop().unwrap();
+    assert_eq!(
+        g0.get_attribute(&"src".to_string()),
+        Some(Some("top.v:3".into()))
+    );
+    assert_eq!(
+        g0.get_attribute(&"keep".to_string()),
+        Some(Some(Parameter::Integer(1)))
+    );
+    assert_equivalent(&netlist, &read, 16);
+    assert_eq!(read.to_yosys_json().unwrap(), text);
+}
+
+/// A module as Yosys writes it, with a cell library module, a bus cell port, an `x` bit and an undriven bit
+const DESIGN: &str = r#"{
+  "creator": "Yosys",
+  "modules": {
+    "LUT": {
+      "attributes": { "blackbox": "00000000000000000000000000000001" },
+      "ports": { "I": { "direction": "input", "bits": [ 2 ] } },
+      "cells": {},
+      "netnames": {}
+    },
+    "top": {
+      "attributes": { "top": "00000000000000000000000000000001", "src": "top.v:1.1-9.10" },
+      "ports": {
+        "b": { "direction": "input", "bits": [ 3 ] },
+        "a": { "direction": "input", "bits": [ 2 ] },
+        "y": { "direction": "output", "bits": [ 4, "0", 5 ] }
+      },
+      "cells": {
+        "$abc$12$auto$1": {
+          "hide_name": 1,
+          "type": "MUX",
+          "parameters": {},
+          "attributes": {},
+          "port_directions": { "S": "input", "A": "input", "B": "input", "Y": "output" },
+          "connections": { "S": [ 2 ], "A": [ 3 ], "B": [ "x" ], "Y": [ 4 ] }
+        },
+        "pair": {
+          "hide_name": 0,
+          "type": "BUFS",
+          "port_directions": { "A": "input", "Y": "output" },
+          "connections": { "A": [ 2, 9 ], "Y": [ 6, 7 ] }
+        }
+      },
+      "netnames": { "a": { "hide_name": 0, "bits": [ 2 ], "attributes": {} } }
+    }
+  }
+}"#;
+
+#[test]
+fn reads_yosys_designs() {
+    let netlist = Netlist::from_yosys_json(DESIGN).unwrap();
+    assert_eq!(netlist.get_name().as_str(), "top");
+    let inputs: Vec<String> = netlist.inputs().map(|i| i.to_string()).collect();
+    assert_eq!(inputs, ["b", "a"]);
+    let outputs: Vec<String> = netlist
+        .outputs()
+        .iter()
+        .map(|(_, port)| port.get_identifier().to_string())
+        .collect();
+    assert_eq!(outputs, ["y[0]", "y[1]"]);
+    let pair = netlist.query("pair").pop().unwrap();
+    let ports: Vec<String> = pair
+        .get_instance_type()
+        .unwrap()
+        .get_input_ports()
+        .into_iter()
+        .map(|p| p.get_identifier().to_string())
+        .collect();
+    assert_eq!(ports, ["A[0]", "A[1]"]);
+    // The undriven bit 9, the `x` bit and the undriven output bit 5 are unconnected
+    let unconnected: usize = netlist
+        .instances()
+        .map(|inst| inst.unconnected_inputs().count())
+        .sum();
+    assert_eq!(unconnected, 2);
+    assert_eq!(netlist.outputs().len(), 2);
+
+    let parameters = DESIGN.replace(r#""parameters": {}"#, r#""parameters": { "W": "1" }"#);
+    assert!(matches!(
+        Netlist::from_yosys_json(&parameters),
+        Err(Error::Unsupported(_))
+    ));
+    let cyclic = DESIGN.replace(r#""Y": [ 6, 7 ]"#, r#""Y": [ 3, 7 ]"#);
+    assert!(matches!(
+        Netlist::from_yosys_json(&cyclic),
+        Err(Error::ParseError(_))
+    ));
+}
+
+#[test]
+fn step_runs_a_pass() {
+    let dir = std::env::temp_dir().join(format!("safety-net-yosys-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("masked.json");
+    std::fs::write(&file, masked().to_yosys_json().unwrap()).unwrap();
+    run_yosys_step(&file, &file, |netlist| netlist.clean().map(|_| ())).unwrap();
+    let cleaned = Netlist::from_yosys_json(&std::fs::read_to_string(&file).unwrap()).unwrap();
+    assert_eq!(cleaned.instances().count(), 3);
+    assert_equivalent(&masked(), &cleaned, 16);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        yosys_commands("snet yosys clean", "top.json"),
+        "write_json top.json; !snet yosys clean top.json top.json; design -reset; read_json top.json"
+    );
+}